@@ -0,0 +1,3 @@
+ALTER TABLE api_keys DROP CONSTRAINT user_key;
+
+DROP TABLE api_keys;
@@ -0,0 +1,14 @@
+CREATE TABLE api_keys (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+
+    -- Only a SHA-256 digest of the key is stored. The key itself is shown to the user once.
+    key_hash VARCHAR(64) UNIQUE NOT NULL,
+    name VARCHAR(120) NOT NULL,
+    is_revoked BOOLEAN NOT NULL,
+
+    last_used_timestamp TIMESTAMP,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+ALTER TABLE api_keys ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
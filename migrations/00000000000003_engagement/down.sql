@@ -0,0 +1,6 @@
+ALTER TABLE spending_challenges DROP CONSTRAINT user_key;
+ALTER TABLE spending_challenges DROP CONSTRAINT budget_key;
+ALTER TABLE user_badges DROP CONSTRAINT user_key;
+
+DROP TABLE spending_challenges;
+DROP TABLE user_badges;
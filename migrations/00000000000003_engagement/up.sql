@@ -0,0 +1,28 @@
+CREATE TABLE spending_challenges (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    budget_id UUID NOT NULL,
+    category_id SMALLINT NOT NULL,
+
+    limit_cents BIGINT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+
+    -- NULL until the challenge ends and is evaluated
+    is_succeeded BOOLEAN,
+    notify_on_completion BOOLEAN NOT NULL,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE TABLE user_badges (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    badge SMALLINT NOT NULL,
+    earned_timestamp TIMESTAMP NOT NULL,
+    UNIQUE (user_id, badge)
+);
+
+ALTER TABLE spending_challenges ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE spending_challenges ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE user_badges ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
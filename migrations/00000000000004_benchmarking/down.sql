@@ -0,0 +1,4 @@
+ALTER TABLE benchmarking_profiles DROP CONSTRAINT user_key;
+
+DROP TABLE benchmarking_profiles;
+DROP TABLE cohort_category_stats;
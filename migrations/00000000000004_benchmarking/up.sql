@@ -0,0 +1,26 @@
+-- A row exists only for users who have opted in to benchmarking
+CREATE TABLE benchmarking_profiles (
+    user_id UUID UNIQUE NOT NULL PRIMARY KEY,
+    country_code VARCHAR(2) NOT NULL,
+    household_size SMALLINT NOT NULL,
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+-- Aggregates only. Rows are only written for cohorts with enough members to keep individuals
+-- from being identified.
+CREATE TABLE cohort_category_stats (
+    id SERIAL PRIMARY KEY,
+    country_code VARCHAR(2) NOT NULL,
+    household_size SMALLINT NOT NULL,
+    category_name VARCHAR(255) NOT NULL,
+    period_start DATE NOT NULL,
+    user_count INTEGER NOT NULL,
+    p25_cents BIGINT NOT NULL,
+    median_cents BIGINT NOT NULL,
+    p75_cents BIGINT NOT NULL,
+    computed_timestamp TIMESTAMP NOT NULL,
+    UNIQUE (country_code, household_size, category_name, period_start)
+);
+
+ALTER TABLE benchmarking_profiles ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
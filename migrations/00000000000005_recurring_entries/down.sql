@@ -0,0 +1,7 @@
+ALTER TABLE entries DROP CONSTRAINT recurring_entry_key;
+ALTER TABLE recurring_entries DROP CONSTRAINT user_key;
+ALTER TABLE recurring_entries DROP CONSTRAINT budget_key;
+
+ALTER TABLE entries DROP COLUMN recurring_entry_id;
+
+DROP TABLE recurring_entries;
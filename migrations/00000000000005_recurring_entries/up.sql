@@ -0,0 +1,26 @@
+CREATE TABLE recurring_entries (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+
+    amount_cents BIGINT NOT NULL,
+    name VARCHAR(25),
+    category SMALLINT,
+    note TEXT,
+
+    frequency SMALLINT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE,
+    next_occurrence_date DATE NOT NULL,
+
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON recurring_entries (next_occurrence_date);
+
+ALTER TABLE entries ADD COLUMN recurring_entry_id UUID;
+
+ALTER TABLE recurring_entries ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE recurring_entries ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE entries ADD CONSTRAINT recurring_entry_key FOREIGN KEY(recurring_entry_id) REFERENCES recurring_entries(id) ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+ALTER TABLE shopping_list_items DROP CONSTRAINT shopping_list_key;
+ALTER TABLE shopping_lists DROP CONSTRAINT budget_key;
+
+DROP TABLE shopping_list_items;
+DROP TABLE shopping_lists;
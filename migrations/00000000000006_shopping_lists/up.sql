@@ -0,0 +1,29 @@
+CREATE TABLE shopping_lists (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+    category SMALLINT NOT NULL,
+
+    name VARCHAR(25) NOT NULL,
+    is_completed BOOLEAN NOT NULL,
+
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE TABLE shopping_list_items (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    shopping_list_id UUID NOT NULL,
+
+    name VARCHAR(50) NOT NULL,
+    estimated_cost_cents BIGINT NOT NULL,
+    is_checked BOOLEAN NOT NULL,
+
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON shopping_lists (budget_id);
+CREATE INDEX ON shopping_list_items (shopping_list_id);
+
+ALTER TABLE shopping_lists ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE shopping_list_items ADD CONSTRAINT shopping_list_key FOREIGN KEY(shopping_list_id) REFERENCES shopping_lists(id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+ALTER TABLE support_tickets DROP CONSTRAINT user_key;
+
+DROP TABLE support_tickets;
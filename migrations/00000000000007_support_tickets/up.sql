@@ -0,0 +1,15 @@
+CREATE TABLE support_tickets (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+
+    message TEXT NOT NULL,
+    context TEXT NOT NULL,
+    is_resolved BOOLEAN NOT NULL,
+
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON support_tickets (user_id);
+
+ALTER TABLE support_tickets ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+ALTER TABLE pending_deletions DROP CONSTRAINT user_key;
+
+DROP TABLE pending_deletions;
@@ -0,0 +1,9 @@
+CREATE TABLE pending_deletions (
+    user_id UUID UNIQUE NOT NULL PRIMARY KEY,
+    delete_after TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON pending_deletions (delete_after);
+
+ALTER TABLE pending_deletions ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1 @@
+ALTER TABLE categories ADD COLUMN is_hard_capped BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE budgets ADD COLUMN is_tracking_only BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,6 @@
+ALTER TABLE category_allocations DROP CONSTRAINT user_key;
+ALTER TABLE category_allocations DROP CONSTRAINT budget_key;
+
+DROP TABLE category_allocations;
+
+ALTER TABLE budgets DROP COLUMN is_envelope;
@@ -0,0 +1,17 @@
+ALTER TABLE budgets ADD COLUMN is_envelope BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE category_allocations (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    category SMALLINT NOT NULL,
+
+    amount_cents BIGINT NOT NULL,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON category_allocations (budget_id);
+
+ALTER TABLE category_allocations ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE category_allocations ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+ALTER TABLE api_key_usage DROP CONSTRAINT api_key_key;
+
+DROP TABLE api_key_usage;
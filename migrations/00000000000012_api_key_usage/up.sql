@@ -0,0 +1,10 @@
+-- One row per key per day with requests. Rows are created on a key's first request of the day.
+CREATE TABLE api_key_usage (
+    api_key_id UUID NOT NULL,
+    usage_date DATE NOT NULL,
+    request_count INT NOT NULL,
+
+    PRIMARY KEY (api_key_id, usage_date)
+);
+
+ALTER TABLE api_key_usage ADD CONSTRAINT api_key_key FOREIGN KEY(api_key_id) REFERENCES api_keys(id) ON DELETE CASCADE;
@@ -0,0 +1,7 @@
+ALTER TABLE entries DROP CONSTRAINT import_batch_key;
+ALTER TABLE entries DROP COLUMN import_batch_id;
+
+ALTER TABLE import_batches DROP CONSTRAINT user_key;
+ALTER TABLE import_batches DROP CONSTRAINT budget_key;
+
+DROP TABLE import_batches;
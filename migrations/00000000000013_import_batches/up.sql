@@ -0,0 +1,22 @@
+CREATE TABLE import_batches (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+
+    source_name VARCHAR(120) NOT NULL,
+    entry_count INT NOT NULL,
+    is_rolled_back BOOLEAN NOT NULL DEFAULT FALSE,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON import_batches (budget_id);
+
+ALTER TABLE import_batches ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE import_batches ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+ALTER TABLE entries ADD COLUMN import_batch_id UUID;
+
+CREATE INDEX ON entries (import_batch_id);
+
+ALTER TABLE entries ADD CONSTRAINT import_batch_key FOREIGN KEY(import_batch_id) REFERENCES import_batches(id) ON DELETE SET NULL;
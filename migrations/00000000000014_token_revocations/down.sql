@@ -0,0 +1,3 @@
+ALTER TABLE token_revocations DROP CONSTRAINT user_key;
+
+DROP TABLE token_revocations;
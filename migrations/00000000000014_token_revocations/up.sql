@@ -0,0 +1,6 @@
+CREATE TABLE token_revocations (
+    user_id UUID UNIQUE NOT NULL PRIMARY KEY,
+    revoked_timestamp TIMESTAMP NOT NULL
+);
+
+ALTER TABLE token_revocations ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+ALTER TABLE budget_category_totals DROP CONSTRAINT budget_key;
+
+DROP TABLE budget_category_totals;
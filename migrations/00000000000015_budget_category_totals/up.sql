@@ -0,0 +1,21 @@
+-- Running totals of the live entries dated within each budget's date range. Entries without a
+-- category are totaled under category -1.
+CREATE TABLE budget_category_totals (
+    budget_id UUID NOT NULL,
+    category SMALLINT NOT NULL,
+
+    spent_cents BIGINT NOT NULL DEFAULT 0,
+    entry_count BIGINT NOT NULL DEFAULT 0,
+
+    PRIMARY KEY (budget_id, category)
+);
+
+ALTER TABLE budget_category_totals ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+
+INSERT INTO budget_category_totals (budget_id, category, spent_cents, entry_count)
+SELECT entries.budget_id, COALESCE(entries.category, -1), SUM(entries.amount_cents), COUNT(*)
+FROM entries
+JOIN budgets ON budgets.id = entries.budget_id
+WHERE entries.is_deleted = FALSE
+AND entries.date BETWEEN budgets.start_date AND budgets.end_date
+GROUP BY entries.budget_id, COALESCE(entries.category, -1);
@@ -0,0 +1,7 @@
+-- Units of each currency per unit of the base currency, as of the last refresh
+CREATE TABLE exchange_rates (
+    currency VARCHAR(3) UNIQUE NOT NULL PRIMARY KEY,
+    base_currency VARCHAR(3) NOT NULL,
+    rate DOUBLE PRECISION NOT NULL,
+    fetched_timestamp TIMESTAMP NOT NULL
+);
@@ -0,0 +1,3 @@
+ALTER TABLE sessions DROP CONSTRAINT user_key;
+
+DROP TABLE sessions;
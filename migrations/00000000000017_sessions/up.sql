@@ -0,0 +1,16 @@
+-- Refresh and password reset tokens carry the ID of a session so they can be revoked without
+-- storing the tokens themselves. The version is bumped whenever a session's refresh token is
+-- exchanged for a new one so earlier tokens for the session stop working.
+CREATE TABLE sessions (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    version INT NOT NULL,
+    expiration_timestamp TIMESTAMP NOT NULL,
+    revoked_timestamp TIMESTAMP,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON sessions (user_id);
+CREATE INDEX ON sessions (expiration_timestamp);
+
+ALTER TABLE sessions ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+ALTER TABLE user_daily_actions DROP CONSTRAINT user_key;
+
+DROP TABLE user_daily_actions;
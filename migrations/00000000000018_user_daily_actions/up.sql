@@ -0,0 +1,14 @@
+-- One row per user per kind of action per day the user took it. Counts the actions that are
+-- capped each day, such as sending budget invitations and posting comments.
+CREATE TABLE user_daily_actions (
+    user_id UUID NOT NULL,
+    action SMALLINT NOT NULL,
+    action_date DATE NOT NULL,
+    action_count INT NOT NULL,
+
+    PRIMARY KEY (user_id, action, action_date)
+);
+
+CREATE INDEX ON user_daily_actions (action_date);
+
+ALTER TABLE user_daily_actions ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
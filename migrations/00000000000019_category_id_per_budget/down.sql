@@ -0,0 +1 @@
+ALTER TABLE categories DROP CONSTRAINT category_id_per_budget;
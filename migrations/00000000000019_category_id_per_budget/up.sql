@@ -0,0 +1,13 @@
+-- Category IDs are only meaningful within a budget, where entries refer to them. Budgets created
+-- before this constraint could be given duplicate IDs, in which case the first category created
+-- with the ID is kept.
+DELETE FROM categories
+WHERE pk IN (
+    SELECT pk FROM (
+        SELECT pk, ROW_NUMBER() OVER (PARTITION BY budget_id, id ORDER BY pk) AS row_number
+        FROM categories
+    ) AS numbered_categories
+    WHERE numbered_categories.row_number > 1
+);
+
+ALTER TABLE categories ADD CONSTRAINT category_id_per_budget UNIQUE (budget_id, id);
@@ -0,0 +1,6 @@
+ALTER TABLE sessions
+    DROP COLUMN is_password_reset,
+    DROP COLUMN device_name,
+    DROP COLUMN user_agent,
+    DROP COLUMN ip_address,
+    DROP COLUMN last_used_timestamp;
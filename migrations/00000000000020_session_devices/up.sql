@@ -0,0 +1,13 @@
+-- Sign-in sessions record the device they were started from so users can see where they are
+-- signed in and end sessions they don't recognize. The last-used time is updated whenever the
+-- session's refresh token is exchanged. Password reset sessions are never listed.
+ALTER TABLE sessions
+    ADD COLUMN is_password_reset BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN device_name VARCHAR(255),
+    ADD COLUMN user_agent VARCHAR(512),
+    ADD COLUMN ip_address VARCHAR(64),
+    ADD COLUMN last_used_timestamp TIMESTAMP;
+
+UPDATE sessions SET last_used_timestamp = created_timestamp;
+
+ALTER TABLE sessions ALTER COLUMN last_used_timestamp SET NOT NULL;
@@ -0,0 +1,19 @@
+-- Links and files kept with a budget so everyone in it can find shared documents, such as a lease
+-- or an insurance policy. A link's location is its URL. A file's location is the key storage gave
+-- it when it was uploaded with a resource upload token.
+CREATE TABLE budget_resources (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+
+    kind SMALLINT NOT NULL,
+    title VARCHAR(120) NOT NULL,
+    location VARCHAR(2048) NOT NULL,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON budget_resources (budget_id);
+
+ALTER TABLE budget_resources ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE budget_resources ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1 @@
+ALTER TABLE budgets ADD COLUMN is_archived BOOLEAN NOT NULL DEFAULT FALSE;
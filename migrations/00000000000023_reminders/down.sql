@@ -0,0 +1,5 @@
+ALTER TABLE reminders DROP CONSTRAINT entry_key;
+ALTER TABLE reminders DROP CONSTRAINT budget_key;
+ALTER TABLE reminders DROP CONSTRAINT user_key;
+
+DROP TABLE reminders;
@@ -0,0 +1,20 @@
+CREATE TABLE reminders (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    budget_id UUID,
+    entry_id UUID,
+
+    note VARCHAR(500) NOT NULL,
+    remind_date DATE NOT NULL,
+    is_delivered BOOLEAN NOT NULL DEFAULT FALSE,
+
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON reminders (user_id);
+CREATE INDEX ON reminders (remind_date) WHERE NOT is_delivered;
+
+ALTER TABLE reminders ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE reminders ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE reminders ADD CONSTRAINT entry_key FOREIGN KEY(entry_id) REFERENCES entries(id) ON DELETE CASCADE;
@@ -0,0 +1,4 @@
+ALTER TABLE budget_category_totals DROP COLUMN tax_cents;
+
+ALTER TABLE entries DROP COLUMN tip_cents;
+ALTER TABLE entries DROP COLUMN tax_cents;
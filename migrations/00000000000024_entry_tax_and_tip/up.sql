@@ -0,0 +1,6 @@
+-- The parts of an entry's amount that went to tax or tip. What is left is the cost of the
+-- purchase itself.
+ALTER TABLE entries ADD COLUMN tax_cents BIGINT;
+ALTER TABLE entries ADD COLUMN tip_cents BIGINT;
+
+ALTER TABLE budget_category_totals ADD COLUMN tax_cents BIGINT NOT NULL DEFAULT 0;
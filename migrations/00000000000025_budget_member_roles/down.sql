@@ -0,0 +1 @@
+ALTER TABLE user_budgets DROP COLUMN role;
@@ -0,0 +1,9 @@
+-- 0 is owner, 1 is editor, and 2 is viewer
+ALTER TABLE user_budgets ADD COLUMN role SMALLINT NOT NULL DEFAULT 1;
+
+-- Each existing budget's longest-standing member was treated as its owner
+UPDATE user_budgets SET role = 0
+WHERE id IN (
+    SELECT DISTINCT ON (budget_id) id FROM user_budgets
+    ORDER BY budget_id, created_timestamp ASC, id ASC
+);
@@ -0,0 +1 @@
+ALTER TABLE entries ADD COLUMN is_deductible BOOLEAN NOT NULL DEFAULT FALSE;
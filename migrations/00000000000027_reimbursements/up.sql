@@ -0,0 +1,20 @@
+-- Expenses that someone else, such as a client or an employer, will pay back. Each moves from
+-- outstanding to submitted to paid. A paid reimbursement is linked to the incoming entry that paid
+-- it back.
+CREATE TABLE reimbursements (
+    entry_id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+
+    status SMALLINT NOT NULL,
+    payment_entry_id UUID,
+
+    submitted_timestamp TIMESTAMP,
+    paid_timestamp TIMESTAMP,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON reimbursements (budget_id);
+
+ALTER TABLE reimbursements ADD CONSTRAINT entry_key FOREIGN KEY(entry_id) REFERENCES entries(id) ON DELETE CASCADE;
+ALTER TABLE reimbursements ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE reimbursements ADD CONSTRAINT payment_entry_key FOREIGN KEY(payment_entry_id) REFERENCES entries(id) ON DELETE SET NULL;
@@ -0,0 +1,21 @@
+-- Receipts and other documents kept with an entry. The file itself lives in the configured
+-- storage backend under `storage_key`.
+CREATE TABLE entry_attachments (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    entry_id UUID NOT NULL,
+    budget_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    storage_key VARCHAR(255) NOT NULL,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON entry_attachments (entry_id);
+
+ALTER TABLE entry_attachments ADD CONSTRAINT entry_key FOREIGN KEY(entry_id) REFERENCES entries(id) ON DELETE CASCADE;
+ALTER TABLE entry_attachments ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE entry_attachments ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
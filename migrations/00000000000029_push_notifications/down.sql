@@ -0,0 +1,4 @@
+DROP TRIGGER queue_push ON user_notifications;
+DROP FUNCTION queue_push();
+DROP TABLE pending_pushes;
+DROP TABLE user_device_tokens;
@@ -0,0 +1,36 @@
+-- Tokens the apps get from APNs or FCM for pushing to a device. A token belongs to whoever
+-- registered it most recently.
+CREATE TABLE user_device_tokens (
+    token VARCHAR(512) UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    platform SMALLINT NOT NULL,
+
+    modified_timestamp TIMESTAMP NOT NULL,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON user_device_tokens (user_id);
+
+ALTER TABLE user_device_tokens ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+-- Notifications waiting to be pushed. Every new notification is queued by a trigger, so a push
+-- goes out no matter which part of the server created the notification.
+CREATE TABLE pending_pushes (
+    notification_id UUID UNIQUE NOT NULL PRIMARY KEY,
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON pending_pushes (created_timestamp);
+
+ALTER TABLE pending_pushes ADD CONSTRAINT notification_key FOREIGN KEY(notification_id) REFERENCES user_notifications(id) ON DELETE CASCADE;
+
+CREATE FUNCTION queue_push() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO pending_pushes (notification_id, created_timestamp)
+        VALUES (NEW.id, NEW.created_timestamp);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER queue_push AFTER INSERT ON user_notifications
+    FOR EACH ROW EXECUTE PROCEDURE queue_push();
@@ -0,0 +1,19 @@
+-- Who changed what in a budget. `before_value` and `after_value` hold the changed record as JSON.
+-- A row is written in the same transaction as the change it describes. The row outlives the
+-- account of the user who made the change.
+CREATE TABLE budget_audit_log (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    budget_id UUID NOT NULL,
+    user_id UUID,
+
+    action SMALLINT NOT NULL,
+    before_value TEXT,
+    after_value TEXT,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON budget_audit_log (budget_id, created_timestamp);
+
+ALTER TABLE budget_audit_log ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE budget_audit_log ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+DROP TRIGGER reject_closed_month_entry_changes ON entries;
+DROP FUNCTION reject_closed_month_entry_changes();
+DROP TABLE closed_month_totals;
+DROP TABLE closed_months;
@@ -0,0 +1,65 @@
+-- Months of a budget that an owner has closed. `month` is the first day of the month.
+CREATE TABLE closed_months (
+    budget_id UUID NOT NULL,
+    month DATE NOT NULL,
+    closed_by_user_id UUID,
+    closed_timestamp TIMESTAMP NOT NULL,
+
+    PRIMARY KEY (budget_id, month)
+);
+
+ALTER TABLE closed_months ADD CONSTRAINT budget_key FOREIGN KEY(budget_id) REFERENCES budgets(id) ON DELETE CASCADE;
+ALTER TABLE closed_months ADD CONSTRAINT user_key FOREIGN KEY(closed_by_user_id) REFERENCES users(id) ON DELETE SET NULL;
+
+-- What was spent in each category of the month when it was closed. Uncategorized entries are
+-- totalled under category -1, as in budget_category_totals.
+CREATE TABLE closed_month_totals (
+    budget_id UUID NOT NULL,
+    month DATE NOT NULL,
+    category SMALLINT NOT NULL,
+    spent_cents BIGINT NOT NULL,
+    entry_count BIGINT NOT NULL,
+
+    PRIMARY KEY (budget_id, month, category)
+);
+
+ALTER TABLE closed_month_totals ADD CONSTRAINT closed_month_key FOREIGN KEY(budget_id, month) REFERENCES closed_months(budget_id, month) ON DELETE CASCADE;
+
+-- Entries dated in a closed month can't be added, changed, deleted or restored, however the change
+-- is made. Changes that leave an entry's contents alone, like moving it to another user when
+-- accounts are merged, are still allowed. So are hard deletes, which only remove entries that are
+-- already in the trash or whose budget or user is being deleted.
+CREATE FUNCTION reject_closed_month_entry_changes() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND NEW.budget_id = OLD.budget_id
+        AND NEW.is_deleted = OLD.is_deleted
+        AND NEW.amount_cents = OLD.amount_cents
+        AND NEW.date = OLD.date
+        AND NEW.name IS NOT DISTINCT FROM OLD.name
+        AND NEW.category IS NOT DISTINCT FROM OLD.category
+        AND NEW.note IS NOT DISTINCT FROM OLD.note
+        AND NEW.tax_cents IS NOT DISTINCT FROM OLD.tax_cents
+        AND NEW.tip_cents IS NOT DISTINCT FROM OLD.tip_cents
+        AND NEW.is_deductible = OLD.is_deductible
+    THEN
+        RETURN NEW;
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM closed_months
+               WHERE budget_id = NEW.budget_id
+               AND month = date_trunc('month', NEW.date)::DATE)
+        OR (TG_OP = 'UPDATE' AND EXISTS (SELECT 1 FROM closed_months
+                                         WHERE budget_id = OLD.budget_id
+                                         AND month = date_trunc('month', OLD.date)::DATE))
+    THEN
+        RAISE EXCEPTION 'Entry is in a closed month'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'entry_in_closed_month';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER reject_closed_month_entry_changes BEFORE INSERT OR UPDATE ON entries
+    FOR EACH ROW EXECUTE PROCEDURE reject_closed_month_entry_changes();
@@ -0,0 +1,18 @@
+-- Entries a user has recorded, or that were synced from their bank, that haven't been put in a
+-- budget yet. Assigning an entry to a budget moves it into the entries table and deletes it from
+-- here in the same transaction, so an entry can only ever end up in one budget.
+CREATE TABLE inbox_entries (
+    id UUID UNIQUE NOT NULL PRIMARY KEY,
+    user_id UUID NOT NULL,
+
+    amount_cents BIGINT NOT NULL,
+    date DATE NOT NULL,
+    name VARCHAR(25),
+    note TEXT,
+
+    created_timestamp TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON inbox_entries (user_id, created_timestamp);
+
+ALTER TABLE inbox_entries ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1 @@
+ALTER TABLE users DROP COLUMN fiscal_year_start_month;
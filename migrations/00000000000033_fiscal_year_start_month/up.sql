@@ -0,0 +1,3 @@
+-- The month a user's fiscal year starts in, which reports use when no other start month is asked for
+ALTER TABLE users ADD COLUMN fiscal_year_start_month SMALLINT NOT NULL DEFAULT 1
+    CHECK (fiscal_year_start_month BETWEEN 1 AND 12);
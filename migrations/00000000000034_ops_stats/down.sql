@@ -0,0 +1 @@
+DROP SCHEMA ops CASCADE;
@@ -0,0 +1,30 @@
+-- Aggregates for capacity planning. Nothing in this schema identifies a user or a budget, so ops
+-- can be granted access to it without access to anything in public. Counts are rounded down to two
+-- significant figures before they are written.
+CREATE SCHEMA ops;
+
+-- Entries created on each day, including ones that have since been deleted
+CREATE TABLE ops.daily_entry_counts (
+    day DATE PRIMARY KEY,
+    entry_count BIGINT NOT NULL,
+    computed_timestamp TIMESTAMP NOT NULL
+);
+
+-- Imports made on each day, grouped by size. A bucket holds imports with at least min_entries
+-- entries and fewer than twice that many.
+CREATE TABLE ops.import_size_buckets (
+    day DATE NOT NULL,
+    min_entries INTEGER NOT NULL,
+    import_count BIGINT NOT NULL,
+    computed_timestamp TIMESTAMP NOT NULL,
+    PRIMARY KEY (day, min_entries)
+);
+
+-- How many entries budgets hold, as of the day the stats were computed
+CREATE TABLE ops.budget_entry_stats (
+    day DATE PRIMARY KEY,
+    budget_count BIGINT NOT NULL,
+    median_entries BIGINT NOT NULL,
+    p95_entries BIGINT NOT NULL,
+    computed_timestamp TIMESTAMP NOT NULL
+);
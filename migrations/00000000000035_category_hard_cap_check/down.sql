@@ -0,0 +1 @@
+DROP FUNCTION check_category_hard_caps(UUID[]);
@@ -0,0 +1,32 @@
+-- Rejects new entries that take a hard-capped category over its limit. It runs after the entries
+-- have been added to budget_category_totals in the same transaction. That upsert holds the lock on
+-- each total row until the transaction ends, so entries added to a category at the same time are
+-- checked one after the other against the total that includes the earlier ones. Refunds and other
+-- negative amounts never count against a cap, and caps have no effect in tracking-only budgets.
+CREATE FUNCTION check_category_hard_caps(new_entry_ids UUID[]) RETURNS VOID AS $$
+BEGIN
+    IF EXISTS (SELECT 1
+               FROM (SELECT entries.budget_id, entries.category,
+                            SUM(entries.amount_cents) AS added_cents
+                     FROM entries
+                     JOIN budgets ON budgets.id = entries.budget_id
+                     WHERE entries.id = ANY(new_entry_ids)
+                     AND entries.is_deleted = FALSE
+                     AND entries.category IS NOT NULL
+                     AND entries.date BETWEEN budgets.start_date AND budgets.end_date
+                     AND budgets.is_tracking_only = FALSE
+                     GROUP BY entries.budget_id, entries.category) AS added
+               JOIN categories ON categories.budget_id = added.budget_id
+                              AND categories.id = added.category
+               JOIN budget_category_totals AS totals ON totals.budget_id = added.budget_id
+                                                    AND totals.category = added.category
+               WHERE added.added_cents > 0
+               AND categories.is_deleted = FALSE
+               AND categories.is_hard_capped = TRUE
+               AND totals.spent_cents > categories.limit_cents)
+    THEN
+        RAISE EXCEPTION 'Entry would take a category over its spending cap'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'category_hard_cap';
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,3 @@
+ALTER TABLE password_reset_attempts DROP CONSTRAINT user_key;
+
+DROP TABLE password_reset_attempts;
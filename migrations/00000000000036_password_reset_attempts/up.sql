@@ -0,0 +1,7 @@
+-- Counts password reset requests when Redis isn't available. Cleared with password_attempts.
+CREATE TABLE password_reset_attempts (
+    user_id UUID UNIQUE NOT NULL PRIMARY KEY,
+    attempt_count SMALLINT NOT NULL
+);
+
+ALTER TABLE password_reset_attempts ADD CONSTRAINT user_key FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE;
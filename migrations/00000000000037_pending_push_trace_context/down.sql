@@ -0,0 +1,9 @@
+CREATE OR REPLACE FUNCTION queue_push() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO pending_pushes (notification_id, created_timestamp)
+        VALUES (NEW.id, NEW.created_timestamp);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE pending_pushes DROP COLUMN traceparent;
@@ -0,0 +1,13 @@
+-- The trace of the request that queued the push, as a W3C traceparent, so delivering it joins that
+-- trace. The server sets budgetapp.traceparent for the transaction before creating notifications
+-- while it handles a request. Pushes queued any other way have no trace.
+ALTER TABLE pending_pushes ADD COLUMN traceparent VARCHAR(55);
+
+CREATE OR REPLACE FUNCTION queue_push() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO pending_pushes (notification_id, created_timestamp, traceparent)
+        VALUES (NEW.id, NEW.created_timestamp,
+                NULLIF(current_setting('budgetapp.traceparent', TRUE), ''));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
@@ -6,6 +6,7 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::definitions::AtomicMutex;
+use crate::utils::error_reporting;
 
 type CronJob = (
     Box<dyn Fn() -> Result<(), CronJobError> + Send + 'static>,
@@ -130,7 +131,10 @@ impl Runner {
 
                         match job.0() {
                             Ok(_) => info!("Cron job completed successfully: '{}'", &job.1),
-                            Err(e) => error!("Cron job failed: '{}': {e}", &job.1),
+                            Err(e) => {
+                                error!("Cron job failed: '{}': {e}", &job.1);
+                                error_reporting::report_job_failure(&job.1, &e);
+                            }
                         }
                     }
 
@@ -1,20 +1,116 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Read;
+use std::sync::{Arc, RwLock};
+
+use crate::utils::secrets::{self, SecretProvider};
 
 #[derive(Deserialize, Serialize)]
 pub struct Conf {
+    pub client_compatibility: ClientCompatibility,
+    pub concurrency_limits: ConcurrencyLimits,
     pub connections: Connections,
+    pub currency: Currency,
+    pub email_screening: EmailScreening,
+    pub error_reporting: ErrorReporting,
+    pub fault_injection: FaultInjection,
     pub hashing: Hashing,
     pub keys: Keys,
-    pub lifetimes: Lifetimes,
+    pub logging: Logging,
+    pub mail: Mail,
+    pub otp: Otp,
+    pub privacy: Privacy,
+    pub push: Push,
+    pub remote_config: RemoteConfig,
     pub security: Security,
+    pub storage: Storage,
+    pub tracing: Tracing,
     pub workers: Workers,
 }
 
+// The sections that are read again when the server gets a SIGHUP. They come from the same file as
+// the rest of the configuration.
+#[derive(Deserialize, Serialize)]
+pub struct ReloadableConf {
+    pub daily_limits: DailyLimits,
+    pub lifetimes: Lifetimes,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ClientCompatibility {
+    pub android: ClientPlatform,
+    pub ios: ClientPlatform,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ClientPlatform {
+    pub min_version: String,
+    pub store_url: String,
+}
+
+// Applies separately to each group of expensive endpoints (imports, reports)
+#[derive(Deserialize, Serialize)]
+pub struct ConcurrencyLimits {
+    pub global_limit: usize,
+    pub per_user_limit: usize,
+    pub queue_timeout_ms: u64,
+    pub retry_after_secs: u64,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct Connections {
     pub database_uri: String,
+    pub redis_uri: Option<String>,
+}
+
+// Rates are in units of each currency per unit of the base currency
+#[derive(Deserialize, Serialize)]
+pub struct Currency {
+    pub base_currency: String,
+    pub rate_refresh_interval_mins: i16,
+    pub rates: BTreeMap<String, f64>,
+}
+
+// Caps on how many times a day each user can take actions that reach other users
+#[derive(Deserialize, Serialize)]
+pub struct DailyLimits {
+    pub free: ActionLimits,
+    pub premium: ActionLimits,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ActionLimits {
+    pub budget_invitations: i32,
+    pub budget_comments: i32,
+}
+
+// Domains in `allowed_domains` are accepted even if they are on the disposable domain list
+#[derive(Deserialize, Serialize)]
+pub struct EmailScreening {
+    pub reject_disposable_domains: bool,
+    pub allowed_domains: Vec<String>,
+    pub blocked_domains: Vec<String>,
+}
+
+// Reporting is disabled when no DSN is configured
+#[derive(Deserialize, Serialize)]
+pub struct ErrorReporting {
+    pub dsn: Option<String>,
+    pub environment: String,
+    pub sample_rate: f32,
+}
+
+// For exercising client resilience in staging. Only takes effect in builds made with the
+// `fault-injection` feature, and never when the error-reporting environment is "production".
+#[derive(Deserialize, Serialize)]
+pub struct FaultInjection {
+    pub enabled: bool,
+    pub latency_rate: f64,
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub db_error_rate: f64,
+    pub server_error_rate: f64,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -30,14 +126,160 @@ pub struct Hashing {
 pub struct Keys {
     pub hashing_key: String,
     pub token_signing_key: String,
+    pub token_signing_key_id: String,
+    #[serde(default)]
+    pub previous_token_signing_keys: Vec<SigningKey>,
     pub otp_key: String,
+    pub accept_legacy_tokens: bool,
+    #[serde(default)]
+    pub internal_service_keys: Vec<String>,
+}
+
+// A retired token signing key. Tokens signed with it stay valid until they expire, so a key can
+// be rotated without signing everyone out.
+#[derive(Deserialize, Serialize)]
+pub struct SigningKey {
+    pub id: String,
+    pub key: String,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct Lifetimes {
     pub access_token_lifetime_mins: u64,
+    pub account_deletion_grace_period_days: u64,
     pub refresh_token_lifetime_days: u64,
     pub otp_lifetime_mins: u64,
+    pub password_reset_token_lifetime_mins: u64,
+    pub trash_retention_days: u64,
+    pub upload_token_lifetime_mins: u64,
+}
+
+// Levels are given as "off", "error", "warn", "info", "debug" or "trace"
+#[derive(Deserialize, Serialize)]
+pub struct Logging {
+    pub default_level: String,
+    #[serde(default)]
+    pub json_output: bool,
+    #[serde(default)]
+    pub module_levels: BTreeMap<String, String>,
+}
+
+// How email to users is sent. The directory backend writes messages to files instead of sending
+// them and is only meant for development and tests.
+#[derive(Deserialize, Serialize)]
+pub struct Mail {
+    pub backend: MailerKind,
+    pub from_address: String,
+    pub directory: String,
+    pub http: Option<HttpMail>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailerKind {
+    Directory,
+    Http,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct HttpMail {
+    pub endpoint: String,
+    pub api_key: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Otp {
+    pub code_length: usize,
+    pub alphabet: OtpAlphabet,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpAlphabet {
+    Digits,
+    Alphanumeric,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Privacy {
+    pub benchmarking_min_cohort_size: i64,
+}
+
+// Notifications are pushed to devices on the platforms with a configured provider
+#[derive(Deserialize, Serialize)]
+pub struct Push {
+    pub delivery_interval_secs: u64,
+    pub apns: Option<ApnsPush>,
+    pub fcm: Option<FcmPush>,
+}
+
+// Signs in with a token signing key (a .p8 file) from the Apple developer account. The topic is
+// the app's bundle ID.
+#[derive(Deserialize, Serialize)]
+pub struct ApnsPush {
+    pub team_id: String,
+    pub key_id: String,
+    pub private_key_path: String,
+    pub topic: String,
+    #[serde(default)]
+    pub use_sandbox: bool,
+}
+
+// Signs in with the JSON key file of a service account that may send Firebase messages
+#[derive(Deserialize, Serialize)]
+pub struct FcmPush {
+    pub service_account_path: String,
+}
+
+// Values served to clients by /api/meta/remote_config. Flags and limits are hints that let the
+// apps adapt without a release.
+#[derive(Deserialize, Serialize)]
+pub struct RemoteConfig {
+    pub display_hints: BTreeMap<String, String>,
+    pub feature_flags: BTreeMap<String, FeatureAvailability>,
+    pub free_limits: BTreeMap<String, i64>,
+    pub premium_limits: BTreeMap<String, i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureAvailability {
+    All,
+    Premium,
+    Disabled,
+}
+
+// Where secret keys are kept when they aren't in this file. Only read at startup. Tokens and
+// credentials for the provider can be given as environment overrides to keep them out of the file.
+#[derive(Deserialize, Serialize)]
+pub struct Secrets {
+    pub provider: SecretProviderKind,
+    pub vault: Option<VaultSecrets>,
+    pub aws_secrets_manager: Option<AwsSecretsManagerSecrets>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretProviderKind {
+    Vault,
+    AwsSecretsManager,
+}
+
+// The secret at `{mount}/data/{path}` in a KV version 2 secrets engine
+#[derive(Deserialize, Serialize)]
+pub struct VaultSecrets {
+    pub address: String,
+    pub token: String,
+    pub mount: String,
+    pub path: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AwsSecretsManagerSecrets {
+    pub region: String,
+    pub secret_id: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -46,6 +288,56 @@ pub struct Security {
     pub otp_attempts_reset_mins: i16,
     pub password_max_attempts: i16,
     pub password_attempts_reset_mins: i16,
+    pub password_reset_max_requests: i16,
+    pub blacklisted_token_purge_interval_mins: i16,
+    pub api_key_daily_request_limit: i32,
+}
+
+// Where files such as entry attachments are kept. Self-hosted servers can use the local directory
+// instead of a cloud provider. Attachment types are MIME types and are checked against the file's
+// contents rather than what the client claims.
+#[derive(Deserialize, Serialize)]
+pub struct Storage {
+    pub backend: BlobStoreKind,
+    pub local_directory: String,
+    pub s3: Option<S3Storage>,
+    pub gcs: Option<GcsStorage>,
+    pub max_attachment_bytes: usize,
+    pub allowed_attachment_types: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobStoreKind {
+    Local,
+    S3,
+    Gcs,
+}
+
+// Any S3-compatible service. Objects are addressed path-style, as in `{endpoint}/{bucket}/{key}`.
+#[derive(Deserialize, Serialize)]
+pub struct S3Storage {
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+// Google Cloud Storage through its XML API, authenticated with an HMAC key for a service account
+#[derive(Deserialize, Serialize)]
+pub struct GcsStorage {
+    pub bucket: String,
+    pub hmac_access_id: String,
+    pub hmac_secret: String,
+}
+
+// Spans are exported to an OpenTelemetry collector over OTLP/HTTP when an endpoint is configured.
+// The endpoint is the full URL, usually ending in /v1/traces.
+#[derive(Deserialize, Serialize)]
+pub struct Tracing {
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -56,31 +348,288 @@ pub struct Workers {
 lazy_static! {
     pub static ref APP_NAME: &'static str = "Budget App";
     pub static ref CONF: Conf = build_conf();
+    static ref RELOADABLE_CONF: RwLock<Arc<ReloadableConf>> =
+        RwLock::new(Arc::new(read_conf().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        })));
 }
 
-fn build_conf() -> Conf {
-    const CONF_FILE_PATH: &str = "conf/budgetapp.toml";
+const CONF_FILE_PATH_VAR: &str = "BUDGETAPP_CONF";
+const DEFAULT_CONF_FILE_PATH: &str = "conf/budgetapp.toml";
+#[cfg(test)]
+const TEST_CONF_FILE_PATH: &str = "conf/budgetapp.test.toml";
+
+// A field is overridden by an environment variable named after its path, with `__` between the
+// parts and `BUDGETAPP__` in front (e.g. `BUDGETAPP__LIFETIMES__OTP_LIFETIME_MINS`). The fields
+// most often set per deployment also have shorter names.
+const ENV_OVERRIDE_PREFIX: &str = "BUDGETAPP__";
+const ENV_OVERRIDE_ALIASES: &[(&str, &str)] = &[
+    ("BUDGETAPP_DB_URI", "connections.database_uri"),
+    ("BUDGETAPP_REDIS_URI", "connections.redis_uri"),
+];
+
+// The values of the reloadable sections as of the last reload. Take what is needed from a single
+// call so the values used together come from the same reload.
+pub fn reloadable_conf() -> Arc<ReloadableConf> {
+    RELOADABLE_CONF
+        .read()
+        .expect("Reloadable configuration lock was poisoned")
+        .clone()
+}
+
+// Leaves the current values in place if the file can't be read or parsed
+pub fn reload_conf() -> Result<(), String> {
+    let conf = read_conf::<ReloadableConf>()?;
+
+    *RELOADABLE_CONF
+        .write()
+        .expect("Reloadable configuration lock was poisoned") = Arc::new(conf);
 
-    let mut conf_file = File::open(CONF_FILE_PATH).unwrap_or_else(|_| {
-        eprintln!("Expected configuration file at '{}'", CONF_FILE_PATH);
+    Ok(())
+}
+
+fn build_conf() -> Conf {
+    read_conf_with_secrets().unwrap_or_else(|e| {
+        eprintln!("{}", e);
         std::process::exit(1);
-    });
+    })
+}
+
+fn read_conf_with_secrets() -> Result<Conf, String> {
+    let (mut conf, conf_file_path) = read_conf_value()?;
+
+    let provider = match conf.get("secrets") {
+        Some(secrets) => {
+            let secrets = secrets
+                .clone()
+                .try_into::<Secrets>()
+                .map_err(|e| format!("Parsing '{}' failed: {}", conf_file_path, e))?;
+            Some(secrets::configured_secret_provider(&secrets)?)
+        }
+        None => None,
+    };
+
+    resolve_keys(&mut conf, provider.as_deref())?;
+
+    conf.try_into::<Conf>()
+        .map_err(|e| format!("Parsing '{}' failed: {}", conf_file_path, e))
+}
+
+fn read_conf<T: serde::de::DeserializeOwned>() -> Result<T, String> {
+    let (conf, conf_file_path) = read_conf_value()?;
+
+    conf.try_into::<T>()
+        .map_err(|e| format!("Parsing '{}' failed: {}", conf_file_path, e))
+}
+
+// Returns the file's contents with the environment overrides applied, along with the file's path
+fn read_conf_value() -> Result<(toml::Value, String), String> {
+    let conf_file_path =
+        std::env::var(CONF_FILE_PATH_VAR).unwrap_or_else(|_| String::from(DEFAULT_CONF_FILE_PATH));
+
+    let mut conf_file = File::open(&conf_file_path)
+        .map_err(|_| format!("Expected configuration file at '{}'", conf_file_path))?;
 
     let mut contents = String::new();
-    conf_file.read_to_string(&mut contents).unwrap_or_else(|_| {
-        eprintln!(
-            "Configuratioin file at '{}' should be a text file in the TOML format.",
-            CONF_FILE_PATH
-        );
-        std::process::exit(1);
-    });
+    conf_file.read_to_string(&mut contents).map_err(|_| {
+        format!(
+            "Configuration file at '{}' should be a text file in the TOML format.",
+            conf_file_path
+        )
+    })?;
 
-    match toml::from_str::<Conf>(&contents) {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("Parsing '{}' failed: {}", CONF_FILE_PATH, e);
-            std::process::exit(1);
+    let mut conf = toml::from_str::<toml::Value>(&contents)
+        .map_err(|e| format!("Parsing '{}' failed: {}", conf_file_path, e))?;
+
+    #[cfg(test)]
+    merge_test_conf(&mut conf)?;
+
+    apply_env_overrides(&mut conf, std::env::vars())?;
+
+    Ok((conf, conf_file_path))
+}
+
+#[cfg(test)]
+fn merge_test_conf(conf: &mut toml::Value) -> Result<(), String> {
+    let contents = std::fs::read_to_string(TEST_CONF_FILE_PATH).map_err(|_| {
+        format!(
+            "Expected test configuration file at '{}'",
+            TEST_CONF_FILE_PATH
+        )
+    })?;
+
+    let test_conf = toml::from_str::<toml::Value>(&contents)
+        .map_err(|e| format!("Parsing '{}' failed: {}", TEST_CONF_FILE_PATH, e))?;
+
+    merge_values(conf, test_conf);
+
+    Ok(())
+}
+
+// Tables are merged field by field. Anything else in `overlay`, arrays included, replaces what is
+// in `base`.
+#[cfg(test)]
+fn merge_values(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (name, value) in overlay {
+                match base.get_mut(&name) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(name, value);
+                    }
+                }
+            }
         }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+// Each of these keys can instead be read from the file named by `{key}_file` in [keys], such as a
+// secret mounted into the container. Keys that are in neither place are taken from the secrets
+// provider, if there is one.
+const SECRET_KEYS: &[&str] = &[
+    "hashing_key",
+    "otp_key",
+    "token_signing_key",
+    "token_signing_key_id",
+];
+
+fn resolve_keys(
+    conf: &mut toml::Value,
+    provider: Option<&dyn SecretProvider>,
+) -> Result<(), String> {
+    let keys = conf
+        .as_table_mut()
+        .expect("A configuration file is always a table")
+        .entry("keys")
+        .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| String::from("keys must be a section"))?;
+
+    for key in SECRET_KEYS {
+        let file_field = format!("{}_file", key);
+        let path = match keys.remove(&file_field) {
+            Some(toml::Value::String(path)) => path,
+            Some(_) => return Err(format!("keys.{} must be a file path", file_field)),
+            None => continue,
+        };
+
+        if keys.contains_key(*key) {
+            return Err(format!(
+                "Only one of keys.{} and keys.{} can be set",
+                key, file_field
+            ));
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read keys.{} from '{}': {}", key, path, e))?;
+
+        // Files written by `echo` and most editors end with a newline that isn't part of the key
+        let value = contents.trim_end_matches(['\r', '\n']);
+        keys.insert(String::from(*key), toml::Value::String(String::from(value)));
+    }
+
+    let missing_keys = SECRET_KEYS
+        .iter()
+        .filter(|key| !keys.contains_key(**key))
+        .collect::<Vec<_>>();
+
+    let provider = match provider {
+        Some(p) if !missing_keys.is_empty() => p,
+        _ => return Ok(()),
+    };
+
+    let fetch = provider.fetch_secrets();
+
+    // The configuration may first be read from inside an async runtime, which can't be blocked
+    // on, so the secrets are fetched on a thread of their own
+    let mut secrets = std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to create runtime for fetching secrets: {}", e))?
+            .block_on(fetch)
+    })
+    .join()
+    .map_err(|_| String::from("Fetching secrets panicked"))?
+    .map_err(|e| format!("Failed to fetch secrets: {}", e))?;
+
+    for key in missing_keys {
+        if let Some(value) = secrets.remove(*key) {
+            keys.insert(String::from(*key), toml::Value::String(value));
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_env_overrides(
+    conf: &mut toml::Value,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<(), String> {
+    for (name, value) in vars {
+        let path = match ENV_OVERRIDE_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == name)
+        {
+            Some((_, path)) => path.split('.').map(String::from).collect::<Vec<_>>(),
+            None => match name.strip_prefix(ENV_OVERRIDE_PREFIX) {
+                Some(path) => path.split("__").map(|p| p.to_lowercase()).collect(),
+                None => continue,
+            },
+        };
+
+        let (field, sections) = path.split_last().expect("split always yields a part");
+
+        let mut table = conf
+            .as_table_mut()
+            .expect("A configuration file is always a table");
+        for section in sections {
+            table = table
+                .entry(section.clone())
+                .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| {
+                    format!("{} overrides '{}', which isn't a section", name, section)
+                })?;
+        }
+
+        // Strings are taken as they are. Anything else is parsed as a TOML value, so numbers,
+        // booleans and arrays can be overridden too.
+        let new_value = match table.get(field.as_str()) {
+            Some(toml::Value::String(_)) => toml::Value::String(value),
+            _ => match toml::from_str::<toml::value::Table>(&format!("value = {}", value)) {
+                Ok(mut parsed) => parsed.remove("value").expect("value was just parsed"),
+                Err(_) => toml::Value::String(value),
+            },
+        };
+
+        table.insert(field.clone(), new_value);
+    }
+
+    Ok(())
+}
+
+pub mod email {
+    use crate::utils::email_domain_set::EmailDomainSet;
+
+    lazy_static! {
+        pub static ref DISPOSABLE_EMAIL_DOMAINS_FILE_PATH: &'static str =
+            "./assets/disposable-email-domains.txt";
+        pub static ref DISPOSABLE_EMAIL_DOMAINS: EmailDomainSet = EmailDomainSet::generate();
+        pub static ref ALLOWED_EMAIL_DOMAINS: EmailDomainSet =
+            EmailDomainSet::from_domains(&crate::env::CONF.email_screening.allowed_domains);
+        pub static ref BLOCKED_EMAIL_DOMAINS: EmailDomainSet =
+            EmailDomainSet::from_domains(&crate::env::CONF.email_screening.blocked_domains);
+    }
+
+    pub fn initialize() {
+        let _ = *DISPOSABLE_EMAIL_DOMAINS_FILE_PATH;
+        let _ = *DISPOSABLE_EMAIL_DOMAINS;
+        let _ = *ALLOWED_EMAIL_DOMAINS;
+        let _ = *BLOCKED_EMAIL_DOMAINS;
     }
 }
 
@@ -128,6 +677,8 @@ pub mod testing {
 
 pub fn initialize() {
     // Forego lazy initialization in order to validate conf file
+    reloadable_conf();
+
     if !CONF.hashing.hash_mem_size_kib.is_power_of_two() {
         eprintln!(
             "Hash memory size must be a power of two. {} is not a power of two.",
@@ -136,6 +687,147 @@ pub fn initialize() {
         std::process::exit(1);
     }
 
+    let missing_storage_section = match CONF.storage.backend {
+        BlobStoreKind::Local => None,
+        BlobStoreKind::S3 => CONF.storage.s3.is_none().then_some("s3"),
+        BlobStoreKind::Gcs => CONF.storage.gcs.is_none().then_some("gcs"),
+    };
+
+    if let Some(section) = missing_storage_section {
+        eprintln!(
+            "The {} storage backend requires a [storage.{}] section",
+            section, section
+        );
+        std::process::exit(1);
+    }
+
+    email::initialize();
     password::initialize();
     rand::initialize();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let mut conf = toml::from_str::<toml::Value>(
+            r#"
+            [connections]
+            database_uri = "postgres://localhost/budgetapp"
+
+            [lifetimes]
+            otp_lifetime_mins = 5
+
+            [keys]
+            token_signing_key = "secret"
+            "#,
+        )
+        .unwrap();
+
+        let vars = [
+            ("BUDGETAPP_DB_URI", "postgres://db.internal/budgetapp"),
+            ("BUDGETAPP__LIFETIMES__OTP_LIFETIME_MINS", "10"),
+            ("BUDGETAPP__KEYS__TOKEN_SIGNING_KEY", "12345"),
+            ("BUDGETAPP__LOGGING__DEFAULT_LEVEL", "debug"),
+            ("BUDGETAPP__LOGGING__JSON_OUTPUT", "true"),
+            ("BUDGETAPP_CONF", "conf/other.toml"),
+            ("HOME", "/root"),
+        ];
+        apply_env_overrides(
+            &mut conf,
+            vars.iter()
+                .map(|(n, v)| (String::from(*n), String::from(*v))),
+        )
+        .unwrap();
+
+        assert_eq!(
+            conf["connections"]["database_uri"].as_str(),
+            Some("postgres://db.internal/budgetapp")
+        );
+        assert_eq!(
+            conf["lifetimes"]["otp_lifetime_mins"].as_integer(),
+            Some(10)
+        );
+
+        // A field that is a string in the file stays a string even if the value looks like a number
+        assert_eq!(conf["keys"]["token_signing_key"].as_str(), Some("12345"));
+
+        // Sections and fields missing from the file are added
+        assert_eq!(conf["logging"]["default_level"].as_str(), Some("debug"));
+        assert_eq!(conf["logging"]["json_output"].as_bool(), Some(true));
+
+        assert!(conf.get("conf").is_none());
+        assert!(conf.get("home").is_none());
+
+        let mut conf = toml::from_str::<toml::Value>("[lifetimes]\notp_lifetime_mins = 5").unwrap();
+        let vars = [(
+            String::from("BUDGETAPP__LIFETIMES__OTP_LIFETIME_MINS__VALUE"),
+            String::from("10"),
+        )];
+        assert!(apply_env_overrides(&mut conf, vars.into_iter()).is_err());
+    }
+
+    struct FakeSecretProvider;
+
+    impl SecretProvider for FakeSecretProvider {
+        fn fetch_secrets(
+            &self,
+        ) -> futures::future::BoxFuture<'static, Result<BTreeMap<String, String>, String>> {
+            Box::pin(async {
+                Ok(BTreeMap::from([
+                    (String::from("hashing_key"), String::from("from-provider")),
+                    (String::from("otp_key"), String::from("from-provider")),
+                    (String::from("unrelated"), String::from("from-provider")),
+                ]))
+            })
+        }
+    }
+
+    #[test]
+    fn test_resolve_keys() {
+        let directory = std::env::temp_dir().join(format!("budgetapp-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let key_file_path = directory.join("token_signing_key");
+        std::fs::write(&key_file_path, "from-file\n").unwrap();
+
+        let mut conf = toml::from_str::<toml::Value>(&format!(
+            r#"
+            [keys]
+            otp_key = "from-conf"
+            token_signing_key_file = "{}"
+            "#,
+            key_file_path.display(),
+        ))
+        .unwrap();
+
+        resolve_keys(&mut conf, Some(&FakeSecretProvider)).unwrap();
+
+        assert_eq!(
+            conf["keys"]["token_signing_key"].as_str(),
+            Some("from-file")
+        );
+        assert!(conf["keys"].get("token_signing_key_file").is_none());
+
+        // The provider only fills in keys that aren't set some other way
+        assert_eq!(conf["keys"]["otp_key"].as_str(), Some("from-conf"));
+        assert_eq!(conf["keys"]["hashing_key"].as_str(), Some("from-provider"));
+        assert!(conf["keys"].get("unrelated").is_none());
+        assert!(conf["keys"].get("token_signing_key_id").is_none());
+
+        let mut conf = toml::from_str::<toml::Value>(&format!(
+            "[keys]\ntoken_signing_key = \"from-conf\"\ntoken_signing_key_file = \"{}\"",
+            key_file_path.display(),
+        ))
+        .unwrap();
+        assert!(resolve_keys(&mut conf, None).is_err());
+
+        let mut conf =
+            toml::from_str::<toml::Value>("[keys]\nhashing_key_file = \"/nonexistent/key\"")
+                .unwrap();
+        assert!(resolve_keys(&mut conf, None).is_err());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+use actix_web::{web, HttpResponse};
+use log::warn;
+
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{InputLogLevels, OutputLogLevels};
+use crate::middleware::internal_service::InternalService;
+use crate::utils::logging::{self, LogLevels, LoggingError, LOG_MODULES};
+
+// Log levels changed here apply only to the instance that handles the request and last until it
+// restarts or the levels are reset
+
+fn output_log_levels(levels: &LogLevels) -> OutputLogLevels {
+    OutputLogLevels {
+        default_level: levels.default_level.to_string().to_lowercase(),
+        module_levels: LOG_MODULES
+            .iter()
+            .map(|(module, _)| {
+                (
+                    module.to_string(),
+                    levels.level_for_module(module).to_string().to_lowercase(),
+                )
+            })
+            .collect(),
+    }
+}
+
+pub async fn get_log_levels(_internal_service: InternalService) -> HttpResponse {
+    HttpResponse::Ok().json(output_log_levels(&logging::current_levels()))
+}
+
+pub async fn set_log_levels(
+    _internal_service: InternalService,
+    input: web::Json<InputLogLevels>,
+) -> Result<HttpResponse, ServerError> {
+    let mut levels = logging::current_levels();
+
+    match levels.update(input.default_level.as_deref(), &input.module_levels) {
+        Ok(()) => (),
+        Err(LoggingError::InvalidLevel(_)) => {
+            return Err(ServerError::InvalidFormat(Some(
+                "Log levels must be one of off, error, warn, info, debug or trace",
+            )));
+        }
+        Err(LoggingError::UnknownModule(_)) => {
+            return Err(ServerError::InvalidFormat(Some(
+                "Log modules must be one of auth, db, jobs, mail or requests",
+            )));
+        }
+    }
+
+    let output = output_log_levels(&levels);
+    logging::set_levels(levels);
+
+    warn!(
+        "Log levels changed to {} (default), {:?}",
+        output.default_level, output.module_levels
+    );
+
+    Ok(HttpResponse::Ok().json(output))
+}
+
+pub async fn reset_log_levels(
+    _internal_service: InternalService,
+) -> Result<HttpResponse, ServerError> {
+    let levels = match logging::configured_levels() {
+        Ok(l) => l,
+        Err(e) => {
+            warn!("{}", e);
+            return Err(ServerError::InternalError(Some(
+                "Configured log levels are invalid",
+            )));
+        }
+    };
+
+    let output = output_log_levels(&levels);
+    logging::set_levels(levels);
+
+    warn!("Log levels reset to the configured levels");
+
+    Ok(HttpResponse::Ok().json(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{http, test, App};
+    use log::LevelFilter;
+
+    use crate::env;
+    use crate::middleware::internal_service::INTERNAL_SERVICE_KEY_HEADER;
+    use crate::services;
+
+    #[actix_rt::test]
+    async fn test_log_levels() {
+        let app = test::init_service(App::new().configure(services::api::configure)).await;
+        let service_key = env::CONF.keys.internal_service_keys[0].as_str();
+
+        let req = test::TestRequest::get()
+            .uri("/api/admin/log_levels")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/set_log_levels")
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+            .set_json(&InputLogLevels {
+                default_level: None,
+                module_levels: [(String::from("billing"), String::from("debug"))]
+                    .into_iter()
+                    .collect(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/set_log_levels")
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+            .set_json(&InputLogLevels {
+                default_level: None,
+                module_levels: [(String::from("auth"), String::from("debug"))]
+                    .into_iter()
+                    .collect(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let levels = test::read_body_json::<OutputLogLevels, _>(resp).await;
+        assert_eq!(levels.module_levels["auth"], "debug");
+        assert_eq!(levels.module_levels["db"], levels.default_level);
+
+        let current = logging::current_levels();
+        assert_eq!(
+            current.level_for_target("budgetapp_server::utils::auth_token"),
+            LevelFilter::Debug
+        );
+        assert!(log::max_level() >= LevelFilter::Debug);
+
+        let req = test::TestRequest::get()
+            .uri("/api/admin/log_levels")
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let fetched = test::read_body_json::<OutputLogLevels, _>(resp).await;
+        assert_eq!(fetched.module_levels, levels.module_levels);
+
+        let req = test::TestRequest::post()
+            .uri("/api/admin/reset_log_levels")
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        assert_eq!(
+            logging::current_levels(),
+            logging::configured_levels().unwrap()
+        );
+    }
+}
@@ -1,16 +1,56 @@
-use actix_web::{web, HttpResponse};
-use log::error;
+use actix_web::http::header::USER_AGENT;
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::{error, info};
+use std::net::SocketAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::definitions::*;
 use crate::env;
 use crate::handlers::error::ServerError;
 use crate::handlers::request_io::{
-    CredentialPair, RefreshToken, SigninToken, SigninTokenOtpPair, TokenPair,
+    CredentialPair, InputEmail, InputPasswordReset, InputToken, OutputTokenIntrospection,
+    RefreshToken, SigninToken, SigninTokenOtpPair, TokenPair,
 };
 use crate::middleware;
+use crate::utils::attempt_counter::{AttemptKind, ATTEMPT_COUNTER};
+use crate::utils::auth_token::{TokenError, TokenType};
 use crate::utils::db;
-use crate::utils::{auth_token, otp, password_hasher};
+use crate::utils::db::session::SessionDevice;
+use crate::utils::logging::MAIL_LOG_TARGET;
+use crate::utils::mail::{self, MailMessage};
+use crate::utils::{auth_token, otp, password_hasher, validators};
+
+// Sent by clients at sign-in so users can tell their sessions apart, e.g. "Jane's iPhone"
+pub const DEVICE_NAME_HEADER: &str = "Device-Name";
+
+// Longer values are cut to fit the columns they are stored in
+const MAX_DEVICE_NAME_LENGTH: usize = 255;
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+fn session_device(req: &HttpRequest) -> SessionDevice {
+    let header = |name, max_length| {
+        req.headers()
+            .get(name)
+            .and_then(|h| h.to_str().ok())
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .map(|h| h.chars().take(max_length).collect::<String>())
+    };
+
+    // Taken from the Forwarded or X-Forwarded-For header when the server is behind a proxy. The
+    // address is shown to the user, never trusted.
+    let ip_address = req.connection_info().realip_remote_addr().map(|addr| {
+        addr.parse::<SocketAddr>()
+            .map(|socket_addr| socket_addr.ip().to_string())
+            .unwrap_or_else(|_| addr.to_string())
+    });
+
+    SessionDevice {
+        device_name: header(DEVICE_NAME_HEADER, MAX_DEVICE_NAME_LENGTH),
+        user_agent: header(USER_AGENT.as_str(), MAX_USER_AGENT_LENGTH),
+        ip_address,
+    }
+}
 
 pub async fn sign_in(
     db_thread_pool: web::Data<DbThreadPool>,
@@ -39,38 +79,65 @@ pub async fn sign_in(
         Err(_) => return Err(ServerError::UserUnauthorized(Some(INVALID_CREDENTIALS_MSG))),
     };
 
+    let db_thread_pool_copy = db_thread_pool.clone();
+
     let attempts = match web::block(move || {
-        let db_connection = db_thread_pool
+        let db_connection = db_thread_pool_copy
             .get()
             .expect("Failed to access database thread pool");
-        db::auth::get_and_increment_password_attempt_count(&db_connection, user.id)
+        ATTEMPT_COUNTER.get_and_increment(&db_connection, AttemptKind::Password, user.id)
     })
     .await?
     {
         Ok(a) => a,
         Err(e) => {
-            error!("{}", e);
-            return Err(ServerError::DatabaseTransactionError(Some(
+            return Err(ServerError::from_database_error(
+                e,
                 "Failed to check password attempt count",
-            )));
+            ))
         }
     };
 
     if attempts > env::CONF.security.password_max_attempts {
-        return Err(ServerError::AccessForbidden(Some(
+        return Err(ServerError::TooManyRequests(Some(
             "Too many login attempts. Try again in a few minutes.",
         )));
     }
 
-    let does_password_match_hash =
-        web::block(move || password_hasher::verify_hash(&password, &user.password_hash)).await?;
+    let user_id = user.id;
+    let password_hash = user.password_hash;
+
+    let does_password_match_hash = web::block(move || {
+        let does_password_match_hash = password_hasher::verify_hash(&password, &password_hash);
+
+        // The plaintext password is only available here, so hashes created under older hashing
+        // settings get upgraded on sign-in. Failing to do so shouldn't block the sign-in.
+        if does_password_match_hash {
+            let db_connection = db_thread_pool
+                .get()
+                .expect("Failed to access database thread pool");
+
+            if let Err(e) = ATTEMPT_COUNTER.refund_password_attempt(&db_connection, user_id) {
+                error!("Failed to refund password attempt: {}", e);
+            }
+
+            if let Err(e) = db::user::rehash_password_if_needed(
+                &db_connection,
+                user_id,
+                &password,
+                &password_hash,
+            ) {
+                error!("Failed to rehash password: {}", e);
+            }
+        }
+
+        does_password_match_hash
+    })
+    .await?;
 
     if does_password_match_hash {
-        let signin_token = auth_token::generate_signin_token(auth_token::TokenParams {
-            user_id: &user.id,
-            user_email: &user.email,
-            user_currency: &user.currency,
-        });
+        let signin_token =
+            auth_token::generate_signin_token(auth_token::TokenParams { user_id: &user.id });
 
         let signin_token = match signin_token {
             Ok(signin_token) => signin_token,
@@ -97,7 +164,7 @@ pub async fn sign_in(
         // OTP_LIFETIME_SECS * 2. A user's code will be valid for a maximum of OTP_LIFETIME_SECS * 2.
         let otp = match otp::generate_otp(
             user.id,
-            current_time + env::CONF.lifetimes.otp_lifetime_mins * 60,
+            current_time + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60,
         ) {
             Ok(p) => p,
             Err(e) => {
@@ -107,7 +174,7 @@ pub async fn sign_in(
         };
 
         // TODO: Don't log this, email it!
-        println!("\n\nOTP: {}\n\n", &otp);
+        info!(target: MAIL_LOG_TARGET, "OTP: {}", &otp);
 
         Ok(HttpResponse::Ok().json(signin_token))
     } else {
@@ -116,45 +183,36 @@ pub async fn sign_in(
 }
 
 pub async fn verify_otp_for_signin(
+    req: HttpRequest,
     db_thread_pool: web::Data<DbThreadPool>,
     otp_and_token: web::Json<SigninTokenOtpPair>,
 ) -> Result<HttpResponse, ServerError> {
+    let device = session_device(&req);
+
     let token_claims =
         match web::block(move || auth_token::validate_signin_token(&otp_and_token.0.signin_token))
             .await?
         {
             Ok(t) => t,
-            Err(e) => match e {
-                auth_token::TokenError::TokenInvalid => {
-                    return Err(ServerError::UserUnauthorized(Some("Token is invalid")))
-                }
-                auth_token::TokenError::TokenExpired => {
-                    return Err(ServerError::UserUnauthorized(Some("Token has expired")))
-                }
-                auth_token::TokenError::WrongTokenType => {
-                    return Err(ServerError::UserUnauthorized(Some("Incorrect token type")))
-                }
-                e => {
-                    error!("{}", e);
-                    return Err(ServerError::InternalError(Some("Error verifying token")));
-                }
-            },
+            Err(e) => return Err(e.into()),
         };
 
+    let db_thread_pool_copy = db_thread_pool.clone();
+
     let attempts = match web::block(move || {
-        let db_connection = db_thread_pool
+        let db_connection = db_thread_pool_copy
             .get()
             .expect("Failed to access database thread pool");
-        db::auth::get_and_increment_otp_verification_count(&db_connection, token_claims.uid)
+        ATTEMPT_COUNTER.get_and_increment(&db_connection, AttemptKind::Otp, token_claims.uid)
     })
     .await?
     {
         Ok(a) => a,
         Err(e) => {
-            error!("{}", e);
-            return Err(ServerError::DatabaseTransactionError(Some(
+            return Err(ServerError::from_database_error(
+                e,
                 "Failed to check OTP attempt count",
-            )));
+            ))
         }
     };
 
@@ -172,14 +230,14 @@ pub async fn verify_otp_for_signin(
             .expect("Failed to fetch system time")
             .as_secs();
 
-        let mut is_valid = otp::verify_otp(otp, token_claims.uid, current_time)?;
+        let mut is_valid = otp::verify_otp(&otp, token_claims.uid, current_time)?;
 
         // A future code gets sent to the user, so check a current and future code
         if !is_valid {
             is_valid = otp::verify_otp(
-                otp,
+                &otp,
                 token_claims.uid,
-                current_time + env::CONF.lifetimes.otp_lifetime_mins * 60,
+                current_time + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60,
             )?;
         }
 
@@ -207,11 +265,36 @@ pub async fn verify_otp_for_signin(
     if !is_valid {
         return Err(ServerError::UserUnauthorized(Some("Incorrect passcode")));
     }
-    let token_pair = auth_token::generate_token_pair(auth_token::TokenParams {
-        user_id: &token_claims.uid,
-        user_email: &token_claims.eml,
-        user_currency: &token_claims.cur,
-    });
+
+    let session = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::session::create_session(
+            &db_connection,
+            token_claims.uid,
+            auth_token::token_lifetime_secs(TokenType::Refresh),
+            &device,
+        )
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to start session",
+            ))
+        }
+    };
+
+    let token_pair = auth_token::generate_token_pair(
+        auth_token::TokenParams {
+            user_id: &token_claims.uid,
+        },
+        &session,
+    );
 
     let token_pair = match token_pair {
         Ok(token_pair) => token_pair,
@@ -232,68 +315,54 @@ pub async fn verify_otp_for_signin(
 }
 
 pub async fn refresh_tokens(
+    req: HttpRequest,
     db_thread_pool: web::Data<DbThreadPool>,
     token: web::Json<RefreshToken>,
 ) -> Result<HttpResponse, ServerError> {
-    let db_thread_pool_pointer_copy = db_thread_pool.clone();
-    let refresh_token = token.0.token.clone();
+    let device = session_device(&req);
 
-    let claims = match web::block(move || {
+    let (claims, session) = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
 
-        auth_token::validate_refresh_token(token.0.token.as_str(), &db_connection)
-    })
-    .await?
-    {
-        Ok(c) => c,
-        Err(e) => match e {
-            auth_token::TokenError::TokenInvalid => {
-                return Err(ServerError::UserUnauthorized(Some("Token is invalid")));
-            }
-            auth_token::TokenError::TokenBlacklisted => {
-                return Err(ServerError::UserUnauthorized(Some(
-                    "Token has been blacklisted",
-                )));
-            }
-            auth_token::TokenError::TokenExpired => {
-                return Err(ServerError::UserUnauthorized(Some("Token has expired")));
-            }
-            auth_token::TokenError::WrongTokenType => {
-                return Err(ServerError::UserUnauthorized(Some("Incorrect token type")));
+        let claims = auth_token::validate_refresh_token(token.0.token.as_str(), &db_connection)?;
+        let lifetime_secs = auth_token::token_lifetime_secs(TokenType::Refresh);
+
+        let session = match (claims.sid, claims.ver) {
+            (Some(session_id), Some(version)) => {
+                match db::session::rotate_session(
+                    &db_connection,
+                    session_id,
+                    version,
+                    lifetime_secs,
+                )? {
+                    Some(s) => s,
+                    // Another request exchanged the same refresh token first
+                    None => return Err(TokenError::TokenBlacklisted),
+                }
             }
-            e => {
-                error!("{}", e);
-                return Err(ServerError::InternalError(Some("Error verifying token")));
+            // Tokens issued before sessions existed are blacklisted and moved onto a new session
+            _ => {
+                auth_token::blacklist_token(token.0.token.as_str(), &db_connection)?;
+                db::session::create_session(&db_connection, claims.uid, lifetime_secs, &device)?
             }
-        },
-    };
+        };
 
-    match web::block(move || {
-        auth_token::blacklist_token(
-            refresh_token.as_str(),
-            &db_thread_pool_pointer_copy
-                .get()
-                .expect("Failed to access database thread pool"),
-        )
+        Ok((claims, session))
     })
     .await?
     {
-        Ok(_) => {}
-        Err(e) => {
-            error!("{}", e);
-            return Err(ServerError::DatabaseTransactionError(Some(
-                "Failed to blacklist token",
-            )));
-        }
-    }
+        Ok(c) => c,
+        Err(e) => return Err(e.into()),
+    };
 
-    let token_pair = auth_token::generate_token_pair(auth_token::TokenParams {
-        user_id: &claims.uid,
-        user_email: &claims.eml,
-        user_currency: &claims.cur,
-    });
+    let token_pair = auth_token::generate_token_pair(
+        auth_token::TokenParams {
+            user_id: &claims.uid,
+        },
+        &session,
+    );
 
     let token_pair = match token_pair {
         Ok(token_pair) => token_pair,
@@ -331,26 +400,7 @@ pub async fn logout(
     .await?
     {
         Ok(tc) => tc,
-        Err(e) => match e {
-            auth_token::TokenError::TokenInvalid => {
-                return Err(ServerError::UserUnauthorized(Some("Token is invalid")))
-            }
-            auth_token::TokenError::TokenBlacklisted => {
-                return Err(ServerError::UserUnauthorized(Some(
-                    "Token has been blacklisted",
-                )))
-            }
-            auth_token::TokenError::TokenExpired => {
-                return Err(ServerError::UserUnauthorized(Some("Token has expired")))
-            }
-            auth_token::TokenError::WrongTokenType => {
-                return Err(ServerError::UserUnauthorized(Some("Incorrect token type")))
-            }
-            e => {
-                error!("{}", e);
-                return Err(ServerError::InternalError(Some("Error verifying token")));
-            }
-        },
+        Err(e) => return Err(e.into()),
     };
 
     if refresh_token_claims.uid != auth_user_claims.0.uid {
@@ -360,25 +410,273 @@ pub async fn logout(
     }
 
     match web::block(move || {
-        auth_token::blacklist_token(
-            refresh_token.0.token.as_str(),
-            &db_thread_pool_pointer_copy
-                .get()
-                .expect("Failed to access database thread pool"),
-        )
+        let db_connection = db_thread_pool_pointer_copy
+            .get()
+            .expect("Failed to access database thread pool");
+
+        match refresh_token_claims.sid {
+            Some(session_id) => {
+                db::session::revoke_session(&db_connection, session_id)?;
+            }
+            None => {
+                auth_token::blacklist_token(refresh_token.0.token.as_str(), &db_connection)?;
+            }
+        }
+
+        Ok::<_, TokenError>(())
     })
-    .await
+    .await?
     {
         Ok(_) => Ok(HttpResponse::Ok().finish()),
         Err(e) => {
             error!("{}", e);
-            Err(ServerError::InternalError(Some(
-                "Failed to blacklist token",
+            Err(ServerError::DatabaseTransactionError(Some(
+                "Failed to end session",
             )))
         }
     }
 }
 
+// Always responds the same way whether or not an account exists for the email so the endpoint
+// can't be used to find out who has an account
+pub async fn forgot_password(
+    db_thread_pool: web::Data<DbThreadPool>,
+    email: web::Json<InputEmail>,
+) -> Result<HttpResponse, ServerError> {
+    if !email.validate_email_address().is_valid() {
+        return Err(ServerError::InvalidFormat(Some("Invalid email address")));
+    }
+
+    let db_thread_pool_copy = db_thread_pool.clone();
+
+    let user = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::user::get_user_by_email(&db_connection, &email.email)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(diesel::result::Error::NotFound) => return Ok(HttpResponse::Ok().finish()),
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to look up user",
+            ))
+        }
+    };
+
+    let user_id = user.id;
+    let db_thread_pool_copy = db_thread_pool.clone();
+
+    let attempts = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
+        ATTEMPT_COUNTER.get_and_increment(&db_connection, AttemptKind::PasswordReset, user_id)
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to check password reset attempt count",
+            ))
+        }
+    };
+
+    // Extra requests are dropped without saying so, since a different response would give away
+    // that the account exists
+    if attempts > env::CONF.security.password_reset_max_requests {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let lifetime_secs = auth_token::token_lifetime_secs(TokenType::PasswordReset);
+
+    let session = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::session::create_password_reset_session(&db_connection, user_id, lifetime_secs)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to generate password reset token",
+            ))
+        }
+    };
+
+    // The token must never be logged, since anyone who can read the logs could use it to take over
+    // the account
+    let reset_token = match auth_token::generate_password_reset_token(
+        auth_token::TokenParams {
+            user_id: &session.user_id,
+        },
+        &session,
+    ) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Err(ServerError::InternalError(Some(
+                "Failed to generate password reset token",
+            )));
+        }
+    };
+
+    let message = MailMessage {
+        to: user.email,
+        subject: String::from("Reset your password"),
+        body: format!(
+            "Someone asked to reset the password for your {} account. If it was you, use this \
+             code to choose a new password within {} minutes:\n\n{}\n\nIf it wasn't you, you \
+             can ignore this email.",
+            *env::APP_NAME,
+            lifetime_secs / 60,
+            reset_token,
+        ),
+    };
+
+    if let Err(e) = mail::MAILER.send(message).await {
+        error!("{}", e);
+        return Err(ServerError::InternalError(Some(
+            "Failed to send password reset email",
+        )));
+    }
+
+    info!("Password reset issued for user {}", session.user_id);
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn reset_password(
+    db_thread_pool: web::Data<DbThreadPool>,
+    password_reset: web::Json<InputPasswordReset>,
+) -> Result<HttpResponse, ServerError> {
+    let db_thread_pool_copy = db_thread_pool.clone();
+    let reset_token = password_reset.reset_token.clone();
+
+    let (claims, user) = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let claims = auth_token::validate_password_reset_token(&reset_token, &db_connection)?;
+        let user = db::user::get_user_by_id(&db_connection, claims.uid)?;
+
+        Ok::<_, TokenError>((claims, user))
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => return Err(e.into()),
+    };
+
+    let new_password_validity = validators::validate_strong_password(
+        &password_reset.new_password,
+        &user.email,
+        &user.first_name,
+        &user.last_name,
+        &user.date_of_birth,
+    );
+
+    if let validators::Validity::Invalid(msg) = new_password_validity {
+        return Err(ServerError::InputRejected(Some(msg)));
+    };
+
+    let token_expiration = match i64::try_from(claims.exp) {
+        Ok(exp) => exp,
+        Err(_) => return Err(TokenError::TokenInvalid.into()),
+    };
+
+    let was_reset = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::user::reset_password(
+            &db_connection,
+            claims.uid,
+            &password_reset.new_password,
+            claims.sid,
+            &password_reset.reset_token,
+            token_expiration,
+        )
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to reset password",
+            ))
+        }
+    };
+
+    // Another request used the token first
+    if !was_reset {
+        return Err(TokenError::TokenBlacklisted.into());
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Lets other backend services check a token without holding the signing keys or reading the
+// blacklist
+pub async fn introspect(
+    db_thread_pool: web::Data<DbThreadPool>,
+    _internal_service: middleware::internal_service::InternalService,
+    token: web::Json<InputToken>,
+) -> Result<HttpResponse, ServerError> {
+    let validation_result = web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        auth_token::validate_token_of_any_type(&token.token, &db_connection)
+    })
+    .await?;
+
+    let introspection = match validation_result {
+        Ok((claims, token_type)) => OutputTokenIntrospection {
+            active: true,
+            user_id: Some(claims.uid),
+            token_type: Some(String::from(match token_type {
+                TokenType::Access => "access",
+                TokenType::Refresh => "refresh",
+                TokenType::SignIn => "signin",
+                TokenType::Upload => "upload",
+                TokenType::PasswordReset => "password_reset",
+            })),
+            exp: Some(claims.exp),
+            key_id: Some(claims.kid),
+            scope: claims.scp,
+        },
+        Err(e @ TokenError::DatabaseError(_))
+        | Err(e @ TokenError::SystemResourceAccessFailure) => {
+            return Err(e.into());
+        }
+        Err(_) => OutputTokenIntrospection {
+            active: false,
+            user_id: None,
+            token_type: None,
+            exp: None,
+            key_id: None,
+            scope: None,
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(introspection))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -390,6 +688,7 @@ mod tests {
 
     use crate::env;
     use crate::handlers::request_io::{InputUser, RefreshToken, SigninToken, SigninTokenOtpPair};
+    use crate::middleware::internal_service::INTERNAL_SERVICE_KEY_HEADER;
     use crate::services;
     use crate::utils::auth_token::TokenClaims;
     use crate::utils::otp;
@@ -544,11 +843,95 @@ mod tests {
         .await;
 
         let credentials = CredentialPair {
-            email: new_user.email,
+            email: new_user.email.clone(),
             password: new_user.password,
         };
 
+        let wrong_credentials = CredentialPair {
+            email: new_user.email,
+            password: String::from("Not the password"),
+        };
+
         for _ in 0..env::CONF.security.password_max_attempts {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/sign_in")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&wrong_credentials).unwrap())
+                .to_request();
+
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+        }
+
+        // Even the correct password is rejected once the limit is reached
+        let req = test::TestRequest::post()
+            .uri("/api/auth/sign_in")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&credentials).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[actix_rt::test]
+    async fn test_successful_sign_ins_dont_count_toward_lockout() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let credentials = CredentialPair {
+            email: new_user.email.clone(),
+            password: new_user.password,
+        };
+
+        let wrong_credentials = CredentialPair {
+            email: new_user.email,
+            password: String::from("Not the password"),
+        };
+
+        // One short of the limit
+        for _ in 1..env::CONF.security.password_max_attempts {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/sign_in")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&wrong_credentials).unwrap())
+                .to_request();
+
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+        }
+
+        for _ in 0..=env::CONF.security.password_max_attempts {
             let req = test::TestRequest::post()
                 .uri("/api/auth/sign_in")
                 .insert_header(("content-type", "application/json"))
@@ -559,6 +942,15 @@ mod tests {
             assert_eq!(res.status(), http::StatusCode::OK);
         }
 
+        let req = test::TestRequest::post()
+            .uri("/api/auth/sign_in")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&wrong_credentials).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
         let req = test::TestRequest::post()
             .uri("/api/auth/sign_in")
             .insert_header(("content-type", "application/json"))
@@ -566,7 +958,7 @@ mod tests {
             .to_request();
 
         let res = test::call_service(&app, req).await;
-        assert_eq!(res.status(), http::StatusCode::FORBIDDEN);
+        assert_eq!(res.status(), http::StatusCode::TOO_MANY_REQUESTS);
     }
 
     #[actix_rt::test]
@@ -726,7 +1118,7 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs()
-            + env::CONF.lifetimes.otp_lifetime_mins * 60;
+            + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60;
 
         let otp = otp::generate_otp(user_id, future_time).unwrap();
 
@@ -826,7 +1218,7 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs()
-            + env::CONF.lifetimes.otp_lifetime_mins * 60;
+            + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60;
 
         let otp = otp::generate_otp(user_id, future_time).unwrap();
 
@@ -980,7 +1372,7 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs()
-            - env::CONF.lifetimes.otp_lifetime_mins * 60;
+            - env::reloadable_conf().lifetimes.otp_lifetime_mins * 60;
 
         let otp = otp::generate_otp(user_id, past_time).unwrap();
 
@@ -1057,7 +1449,7 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs()
-            + (2 * env::CONF.lifetimes.otp_lifetime_mins * 60);
+            + (2 * env::reloadable_conf().lifetimes.otp_lifetime_mins * 60);
 
         let otp = otp::generate_otp(user_id, far_future_time).unwrap();
 
@@ -1235,7 +1627,10 @@ mod tests {
         assert!(!access_token.is_empty());
         assert!(!refresh_token.is_empty());
 
-        assert!(auth_token::is_on_blacklist(&refresh_token_payload.token, &db_connection).unwrap());
+        assert!(
+            auth_token::validate_refresh_token(&refresh_token_payload.token, &db_connection)
+                .is_err()
+        );
         assert_eq!(
             auth_token::validate_access_token(&access_token)
                 .unwrap()
@@ -1248,6 +1643,16 @@ mod tests {
                 .uid,
             user_id
         );
+
+        // The exchanged refresh token can't be used again
+        let req = test::TestRequest::post()
+            .uri("/api/auth/refresh_tokens")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&refresh_token_payload).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
     }
 
     #[actix_rt::test]
@@ -1476,7 +1881,7 @@ mod tests {
         assert_eq!(res.status(), http::StatusCode::OK);
 
         let db_connection = db_thread_pool.get().unwrap();
-        assert!(auth_token::is_on_blacklist(&logout_payload.token, &db_connection).unwrap());
+        assert!(auth_token::validate_refresh_token(&logout_payload.token, &db_connection).is_err());
     }
 
     #[actix_rt::test]
@@ -1557,6 +1962,301 @@ mod tests {
         assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
 
         let db_connection = db_thread_pool.get().unwrap();
-        assert!(!auth_token::is_on_blacklist(&logout_payload.token, &db_connection).unwrap());
+        assert!(
+            auth_token::validate_refresh_token(&token_pair.refresh_token, &db_connection).is_ok()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_forgot_and_reset_password() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let db_connection = db_thread_pool.get().unwrap();
+        let user = db::user::create_user(&db_connection, &web::Json(new_user.clone())).unwrap();
+
+        for (email, expected_status) in [
+            (new_user.email.clone(), http::StatusCode::OK),
+            (
+                format!("no_account{}@test.com", &user_number),
+                http::StatusCode::OK,
+            ),
+            (String::from("not an email"), http::StatusCode::BAD_REQUEST),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/forgot_password")
+                .insert_header(("content-type", "application/json"))
+                .set_json(&InputEmail { email })
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), expected_status);
+        }
+
+        let session = db::session::create_session(
+            &db_connection,
+            user.id,
+            3600,
+            &db::session::SessionDevice::default(),
+        )
+        .unwrap();
+        let token_pair = auth_token::generate_token_pair(
+            auth_token::TokenParams { user_id: &user.id },
+            &session,
+        )
+        .unwrap();
+
+        let inbox = mail::testing::read_inbox(&new_user.email);
+        assert_eq!(inbox.len(), 1);
+
+        let reset_token = inbox[0]
+            .lines()
+            .skip_while(|line| !line.ends_with("minutes:"))
+            .nth(2)
+            .map(String::from)
+            .unwrap();
+
+        // Only a reset token can reset the password
+        let req = test::TestRequest::post()
+            .uri("/api/auth/reset_password")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&InputPasswordReset {
+                reset_token: token_pair.access_token.to_string(),
+                new_password: String::from("Xk2#pLm9!vQr7&Tz4$Wd"),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/reset_password")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&InputPasswordReset {
+                reset_token: reset_token.clone(),
+                new_password: String::from("password"),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/reset_password")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&InputPasswordReset {
+                reset_token: reset_token.clone(),
+                new_password: String::from("Xk2#pLm9!vQr7&Tz4$Wd"),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        // The token can only be used once
+        let req = test::TestRequest::post()
+            .uri("/api/auth/reset_password")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&InputPasswordReset {
+                reset_token,
+                new_password: String::from("Jd8%sWq2@nBv5^Lx1*Ph"),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
+        // Refresh tokens issued before the reset no longer work
+        let req = test::TestRequest::post()
+            .uri("/api/auth/refresh_tokens")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&RefreshToken {
+                token: token_pair.refresh_token.to_string(),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
+        for (password, expected_status) in [
+            (new_user.password, http::StatusCode::UNAUTHORIZED),
+            (String::from("Xk2#pLm9!vQr7&Tz4$Wd"), http::StatusCode::OK),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/sign_in")
+                .insert_header(("content-type", "application/json"))
+                .set_json(&CredentialPair {
+                    email: new_user.email.clone(),
+                    password,
+                })
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), expected_status);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_forgot_password_request_limit() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let db_connection = db_thread_pool.get().unwrap();
+        db::user::create_user(&db_connection, &web::Json(new_user.clone())).unwrap();
+
+        let max_requests = env::CONF.security.password_reset_max_requests;
+
+        // Requests over the limit look the same to the client but send nothing
+        for _ in 0..(max_requests + 2) {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/forgot_password")
+                .insert_header(("content-type", "application/json"))
+                .set_json(&InputEmail {
+                    email: new_user.email.clone(),
+                })
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), http::StatusCode::OK);
+        }
+
+        assert_eq!(
+            mail::testing::read_inbox(&new_user.email).len(),
+            usize::try_from(max_requests).unwrap()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_introspect() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let db_connection = db_thread_pool.get().unwrap();
+        let user = db::user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        let session = db::session::create_session(
+            &db_connection,
+            user.id,
+            3600,
+            &db::session::SessionDevice::default(),
+        )
+        .unwrap();
+        let token_pair = auth_token::generate_token_pair(
+            auth_token::TokenParams { user_id: &user.id },
+            &session,
+        )
+        .unwrap();
+        let access_token = token_pair.access_token.to_string();
+        let refresh_token = token_pair.refresh_token.to_string();
+
+        let service_key = env::CONF.keys.internal_service_keys[0].as_str();
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/introspect")
+            .set_json(&InputToken {
+                token: access_token.clone(),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/introspect")
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+            .set_json(&InputToken {
+                token: access_token,
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let introspection = test::read_body_json::<OutputTokenIntrospection, _>(res).await;
+        assert!(introspection.active);
+        assert_eq!(introspection.user_id, Some(user.id));
+        assert_eq!(introspection.token_type.as_deref(), Some("access"));
+        assert_eq!(
+            introspection.key_id.as_deref(),
+            Some(env::CONF.keys.token_signing_key_id.as_str())
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/introspect")
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+            .set_json(&InputToken {
+                token: refresh_token.clone(),
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let introspection = test::read_body_json::<OutputTokenIntrospection, _>(res).await;
+        assert!(introspection.active);
+        assert_eq!(introspection.token_type.as_deref(), Some("refresh"));
+
+        db::session::revoke_session(&db_connection, session.id).unwrap();
+
+        for token in [refresh_token, String::from("not a token")] {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/introspect")
+                .insert_header((INTERNAL_SERVICE_KEY_HEADER, service_key))
+                .set_json(&InputToken { token })
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), http::StatusCode::OK);
+
+            let introspection = test::read_body_json::<OutputTokenIntrospection, _>(res).await;
+            assert!(!introspection.active);
+            assert!(introspection.user_id.is_none());
+        }
     }
 }
@@ -0,0 +1,458 @@
+use actix_web::{web, HttpResponse};
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::budget::{ensure_user_has_budget_role, validate_entry_components};
+use crate::handlers::error::ServerError;
+use crate::handlers::inbox::validate_inbox_entry;
+use crate::handlers::request_io::{
+    InputEntry, InputInboxEntry, InputThresholdPercent, OutputCategoryThresholdCrossing,
+    OutputUserPublic,
+};
+use crate::middleware;
+use crate::utils::db;
+use crate::utils::db::budget::BudgetRole;
+
+// Automation platforms poll triggers and deduplicate the results by ID, so only the most recent
+// items need to be returned
+const TRIGGER_RESULT_LIMIT: i64 = 50;
+
+pub async fn me(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+) -> Result<HttpResponse, ServerError> {
+    let user = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::get_user_by_id(&db_connection, api_key_user.0)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::AccessForbidden(Some("No user with ID")))
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get user data",
+                ))
+            }
+        },
+    };
+
+    let output_user = OutputUserPublic {
+        id: user.id,
+        is_premium: user.is_premium,
+        is_active: user.is_active,
+        first_name: user.first_name,
+        last_name: user.last_name,
+        currency: user.currency,
+    };
+
+    Ok(HttpResponse::Ok().json(output_user))
+}
+
+pub async fn new_entry_trigger(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+) -> Result<HttpResponse, ServerError> {
+    let recent_entries = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_recent_entries_for_user(
+            &db_connection,
+            api_key_user.0,
+            TRIGGER_RESULT_LIMIT,
+        )
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to get entries")),
+    };
+
+    Ok(HttpResponse::Ok().json(recent_entries))
+}
+
+pub async fn budget_threshold_trigger(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    threshold: web::Query<InputThresholdPercent>,
+) -> Result<HttpResponse, ServerError> {
+    let threshold_percent = threshold.threshold_percent;
+
+    if !(1..=1000).contains(&threshold_percent) {
+        return Err(ServerError::InvalidFormat(Some(
+            "Threshold must be between 1 and 1000 percent",
+        )));
+    }
+
+    let crossings = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_categories_over_threshold_for_user(
+            &db_connection,
+            api_key_user.0,
+            threshold_percent,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get budget data",
+            ))
+        }
+    };
+
+    let output_crossings = crossings
+        .into_iter()
+        .take(TRIGGER_RESULT_LIMIT as usize)
+        .map(|c| OutputCategoryThresholdCrossing {
+            id: format!("{}:{}:{}", c.budget_id, c.category_id, threshold_percent),
+            budget_id: c.budget_id,
+            budget_name: c.budget_name,
+            category_id: c.category_id,
+            category_name: c.category_name,
+            limit_cents: c.limit_cents,
+            spent_cents: c.spent_cents,
+            threshold_percent,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(output_crossings))
+}
+
+pub async fn add_entry_action(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    entry_data: web::Json<InputEntry>,
+) -> Result<HttpResponse, ServerError> {
+    validate_entry_components(
+        entry_data.amount_cents,
+        entry_data.tax_cents,
+        entry_data.tip_cents,
+    )?;
+
+    let user_id = api_key_user.0;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        entry_data.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let new_entry = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::create_entry(&db_connection, &entry_data, user_id)
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create entry",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(new_entry))
+}
+
+// For bank syncs and other sources that can't tell which budget a transaction belongs in. The user
+// assigns it to a budget from their inbox.
+pub async fn add_inbox_entry_action(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    entry_data: web::Json<InputInboxEntry>,
+) -> Result<HttpResponse, ServerError> {
+    validate_inbox_entry(&entry_data)?;
+
+    let mut new_entries = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::inbox::add_entries(&db_connection, api_key_user.0, &[entry_data.into_inner()])
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to add inbox entry",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Created().json(new_entries.remove(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::env;
+    use crate::handlers::request_io::InputCategory;
+    use crate::handlers::testing::{self, UserWithBudgetAndKey};
+    use crate::middleware::api_key::API_KEY_HEADER;
+    use crate::models::entry::Entry;
+    use crate::models::inbox_entry::InboxEntry;
+    use crate::services;
+
+    fn create_user_with_budget_and_key() -> UserWithBudgetAndKey {
+        testing::create_user_with_budget_and_key(vec![
+            InputCategory {
+                id: 0,
+                name: String::from("Groceries"),
+                limit_cents: 10000,
+                color: String::from("#ff11ee"),
+            },
+            InputCategory {
+                id: 1,
+                name: String::from("Fun"),
+                limit_cents: 5000,
+                color: String::from("#112233"),
+            },
+        ])
+    }
+
+    #[actix_rt::test]
+    async fn test_me_requires_valid_key() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_and_budget = create_user_with_budget_and_key();
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/me")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/me")
+            .insert_header((API_KEY_HEADER, "not-a-real-key"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/me")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let user = test::read_body_json::<OutputUserPublic, _>(resp).await;
+        assert_eq!(user.id, user_and_budget.user_id);
+    }
+
+    #[actix_rt::test]
+    async fn test_add_entry_action_and_new_entry_trigger() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_and_budget = create_user_with_budget_and_key();
+        let other_user_and_budget = create_user_with_budget_and_key();
+
+        let entry = InputEntry {
+            id: None,
+            budget_id: user_and_budget.budget.id,
+            amount_cents: 2500,
+            date: chrono::Utc::now().naive_utc().date(),
+            name: Some(String::from("Added from Zapier")),
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/automation/actions/add_entry")
+            .insert_header((API_KEY_HEADER, other_user_and_budget.key.as_str()))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        // A viewer's key can't add entries either
+        let viewer = create_user_with_budget_and_key();
+        db::budget::add_user(
+            &env::testing::DB_THREAD_POOL.get().unwrap(),
+            user_and_budget.budget.id,
+            viewer.user_id,
+            BudgetRole::Viewer,
+        )
+        .unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/api/automation/actions/add_entry")
+            .insert_header((API_KEY_HEADER, viewer.key.as_str()))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/automation/actions/add_entry")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let created_entry = test::read_body_json::<Entry, _>(resp).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/triggers/new_entry")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let recent_entries = test::read_body_json::<Vec<Entry>, _>(resp).await;
+        assert_eq!(recent_entries.len(), 1);
+        assert_eq!(recent_entries[0].id, created_entry.id);
+        assert_eq!(recent_entries[0].amount_cents, 2500);
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/triggers/new_entry")
+            .insert_header((API_KEY_HEADER, other_user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let recent_entries = test::read_body_json::<Vec<Entry>, _>(resp).await;
+        assert!(recent_entries.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_budget_threshold_trigger() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_and_budget = create_user_with_budget_and_key();
+        let db_connection = db_thread_pool.get().unwrap();
+
+        for amount_cents in [4000, 4500] {
+            let entry = InputEntry {
+                id: None,
+                budget_id: user_and_budget.budget.id,
+                amount_cents,
+                date: chrono::Utc::now().naive_utc().date(),
+                name: None,
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            db::budget::create_entry(&db_connection, &web::Json(entry), user_and_budget.user_id)
+                .unwrap();
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/triggers/budget_threshold?threshold_percent=80")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let crossings = test::read_body_json::<Vec<OutputCategoryThresholdCrossing>, _>(resp).await;
+        assert_eq!(crossings.len(), 1);
+        assert_eq!(crossings[0].budget_id, user_and_budget.budget.id);
+        assert_eq!(crossings[0].category_id, 0);
+        assert_eq!(crossings[0].spent_cents, 8500);
+        assert_eq!(crossings[0].limit_cents, 10000);
+        assert_eq!(
+            crossings[0].id,
+            format!("{}:0:80", user_and_budget.budget.id)
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/triggers/budget_threshold?threshold_percent=90")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let crossings = test::read_body_json::<Vec<OutputCategoryThresholdCrossing>, _>(resp).await;
+        assert!(crossings.is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/api/automation/triggers/budget_threshold?threshold_percent=0")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_add_inbox_entry_action() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_and_budget = create_user_with_budget_and_key();
+
+        let req = test::TestRequest::post()
+            .uri("/api/automation/actions/add_inbox_entry")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .set_json(&InputInboxEntry {
+                amount_cents: 1899,
+                date: chrono::Utc::now().naive_utc().date(),
+                name: Some(String::from("Synced from bank")),
+                note: None,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let inbox_entry = test::read_body_json::<InboxEntry, _>(resp).await;
+        assert_eq!(inbox_entry.user_id, user_and_budget.user_id);
+        assert_eq!(inbox_entry.amount_cents, 1899);
+
+        let db_connection = db_thread_pool.get().unwrap();
+        assert_eq!(
+            db::inbox::count_entries(&db_connection, user_and_budget.user_id).unwrap(),
+            1
+        );
+    }
+}
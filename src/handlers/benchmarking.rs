@@ -0,0 +1,230 @@
+use actix_web::{web, HttpResponse};
+use log::error;
+use std::collections::HashMap;
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{
+    InputBenchmarkingProfile, OutputBenchmarkComparison, OutputCategoryBenchmark,
+};
+use crate::middleware;
+use crate::utils::db;
+use crate::utils::{benchmarking, validators};
+
+pub async fn opt_in(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    profile_data: web::Json<InputBenchmarkingProfile>,
+) -> Result<HttpResponse, ServerError> {
+    if let validators::Validity::Invalid(msg) =
+        benchmarking::validate_country_code(&profile_data.country_code)
+    {
+        return Err(ServerError::InvalidFormat(Some(msg)));
+    }
+
+    if let validators::Validity::Invalid(msg) =
+        benchmarking::validate_household_size(profile_data.household_size)
+    {
+        return Err(ServerError::InvalidFormat(Some(msg)));
+    }
+
+    let profile = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::benchmarking::set_profile(
+            &db_connection,
+            auth_user_claims.0.uid,
+            &profile_data.country_code,
+            profile_data.household_size,
+        )
+    })
+    .await?
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to save benchmarking profile",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(profile))
+}
+
+pub async fn opt_out(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::benchmarking::delete_profile(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    .map(|_| HttpResponse::Ok().finish())
+    .map_err(|e| {
+        error!("{}", e);
+        ServerError::DatabaseTransactionError(Some("Failed to delete benchmarking profile"))
+    })
+}
+
+pub async fn compare(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+    let (period_start, period_end) =
+        benchmarking::previous_month_range(chrono::Utc::now().naive_utc().date());
+
+    let (profile, user_spending, cohort_stats) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let profile = db::benchmarking::get_profile(&db_connection, user_id)?;
+        let user_spending = db::benchmarking::get_user_spending_by_category(
+            &db_connection,
+            user_id,
+            period_start,
+            period_end,
+        )?;
+        let cohort_stats = db::benchmarking::get_cohort_stats(
+            &db_connection,
+            &profile.country_code,
+            profile.household_size,
+            period_start,
+        )?;
+
+        Ok((profile, user_spending, cohort_stats))
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::AccessForbidden(Some(
+                    "User has not opted in to benchmarking",
+                )))
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get benchmarking data",
+                ))
+            }
+        },
+    };
+
+    let user_spending = user_spending
+        .into_iter()
+        .map(|s| (s.category_name, s.spent_cents))
+        .collect::<HashMap<_, _>>();
+
+    // Only categories with a large enough cohort have stats, so nothing is reported for the rest
+    let categories = cohort_stats
+        .into_iter()
+        .map(|stat| OutputCategoryBenchmark {
+            user_spent_cents: user_spending.get(&stat.category_name).copied().unwrap_or(0),
+            category_name: stat.category_name,
+            cohort_user_count: stat.user_count,
+            cohort_p25_cents: stat.p25_cents,
+            cohort_median_cents: stat.median_cents,
+            cohort_p75_cents: stat.p75_cents,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(OutputBenchmarkComparison {
+        country_code: profile.country_code,
+        household_size: profile.household_size,
+        period_start,
+        period_end,
+        categories,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::env;
+    use crate::handlers::testing::create_user_with_access_token;
+    use crate::models::benchmarking_profile::BenchmarkingProfile;
+    use crate::services;
+
+    #[actix_rt::test]
+    async fn test_opt_in_compare_and_opt_out() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let access_token = create_user_with_access_token("USD");
+
+        let req = test::TestRequest::get()
+            .uri("/api/benchmarking/compare")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/benchmarking/opt_in")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBenchmarkingProfile {
+                country_code: String::from("usa"),
+                household_size: 2,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/benchmarking/opt_in")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBenchmarkingProfile {
+                country_code: String::from("NZ"),
+                household_size: 2,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let profile = test::read_body_json::<BenchmarkingProfile, _>(resp).await;
+        assert_eq!(profile.country_code, "NZ");
+
+        let req = test::TestRequest::get()
+            .uri("/api/benchmarking/compare")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let comparison = test::read_body_json::<OutputBenchmarkComparison, _>(resp).await;
+        assert_eq!(comparison.country_code, "NZ");
+        assert_eq!(comparison.household_size, 2);
+        assert!(comparison.period_start < comparison.period_end);
+
+        let req = test::TestRequest::post()
+            .uri("/api/benchmarking/opt_out")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/benchmarking/compare")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+    }
+}
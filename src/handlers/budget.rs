@@ -1,20 +1,164 @@
-use actix_web::{web, HttpResponse};
+use actix_multipart::Multipart;
+use actix_web::body::BoxBody;
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse, ResponseError};
+use chrono::{Datelike, NaiveDate};
+use futures::{stream, TryStreamExt};
 use log::error;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
 use uuid::Uuid;
 
 use crate::definitions::DbThreadPool;
+use crate::env;
 use crate::handlers::error::ServerError;
 use crate::handlers::request_io::{
-    InputBudget, InputBudgetId, InputBudgetShareEventId, InputDateRange, InputEditBudget,
-    InputEntry, OutputBudget, UserInvitationToBudget,
+    InputArchiveFilter, InputBudget, InputBudgetComment, InputBudgetCommentId, InputBudgetId,
+    InputBudgetMember, InputBudgetMemberRole, InputBudgetMonth, InputBudgetResource,
+    InputBudgetResourceId, InputBudgetShareEventId, InputBudgetSimulation, InputBulkEntryDeletion,
+    InputCategoryHardCap, InputCategoryId, InputCategoryImport, InputColumnMapping,
+    InputCompleteShoppingList, InputCurrencyConversion, InputDateRange, InputEditBudget,
+    InputEditBudgetComment, InputEditCategory, InputEditEntry, InputEditRecurringEntry,
+    InputEditShoppingListItem, InputEntry, InputEntryAttachmentId, InputEntryAttachments,
+    InputEntryFilter, InputEntryId, InputEntryImport, InputFundAllocation, InputHardCapOverride,
+    InputImportBatchId, InputNewCategory, InputPagination, InputRecurringEntry,
+    InputRecurringEntryId, InputReimbursement, InputReimbursementPayment, InputShoppingList,
+    InputShoppingListId, InputShoppingListItem, InputShoppingListItemId, InputSimulatedChange,
+    InputUnusualAmountConfirmation, OutputBudgetPage, OutputBulkDeletion,
+    OutputBulkDeletionPreview, OutputCategoryExport, OutputCreatedEntry, OutputEnvelopeSummary,
+    OutputExportedCategory, OutputInvitation, OutputInvitationBudget, OutputSkippedRow,
+    OutputStatementImport, OutputTrash, UploadToken, UserInvitationToBudget,
 };
 use crate::middleware;
+use crate::models::budget_share_event::BudgetShareEventWithSharer;
+use crate::models::category::Category;
+use crate::models::entry::Entry;
+use crate::models::entry_attachment::NewEntryAttachment;
+use crate::utils::auth_token::{self, UploadScope};
+use crate::utils::confirmation_token;
 use crate::utils::db;
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::db::budget_resource::BudgetResourceKind;
+use crate::utils::db::daily_action::DailyAction;
+use crate::utils::forecasting::{self, Adjustment, BudgetForecast, ScheduledExpense};
+use crate::utils::import::{self, ImportError, StatementFormat};
+use crate::utils::live_updates::{self, BudgetEvent};
+use crate::utils::logging;
+use crate::utils::record_id;
+use crate::utils::recurrence::RecurrenceFrequency;
+use crate::utils::storage::{self, StorageError};
+use crate::utils::unusual_amount::{self, EntryWarning};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+pub const MAX_COMMENT_LENGTH: usize = 2000;
+
+pub const MAX_SIMULATED_CHANGES: usize = 20;
+
+pub const MAX_IMPORTED_CATEGORIES: usize = 100;
+
+pub const MAX_ENTRY_ATTACHMENTS: i64 = 10;
+pub const MAX_ATTACHMENT_FILE_NAME_LENGTH: usize = 255;
+
+pub const MAX_BUDGET_RESOURCES: i64 = 10;
+pub const MAX_RESOURCE_TITLE_LENGTH: usize = 120;
+pub const MAX_RESOURCE_LOCATION_LENGTH: usize = 2048;
+
+// Keeps a single import's insert well under Postgres's limit on bind parameters
+pub const MAX_IMPORTED_ENTRIES: usize = 1000;
+pub const MAX_STATEMENT_FILE_BYTES: usize = 2 * 1024 * 1024;
+
+pub const EXPORT_CHUNK_SIZE: i64 = 1000;
+
+pub const BULK_DELETION_SAMPLE_SIZE: i64 = 10;
+pub const BULK_DELETION_CONFIRMATION_LIFETIME_SECS: u64 = 600;
+
+// Returned instead of a plain error so clients can show how much of the limit is left
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryCapExceeded {
+    pub error: String,
+    pub category: i16,
+    pub limit_cents: i64,
+    pub spent_cents: i64,
+    pub remaining_cents: i64,
+    pub amount_cents: i64,
+}
+
+impl fmt::Display for CategoryCapExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Category spending cap exceeded: {} cents remaining in category {}",
+            self.remaining_cents, self.category
+        )
+    }
+}
+
+impl ResponseError for CategoryCapExceeded {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::CONFLICT
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+pub fn page_bounds(pagination: &InputPagination) -> Result<(i64, i64), ServerError> {
+    let limit = pagination.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = pagination.offset.unwrap_or(0);
+
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(ServerError::InvalidFormat(Some(
+            "Limit must be between 1 and 200",
+        )));
+    }
+
+    if offset < 0 {
+        return Err(ServerError::InvalidFormat(Some(
+            "Offset cannot be negative",
+        )));
+    }
+
+    Ok((limit, offset))
+}
+
+// Tax and tip are parts of an entry's amount, not extra on top of it. They take the amount's sign,
+// so a refund's tax is negative too, and together can't come to more than the amount.
+pub fn validate_entry_components(
+    amount_cents: i64,
+    tax_cents: Option<i64>,
+    tip_cents: Option<i64>,
+) -> Result<(), ServerError> {
+    let components = [tax_cents, tip_cents];
+    let components = components.iter().flatten();
+
+    if components
+        .clone()
+        .any(|c| *c != 0 && c.signum() != amount_cents.signum())
+    {
+        return Err(ServerError::InputRejected(Some(
+            "Tax and tip must have the same sign as the amount",
+        )));
+    }
+
+    let components_total = components.fold(0i64, |total, c| total.saturating_add(c.abs()));
+    if components_total > amount_cents.saturating_abs() {
+        return Err(ServerError::InputRejected(Some(
+            "Tax and tip cannot add up to more than the amount",
+        )));
+    }
+
+    Ok(())
+}
 
 pub async fn get(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
     budget_id: web::Json<InputBudgetId>,
+    conversion: web::Query<InputCurrencyConversion>,
 ) -> Result<HttpResponse, ServerError> {
     let budget_id_clone = budget_id.budget_id;
 
@@ -25,8 +169,10 @@ pub async fn get(
     )
     .await?;
 
-    let budget = match web::block(move || {
-        let db_connection = db_thread_pool
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let mut budget = match web::block(move || {
+        let db_connection = db_thread_pool_ref
             .get()
             .expect("Failed to access database thread pool");
         db::budget::get_budget_by_id(&db_connection, budget_id.budget_id)
@@ -43,26 +189,119 @@ pub async fn get(
                 return Err(ServerError::NotFound(Some("No budget with provided ID")));
             }
             _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
+                return Err(ServerError::from_database_error(
+                    e,
                     "Failed to get budget data",
-                )));
+                ))
             }
         },
     };
 
+    if conversion.convert_currency {
+        let (categories, entries) = convert_to_user_currency(
+            db_thread_pool,
+            auth_user_claims.0.uid,
+            budget.id,
+            budget.categories,
+            budget.entries,
+        )
+        .await?;
+
+        budget.categories = categories;
+        budget.entries = entries;
+    }
+
     Ok(HttpResponse::Ok().json(budget))
 }
 
+// Entry amounts are converted from the currency of the user who recorded them and category limits
+// from the currency of the budget's owner
+async fn convert_to_user_currency(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: Uuid,
+    budget_id: Uuid,
+    mut categories: Vec<Category>,
+    mut entries: Vec<Entry>,
+) -> Result<(Vec<Category>, Vec<Entry>), ServerError> {
+    let converted = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let rates = db::exchange_rate::get_exchange_rates(
+            &db_connection,
+            &env::CONF.currency.base_currency,
+        )?;
+        let owner_id = db::budget::get_budget_owner_id(&db_connection, budget_id)?;
+
+        let mut user_ids = entries.iter().map(|e| e.user_id).collect::<Vec<_>>();
+        user_ids.extend([user_id, owner_id]);
+        user_ids.sort_unstable();
+        user_ids.dedup();
+
+        let currencies = db::user::get_currencies_for_users(&db_connection, &user_ids)?;
+        let convert = |amount: i64, from_user_id: Uuid| {
+            rates.convert(
+                amount,
+                currencies.get(&from_user_id)?,
+                currencies.get(&user_id)?,
+            )
+        };
+
+        for category in categories.iter_mut() {
+            category.limit_cents = match convert(category.limit_cents, owner_id) {
+                Some(a) => a,
+                None => return Ok(None),
+            };
+        }
+
+        for entry in entries.iter_mut() {
+            entry.amount_cents = match convert(entry.amount_cents, entry.user_id) {
+                Some(a) => a,
+                None => return Ok(None),
+            };
+        }
+
+        Ok::<_, diesel::result::Error>(Some((categories, entries)))
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to convert amounts",
+            ))
+        }
+    };
+
+    match converted {
+        Some(c) => Ok(c),
+        None => Err(ServerError::InputRejected(Some(
+            "No exchange rate is available for a currency used in this budget",
+        ))),
+    }
+}
+
 pub async fn get_all(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    pagination: web::Query<InputPagination>,
+    archive_filter: web::Query<InputArchiveFilter>,
 ) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
     let budgets = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-        db::budget::get_all_budgets_for_user(&db_connection, auth_user_claims.0.uid)
+        db::budget::get_all_budgets_for_user(
+            &db_connection,
+            auth_user_claims.0.uid,
+            archive_filter.include_archived,
+            limit,
+            offset,
+        )
     })
     .await?
     {
@@ -73,13 +312,16 @@ pub async fn get_all(
                 return Err(ServerError::InvalidFormat(None));
             }
             diesel::result::Error::NotFound => {
-                return Ok(HttpResponse::Ok().json(Vec::<OutputBudget>::new()));
+                return Ok(HttpResponse::Ok().json(OutputBudgetPage {
+                    budgets: Vec::new(),
+                    has_more: false,
+                }));
             }
             _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
+                return Err(ServerError::from_database_error(
+                    e,
                     "Failed to get budget data",
-                )));
+                ))
             }
         },
     };
@@ -91,7 +333,11 @@ pub async fn get_all_between_dates(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
     date_range: web::Json<InputDateRange>,
+    pagination: web::Query<InputPagination>,
+    archive_filter: web::Query<InputArchiveFilter>,
 ) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
     let budgets = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
@@ -101,6 +347,9 @@ pub async fn get_all_between_dates(
             auth_user_claims.0.uid,
             date_range.start_date,
             date_range.end_date,
+            archive_filter.include_archived,
+            limit,
+            offset,
         )
     })
     .await?
@@ -112,13 +361,16 @@ pub async fn get_all_between_dates(
                 return Err(ServerError::InvalidFormat(None))
             }
             diesel::result::Error::NotFound => {
-                return Ok(HttpResponse::Ok().json(Vec::<OutputBudget>::new()));
+                return Ok(HttpResponse::Ok().json(OutputBudgetPage {
+                    budgets: Vec::new(),
+                    has_more: false,
+                }));
             }
             _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
+                return Err(ServerError::from_database_error(
+                    e,
                     "Failed to get budget data",
-                )));
+                ))
             }
         },
     };
@@ -126,11 +378,141 @@ pub async fn get_all_between_dates(
     Ok(HttpResponse::Ok().json(budgets))
 }
 
+pub async fn get_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+    pagination: web::Query<InputPagination>,
+    conversion: web::Query<InputCurrencyConversion>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let mut entries = match web::block(move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_entries_for_budget(&db_connection, budget_id, limit, offset)
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => return Err(ServerError::from_database_error(e, "Failed to get entries")),
+        },
+    };
+
+    if conversion.convert_currency {
+        let (_, converted_entries) = convert_to_user_currency(
+            db_thread_pool,
+            auth_user_claims.0.uid,
+            budget_id,
+            Vec::new(),
+            entries.entries,
+        )
+        .await?;
+
+        entries.entries = converted_entries;
+    }
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+enum ExportCursor {
+    Start,
+    After(NaiveDate, Uuid),
+    Done,
+}
+
+// Streams every live entry in the budget as JSON lines, one chunk of entries at a time, so an
+// export of a large budget is never held in memory all at once
+pub async fn export_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let chunks = stream::try_unfold(ExportCursor::Start, move |cursor| {
+        let db_thread_pool = db_thread_pool.clone();
+
+        async move {
+            let after = match cursor {
+                ExportCursor::Start => None,
+                ExportCursor::After(date, id) => Some((date, id)),
+                ExportCursor::Done => return Ok(None),
+            };
+
+            let chunk = match web::block(move || {
+                let db_connection = db_thread_pool
+                    .get()
+                    .expect("Failed to access database thread pool");
+                db::budget::get_live_entries_for_budget_after(
+                    &db_connection,
+                    budget_id,
+                    after,
+                    EXPORT_CHUNK_SIZE,
+                )
+            })
+            .await?
+            {
+                Ok(c) => c,
+                Err(e) => {
+                    return Err(ServerError::from_database_error(
+                        e,
+                        "Failed to export entries",
+                    ))
+                }
+            };
+
+            let next_cursor = match chunk.last() {
+                Some(e) if chunk.len() as i64 == EXPORT_CHUNK_SIZE => {
+                    ExportCursor::After(e.date, e.id)
+                }
+                Some(_) => ExportCursor::Done,
+                None => return Ok(None),
+            };
+
+            let mut lines = Vec::new();
+            for entry in chunk.iter() {
+                if serde_json::to_writer(&mut lines, entry).is_err() {
+                    return Err(ServerError::InternalError(Some(
+                        "Failed to serialize entry",
+                    )));
+                }
+
+                lines.push(b'\n');
+            }
+
+            Ok(Some((web::Bytes::from(lines), next_cursor)))
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(chunks))
+}
+
 pub async fn create(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
     budget_data: web::Json<InputBudget>,
 ) -> Result<HttpResponse, ServerError> {
+    if budget_data.is_tracking_only && budget_data.is_envelope {
+        return Err(ServerError::InvalidFormat(Some(
+            "A budget cannot be both tracking-only and envelope-based",
+        )));
+    }
+
     let new_budget = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
@@ -146,10 +528,10 @@ pub async fn create(
                 return Err(ServerError::InvalidFormat(None));
             }
             _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
+                return Err(ServerError::from_database_error(
+                    e,
                     "Failed to create budget",
-                )));
+                ))
             }
         },
     };
@@ -169,7 +551,13 @@ pub async fn edit(
     }
 
     let budget_id = budget_data.id.clone();
-    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
 
     web::block(move || {
         let db_connection = db_thread_pool
@@ -186,446 +574,7783 @@ pub async fn edit(
     })
 }
 
-pub async fn add_entry(
+pub async fn archive(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    entry_data: web::Json<InputEntry>,
+    budget_id: web::Json<InputBudgetId>,
 ) -> Result<HttpResponse, ServerError> {
-    let budget_id = entry_data.budget_id;
-    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+    set_archived(db_thread_pool, auth_user_claims, budget_id.budget_id, true).await
+}
 
-    let new_entry = match web::block(move || {
+pub async fn unarchive(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    set_archived(db_thread_pool, auth_user_claims, budget_id.budget_id, false).await
+}
+
+// Archiving is a flag on the budget, so it archives the budget for everyone it is shared with
+async fn set_archived(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: Uuid,
+    is_archived: bool,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-        db::budget::create_entry(&db_connection, &entry_data, auth_user_claims.0.uid)
+        db::budget::set_budget_archived(&db_connection, budget_id, is_archived)
     })
     .await?
     {
-        Ok(b) => b,
-        Err(e) => match e {
-            diesel::result::Error::InvalidCString(_)
-            | diesel::result::Error::DeserializationError(_) => {
-                return Err(ServerError::InvalidFormat(None));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to create entry",
-                )));
-            }
-        },
-    };
-
-    Ok(HttpResponse::Created().json(new_entry))
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(e) => Err(ServerError::from_database_error(
+            e,
+            "Failed to update budget",
+        )),
+    }
 }
 
-// TODO: Test
-pub async fn invite_user(
+pub async fn add_entry(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    invitation_info: web::Json<UserInvitationToBudget>,
-) -> Result<HttpResponse, ServerError> {
-    let inviting_user_id = auth_user_claims.0.uid.clone();
-    ensure_user_in_budget(
+    entry_data: web::Json<InputEntry>,
+    cap_override: web::Query<InputHardCapOverride>,
+    amount_confirmation: web::Query<InputUnusualAmountConfirmation>,
+) -> Result<HttpResponse, actix_web::Error> {
+    validate_entry_components(
+        entry_data.amount_cents,
+        entry_data.tax_cents,
+        entry_data.tip_cents,
+    )?;
+
+    if entry_data
+        .id
+        .is_some_and(|id| !record_id::is_acceptable_client_id(id))
+    {
+        return Err(ServerError::InvalidFormat(Some(
+            "Entry ID must be a UUIDv7 that isn't in the future",
+        ))
+        .into());
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    let budget_id = entry_data.budget_id;
+    ensure_user_has_budget_role(
         db_thread_pool.clone(),
-        inviting_user_id,
-        invitation_info.budget_id.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Editor,
     )
     .await?;
 
-    match web::block(move || {
-        let db_connection = db_thread_pool
+    // A client that created the entry offline uploads it again if it never got the response to the
+    // first upload. The entry it already uploaded is sent back instead of an error.
+    if let Some(entry_id) = entry_data.id {
+        let db_thread_pool_ref = db_thread_pool.clone();
+        match web::block(move || {
+            let db_connection = db_thread_pool_ref
+                .get()
+                .expect("Failed to access database thread pool");
+            db::budget::get_entry(&db_connection, entry_id)
+        })
+        .await?
+        {
+            Ok(e) if e.user_id == user_id && e.budget_id == budget_id => {
+                return Ok(HttpResponse::Ok().json(e));
+            }
+            Ok(_) => {
+                return Err(ServerError::AlreadyExists(Some(
+                    "An entry with the given ID already exists",
+                ))
+                .into());
+            }
+            Err(diesel::result::Error::NotFound) => (),
+            Err(e) => {
+                return Err(ServerError::from_database_error(e, "Failed to create entry").into())
+            }
+        }
+    }
+
+    let warning = match (
+        entry_data.category,
+        amount_confirmation.confirm_unusual_amount,
+    ) {
+        (Some(category), false) => {
+            check_entry_amount(
+                db_thread_pool.clone(),
+                user_id,
+                budget_id,
+                category,
+                entry_data.amount_cents,
+            )
+            .await?
+        }
+        _ => None,
+    };
+
+    let category = entry_data.category;
+    let amount_cents = entry_data.amount_cents;
+    let override_hard_cap = cap_override.override_hard_cap;
+
+    // The budget's owners can override a spending cap. The role is only looked up once the cap has
+    // turned the entry away.
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let new_entry = match web::block(move || {
+        let db_connection = db_thread_pool_ref
             .get()
             .expect("Failed to access database thread pool");
 
-        db::budget::invite_user(
-            &db_connection,
-            invitation_info.budget_id,
-            invitation_info.invitee_user_id,
-            inviting_user_id,
-        )
+        match db::budget::create_entry(&db_connection, &entry_data, user_id) {
+            Err(e) if override_hard_cap && db::category_total::is_hard_cap_violation(&e) => {
+                match db::budget::get_user_budget_role(&db_connection, user_id, budget_id)? {
+                    Some(BudgetRole::Owner) => {
+                        db::budget::create_entry_over_hard_cap(&db_connection, &entry_data, user_id)
+                    }
+                    _ => Err(e),
+                }
+            }
+            result => result,
+        }
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::InvalidCString(_)
-            | diesel::result::Error::DeserializationError(_) => {
-                return Err(ServerError::InvalidFormat(None));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to share budget",
-                )));
-            }
+        Ok(b) => b,
+        Err(e) if db::category_total::is_hard_cap_violation(&e) => {
+            return Err(hard_cap_rejection(
+                db_thread_pool,
+                override_hard_cap,
+                budget_id,
+                category,
+                amount_cents,
+            )
+            .await);
+        }
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to create entry").into()),
+    };
+
+    live_updates::publish(
+        db_thread_pool,
+        budget_id,
+        BudgetEvent::EntryAdded {
+            entry: new_entry.clone(),
         },
-    }
+    );
 
-    Ok(HttpResponse::Ok().finish())
+    Ok(HttpResponse::Created().json(OutputCreatedEntry {
+        entry: new_entry,
+        warning,
+    }))
 }
 
-// TODO: Test
-pub async fn retract_invitation(
+// Flags an amount far outside what the user usually records in the category, which is most often a
+// typo. The entry is still created, and the client shows the warning so the user can fix the
+// entry. A client that has already had the user confirm the amount sends `confirm_unusual_amount`
+// to skip the check.
+async fn check_entry_amount(
     db_thread_pool: web::Data<DbThreadPool>,
-    auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    invitation_id: web::Json<InputBudgetShareEventId>,
-) -> Result<HttpResponse, ServerError> {
-    match web::block(move || {
+    user_id: Uuid,
+    budget_id: Uuid,
+    category: i16,
+    amount_cents: i64,
+) -> Result<Option<EntryWarning>, ServerError> {
+    const HISTORY_LEN: i64 = 100;
+
+    let mut history = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-
-        db::budget::delete_invitation(
+        db::budget::get_recent_expense_amounts(
             &db_connection,
-            invitation_id.share_event_id,
-            auth_user_claims.0.uid,
+            user_id,
+            budget_id,
+            category,
+            HISTORY_LEN,
         )
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some(
-                    "No share event with provided ID",
-                )));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to delete invitation",
-                )));
-            }
-        },
-    }
+        Ok(h) => h,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to check entry amount",
+            ))
+        }
+    };
 
-    Ok(HttpResponse::Ok().finish())
+    Ok(unusual_amount::check_amount(&mut history, amount_cents))
 }
 
-// TODO: Test
-pub async fn accept_invitation(
+// Describes how far over its cap an entry would have taken a category, so the client can show how
+// much of the limit is left
+async fn hard_cap_rejection(
     db_thread_pool: web::Data<DbThreadPool>,
-    auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    invitation_id: web::Json<InputBudgetShareEventId>,
-) -> Result<HttpResponse, ServerError> {
-    match web::block(move || {
+    override_hard_cap: bool,
+    budget_id: Uuid,
+    category: Option<i16>,
+    amount_cents: i64,
+) -> actix_web::Error {
+    if override_hard_cap {
+        return ServerError::AccessForbidden(Some(
+            "Only the budget's owner can override a spending cap",
+        ))
+        .into();
+    }
+
+    let category = match category {
+        Some(c) => c,
+        None => return ServerError::InternalError(Some("Uncategorized entry hit a cap")).into(),
+    };
+
+    let cap_usage = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-
-        db::budget::mark_invitation_accepted(
-            &db_connection,
-            invitation_id.share_event_id,
-            auth_user_claims.0.uid,
-        )
+        db::budget::get_hard_cap_usage(&db_connection, budget_id, category)
     })
-    .await?
+    .await
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some(
-                    "No share event with provided ID",
-                )));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to accept invitation",
-                )));
-            }
-        },
-    }
+        Ok(Ok(u)) => u,
+        Ok(Err(e)) => {
+            return ServerError::from_database_error(e, "Failed to check category spending cap")
+                .into()
+        }
+        Err(e) => return ServerError::from(e).into(),
+    };
 
-    Ok(HttpResponse::Ok().finish())
+    // The cap may have been lifted since the entry was turned away
+    let (limit_cents, spent_cents) = cap_usage.unwrap_or((0, 0));
+
+    CategoryCapExceeded {
+        error: String::from("Category spending cap exceeded"),
+        category,
+        limit_cents,
+        spent_cents,
+        remaining_cents: (limit_cents - spent_cents).max(0),
+        amount_cents,
+    }
+    .into()
 }
 
-// TODO: Test
-pub async fn decline_invitation(
+pub async fn add_category(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    invitation_id: web::Json<InputBudgetShareEventId>,
+    category_data: web::Json<InputNewCategory>,
 ) -> Result<HttpResponse, ServerError> {
-    match web::block(move || {
+    if category_data.limit_cents < 0 {
+        return Err(ServerError::InputRejected(Some(
+            "Category limit cannot be negative",
+        )));
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        category_data.budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let category = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-
-        db::budget::mark_invitation_declined(
-            &db_connection,
-            invitation_id.share_event_id,
-            auth_user_claims.0.uid,
-        )
+        db::budget::add_category(&db_connection, &category_data)
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some(
-                    "No share event with provided ID",
-                )));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to decline invitation",
-                )));
-            }
-        },
-    }
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err(ServerError::InputRejected(Some(
+                "Budget cannot have any more categories",
+            )));
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to add category",
+            ))
+        }
+    };
 
-    Ok(HttpResponse::Ok().finish())
+    Ok(HttpResponse::Created().json(category))
 }
 
-// TODO: Test
-pub async fn get_all_pending_invitations_for_user(
+pub async fn export_categories(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
 ) -> Result<HttpResponse, ServerError> {
-    let invites = match web::block(move || {
+    let budget_id = budget_id.budget_id;
+
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let budget_categories = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-
-        db::budget::get_all_pending_invitations_for_user(&db_connection, auth_user_claims.0.uid)
+        db::budget::get_live_categories_for_budget(&db_connection, budget_id)
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some("No share events for user")));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to find invitations",
-                )));
-            }
-        },
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to export categories",
+            ))
+        }
     };
 
-    Ok(HttpResponse::Ok().json(invites))
+    Ok(HttpResponse::Ok().json(OutputCategoryExport {
+        budget_id,
+        categories: budget_categories
+            .into_iter()
+            .map(|c| OutputExportedCategory {
+                id: c.id,
+                name: c.name,
+                limit_cents: c.limit_cents,
+                color: c.color,
+            })
+            .collect(),
+    }))
 }
 
-// TODO: Test
-pub async fn get_all_pending_invitations_made_by_user(
+pub async fn import_categories(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    import_data: web::Json<InputCategoryImport>,
 ) -> Result<HttpResponse, ServerError> {
-    let invites = match web::block(move || {
+    if import_data.categories.is_empty() {
+        return Err(ServerError::InputRejected(Some("No categories to import")));
+    }
+
+    if import_data.categories.len() > MAX_IMPORTED_CATEGORIES {
+        return Err(ServerError::InputRejected(Some(
+            "Cannot import more than 100 categories at once",
+        )));
+    }
+
+    if import_data.categories.iter().any(|c| c.limit_cents < 0) {
+        return Err(ServerError::InputRejected(Some(
+            "Category limit cannot be negative",
+        )));
+    }
+
+    let mut category_ids = import_data
+        .categories
+        .iter()
+        .map(|c| c.id)
+        .collect::<Vec<_>>();
+    category_ids.sort_unstable();
+    category_ids.dedup();
+
+    if category_ids.len() != import_data.categories.len() {
+        return Err(ServerError::InputRejected(Some(
+            "Imported categories must have unique IDs",
+        )));
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        import_data.budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let imported_categories = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
-
-        db::budget::get_all_pending_invitations_made_by_user(&db_connection, auth_user_claims.0.uid)
+        db::budget::import_categories(
+            &db_connection,
+            import_data.budget_id,
+            &import_data.categories,
+        )
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some("No share events made by user")));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to find invitations",
-                )));
-            }
-        },
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to import categories",
+            ))
+        }
     };
 
-    Ok(HttpResponse::Ok().json(invites))
+    Ok(HttpResponse::Created().json(imported_categories))
 }
 
-// TODO: Test
-pub async fn get_invitation(
+pub async fn edit_category(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    invitation_id: web::Json<InputBudgetShareEventId>,
+    category_data: web::Json<InputEditCategory>,
 ) -> Result<HttpResponse, ServerError> {
-    let invite = match web::block(move || {
+    if category_data.limit_cents < 0 {
+        return Err(ServerError::InputRejected(Some(
+            "Category limit cannot be negative",
+        )));
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        category_data.budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let updated_count = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
+        db::budget::edit_category(&db_connection, auth_user_claims.0.uid, &category_data)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to update category",
+            ))
+        }
+    };
+
+    if updated_count == 0 {
+        return Err(ServerError::NotFound(Some("No category with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn remove_category(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    category_id: web::Json<InputCategoryId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        category_id.budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
 
-        db::budget::get_invitation(
+    let removed_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::remove_category(
             &db_connection,
-            invitation_id.share_event_id,
-            auth_user_claims.0.uid,
+            category_id.budget_id,
+            category_id.category_id,
         )
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some("Share event not found")));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to find invitations",
-                )));
-            }
-        },
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to remove category",
+            ))
+        }
     };
 
-    Ok(HttpResponse::Ok().json(invite))
+    if removed_count == 0 {
+        return Err(ServerError::NotFound(Some("No category with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
 }
 
-// TODO: Test (also test deletion of budgetb
-pub async fn remove_budget(
+pub async fn set_category_hard_cap(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
-    budget_id: web::Json<Uuid>,
+    hard_cap: web::Json<InputCategoryHardCap>,
 ) -> Result<HttpResponse, ServerError> {
-    let db_thread_pool_copy = db_thread_pool.clone();
-    let db_thread_pool_second_copy = db_thread_pool.clone();
+    let user_id = auth_user_claims.0.uid;
+    let budget_id = hard_cap.budget_id;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
 
-    match web::block(move || {
+    let updated_count = match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
 
-        db::budget::remove_user(&db_connection, budget_id.0, auth_user_claims.0.uid)
+        db::budget::set_category_hard_cap(
+            &db_connection,
+            budget_id,
+            hard_cap.category_id,
+            hard_cap.is_hard_capped,
+        )
     })
     .await?
     {
-        Ok(_) => (),
-        Err(e) => match e {
-            diesel::result::Error::NotFound => {
-                return Err(ServerError::NotFound(Some(
-                    "User budget association not found",
-                )));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to remove association with budget",
-                )));
-            }
-        },
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to update category",
+            ))
+        }
+    };
+
+    if updated_count == 0 {
+        return Err(ServerError::NotFound(Some("No category with provided ID")));
     }
 
-    // TODO: Perhaps user shouldn't have to wait for this (make it non-blocking)
-    let remaining_users_in_budget = match web::block(move || {
-        let db_connection = db_thread_pool_copy
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn edit_entry(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    entry_data: web::Json<InputEditEntry>,
+    cap_override: web::Query<InputHardCapOverride>,
+) -> Result<HttpResponse, actix_web::Error> {
+    validate_entry_components(
+        entry_data.amount_cents,
+        entry_data.tax_cents,
+        entry_data.tip_cents,
+    )?;
+
+    let user_id = auth_user_claims.0.uid;
+    let entry_id = entry_data.entry_id;
+    let category = entry_data.category;
+    let amount_cents = entry_data.amount_cents;
+    let override_hard_cap = cap_override.override_hard_cap;
+
+    // As with new entries, the budget's owners can override a spending cap
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let edited_count = match web::block(move || {
+        let db_connection = db_thread_pool_ref
             .get()
             .expect("Failed to access database thread pool");
 
-        db::budget::count_users_remaining_in_budget(&db_connection, budget_id.0)
+        match db::budget::edit_entry(&db_connection, user_id, &entry_data) {
+            Err(e) if override_hard_cap && db::category_total::is_hard_cap_violation(&e) => {
+                let budget_id = db::budget::get_entry(&db_connection, entry_id)?.budget_id;
+
+                match db::budget::get_user_budget_role(&db_connection, user_id, budget_id)? {
+                    Some(BudgetRole::Owner) => {
+                        db::budget::edit_entry_over_hard_cap(&db_connection, user_id, &entry_data)
+                    }
+                    _ => Err(e),
+                }
+            }
+            result => result,
+        }
     })
     .await?
     {
         Ok(c) => c,
+        Err(e) if db::category_total::is_hard_cap_violation(&e) => {
+            let entry = get_capped_entry(db_thread_pool.clone(), entry_id).await?;
+
+            return Err(hard_cap_rejection(
+                db_thread_pool,
+                override_hard_cap,
+                entry.budget_id,
+                category,
+                amount_cents,
+            )
+            .await);
+        }
         Err(e) => match e {
-            _ => {
-                error!(
-                    "Failed to see how many users left in budget with ID '{}': {}",
-                    budget_id.0, e
-                );
-                10
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None).into());
             }
+            _ => return Err(ServerError::from_database_error(e, "Failed to edit entry").into()),
         },
     };
 
-    if remaining_users_in_budget == 0 {
-        match web::block(move || {
-            let db_connection = db_thread_pool_second_copy
-                .get()
-                .expect("Failed to access database thread pool");
-
-            db::budget::delete_budget(&db_connection, budget_id.0)
-        })
-        .await?
-        {
-            Ok(_) => (),
-            Err(e) => match e {
-                _ => error!("Failed to delete budget with ID '{}': {}", budget_id.0, e),
-            },
-        };
+    if edited_count == 0 {
+        return Err(ServerError::NotFound(Some("No entry with provided ID")).into());
     }
 
     Ok(HttpResponse::Ok().finish())
 }
 
-#[inline]
-async fn ensure_user_in_budget(
+// Looks up an entry that a category's hard cap turned away so the rejection can say which cap
+async fn get_capped_entry(
     db_thread_pool: web::Data<DbThreadPool>,
-    user_id: Uuid,
-    budget_id: Uuid,
-) -> Result<(), ServerError> {
-    let is_user_in_budget = match web::block(move || {
+    entry_id: Uuid,
+) -> Result<Entry, ServerError> {
+    match web::block(move || {
         let db_connection = db_thread_pool
             .get()
             .expect("Failed to access database thread pool");
+        db::budget::get_entry(&db_connection, entry_id)
+    })
+    .await?
+    {
+        Ok(e) => Ok(e),
+        Err(e) => Err(ServerError::from_database_error(
+            e,
+            "Failed to check category spending cap",
+        )),
+    }
+}
 
-        db::budget::check_user_in_budget(&db_connection, user_id, budget_id)
+pub async fn delete_entry(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    entry_id: web::Json<InputEntryId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::delete_entry(&db_connection, auth_user_claims.0.uid, entry_id.entry_id)
     })
     .await?
     {
-        Ok(b) => b,
-        Err(e) => match e {
-            diesel::result::Error::InvalidCString(_)
-            | diesel::result::Error::DeserializationError(_) => {
-                return Err(ServerError::InvalidFormat(None));
-            }
-            _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
-                    "Failed to get budget data",
-                )));
-            }
-        },
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete entry",
+            ))
+        }
     };
 
-    if !is_user_in_budget {
-        return Err(ServerError::NotFound(Some(
-            "User has no budget with provided ID",
-        )));
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some("No entry with provided ID")));
     }
 
-    Ok(())
+    Ok(HttpResponse::Ok().finish())
 }
 
-#[cfg(test)]
-mod tests {
-    use actix_web::web::Data;
-    use actix_web::{http, test, App};
-    use chrono::NaiveDate;
-    use diesel::prelude::*;
-    use rand::prelude::*;
-    use std::time::{SystemTime, UNIX_EPOCH};
+// Entries and comments can be recovered from the trash until they're purged
+fn trash_cutoff() -> chrono::NaiveDateTime {
+    chrono::Utc::now().naive_utc()
+        - chrono::Duration::days(env::reloadable_conf().lifetimes.trash_retention_days as i64)
+}
 
-    use crate::definitions::*;
-    use crate::env;
-    use crate::handlers::request_io::{
-        InputBudget, InputBudgetId, InputCategory, InputDateRange, InputEditBudget, InputEntry,
-        InputUser, OutputBudget, SigninToken, SigninTokenOtpPair, TokenPair,
-    };
-    use crate::models::budget::Budget;
-    use crate::models::category::Category;
-    use crate::models::entry::Entry;
+pub async fn get_trash(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let (deleted_entries, deleted_comments) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::trash::get_trash(&db_connection, budget_id, trash_cutoff())
+    })
+    .await?
+    {
+        Ok(t) => t,
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to get trash")),
+    };
+
+    Ok(HttpResponse::Ok().json(OutputTrash {
+        budget_id,
+        retention_days: env::reloadable_conf().lifetimes.trash_retention_days,
+        entries: deleted_entries,
+        comments: deleted_comments,
+    }))
+}
+
+pub async fn restore_entry(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    entry_id: web::Json<InputEntryId>,
+    cap_override: web::Query<InputHardCapOverride>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = auth_user_claims.0.uid;
+    let entry_id = entry_id.entry_id;
+    let override_hard_cap = cap_override.override_hard_cap;
+
+    // As with new entries, the budget's owners can override a spending cap
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let restored_entry = match web::block(move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to access database thread pool");
+        let deleted_since = trash_cutoff();
+
+        match db::trash::restore_entry(&db_connection, user_id, entry_id, deleted_since) {
+            Err(e) if override_hard_cap && db::category_total::is_hard_cap_violation(&e) => {
+                let budget_id = db::budget::get_entry(&db_connection, entry_id)?.budget_id;
+
+                match db::budget::get_user_budget_role(&db_connection, user_id, budget_id)? {
+                    Some(BudgetRole::Owner) => db::trash::restore_entry_over_hard_cap(
+                        &db_connection,
+                        user_id,
+                        entry_id,
+                        deleted_since,
+                    ),
+                    _ => Err(e),
+                }
+            }
+            result => result,
+        }
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) if db::category_total::is_hard_cap_violation(&e) => {
+            let entry = get_capped_entry(db_thread_pool.clone(), entry_id).await?;
+
+            return Err(hard_cap_rejection(
+                db_thread_pool,
+                override_hard_cap,
+                entry.budget_id,
+                entry.category,
+                entry.amount_cents,
+            )
+            .await);
+        }
+        Err(diesel::result::Error::NotFound) => {
+            return Err(
+                ServerError::NotFound(Some("No entry with provided ID in the trash")).into(),
+            )
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(e, "Failed to restore entry").into())
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(restored_entry))
+}
+
+pub async fn get_audit_log(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+    pagination: web::Query<InputPagination>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let budget_id = budget_id.budget_id;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let audit_log = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::audit_log::get_audit_log(&db_connection, budget_id, limit, offset)
+    })
+    .await?
+    {
+        Ok(l) => l,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get audit log",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(audit_log))
+}
+
+// Only a month that is already over can be closed. Closing it locks the budget's entries dated in
+// that month. An owner can reopen the month to change them.
+pub async fn close_month(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    month_data: web::Json<InputBudgetMonth>,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+    let budget_id = month_data.budget_id;
+    let month = first_of_month(month_data.month);
+
+    if month >= first_of_month(chrono::Utc::now().naive_utc().date()) {
+        return Err(ServerError::InvalidFormat(Some(
+            "Only a month that has ended can be closed",
+        )));
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    match logging::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::closed_month::close_month(&db_connection, budget_id, month, user_id)
+    })
+    .await?
+    {
+        Ok(_) => (),
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to close month")),
+    };
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn reopen_month(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    month_data: web::Json<InputBudgetMonth>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = month_data.budget_id;
+    let month = first_of_month(month_data.month);
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let reopened_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::closed_month::reopen_month(&db_connection, budget_id, month)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to reopen month",
+            ))
+        }
+    };
+
+    if reopened_count == 0 {
+        return Err(ServerError::NotFound(Some("Month is not closed")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn get_closed_months(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let months = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::closed_month::get_closed_months(&db_connection, budget_id)
+    })
+    .await?
+    {
+        Ok(m) => m,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get closed months",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(months))
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("Every month has a first day")
+}
+
+// Bulk deletion is a two-step process. A dry run reports what the filter matches and hands back a
+// confirmation token. Sending the token back with the same filter deletes the entries, but only if
+// the filter still matches exactly what was previewed. Once the entries are deleted the filter
+// matches nothing, so replaying the confirmed request has no effect.
+pub async fn bulk_delete_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    deletion_data: web::Json<InputBulkEntryDeletion>,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+    let deletion_data = deletion_data.into_inner();
+    let filter = deletion_data.filter;
+
+    if let (Some(start_date), Some(end_date)) = (filter.start_date, filter.end_date) {
+        if end_date < start_date {
+            return Err(ServerError::InputRejected(Some(
+                "End date cannot come before start date",
+            )));
+        }
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        filter.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let (expected_count, confirmation_token) = match (
+        deletion_data.expected_count,
+        deletion_data.confirmation_token,
+    ) {
+        (Some(c), Some(t)) => (c, t),
+        (None, None) => {
+            return preview_bulk_entry_deletion(db_thread_pool, user_id, filter).await;
+        }
+        _ => {
+            return Err(ServerError::InvalidFormat(Some(
+                "Confirming a deletion requires both the expected count and confirmation token",
+            )));
+        }
+    };
+
+    if !confirmation_token::verify_confirmation_token(
+        &confirmation_token,
+        user_id,
+        &bulk_entry_deletion_description(&filter, expected_count),
+    ) {
+        return Err(ServerError::AccessForbidden(Some(
+            "Confirmation token is invalid or has expired",
+        )));
+    }
+
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::delete_entries_matching_filter(&db_connection, &filter, expected_count)
+    })
+    .await?
+    {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err(ServerError::AccessForbidden(Some(
+                "Matching entries have changed since the dry run",
+            )));
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete entries",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(OutputBulkDeletion { deleted_count }))
+}
+
+async fn preview_bulk_entry_deletion(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: Uuid,
+    filter: InputEntryFilter,
+) -> Result<HttpResponse, ServerError> {
+    let (matched_count, sample, filter) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::preview_entry_deletion(&db_connection, &filter, BULK_DELETION_SAMPLE_SIZE)
+            .map(|(count, sample)| (count, sample, filter))
+    })
+    .await?
+    {
+        Ok(p) => p,
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to get entries")),
+    };
+
+    let confirmation_token = if matched_count > 0 {
+        Some(confirmation_token::generate_confirmation_token(
+            user_id,
+            &bulk_entry_deletion_description(&filter, matched_count),
+            BULK_DELETION_CONFIRMATION_LIFETIME_SECS,
+        ))
+    } else {
+        None
+    };
+
+    Ok(HttpResponse::Ok().json(OutputBulkDeletionPreview {
+        matched_count,
+        sample,
+        confirmation_token,
+    }))
+}
+
+fn bulk_entry_deletion_description(filter: &InputEntryFilter, matched_count: i64) -> String {
+    format!(
+        "bulk_delete_entries|{}|{:?}|{:?}|{:?}|{:?}|{}",
+        filter.budget_id,
+        filter.start_date,
+        filter.end_date,
+        filter.category,
+        filter.import_batch_id,
+        matched_count
+    )
+}
+
+pub async fn add_recurring_entry(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    recurring_entry_data: web::Json<InputRecurringEntry>,
+) -> Result<HttpResponse, ServerError> {
+    if RecurrenceFrequency::try_from(recurring_entry_data.frequency).is_err() {
+        return Err(ServerError::InvalidFormat(Some(
+            "Invalid recurrence frequency",
+        )));
+    }
+
+    if let Some(end_date) = recurring_entry_data.end_date {
+        if end_date < recurring_entry_data.start_date {
+            return Err(ServerError::InputRejected(Some(
+                "End date cannot come before start date",
+            )));
+        }
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        recurring_entry_data.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let recurring_entry = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::recurring_entry::create_recurring_entry(&db_connection, user_id, &recurring_entry_data)
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create recurring entry",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(recurring_entry))
+}
+
+pub async fn get_all_recurring_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let recurring_entries = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::recurring_entry::get_all_recurring_entries_for_user(
+            &db_connection,
+            auth_user_claims.0.uid,
+        )
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get recurring entries",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(recurring_entries))
+}
+
+pub async fn edit_recurring_entry(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    recurring_entry_data: web::Json<InputEditRecurringEntry>,
+) -> Result<HttpResponse, ServerError> {
+    let edited_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::recurring_entry::edit_recurring_entry(
+            &db_connection,
+            auth_user_claims.0.uid,
+            &recurring_entry_data,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to edit recurring entry",
+                ))
+            }
+        },
+    };
+
+    if edited_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No recurring entry with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn delete_recurring_entry(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    recurring_entry_id: web::Json<InputRecurringEntryId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::recurring_entry::delete_recurring_entry(
+            &db_connection,
+            auth_user_claims.0.uid,
+            recurring_entry_id.recurring_entry_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete recurring entry",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No recurring entry with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+fn to_adjustment(change: &InputSimulatedChange) -> Result<Adjustment, ServerError> {
+    match change {
+        InputSimulatedChange::ScaleCategory {
+            category,
+            percent_change,
+        } => {
+            if *percent_change < -100 {
+                return Err(ServerError::InputRejected(Some(
+                    "Spending cannot be reduced by more than 100%",
+                )));
+            }
+
+            Ok(Adjustment::ScaleCategory {
+                category: *category,
+                percent_change: *percent_change,
+            })
+        }
+        InputSimulatedChange::AddRecurringExpense {
+            amount_cents,
+            category,
+            frequency,
+            start_date,
+            end_date,
+        } => {
+            let frequency = match RecurrenceFrequency::try_from(*frequency) {
+                Ok(f) => f,
+                Err(_) => {
+                    return Err(ServerError::InvalidFormat(Some(
+                        "Invalid recurrence frequency",
+                    )))
+                }
+            };
+
+            if let Some(end_date) = end_date {
+                if end_date < start_date {
+                    return Err(ServerError::InputRejected(Some(
+                        "End date cannot come before start date",
+                    )));
+                }
+            }
+
+            Ok(Adjustment::AddScheduledExpense(ScheduledExpense {
+                category: *category,
+                amount_cents: *amount_cents,
+                frequency,
+                start_date: *start_date,
+                end_date: *end_date,
+                next_occurrence_date: *start_date,
+            }))
+        }
+    }
+}
+
+// Projects the budget with hypothetical changes applied. Nothing is saved.
+pub async fn simulate(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    simulation: web::Json<InputBudgetSimulation>,
+) -> Result<HttpResponse, ServerError> {
+    if simulation.changes.len() > MAX_SIMULATED_CHANGES {
+        return Err(ServerError::InputRejected(Some(
+            "Too many changes in simulation",
+        )));
+    }
+
+    let adjustments = simulation
+        .changes
+        .iter()
+        .map(to_adjustment)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let budget_id = simulation.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let forecast = forecast_budget(db_thread_pool, budget_id, adjustments).await?;
+
+    Ok(HttpResponse::Ok().json(forecast))
+}
+
+// Recurring entries are projected from their schedules. Callers must check that the user can
+// access the budget.
+pub async fn forecast_budget(
+    db_thread_pool: web::Data<DbThreadPool>,
+    budget_id: Uuid,
+    adjustments: Vec<Adjustment>,
+) -> Result<BudgetForecast, ServerError> {
+    let (budget, recurring_entries) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let budget = db::budget::get_budget_by_id(&db_connection, budget_id)?;
+        let recurring_entries =
+            db::recurring_entry::get_recurring_entries_for_budget(&db_connection, budget_id)?;
+
+        Ok((budget, recurring_entries))
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some("No budget with provided ID")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get budget data",
+                ))
+            }
+        },
+    };
+
+    let scheduled_expenses = recurring_entries
+        .iter()
+        .filter_map(|r| ScheduledExpense::try_from(r).ok())
+        .collect::<Vec<_>>();
+
+    let today = chrono::Utc::now().naive_utc().date();
+    let forecast = web::block(move || {
+        forecasting::forecast_budget(&budget, &scheduled_expenses, &adjustments, today)
+    })
+    .await?;
+
+    Ok(forecast)
+}
+
+fn validate_comment_text(text: &str) -> Result<(), ServerError> {
+    if text.trim().is_empty() {
+        return Err(ServerError::InputRejected(Some("Comment cannot be empty")));
+    }
+
+    if text.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ServerError::InputRejected(Some("Comment is too long")));
+    }
+
+    Ok(())
+}
+
+pub async fn create_comment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    comment_data: web::Json<InputBudgetComment>,
+) -> Result<HttpResponse, ServerError> {
+    validate_comment_text(&comment_data.text)?;
+
+    if comment_data
+        .id
+        .is_some_and(|id| !record_id::is_acceptable_client_id(id))
+    {
+        return Err(ServerError::InvalidFormat(Some(
+            "Comment ID must be a UUIDv7 that isn't in the future",
+        )));
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    let budget_id = comment_data.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), user_id, budget_id).await?;
+
+    // Comments created offline are handled the same way as entries created offline
+    if let Some(comment_id) = comment_data.id {
+        let db_thread_pool_ref = db_thread_pool.clone();
+        match web::block(move || {
+            let db_connection = db_thread_pool_ref
+                .get()
+                .expect("Failed to access database thread pool");
+            db::budget_comment::get_comment(&db_connection, comment_id)
+        })
+        .await?
+        {
+            Ok(c) if c.user_id == user_id && c.budget_id == budget_id => {
+                return Ok(HttpResponse::Ok().json(c));
+            }
+            Ok(_) => {
+                return Err(ServerError::AlreadyExists(Some(
+                    "A comment with the given ID already exists",
+                )));
+            }
+            Err(diesel::result::Error::NotFound) => (),
+            Err(e) => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create comment",
+                ))
+            }
+        }
+    }
+
+    enforce_daily_limit(db_thread_pool.clone(), user_id, DailyAction::BudgetComment).await?;
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let comment = match web::block(move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_comment::create_comment(&db_connection, user_id, &comment_data)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create comment",
+                ))
+            }
+        },
+    };
+
+    live_updates::publish(
+        db_thread_pool,
+        comment.budget_id,
+        BudgetEvent::CommentPosted {
+            comment: comment.clone(),
+        },
+    );
+
+    Ok(HttpResponse::Created().json(comment))
+}
+
+pub async fn get_comments(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+    )
+    .await?;
+
+    let comments = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_comment::get_comments_for_budget(&db_connection, budget_id.budget_id)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get comments",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(comments))
+}
+
+pub async fn edit_comment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    comment_data: web::Json<InputEditBudgetComment>,
+) -> Result<HttpResponse, ServerError> {
+    validate_comment_text(&comment_data.text)?;
+
+    let comment = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_comment::edit_comment(&db_connection, auth_user_claims.0.uid, &comment_data)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some("No comment with provided ID")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to edit comment",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(comment))
+}
+
+pub async fn delete_comment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    comment_id: web::Json<InputBudgetCommentId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_comment::delete_comment(
+            &db_connection,
+            auth_user_claims.0.uid,
+            comment_id.comment_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete comment",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some("No comment with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn allocate_funds(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    allocation_data: web::Json<InputFundAllocation>,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+    let budget_id = allocation_data.budget_id;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    if allocation_data.amount_cents == 0 {
+        return Err(ServerError::InvalidFormat(Some(
+            "Allocation amount cannot be zero",
+        )));
+    }
+
+    let summary = get_envelope_summary_for_budget(db_thread_pool.clone(), budget_id).await?;
+
+    let envelope = match summary
+        .envelopes
+        .iter()
+        .find(|e| e.category == allocation_data.category)
+    {
+        Some(e) => e,
+        None => {
+            return Err(ServerError::NotFound(Some(
+                "Budget has no category with provided ID",
+            )))
+        }
+    };
+
+    if allocation_data.amount_cents > summary.to_be_budgeted_cents {
+        return Err(ServerError::InvalidFormat(Some(
+            "Not enough money left to be budgeted",
+        )));
+    }
+
+    if envelope.allocated_cents + allocation_data.amount_cents < 0 {
+        return Err(ServerError::InvalidFormat(Some(
+            "Cannot move more money out of a category than was allocated to it",
+        )));
+    }
+
+    let allocation = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::envelope::allocate_funds(&db_connection, user_id, &allocation_data)
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to allocate funds",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Created().json(allocation))
+}
+
+pub async fn get_envelope_summary(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+    )
+    .await?;
+
+    let summary = get_envelope_summary_for_budget(db_thread_pool, budget_id.budget_id).await?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+pub async fn get_summary(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let today = chrono::Utc::now().naive_utc().date();
+    let summary = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_budget_summary(&db_connection, budget_id, today)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some("No budget with provided ID")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get budget summary",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+pub async fn import_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    import_data: web::Json<InputEntryImport>,
+) -> Result<HttpResponse, ServerError> {
+    if import_data.entries.is_empty() || import_data.entries.len() > MAX_IMPORTED_ENTRIES {
+        return Err(ServerError::InvalidFormat(Some(
+            "An import must contain between 1 and 1000 entries",
+        )));
+    }
+
+    if import_data.source_name.is_empty() || import_data.source_name.chars().count() > 120 {
+        return Err(ServerError::InvalidFormat(Some(
+            "Source name must be between 1 and 120 characters",
+        )));
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        import_data.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let import_batch = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::import::import_entries(&db_connection, user_id, &import_data)
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to import entries",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(import_batch))
+}
+
+// Takes a multipart form with the budget ID in a `budget_id` field, the statement in a `file`
+// field, and, for CSV files, the column mapping as JSON in a `mapping` field. The file's format is
+// taken from its extension.
+pub async fn import_statement(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ServerError> {
+    const INVALID_FORM_MSG: &str = "Invalid multipart form";
+
+    let mut budget_id = None;
+    let mut mapping = None;
+    let mut file = None;
+
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|_| ServerError::InvalidFormat(Some(INVALID_FORM_MSG)))?
+    {
+        let field_name = field.name().map(String::from);
+        let file_name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .map(String::from);
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|_| ServerError::InvalidFormat(Some(INVALID_FORM_MSG)))?
+        {
+            if data.len() + chunk.len() > MAX_STATEMENT_FILE_BYTES {
+                return Err(ServerError::InputRejected(Some(
+                    "Statement files can be at most 2 MiB",
+                )));
+            }
+
+            data.extend_from_slice(&chunk);
+        }
+
+        match field_name.as_deref() {
+            Some("budget_id") => {
+                budget_id = std::str::from_utf8(&data)
+                    .ok()
+                    .and_then(|id| Uuid::parse_str(id.trim()).ok());
+            }
+            Some("mapping") => match serde_json::from_slice::<InputColumnMapping>(&data) {
+                Ok(m) => mapping = Some(m),
+                Err(_) => return Err(ServerError::InvalidFormat(Some("Invalid column mapping"))),
+            },
+            Some("file") => file = Some((file_name.unwrap_or_default(), data)),
+            _ => (),
+        }
+    }
+
+    let (budget_id, (file_name, data)) = match (budget_id, file) {
+        (Some(b), Some(f)) => (b, f),
+        _ => {
+            return Err(ServerError::InvalidFormat(Some(
+                "A budget ID and a statement file are required",
+            )))
+        }
+    };
+
+    let format = match StatementFormat::from_file_name(&file_name) {
+        Some(f) => f,
+        None => {
+            return Err(ServerError::InputRejected(Some(
+                "Statements must be CSV or OFX files",
+            )))
+        }
+    };
+
+    let user_id = auth_user_claims.0.uid;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let statement =
+        match web::block(move || import::parse_statement(format, &data, mapping.as_ref())).await? {
+            Ok(s) => s,
+            Err(ImportError::Unreadable(msg)) => return Err(ServerError::InputRejected(Some(msg))),
+            Err(ImportError::MissingColumn(_)) => {
+                return Err(ServerError::InputRejected(Some(
+                    "A mapped column isn't in the file",
+                )))
+            }
+        };
+
+    if statement.rows.len() > MAX_IMPORTED_ENTRIES {
+        return Err(ServerError::InputRejected(Some(
+            "A statement can contain at most 1000 transactions",
+        )));
+    }
+
+    let source_name = match file_name.chars().count() {
+        1..=120 => file_name,
+        _ => file_name.chars().take(120).collect(),
+    };
+
+    let (import_batch, duplicate_rows) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::import::import_statement(
+            &db_connection,
+            user_id,
+            budget_id,
+            &source_name,
+            &statement.rows,
+        )
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to import statement",
+            ))
+        }
+    };
+
+    let mut skipped_rows = statement
+        .skipped_rows
+        .into_iter()
+        .chain(duplicate_rows)
+        .map(|s| OutputSkippedRow {
+            row: s.row,
+            reason: String::from(s.reason),
+        })
+        .collect::<Vec<_>>();
+    skipped_rows.sort_by_key(|s| s.row);
+
+    let output = OutputStatementImport {
+        imported_count: import_batch.as_ref().map_or(0, |b| b.entry_count as usize),
+        import_batch,
+        skipped_rows,
+    };
+
+    if output.import_batch.is_some() {
+        Ok(HttpResponse::Created().json(output))
+    } else {
+        Ok(HttpResponse::Ok().json(output))
+    }
+}
+
+pub async fn get_import_batches(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+    )
+    .await?;
+
+    let import_batches = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::import::get_import_batches_for_budget(&db_connection, budget_id.budget_id)
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get import batches",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(import_batches))
+}
+
+// Clients upload files to be imported straight to storage, which only ever sees this token
+pub async fn get_import_upload_token(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let upload_token = match auth_token::generate_upload_token(
+        auth_token::TokenParams {
+            user_id: &auth_user_claims.0.uid,
+        },
+        UploadScope::Import {
+            budget_id: budget_id.budget_id,
+        },
+    ) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Err(ServerError::InternalError(Some(
+                "Failed to generate upload token",
+            )));
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(UploadToken {
+        upload_token: upload_token.to_string(),
+    }))
+}
+
+pub async fn get_resources(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+    )
+    .await?;
+
+    let resources = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_resource::get_resources_for_budget(&db_connection, budget_id.budget_id)
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get budget resources",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(resources))
+}
+
+pub async fn add_resource(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    resource_data: web::Json<InputBudgetResource>,
+) -> Result<HttpResponse, ServerError> {
+    let title_length = resource_data.title.trim().chars().count();
+    if title_length == 0 || title_length > MAX_RESOURCE_TITLE_LENGTH {
+        return Err(ServerError::InvalidFormat(Some(
+            "Title must be between 1 and 120 characters",
+        )));
+    }
+
+    let location = resource_data.location.trim();
+    if location.is_empty() || location.chars().count() > MAX_RESOURCE_LOCATION_LENGTH {
+        return Err(ServerError::InvalidFormat(Some(
+            "Location must be between 1 and 2048 characters",
+        )));
+    }
+
+    match BudgetResourceKind::try_from(resource_data.kind) {
+        Ok(BudgetResourceKind::Link) => {
+            let is_web_url = ["https://", "http://"].iter().any(|scheme| {
+                location
+                    .get(..scheme.len())
+                    .is_some_and(|s| s.eq_ignore_ascii_case(scheme))
+            });
+
+            if !is_web_url || location.contains(char::is_whitespace) {
+                return Err(ServerError::InputRejected(Some(
+                    "Links must be http or https URLs",
+                )));
+            }
+        }
+        Ok(BudgetResourceKind::File) => (),
+        Err(_) => return Err(ServerError::InvalidFormat(Some("Invalid resource kind"))),
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        resource_data.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let resource = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_resource::add_resource(
+            &db_connection,
+            user_id,
+            &resource_data,
+            MAX_BUDGET_RESOURCES,
+        )
+    })
+    .await?
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return Err(ServerError::InputRejected(Some(
+                "Budget cannot have more than 10 resources",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to add budget resource",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Created().json(resource))
+}
+
+// Removing a file resource doesn't delete the file from storage
+pub async fn remove_resource(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    resource_id: web::Json<InputBudgetResourceId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        resource_id.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let removed_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget_resource::remove_resource(
+            &db_connection,
+            resource_id.budget_id,
+            resource_id.resource_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to remove budget resource",
+            ))
+        }
+    };
+
+    if removed_count == 0 {
+        return Err(ServerError::NotFound(Some("No resource with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn get_reimbursements(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), auth_user_claims.0.uid, budget_id).await?;
+
+    let summary = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reimbursement::get_reimbursement_summary(&db_connection, budget_id)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get reimbursements",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+pub async fn mark_reimbursable(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    reimbursement: web::Json<InputReimbursement>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        reimbursement.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let reimbursement = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reimbursement::mark_reimbursable(
+            &db_connection,
+            reimbursement.budget_id,
+            reimbursement.entry_id,
+        )
+    })
+    .await?
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return Err(ServerError::NotFound(Some(
+                "No expense entry with provided ID",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to mark entry as reimbursable",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Created().json(reimbursement))
+}
+
+pub async fn unmark_reimbursable(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    reimbursement: web::Json<InputReimbursement>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        reimbursement.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let removed_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reimbursement::unmark_reimbursable(
+            &db_connection,
+            reimbursement.budget_id,
+            reimbursement.entry_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to unmark entry as reimbursable",
+            ))
+        }
+    };
+
+    if removed_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No reimbursable entry with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn submit_reimbursement(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    reimbursement: web::Json<InputReimbursement>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        reimbursement.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let submitted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reimbursement::submit_reimbursement(
+            &db_connection,
+            reimbursement.budget_id,
+            reimbursement.entry_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to submit reimbursement",
+            ))
+        }
+    };
+
+    if submitted_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No outstanding reimbursement for provided entry",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn record_reimbursement_payment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    payment: web::Json<InputReimbursementPayment>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        payment.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let paid_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reimbursement::record_payment(
+            &db_connection,
+            payment.budget_id,
+            payment.entry_id,
+            payment.payment_entry_id,
+        )
+    })
+    .await?
+    {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err(ServerError::InputRejected(Some(
+                "Payment must be an incoming entry in the same budget",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to record reimbursement payment",
+            ))
+        }
+    };
+
+    if paid_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No unpaid reimbursement for provided entry",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn get_entry_attachments(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    attachments: web::Json<InputEntryAttachments>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        attachments.budget_id,
+    )
+    .await?;
+
+    let attachments = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::entry_attachment::get_attachments_for_entry(
+            &db_connection,
+            attachments.budget_id,
+            attachments.entry_id,
+        )
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get attachments",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(attachments))
+}
+
+// Takes a multipart form with the budget ID in a `budget_id` field, the entry ID in an `entry_id`
+// field, and the receipt in a `file` field. The file's type is read from its contents.
+pub async fn upload_entry_attachment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    mut payload: Multipart,
+) -> Result<HttpResponse, ServerError> {
+    const INVALID_FORM_MSG: &str = "Invalid multipart form";
+
+    let mut budget_id = None;
+    let mut entry_id = None;
+    let mut file = None;
+
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|_| ServerError::InvalidFormat(Some(INVALID_FORM_MSG)))?
+    {
+        let field_name = field.name().map(String::from);
+        let file_name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .map(String::from);
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|_| ServerError::InvalidFormat(Some(INVALID_FORM_MSG)))?
+        {
+            if data.len() + chunk.len() > env::CONF.storage.max_attachment_bytes {
+                return Err(ServerError::InputRejected(Some("Attachment is too large")));
+            }
+
+            data.extend_from_slice(&chunk);
+        }
+
+        let parse_id = |data: &[u8]| {
+            std::str::from_utf8(data)
+                .ok()
+                .and_then(|id| Uuid::parse_str(id.trim()).ok())
+        };
+
+        match field_name.as_deref() {
+            Some("budget_id") => budget_id = parse_id(&data),
+            Some("entry_id") => entry_id = parse_id(&data),
+            Some("file") => file = Some((file_name.unwrap_or_default(), data)),
+            _ => (),
+        }
+    }
+
+    let (budget_id, entry_id, (file_name, data)) = match (budget_id, entry_id, file) {
+        (Some(b), Some(e), Some(f)) => (b, e, f),
+        _ => {
+            return Err(ServerError::InvalidFormat(Some(
+                "A budget ID, an entry ID, and a file are required",
+            )))
+        }
+    };
+
+    let content_type = match storage::sniff_content_type(&data) {
+        Some(t)
+            if env::CONF
+                .storage
+                .allowed_attachment_types
+                .iter()
+                .any(|allowed| allowed == t) =>
+        {
+            t
+        }
+        _ => {
+            return Err(ServerError::InputRejected(Some(
+                "Attachments must be a PDF or an image of a supported type",
+            )))
+        }
+    };
+
+    let user_id = auth_user_claims.0.uid;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    // The name is echoed back in a header when the file is downloaded
+    let file_name = match file_name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '/' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_ATTACHMENT_FILE_NAME_LENGTH)
+        .collect::<String>()
+    {
+        n if n.is_empty() => String::from("attachment"),
+        n => n,
+    };
+
+    let attachment_id = record_id::generate();
+    let storage_key = format!("attachments/{}/{}", budget_id, attachment_id);
+    let size_bytes = data.len() as i64;
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let attachment = match web::block(move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let new_attachment = NewEntryAttachment {
+            id: attachment_id,
+            entry_id,
+            budget_id,
+            user_id,
+            file_name: &file_name,
+            content_type,
+            size_bytes,
+            storage_key: &storage_key,
+            created_timestamp: chrono::Utc::now().naive_utc(),
+        };
+
+        db::entry_attachment::add_attachment(&db_connection, &new_attachment, MAX_ENTRY_ATTACHMENTS)
+    })
+    .await?
+    {
+        Ok(Some(a)) => a,
+        Ok(None) => {
+            return Err(ServerError::InputRejected(Some(
+                "An entry can have at most 10 attachments",
+            )))
+        }
+        Err(diesel::result::Error::NotFound) => {
+            return Err(ServerError::NotFound(Some("No entry with provided ID")))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to add attachment",
+            ))
+        }
+    };
+
+    if let Err(e) = storage::BLOB_STORE.put(&attachment.storage_key, data).await {
+        error!("Failed to store attachment {}: {}", attachment.id, e);
+
+        // Without its file the record is useless, so it is removed rather than left dangling
+        let _ = web::block(move || {
+            let db_connection = db_thread_pool
+                .get()
+                .expect("Failed to access database thread pool");
+            db::entry_attachment::delete_attachment(&db_connection, budget_id, attachment_id)
+        })
+        .await;
+
+        return Err(ServerError::InternalError(Some(
+            "Failed to store attachment",
+        )));
+    }
+
+    Ok(HttpResponse::Created().json(attachment))
+}
+
+pub async fn download_entry_attachment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    attachment_id: web::Json<InputEntryAttachmentId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        attachment_id.budget_id,
+    )
+    .await?;
+
+    let attachment = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::entry_attachment::get_attachment(
+            &db_connection,
+            attachment_id.budget_id,
+            attachment_id.attachment_id,
+        )
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(diesel::result::Error::NotFound) => {
+            return Err(ServerError::NotFound(Some(
+                "No attachment with provided ID",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get attachment",
+            ))
+        }
+    };
+
+    let data = match storage::BLOB_STORE.get(&attachment.storage_key).await {
+        Ok(d) => d,
+        Err(StorageError::NotFound) => {
+            error!(
+                "File for attachment {} is missing from storage",
+                attachment.id
+            );
+            return Err(ServerError::NotFound(Some(
+                "No attachment with provided ID",
+            )));
+        }
+        Err(e) => {
+            error!("Failed to load attachment {}: {}", attachment.id, e);
+            return Err(ServerError::InternalError(Some(
+                "Failed to load attachment",
+            )));
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(attachment.content_type)
+        .insert_header((
+            "content-disposition",
+            format!("attachment; filename=\"{}\"", attachment.file_name),
+        ))
+        .body(data))
+}
+
+pub async fn delete_entry_attachment(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    attachment_id: web::Json<InputEntryAttachmentId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        attachment_id.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let attachment = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::entry_attachment::delete_attachment(
+            &db_connection,
+            attachment_id.budget_id,
+            attachment_id.attachment_id,
+        )
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(diesel::result::Error::NotFound) => {
+            return Err(ServerError::NotFound(Some(
+                "No attachment with provided ID",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete attachment",
+            ))
+        }
+    };
+
+    // The record is already gone, so a file left behind is only logged
+    if let Err(e) = storage::BLOB_STORE.delete(&attachment.storage_key).await {
+        error!(
+            "Failed to delete file {} for attachment {}: {}",
+            attachment.storage_key, attachment.id, e
+        );
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Files are uploaded straight to storage the same way statements to import are. The key storage
+// returns is then added as a resource.
+pub async fn get_resource_upload_token(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let upload_token = match auth_token::generate_upload_token(
+        auth_token::TokenParams {
+            user_id: &auth_user_claims.0.uid,
+        },
+        UploadScope::Resource {
+            budget_id: budget_id.budget_id,
+        },
+    ) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("{}", e);
+            return Err(ServerError::InternalError(Some(
+                "Failed to generate upload token",
+            )));
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(UploadToken {
+        upload_token: upload_token.to_string(),
+    }))
+}
+
+pub async fn rollback_import(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    import_batch_id: web::Json<InputImportBatchId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::import::rollback_import_batch(
+            &db_connection,
+            auth_user_claims.0.uid,
+            import_batch_id.import_batch_id,
+        )
+    })
+    .await?
+    {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err(ServerError::NotFound(Some(
+                "No import batch with provided ID that can be rolled back",
+            )));
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to roll back import",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(OutputBulkDeletion { deleted_count }))
+}
+
+async fn get_envelope_summary_for_budget(
+    db_thread_pool: web::Data<DbThreadPool>,
+    budget_id: Uuid,
+) -> Result<OutputEnvelopeSummary, ServerError> {
+    let summary = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        if !db::envelope::is_envelope_budget(&db_connection, budget_id)? {
+            return Ok(None);
+        }
+
+        db::envelope::get_envelope_summary(&db_connection, budget_id).map(Some)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get envelope summary",
+            ))
+        }
+    };
+
+    match summary {
+        Some(s) => Ok(s),
+        None => Err(ServerError::InvalidFormat(Some(
+            "Budget does not use envelope budgeting",
+        ))),
+    }
+}
+
+pub async fn create_shopping_list(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    shopping_list_data: web::Json<InputShoppingList>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        shopping_list_data.budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let shopping_list = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::create_shopping_list(&db_connection, &shopping_list_data)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some(
+                    "Budget has no category with provided ID",
+                )));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create shopping list",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(shopping_list))
+}
+
+pub async fn get_shopping_lists(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    ensure_user_in_budget(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id.budget_id,
+    )
+    .await?;
+
+    let shopping_lists = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::get_shopping_lists_for_budget(&db_connection, budget_id.budget_id)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get shopping lists",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(shopping_lists))
+}
+
+pub async fn delete_shopping_list(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    shopping_list_id: web::Json<InputShoppingListId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::delete_shopping_list(
+            &db_connection,
+            auth_user_claims.0.uid,
+            shopping_list_id.shopping_list_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete shopping list",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No shopping list with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn add_shopping_list_item(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    item_data: web::Json<InputShoppingListItem>,
+) -> Result<HttpResponse, ServerError> {
+    if item_data.estimated_cost_cents < 0 {
+        return Err(ServerError::InputRejected(Some(
+            "Estimated cost cannot be negative",
+        )));
+    }
+
+    let item = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::add_item(&db_connection, auth_user_claims.0.uid, &item_data)
+    })
+    .await?
+    {
+        Ok(i) => i,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some(
+                    "No open shopping list with provided ID",
+                )));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to add shopping list item",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(item))
+}
+
+pub async fn edit_shopping_list_item(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    item_data: web::Json<InputEditShoppingListItem>,
+) -> Result<HttpResponse, ServerError> {
+    if item_data.estimated_cost_cents < 0 {
+        return Err(ServerError::InputRejected(Some(
+            "Estimated cost cannot be negative",
+        )));
+    }
+
+    let edited_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::edit_item(&db_connection, auth_user_claims.0.uid, &item_data)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to edit shopping list item",
+                ))
+            }
+        },
+    };
+
+    if edited_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No shopping list item with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn delete_shopping_list_item(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    item_id: web::Json<InputShoppingListItemId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::delete_item(&db_connection, auth_user_claims.0.uid, item_id.item_id)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete shopping list item",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No shopping list item with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn complete_shopping_list(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    completion_data: web::Json<InputCompleteShoppingList>,
+) -> Result<HttpResponse, ServerError> {
+    let entry = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::shopping_list::complete_shopping_list(
+            &db_connection,
+            auth_user_claims.0.uid,
+            &completion_data,
+        )
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some(
+                    "No open shopping list with provided ID",
+                )));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to complete shopping list",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(entry))
+}
+
+pub async fn invite_user(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    invitation_info: web::Json<UserInvitationToBudget>,
+) -> Result<HttpResponse, ServerError> {
+    let inviting_user_id = auth_user_claims.0.uid;
+
+    if invitation_info.invitee_user_id == inviting_user_id {
+        return Err(ServerError::InputRejected(Some(
+            "Cannot invite yourself to a budget",
+        )));
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        inviting_user_id,
+        invitation_info.budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let db_thread_pool_copy = db_thread_pool.clone();
+    let invitee_user_id = invitation_info.invitee_user_id;
+    let budget_id = invitation_info.budget_id;
+
+    let is_invitee_in_budget = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget::check_user_in_budget(&db_connection, invitee_user_id, budget_id)
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get budget data",
+            ))
+        }
+    };
+
+    if is_invitee_in_budget {
+        return Err(ServerError::AlreadyExists(Some(
+            "User is already a member of the budget",
+        )));
+    }
+
+    enforce_daily_limit(
+        db_thread_pool.clone(),
+        inviting_user_id,
+        DailyAction::BudgetInvitation,
+    )
+    .await?;
+
+    let share_event = match logging::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget_share::invite_user(
+            &db_connection,
+            invitation_info.budget_id,
+            invitation_info.invitee_user_id,
+            inviting_user_id,
+        )
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => {
+                return Err(ServerError::AlreadyExists(Some(
+                    "User has already been invited to the budget",
+                )));
+            }
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::ForeignKeyViolation,
+                _,
+            ) => {
+                return Err(ServerError::NotFound(Some("No user with provided ID")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to share budget",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(share_event))
+}
+
+// Only owners can change roles. An owner can step down as long as another owner remains.
+pub async fn change_member_role(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    member_role: web::Json<InputBudgetMemberRole>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = member_role.budget_id;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        auth_user_claims.0.uid,
+        budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let role = match BudgetRole::try_from(member_role.role) {
+        Ok(r) => r,
+        Err(_) => return Err(ServerError::InvalidFormat(Some("Invalid role"))),
+    };
+
+    let updated_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget::set_user_role(&db_connection, budget_id, member_role.user_id, role)
+    })
+    .await?
+    {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err(ServerError::InputRejected(Some(
+                "Budget must keep at least one owner",
+            )));
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to change member role",
+            ))
+        }
+    };
+
+    if updated_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No budget member with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn retract_invitation(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    invitation_id: web::Json<InputBudgetShareEventId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget_share::delete_invitation(
+            &db_connection,
+            invitation_id.share_event_id,
+            auth_user_claims.0.uid,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete invitation",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No pending invitation with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn accept_invitation(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    invitation_id: web::Json<InputBudgetShareEventId>,
+) -> Result<HttpResponse, ServerError> {
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let share_event = match web::block(move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget_share::accept_invitation(
+            &db_connection,
+            invitation_id.share_event_id,
+            auth_user_claims.0.uid,
+        )
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some(
+                    "No pending invitation with provided ID",
+                )));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to accept invitation",
+                ))
+            }
+        },
+    };
+
+    live_updates::publish(
+        db_thread_pool,
+        share_event.budget_id,
+        BudgetEvent::ShareAccepted {
+            user_id: share_event.recipient_user_id,
+        },
+    );
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn decline_invitation(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    invitation_id: web::Json<InputBudgetShareEventId>,
+) -> Result<HttpResponse, ServerError> {
+    let declined_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget_share::decline_invitation(
+            &db_connection,
+            invitation_id.share_event_id,
+            auth_user_claims.0.uid,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to decline invitation",
+            ))
+        }
+    };
+
+    if declined_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No pending invitation with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+fn output_invitation(
+    invite: BudgetShareEventWithSharer,
+    budget: Option<OutputInvitationBudget>,
+) -> OutputInvitation {
+    OutputInvitation {
+        id: invite.share_event.id,
+        recipient_user_id: invite.share_event.recipient_user_id,
+        sharer_user_id: invite.share_event.sharer_user_id,
+        sharer_first_name: invite.sharer_first_name,
+        sharer_last_name: invite.sharer_last_name,
+        accepted: invite.share_event.accepted,
+        budget,
+        share_timestamp: invite.share_event.share_timestamp,
+        accepted_declined_timestamp: invite.share_event.accepted_declined_timestamp,
+    }
+}
+
+// Pending invitations only name the sharer. The budget is disclosed once an invitation is accepted.
+pub async fn get_all_pending_invitations_for_user(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let invites = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget_share::get_all_pending_invitations_for_user(
+            &db_connection,
+            auth_user_claims.0.uid,
+        )
+    })
+    .await?
+    {
+        Ok(i) => i,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to find invitations",
+            ))
+        }
+    };
+
+    let invites = invites
+        .into_iter()
+        .map(|invite| output_invitation(invite, None))
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(invites))
+}
+
+pub async fn get_all_pending_invitations_made_by_user(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let invites = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget_share::get_all_pending_invitations_made_by_user(
+            &db_connection,
+            auth_user_claims.0.uid,
+        )
+    })
+    .await?
+    {
+        Ok(i) => i,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to find invitations",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(invites))
+}
+
+pub async fn get_invitation(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    invitation_id: web::Json<InputBudgetShareEventId>,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+
+    let invite = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let invite = db::budget_share::get_invitation(
+            &db_connection,
+            invitation_id.share_event_id,
+            user_id,
+        )?;
+
+        // The sharer is already in the budget. The recipient only sees it after accepting.
+        let budget_id = invite.share_event.budget_id;
+        let budget = if invite.share_event.sharer_user_id == user_id || invite.share_event.accepted
+        {
+            let (name, description) =
+                db::budget_share::get_invitation_budget_details(&db_connection, budget_id)?;
+
+            Some(OutputInvitationBudget {
+                id: budget_id,
+                name,
+                description,
+            })
+        } else {
+            None
+        };
+
+        Ok(output_invitation(invite, budget))
+    })
+    .await?
+    {
+        Ok(i) => i,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some("Share event not found")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to find invitations",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(invite))
+}
+
+// The last member to leave a budget takes it with them. If the last owner leaves, the
+// longest-standing remaining member becomes the owner.
+pub async fn leave(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    budget_id: web::Json<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let removed_count = match logging::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget::remove_member(
+            &db_connection,
+            budget_id.budget_id,
+            auth_user_claims.0.uid,
+            None,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to leave budget",
+            ))
+        }
+    };
+
+    if removed_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "User has no budget with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn remove_member(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    member: web::Json<InputBudgetMember>,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+
+    if member.user_id == user_id {
+        return Err(ServerError::InputRejected(Some(
+            "Leave the budget to remove yourself from it",
+        )));
+    }
+
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        member.budget_id,
+        BudgetRole::Owner,
+    )
+    .await?;
+
+    let removed_count = match logging::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget::remove_member(
+            &db_connection,
+            member.budget_id,
+            member.user_id,
+            Some(user_id),
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to remove member from budget",
+            ))
+        }
+    };
+
+    if removed_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No budget member with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[inline]
+pub async fn ensure_user_in_budget(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: Uuid,
+    budget_id: Uuid,
+) -> Result<(), ServerError> {
+    ensure_user_has_budget_role(db_thread_pool, user_id, budget_id, BudgetRole::Viewer).await
+}
+
+// Responds with 404 if the user isn't in the budget and 403 if their role there is lower than
+// `required_role`
+pub async fn ensure_user_has_budget_role(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: Uuid,
+    budget_id: Uuid,
+    required_role: BudgetRole,
+) -> Result<(), ServerError> {
+    let role = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::budget::get_user_budget_role(&db_connection, user_id, budget_id)
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get budget data",
+                ))
+            }
+        },
+    };
+
+    match role {
+        Some(role) if role.allows(required_role) => Ok(()),
+        Some(_) => Err(ServerError::AccessForbidden(Some(
+            "User's role in budget does not allow this",
+        ))),
+        None => Err(ServerError::NotFound(Some(
+            "User has no budget with provided ID",
+        ))),
+    }
+}
+
+// Counts the action against the user's daily cap, which is higher for premium users. Attempts
+// count even if the action then fails.
+async fn enforce_daily_limit(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: Uuid,
+    action: DailyAction,
+) -> Result<(), ServerError> {
+    let (is_premium, action_count) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let user = db::user::get_user_by_id(&db_connection, user_id)?;
+        let action_count =
+            db::daily_action::record_daily_action(&db_connection, user_id, action, today)?;
+
+        Ok::<_, diesel::result::Error>((user.has_premium(today), action_count))
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to check daily limit",
+            ))
+        }
+    };
+
+    let limits = if is_premium {
+        &env::reloadable_conf().daily_limits.premium
+    } else {
+        &env::reloadable_conf().daily_limits.free
+    };
+
+    let (limit, limit_msg) = match action {
+        DailyAction::BudgetInvitation => (
+            limits.budget_invitations,
+            "Daily limit for budget invitations reached. Try again tomorrow.",
+        ),
+        DailyAction::BudgetComment => (
+            limits.budget_comments,
+            "Daily limit for comments reached. Try again tomorrow.",
+        ),
+    };
+
+    if action_count > limit {
+        return Err(ServerError::TooManyRequests(Some(limit_msg)));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::web::{self, Data};
+    use actix_web::{http, test, App};
+    use chrono::{Datelike, NaiveDate};
+    use diesel::prelude::*;
+    use rand::prelude::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::definitions::*;
+    use crate::env;
+    use crate::handlers::budget::{
+        CategoryCapExceeded, EXPORT_CHUNK_SIZE, MAX_BUDGET_RESOURCES, MAX_ENTRY_ATTACHMENTS,
+    };
+    use crate::handlers::error::ServerError;
+    use crate::handlers::request_io::{
+        InputBudget, InputBudgetComment, InputBudgetCommentId, InputBudgetId, InputBudgetMember,
+        InputBudgetMemberRole, InputBudgetMonth, InputBudgetResource, InputBudgetResourceId,
+        InputBudgetShareEventId, InputBudgetSimulation, InputBulkEntryDeletion, InputCategory,
+        InputCategoryHardCap, InputCategoryId, InputCategoryImport, InputColumnMapping,
+        InputCompleteShoppingList, InputDateRange, InputEditBudget, InputEditBudgetComment,
+        InputEditCategory, InputEditEntry, InputEditRecurringEntry, InputEditShoppingListItem,
+        InputEntry, InputEntryAttachmentId, InputEntryAttachments, InputEntryFilter, InputEntryId,
+        InputEntryImport, InputFundAllocation, InputImportBatchId, InputImportedEntry,
+        InputNewCategory, InputRecurringEntry, InputRecurringEntryId, InputReimbursement,
+        InputReimbursementPayment, InputShoppingList, InputShoppingListId, InputShoppingListItem,
+        InputShoppingListItemId, InputSimulatedChange, InputToken, InputUser, OutputAuditLogPage,
+        OutputBudget, OutputBudgetPage, OutputBudgetSummary, OutputBulkDeletion,
+        OutputBulkDeletionPreview, OutputCategoryExport, OutputClosedMonth, OutputCreatedEntry,
+        OutputEntryPage, OutputEnvelopeSummary, OutputInvitation, OutputReimbursementSummary,
+        OutputShoppingList, OutputStatementImport, OutputTokenIntrospection, OutputTrash,
+        SigninToken, SigninTokenOtpPair, TokenPair, UploadToken, UserInvitationToBudget,
+    };
+    use crate::middleware::internal_service::INTERNAL_SERVICE_KEY_HEADER;
+    use crate::models::budget::Budget;
+    use crate::models::budget_comment::BudgetComment;
+    use crate::models::budget_resource::BudgetResource;
+    use crate::models::budget_share_event::BudgetShareEvent;
+    use crate::models::category::Category;
+    use crate::models::entry::Entry;
+    use crate::models::entry_attachment::EntryAttachment;
+    use crate::models::import_batch::ImportBatch;
+    use crate::models::recurring_entry::RecurringEntry;
+    use crate::models::shopping_list::ShoppingList;
+    use crate::models::shopping_list_item::ShoppingListItem;
     use crate::schema::budgets as budget_fields;
     use crate::schema::budgets::dsl::budgets;
+    use crate::schema::categories as category_fields;
+    use crate::schema::categories::dsl::categories;
     use crate::schema::entries as entry_fields;
+    use crate::schema::user_notifications as user_notification_fields;
+    use crate::schema::user_notifications::dsl::user_notifications;
+    use crate::schema::users as user_fields;
+    use crate::schema::users::dsl::users;
     use crate::services;
-    use crate::utils::auth_token::TokenClaims;
+    use crate::utils::auth_token::{self, TokenClaims, TokenError, UploadScope};
+    use crate::utils::currency::{ConfiguredRates, ExchangeRateProvider};
+    use crate::utils::db::audit_log::AuditAction;
+    use crate::utils::db::budget::BudgetRole;
+    use crate::utils::forecasting::{self, BudgetForecast};
+    use crate::utils::live_updates::{self, BudgetEvent, BudgetUpdate};
+    use crate::utils::notification::NotificationType;
+    use crate::utils::record_id;
+    use crate::utils::unusual_amount::EntryWarning;
     use crate::utils::{db, otp};
 
-    pub struct UserAndBudgetWithAuthTokens {
-        budget: OutputBudget,
-        token_pair: TokenPair,
+    pub struct UserAndBudgetWithAuthTokens {
+        budget: OutputBudget,
+        token_pair: TokenPair,
+    }
+
+    pub async fn create_user_and_budget_and_sign_in(
+        db_thread_pool: DbThreadPool,
+    ) -> UserAndBudgetWithAuthTokens {
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
+        let otp_req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .to_request();
+
+        let otp_res = test::call_service(&app, otp_req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(otp_res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let category0 = InputCategory {
+            id: 0,
+            name: format!("First Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#ff11ee"),
+        };
+
+        let category1 = InputCategory {
+            id: 1,
+            name: format!("Second Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#112233"),
+        };
+
+        let budget_categories = vec![category0, category1];
+
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: Some(format!(
+                "This is a description of Test Budget {user_number}.",
+            )),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(
+                2021,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            end_date: NaiveDate::from_ymd(
+                2023,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let create_budget_req = test::TestRequest::post()
+            .uri("/api/budget/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_budget)
+            .to_request();
+
+        let create_budget_res = test::call_service(&app, create_budget_req).await;
+        let create_budget_res_body =
+            String::from_utf8(actix_web::test::read_body(create_budget_res).await.to_vec())
+                .unwrap();
+
+        let budget = serde_json::from_str::<OutputBudget>(create_budget_res_body.as_str()).unwrap();
+
+        UserAndBudgetWithAuthTokens { budget, token_pair }
+    }
+
+    #[actix_rt::test]
+    async fn test_constraint_violations() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        let user_id = user_id_from_token(&access_token);
+
+        let budget_name = format!("Duplicate Categories {}", uuid::Uuid::new_v4());
+        let category = |id, name: &str| InputCategory {
+            id,
+            name: String::from(name),
+            limit_cents: 10000,
+            color: String::from("#ff11ee"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudget {
+                name: budget_name.clone(),
+                description: None,
+                categories: vec![category(0, "Groceries"), category(0, "Rent")],
+                start_date: NaiveDate::from_ymd(2022, 6, 1),
+                end_date: NaiveDate::from_ymd(2022, 6, 30),
+                is_tracking_only: false,
+                is_envelope: false,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body)
+            .contains("Budget already has a category with the given ID"));
+
+        // The budget isn't left behind without its categories
+        let db_connection = db_thread_pool.get().unwrap();
+        let budget_count = budgets
+            .filter(budget_fields::name.eq(&budget_name))
+            .count()
+            .get_result::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(budget_count, 0);
+
+        let missing_budget_error = db::budget::create_entry(
+            &db_connection,
+            &web::Json(InputEntry {
+                id: None,
+                budget_id: uuid::Uuid::new_v4(),
+                amount_cents: 1200,
+                date: NaiveDate::from_ymd(2022, 6, 3),
+                name: None,
+                category: None,
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            }),
+            user_id,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            ServerError::from_database_error(missing_budget_error, "Failed to create entry"),
+            ServerError::NotFound(Some("No budget with provided ID"))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_export_and_import_categories() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let source = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let target = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+
+        let source_token = source.token_pair.access_token.clone();
+        let target_token = target.token_pair.access_token.clone();
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/export/categories")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {target_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: source.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/export/categories")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {source_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: source.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let export = test::read_body_json::<OutputCategoryExport, _>(resp).await;
+        assert_eq!(export.budget_id, source.budget.id);
+        assert_eq!(export.categories.len(), source.budget.categories.len());
+
+        for (exported, category) in export.categories.iter().zip(&source.budget.categories) {
+            assert_eq!(exported.id, category.id);
+            assert_eq!(exported.name, category.name);
+            assert_eq!(exported.limit_cents, category.limit_cents);
+            assert_eq!(exported.color, category.color);
+        }
+
+        let exported_categories = export
+            .categories
+            .iter()
+            .map(|c| InputCategory {
+                id: c.id,
+                name: c.name.clone(),
+                limit_cents: c.limit_cents,
+                color: c.color.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let import = |imported_categories: Vec<InputCategory>| {
+            test::TestRequest::post()
+                .uri("/api/budget/import/categories")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {target_token}")))
+                .set_json(&InputCategoryImport {
+                    budget_id: target.budget.id,
+                    categories: imported_categories,
+                })
+                .to_request()
+        };
+
+        // The target budget already uses the exported IDs
+        let resp = test::call_service(&app, import(exported_categories.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let mut duplicated = exported_categories.clone();
+        duplicated.push(duplicated[0].clone());
+        let resp = test::call_service(&app, import(duplicated)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let mut negative_limit = exported_categories.clone();
+        negative_limit[0].limit_cents = -1;
+        let resp = test::call_service(&app, import(negative_limit)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, import(Vec::new())).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let renumbered = exported_categories
+            .iter()
+            .map(|c| InputCategory {
+                id: c.id + 10,
+                ..c.clone()
+            })
+            .collect::<Vec<_>>();
+        let resp = test::call_service(&app, import(renumbered)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let imported = test::read_body_json::<Vec<Category>, _>(resp).await;
+        assert_eq!(imported.len(), exported_categories.len());
+
+        for (imported, exported) in imported.iter().zip(&exported_categories) {
+            assert_eq!(imported.budget_id, target.budget.id);
+            assert_eq!(imported.id, exported.id + 10);
+            assert_eq!(imported.name, exported.name);
+            assert_eq!(imported.limit_cents, exported.limit_cents);
+            assert_eq!(imported.color, exported.color);
+        }
+
+        // The rejected imports left nothing behind
+        let target_categories = categories
+            .filter(category_fields::budget_id.eq(target.budget.id))
+            .load::<Category>(&db_thread_pool.get().unwrap())
+            .unwrap();
+        assert_eq!(
+            target_categories.len(),
+            target.budget.categories.len() + exported_categories.len()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_budget_resources() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_id = created_user_and_budget.budget.id;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user_access_token = other_user_and_budget.token_pair.access_token;
+
+        let add_resource = |token: &str, kind: i16, title: &str, location: &str| {
+            test::TestRequest::post()
+                .uri("/api/budget/resources/add")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputBudgetResource {
+                    budget_id,
+                    kind,
+                    title: String::from(title),
+                    location: String::from(location),
+                })
+                .to_request()
+        };
+
+        let req = add_resource(
+            &other_user_access_token,
+            0,
+            "Rent agreement",
+            "https://example.com/lease",
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = add_resource(
+            &access_token,
+            0,
+            "Rent agreement",
+            "ftp://example.com/lease",
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = add_resource(&access_token, 0, "   ", "https://example.com/lease");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = add_resource(&access_token, 7, "Rent agreement", "lease.pdf");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = add_resource(
+            &access_token,
+            0,
+            " Rent agreement ",
+            "https://example.com/lease",
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let link = test::read_body_json::<BudgetResource, _>(resp).await;
+        assert_eq!(link.budget_id, budget_id);
+        assert_eq!(link.kind, 0);
+        assert_eq!(link.title, "Rent agreement");
+        assert_eq!(link.location, "https://example.com/lease");
+
+        // Files are uploaded with a resource upload token, which is only good for this budget
+        let req = test::TestRequest::post()
+            .uri("/api/budget/resources/upload_token")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let upload_token = test::read_body_json::<UploadToken, _>(resp)
+            .await
+            .upload_token;
+        assert!(auth_token::validate_upload_token(
+            &upload_token,
+            UploadScope::Resource { budget_id }
+        )
+        .is_ok());
+        assert!(matches!(
+            auth_token::validate_upload_token(&upload_token, UploadScope::Import { budget_id }),
+            Err(TokenError::WrongScope)
+        ));
+
+        let req = add_resource(&access_token, 1, "Receipts", "uploads/receipts.pdf");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let file = test::read_body_json::<BudgetResource, _>(resp).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/resources")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resources = test::read_body_json::<Vec<BudgetResource>, _>(resp).await;
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[0].id, link.id);
+        assert_eq!(resources[1].id, file.id);
+
+        for i in 2..MAX_BUDGET_RESOURCES {
+            let req = add_resource(
+                &access_token,
+                0,
+                &format!("Link {i}"),
+                "https://example.com",
+            );
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+        }
+
+        let req = add_resource(&access_token, 0, "One too many", "https://example.com");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/resources/remove")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&InputBudgetResourceId {
+                budget_id,
+                resource_id: link.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/resources/remove")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetResourceId {
+                budget_id,
+                resource_id: link.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/resources/remove")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetResourceId {
+                budget_id,
+                resource_id: link.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = add_resource(&access_token, 0, "Another link", "https://example.com");
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_create_budget() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
+        let otp_req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .to_request();
+
+        let otp_res = test::call_service(&app, otp_req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(otp_res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let category0 = InputCategory {
+            id: 0,
+            name: format!("First Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#ff11ee"),
+        };
+
+        let category1 = InputCategory {
+            id: 1,
+            name: format!("Second Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#112233"),
+        };
+
+        let budget_categories = vec![category0, category1];
+
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: Some(format!(
+                "This is a description of Test Budget {user_number}.",
+            )),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(
+                2021,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            end_date: NaiveDate::from_ymd(
+                2023,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_budget)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_budget = budgets
+            .filter(budget_fields::name.eq(&new_budget.name))
+            .filter(budget_fields::start_date.eq(&new_budget.start_date))
+            .first::<Budget>(&db_connection)
+            .unwrap();
+
+        let created_categories = Category::belonging_to(&created_budget)
+            .load::<Category>(&db_connection)
+            .unwrap();
+
+        let created_entries = Entry::belonging_to(&created_budget)
+            .load::<Entry>(&db_connection)
+            .unwrap();
+
+        assert_eq!(&new_budget.name, &created_budget.name);
+        assert_eq!(&new_budget.description, &created_budget.description);
+        assert_eq!(&new_budget.start_date, &created_budget.start_date);
+        assert_eq!(&new_budget.end_date, &created_budget.end_date);
+
+        assert!(created_entries.is_empty());
+
+        for i in 0..created_categories.len() {
+            let created_cat = &created_categories[i];
+            let new_cat = &new_budget.categories[i];
+
+            assert_eq!(created_cat.budget_id, created_budget.id);
+            assert_eq!(created_cat.id, new_cat.id);
+            assert_eq!(created_cat.name, new_cat.name);
+            assert_eq!(created_cat.limit_cents, new_cat.limit_cents);
+            assert_eq!(created_cat.color, new_cat.color);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_edit_budget() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_before_edit = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let edit_budget = InputEditBudget {
+            id: budget_before_edit.id.clone(),
+            name: format!("Test Budget user after edit"),
+            description: budget_before_edit.description.clone(),
+            start_date: budget_before_edit.start_date.clone(),
+            end_date: NaiveDate::from_ymd(
+                2024,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&edit_budget)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let budget_after_edit = db::budget::get_budget_by_id(
+            &db_thread_pool.get().unwrap(),
+            budget_before_edit.id.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(&budget_after_edit.name, &edit_budget.name);
+        assert_eq!(&budget_after_edit.description, &edit_budget.description);
+        assert_eq!(&budget_after_edit.start_date, &edit_budget.start_date);
+        assert_eq!(&budget_after_edit.end_date, &edit_budget.end_date);
+    }
+
+    #[actix_rt::test]
+    async fn test_edit_budget_start_cannot_be_after_end() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_before_edit = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let edit_budget = InputEditBudget {
+            id: budget_before_edit.id.clone(),
+            name: format!("Test Budget user after edit"),
+            description: budget_before_edit.description.clone(),
+            start_date: budget_before_edit.start_date.clone(),
+            end_date: NaiveDate::from_ymd(
+                2019,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&edit_budget)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let budget_after_edit = db::budget::get_budget_by_id(
+            &db_thread_pool.get().unwrap(),
+            budget_before_edit.id.clone(),
+        )
+        .unwrap();
+
+        assert_eq!(&budget_after_edit.name, &budget_before_edit.name);
+        assert_eq!(
+            &budget_after_edit.description,
+            &budget_before_edit.description
+        );
+        assert_eq!(
+            &budget_after_edit.start_date,
+            &budget_before_edit.start_date
+        );
+        assert_eq!(&budget_after_edit.end_date, &budget_before_edit.end_date);
+    }
+
+    #[actix_rt::test]
+    async fn test_add_entry() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let entry0 = InputEntry {
+            id: None,
+            budget_id: budget.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: Some(format!("Test Entry 0 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let entry1 = InputEntry {
+            id: None,
+            budget_id: budget.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(7..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let new_entries = vec![entry0.clone(), entry1.clone()];
+
+        let req0 = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_entries[0])
+            .to_request();
+
+        let resp0 = test::call_service(&app, req0).await;
+        assert_eq!(resp0.status(), http::StatusCode::CREATED);
+
+        let req1 = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_entries[1])
+            .to_request();
+
+        let resp1 = test::call_service(&app, req1).await;
+        assert_eq!(resp1.status(), http::StatusCode::CREATED);
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let budget_id = InputBudgetId {
+            budget_id: budget.id,
+        };
+
+        let fetched_budget_req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(budget_id)
+            .to_request();
+
+        let fetched_budget_resp = test::call_service(&app, fetched_budget_req).await;
+        let fetched_budget_res_body = String::from_utf8(
+            actix_web::test::read_body(fetched_budget_resp)
+                .await
+                .to_vec(),
+        )
+        .unwrap();
+
+        let budget =
+            serde_json::from_str::<OutputBudget>(fetched_budget_res_body.as_str()).unwrap();
+
+        let created_budget = budgets
+            .find(budget.id)
+            .first::<Budget>(&db_connection)
+            .unwrap();
+
+        let created_entries = Entry::belonging_to(&created_budget)
+            .order(entry_fields::date.asc())
+            .load::<Entry>(&db_connection)
+            .unwrap();
+
+        for i in 0..created_entries.len() {
+            assert_eq!(created_entries[i].budget_id, new_entries[i].budget_id);
+            assert_eq!(created_entries[i].amount_cents, new_entries[i].amount_cents);
+            assert_eq!(created_entries[i].date, new_entries[i].date);
+            assert_eq!(created_entries[i].name, new_entries[i].name);
+            assert_eq!(created_entries[i].category, new_entries[i].category);
+            assert_eq!(created_entries[i].note, new_entries[i].note);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_edit_and_delete_entry() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+
+        let new_entry = InputEntry {
+            id: None,
+            budget_id: budget.id,
+            amount_cents: 4200,
+            date: NaiveDate::from_ymd(2022, 6, 12),
+            name: Some(String::from("Dinner")),
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let created_entry = test::read_body_json::<Entry, _>(resp).await;
+
+        let edited_entry = InputEditEntry {
+            entry_id: created_entry.id,
+            amount_cents: 3900,
+            date: NaiveDate::from_ymd(2022, 6, 11),
+            name: Some(String::from("Lunch")),
+            category: Some(1),
+            note: Some(String::from("Paid with cash")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&edited_entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&edited_entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let entry_id = InputEntryId {
+            entry_id: created_entry.id,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&entry_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let fetched_budget = test::read_body_json::<OutputBudget, _>(resp).await;
+
+        assert_eq!(fetched_budget.entries.len(), 1);
+        let fetched_entry = &fetched_budget.entries[0];
+        assert!(fetched_entry.is_deleted);
+        assert_eq!(fetched_entry.amount_cents, edited_entry.amount_cents);
+        assert_eq!(fetched_entry.date, edited_entry.date);
+        assert_eq!(fetched_entry.name, edited_entry.name);
+        assert_eq!(fetched_entry.category, edited_entry.category);
+        assert_eq!(fetched_entry.note, edited_entry.note);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_bulk_delete_entries() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+
+        for (day, category) in [(1, 0), (2, 0), (3, 1), (20, 0)] {
+            let new_entry = InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 1000,
+                date: NaiveDate::from_ymd(2022, 6, day),
+                name: None,
+                category: Some(category),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            let req = test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&new_entry)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+        }
+
+        let filter = InputEntryFilter {
+            budget_id: budget.id,
+            start_date: Some(NaiveDate::from_ymd(2022, 6, 1)),
+            end_date: Some(NaiveDate::from_ymd(2022, 6, 10)),
+            category: Some(0),
+            import_batch_id: None,
+        };
+
+        let dry_run = InputBulkEntryDeletion {
+            filter: filter.clone(),
+            expected_count: None,
+            confirmation_token: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/bulk_delete_entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&dry_run)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/bulk_delete_entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&dry_run)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let preview = test::read_body_json::<OutputBulkDeletionPreview, _>(resp).await;
+        assert_eq!(preview.matched_count, 2);
+        assert_eq!(preview.sample.len(), 2);
+        assert!(preview.sample.iter().all(|e| e.category == Some(0)));
+        let confirmation_token = preview.confirmation_token.unwrap();
+
+        // The token is only good for the filter and count that were previewed
+        let wrong_count = InputBulkEntryDeletion {
+            filter: filter.clone(),
+            expected_count: Some(3),
+            confirmation_token: Some(confirmation_token.clone()),
+        };
+
+        let wrong_filter = InputBulkEntryDeletion {
+            filter: InputEntryFilter {
+                end_date: Some(NaiveDate::from_ymd(2022, 6, 30)),
+                ..filter.clone()
+            },
+            expected_count: Some(2),
+            confirmation_token: Some(confirmation_token.clone()),
+        };
+
+        for deletion in [wrong_count, wrong_filter] {
+            let req = test::TestRequest::post()
+                .uri("/api/budget/bulk_delete_entries")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&deletion)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        }
+
+        let confirmed = InputBulkEntryDeletion {
+            filter: filter.clone(),
+            expected_count: Some(2),
+            confirmation_token: Some(confirmation_token),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/bulk_delete_entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&confirmed)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let deletion = test::read_body_json::<OutputBulkDeletion, _>(resp).await;
+        assert_eq!(deletion.deleted_count, 2);
+
+        // Replaying the confirmed request doesn't delete anything else
+        let req = test::TestRequest::post()
+            .uri("/api/budget/bulk_delete_entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&confirmed)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let fetched_budget = test::read_body_json::<OutputBudget, _>(resp).await;
+
+        assert_eq!(fetched_budget.entries.len(), 4);
+        assert_eq!(
+            fetched_budget
+                .entries
+                .iter()
+                .filter(|e| e.is_deleted)
+                .count(),
+            2
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/bulk_delete_entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&dry_run)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let preview = test::read_body_json::<OutputBulkDeletionPreview, _>(resp).await;
+        assert_eq!(preview.matched_count, 0);
+        assert!(preview.confirmation_token.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_import_entries_and_rollback() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+
+        let import_data = InputEntryImport {
+            budget_id: budget.id,
+            source_name: String::from("statement.csv"),
+            entries: (1..=3)
+                .map(|day| InputImportedEntry {
+                    amount_cents: 1000 * i64::from(day),
+                    date: NaiveDate::from_ymd(2022, 6, day),
+                    name: Some(format!("Card purchase {day}")),
+                    category: Some(0),
+                    note: None,
+                })
+                .collect(),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&import_data)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let empty_import = InputEntryImport {
+            entries: Vec::new(),
+            ..import_data.clone()
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&empty_import)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&import_data)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let import_batch = test::read_body_json::<ImportBatch, _>(resp).await;
+        assert_eq!(import_batch.entry_count, 3);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/batches")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let import_batches = test::read_body_json::<Vec<ImportBatch>, _>(resp).await;
+        assert_eq!(import_batches.len(), 1);
+        assert_eq!(import_batches[0].id, import_batch.id);
+        assert_eq!(import_batches[0].source_name, "statement.csv");
+
+        let import_batch_id = InputImportBatchId {
+            import_batch_id: import_batch.id,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/rollback")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&import_batch_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/rollback")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&import_batch_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let deletion = test::read_body_json::<OutputBulkDeletion, _>(resp).await;
+        assert_eq!(deletion.deleted_count, 3);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/rollback")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&import_batch_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let fetched_budget = test::read_body_json::<OutputBudget, _>(resp).await;
+
+        assert_eq!(fetched_budget.entries.len(), 3);
+        assert!(fetched_budget
+            .entries
+            .iter()
+            .all(|e| e.is_deleted && e.import_batch_id == Some(import_batch.id)));
+    }
+
+    #[actix_rt::test]
+    async fn test_get_import_upload_token() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_id = created_user_and_budget.budget.id;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user_access_token = other_user_and_budget.token_pair.access_token;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/upload_token")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/upload_token")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let upload_token = test::read_body_json::<UploadToken, _>(resp)
+            .await
+            .upload_token;
+
+        let claims =
+            auth_token::validate_upload_token(&upload_token, UploadScope::Import { budget_id })
+                .unwrap();
+        assert_eq!(
+            claims.uid,
+            TokenClaims::from_token_without_validation(&access_token)
+                .unwrap()
+                .uid
+        );
+
+        assert!(matches!(
+            auth_token::validate_upload_token(
+                &upload_token,
+                UploadScope::Import {
+                    budget_id: other_user_and_budget.budget.id
+                },
+            ),
+            Err(TokenError::WrongScope)
+        ));
+
+        // An upload token can't stand in for an access token
+        let req = test::TestRequest::post()
+            .uri("/api/budget/import/batches")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {upload_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/introspect")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                INTERNAL_SERVICE_KEY_HEADER,
+                env::CONF.keys.internal_service_keys[0].as_str(),
+            ))
+            .set_json(&InputToken {
+                token: upload_token,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let introspection = test::read_body_json::<OutputTokenIntrospection, _>(resp).await;
+        assert!(introspection.active);
+        assert_eq!(introspection.token_type.as_deref(), Some("upload"));
+        assert_eq!(introspection.scope, Some(format!("import:{budget_id}")));
+    }
+
+    #[actix_rt::test]
+    async fn test_get_entries_paginated() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        for day in 1..=5 {
+            let entry = InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: rand::thread_rng().gen_range(90..=120000),
+                date: NaiveDate::from_ymd(2022, 3, day),
+                name: None,
+                category: None,
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            let req = test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&entry)
+                .to_request();
+            test::call_service(&app, req).await;
+        }
+
+        let budget_id = InputBudgetId {
+            budget_id: budget.id,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get_entries?limit=2")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&budget_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let page = test::read_body_json::<OutputEntryPage, _>(resp).await;
+        assert!(page.has_more);
+        assert_eq!(
+            page.entries.iter().map(|e| e.date).collect::<Vec<_>>(),
+            vec![
+                NaiveDate::from_ymd(2022, 3, 1),
+                NaiveDate::from_ymd(2022, 3, 2)
+            ]
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get_entries?limit=2&offset=4")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&budget_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let page = test::read_body_json::<OutputEntryPage, _>(resp).await;
+        assert!(!page.has_more);
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].date, NaiveDate::from_ymd(2022, 3, 5));
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get_entries?limit=0")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&budget_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all?limit=1&offset=1")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let page = test::read_body_json::<OutputBudgetPage, _>(resp).await;
+        assert!(page.budgets.is_empty());
+        assert!(!page.has_more);
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all?limit=1")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let page = test::read_body_json::<OutputBudgetPage, _>(resp).await;
+        assert_eq!(page.budgets.len(), 1);
+        assert_eq!(page.budgets[0].id, budget.id);
+        assert_eq!(page.budgets[0].entries.len(), 5);
+        assert!(!page.has_more);
+    }
+
+    #[actix_rt::test]
+    async fn test_archive_and_unarchive_budget() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_id = created_user_and_budget.budget.id;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        assert!(!created_user_and_budget.budget.is_archived);
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user_access_token = other_user_and_budget.token_pair.access_token;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/archive")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/archive")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let page = test::read_body_json::<OutputBudgetPage, _>(resp).await;
+        assert!(page.budgets.is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all?include_archived=true")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let page = test::read_body_json::<OutputBudgetPage, _>(resp).await;
+        assert_eq!(page.budgets.len(), 1);
+        assert_eq!(page.budgets[0].id, budget_id);
+        assert!(page.budgets[0].is_archived);
+
+        // An archived budget can still be opened directly
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let budget = test::read_body_json::<OutputBudget, _>(resp).await;
+        assert!(budget.is_archived);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/unarchive")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let page = test::read_body_json::<OutputBudgetPage, _>(resp).await;
+        assert_eq!(page.budgets.len(), 1);
+        assert!(!page.budgets[0].is_archived);
+    }
+
+    #[actix_rt::test]
+    async fn test_add_edit_and_delete_recurring_entry() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let mut recurring_entry = InputRecurringEntry {
+            budget_id: budget.id,
+            amount_cents: 150000,
+            name: Some(String::from("Rent")),
+            category: Some(0),
+            note: None,
+            frequency: 42,
+            start_date: NaiveDate::from_ymd(2022, 1, 1),
+            end_date: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_recurring_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&recurring_entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        recurring_entry.frequency = 2;
+        recurring_entry.end_date = Some(NaiveDate::from_ymd(2021, 12, 31));
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_recurring_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&recurring_entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        recurring_entry.end_date = None;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_recurring_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&recurring_entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let created = test::read_body_json::<RecurringEntry, _>(resp).await;
+        assert_eq!(created.next_occurrence_date, recurring_entry.start_date);
+
+        let edit = InputEditRecurringEntry {
+            recurring_entry_id: created.id,
+            amount_cents: 160000,
+            name: Some(String::from("Rent")),
+            category: Some(0),
+            note: Some(String::from("New lease")),
+            end_date: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit_recurring_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&edit)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all_recurring_entries")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let all_recurring_entries = test::read_body_json::<Vec<RecurringEntry>, _>(resp).await;
+        assert_eq!(all_recurring_entries.len(), 1);
+        assert_eq!(all_recurring_entries[0].amount_cents, 160000);
+        assert_eq!(all_recurring_entries[0].note.as_deref(), Some("New lease"));
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_recurring_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputRecurringEntryId {
+                recurring_entry_id: created.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_recurring_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputRecurringEntryId {
+                recurring_entry_id: created.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_simulate() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let today = chrono::Utc::now().naive_utc().date();
+        let next_month_start = if today.month() == 12 {
+            NaiveDate::from_ymd(today.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd(today.year(), today.month() + 1, 1)
+        };
+
+        let mut simulation = InputBudgetSimulation {
+            budget_id: budget.id,
+            changes: vec![
+                InputSimulatedChange::ScaleCategory {
+                    category: 0,
+                    percent_change: -120,
+                },
+                InputSimulatedChange::AddRecurringExpense {
+                    amount_cents: 40000,
+                    category: None,
+                    frequency: 2,
+                    start_date: next_month_start,
+                    end_date: None,
+                },
+            ],
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/simulate")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&simulation)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        simulation.changes[0] = InputSimulatedChange::ScaleCategory {
+            category: 0,
+            percent_change: -20,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/simulate")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&simulation)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let forecast = test::read_body_json::<BudgetForecast, _>(resp).await;
+        assert_eq!(forecast.period_end, budget.end_date);
+        assert_eq!(
+            forecast.monthly_outlook.len(),
+            forecasting::OUTLOOK_MONTHS as usize
+        );
+        assert_eq!(forecast.monthly_outlook[0].month_start, next_month_start);
+        assert!(forecast.monthly_outlook[0].projected_cents >= 40000);
+
+        // Nothing from the simulation is saved
+        let recurring_entries = db::recurring_entry::get_recurring_entries_for_budget(
+            &db_thread_pool.get().unwrap(),
+            budget.id,
+        )
+        .unwrap();
+        assert!(recurring_entries.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_convert_currency() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let owner_id = user_id_from_token(&created_user_and_budget.token_pair.access_token);
+
+        let provider = ConfiguredRates;
+        db::exchange_rate::replace_exchange_rates(
+            &db_connection,
+            provider.base_currency(),
+            &provider.fetch_rates().unwrap(),
+        )
+        .unwrap();
+
+        let add_member = |currency: &str| {
+            let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+            let user = db::user::create_user(
+                &db_connection,
+                &web::Json(InputUser {
+                    email: format!("test_user{}@test.com", &user_number),
+                    password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+                    first_name: format!("Test-{}", &user_number),
+                    last_name: format!("User-{}", &user_number),
+                    date_of_birth: NaiveDate::from_ymd(1990, 1, 1),
+                    currency: String::from(currency),
+                }),
+            )
+            .unwrap();
+
+            db::budget::add_user(&db_connection, budget.id, user.id, BudgetRole::Editor).unwrap();
+
+            user.id
+        };
+
+        let add_entry = |user_id, amount_cents| {
+            db::budget::create_entry(
+                &db_connection,
+                &web::Json(InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents,
+                    date: budget.start_date,
+                    name: None,
+                    category: Some(0),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                }),
+                user_id,
+            )
+            .unwrap()
+        };
+
+        let euro_user_id = add_member("EUR");
+        let euro_access_token = auth_token::generate_access_token(auth_token::TokenParams {
+            user_id: &euro_user_id,
+        })
+        .unwrap()
+        .to_string();
+
+        let dollar_entry = add_entry(owner_id, 1000);
+        let euro_entry = add_entry(euro_user_id, 800);
+
+        let get_entries = |convert_currency: bool| {
+            test::TestRequest::post()
+                .uri(&format!(
+                    "/api/budget/get_entries?convert_currency={convert_currency}"
+                ))
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {euro_access_token}")))
+                .set_json(&InputBudgetId {
+                    budget_id: budget.id,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, get_entries(false)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let entry_page = test::read_body_json::<OutputEntryPage, _>(resp).await;
+        let amount_of = |page: &OutputEntryPage, entry_id| {
+            page.entries
+                .iter()
+                .find(|e| e.id == entry_id)
+                .unwrap()
+                .amount_cents
+        };
+        assert_eq!(amount_of(&entry_page, dollar_entry.id), 1000);
+        assert_eq!(amount_of(&entry_page, euro_entry.id), 800);
+
+        let resp = test::call_service(&app, get_entries(true)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let entry_page = test::read_body_json::<OutputEntryPage, _>(resp).await;
+        assert_eq!(amount_of(&entry_page, dollar_entry.id), 800);
+        assert_eq!(amount_of(&entry_page, euro_entry.id), 800);
+
+        // Category limits are in the owner's currency
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get?convert_currency=true")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {euro_access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let converted_budget = test::read_body_json::<OutputBudget, _>(resp).await;
+        for (converted, original) in converted_budget
+            .categories
+            .iter()
+            .zip(budget.categories.iter())
+        {
+            assert_eq!(
+                converted.limit_cents,
+                (original.limit_cents as f64 * 0.8).round() as i64
+            );
+        }
+
+        // Amounts can't be converted from a currency with no known rate
+        let unknown_currency_user_id = add_member("XTS");
+        add_entry(unknown_currency_user_id, 500);
+
+        let resp = test::call_service(&app, get_entries(true)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_import_statement() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        const BOUNDARY: &str = "statement-boundary";
+
+        let multipart_body = |fields: &[(&str, Option<&str>, &str)]| {
+            let mut body = String::new();
+
+            for (name, file_name, value) in fields {
+                body.push_str(&format!("--{BOUNDARY}\r\n"));
+
+                match file_name {
+                    Some(f) => body.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{name}\"; filename=\"{f}\"\r\n\
+                         Content-Type: application/octet-stream\r\n\r\n"
+                    )),
+                    None => body.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"
+                    )),
+                }
+
+                body.push_str(value);
+                body.push_str("\r\n");
+            }
+
+            body.push_str(&format!("--{BOUNDARY}--\r\n"));
+            body
+        };
+
+        let import_request = |body: String| {
+            test::TestRequest::post()
+                .uri("/api/budget/import")
+                .insert_header((
+                    "content-type",
+                    format!("multipart/form-data; boundary={BOUNDARY}"),
+                ))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_payload(body)
+                .to_request()
+        };
+
+        let budget_id = budget.id.to_string();
+        let mapping = serde_json::to_string(&InputColumnMapping {
+            date_column: String::from("Date"),
+            amount_column: String::from("Amount"),
+            name_column: Some(String::from("Description")),
+            note_column: None,
+            date_format: String::from("%Y-%m-%d"),
+            negate_amounts: true,
+        })
+        .unwrap();
+
+        let csv = "Date,Description,Amount\n\
+                   2022-06-01,Grocer,-45.10\n\
+                   2022-06-02,Coffee,-3.50\n\
+                   2022-06-02,Coffee,-3.50\n\
+                   June 3rd,Cinema,-12.00\n";
+
+        // CSV files can't be read without a mapping
+        let resp = test::call_service(
+            &app,
+            import_request(multipart_body(&[
+                ("budget_id", None, &budget_id),
+                ("file", Some("june.csv"), csv),
+            ])),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(
+            &app,
+            import_request(multipart_body(&[
+                ("budget_id", None, &budget_id),
+                ("mapping", None, &mapping),
+                ("file", Some("june.csv"), csv),
+            ])),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let report = test::read_body_json::<OutputStatementImport, _>(resp).await;
+        assert_eq!(report.imported_count, 2);
+        assert_eq!(
+            report
+                .skipped_rows
+                .iter()
+                .map(|s| (s.row, s.reason.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(3, "Matches an earlier row"), (4, "Invalid date")]
+        );
+
+        let import_batch = report.import_batch.unwrap();
+        assert_eq!(import_batch.source_name, "june.csv");
+
+        let db_connection = db_thread_pool.get().unwrap();
+        let mut imported_entries =
+            db::budget::get_live_entries_for_budget_after(&db_connection, budget.id, None, 100)
+                .unwrap()
+                .into_iter()
+                .filter(|e| e.import_batch_id == Some(import_batch.id))
+                .map(|e| (e.date, e.amount_cents, e.name))
+                .collect::<Vec<_>>();
+        imported_entries.sort();
+        assert_eq!(
+            imported_entries,
+            vec![
+                (
+                    NaiveDate::from_ymd(2022, 6, 1),
+                    4510,
+                    Some(String::from("Grocer"))
+                ),
+                (
+                    NaiveDate::from_ymd(2022, 6, 2),
+                    350,
+                    Some(String::from("Coffee"))
+                ),
+            ]
+        );
+
+        // An overlapping OFX statement only brings in the new transaction
+        let ofx = "<OFX><BANKTRANLIST>\
+                   <STMTTRN><DTPOSTED>20220601<TRNAMT>-45.10<NAME>Grocer</STMTTRN>\
+                   <STMTTRN><DTPOSTED>20220605<TRNAMT>-60.00<NAME>Hardware store</STMTTRN>\
+                   </BANKTRANLIST></OFX>";
+
+        let resp = test::call_service(
+            &app,
+            import_request(multipart_body(&[
+                ("budget_id", None, &budget_id),
+                ("file", Some("june.ofx"), ofx),
+            ])),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let report = test::read_body_json::<OutputStatementImport, _>(resp).await;
+        assert_eq!(report.imported_count, 1);
+        assert_eq!(report.skipped_rows.len(), 1);
+        assert_eq!(report.skipped_rows[0].row, 1);
+        assert_eq!(report.skipped_rows[0].reason, "Matches an existing entry");
+
+        // Nothing new to import
+        let resp = test::call_service(
+            &app,
+            import_request(multipart_body(&[
+                ("budget_id", None, &budget_id),
+                ("file", Some("june.ofx"), ofx),
+            ])),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let report = test::read_body_json::<OutputStatementImport, _>(resp).await;
+        assert!(report.import_batch.is_none());
+        assert_eq!(report.imported_count, 0);
+        assert_eq!(report.skipped_rows.len(), 2);
+
+        let resp = test::call_service(
+            &app,
+            import_request(multipart_body(&[
+                ("budget_id", None, &budget_id),
+                ("file", Some("june.pdf"), ofx),
+            ])),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_export_entries() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        let user_id = user_id_from_token(&access_token);
+
+        // Enough entries to take more than one chunk
+        db::import::import_entries(
+            &db_connection,
+            user_id,
+            &InputEntryImport {
+                budget_id: budget.id,
+                source_name: String::from("history.csv"),
+                entries: (0..EXPORT_CHUNK_SIZE)
+                    .map(|i| InputImportedEntry {
+                        amount_cents: i,
+                        date: budget.start_date + chrono::Duration::days(i % 90),
+                        name: None,
+                        category: Some(0),
+                        note: None,
+                    })
+                    .collect(),
+            },
+        )
+        .unwrap();
+
+        let add_entry = || {
+            db::budget::create_entry(
+                &db_connection,
+                &web::Json(InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents: 5000,
+                    date: budget.start_date,
+                    name: None,
+                    category: None,
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                }),
+                user_id,
+            )
+            .unwrap()
+        };
+
+        add_entry();
+        let deleted_entry = add_entry();
+        db::budget::delete_entry(&db_connection, user_id, deleted_entry.id).unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/export/entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/x-ndjson"
+        );
+
+        let body = test::read_body(resp).await;
+        let exported_entries = body
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice::<Entry>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(exported_entries.len() as i64, EXPORT_CHUNK_SIZE + 1);
+        assert!(exported_entries.iter().all(|e| e.id != deleted_entry.id));
+        assert!(exported_entries
+            .windows(2)
+            .all(|w| (w[0].date, w[0].id) < (w[1].date, w[1].id)));
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_access_token = other_user_and_budget.token_pair.access_token;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/export/entries")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_summary() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        let user_id = user_id_from_token(&access_token);
+
+        let entries_to_add = [
+            (Some(0), 1200, budget.start_date),
+            (Some(0), 800, budget.end_date),
+            (None, 300, budget.start_date),
+            // Outside of the budget's date range
+            (Some(1), 5000, budget.start_date.pred()),
+        ];
+
+        for (category, amount_cents, date) in entries_to_add {
+            db::budget::create_entry(
+                &db_connection,
+                &web::Json(InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents,
+                    date,
+                    name: None,
+                    category,
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                }),
+                user_id,
+            )
+            .unwrap();
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/summary")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let summary = test::read_body_json::<OutputBudgetSummary, _>(resp).await;
+
+        // The budget ended in the past, so every day of it has elapsed
+        let days = (budget.end_date - budget.start_date).num_days() + 1;
+        let limit_cents = budget.categories[0].limit_cents + budget.categories[1].limit_cents;
+
+        assert_eq!(summary.days_elapsed, days);
+        assert_eq!(summary.spent_cents, 2300);
+        assert_eq!(summary.limit_cents, Some(limit_cents));
+        assert_eq!(summary.remaining_cents, Some(limit_cents - 2300));
+        assert_eq!(summary.daily_spend_rate_cents, 2300 / days);
+
+        assert_eq!(summary.categories.len(), 3);
+
+        assert_eq!(summary.categories[0].category_id, Some(0));
+        assert_eq!(summary.categories[0].spent_cents, 2000);
+        assert_eq!(summary.categories[0].entry_count, 2);
+        assert_eq!(
+            summary.categories[0].remaining_cents,
+            Some(budget.categories[0].limit_cents - 2000)
+        );
+
+        assert_eq!(summary.categories[1].category_id, Some(1));
+        assert_eq!(summary.categories[1].spent_cents, 0);
+        assert_eq!(summary.categories[1].entry_count, 0);
+        assert_eq!(summary.categories[1].daily_spend_rate_cents, 0);
+
+        assert_eq!(summary.categories[2].category_id, None);
+        assert_eq!(summary.categories[2].spent_cents, 300);
+        assert_eq!(summary.categories[2].limit_cents, None);
+        assert_eq!(summary.categories[2].remaining_cents, None);
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_access_token = other_user_and_budget.token_pair.access_token;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/summary")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_entry_tax_and_tip() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let add_entry = |amount_cents: i64, tax_cents: Option<i64>, tip_cents: Option<i64>| {
+            test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents,
+                    date: budget.start_date,
+                    name: Some(String::from("Dinner")),
+                    category: Some(0),
+                    note: None,
+                    tax_cents,
+                    tip_cents,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, add_entry(5000, Some(500), Some(4600))).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, add_entry(5000, Some(-500), None)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, add_entry(-5000, Some(500), None)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, add_entry(5000, Some(400), Some(900))).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let dinner = test::read_body_json::<Entry, _>(resp).await;
+        assert_eq!(dinner.tax_cents, Some(400));
+        assert_eq!(dinner.tip_cents, Some(900));
+
+        // A refund's tax comes back too
+        let resp = test::call_service(&app, add_entry(-1000, Some(-80), None)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let resp = test::call_service(&app, add_entry(2000, None, None)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let get_summary = || {
+            test::TestRequest::post()
+                .uri("/api/budget/summary")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputBudgetId {
+                    budget_id: budget.id,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, get_summary()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let summary = test::read_body_json::<OutputBudgetSummary, _>(resp).await;
+        assert_eq!(summary.spent_cents, 6000);
+        assert_eq!(summary.tax_cents, 320);
+        assert_eq!(summary.categories[0].tax_cents, 320);
+        assert_eq!(summary.categories[1].tax_cents, 0);
+
+        let edit_entry = |tax_cents: Option<i64>| {
+            test::TestRequest::post()
+                .uri("/api/budget/edit_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputEditEntry {
+                    entry_id: dinner.id,
+                    amount_cents: 5000,
+                    date: dinner.date,
+                    name: dinner.name.clone(),
+                    category: dinner.category,
+                    note: None,
+                    tax_cents,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, edit_entry(Some(5001))).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, edit_entry(Some(650))).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, get_summary()).await;
+        let summary = test::read_body_json::<OutputBudgetSummary, _>(resp).await;
+        assert_eq!(summary.tax_cents, 570);
+    }
+
+    fn user_id_from_token(access_token: &str) -> uuid::Uuid {
+        TokenClaims::from_token_without_validation(access_token)
+            .unwrap()
+            .uid
+    }
+
+    #[actix_rt::test]
+    async fn test_add_edit_and_remove_category() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+
+        let next_id = budget.categories.iter().map(|c| c.id).max().unwrap() + 1;
+
+        let new_category = InputNewCategory {
+            budget_id: budget.id,
+            name: String::from("Travel"),
+            limit_cents: 25000,
+            color: String::from("#22aa88"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_access_token}")))
+            .set_json(&new_category)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputNewCategory {
+                limit_cents: -1,
+                ..new_category.clone()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_category)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let category = test::read_body_json::<Category, _>(resp).await;
+        assert_eq!(category.id, next_id);
+        assert_eq!(category.name, "Travel");
+        assert_eq!(category.limit_cents, 25000);
+
+        let entry = db::budget::create_entry(
+            &db_thread_pool.get().unwrap(),
+            &web::Json(InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 4000,
+                date: NaiveDate::from_ymd(2022, 3, 14),
+                name: Some(String::from("Train tickets")),
+                category: Some(category.id),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            }),
+            user_id_from_token(&access_token),
+        )
+        .unwrap();
+
+        let edited_category = InputEditCategory {
+            budget_id: budget.id,
+            category_id: category.id,
+            name: String::from("Trips"),
+            limit_cents: 30000,
+            color: String::from("#2288aa"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&edited_category)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let category_id = InputCategoryId {
+            budget_id: budget.id,
+            category_id: category.id,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/remove_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&category_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // A removed category can't be edited or removed again
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&edited_category)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/remove_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&category_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        // The removed category's ID isn't given out again
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_category")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&new_category)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        assert_eq!(
+            test::read_body_json::<Category, _>(resp).await.id,
+            next_id + 1
+        );
+
+        let fetched_budget =
+            db::budget::get_budget_by_id(&db_thread_pool.get().unwrap(), budget.id).unwrap();
+
+        let removed_category = fetched_budget
+            .categories
+            .iter()
+            .find(|c| c.id == category.id)
+            .unwrap();
+        assert!(removed_category.is_deleted);
+        assert_eq!(removed_category.name, "Trips");
+        assert_eq!(removed_category.limit_cents, 30000);
+        assert_eq!(removed_category.color, "#2288aa");
+
+        let fetched_entry = fetched_budget
+            .entries
+            .iter()
+            .find(|e| e.id == entry.id)
+            .unwrap();
+        assert!(!fetched_entry.is_deleted);
+        assert_eq!(fetched_entry.category, Some(category.id));
+    }
+
+    #[actix_rt::test]
+    async fn test_reimbursements() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let mut entry_ids = Vec::new();
+        for (name, amount_cents) in [("Client dinner", 4200), ("Payment", -4200), ("Taxi", 1000)] {
+            let req = test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents,
+                    date: budget.start_date,
+                    name: Some(String::from(name)),
+                    category: Some(0),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+            entry_ids.push(test::read_body_json::<Entry, _>(resp).await.id);
+        }
+
+        let (dinner_id, payment_id, taxi_id) = (entry_ids[0], entry_ids[1], entry_ids[2]);
+
+        let reimbursement_request = |uri: &str, entry_id: uuid::Uuid| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputReimbursement {
+                    budget_id: budget.id,
+                    entry_id,
+                })
+                .to_request()
+        };
+
+        let record_payment = |payment_entry_id: uuid::Uuid| {
+            test::TestRequest::post()
+                .uri("/api/budget/reimbursements/record_payment")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputReimbursementPayment {
+                    budget_id: budget.id,
+                    entry_id: dinner_id,
+                    payment_entry_id,
+                })
+                .to_request()
+        };
+
+        // Only expenses can be reimbursed
+        let resp = test::call_service(
+            &app,
+            reimbursement_request("/api/budget/reimbursements/mark", payment_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        for entry_id in [dinner_id, taxi_id] {
+            let resp = test::call_service(
+                &app,
+                reimbursement_request("/api/budget/reimbursements/mark", entry_id),
+            )
+            .await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+        }
+
+        let resp = test::call_service(
+            &app,
+            reimbursement_request("/api/budget/reimbursements/mark", dinner_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(
+            &app,
+            reimbursement_request("/api/budget/reimbursements/submit", dinner_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(
+            &app,
+            reimbursement_request("/api/budget/reimbursements/submit", dinner_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let get_reimbursements = || {
+            test::TestRequest::post()
+                .uri("/api/budget/reimbursements")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputBudgetId {
+                    budget_id: budget.id,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, get_reimbursements()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let summary = test::read_body_json::<OutputReimbursementSummary, _>(resp).await;
+        assert_eq!(summary.outstanding_cents, 1000);
+        assert_eq!(summary.submitted_cents, 4200);
+        assert_eq!(summary.paid_cents, 0);
+        assert_eq!(summary.unpaid.len(), 2);
+
+        // The payment has to be money coming in
+        let resp = test::call_service(&app, record_payment(taxi_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, record_payment(payment_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, record_payment(payment_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, get_reimbursements()).await;
+        let summary = test::read_body_json::<OutputReimbursementSummary, _>(resp).await;
+        assert_eq!(summary.outstanding_cents, 1000);
+        assert_eq!(summary.submitted_cents, 0);
+        assert_eq!(summary.paid_cents, 4200);
+        assert_eq!(summary.unpaid.len(), 1);
+        assert_eq!(summary.unpaid[0].entry.id, taxi_id);
+        assert_eq!(summary.unpaid[0].status, 0);
+
+        let resp = test::call_service(
+            &app,
+            reimbursement_request("/api/budget/reimbursements/unmark", taxi_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(
+            &app,
+            reimbursement_request("/api/budget/reimbursements/unmark", taxi_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, get_reimbursements()).await;
+        let summary = test::read_body_json::<OutputReimbursementSummary, _>(resp).await;
+        assert_eq!(summary.outstanding_cents, 0);
+        assert!(summary.unpaid.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_budget_member_roles() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let owner_access_token = created_user_and_budget.token_pair.access_token.clone();
+        let owner_id = user_id_from_token(&owner_access_token);
+
+        let editor_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+        let editor_id = user_id_from_token(&editor_access_token);
+        let viewer_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+        let viewer_id = user_id_from_token(&viewer_access_token);
+        let outsider_id = user_id_from_token(
+            &create_user_and_budget_and_sign_in(db_thread_pool.clone())
+                .await
+                .token_pair
+                .access_token,
+        );
+
+        let db_connection = db_thread_pool.get().unwrap();
+        db::budget::add_user(&db_connection, budget.id, editor_id, BudgetRole::Editor).unwrap();
+        db::budget::add_user(&db_connection, budget.id, viewer_id, BudgetRole::Viewer).unwrap();
+
+        let add_entry = |access_token: &str| {
+            test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents: 1500,
+                    date: budget.start_date,
+                    name: Some(String::from("Groceries")),
+                    category: Some(0),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
+
+        let change_role = |access_token: &str, user_id: uuid::Uuid, role: i16| {
+            test::TestRequest::post()
+                .uri("/api/budget/change_member_role")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputBudgetMemberRole {
+                    budget_id: budget.id,
+                    user_id,
+                    role,
+                })
+                .to_request()
+        };
+
+        // Viewers can read the budget but not change it
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {viewer_access_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, add_entry(&viewer_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let resp = test::call_service(&app, add_entry(&editor_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry = test::read_body_json::<Entry, _>(resp).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {viewer_access_token}")))
+            .set_json(&InputEntryId { entry_id: entry.id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        // Editors can change entries but not the budget itself
+        let edit_budget = InputEditBudget {
+            id: budget.id,
+            name: String::from("Renamed by an editor"),
+            description: budget.description.clone(),
+            start_date: budget.start_date,
+            end_date: budget.end_date,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {editor_access_token}")))
+            .set_json(&edit_budget)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {editor_access_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: outsider_id,
+                budget_id: budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let resp = test::call_service(
+            &app,
+            change_role(&editor_access_token, viewer_id, BudgetRole::Editor as i16),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        // Only owners can change roles
+        let resp = test::call_service(
+            &app,
+            change_role(&owner_access_token, viewer_id, BudgetRole::Editor as i16),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, add_entry(&viewer_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let resp = test::call_service(&app, change_role(&owner_access_token, viewer_id, 7)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(
+            &app,
+            change_role(&owner_access_token, outsider_id, BudgetRole::Viewer as i16),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        // The last owner can't step down
+        let resp = test::call_service(
+            &app,
+            change_role(&owner_access_token, owner_id, BudgetRole::Viewer as i16),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(
+            &app,
+            change_role(&owner_access_token, editor_id, BudgetRole::Owner as i16),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(
+            &app,
+            change_role(&owner_access_token, owner_id, BudgetRole::Viewer as i16),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, add_entry(&owner_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {editor_access_token}")))
+            .set_json(&edit_budget)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        assert_eq!(
+            db::budget::get_budget_owner_id(&db_connection, budget.id).unwrap(),
+            editor_id
+        );
+
+        // When the last owner leaves, the longest-standing member takes over
+        db::budget::remove_user(&db_connection, budget.id, editor_id).unwrap();
+        assert_eq!(
+            db::budget::get_budget_owner_id(&db_connection, budget.id).unwrap(),
+            owner_id
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_remove_member_and_leave() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let owner_access_token = created_user_and_budget.token_pair.access_token.clone();
+        let owner_id = user_id_from_token(&owner_access_token);
+
+        let removed_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+        let removed_id = user_id_from_token(&removed_access_token);
+        let remaining_access_token = create_user_and_budget_and_sign_in(db_thread_pool.clone())
+            .await
+            .token_pair
+            .access_token;
+        let remaining_id = user_id_from_token(&remaining_access_token);
+
+        let db_connection = db_thread_pool.get().unwrap();
+        db::budget::add_user(&db_connection, budget.id, removed_id, BudgetRole::Editor).unwrap();
+        db::budget::add_user(&db_connection, budget.id, remaining_id, BudgetRole::Editor).unwrap();
+
+        let remove_member = |access_token: &str, user_id: uuid::Uuid| {
+            test::TestRequest::post()
+                .uri("/api/budget/remove_member")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputBudgetMember {
+                    budget_id: budget.id,
+                    user_id,
+                })
+                .to_request()
+        };
+
+        let leave = |access_token: &str| {
+            test::TestRequest::post()
+                .uri("/api/budget/leave")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputBudgetId {
+                    budget_id: budget.id,
+                })
+                .to_request()
+        };
+
+        let count_notifications = |user_id: uuid::Uuid, notification_type: NotificationType| {
+            user_notifications
+                .filter(user_notification_fields::user_id.eq(user_id))
+                .filter(
+                    user_notification_fields::notification_type.eq(i16::from(notification_type)),
+                )
+                .count()
+                .get_result::<i64>(&db_connection)
+                .unwrap()
+        };
+
+        let resp =
+            test::call_service(&app, remove_member(&remaining_access_token, removed_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let resp = test::call_service(&app, remove_member(&owner_access_token, owner_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, remove_member(&owner_access_token, removed_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, remove_member(&owner_access_token, removed_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        assert!(!db::budget::check_user_in_budget(&db_connection, removed_id, budget.id).unwrap());
+        assert_eq!(
+            count_notifications(removed_id, NotificationType::RemovedFromBudget),
+            1
+        );
+
+        let resp = test::call_service(&app, leave(&removed_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        // The last owner leaving hands the budget to the remaining member
+        let resp = test::call_service(&app, leave(&owner_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        assert_eq!(
+            db::budget::get_user_budget_role(&db_connection, remaining_id, budget.id).unwrap(),
+            Some(BudgetRole::Owner)
+        );
+        assert_eq!(
+            count_notifications(remaining_id, NotificationType::MemberLeftBudget),
+            1
+        );
+
+        // The last member leaving deletes the budget
+        let resp = test::call_service(&app, leave(&remaining_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        assert!(budgets
+            .find(budget.id)
+            .first::<Budget>(&db_connection)
+            .optional()
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_category_hard_cap() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let created_member_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let member_access_token = created_member_and_budget.token_pair.access_token.clone();
+        db::budget::add_user(
+            &db_thread_pool.get().unwrap(),
+            budget.id,
+            user_id_from_token(&member_access_token),
+            BudgetRole::Editor,
+        )
+        .unwrap();
+
+        let limit_cents = budget.categories[0].limit_cents;
+
+        let hard_cap = InputCategoryHardCap {
+            budget_id: budget.id,
+            category_id: 0,
+            is_hard_capped: true,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/set_category_hard_cap")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_access_token}")))
+            .set_json(&hard_cap)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/set_category_hard_cap")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&hard_cap)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let mut entry = InputEntry {
+            id: None,
+            budget_id: budget.id,
+            amount_cents: limit_cents - 10,
+            date: NaiveDate::from_ymd(2022, 3, 14),
+            name: Some(String::from("Within the cap")),
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        entry.amount_cents = 25;
+        entry.name = Some(String::from("Over the cap"));
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let cap_exceeded: CategoryCapExceeded = test::read_body_json(resp).await;
+        assert_eq!(cap_exceeded.category, 0);
+        assert_eq!(cap_exceeded.limit_cents, limit_cents);
+        assert_eq!(cap_exceeded.spent_cents, limit_cents - 10);
+        assert_eq!(cap_exceeded.remaining_cents, 10);
+        assert_eq!(cap_exceeded.amount_cents, 25);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry?override_hard_cap=true")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry?override_hard_cap=true")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        entry.category = Some(1);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_category_hard_cap_on_edit_and_restore() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let created_member_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let member_access_token = created_member_and_budget.token_pair.access_token.clone();
+        db::budget::add_user(
+            &db_thread_pool.get().unwrap(),
+            budget.id,
+            user_id_from_token(&member_access_token),
+            BudgetRole::Editor,
+        )
+        .unwrap();
+
+        let limit_cents = budget.categories[0].limit_cents;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/set_category_hard_cap")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputCategoryHardCap {
+                budget_id: budget.id,
+                category_id: 0,
+                is_hard_capped: true,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let add_entry = |amount_cents: i64, category: i16| {
+            test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {member_access_token}")))
+                .set_json(&InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents,
+                    date: NaiveDate::from_ymd(2022, 3, 14),
+                    name: None,
+                    category: Some(category),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, add_entry(limit_cents - 10, 0)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let capped_entry = test::read_body_json::<OutputCreatedEntry, _>(resp)
+            .await
+            .entry;
+
+        let resp = test::call_service(&app, add_entry(25, 1)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let other_entry = test::read_body_json::<OutputCreatedEntry, _>(resp)
+            .await
+            .entry;
+
+        let edit_entry = |entry: &Entry, amount_cents: i64, category: i16, query: &str, token| {
+            test::TestRequest::post()
+                .uri(&format!("/api/budget/edit_entry{query}"))
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEditEntry {
+                    entry_id: entry.id,
+                    amount_cents,
+                    date: entry.date,
+                    name: None,
+                    category: Some(category),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
+
+        // Moving an entry into the capped category
+        let req = edit_entry(&other_entry, 25, 0, "", &member_access_token);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let cap_exceeded: CategoryCapExceeded = test::read_body_json(resp).await;
+        assert_eq!(cap_exceeded.spent_cents, limit_cents - 10);
+        assert_eq!(cap_exceeded.amount_cents, 25);
+
+        // Raising an entry's amount
+        let req = edit_entry(&capped_entry, limit_cents + 5, 0, "", &member_access_token);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let req = edit_entry(
+            &capped_entry,
+            limit_cents + 5,
+            0,
+            "?override_hard_cap=true",
+            &member_access_token,
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = edit_entry(
+            &capped_entry,
+            limit_cents + 5,
+            0,
+            "?override_hard_cap=true",
+            &access_token,
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // Lowering an entry in a category that's over its cap is still allowed
+        let req = edit_entry(&capped_entry, limit_cents - 20, 0, "", &member_access_token);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_access_token}")))
+            .set_json(&InputEntryId {
+                entry_id: capped_entry.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, add_entry(limit_cents - 5, 0)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let restore_entry = |query: &str, token| {
+            test::TestRequest::post()
+                .uri(&format!("/api/budget/restore_entry{query}"))
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEntryId {
+                    entry_id: capped_entry.id,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, restore_entry("", &member_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+
+        let cap_exceeded: CategoryCapExceeded = test::read_body_json(resp).await;
+        assert_eq!(cap_exceeded.spent_cents, limit_cents - 5);
+        assert_eq!(cap_exceeded.amount_cents, limit_cents - 20);
+
+        let req = restore_entry("?override_hard_cap=true", &member_access_token);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let req = restore_entry("?override_hard_cap=true", &access_token);
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let audit_log =
+            db::audit_log::get_audit_log(&db_thread_pool.get().unwrap(), budget.id, 100, 0)
+                .unwrap();
+        let actions = audit_log
+            .events
+            .iter()
+            .map(|e| e.action)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            &actions[..4],
+            &[
+                AuditAction::EntryRestored as i16,
+                AuditAction::EntryAdded as i16,
+                AuditAction::EntryDeleted as i16,
+                AuditAction::EntryEdited as i16,
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_envelope_budgeting() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let budget_id = InputBudgetId {
+            budget_id: budget.id,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/envelope/summary")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&budget_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        diesel::update(budgets.find(budget.id))
+            .set(budget_fields::is_envelope.eq(true))
+            .execute(&db_thread_pool.get().unwrap())
+            .unwrap();
+
+        let mut entry = InputEntry {
+            id: None,
+            budget_id: budget.id,
+            amount_cents: -100000,
+            date: NaiveDate::from_ymd(2022, 3, 1),
+            name: Some(String::from("Paycheck")),
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let mut allocation = InputFundAllocation {
+            budget_id: budget.id,
+            category: 0,
+            amount_cents: 60000,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/envelope/allocate")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&allocation)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        allocation.category = 1;
+        allocation.amount_cents = 50000;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/envelope/allocate")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&allocation)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        allocation.category = 0;
+        allocation.amount_cents = -70000;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/envelope/allocate")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&allocation)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        allocation.category = 9;
+        allocation.amount_cents = 100;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/envelope/allocate")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&allocation)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        entry.amount_cents = 2000;
+        entry.name = Some(String::from("Groceries"));
+        entry.category = Some(0);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/envelope/summary")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&budget_id)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let summary: OutputEnvelopeSummary = test::read_body_json(resp).await;
+        assert_eq!(summary.income_cents, 100000);
+        assert_eq!(summary.allocated_cents, 60000);
+        assert_eq!(summary.to_be_budgeted_cents, 40000);
+        assert_eq!(summary.envelopes.len(), 2);
+        assert_eq!(summary.envelopes[0].allocated_cents, 60000);
+        assert_eq!(summary.envelopes[0].spent_cents, 2000);
+        assert_eq!(summary.envelopes[0].available_cents, 58000);
+        assert_eq!(summary.envelopes[1].allocated_cents, 0);
+        assert_eq!(summary.envelopes[1].available_cents, 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_invite_user_and_accept() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let sharer = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let recipient = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+
+        let sharer_token = sharer.token_pair.access_token.clone();
+        let recipient_token = recipient.token_pair.access_token.clone();
+        let other_user_token = other_user.token_pair.access_token.clone();
+
+        let recipient_id = user_id_from_token(&recipient_token);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: user_id_from_token(&sharer_token),
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: recipient_id,
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: recipient_id,
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let share_event = test::read_body_json::<BudgetShareEvent, _>(resp).await;
+        assert_eq!(share_event.recipient_user_id, recipient_id);
+        assert_eq!(share_event.budget_id, sharer.budget.id);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: recipient_id,
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let db_connection = db_thread_pool.get().unwrap();
+        let notification_count = user_notifications
+            .filter(user_notification_fields::user_id.eq(recipient_id))
+            .filter(
+                user_notification_fields::notification_type
+                    .eq(i16::from(NotificationType::BudgetInvitation)),
+            )
+            .count()
+            .get_result::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(notification_count, 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_invite_user_and_decline() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let sharer = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let recipient = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+
+        let sharer_token = sharer.token_pair.access_token.clone();
+        let recipient_token = recipient.token_pair.access_token.clone();
+        let other_user_token = other_user.token_pair.access_token.clone();
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: user_id_from_token(&recipient_token),
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let share_event = test::read_body_json::<BudgetShareEvent, _>(resp).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/decline_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/decline_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_retract_invitation() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let sharer = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let recipient = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+
+        let sharer_token = sharer.token_pair.access_token.clone();
+        let recipient_token = recipient.token_pair.access_token.clone();
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: user_id_from_token(&recipient_token),
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let share_event = test::read_body_json::<BudgetShareEvent, _>(resp).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/retract_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/retract_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_get_invitations() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let sharer = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let recipient = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+
+        let sharer_token = sharer.token_pair.access_token.clone();
+        let recipient_token = recipient.token_pair.access_token.clone();
+        let other_user_token = other_user.token_pair.access_token.clone();
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: user_id_from_token(&recipient_token),
+                budget_id: sharer.budget.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let share_event = test::read_body_json::<BudgetShareEvent, _>(resp).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all_pending_invitations_for_user")
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let sharer_first_name = users
+            .find(share_event.sharer_user_id)
+            .select(user_fields::first_name)
+            .get_result::<String>(&db_thread_pool.get().unwrap())
+            .unwrap();
+
+        // Until the invitation is accepted, the recipient only learns who sent it
+        let invites = test::read_body_json::<Vec<OutputInvitation>, _>(resp).await;
+        assert_eq!(invites.len(), 1);
+        assert_eq!(invites[0].id, share_event.id);
+        assert_eq!(invites[0].sharer_first_name, sharer_first_name);
+        assert!(invites[0].budget.is_none());
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all_pending_invitations_made_by_user")
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let invites = test::read_body_json::<Vec<BudgetShareEvent>, _>(resp).await;
+        assert_eq!(invites.len(), 1);
+        assert_eq!(invites[0].id, share_event.id);
+
+        let get_invitation = |token: String| {
+            test::TestRequest::post()
+                .uri("/api/budget/get_invitation")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputBudgetShareEventId {
+                    share_event_id: share_event.id,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, get_invitation(sharer_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let invite = test::read_body_json::<OutputInvitation, _>(resp).await;
+        assert_eq!(invite.id, share_event.id);
+        assert_eq!(invite.budget.unwrap().name, sharer.budget.name);
+
+        let resp = test::call_service(&app, get_invitation(recipient_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let invite = test::read_body_json::<OutputInvitation, _>(resp).await;
+        assert_eq!(invite.id, share_event.id);
+        assert_eq!(invite.sharer_first_name, sharer_first_name);
+        assert!(invite.budget.is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // The budget is disclosed once the recipient has accepted
+        let resp = test::call_service(&app, get_invitation(recipient_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let invite = test::read_body_json::<OutputInvitation, _>(resp).await;
+        let budget = invite.budget.unwrap();
+        assert_eq!(budget.id, sharer.budget.id);
+        assert_eq!(budget.name, sharer.budget.name);
+        assert_eq!(budget.description, sharer.budget.description);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_daily_limits() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_id = created_user_and_budget.budget.id;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        let user_id = user_id_from_token(&access_token);
+
+        let free_limits = &env::reloadable_conf().daily_limits.free;
+        let premium_limits = &env::reloadable_conf().daily_limits.premium;
+
+        // Invitations to users that don't exist fail, but still count toward the limit
+        for i in 0..=free_limits.budget_invitations {
+            let req = test::TestRequest::post()
+                .uri("/api/budget/invite")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&UserInvitationToBudget {
+                    invitee_user_id: uuid::Uuid::new_v4(),
+                    budget_id,
+                })
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+
+            if i < free_limits.budget_invitations {
+                assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+            } else {
+                assert_eq!(resp.status(), http::StatusCode::TOO_MANY_REQUESTS);
+            }
+        }
+
+        let create_comment = || async {
+            let req = test::TestRequest::post()
+                .uri("/api/budget/comment/create")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputBudgetComment {
+                    id: None,
+                    budget_id,
+                    text: String::from("Who bought the fancy cheese?"),
+                })
+                .to_request();
+            test::call_service(&app, req).await.status()
+        };
+
+        for _ in 0..free_limits.budget_comments {
+            assert_eq!(create_comment().await, http::StatusCode::CREATED);
+        }
+
+        assert_eq!(create_comment().await, http::StatusCode::TOO_MANY_REQUESTS);
+
+        // Premium users get a higher limit for the rest of the day
+        let db_connection = db_thread_pool.get().unwrap();
+        diesel::update(users.find(user_id))
+            .set(user_fields::is_premium.eq(true))
+            .execute(&db_connection)
+            .unwrap();
+
+        let rejected_so_far = 1;
+        for _ in (free_limits.budget_comments + rejected_so_far)..premium_limits.budget_comments {
+            assert_eq!(create_comment().await, http::StatusCode::CREATED);
+        }
+
+        assert_eq!(create_comment().await, http::StatusCode::TOO_MANY_REQUESTS);
+
+        // Other users have limits of their own
+        let other_user = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                "authorization",
+                format!("bearer {}", other_user.token_pair.access_token),
+            ))
+            .set_json(&InputBudgetComment {
+                id: None,
+                budget_id: other_user.budget.id,
+                text: String::from("First!"),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_budget_comments() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_id = created_user_and_budget.budget.id;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_user_token = other_user.token_pair.access_token.clone();
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetComment {
+                id: None,
+                budget_id,
+                text: String::from("   "),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_token}")))
+            .set_json(&InputBudgetComment {
+                id: None,
+                budget_id,
+                text: String::from("Not my budget"),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetComment {
+                id: None,
+                budget_id,
+                text: String::from("Groceries were high this week"),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let comment = test::read_body_json::<BudgetComment, _>(resp).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputEditBudgetComment {
+                comment_id: comment.id,
+                text: String::from("Groceries were high this month"),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let edited_comment = test::read_body_json::<BudgetComment, _>(resp).await;
+        assert_ne!(edited_comment.id, comment.id);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/list")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let comments = test::read_body_json::<Vec<BudgetComment>, _>(resp).await;
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, edited_comment.id);
+        assert_eq!(comments[0].text, "Groceries were high this month");
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/list")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_user_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/delete")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetCommentId {
+                comment_id: comment.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/delete")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetCommentId {
+                comment_id: edited_comment.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/list")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let comments = test::read_body_json::<Vec<BudgetComment>, _>(resp).await;
+        assert!(comments.is_empty());
     }
 
-    pub async fn create_user_and_budget_and_sign_in(
-        db_thread_pool: DbThreadPool,
-    ) -> UserAndBudgetWithAuthTokens {
+    #[actix_rt::test]
+    async fn test_shopping_list() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(db_thread_pool.clone()))
@@ -633,109 +8358,124 @@ mod tests {
         )
         .await;
 
-        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
-        let new_user = InputUser {
-            email: format!("test_user{}@test.com", &user_number),
-            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
-            first_name: format!("Test-{}", &user_number),
-            last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            currency: String::from("USD"),
-        };
-
-        let create_user_res = test::call_service(
-            &app,
-            test::TestRequest::post()
-                .uri("/api/user/create")
-                .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
-                .to_request(),
-        )
-        .await;
-
-        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
-        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
-            .unwrap()
-            .uid;
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget_id = created_user_and_budget.budget.id;
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
 
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputShoppingList {
+                budget_id,
+                category: 1,
+                name: String::from("Hardware store"),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
 
-        let token_and_otp = SigninTokenOtpPair {
-            signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
-        };
+        let shopping_list = test::read_body_json::<ShoppingList, _>(resp).await;
 
-        let otp_req = test::TestRequest::post()
-            .uri("/api/auth/verify_otp_for_signin")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/add_item")
             .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputShoppingListItem {
+                shopping_list_id: shopping_list.id,
+                name: String::from("Paint"),
+                estimated_cost_cents: -100,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
 
-        let otp_res = test::call_service(&app, otp_req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(otp_res).await;
-        let access_token = token_pair.access_token.to_string();
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/add_item")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputShoppingListItem {
+                shopping_list_id: shopping_list.id,
+                name: String::from("Paint"),
+                estimated_cost_cents: 3500,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
 
-        let category0 = InputCategory {
-            id: 0,
-            name: format!("First Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#ff11ee"),
-        };
+        let item = test::read_body_json::<ShoppingListItem, _>(resp).await;
+        assert!(!item.is_checked);
 
-        let category1 = InputCategory {
-            id: 1,
-            name: format!("Second Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#112233"),
-        };
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/edit_item")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputEditShoppingListItem {
+                item_id: item.id,
+                name: String::from("Paint"),
+                estimated_cost_cents: 4200,
+                is_checked: true,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        let budget_categories = vec![category0, category1];
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/list")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputBudgetId { budget_id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        let new_budget = InputBudget {
-            name: format!("Test Budget {user_number}"),
-            description: Some(format!(
-                "This is a description of Test Budget {user_number}.",
-            )),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(
-                2021,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            end_date: NaiveDate::from_ymd(
-                2023,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-        };
+        let shopping_lists = test::read_body_json::<Vec<OutputShoppingList>, _>(resp).await;
+        assert_eq!(shopping_lists.len(), 1);
+        assert_eq!(shopping_lists[0].items.len(), 1);
+        assert!(shopping_lists[0].items[0].is_checked);
+        assert_eq!(shopping_lists[0].items[0].estimated_cost_cents, 4200);
 
-        let create_budget_req = test::TestRequest::post()
-            .uri("/api/budget/create")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/complete")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&new_budget)
+            .set_json(&InputCompleteShoppingList {
+                shopping_list_id: shopping_list.id,
+                date: NaiveDate::from_ymd(2022, 5, 2),
+                amount_cents: None,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
 
-        let create_budget_res = test::call_service(&app, create_budget_req).await;
-        let create_budget_res_body =
-            String::from_utf8(actix_web::test::read_body(create_budget_res).await.to_vec())
-                .unwrap();
+        let entry = test::read_body_json::<Entry, _>(resp).await;
+        assert_eq!(entry.amount_cents, 4200);
+        assert_eq!(entry.category, Some(1));
 
-        let budget = serde_json::from_str::<OutputBudget>(create_budget_res_body.as_str()).unwrap();
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/delete_item")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputShoppingListItemId { item_id: item.id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
 
-        UserAndBudgetWithAuthTokens { budget, token_pair }
+        let req = test::TestRequest::post()
+            .uri("/api/budget/shopping_list/delete")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputShoppingListId {
+                shopping_list_id: shopping_list.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
     }
 
     #[actix_rt::test]
-    async fn test_create_budget() {
+    async fn test_get_budget() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -745,137 +8485,126 @@ mod tests {
         )
         .await;
 
-        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
-        let new_user = InputUser {
-            email: format!("test_user{}@test.com", &user_number),
-            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
-            first_name: format!("Test-{}", &user_number),
-            last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let created_budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        let budget_categories = created_budget.categories.clone();
+
+        let entry0 = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
                 rand::thread_rng().gen_range(1..=28),
             ),
-            currency: String::from("USD"),
+            name: Some(format!("Test Entry 0 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let create_user_res = test::call_service(
-            &app,
-            test::TestRequest::post()
-                .uri("/api/user/create")
-                .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
-                .to_request(),
-        )
-        .await;
-
-        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
-        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
-            .unwrap()
-            .uid;
-
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
-
-        let token_and_otp = SigninTokenOtpPair {
-            signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
+        let entry1 = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(7..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let otp_req = test::TestRequest::post()
-            .uri("/api/auth/verify_otp_for_signin")
+        let created_entries = vec![entry0.clone(), entry1.clone()];
+
+        let entry0_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&created_entries[0])
             .to_request();
 
-        let otp_res = test::call_service(&app, otp_req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(otp_res).await;
-        let access_token = token_pair.access_token.to_string();
-
-        let category0 = InputCategory {
-            id: 0,
-            name: format!("First Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#ff11ee"),
-        };
+        test::call_service(&app, entry0_req).await;
 
-        let category1 = InputCategory {
-            id: 1,
-            name: format!("Second Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#112233"),
-        };
+        let entry1_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&created_entries[1])
+            .to_request();
 
-        let budget_categories = vec![category0, category1];
+        test::call_service(&app, entry1_req).await;
 
-        let new_budget = InputBudget {
-            name: format!("Test Budget {user_number}"),
-            description: Some(format!(
-                "This is a description of Test Budget {user_number}.",
-            )),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(
-                2021,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            end_date: NaiveDate::from_ymd(
-                2023,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
+        let input_budget_id = InputBudgetId {
+            budget_id: created_budget.id,
         };
 
         let req = test::TestRequest::post()
-            .uri("/api/budget/create")
+            .uri("/api/budget/get")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&new_budget)
+            .set_json(input_budget_id)
             .to_request();
 
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
 
-        let db_connection = db_thread_pool.get().unwrap();
+        let res_body = String::from_utf8(actix_web::test::read_body(res).await.to_vec()).unwrap();
+        let budget = serde_json::from_str::<OutputBudget>(res_body.as_str()).unwrap();
 
-        let created_budget = budgets
-            .filter(budget_fields::name.eq(&new_budget.name))
-            .filter(budget_fields::start_date.eq(&new_budget.start_date))
-            .first::<Budget>(&db_connection)
-            .unwrap();
+        assert_eq!(budget.id, created_budget.id);
+        assert_eq!(budget.is_shared, created_budget.is_shared);
+        assert_eq!(budget.is_private, created_budget.is_private);
+        assert_eq!(budget.is_deleted, created_budget.is_deleted);
+        assert_eq!(budget.name, created_budget.name);
+        assert_eq!(budget.description, created_budget.description);
+        assert_eq!(budget.start_date, created_budget.start_date);
+        assert_eq!(budget.end_date, created_budget.end_date);
 
-        let created_categories = Category::belonging_to(&created_budget)
-            .load::<Category>(&db_connection)
-            .unwrap();
+        assert!(budget.latest_entry_time > created_budget.latest_entry_time);
 
-        let created_entries = Entry::belonging_to(&created_budget)
-            .load::<Entry>(&db_connection)
-            .unwrap();
+        assert_eq!(budget.modified_timestamp, created_budget.modified_timestamp);
+        assert_eq!(budget.created_timestamp, created_budget.created_timestamp);
 
-        assert_eq!(&new_budget.name, &created_budget.name);
-        assert_eq!(&new_budget.description, &created_budget.description);
-        assert_eq!(&new_budget.start_date, &created_budget.start_date);
-        assert_eq!(&new_budget.end_date, &created_budget.end_date);
+        assert!(!budget.categories.is_empty());
+        assert_eq!(budget.categories.len(), created_budget.categories.len());
 
-        assert!(created_entries.is_empty());
+        for i in 0..budget_categories.len() {
+            let fetched_cat = &budget.categories[i];
+            let created_cat = &created_budget.categories[i];
 
-        for i in 0..created_categories.len() {
-            let created_cat = &created_categories[i];
-            let new_cat = &new_budget.categories[i];
+            assert_eq!(fetched_cat.pk, created_cat.pk);
+            assert_eq!(fetched_cat.budget_id, created_cat.budget_id);
+            assert_eq!(fetched_cat.id, created_cat.id);
+            assert_eq!(fetched_cat.name, created_cat.name);
+            assert_eq!(fetched_cat.limit_cents, created_cat.limit_cents);
+            assert_eq!(fetched_cat.color, created_cat.color);
+        }
 
-            assert_eq!(created_cat.budget_id, created_budget.id);
-            assert_eq!(created_cat.id, new_cat.id);
-            assert_eq!(created_cat.name, new_cat.name);
-            assert_eq!(created_cat.limit_cents, new_cat.limit_cents);
-            assert_eq!(created_cat.color, new_cat.color);
+        for i in 0..created_entries.len() {
+            assert_eq!(
+                budget.entries[i].amount_cents,
+                created_entries[i].amount_cents
+            );
+            assert_eq!(budget.entries[i].date, created_entries[i].date);
+            assert_eq!(budget.entries[i].name, created_entries[i].name);
+            assert_eq!(budget.entries[i].category, created_entries[i].category);
+            assert_eq!(budget.entries[i].note, created_entries[i].note);
         }
     }
 
     #[actix_rt::test]
-    async fn test_edit_budget() {
+    async fn test_get_all_budgets_for_user() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -887,101 +8616,233 @@ mod tests {
 
         let created_user_and_budget =
             create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let budget_before_edit = created_user_and_budget.budget.clone();
+        let created_budget0 = created_user_and_budget.budget.clone();
         let access_token = created_user_and_budget.token_pair.access_token.clone();
 
-        let edit_budget = InputEditBudget {
-            id: budget_before_edit.id.clone(),
-            name: format!("Test Budget user after edit"),
-            description: budget_before_edit.description.clone(),
-            start_date: budget_before_edit.start_date.clone(),
+        let mut budget_categories = Vec::new();
+        budget_categories.push(InputCategory {
+            id: created_budget0.categories[0].id,
+            name: created_budget0.categories[0].name.clone(),
+            limit_cents: created_budget0.categories[0].limit_cents,
+            color: created_budget0.categories[0].color.clone(),
+        });
+
+        budget_categories.push(InputCategory {
+            id: created_budget0.categories[1].id,
+            name: created_budget0.categories[1].name.clone(),
+            limit_cents: created_budget0.categories[1].limit_cents,
+            color: created_budget0.categories[1].color.clone(),
+        });
+
+        let new_budget1 = InputBudget {
+            name: format!("Test Budget user"),
+            description: Some(format!("This is a description of Test Budget user.",)),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
             end_date: NaiveDate::from_ymd(
-                2024,
+                2023,
                 rand::thread_rng().gen_range(1..=12),
                 rand::thread_rng().gen_range(1..=28),
             ),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
-        let req = test::TestRequest::post()
-            .uri("/api/budget/edit")
+        let create_budget1_req = test::TestRequest::post()
+            .uri("/api/budget/create")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&edit_budget)
+            .set_json(&new_budget1)
             .to_request();
 
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::OK);
-
-        let budget_after_edit = db::budget::get_budget_by_id(
-            &db_thread_pool.get().unwrap(),
-            budget_before_edit.id.clone(),
+        let create_budget1_resp = test::call_service(&app, create_budget1_req).await;
+        let create_budget1_res_body = String::from_utf8(
+            actix_web::test::read_body(create_budget1_resp)
+                .await
+                .to_vec(),
         )
         .unwrap();
 
-        assert_eq!(&budget_after_edit.name, &edit_budget.name);
-        assert_eq!(&budget_after_edit.description, &edit_budget.description);
-        assert_eq!(&budget_after_edit.start_date, &edit_budget.start_date);
-        assert_eq!(&budget_after_edit.end_date, &edit_budget.end_date);
-    }
+        let created_budget1 =
+            serde_json::from_str::<OutputBudget>(create_budget1_res_body.as_str()).unwrap();
 
-    #[actix_rt::test]
-    async fn test_edit_budget_start_cannot_be_after_end() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let created_budgets = vec![created_budget0.clone(), created_budget1.clone()];
 
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(db_thread_pool.clone()))
-                .configure(services::api::configure),
-        )
-        .await;
+        let entry0 = InputEntry {
+            id: None,
+            budget_id: created_budget0.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: Some(format!("Test Entry 0 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
 
-        let created_user_and_budget =
-            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let budget_before_edit = created_user_and_budget.budget.clone();
-        let access_token = created_user_and_budget.token_pair.access_token.clone();
+        let entry1 = InputEntry {
+            id: None,
+            budget_id: created_budget0.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(7..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
 
-        let edit_budget = InputEditBudget {
-            id: budget_before_edit.id.clone(),
-            name: format!("Test Budget user after edit"),
-            description: budget_before_edit.description.clone(),
-            start_date: budget_before_edit.start_date.clone(),
-            end_date: NaiveDate::from_ymd(
-                2019,
-                rand::thread_rng().gen_range(1..=12),
+        let entry2 = InputEntry {
+            id: None,
+            budget_id: created_budget1.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
                 rand::thread_rng().gen_range(1..=28),
             ),
+            name: Some(format!("Test Entry 2 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let entry3 = InputEntry {
+            id: None,
+            budget_id: created_budget1.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(7..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let req = test::TestRequest::post()
-            .uri("/api/budget/edit")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&edit_budget)
-            .to_request();
+        let created_entries = vec![
+            vec![entry0.clone(), entry1.clone()],
+            vec![entry2.clone(), entry3.clone()],
+        ];
+
+        let entry0_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry0)
+            .to_request();
+
+        test::call_service(&app, entry0_req).await;
+
+        let entry1_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry1)
+            .to_request();
+
+        test::call_service(&app, entry1_req).await;
+
+        let entry2_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry2)
+            .to_request();
+
+        test::call_service(&app, entry2_req).await;
+
+        let entry3_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry3)
+            .to_request();
+
+        test::call_service(&app, entry3_req).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/budget/get_all")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let res_body = String::from_utf8(actix_web::test::read_body(resp).await.to_vec()).unwrap();
+        let output_budgets = serde_json::from_str::<OutputBudgetPage>(res_body.as_str())
+            .unwrap()
+            .budgets;
+        assert_eq!(output_budgets.len(), 2);
+
+        for i in 0..output_budgets.len() {
+            let budget = &output_budgets[i];
+            let created_budget = &created_budgets[i];
+
+            assert_eq!(budget.id, created_budget.id);
+            assert_eq!(budget.is_shared, created_budget.is_shared);
+            assert_eq!(budget.is_private, created_budget.is_private);
+            assert_eq!(budget.is_deleted, created_budget.is_deleted);
+            assert_eq!(budget.name, created_budget.name);
+            assert_eq!(budget.description, created_budget.description);
+            assert_eq!(budget.start_date, created_budget.start_date);
+            assert_eq!(budget.end_date, created_budget.end_date);
+
+            assert!(budget.latest_entry_time > created_budget.latest_entry_time);
 
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+            assert_eq!(budget.modified_timestamp, created_budget.modified_timestamp);
+            assert_eq!(budget.created_timestamp, created_budget.created_timestamp);
 
-        let budget_after_edit = db::budget::get_budget_by_id(
-            &db_thread_pool.get().unwrap(),
-            budget_before_edit.id.clone(),
-        )
-        .unwrap();
+            assert!(!budget.categories.is_empty());
+            assert_eq!(budget.categories.len(), created_budget.categories.len());
 
-        assert_eq!(&budget_after_edit.name, &budget_before_edit.name);
-        assert_eq!(
-            &budget_after_edit.description,
-            &budget_before_edit.description
-        );
-        assert_eq!(
-            &budget_after_edit.start_date,
-            &budget_before_edit.start_date
-        );
-        assert_eq!(&budget_after_edit.end_date, &budget_before_edit.end_date);
+            for j in 0..budget_categories.len() {
+                let fetched_cat = &budget.categories[j];
+                let created_cat = &created_budget.categories[j];
+
+                assert_eq!(fetched_cat.pk, created_cat.pk);
+                assert_eq!(fetched_cat.budget_id, created_cat.budget_id);
+                assert_eq!(fetched_cat.id, created_cat.id);
+                assert_eq!(fetched_cat.name, created_cat.name);
+                assert_eq!(fetched_cat.limit_cents, created_cat.limit_cents);
+                assert_eq!(fetched_cat.color, created_cat.color);
+            }
+
+            for j in 0..created_entries[i].len() {
+                assert_eq!(
+                    budget.entries[j].amount_cents,
+                    created_entries[i][j].amount_cents
+                );
+                assert_eq!(budget.entries[j].date, created_entries[i][j].date);
+                assert_eq!(budget.entries[j].name, created_entries[i][j].name);
+                assert_eq!(budget.entries[j].category, created_entries[i][j].category);
+                assert_eq!(budget.entries[j].note, created_entries[i][j].note);
+            }
+        }
     }
 
     #[actix_rt::test]
-    async fn test_add_entry() {
+    async fn test_get_all_budgets_for_user_between_dates() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -993,166 +8854,187 @@ mod tests {
 
         let created_user_and_budget =
             create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let budget = created_user_and_budget.budget.clone();
+        let created_budget = created_user_and_budget.budget.clone();
         let access_token = created_user_and_budget.token_pair.access_token.clone();
 
-        let entry0 = InputEntry {
-            budget_id: budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: Some(format!("Test Entry 0 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
+        diesel::delete(budgets.find(created_budget.id))
+            .execute(&db_thread_pool.get().unwrap())
+            .unwrap();
 
-        let entry1 = InputEntry {
-            budget_id: budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: None,
-            category: None,
-            note: None,
+        let category0 = InputCategory {
+            id: 0,
+            name: format!("First Random Category user"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#ff11ee"),
         };
 
-        let new_entries = vec![entry0.clone(), entry1.clone()];
+        let category1 = InputCategory {
+            id: 1,
+            name: format!("Second Random Category user"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#112233"),
+        };
 
-        let req0 = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&new_entries[0])
-            .to_request();
+        let budget_categories = vec![category0.clone(), category1.clone()];
 
-        let resp0 = test::call_service(&app, req0).await;
-        assert_eq!(resp0.status(), http::StatusCode::CREATED);
+        let too_early_budget = InputBudget {
+            name: format!("Test Budget user"),
+            description: Some(format!("This is a description of Test Budget user.",)),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(2022, 3, 14),
+            end_date: NaiveDate::from_ymd(2022, 3, 30),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
 
-        let req1 = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let create_too_early_budget_req = test::TestRequest::post()
+            .uri("/api/budget/create")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&new_entries[1])
+            .set_json(&too_early_budget)
             .to_request();
 
-        let resp1 = test::call_service(&app, req1).await;
-        assert_eq!(resp1.status(), http::StatusCode::CREATED);
+        let create_too_early_budget_resp =
+            test::call_service(&app, create_too_early_budget_req).await;
+        let create_too_early_budget_res_body = String::from_utf8(
+            actix_web::test::read_body(create_too_early_budget_resp)
+                .await
+                .to_vec(),
+        )
+        .unwrap();
 
-        let db_connection = db_thread_pool.get().unwrap();
+        let created_too_early_budget =
+            serde_json::from_str::<OutputBudget>(create_too_early_budget_res_body.as_str())
+                .unwrap();
 
-        let budget_id = InputBudgetId {
-            budget_id: budget.id,
+        let in_range_budget0 = InputBudget {
+            name: format!("Test Budget user"),
+            description: Some(format!("This is a description of Test Budget user.",)),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(2022, 3, 12),
+            end_date: NaiveDate::from_ymd(2022, 4, 18),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
-        let fetched_budget_req = test::TestRequest::post()
-            .uri("/api/budget/get")
+        let create_in_range_budget0_req = test::TestRequest::post()
+            .uri("/api/budget/create")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(budget_id)
+            .set_json(&in_range_budget0)
             .to_request();
 
-        let fetched_budget_resp = test::call_service(&app, fetched_budget_req).await;
-        let fetched_budget_res_body = String::from_utf8(
-            actix_web::test::read_body(fetched_budget_resp)
+        let create_in_range_budget0_resp =
+            test::call_service(&app, create_in_range_budget0_req).await;
+        let create_in_range_budget0_res_body = String::from_utf8(
+            actix_web::test::read_body(create_in_range_budget0_resp)
                 .await
                 .to_vec(),
         )
         .unwrap();
 
-        let budget =
-            serde_json::from_str::<OutputBudget>(fetched_budget_res_body.as_str()).unwrap();
-
-        let created_budget = budgets
-            .find(budget.id)
-            .first::<Budget>(&db_connection)
-            .unwrap();
-
-        let created_entries = Entry::belonging_to(&created_budget)
-            .order(entry_fields::date.asc())
-            .load::<Entry>(&db_connection)
-            .unwrap();
-
-        for i in 0..created_entries.len() {
-            assert_eq!(created_entries[i].budget_id, new_entries[i].budget_id);
-            assert_eq!(created_entries[i].amount_cents, new_entries[i].amount_cents);
-            assert_eq!(created_entries[i].date, new_entries[i].date);
-            assert_eq!(created_entries[i].name, new_entries[i].name);
-            assert_eq!(created_entries[i].category, new_entries[i].category);
-            assert_eq!(created_entries[i].note, new_entries[i].note);
-        }
-    }
+        let created_in_range_budget0 =
+            serde_json::from_str::<OutputBudget>(create_in_range_budget0_res_body.as_str())
+                .unwrap();
 
-    // #[actix_rt::test]
-    // async fn test_invite_user_and_accept() {
-    //     todo!();
-    // }
+        let in_range_budget1 = InputBudget {
+            name: format!("Test Budget user"),
+            description: Some(format!("This is a description of Test Budget user.",)),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(2022, 4, 8),
+            end_date: NaiveDate::from_ymd(2022, 4, 10),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
 
-    // #[actix_rt::test]
-    // async fn test_cannot_accept_invites_for_another_user() {
-    //     todo!();
-    // }
+        let create_in_range_budget1_req = test::TestRequest::post()
+            .uri("/api/budget/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&in_range_budget1)
+            .to_request();
 
-    // #[actix_rt::test]
-    // async fn test_invite_user_and_decline() {
-    //     todo!();
-    // }
+        let create_in_range_budget1_resp =
+            test::call_service(&app, create_in_range_budget1_req).await;
+        let create_in_range_budget1_res_body = String::from_utf8(
+            actix_web::test::read_body(create_in_range_budget1_resp)
+                .await
+                .to_vec(),
+        )
+        .unwrap();
 
-    // #[actix_rt::test]
-    // async fn test_cannot_decline_invites_for_another_user() {
-    //     todo!();
-    // }
+        let created_in_range_budget1 =
+            serde_json::from_str::<OutputBudget>(create_in_range_budget1_res_body.as_str())
+                .unwrap();
 
-    // #[actix_rt::test]
-    // async fn test_retract_invitation() {
-    //     todo!();
-    // }
+        let in_range_budget2 = InputBudget {
+            name: format!("Test Budget user"),
+            description: Some(format!("This is a description of Test Budget user.",)),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(2022, 4, 9),
+            end_date: NaiveDate::from_ymd(2022, 5, 6),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
 
-    // #[actix_rt::test]
-    // async fn test_cannot_retract_invites_made_by__another_user() {
-    //     todo!();
-    // }
+        let create_in_range_budget2_req = test::TestRequest::post()
+            .uri("/api/budget/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&in_range_budget2)
+            .to_request();
 
-    // #[actix_rt::test]
-    // async fn test_get_all_invitations_for_user() {
-    //     todo!();
-    // }
+        let create_in_range_budget2_resp =
+            test::call_service(&app, create_in_range_budget2_req).await;
+        let create_in_range_budget2_res_body = String::from_utf8(
+            actix_web::test::read_body(create_in_range_budget2_resp)
+                .await
+                .to_vec(),
+        )
+        .unwrap();
 
-    // #[actix_rt::test]
-    // async fn test_get_all_invitations_made_by_user() {
-    //     todo!();
-    // }
+        let created_in_range_budget2 =
+            serde_json::from_str::<OutputBudget>(create_in_range_budget2_res_body.as_str())
+                .unwrap();
 
-    // #[actix_rt::test]
-    // async fn test_get_invitation() {
-    //     // TODO: Test that both the inviter and the invitee can get the invitation, but not another user
-    //     todo!();
-    // }
+        let too_late_budget = InputBudget {
+            name: format!("Test Budget user"),
+            description: Some(format!("This is a description of Test Budget user.",)),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(2022, 4, 22),
+            end_date: NaiveDate::from_ymd(2022, 4, 30),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
 
-    #[actix_rt::test]
-    async fn test_get_budget() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let create_too_late_budget_req = test::TestRequest::post()
+            .uri("/api/budget/create")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&too_late_budget)
+            .to_request();
 
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(db_thread_pool.clone()))
-                .configure(services::api::configure),
+        let create_too_late_budget_resp =
+            test::call_service(&app, create_too_late_budget_req).await;
+        let create_too_late_budget_res_body = String::from_utf8(
+            actix_web::test::read_body(create_too_late_budget_resp)
+                .await
+                .to_vec(),
         )
-        .await;
+        .unwrap();
+
+        let created_too_late_budget =
+            serde_json::from_str::<OutputBudget>(create_too_late_budget_res_body.as_str()).unwrap();
 
-        let created_user_and_budget =
-            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let created_budget = created_user_and_budget.budget.clone();
-        let access_token = created_user_and_budget.token_pair.access_token.clone();
-        let budget_categories = created_budget.categories.clone();
+        let in_range_budgets = vec![
+            created_in_range_budget0.clone(),
+            created_in_range_budget1.clone(),
+            created_in_range_budget2.clone(),
+        ];
 
         let entry0 = InputEntry {
-            budget_id: created_budget.id,
+            id: None,
+            budget_id: created_too_early_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
                 2022,
@@ -1162,10 +9044,14 @@ mod tests {
             name: Some(format!("Test Entry 0 for user")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let entry1 = InputEntry {
-            budget_id: created_budget.id,
+            id: None,
+            budget_id: created_too_early_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
                 2022,
@@ -1175,168 +9061,99 @@ mod tests {
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let created_entries = vec![entry0.clone(), entry1.clone()];
-
-        let entry0_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&created_entries[0])
-            .to_request();
-
-        test::call_service(&app, entry0_req).await;
-
-        let entry1_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&created_entries[1])
-            .to_request();
-
-        test::call_service(&app, entry1_req).await;
-
-        let input_budget_id = InputBudgetId {
-            budget_id: created_budget.id,
+        let entry2 = InputEntry {
+            id: None,
+            budget_id: created_in_range_budget0.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: Some(format!("Test Entry 2 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let req = test::TestRequest::post()
-            .uri("/api/budget/get")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(input_budget_id)
-            .to_request();
-
-        let res = test::call_service(&app, req).await;
-        assert_eq!(res.status(), http::StatusCode::OK);
-
-        let res_body = String::from_utf8(actix_web::test::read_body(res).await.to_vec()).unwrap();
-        let budget = serde_json::from_str::<OutputBudget>(res_body.as_str()).unwrap();
-
-        assert_eq!(budget.id, created_budget.id);
-        assert_eq!(budget.is_shared, created_budget.is_shared);
-        assert_eq!(budget.is_private, created_budget.is_private);
-        assert_eq!(budget.is_deleted, created_budget.is_deleted);
-        assert_eq!(budget.name, created_budget.name);
-        assert_eq!(budget.description, created_budget.description);
-        assert_eq!(budget.start_date, created_budget.start_date);
-        assert_eq!(budget.end_date, created_budget.end_date);
-
-        assert!(budget.latest_entry_time > created_budget.latest_entry_time);
-
-        assert_eq!(budget.modified_timestamp, created_budget.modified_timestamp);
-        assert_eq!(budget.created_timestamp, created_budget.created_timestamp);
-
-        assert!(!budget.categories.is_empty());
-        assert_eq!(budget.categories.len(), created_budget.categories.len());
-
-        for i in 0..budget_categories.len() {
-            let fetched_cat = &budget.categories[i];
-            let created_cat = &created_budget.categories[i];
-
-            assert_eq!(fetched_cat.pk, created_cat.pk);
-            assert_eq!(fetched_cat.budget_id, created_cat.budget_id);
-            assert_eq!(fetched_cat.id, created_cat.id);
-            assert_eq!(fetched_cat.name, created_cat.name);
-            assert_eq!(fetched_cat.limit_cents, created_cat.limit_cents);
-            assert_eq!(fetched_cat.color, created_cat.color);
-        }
-
-        for i in 0..created_entries.len() {
-            assert_eq!(
-                budget.entries[i].amount_cents,
-                created_entries[i].amount_cents
-            );
-            assert_eq!(budget.entries[i].date, created_entries[i].date);
-            assert_eq!(budget.entries[i].name, created_entries[i].name);
-            assert_eq!(budget.entries[i].category, created_entries[i].category);
-            assert_eq!(budget.entries[i].note, created_entries[i].note);
-        }
-    }
-
-    #[actix_rt::test]
-    async fn test_get_all_budgets_for_user() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(db_thread_pool.clone()))
-                .configure(services::api::configure),
-        )
-        .await;
-
-        let created_user_and_budget =
-            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let created_budget0 = created_user_and_budget.budget.clone();
-        let access_token = created_user_and_budget.token_pair.access_token.clone();
-
-        let mut budget_categories = Vec::new();
-        budget_categories.push(InputCategory {
-            id: created_budget0.categories[0].id,
-            name: created_budget0.categories[0].name.clone(),
-            limit_cents: created_budget0.categories[0].limit_cents,
-            color: created_budget0.categories[0].color.clone(),
-        });
-
-        budget_categories.push(InputCategory {
-            id: created_budget0.categories[1].id,
-            name: created_budget0.categories[1].name.clone(),
-            limit_cents: created_budget0.categories[1].limit_cents,
-            color: created_budget0.categories[1].color.clone(),
-        });
-
-        let new_budget1 = InputBudget {
-            name: format!("Test Budget user"),
-            description: Some(format!("This is a description of Test Budget user.",)),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(
+        let entry3 = InputEntry {
+            id: None,
+            budget_id: created_in_range_budget0.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
                 2022,
-                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(7..=12),
                 rand::thread_rng().gen_range(1..=28),
             ),
-            end_date: NaiveDate::from_ymd(
-                2023,
-                rand::thread_rng().gen_range(1..=12),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let entry4 = InputEntry {
+            id: None,
+            budget_id: created_in_range_budget1.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
                 rand::thread_rng().gen_range(1..=28),
             ),
+            name: Some(format!("Test Entry 2 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let create_budget1_req = test::TestRequest::post()
-            .uri("/api/budget/create")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&new_budget1)
-            .to_request();
-
-        let create_budget1_resp = test::call_service(&app, create_budget1_req).await;
-        let create_budget1_res_body = String::from_utf8(
-            actix_web::test::read_body(create_budget1_resp)
-                .await
-                .to_vec(),
-        )
-        .unwrap();
-
-        let created_budget1 =
-            serde_json::from_str::<OutputBudget>(create_budget1_res_body.as_str()).unwrap();
-
-        let created_budgets = vec![created_budget0.clone(), created_budget1.clone()];
+        let entry5 = InputEntry {
+            id: None,
+            budget_id: created_in_range_budget1.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(7..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
 
-        let entry0 = InputEntry {
-            budget_id: created_budget0.id,
+        let entry6 = InputEntry {
+            id: None,
+            budget_id: created_in_range_budget2.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
                 2022,
                 rand::thread_rng().gen_range(1..=6),
                 rand::thread_rng().gen_range(1..=28),
             ),
-            name: Some(format!("Test Entry 0 for user")),
+            name: Some(format!("Test Entry 2 for user")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let entry1 = InputEntry {
-            budget_id: created_budget0.id,
+        let entry7 = InputEntry {
+            id: None,
+            budget_id: created_in_range_budget2.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
                 2022,
@@ -1346,10 +9163,14 @@ mod tests {
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let entry2 = InputEntry {
-            budget_id: created_budget1.id,
+        let entry8 = InputEntry {
+            id: None,
+            budget_id: created_too_late_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
                 2022,
@@ -1359,10 +9180,14 @@ mod tests {
             name: Some(format!("Test Entry 2 for user")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let entry3 = InputEntry {
-            budget_id: created_budget1.id,
+        let entry9 = InputEntry {
+            id: None,
+            budget_id: created_too_late_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
                 2022,
@@ -1372,64 +9197,131 @@ mod tests {
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let created_entries = vec![
-            vec![entry0.clone(), entry1.clone()],
-            vec![entry2.clone(), entry3.clone()],
-        ];
+        let in_range_budget_entries = vec![
+            vec![entry2.clone(), entry3.clone()],
+            vec![entry4.clone(), entry5.clone()],
+            vec![entry6.clone(), entry7.clone()],
+        ];
+
+        let entry0_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry0)
+            .to_request();
+
+        test::call_service(&app, entry0_req).await;
+
+        let entry1_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry1)
+            .to_request();
+
+        test::call_service(&app, entry1_req).await;
+
+        let entry2_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry2)
+            .to_request();
+
+        test::call_service(&app, entry2_req).await;
+
+        let entry3_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry3)
+            .to_request();
+
+        test::call_service(&app, entry3_req).await;
+
+        let entry4_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry4)
+            .to_request();
+
+        test::call_service(&app, entry4_req).await;
 
-        let entry0_req = test::TestRequest::post()
+        let entry5_req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry0)
+            .set_json(&entry5)
             .to_request();
 
-        test::call_service(&app, entry0_req).await;
+        test::call_service(&app, entry5_req).await;
 
-        let entry1_req = test::TestRequest::post()
+        let entry6_req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry1)
+            .set_json(&entry6)
             .to_request();
 
-        test::call_service(&app, entry1_req).await;
+        test::call_service(&app, entry6_req).await;
 
-        let entry2_req = test::TestRequest::post()
+        let entry7_req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry2)
+            .set_json(&entry7)
             .to_request();
 
-        test::call_service(&app, entry2_req).await;
+        test::call_service(&app, entry7_req).await;
 
-        let entry3_req = test::TestRequest::post()
+        let entry8_req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry3)
+            .set_json(&entry8)
             .to_request();
 
-        test::call_service(&app, entry3_req).await;
+        test::call_service(&app, entry8_req).await;
 
-        let req = test::TestRequest::get()
-            .uri("/api/budget/get_all")
+        let entry9_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry9)
+            .to_request();
+
+        test::call_service(&app, entry9_req).await;
+
+        let date_range = InputDateRange {
+            start_date: NaiveDate::from_ymd(2022, 4, 6),
+            end_date: NaiveDate::from_ymd(2022, 4, 12),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get_all_between_dates")
             .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&date_range)
             .to_request();
 
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::OK);
 
         let res_body = String::from_utf8(actix_web::test::read_body(resp).await.to_vec()).unwrap();
-        let output_budgets = serde_json::from_str::<Vec<OutputBudget>>(res_body.as_str()).unwrap();
-        assert_eq!(output_budgets.len(), 2);
+        let output_budgets = serde_json::from_str::<OutputBudgetPage>(res_body.as_str())
+            .unwrap()
+            .budgets;
+
+        assert_eq!(output_budgets.len(), 3);
 
         for i in 0..output_budgets.len() {
             let budget = &output_budgets[i];
-            let created_budget = &created_budgets[i];
+            let created_budget = &in_range_budgets[i];
 
             assert_eq!(budget.id, created_budget.id);
             assert_eq!(budget.is_shared, created_budget.is_shared);
@@ -1460,21 +9352,24 @@ mod tests {
                 assert_eq!(fetched_cat.color, created_cat.color);
             }
 
-            for j in 0..created_entries[i].len() {
+            for j in 0..in_range_budget_entries[i].len() {
                 assert_eq!(
                     budget.entries[j].amount_cents,
-                    created_entries[i][j].amount_cents
+                    in_range_budget_entries[i][j].amount_cents
                 );
-                assert_eq!(budget.entries[j].date, created_entries[i][j].date);
-                assert_eq!(budget.entries[j].name, created_entries[i][j].name);
-                assert_eq!(budget.entries[j].category, created_entries[i][j].category);
-                assert_eq!(budget.entries[j].note, created_entries[i][j].note);
+                assert_eq!(budget.entries[j].date, in_range_budget_entries[i][j].date);
+                assert_eq!(budget.entries[j].name, in_range_budget_entries[i][j].name);
+                assert_eq!(
+                    budget.entries[j].category,
+                    in_range_budget_entries[i][j].category
+                );
+                assert_eq!(budget.entries[j].note, in_range_budget_entries[i][j].note);
             }
         }
     }
 
     #[actix_rt::test]
-    async fn test_get_all_budgets_for_user_between_dates() {
+    async fn test_cant_access_budget_for_another_user() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -1489,467 +9384,1015 @@ mod tests {
         let created_budget = created_user_and_budget.budget.clone();
         let access_token = created_user_and_budget.token_pair.access_token.clone();
 
-        diesel::delete(budgets.find(created_budget.id))
-            .execute(&db_thread_pool.get().unwrap())
-            .unwrap();
+        let mut budget_categories = Vec::new();
+        budget_categories.push(InputCategory {
+            id: created_budget.categories[0].id,
+            name: created_budget.categories[0].name.clone(),
+            limit_cents: created_budget.categories[0].limit_cents,
+            color: created_budget.categories[0].color.clone(),
+        });
 
-        let category0 = InputCategory {
-            id: 0,
-            name: format!("First Random Category user"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#ff11ee"),
+        budget_categories.push(InputCategory {
+            id: created_budget.categories[1].id,
+            name: created_budget.categories[1].name.clone(),
+            limit_cents: created_budget.categories[1].limit_cents,
+            color: created_budget.categories[1].color.clone(),
+        });
+
+        let created_unauth_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let unauth_user_access_token = created_unauth_user_and_budget
+            .token_pair
+            .access_token
+            .clone();
+
+        let entry0 = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(1..=6),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: Some(format!("Test Entry 0 for user")),
+            category: Some(0),
+            note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let category1 = InputCategory {
-            id: 1,
-            name: format!("Second Random Category user"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#112233"),
+        let entry1 = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: rand::thread_rng().gen_range(90..=120000),
+            date: NaiveDate::from_ymd(
+                2022,
+                rand::thread_rng().gen_range(7..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            name: None,
+            category: None,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
-        let budget_categories = vec![category0.clone(), category1.clone()];
+        let created_entries = vec![entry0.clone(), entry1.clone()];
 
-        let too_early_budget = InputBudget {
-            name: format!("Test Budget user"),
-            description: Some(format!("This is a description of Test Budget user.",)),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(2022, 3, 14),
-            end_date: NaiveDate::from_ymd(2022, 3, 30),
-        };
+        let entry0_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&created_entries[0])
+            .to_request();
 
-        let create_too_early_budget_req = test::TestRequest::post()
-            .uri("/api/budget/create")
+        test::call_service(&app, entry0_req).await;
+
+        let entry1_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&too_early_budget)
+            .set_json(&created_entries[1])
             .to_request();
 
-        let create_too_early_budget_resp =
-            test::call_service(&app, create_too_early_budget_req).await;
-        let create_too_early_budget_res_body = String::from_utf8(
-            actix_web::test::read_body(create_too_early_budget_resp)
-                .await
-                .to_vec(),
-        )
-        .unwrap();
+        test::call_service(&app, entry1_req).await;
 
-        let created_too_early_budget =
-            serde_json::from_str::<OutputBudget>(create_too_early_budget_res_body.as_str())
-                .unwrap();
+        let input_budget_id = InputBudgetId {
+            budget_id: created_budget.id,
+        };
 
-        let in_range_budget0 = InputBudget {
-            name: format!("Test Budget user"),
-            description: Some(format!("This is a description of Test Budget user.",)),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(2022, 3, 12),
-            end_date: NaiveDate::from_ymd(2022, 4, 18),
+        let unauth_get_req = test::TestRequest::post()
+            .uri("/api/budget/get")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                "authorization",
+                format!("bearer {unauth_user_access_token}"),
+            ))
+            .set_json(input_budget_id)
+            .to_request();
+
+        let unauth_get_res = test::call_service(&app, unauth_get_req).await;
+        assert_eq!(unauth_get_res.status(), http::StatusCode::NOT_FOUND);
+
+        let unauth_get_res_body =
+            String::from_utf8(actix_web::test::read_body(unauth_get_res).await.to_vec()).unwrap();
+        let _unauth_get_parsed_body =
+            serde_json::from_str::<OutputBudget>(unauth_get_res_body.as_str()).unwrap_err();
+
+        let unauth_entry0_req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                "authorization",
+                format!("bearer {unauth_user_access_token}"),
+            ))
+            .set_json(&created_entries[0])
+            .to_request();
+
+        let unauth_entry0_res = test::call_service(&app, unauth_entry0_req).await;
+        assert_eq!(unauth_entry0_res.status(), http::StatusCode::NOT_FOUND);
+
+        // Make sure the created budget hasn't changed
+
+        let input_budget_id = InputBudgetId {
+            budget_id: created_budget.id,
         };
 
-        let create_in_range_budget0_req = test::TestRequest::post()
-            .uri("/api/budget/create")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/get")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&in_range_budget0)
+            .set_json(input_budget_id)
             .to_request();
 
-        let create_in_range_budget0_resp =
-            test::call_service(&app, create_in_range_budget0_req).await;
-        let create_in_range_budget0_res_body = String::from_utf8(
-            actix_web::test::read_body(create_in_range_budget0_resp)
-                .await
-                .to_vec(),
-        )
-        .unwrap();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
 
-        let created_in_range_budget0 =
-            serde_json::from_str::<OutputBudget>(create_in_range_budget0_res_body.as_str())
-                .unwrap();
+        let res_body = String::from_utf8(actix_web::test::read_body(res).await.to_vec()).unwrap();
+        let budget = serde_json::from_str::<OutputBudget>(res_body.as_str()).unwrap();
+
+        assert_eq!(budget.id, created_budget.id);
+        assert_eq!(budget.is_shared, created_budget.is_shared);
+        assert_eq!(budget.is_private, created_budget.is_private);
+        assert_eq!(budget.is_deleted, created_budget.is_deleted);
+        assert_eq!(budget.name, created_budget.name);
+        assert_eq!(budget.description, created_budget.description);
+        assert_eq!(budget.start_date, created_budget.start_date);
+        assert_eq!(budget.end_date, created_budget.end_date);
+
+        assert!(budget.latest_entry_time > created_budget.latest_entry_time);
+
+        assert_eq!(budget.modified_timestamp, created_budget.modified_timestamp);
+        assert_eq!(budget.created_timestamp, created_budget.created_timestamp);
+
+        assert!(!budget.categories.is_empty());
+        assert_eq!(budget.categories.len(), created_budget.categories.len());
+
+        for i in 0..budget_categories.len() {
+            let fetched_cat = &budget.categories[i];
+            let created_cat = &created_budget.categories[i];
+
+            assert_eq!(fetched_cat.pk, created_cat.pk);
+            assert_eq!(fetched_cat.budget_id, created_cat.budget_id);
+            assert_eq!(fetched_cat.id, created_cat.id);
+            assert_eq!(fetched_cat.name, created_cat.name);
+            assert_eq!(fetched_cat.limit_cents, created_cat.limit_cents);
+            assert_eq!(fetched_cat.color, created_cat.color);
+        }
 
-        let in_range_budget1 = InputBudget {
-            name: format!("Test Budget user"),
-            description: Some(format!("This is a description of Test Budget user.",)),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(2022, 4, 8),
-            end_date: NaiveDate::from_ymd(2022, 4, 10),
-        };
+        for i in 0..created_entries.len() {
+            assert_eq!(
+                budget.entries[i].amount_cents,
+                created_entries[i].amount_cents
+            );
+            assert_eq!(budget.entries[i].date, created_entries[i].date);
+            assert_eq!(budget.entries[i].name, created_entries[i].name);
+            assert_eq!(budget.entries[i].category, created_entries[i].category);
+            assert_eq!(budget.entries[i].note, created_entries[i].note);
+        }
+    }
 
-        let create_in_range_budget1_req = test::TestRequest::post()
-            .uri("/api/budget/create")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&in_range_budget1)
-            .to_request();
+    #[actix_rt::test]
+    async fn test_entry_attachments() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
-        let create_in_range_budget1_resp =
-            test::call_service(&app, create_in_range_budget1_req).await;
-        let create_in_range_budget1_res_body = String::from_utf8(
-            actix_web::test::read_body(create_in_range_budget1_resp)
-                .await
-                .to_vec(),
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
         )
-        .unwrap();
+        .await;
 
-        let created_in_range_budget1 =
-            serde_json::from_str::<OutputBudget>(create_in_range_budget1_res_body.as_str())
-                .unwrap();
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
 
-        let in_range_budget2 = InputBudget {
-            name: format!("Test Budget user"),
-            description: Some(format!("This is a description of Test Budget user.",)),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(2022, 4, 9),
-            end_date: NaiveDate::from_ymd(2022, 5, 6),
-        };
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_access_token = other_user_and_budget.token_pair.access_token.clone();
 
-        let create_in_range_budget2_req = test::TestRequest::post()
-            .uri("/api/budget/create")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&in_range_budget2)
+            .set_json(&InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 2500,
+                date: budget.start_date,
+                name: Some(String::from("Hardware store")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry_id = test::read_body_json::<Entry, _>(resp).await.id;
+
+        const BOUNDARY: &str = "attachment-boundary";
+
+        let upload_request = |entry_id: uuid::Uuid, file_name: &str, contents: &[u8]| {
+            let mut body = Vec::new();
+
+            for (name, value) in [
+                ("budget_id", budget.id.to_string()),
+                ("entry_id", entry_id.to_string()),
+            ] {
+                body.extend_from_slice(
+                    format!(
+                        "--{BOUNDARY}\r\n\
+                         Content-Disposition: form-data; name=\"{name}\"\r\n\r\n\
+                         {value}\r\n"
+                    )
+                    .as_bytes(),
+                );
+            }
 
-        let create_in_range_budget2_resp =
-            test::call_service(&app, create_in_range_budget2_req).await;
-        let create_in_range_budget2_res_body = String::from_utf8(
-            actix_web::test::read_body(create_in_range_budget2_resp)
-                .await
-                .to_vec(),
-        )
-        .unwrap();
+            body.extend_from_slice(
+                format!(
+                    "--{BOUNDARY}\r\n\
+                     Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+                     Content-Type: application/octet-stream\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(contents);
+            body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
 
-        let created_in_range_budget2 =
-            serde_json::from_str::<OutputBudget>(create_in_range_budget2_res_body.as_str())
-                .unwrap();
+            test::TestRequest::post()
+                .uri("/api/budget/attachments/upload")
+                .insert_header((
+                    "content-type",
+                    format!("multipart/form-data; boundary={BOUNDARY}"),
+                ))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_payload(body)
+                .to_request()
+        };
 
-        let too_late_budget = InputBudget {
-            name: format!("Test Budget user"),
-            description: Some(format!("This is a description of Test Budget user.",)),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(2022, 4, 22),
-            end_date: NaiveDate::from_ymd(2022, 4, 30),
+        let attachment_request = |uri: &str, token: &str, attachment_id: uuid::Uuid| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEntryAttachmentId {
+                    budget_id: budget.id,
+                    attachment_id,
+                })
+                .to_request()
         };
 
-        let create_too_late_budget_req = test::TestRequest::post()
-            .uri("/api/budget/create")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&too_late_budget)
-            .to_request();
+        let receipt = [
+            &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A][..],
+            b"receipt image data",
+        ]
+        .concat();
 
-        let create_too_late_budget_resp =
-            test::call_service(&app, create_too_late_budget_req).await;
-        let create_too_late_budget_res_body = String::from_utf8(
-            actix_web::test::read_body(create_too_late_budget_resp)
-                .await
-                .to_vec(),
+        // Types are read from the contents, not the file name
+        let resp = test::call_service(
+            &app,
+            upload_request(entry_id, "receipt.png", b"Date,Amount\n2022-06-01,25.00\n"),
         )
-        .unwrap();
-
-        let created_too_late_budget =
-            serde_json::from_str::<OutputBudget>(create_too_late_budget_res_body.as_str()).unwrap();
-
-        let in_range_budgets = vec![
-            created_in_range_budget0.clone(),
-            created_in_range_budget1.clone(),
-            created_in_range_budget2.clone(),
-        ];
-
-        let entry0 = InputEntry {
-            budget_id: created_too_early_budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: Some(format!("Test Entry 0 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
 
-        let entry1 = InputEntry {
-            budget_id: created_too_early_budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: None,
-            category: None,
-            note: None,
-        };
+        let too_large = [
+            &b"%PDF-1.7\n"[..],
+            &vec![b'0'; env::CONF.storage.max_attachment_bytes],
+        ]
+        .concat();
+        let resp = test::call_service(&app, upload_request(entry_id, "big.pdf", &too_large)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
 
-        let entry2 = InputEntry {
-            budget_id: created_in_range_budget0.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: Some(format!("Test Entry 2 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
+        let resp = test::call_service(
+            &app,
+            upload_request(uuid::Uuid::new_v4(), "receipt.png", &receipt),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
 
-        let entry3 = InputEntry {
-            budget_id: created_in_range_budget0.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: None,
-            category: None,
-            note: None,
-        };
+        let resp = test::call_service(
+            &app,
+            upload_request(entry_id, "2022/06 hardware.png", &receipt),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let attachment = test::read_body_json::<EntryAttachment, _>(resp).await;
+        assert_eq!(attachment.entry_id, entry_id);
+        assert_eq!(attachment.file_name, "2022_06 hardware.png");
+        assert_eq!(attachment.content_type, "image/png");
+        assert_eq!(attachment.size_bytes, receipt.len() as i64);
 
-        let entry4 = InputEntry {
-            budget_id: created_in_range_budget1.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: Some(format!("Test Entry 2 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
+        let req = test::TestRequest::post()
+            .uri("/api/budget/attachments")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputEntryAttachments {
+                budget_id: budget.id,
+                entry_id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let attachments = test::read_body_json::<Vec<EntryAttachment>, _>(resp).await;
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].id, attachment.id);
 
-        let entry5 = InputEntry {
-            budget_id: created_in_range_budget1.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
+        // Only members of the budget can see its attachments
+        let resp = test::call_service(
+            &app,
+            attachment_request(
+                "/api/budget/attachments/download",
+                &other_access_token,
+                attachment.id,
             ),
-            name: None,
-            category: None,
-            note: None,
-        };
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
 
-        let entry6 = InputEntry {
-            budget_id: created_in_range_budget2.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
+        let resp = test::call_service(
+            &app,
+            attachment_request(
+                "/api/budget/attachments/download",
+                &access_token,
+                attachment.id,
             ),
-            name: Some(format!("Test Entry 2 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get("content-type")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "image/png"
+        );
+        assert_eq!(
+            resp.headers()
+                .get("content-disposition")
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            "attachment; filename=\"2022_06 hardware.png\""
+        );
+        assert_eq!(test::read_body(resp).await, receipt);
 
-        let entry7 = InputEntry {
-            budget_id: created_in_range_budget2.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
+        let resp = test::call_service(
+            &app,
+            attachment_request(
+                "/api/budget/attachments/delete",
+                &other_access_token,
+                attachment.id,
             ),
-            name: None,
-            category: None,
-            note: None,
-        };
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
 
-        let entry8 = InputEntry {
-            budget_id: created_too_late_budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
+        let resp = test::call_service(
+            &app,
+            attachment_request(
+                "/api/budget/attachments/delete",
+                &access_token,
+                attachment.id,
             ),
-            name: Some(format!("Test Entry 2 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
-
-        let entry9 = InputEntry {
-            budget_id: created_too_late_budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(
+            &app,
+            attachment_request(
+                "/api/budget/attachments/download",
+                &access_token,
+                attachment.id,
             ),
-            name: None,
-            category: None,
-            note: None,
-        };
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
 
-        let in_range_budget_entries = vec![
-            vec![entry2.clone(), entry3.clone()],
-            vec![entry4.clone(), entry5.clone()],
-            vec![entry6.clone(), entry7.clone()],
-        ];
+        let stored_file =
+            std::path::Path::new(&env::CONF.storage.local_directory).join(&attachment.storage_key);
+        assert!(!stored_file.exists());
 
-        let entry0_req = test::TestRequest::post()
+        for _ in 0..MAX_ENTRY_ATTACHMENTS {
+            let resp =
+                test::call_service(&app, upload_request(entry_id, "receipt.png", &receipt)).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+        }
+
+        let resp =
+            test::call_service(&app, upload_request(entry_id, "receipt.png", &receipt)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_live_updates() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let sharer = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let recipient = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = sharer.budget.clone();
+
+        let sharer_token = sharer.token_pair.access_token.clone();
+        let recipient_token = recipient.token_pair.access_token.clone();
+        let sharer_id = user_id_from_token(&sharer_token);
+        let recipient_id = user_id_from_token(&recipient_token);
+
+        let mut updates = live_updates::subscribe();
+
+        // Other tests publish to the same bus, so only updates for this budget are looked at
+        async fn next_update(
+            updates: &mut tokio::sync::broadcast::Receiver<BudgetUpdate>,
+            budget_id: uuid::Uuid,
+        ) -> BudgetUpdate {
+            loop {
+                let update =
+                    actix_web::rt::time::timeout(std::time::Duration::from_secs(5), updates.recv())
+                        .await
+                        .expect("Timed out waiting for a budget update")
+                        .unwrap();
+
+                if update.budget_id == budget_id {
+                    return update;
+                }
+            }
+        }
+
+        let req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry0)
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 4200,
+                date: budget.start_date,
+                name: Some(String::from("Groceries")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry = test::read_body_json::<Entry, _>(resp).await;
 
-        test::call_service(&app, entry0_req).await;
+        let update = next_update(&mut updates, budget.id).await;
+        assert!(matches!(&update.event, BudgetEvent::EntryAdded { entry: e } if e.id == entry.id));
+        assert!(update.is_for(sharer_id));
+        assert!(!update.is_for(recipient_id));
 
-        let entry1_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let update_json = serde_json::to_value(&update).unwrap();
+        assert_eq!(update_json["type"], "entry_added");
+        assert_eq!(update_json["budget_id"], budget.id.to_string());
+        assert_eq!(update_json["entry"]["id"], entry.id.to_string());
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/invite")
             .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry1)
+            .insert_header(("authorization", format!("bearer {sharer_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: recipient_id,
+                budget_id: budget.id,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let share_event = test::read_body_json::<BudgetShareEvent, _>(resp).await;
 
-        test::call_service(&app, entry1_req).await;
-
-        let entry2_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
             .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry2)
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        test::call_service(&app, entry2_req).await;
+        let update = next_update(&mut updates, budget.id).await;
+        assert!(
+            matches!(update.event, BudgetEvent::ShareAccepted { user_id } if user_id == recipient_id)
+        );
+        assert!(update.is_for(sharer_id));
+        assert!(update.is_for(recipient_id));
 
-        let entry3_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/create")
             .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry3)
+            .insert_header(("authorization", format!("bearer {recipient_token}")))
+            .set_json(&InputBudgetComment {
+                id: None,
+                budget_id: budget.id,
+                text: String::from("Don't forget the milk"),
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let comment = test::read_body_json::<BudgetComment, _>(resp).await;
 
-        test::call_service(&app, entry3_req).await;
+        let update = next_update(&mut updates, budget.id).await;
+        assert!(
+            matches!(&update.event, BudgetEvent::CommentPosted { comment: c } if c.id == comment.id)
+        );
+        assert!(update.is_for(sharer_id));
+        assert!(update.is_for(recipient_id));
+    }
 
-        let entry4_req = test::TestRequest::post()
+    #[actix_rt::test]
+    async fn test_trash() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_access_token = other_user_and_budget.token_pair.access_token.clone();
+
+        let req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry4)
+            .set_json(&InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 1800,
+                date: budget.start_date,
+                name: Some(String::from("Bookstore")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry = test::read_body_json::<Entry, _>(resp).await;
 
-        test::call_service(&app, entry4_req).await;
-
-        let entry5_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/create")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry5)
+            .set_json(&InputBudgetComment {
+                id: None,
+                budget_id: budget.id,
+                text: String::from("Saving for a new couch"),
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let comment = test::read_body_json::<BudgetComment, _>(resp).await;
 
-        test::call_service(&app, entry5_req).await;
+        let get_trash = |token: String| {
+            test::TestRequest::post()
+                .uri("/api/budget/trash")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputBudgetId {
+                    budget_id: budget.id,
+                })
+                .to_request()
+        };
 
-        let entry6_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let resp = test::call_service(&app, get_trash(access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let trash = test::read_body_json::<OutputTrash, _>(resp).await;
+        assert!(trash.entries.is_empty());
+        assert!(trash.comments.is_empty());
+        assert_eq!(
+            trash.retention_days,
+            env::reloadable_conf().lifetimes.trash_retention_days
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry6)
+            .set_json(&InputEntryId { entry_id: entry.id })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        test::call_service(&app, entry6_req).await;
-
-        let entry7_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let req = test::TestRequest::post()
+            .uri("/api/budget/comment/delete")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry7)
+            .set_json(&InputBudgetCommentId {
+                comment_id: comment.id,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        test::call_service(&app, entry7_req).await;
+        let resp = test::call_service(&app, get_trash(access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let trash = test::read_body_json::<OutputTrash, _>(resp).await;
+        assert_eq!(trash.entries.len(), 1);
+        assert_eq!(trash.entries[0].id, entry.id);
+        assert_eq!(trash.comments.len(), 1);
+        assert_eq!(trash.comments[0].id, comment.id);
 
-        let entry8_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
+        let resp = test::call_service(&app, get_trash(other_access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let restore_entry = |token: String| {
+            test::TestRequest::post()
+                .uri("/api/budget/restore_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEntryId { entry_id: entry.id })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, restore_entry(other_access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, restore_entry(access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let restored_entry = test::read_body_json::<Entry, _>(resp).await;
+        assert_eq!(restored_entry.id, entry.id);
+        assert!(!restored_entry.is_deleted);
+
+        // Restoring puts the entry back in the category totals
+        let db_connection = db_thread_pool.get().unwrap();
+        let spent_cents = crate::schema::budget_category_totals::table
+            .select(crate::schema::budget_category_totals::spent_cents)
+            .filter(crate::schema::budget_category_totals::budget_id.eq(budget.id))
+            .filter(crate::schema::budget_category_totals::category.eq(0))
+            .first::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(spent_cents, 1800);
+
+        let resp = test::call_service(&app, restore_entry(access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
             .insert_header(("content-type", "application/json"))
             .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry8)
+            .set_json(&InputEntryId { entry_id: entry.id })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        test::call_service(&app, entry8_req).await;
+        // Items deleted before the retention window are out of the trash and get purged. Backdating
+        // them far enough keeps the purge from touching anything other tests deleted.
+        let long_ago = NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0);
 
-        let entry9_req = test::TestRequest::post()
+        diesel::update(crate::schema::entries::table.find(entry.id))
+            .set(entry_fields::modified_timestamp.eq(long_ago))
+            .execute(&db_connection)
+            .unwrap();
+        diesel::update(
+            crate::schema::budget_comments::table
+                .filter(crate::schema::budget_comments::id.eq(comment.id)),
+        )
+        .set(crate::schema::budget_comments::modified_timestamp.eq(long_ago))
+        .execute(&db_connection)
+        .unwrap();
+
+        let resp = test::call_service(&app, get_trash(access_token.clone())).await;
+        let trash = test::read_body_json::<OutputTrash, _>(resp).await;
+        assert!(trash.entries.is_empty());
+        assert!(trash.comments.is_empty());
+
+        let resp = test::call_service(&app, restore_entry(access_token.clone())).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        db::trash::purge_trash(&db_connection, long_ago + chrono::Duration::days(1)).unwrap();
+
+        assert!(crate::schema::entries::table
+            .find(entry.id)
+            .first::<Entry>(&db_connection)
+            .optional()
+            .unwrap()
+            .is_none());
+        assert!(crate::schema::budget_comments::table
+            .filter(crate::schema::budget_comments::id.eq(comment.id))
+            .first::<BudgetComment>(&db_connection)
+            .optional()
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_audit_log() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let owner = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let member = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = owner.budget.clone();
+
+        let owner_token = owner.token_pair.access_token.clone();
+        let member_token = member.token_pair.access_token.clone();
+        let owner_id = user_id_from_token(&owner_token);
+        let member_id = user_id_from_token(&member_token);
+
+        let req = test::TestRequest::post()
             .uri("/api/budget/add_entry")
             .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&entry9)
+            .insert_header(("authorization", format!("bearer {owner_token}")))
+            .set_json(&InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 4200,
+                date: budget.start_date,
+                name: Some(String::from("Groceries")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry = test::read_body_json::<Entry, _>(resp).await;
 
-        test::call_service(&app, entry9_req).await;
+        let req = test::TestRequest::post()
+            .uri("/api/budget/edit_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {owner_token}")))
+            .set_json(&InputEditEntry {
+                entry_id: entry.id,
+                amount_cents: 3900,
+                date: entry.date,
+                name: entry.name.clone(),
+                category: entry.category,
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        let date_range = InputDateRange {
-            start_date: NaiveDate::from_ymd(2022, 4, 6),
-            end_date: NaiveDate::from_ymd(2022, 4, 12),
+        let category = budget.categories[0].clone();
+        let edit_category = |name: &str, limit_cents: i64| {
+            test::TestRequest::post()
+                .uri("/api/budget/edit_category")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {owner_token}")))
+                .set_json(&InputEditCategory {
+                    budget_id: budget.id,
+                    category_id: category.id,
+                    name: String::from(name),
+                    limit_cents,
+                    color: category.color.clone(),
+                })
+                .to_request()
         };
 
+        let resp = test::call_service(
+            &app,
+            edit_category(&category.name, category.limit_cents + 1000),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // Renaming a category leaves its limit alone, so it isn't logged
+        let resp =
+            test::call_service(&app, edit_category("Renamed", category.limit_cents + 1000)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
         let req = test::TestRequest::post()
-            .uri("/api/budget/get_all_between_dates")
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&date_range)
+            .uri("/api/budget/invite")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {owner_token}")))
+            .set_json(&UserInvitationToBudget {
+                invitee_user_id: member_id,
+                budget_id: budget.id,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let share_event = test::read_body_json::<BudgetShareEvent, _>(resp).await;
 
+        let req = test::TestRequest::post()
+            .uri("/api/budget/accept_invitation")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {member_token}")))
+            .set_json(&InputBudgetShareEventId {
+                share_event_id: share_event.id,
+            })
+            .to_request();
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::OK);
 
-        let res_body = String::from_utf8(actix_web::test::read_body(resp).await.to_vec()).unwrap();
-        let output_budgets = serde_json::from_str::<Vec<OutputBudget>>(res_body.as_str()).unwrap();
+        let get_audit_log = |token: &str, query: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/api/budget/audit_log{query}"))
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputBudgetId {
+                    budget_id: budget.id,
+                })
+                .to_request()
+        };
 
-        assert_eq!(output_budgets.len(), 3);
+        let resp = test::call_service(&app, get_audit_log(&member_token, "")).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-        for i in 0..output_budgets.len() {
-            let budget = &output_budgets[i];
-            let created_budget = &in_range_budgets[i];
+        let resp = test::call_service(&app, get_audit_log(&owner_token, "")).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let audit_log = test::read_body_json::<OutputAuditLogPage, _>(resp).await;
+        assert!(!audit_log.has_more);
+
+        let actions = audit_log
+            .events
+            .iter()
+            .map(|e| e.action)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                AuditAction::MemberAdded as i16,
+                AuditAction::CategoryLimitChanged as i16,
+                AuditAction::EntryEdited as i16,
+                AuditAction::EntryAdded as i16,
+            ]
+        );
 
-            assert_eq!(budget.id, created_budget.id);
-            assert_eq!(budget.is_shared, created_budget.is_shared);
-            assert_eq!(budget.is_private, created_budget.is_private);
-            assert_eq!(budget.is_deleted, created_budget.is_deleted);
-            assert_eq!(budget.name, created_budget.name);
-            assert_eq!(budget.description, created_budget.description);
-            assert_eq!(budget.start_date, created_budget.start_date);
-            assert_eq!(budget.end_date, created_budget.end_date);
+        let member_added = &audit_log.events[0];
+        assert_eq!(member_added.user_id, Some(member_id));
+        assert!(member_added.before.is_none());
+        assert_eq!(
+            member_added.after.as_ref().unwrap()["user_id"],
+            member_id.to_string()
+        );
+
+        let limit_changed = &audit_log.events[1];
+        assert_eq!(limit_changed.user_id, Some(owner_id));
+        assert_eq!(
+            limit_changed.before.as_ref().unwrap()["limit_cents"],
+            category.limit_cents
+        );
+        assert_eq!(
+            limit_changed.after.as_ref().unwrap()["limit_cents"],
+            category.limit_cents + 1000
+        );
+
+        let entry_edited = &audit_log.events[2];
+        assert_eq!(entry_edited.before.as_ref().unwrap()["amount_cents"], 4200);
+        assert_eq!(entry_edited.after.as_ref().unwrap()["amount_cents"], 3900);
+
+        let entry_added = &audit_log.events[3];
+        assert!(entry_added.before.is_none());
+        assert_eq!(
+            entry_added.after.as_ref().unwrap()["id"],
+            entry.id.to_string()
+        );
+
+        let resp = test::call_service(&app, get_audit_log(&owner_token, "?limit=1&offset=1")).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let audit_log = test::read_body_json::<OutputAuditLogPage, _>(resp).await;
+        assert!(audit_log.has_more);
+        assert_eq!(audit_log.events.len(), 1);
+        assert_eq!(
+            audit_log.events[0].action,
+            AuditAction::CategoryLimitChanged as i16
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_create_records_with_client_ids() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let created_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = created_user_and_budget.budget.clone();
+        let access_token = created_user_and_budget.token_pair.access_token.clone();
+
+        let other_user_and_budget =
+            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let other_budget = other_user_and_budget.budget.clone();
+        let other_access_token = other_user_and_budget.token_pair.access_token.clone();
+
+        let add_entry = |token: &str, id: uuid::Uuid, budget: &OutputBudget| {
+            test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEntry {
+                    id: Some(id),
+                    budget_id: budget.id,
+                    amount_cents: 1250,
+                    date: budget.start_date,
+                    name: Some(String::from("Made offline")),
+                    category: Some(0),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(
+            &app,
+            add_entry(&access_token, uuid::Uuid::new_v4(), &budget),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
 
-            assert!(budget.latest_entry_time > created_budget.latest_entry_time);
+        let entry_id = record_id::generate();
+        let resp = test::call_service(&app, add_entry(&access_token, entry_id, &budget)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry = test::read_body_json::<Entry, _>(resp).await;
+        assert_eq!(entry.id, entry_id);
 
-            assert_eq!(budget.modified_timestamp, created_budget.modified_timestamp);
-            assert_eq!(budget.created_timestamp, created_budget.created_timestamp);
+        // Uploading the entry again returns the entry that was already uploaded
+        let resp = test::call_service(&app, add_entry(&access_token, entry_id, &budget)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let reuploaded_entry = test::read_body_json::<Entry, _>(resp).await;
+        assert_eq!(reuploaded_entry.id, entry_id);
+        assert_eq!(reuploaded_entry.created_timestamp, entry.created_timestamp);
+
+        let entry_count = crate::schema::entries::table
+            .filter(crate::schema::entries::id.eq(entry_id))
+            .count()
+            .get_result::<i64>(&db_thread_pool.get().unwrap())
+            .unwrap();
+        assert_eq!(entry_count, 1);
 
-            assert!(!budget.categories.is_empty());
-            assert_eq!(budget.categories.len(), created_budget.categories.len());
+        let resp = test::call_service(
+            &app,
+            add_entry(&other_access_token, entry_id, &other_budget),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
 
-            for j in 0..budget_categories.len() {
-                let fetched_cat = &budget.categories[j];
-                let created_cat = &created_budget.categories[j];
+        let create_comment = |token: &str, id: uuid::Uuid| {
+            test::TestRequest::post()
+                .uri("/api/budget/comment/create")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputBudgetComment {
+                    id: Some(id),
+                    budget_id: budget.id,
+                    text: String::from("Written on the train"),
+                })
+                .to_request()
+        };
 
-                assert_eq!(fetched_cat.pk, created_cat.pk);
-                assert_eq!(fetched_cat.budget_id, created_cat.budget_id);
-                assert_eq!(fetched_cat.id, created_cat.id);
-                assert_eq!(fetched_cat.name, created_cat.name);
-                assert_eq!(fetched_cat.limit_cents, created_cat.limit_cents);
-                assert_eq!(fetched_cat.color, created_cat.color);
-            }
+        let comment_id = record_id::generate();
+        let resp = test::call_service(&app, create_comment(&access_token, comment_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let comment = test::read_body_json::<BudgetComment, _>(resp).await;
+        assert_eq!(comment.id, comment_id);
 
-            for j in 0..in_range_budget_entries[i].len() {
-                assert_eq!(
-                    budget.entries[j].amount_cents,
-                    in_range_budget_entries[i][j].amount_cents
-                );
-                assert_eq!(budget.entries[j].date, in_range_budget_entries[i][j].date);
-                assert_eq!(budget.entries[j].name, in_range_budget_entries[i][j].name);
-                assert_eq!(
-                    budget.entries[j].category,
-                    in_range_budget_entries[i][j].category
-                );
-                assert_eq!(budget.entries[j].note, in_range_budget_entries[i][j].note);
-            }
-        }
+        let resp = test::call_service(&app, create_comment(&access_token, comment_id)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let reuploaded_comment = test::read_body_json::<BudgetComment, _>(resp).await;
+        assert_eq!(reuploaded_comment.id, comment_id);
     }
 
     #[actix_rt::test]
-    async fn test_cant_access_budget_for_another_user() {
+    async fn test_add_entry_warns_of_unusual_amount() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -1961,169 +10404,297 @@ mod tests {
 
         let created_user_and_budget =
             create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let created_budget = created_user_and_budget.budget.clone();
+        let budget = created_user_and_budget.budget.clone();
         let access_token = created_user_and_budget.token_pair.access_token.clone();
 
-        let mut budget_categories = Vec::new();
-        budget_categories.push(InputCategory {
-            id: created_budget.categories[0].id,
-            name: created_budget.categories[0].name.clone(),
-            limit_cents: created_budget.categories[0].limit_cents,
-            color: created_budget.categories[0].color.clone(),
-        });
+        let add_entry = |amount_cents: i64, query: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/api/budget/add_entry{query}"))
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents,
+                    date: budget.start_date,
+                    name: Some(String::from("Coffee")),
+                    category: Some(0),
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
 
-        budget_categories.push(InputCategory {
-            id: created_budget.categories[1].id,
-            name: created_budget.categories[1].name.clone(),
-            limit_cents: created_budget.categories[1].limit_cents,
-            color: created_budget.categories[1].color.clone(),
-        });
+        for amount_cents in [450, 500, 475, 520, 390] {
+            let resp = test::call_service(&app, add_entry(amount_cents, "")).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+            let body = test::read_body_json::<serde_json::Value, _>(resp).await;
+            assert!(body.get("warning").is_none());
+        }
 
-        let created_unauth_user_and_budget =
-            create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
-        let unauth_user_access_token = created_unauth_user_and_budget
-            .token_pair
-            .access_token
-            .clone();
+        let resp = test::call_service(&app, add_entry(500000, "")).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let created_entry = test::read_body_json::<OutputCreatedEntry, _>(resp).await;
+        assert_eq!(created_entry.entry.amount_cents, 500000);
+        assert_eq!(
+            created_entry.warning,
+            Some(EntryWarning::UnusualAmount {
+                usual_amount_cents: 475,
+                smallest_amount_cents: 390,
+                largest_amount_cents: 520,
+            })
+        );
 
-        let entry0 = InputEntry {
-            budget_id: created_budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(1..=6),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: Some(format!("Test Entry 0 for user")),
-            category: Some(0),
-            note: Some(String::from("This is a little note")),
-        };
+        // The flagged entry was still saved
+        let entry_count = crate::schema::entries::table
+            .filter(crate::schema::entries::budget_id.eq(budget.id))
+            .count()
+            .get_result::<i64>(&db_thread_pool.get().unwrap())
+            .unwrap();
+        assert_eq!(entry_count, 6);
 
-        let entry1 = InputEntry {
-            budget_id: created_budget.id,
-            amount_cents: rand::thread_rng().gen_range(90..=120000),
-            date: NaiveDate::from_ymd(
-                2022,
-                rand::thread_rng().gen_range(7..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            name: None,
-            category: None,
-            note: None,
-        };
+        // Far above even the flagged amount, but the user has confirmed it
+        let resp =
+            test::call_service(&app, add_entry(9_000_000, "?confirm_unusual_amount=true")).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let created_entry = test::read_body_json::<OutputCreatedEntry, _>(resp).await;
+        assert!(created_entry.warning.is_none());
+    }
 
-        let created_entries = vec![entry0.clone(), entry1.clone()];
+    #[actix_rt::test]
+    async fn test_close_month() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
-        let entry0_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&created_entries[0])
-            .to_request();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
 
-        test::call_service(&app, entry0_req).await;
+        let owner = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let member = create_user_and_budget_and_sign_in(db_thread_pool.clone()).await;
+        let budget = owner.budget.clone();
 
-        let entry1_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
-            .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(&created_entries[1])
-            .to_request();
+        let owner_token = owner.token_pair.access_token.clone();
+        let member_token = member.token_pair.access_token.clone();
+        let member_id = user_id_from_token(&member_token);
 
-        test::call_service(&app, entry1_req).await;
+        let db_connection = db_thread_pool.get().unwrap();
+        db::budget::add_user(&db_connection, budget.id, member_id, BudgetRole::Editor).unwrap();
 
-        let input_budget_id = InputBudgetId {
-            budget_id: created_budget.id,
+        let month = budget.start_date.with_day(1).unwrap();
+        let next_month = (month + chrono::Duration::days(31)).with_day(1).unwrap();
+
+        let add_entry = |token: &str, date: NaiveDate, category: Option<i16>| {
+            test::TestRequest::post()
+                .uri("/api/budget/add_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEntry {
+                    id: None,
+                    budget_id: budget.id,
+                    amount_cents: 2500,
+                    date,
+                    name: None,
+                    category,
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
         };
 
-        let unauth_get_req = test::TestRequest::post()
-            .uri("/api/budget/get")
-            .insert_header(("content-type", "application/json"))
-            .insert_header((
-                "authorization",
-                format!("bearer {unauth_user_access_token}"),
-            ))
-            .set_json(input_budget_id)
-            .to_request();
+        let mut entries_in_month = Vec::new();
+        for category in [Some(0), Some(0), None] {
+            let resp = test::call_service(&app, add_entry(&owner_token, month, category)).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+            entries_in_month.push(test::read_body_json::<Entry, _>(resp).await);
+        }
 
-        let unauth_get_res = test::call_service(&app, unauth_get_req).await;
-        assert_eq!(unauth_get_res.status(), http::StatusCode::NOT_FOUND);
+        let resp = test::call_service(&app, add_entry(&owner_token, next_month, Some(1))).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let entry_in_next_month = test::read_body_json::<Entry, _>(resp).await;
 
-        let unauth_get_res_body =
-            String::from_utf8(actix_web::test::read_body(unauth_get_res).await.to_vec()).unwrap();
-        let _unauth_get_parsed_body =
-            serde_json::from_str::<OutputBudget>(unauth_get_res_body.as_str()).unwrap_err();
+        let month_request = |uri: &str, token: &str, month: NaiveDate| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputBudgetMonth {
+                    budget_id: budget.id,
+                    month,
+                })
+                .to_request()
+        };
 
-        let unauth_entry0_req = test::TestRequest::post()
-            .uri("/api/budget/add_entry")
-            .insert_header(("content-type", "application/json"))
-            .insert_header((
-                "authorization",
-                format!("bearer {unauth_user_access_token}"),
-            ))
-            .set_json(&created_entries[0])
-            .to_request();
+        let resp = test::call_service(
+            &app,
+            month_request("/api/budget/close_month", &member_token, month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-        let unauth_entry0_res = test::call_service(&app, unauth_entry0_req).await;
-        assert_eq!(unauth_entry0_res.status(), http::StatusCode::NOT_FOUND);
+        // The current month hasn't ended yet
+        let resp = test::call_service(
+            &app,
+            month_request(
+                "/api/budget/close_month",
+                &owner_token,
+                chrono::Utc::now().naive_utc().date(),
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
 
-        // Make sure the created budget hasn't changed
+        // Any day of the month closes the whole month
+        let resp = test::call_service(
+            &app,
+            month_request(
+                "/api/budget/close_month",
+                &owner_token,
+                month.with_day(15).unwrap(),
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
 
-        let input_budget_id = InputBudgetId {
-            budget_id: created_budget.id,
-        };
+        let resp = test::call_service(
+            &app,
+            month_request("/api/budget/close_month", &owner_token, month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let member_notification_count = user_notifications
+            .filter(user_notification_fields::user_id.eq(member_id))
+            .filter(
+                user_notification_fields::notification_type
+                    .eq(i16::from(NotificationType::MonthClosed)),
+            )
+            .count()
+            .get_result::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(member_notification_count, 1);
 
         let req = test::TestRequest::post()
-            .uri("/api/budget/get")
+            .uri("/api/budget/closed_months")
             .insert_header(("content-type", "application/json"))
-            .insert_header(("authorization", format!("bearer {access_token}")))
-            .set_json(input_budget_id)
+            .insert_header(("authorization", format!("bearer {member_token}")))
+            .set_json(&InputBudgetId {
+                budget_id: budget.id,
+            })
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let closed_months = test::read_body_json::<Vec<OutputClosedMonth>, _>(resp).await;
 
-        let res = test::call_service(&app, req).await;
-        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(closed_months.len(), 1);
+        assert_eq!(closed_months[0].month, month);
+        assert_eq!(
+            closed_months[0].closed_by_user_id,
+            Some(user_id_from_token(&owner_token))
+        );
 
-        let res_body = String::from_utf8(actix_web::test::read_body(res).await.to_vec()).unwrap();
-        let budget = serde_json::from_str::<OutputBudget>(res_body.as_str()).unwrap();
+        let totals = &closed_months[0].totals;
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].category, None);
+        assert_eq!(totals[0].spent_cents, 2500);
+        assert_eq!(totals[0].entry_count, 1);
+        assert_eq!(totals[1].category, Some(0));
+        assert_eq!(totals[1].spent_cents, 5000);
+        assert_eq!(totals[1].entry_count, 2);
 
-        assert_eq!(budget.id, created_budget.id);
-        assert_eq!(budget.is_shared, created_budget.is_shared);
-        assert_eq!(budget.is_private, created_budget.is_private);
-        assert_eq!(budget.is_deleted, created_budget.is_deleted);
-        assert_eq!(budget.name, created_budget.name);
-        assert_eq!(budget.description, created_budget.description);
-        assert_eq!(budget.start_date, created_budget.start_date);
-        assert_eq!(budget.end_date, created_budget.end_date);
+        let edit_entry = |token: &str, entry: &Entry, date: NaiveDate| {
+            test::TestRequest::post()
+                .uri("/api/budget/edit_entry")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputEditEntry {
+                    entry_id: entry.id,
+                    amount_cents: 3000,
+                    date,
+                    name: entry.name.clone(),
+                    category: entry.category,
+                    note: None,
+                    tax_cents: None,
+                    tip_cents: None,
+                    is_deductible: false,
+                })
+                .to_request()
+        };
 
-        assert!(budget.latest_entry_time > created_budget.latest_entry_time);
+        let resp = test::call_service(&app, add_entry(&member_token, month, None)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-        assert_eq!(budget.modified_timestamp, created_budget.modified_timestamp);
-        assert_eq!(budget.created_timestamp, created_budget.created_timestamp);
+        // Owners are held to the lock too until they reopen the month
+        let resp =
+            test::call_service(&app, edit_entry(&owner_token, &entries_in_month[0], month)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-        assert!(!budget.categories.is_empty());
-        assert_eq!(budget.categories.len(), created_budget.categories.len());
+        // Moving an entry into or out of a closed month would change the month
+        let resp = test::call_service(
+            &app,
+            edit_entry(&owner_token, &entries_in_month[0], next_month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-        for i in 0..budget_categories.len() {
-            let fetched_cat = &budget.categories[i];
-            let created_cat = &created_budget.categories[i];
+        let resp =
+            test::call_service(&app, edit_entry(&owner_token, &entry_in_next_month, month)).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-            assert_eq!(fetched_cat.pk, created_cat.pk);
-            assert_eq!(fetched_cat.budget_id, created_cat.budget_id);
-            assert_eq!(fetched_cat.id, created_cat.id);
-            assert_eq!(fetched_cat.name, created_cat.name);
-            assert_eq!(fetched_cat.limit_cents, created_cat.limit_cents);
-            assert_eq!(fetched_cat.color, created_cat.color);
-        }
+        let req = test::TestRequest::post()
+            .uri("/api/budget/delete_entry")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {owner_token}")))
+            .set_json(&InputEntryId {
+                entry_id: entries_in_month[1].id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
 
-        for i in 0..created_entries.len() {
-            assert_eq!(
-                budget.entries[i].amount_cents,
-                created_entries[i].amount_cents
-            );
-            assert_eq!(budget.entries[i].date, created_entries[i].date);
-            assert_eq!(budget.entries[i].name, created_entries[i].name);
-            assert_eq!(budget.entries[i].category, created_entries[i].category);
-            assert_eq!(budget.entries[i].note, created_entries[i].note);
-        }
+        let resp = test::call_service(
+            &app,
+            edit_entry(&member_token, &entry_in_next_month, next_month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(
+            &app,
+            month_request("/api/budget/reopen_month", &member_token, month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let resp = test::call_service(
+            &app,
+            month_request("/api/budget/reopen_month", &owner_token, month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(
+            &app,
+            month_request("/api/budget/reopen_month", &owner_token, month),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp =
+            test::call_service(&app, edit_entry(&member_token, &entries_in_month[0], month)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let closed_month_total_count = crate::schema::closed_month_totals::table
+            .filter(crate::schema::closed_month_totals::budget_id.eq(budget.id))
+            .count()
+            .get_result::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(closed_month_total_count, 0);
     }
 }
@@ -0,0 +1,426 @@
+use actix_web::{web, HttpResponse};
+use chrono::Duration;
+use log::error;
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::budget::ensure_user_in_budget;
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{
+    InputSpendingChallenge, InputSpendingChallengeId, OutputBadge, OutputSpendingChallenge,
+    OutputStreak,
+};
+use crate::middleware;
+use crate::utils::db;
+use crate::utils::engagement::{self, Badge};
+
+pub async fn get_streak(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+
+    let streak = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let user = db::user::get_user_by_id(&db_connection, user_id)?;
+
+        let today = chrono::Utc::now().naive_utc().date();
+        let earliest_date = std::cmp::max(
+            user.created_timestamp.date(),
+            today - Duration::days(engagement::MAX_STREAK_DAYS - 1),
+        );
+
+        let spending_dates =
+            db::engagement::get_spending_dates_for_user(&db_connection, user_id, earliest_date)?;
+        let streak = engagement::compute_no_spend_streak(&spending_dates, today, earliest_date);
+
+        db::engagement::award_badges(&db_connection, user_id, &Badge::for_streak(streak))?;
+
+        Ok(streak)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::AccessForbidden(Some("No user with ID")))
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to compute streak",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(OutputStreak {
+        no_spend_streak_days: streak,
+    }))
+}
+
+pub async fn get_badges(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let badges = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::engagement::get_badges_for_user(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to get badges")),
+    };
+
+    let mut output_badges = Vec::new();
+
+    for user_badge in badges.into_iter() {
+        let badge = match Badge::try_from(user_badge.badge) {
+            Ok(b) => b,
+            Err(e) => {
+                error!("{}", e);
+                continue;
+            }
+        };
+
+        output_badges.push(OutputBadge {
+            badge,
+            earned_timestamp: user_badge.earned_timestamp,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(output_badges))
+}
+
+pub async fn create_challenge(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    challenge_data: web::Json<InputSpendingChallenge>,
+) -> Result<HttpResponse, ServerError> {
+    if challenge_data.end_date < challenge_data.start_date {
+        return Err(ServerError::InvalidFormat(Some(
+            "End date cannot come before start date",
+        )));
+    }
+
+    if challenge_data.limit_cents < 0 {
+        return Err(ServerError::InvalidFormat(Some(
+            "Spending limit cannot be negative",
+        )));
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    ensure_user_in_budget(db_thread_pool.clone(), user_id, challenge_data.budget_id).await?;
+
+    let challenge = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::engagement::create_challenge(&db_connection, user_id, &challenge_data)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create challenge",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(challenge))
+}
+
+pub async fn get_all_challenges(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let challenges = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let challenges =
+            db::engagement::get_all_challenges_for_user(&db_connection, auth_user_claims.0.uid)?;
+        let mut output_challenges = Vec::with_capacity(challenges.len());
+
+        for challenge in challenges.into_iter() {
+            let spent_cents = db::engagement::get_spent_cents_in_category(
+                &db_connection,
+                challenge.budget_id,
+                challenge.category_id,
+                challenge.start_date,
+                challenge.end_date,
+            )?;
+
+            output_challenges.push(OutputSpendingChallenge {
+                id: challenge.id,
+                budget_id: challenge.budget_id,
+                category_id: challenge.category_id,
+                limit_cents: challenge.limit_cents,
+                spent_cents,
+                start_date: challenge.start_date,
+                end_date: challenge.end_date,
+                is_succeeded: challenge.is_succeeded,
+                notify_on_completion: challenge.notify_on_completion,
+                created_timestamp: challenge.created_timestamp,
+            });
+        }
+
+        Ok::<_, diesel::result::Error>(output_challenges)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get challenges",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(challenges))
+}
+
+pub async fn delete_challenge(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    challenge_id: web::Json<InputSpendingChallengeId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::engagement::delete_challenge(
+            &db_connection,
+            auth_user_claims.0.uid,
+            challenge_id.challenge_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete challenge",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some("No challenge with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+    use chrono::NaiveDate;
+    use rand::prelude::*;
+    use uuid::Uuid;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputEntry, InputUser};
+    use crate::services;
+    use crate::utils::auth_token;
+
+    fn create_user_and_budget_with_access_token() -> (Uuid, String) {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("Vb7&kR2qLz!9wPe$3mXa"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let user = db::user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        let today = chrono::Utc::now().naive_utc().date();
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: String::from("Dining"),
+                limit_cents: 20000,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: today - Duration::days(10),
+            end_date: today + Duration::days(20),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let budget =
+            db::budget::create_budget(&db_connection, &web::Json(new_budget), user.id).unwrap();
+
+        let access_token =
+            auth_token::generate_access_token(auth_token::TokenParams { user_id: &user.id })
+                .unwrap();
+
+        (budget.id, access_token.to_string())
+    }
+
+    #[actix_rt::test]
+    async fn test_challenge_progress() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let (budget_id, access_token) = create_user_and_budget_with_access_token();
+        let (other_budget_id, _) = create_user_and_budget_with_access_token();
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let mut challenge = InputSpendingChallenge {
+            budget_id: other_budget_id,
+            category_id: 0,
+            limit_cents: 5000,
+            start_date: today - Duration::days(3),
+            end_date: today + Duration::days(3),
+            notify_on_completion: true,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/engagement/create_challenge")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&challenge)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        challenge.budget_id = budget_id;
+        let req = test::TestRequest::post()
+            .uri("/api/engagement/create_challenge")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&challenge)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let entry = InputEntry {
+            id: None,
+            budget_id,
+            amount_cents: 1200,
+            date: today,
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/budget/add_entry")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&entry)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/engagement/get_all_challenges")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let challenges = test::read_body_json::<Vec<OutputSpendingChallenge>, _>(resp).await;
+        assert_eq!(challenges.len(), 1);
+        assert_eq!(challenges[0].spent_cents, 1200);
+        assert_eq!(challenges[0].limit_cents, 5000);
+        assert!(challenges[0].is_succeeded.is_none());
+
+        let req = test::TestRequest::get()
+            .uri("/api/engagement/streak")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let streak = test::read_body_json::<OutputStreak, _>(resp).await;
+        assert_eq!(streak.no_spend_streak_days, 0);
+
+        let req = test::TestRequest::post()
+            .uri("/api/engagement/delete_challenge")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputSpendingChallengeId {
+                challenge_id: challenges[0].id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/engagement/delete_challenge")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputSpendingChallengeId {
+                challenge_id: challenges[0].id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_create_challenge_rejects_invalid_dates() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let (budget_id, access_token) = create_user_and_budget_with_access_token();
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let challenge = InputSpendingChallenge {
+            budget_id,
+            category_id: 0,
+            limit_cents: 5000,
+            start_date: today,
+            end_date: today - Duration::days(1),
+            notify_on_completion: false,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/engagement/create_challenge")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&challenge)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+}
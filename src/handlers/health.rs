@@ -0,0 +1,134 @@
+use actix_web::{web, HttpResponse};
+use diesel::RunQueryDsl;
+use log::error;
+use std::time::Duration;
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::request_io::{OutputConnectionPoolStats, OutputReadiness};
+
+// Readiness is polled often, so a check waits only briefly for a connection rather than the pool's
+// full connection timeout
+const READINESS_CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+// The process is up and serving requests. Never touches the database so a database outage doesn't
+// get every instance restarted.
+pub async fn live() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+pub async fn ready(db_thread_pool: web::Data<DbThreadPool>) -> HttpResponse {
+    let pool = db_thread_pool.clone();
+
+    let database_result = web::block(move || {
+        let db_connection = pool.get_timeout(READINESS_CONNECTION_TIMEOUT)?;
+        diesel::sql_query("SELECT 1").execute(&db_connection)?;
+
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+    })
+    .await;
+
+    let is_database_available = match database_result {
+        Ok(Ok(())) => true,
+        Ok(Err(e)) => {
+            error!("Readiness check failed: {}", e);
+            false
+        }
+        Err(e) => {
+            error!("Readiness check failed: {}", e);
+            false
+        }
+    };
+
+    let state = db_thread_pool.state();
+
+    let readiness = OutputReadiness {
+        is_database_available,
+        connection_pool: OutputConnectionPoolStats {
+            max_connections: db_thread_pool.max_size(),
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            connections_in_use: state.connections - state.idle_connections,
+        },
+    };
+
+    if is_database_available {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+    use diesel::r2d2::{self, ConnectionManager};
+    use diesel::PgConnection;
+
+    use crate::env;
+    use crate::services;
+
+    #[actix_rt::test]
+    async fn test_health() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::health::configure)
+                .configure(services::web::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/health/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let readiness = test::read_body_json::<OutputReadiness, _>(resp).await;
+        assert!(readiness.is_database_available);
+        assert_eq!(
+            readiness.connection_pool.max_connections,
+            db_thread_pool.max_size()
+        );
+        assert!(readiness.connection_pool.connections >= 1);
+        assert_eq!(
+            readiness.connection_pool.connections_in_use,
+            readiness.connection_pool.connections - readiness.connection_pool.idle_connections
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_ready_without_database() {
+        // Nothing listens on the port, so no connection can be made
+        let unreachable_pool = r2d2::Pool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(250))
+            .build_unchecked(ConnectionManager::<PgConnection>::new(
+                "postgres://nobody@127.0.0.1:1/nothing",
+            ));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(unreachable_pool))
+                .configure(services::health::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/health/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+
+        let readiness = test::read_body_json::<OutputReadiness, _>(resp).await;
+        assert!(!readiness.is_database_available);
+        assert_eq!(readiness.connection_pool.connections_in_use, 0);
+    }
+}
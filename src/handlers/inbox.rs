@@ -0,0 +1,412 @@
+use actix_web::{web, HttpResponse};
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::budget::{ensure_user_has_budget_role, page_bounds};
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{
+    InputInboxAssignment, InputInboxEntries, InputInboxEntry, InputInboxEntryIds, InputPagination,
+    OutputInboxCount,
+};
+use crate::middleware;
+use crate::utils::db;
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::live_updates::{self, BudgetEvent};
+
+// The inbox holds a user's entries that haven't been put in a budget yet, such as transactions
+// synced from their bank. Entries only ever leave the inbox by being assigned to a budget or
+// dismissed.
+
+const MAX_INBOX_BATCH_SIZE: usize = 200;
+const MAX_ENTRY_NAME_LENGTH: usize = 25;
+
+pub fn validate_inbox_entry(entry: &InputInboxEntry) -> Result<(), ServerError> {
+    if entry
+        .name
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > MAX_ENTRY_NAME_LENGTH)
+    {
+        return Err(ServerError::InvalidFormat(Some(
+            "Entry name cannot be longer than 25 characters",
+        )));
+    }
+
+    Ok(())
+}
+
+fn validate_batch_size(size: usize) -> Result<(), ServerError> {
+    if size == 0 || size > MAX_INBOX_BATCH_SIZE {
+        return Err(ServerError::InvalidFormat(Some(
+            "A batch must have between 1 and 200 inbox entries",
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn get_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    pagination: web::Query<InputPagination>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let inbox_page = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::inbox::get_entries(&db_connection, auth_user_claims.0.uid, limit, offset)
+    })
+    .await?
+    {
+        Ok(p) => p,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get inbox entries",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(inbox_page))
+}
+
+// The number clients show on the inbox's badge
+pub async fn count(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let entry_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::inbox::count_entries(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to count inbox entries",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(OutputInboxCount { entry_count }))
+}
+
+pub async fn add_entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    entries_data: web::Json<InputInboxEntries>,
+) -> Result<HttpResponse, ServerError> {
+    validate_batch_size(entries_data.entries.len())?;
+
+    for entry in entries_data.entries.iter() {
+        validate_inbox_entry(entry)?;
+    }
+
+    let new_entries = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::inbox::add_entries(
+            &db_connection,
+            auth_user_claims.0.uid,
+            &entries_data.entries,
+        )
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to add inbox entries",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Created().json(new_entries))
+}
+
+pub async fn assign(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    assignment: web::Json<InputInboxAssignment>,
+) -> Result<HttpResponse, ServerError> {
+    validate_batch_size(assignment.inbox_entry_ids.len())?;
+
+    let user_id = auth_user_claims.0.uid;
+    let budget_id = assignment.budget_id;
+    ensure_user_has_budget_role(
+        db_thread_pool.clone(),
+        user_id,
+        budget_id,
+        BudgetRole::Editor,
+    )
+    .await?;
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+    let new_entries = match web::block(move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to access database thread pool");
+        db::inbox::assign_entries(&db_connection, user_id, &assignment)
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(diesel::result::Error::NotFound) => {
+            return Err(ServerError::NotFound(Some(
+                "An entry is not in the inbox. It may have already been assigned.",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to assign inbox entries",
+            ))
+        }
+    };
+
+    for entry in new_entries.iter() {
+        live_updates::publish(
+            db_thread_pool.clone(),
+            budget_id,
+            BudgetEvent::EntryAdded {
+                entry: entry.clone(),
+            },
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(new_entries))
+}
+
+pub async fn dismiss(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    inbox_entry_ids: web::Json<InputInboxEntryIds>,
+) -> Result<HttpResponse, ServerError> {
+    validate_batch_size(inbox_entry_ids.inbox_entry_ids.len())?;
+
+    match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::inbox::dismiss_entries(
+            &db_connection,
+            auth_user_claims.0.uid,
+            &inbox_entry_ids.inbox_entry_ids,
+        )
+    })
+    .await?
+    {
+        Ok(_) => (),
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to dismiss inbox entries",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+    use chrono::NaiveDate;
+    use diesel::prelude::*;
+    use uuid::Uuid;
+
+    use crate::env;
+    use crate::handlers::request_io::OutputInboxPage;
+    use crate::handlers::testing::create_user_and_budget_with_access_token;
+    use crate::models::entry::Entry;
+    use crate::models::inbox_entry::InboxEntry;
+    use crate::services;
+
+    #[actix_rt::test]
+    async fn test_inbox() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let (user_id, budget_id, access_token) = create_user_and_budget_with_access_token();
+        let (_, other_budget_id, other_access_token) = create_user_and_budget_with_access_token();
+
+        let inbox_entry = |name: &str, amount_cents: i64| InputInboxEntry {
+            amount_cents,
+            date: NaiveDate::from_ymd(2022, 6, 3),
+            name: Some(String::from(name)),
+            note: None,
+        };
+
+        let add_entries = |entries: Vec<InputInboxEntry>| {
+            test::TestRequest::post()
+                .uri("/api/inbox/add_entries")
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputInboxEntries { entries })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, add_entries(Vec::new())).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(
+            &app,
+            add_entries(vec![inbox_entry("A name that is far too long to fit", 100)]),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(
+            &app,
+            add_entries(vec![
+                inbox_entry("Corner store", 1250),
+                inbox_entry("Bakery", 800),
+                inbox_entry("Gas station", 4000),
+            ]),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let inbox_entries = test::read_body_json::<Vec<InboxEntry>, _>(resp).await;
+        assert_eq!(inbox_entries.len(), 3);
+
+        let get_count = |token: &str| {
+            test::TestRequest::get()
+                .uri("/api/inbox/count")
+                .insert_header(("authorization", format!("bearer {token}")))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, get_count(&access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let count = test::read_body_json::<OutputInboxCount, _>(resp).await;
+        assert_eq!(count.entry_count, 3);
+
+        let resp = test::call_service(&app, get_count(&other_access_token)).await;
+        let count = test::read_body_json::<OutputInboxCount, _>(resp).await;
+        assert_eq!(count.entry_count, 0);
+
+        let req = test::TestRequest::get()
+            .uri("/api/inbox/entries?limit=2")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let page = test::read_body_json::<OutputInboxPage, _>(resp).await;
+        assert_eq!(page.entries.len(), 2);
+        assert!(page.has_more);
+
+        let assign = |token: &str, inbox_entry_ids: Vec<Uuid>, budget_id: Uuid| {
+            test::TestRequest::post()
+                .uri("/api/inbox/assign")
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputInboxAssignment {
+                    inbox_entry_ids,
+                    budget_id,
+                    category: Some(0),
+                })
+                .to_request()
+        };
+
+        // Only members of the budget can put entries in it
+        let resp = test::call_service(
+            &app,
+            assign(&access_token, vec![inbox_entries[0].id], other_budget_id),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        // Only the user whose inbox holds the entries can assign them
+        let resp = test::call_service(
+            &app,
+            assign(
+                &other_access_token,
+                vec![inbox_entries[0].id],
+                other_budget_id,
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(
+            &app,
+            assign(
+                &access_token,
+                vec![inbox_entries[0].id, inbox_entries[1].id],
+                budget_id,
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let assigned_entries = test::read_body_json::<Vec<Entry>, _>(resp).await;
+        assert_eq!(assigned_entries.len(), 2);
+
+        for entry in assigned_entries.iter() {
+            let inbox_entry = inbox_entries.iter().find(|e| e.id == entry.id).unwrap();
+            assert_eq!(entry.budget_id, budget_id);
+            assert_eq!(entry.user_id, user_id);
+            assert_eq!(entry.category, Some(0));
+            assert_eq!(entry.amount_cents, inbox_entry.amount_cents);
+            assert_eq!(entry.name, inbox_entry.name);
+        }
+
+        // An entry that has already been assigned can't be put in a second budget. Nothing in the
+        // batch is assigned if any of it fails.
+        let resp = test::call_service(
+            &app,
+            assign(
+                &access_token,
+                vec![inbox_entries[0].id, inbox_entries[2].id],
+                budget_id,
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, get_count(&access_token)).await;
+        let count = test::read_body_json::<OutputInboxCount, _>(resp).await;
+        assert_eq!(count.entry_count, 1);
+
+        let db_connection = db_thread_pool.get().unwrap();
+        let budget_entry_count = crate::schema::entries::table
+            .filter(crate::schema::entries::budget_id.eq(budget_id))
+            .count()
+            .get_result::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(budget_entry_count, 2);
+
+        let spent_cents = crate::schema::budget_category_totals::table
+            .select(crate::schema::budget_category_totals::spent_cents)
+            .filter(crate::schema::budget_category_totals::budget_id.eq(budget_id))
+            .filter(crate::schema::budget_category_totals::category.eq(0))
+            .first::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(spent_cents, 2050);
+
+        let req = test::TestRequest::post()
+            .uri("/api/inbox/dismiss")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputInboxEntryIds {
+                inbox_entry_ids: vec![inbox_entries[2].id],
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, get_count(&access_token)).await;
+        let count = test::read_body_json::<OutputInboxCount, _>(resp).await;
+        assert_eq!(count.entry_count, 0);
+    }
+}
@@ -0,0 +1,158 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::middleware;
+use crate::utils::live_updates;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+// A client that hasn't answered a ping (or sent anything else) in this long is assumed to be gone
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(75);
+
+// Sent in place of the updates a slow client missed so it knows to fetch its budgets again
+const MISSED_UPDATES_MSG: &str = r#"{"type":"missed_updates"}"#;
+
+pub async fn subscribe(
+    req: HttpRequest,
+    body: web::Payload,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user_id = auth_user_claims.0.uid;
+    let token_expiration = UNIX_EPOCH + Duration::from_secs(auth_user_claims.0.exp);
+
+    let (response, session, msg_stream) = actix_ws::handle(&req, body)?;
+    actix_web::rt::spawn(forward_updates(
+        user_id,
+        token_expiration,
+        session,
+        msg_stream,
+    ));
+
+    Ok(response)
+}
+
+async fn forward_updates(
+    user_id: Uuid,
+    token_expiration: SystemTime,
+    mut session: Session,
+    mut msg_stream: MessageStream,
+) {
+    let mut updates = live_updates::subscribe();
+    let mut heartbeat = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_heard_from_client = Instant::now();
+
+    // The connection is closed when the access token expires so a signed-out user doesn't keep
+    // getting updates. The client can reconnect with a fresh token.
+    let token_expired = actix_web::rt::time::sleep(
+        token_expiration
+            .duration_since(SystemTime::now())
+            .unwrap_or_default(),
+    );
+    tokio::pin!(token_expired);
+
+    let close_reason = loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let msg = match update {
+                    Ok(u) if u.is_for(user_id) => match serde_json::to_string(&u) {
+                        Ok(m) => m,
+                        Err(e) => {
+                            log::error!("Failed to serialize budget update: {}", e);
+                            continue;
+                        }
+                    },
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => String::from(MISSED_UPDATES_MSG),
+                    Err(RecvError::Closed) => break None,
+                };
+
+                if session.text(msg).await.is_err() {
+                    return;
+                }
+            }
+            msg = msg_stream.recv() => {
+                last_heard_from_client = Instant::now();
+
+                match msg {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => break reason,
+                    Some(Ok(_)) => (),
+                    Some(Err(_)) | None => break None,
+                }
+            }
+            _ = heartbeat.tick() => {
+                if last_heard_from_client.elapsed() > CLIENT_TIMEOUT {
+                    break None;
+                }
+
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            }
+            _ = &mut token_expired => {
+                break Some(CloseReason {
+                    code: CloseCode::Policy,
+                    description: Some(String::from("Token expired")),
+                });
+            }
+        }
+    };
+
+    let _ = session.close(close_reason).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http, test, App};
+
+    use crate::services;
+    use crate::utils::auth_token;
+
+    #[actix_rt::test]
+    async fn test_subscribe_handshake() {
+        let app = test::init_service(App::new().configure(services::live_updates::configure)).await;
+
+        let access_token = auth_token::generate_access_token(auth_token::TokenParams {
+            user_id: &uuid::Uuid::new_v4(),
+        })
+        .unwrap()
+        .to_string();
+
+        let upgrade_request = || {
+            test::TestRequest::get()
+                .uri("/ws")
+                .insert_header(("connection", "upgrade"))
+                .insert_header(("upgrade", "websocket"))
+                .insert_header(("sec-websocket-version", "13"))
+                .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+        };
+
+        let resp = test::call_service(&app, upgrade_request().to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = upgrade_request()
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(
+            resp.headers().get("sec-websocket-accept").unwrap(),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+
+        // A plain request isn't upgraded
+        let req = test::TestRequest::get()
+            .uri("/ws")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,313 @@
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::error;
+use sha2::{Digest, Sha256};
+
+use crate::definitions::DbThreadPool;
+use crate::env::{self, FeatureAvailability};
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{OutputCompatibility, OutputRemoteConfig};
+use crate::middleware;
+use crate::middleware::client_version::{self, APP_PLATFORM_HEADER, APP_VERSION_HEADER};
+use crate::utils::{app_version, db};
+
+pub async fn compatibility(req: HttpRequest) -> HttpResponse {
+    let platform = req
+        .headers()
+        .get(APP_PLATFORM_HEADER)
+        .and_then(|h| h.to_str().ok());
+    let version = req
+        .headers()
+        .get(APP_VERSION_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(app_version::parse_version);
+
+    let upgrade_required = match (platform.and_then(client_version::platform_conf), version) {
+        (Some(platform_conf), Some(version)) => {
+            let min_version = app_version::parse_version(&platform_conf.min_version)
+                .expect("Invalid client_compatibility min_version config");
+            Some(!app_version::is_version_at_least(&version, &min_version))
+        }
+        _ => None,
+    };
+
+    HttpResponse::Ok().json(OutputCompatibility {
+        android: env::CONF.client_compatibility.android.clone(),
+        ios: env::CONF.client_compatibility.ios.clone(),
+        upgrade_required,
+    })
+}
+
+pub async fn remote_config(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    req: HttpRequest,
+) -> Result<HttpResponse, ServerError> {
+    let user = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::get_user_by_id(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None))
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::AccessForbidden(Some("No user with ID")))
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get user data",
+                ))
+            }
+        },
+    };
+
+    let is_premium = user.has_premium(chrono::Utc::now().naive_utc().date());
+
+    let conf = &env::CONF.remote_config;
+
+    let feature_flags = conf
+        .feature_flags
+        .iter()
+        .map(|(name, availability)| {
+            let is_enabled = match availability {
+                FeatureAvailability::All => true,
+                FeatureAvailability::Premium => is_premium,
+                FeatureAvailability::Disabled => false,
+            };
+
+            (name.clone(), is_enabled)
+        })
+        .collect();
+
+    let limits = if is_premium {
+        conf.premium_limits.clone()
+    } else {
+        conf.free_limits.clone()
+    };
+
+    let output = OutputRemoteConfig {
+        is_premium,
+        feature_flags,
+        limits,
+        otp_length: env::CONF.otp.code_length,
+        otp_alphabet: env::CONF.otp.alphabet,
+        otp_lifetime_mins: env::reloadable_conf().lifetimes.otp_lifetime_mins,
+        display_hints: conf.display_hints.clone(),
+    };
+
+    let body = match serde_json::to_vec(&output) {
+        Ok(b) => b,
+        Err(e) => {
+            error!("{}", e);
+            return Err(ServerError::InternalError(Some(
+                "Failed to serialize remote config",
+            )));
+        }
+    };
+
+    // The config only differs between tiers, so the body hash is a stable ETag that lets
+    // clients skip re-downloading an unchanged config
+    let etag = format!("\"{}\"", hex::encode(Sha256::digest(&body)));
+
+    let is_unchanged = match req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|h| h.to_str().ok())
+    {
+        Some(tags) => tags
+            .split(',')
+            .any(|tag| tag.trim() == etag || tag.trim() == "*"),
+        None => false,
+    };
+
+    if is_unchanged {
+        return Ok(HttpResponse::NotModified()
+            .insert_header((header::ETAG, etag))
+            .finish());
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, "private, no-cache"))
+        .content_type("application/json")
+        .body(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+    use diesel::prelude::*;
+
+    use crate::models::user::User;
+    use crate::schema::users as user_fields;
+    use crate::schema::users::dsl::users;
+    use crate::services;
+    use crate::utils::auth_token;
+    use crate::utils::db::testing::create_test_user;
+
+    fn access_token_for(user: &User) -> String {
+        auth_token::generate_access_token(auth_token::TokenParams { user_id: &user.id })
+            .unwrap()
+            .to_string()
+    }
+
+    #[actix_rt::test]
+    async fn test_compatibility() {
+        let app = test::init_service(App::new().configure(services::api::configure)).await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/compatibility")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let compatibility = test::read_body_json::<OutputCompatibility, _>(resp).await;
+        assert_eq!(
+            compatibility.ios.min_version,
+            env::CONF.client_compatibility.ios.min_version
+        );
+        assert!(compatibility.upgrade_required.is_none());
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/compatibility")
+            .insert_header((APP_PLATFORM_HEADER, "ios"))
+            .insert_header((APP_VERSION_HEADER, "1.0"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let compatibility = test::read_body_json::<OutputCompatibility, _>(resp).await;
+        assert_eq!(compatibility.upgrade_required, Some(true));
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/compatibility")
+            .insert_header((APP_PLATFORM_HEADER, "ios"))
+            .insert_header((APP_VERSION_HEADER, "99.0.1"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let compatibility = test::read_body_json::<OutputCompatibility, _>(resp).await;
+        assert_eq!(compatibility.upgrade_required, Some(false));
+    }
+
+    #[actix_rt::test]
+    async fn test_remote_config() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let free_user = create_test_user(&db_connection);
+        let premium_user = create_test_user(&db_connection);
+
+        diesel::update(users.find(premium_user.id))
+            .set(user_fields::is_premium.eq(true))
+            .execute(&db_connection)
+            .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/remote_config")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/remote_config")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", access_token_for(&free_user)),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let free_etag = resp
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        let free_config = test::read_body_json::<OutputRemoteConfig, _>(resp).await;
+        assert!(!free_config.is_premium);
+        assert_eq!(free_config.otp_length, env::CONF.otp.code_length);
+        assert_eq!(free_config.otp_alphabet, env::CONF.otp.alphabet);
+        assert_eq!(free_config.limits, env::CONF.remote_config.free_limits);
+
+        for (name, availability) in env::CONF.remote_config.feature_flags.iter() {
+            assert_eq!(
+                free_config.feature_flags[name],
+                *availability == FeatureAvailability::All
+            );
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/remote_config")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", access_token_for(&premium_user)),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let premium_etag = resp
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned();
+        assert_ne!(free_etag, premium_etag);
+
+        let premium_config = test::read_body_json::<OutputRemoteConfig, _>(resp).await;
+        assert!(premium_config.is_premium);
+        assert_eq!(
+            premium_config.limits,
+            env::CONF.remote_config.premium_limits
+        );
+
+        for (name, availability) in env::CONF.remote_config.feature_flags.iter() {
+            assert_eq!(
+                premium_config.feature_flags[name],
+                *availability != FeatureAvailability::Disabled
+            );
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/remote_config")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", access_token_for(&free_user)),
+            ))
+            .insert_header((header::IF_NONE_MATCH, free_etag.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_MODIFIED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/meta/remote_config")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", access_token_for(&free_user)),
+            ))
+            .insert_header((header::IF_NONE_MATCH, premium_etag.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+}
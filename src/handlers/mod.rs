@@ -1,15 +1,33 @@
+pub mod admin;
 pub mod auth;
+pub mod automation;
+pub mod benchmarking;
 pub mod budget;
+pub mod engagement;
+pub mod health;
+pub mod inbox;
 pub mod index;
+pub mod live_updates;
+pub mod meta;
+pub mod notification;
+pub mod public;
+pub mod reminder;
+pub mod subscription;
+pub mod support;
 pub mod user;
 
 pub mod request_io;
 
 pub mod error {
-    use actix_web::http::{header, StatusCode};
-    use actix_web::{HttpResponse, HttpResponseBuilder};
+    use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+    use actix_web::http::StatusCode;
+    use actix_web::{HttpRequest, HttpResponse, HttpResponseBuilder};
+    use log::error;
+    use serde::Serialize;
     use std::fmt;
 
+    use crate::utils::auth_token::TokenError;
+
     #[allow(dead_code)]
     #[derive(Debug)]
     pub enum ServerError {
@@ -17,49 +35,179 @@ pub mod error {
         InvalidFormat(Option<&'static str>),
         InputRejected(Option<&'static str>),
         AlreadyExists(Option<&'static str>),
+        InvalidFields(Vec<FieldError>),
         UserUnauthorized(Option<&'static str>),
+        TokenExpired(Option<&'static str>),
         AccessForbidden(Option<&'static str>),
         NotFound(Option<&'static str>),
+        TooManyRequests(Option<&'static str>),
 
         // 500 Errors
         InternalError(Option<&'static str>),
         DatabaseTransactionError(Option<&'static str>),
+        ServiceUnavailable(Option<&'static str>),
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct FieldError {
+        pub field: &'static str,
+        pub message: &'static str,
+    }
+
+    // Every error response has this shape. Clients should branch on `error_code`, which won't
+    // change between releases; `message` is for humans and may be reworded.
+    #[derive(Debug, Serialize)]
+    pub struct ErrorEnvelope<'a> {
+        pub error_code: &'static str,
+        pub message: String,
+        #[serde(skip_serializing_if = "<[FieldError]>::is_empty")]
+        pub field_errors: &'a [FieldError],
     }
 
     impl std::error::Error for ServerError {}
 
+    impl ServerError {
+        pub fn error_code(&self) -> &'static str {
+            match self {
+                ServerError::InvalidFormat(_) => "invalid_format",
+                ServerError::InputRejected(_) => "input_rejected",
+                ServerError::AlreadyExists(_) => "already_exists",
+                ServerError::InvalidFields(_) => "invalid_fields",
+                ServerError::UserUnauthorized(_) => "unauthorized",
+                ServerError::TokenExpired(_) => "token_expired",
+                ServerError::AccessForbidden(_) => "forbidden",
+                ServerError::NotFound(_) => "not_found",
+                ServerError::TooManyRequests(_) => "too_many_requests",
+                ServerError::InternalError(_) => "internal_error",
+                ServerError::DatabaseTransactionError(_) => "database_error",
+                ServerError::ServiceUnavailable(_) => "service_unavailable",
+            }
+        }
+
+        // Constraint violations come from what the client sent (a duplicate, or a reference to
+        // something that doesn't exist) and are reported as such. Any other database error is
+        // logged and reported as a failed transaction with the given message.
+        pub fn from_database_error(e: diesel::result::Error, msg: &'static str) -> Self {
+            use diesel::result::{DatabaseErrorKind, Error};
+
+            match &e {
+                Error::InvalidCString(_) | Error::DeserializationError(_) => {
+                    ServerError::InvalidFormat(None)
+                }
+                Error::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                    ServerError::AlreadyExists(Some(match info.constraint_name() {
+                        Some("users_email_key") => {
+                            "A user with the given email address already exists"
+                        }
+                        Some("category_id_per_budget") => {
+                            "Budget already has a category with the given ID"
+                        }
+                        Some(
+                            "budget_share_events_recipient_user_id_sharer_user_id_budget_id_key",
+                        ) => "User has already been invited to the budget",
+                        Some("reimbursements_pkey") => "Entry is already marked as reimbursable",
+                        Some("entries_pkey") | Some("entries_id_key") => {
+                            "An entry with the given ID already exists"
+                        }
+                        Some("budget_comments_pkey") | Some("budget_comments_id_key") => {
+                            "A comment with the given ID already exists"
+                        }
+                        Some("closed_months_pkey") => "Month is already closed",
+                        Some("ub_only_one_association")
+                        | Some("user_budgets_user_id_budget_id_key") => {
+                            "User is already a member of the budget"
+                        }
+                        _ => "Record already exists",
+                    }))
+                }
+                Error::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                    ServerError::NotFound(Some(match info.constraint_name() {
+                        Some("budget_key") => "No budget with provided ID",
+                        Some("user_key") | Some("recipient_key") | Some("sharer_key") => {
+                            "No user with provided ID"
+                        }
+                        _ => "Referenced record does not exist",
+                    }))
+                }
+                Error::DatabaseError(_, info)
+                    if info.constraint_name() == Some("entry_in_closed_month") =>
+                {
+                    ServerError::AccessForbidden(Some(
+                        "Entries in a closed month can't be changed until an owner reopens the month",
+                    ))
+                }
+                Error::DatabaseError(_, info)
+                    if info.constraint_name() == Some("category_hard_cap") =>
+                {
+                    ServerError::AccessForbidden(Some(
+                        "Entry would take a category over its spending cap",
+                    ))
+                }
+                _ => {
+                    error!("{}", e);
+                    ServerError::DatabaseTransactionError(Some(msg))
+                }
+            }
+        }
+    }
+
     impl fmt::Display for ServerError {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             match self {
                 ServerError::InvalidFormat(msg) => format_err(f, "Invalid request format", msg),
                 ServerError::InputRejected(msg) => format_err(f, "Insecure password", msg),
                 ServerError::AlreadyExists(msg) => format_err(f, "Already exists", msg),
+                ServerError::InvalidFields(field_errors) => {
+                    write!(f, "Invalid fields: ")?;
+                    for (i, e) in field_errors.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, "; ")?;
+                        }
+                        write!(f, "{}: {}", e.field, e.message)?;
+                    }
+                    Ok(())
+                }
                 ServerError::UserUnauthorized(msg) => format_err(f, "User unauthorized", msg),
+                ServerError::TokenExpired(msg) => format_err(f, "Token expired", msg),
                 ServerError::AccessForbidden(msg) => format_err(f, "Access forbidden", msg),
                 ServerError::NotFound(msg) => format_err(f, "Not found", msg),
+                ServerError::TooManyRequests(msg) => format_err(f, "Too many requests", msg),
                 ServerError::InternalError(msg) => format_err(f, "Internal server error", msg),
                 ServerError::DatabaseTransactionError(msg) => {
                     format_err(f, "Database transaction failed", msg)
                 }
+                ServerError::ServiceUnavailable(msg) => format_err(f, "Service unavailable", msg),
             }
         }
     }
 
     impl actix_web::error::ResponseError for ServerError {
         fn error_response(&self) -> HttpResponse {
-            HttpResponseBuilder::new(self.status_code())
-                .insert_header((header::CONTENT_TYPE, "text/html; charset=utf-8"))
-                .body(self.to_string())
+            let field_errors = match self {
+                ServerError::InvalidFields(field_errors) => &field_errors[..],
+                _ => &[],
+            };
+
+            HttpResponseBuilder::new(self.status_code()).json(ErrorEnvelope {
+                error_code: self.error_code(),
+                message: self.to_string(),
+                field_errors,
+            })
         }
 
         fn status_code(&self) -> StatusCode {
             match *self {
                 ServerError::InvalidFormat(_)
                 | ServerError::InputRejected(_)
-                | ServerError::AlreadyExists(_) => StatusCode::BAD_REQUEST,
-                ServerError::UserUnauthorized(_) => StatusCode::UNAUTHORIZED,
+                | ServerError::AlreadyExists(_)
+                | ServerError::InvalidFields(_) => StatusCode::BAD_REQUEST,
+                ServerError::UserUnauthorized(_) | ServerError::TokenExpired(_) => {
+                    StatusCode::UNAUTHORIZED
+                }
                 ServerError::AccessForbidden(_) => StatusCode::FORBIDDEN,
                 ServerError::NotFound(_) => StatusCode::NOT_FOUND,
+                ServerError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+                ServerError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             }
         }
@@ -71,6 +219,36 @@ pub mod error {
         }
     }
 
+    // Token problems are the client's to fix with a fresh sign-in. Anything else is a server-side
+    // failure and gets logged.
+    impl From<TokenError> for ServerError {
+        fn from(e: TokenError) -> Self {
+            match e {
+                TokenError::TokenInvalid | TokenError::InvalidTokenType(_) => {
+                    ServerError::UserUnauthorized(Some("Token is invalid"))
+                }
+                TokenError::TokenBlacklisted => {
+                    ServerError::UserUnauthorized(Some("Token has been blacklisted"))
+                }
+                TokenError::TokenExpired => ServerError::TokenExpired(Some("Token has expired")),
+                TokenError::WrongTokenType => {
+                    ServerError::UserUnauthorized(Some("Incorrect token type"))
+                }
+                TokenError::WrongScope => {
+                    ServerError::AccessForbidden(Some("Token is not valid for this resource"))
+                }
+                TokenError::DatabaseError(_) => {
+                    error!("{}", e);
+                    ServerError::DatabaseTransactionError(Some("Error verifying token"))
+                }
+                TokenError::SystemResourceAccessFailure => {
+                    error!("{}", e);
+                    ServerError::InternalError(Some("Error verifying token"))
+                }
+            }
+        }
+    }
+
     impl From<std::result::Result<HttpResponse, ServerError>> for ServerError {
         fn from(result: std::result::Result<HttpResponse, ServerError>) -> Self {
             match result {
@@ -80,6 +258,25 @@ pub mod error {
         }
     }
 
+    // Actix renders extractor failures as plain text. These put them in the same envelope as every
+    // other error.
+    pub fn json_error_handler(e: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+        match e {
+            JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+                ServerError::InvalidFormat(Some("Request body is too large")).into()
+            }
+            _ => ServerError::InvalidFormat(Some("Invalid request body")).into(),
+        }
+    }
+
+    pub fn query_error_handler(_e: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+        ServerError::InvalidFormat(Some("Invalid query string")).into()
+    }
+
+    pub fn path_error_handler(_e: PathError, _req: &HttpRequest) -> actix_web::Error {
+        ServerError::NotFound(None).into()
+    }
+
     fn format_err(
         f: &mut fmt::Formatter<'_>,
         error_txt: &str,
@@ -97,3 +294,119 @@ pub mod error {
         )
     }
 }
+
+#[cfg(test)]
+pub mod testing {
+    use actix_web::web;
+    use chrono::{Duration, NaiveDate};
+    use rand::prelude::*;
+    use uuid::Uuid;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputUser, OutputBudget};
+    use crate::utils::{auth_token, db};
+
+    pub struct UserWithBudgetAndKey {
+        pub user_id: Uuid,
+        pub budget: OutputBudget,
+        pub key: String,
+    }
+
+    // The budget runs from ten days ago to twenty days from now and is owned by the new user, who
+    // also gets an API key
+    pub fn create_user_with_budget_and_key(categories: Vec<InputCategory>) -> UserWithBudgetAndKey {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("X3l%Jd8u!pQz2#vRs0Ke"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let user = db::user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        let today = chrono::Utc::now().naive_utc().date();
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: None,
+            categories,
+            start_date: today - Duration::days(10),
+            end_date: today + Duration::days(20),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let budget =
+            db::budget::create_budget(&db_connection, &web::Json(new_budget), user.id).unwrap();
+        let (_, key) = db::api_key::create_api_key(&db_connection, user.id, "Test Key").unwrap();
+
+        UserWithBudgetAndKey {
+            user_id: user.id,
+            budget,
+            key,
+        }
+    }
+
+    pub fn create_user_with_access_token(currency: &str) -> String {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("Zc4#pLw8!tRm2&vNq7Hy"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from(currency),
+        };
+
+        let user = db::user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        auth_token::generate_access_token(auth_token::TokenParams { user_id: &user.id })
+            .unwrap()
+            .to_string()
+    }
+
+    // The budget covers 2022 and has a single category with ID 0. Returns the user's ID, the
+    // budget's ID and an access token for the user.
+    pub fn create_user_and_budget_with_access_token() -> (Uuid, Uuid, String) {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user = db::testing::create_test_user(&db_connection);
+
+        let new_budget = InputBudget {
+            name: format!("Test Budget {}", user.first_name),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: String::from("Groceries"),
+                limit_cents: 30000,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: NaiveDate::from_ymd(2022, 1, 1),
+            end_date: NaiveDate::from_ymd(2022, 12, 31),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let budget =
+            db::budget::create_budget(&db_connection, &web::Json(new_budget), user.id).unwrap();
+
+        let access_token =
+            auth_token::generate_access_token(auth_token::TokenParams { user_id: &user.id })
+                .unwrap();
+
+        (user.id, budget.id, access_token.to_string())
+    }
+}
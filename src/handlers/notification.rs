@@ -0,0 +1,294 @@
+use actix_web::{web, HttpResponse};
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::budget::page_bounds;
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{InputNotificationId, InputPagination, OutputUnreadCount};
+use crate::middleware;
+use crate::utils::db;
+
+pub async fn list(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    pagination: web::Query<InputPagination>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let notifications = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::notification::get_notifications_for_user(
+            &db_connection,
+            auth_user_claims.0.uid,
+            limit,
+            offset,
+        )
+    })
+    .await?
+    {
+        Ok(n) => n,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get notifications",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(notifications))
+}
+
+pub async fn unread_count(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let unread_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::notification::count_unread_notifications(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to count unread notifications",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(OutputUnreadCount { unread_count }))
+}
+
+pub async fn mark_read(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    notification_id: web::Json<InputNotificationId>,
+) -> Result<HttpResponse, ServerError> {
+    let updated_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::notification::mark_notification_read(
+            &db_connection,
+            auth_user_claims.0.uid,
+            notification_id.notification_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to mark notification read",
+            ))
+        }
+    };
+
+    if updated_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No notification with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn mark_all_read(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::notification::mark_all_notifications_read(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(_) => (),
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to mark notifications read",
+            ))
+        }
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn dismiss(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    notification_id: web::Json<InputNotificationId>,
+) -> Result<HttpResponse, ServerError> {
+    let dismissed_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::notification::dismiss_notification(
+            &db_connection,
+            auth_user_claims.0.uid,
+            notification_id.notification_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to dismiss notification",
+            ))
+        }
+    };
+
+    if dismissed_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "No notification with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::env;
+    use crate::handlers::request_io::OutputNotificationPage;
+    use crate::handlers::testing::create_user_and_budget_with_access_token;
+    use crate::services;
+    use crate::utils::notification::{BudgetInvitationData, NotificationData};
+
+    #[actix_rt::test]
+    async fn test_notifications() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let (sharer_id, _, _) = create_user_and_budget_with_access_token();
+        let (recipient_id, _, access_token) = create_user_and_budget_with_access_token();
+
+        let mut share_events = Vec::new();
+        for _ in 0..2 {
+            let (_, budget_id, _) = create_user_and_budget_with_access_token();
+            let db_connection = db_thread_pool.get().unwrap();
+            share_events.push(
+                db::budget_share::invite_user(&db_connection, budget_id, recipient_id, sharer_id)
+                    .unwrap(),
+            );
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/unread_count")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let unread = test::read_body_json::<OutputUnreadCount, _>(resp).await;
+        assert_eq!(unread.unread_count, 2);
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/list?limit=1")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let page = test::read_body_json::<OutputNotificationPage, _>(resp).await;
+        assert_eq!(page.notifications.len(), 1);
+        assert!(page.has_more);
+
+        let newest = &page.notifications[0];
+        assert!(newest.is_unread);
+        assert_eq!(
+            newest.data,
+            Some(NotificationData::BudgetInvitation(BudgetInvitationData {
+                share_event_id: share_events[1].id,
+                budget_id: share_events[1].budget_id,
+                sharer_user_id: sharer_id,
+            }))
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/notification/mark_read")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputNotificationId {
+                notification_id: newest.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/unread_count")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let unread = test::read_body_json::<OutputUnreadCount, _>(resp).await;
+        assert_eq!(unread.unread_count, 1);
+
+        let req = test::TestRequest::post()
+            .uri("/api/notification/mark_all_read")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/unread_count")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let unread = test::read_body_json::<OutputUnreadCount, _>(resp).await;
+        assert_eq!(unread.unread_count, 0);
+
+        let req = test::TestRequest::post()
+            .uri("/api/notification/dismiss")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputNotificationId {
+                notification_id: newest.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let req = test::TestRequest::post()
+            .uri("/api/notification/dismiss")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputNotificationId {
+                notification_id: newest.id,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/list")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let page = test::read_body_json::<OutputNotificationPage, _>(resp).await;
+        assert_eq!(page.notifications.len(), 1);
+        assert!(!page.has_more);
+        assert_ne!(page.notifications[0].id, newest.id);
+    }
+}
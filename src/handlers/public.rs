@@ -0,0 +1,305 @@
+use actix_web::{web, HttpResponse};
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::budget::{ensure_user_in_budget, forecast_budget, page_bounds};
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{InputBudgetId, InputPagination, OutputBudgetPage};
+use crate::middleware;
+use crate::utils::db;
+
+// The public API is read-only. Every request counts against the key's daily quota, which the
+// API key middleware enforces before any of these handlers run.
+
+pub async fn budgets(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    pagination: web::Query<InputPagination>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let budgets = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_all_budgets_for_user(&db_connection, api_key_user.0, true, limit, offset)
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Ok(HttpResponse::Ok().json(OutputBudgetPage {
+                    budgets: Vec::new(),
+                    has_more: false,
+                }));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get budget data",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(budgets))
+}
+
+pub async fn budget(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    budget_id: web::Query<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), api_key_user.0, budget_id).await?;
+
+    let budget = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_budget_by_id(&db_connection, budget_id)
+    })
+    .await?
+    {
+        Ok(b) => b,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some("No budget with provided ID")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get budget data",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(budget))
+}
+
+pub async fn entries(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    budget_id: web::Query<InputBudgetId>,
+    pagination: web::Query<InputPagination>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), api_key_user.0, budget_id).await?;
+
+    let entries = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_entries_for_budget(&db_connection, budget_id, limit, offset)
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to get entries")),
+    };
+
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub async fn summary(
+    db_thread_pool: web::Data<DbThreadPool>,
+    api_key_user: middleware::api_key::ApiKeyUser,
+    budget_id: web::Query<InputBudgetId>,
+) -> Result<HttpResponse, ServerError> {
+    let budget_id = budget_id.budget_id;
+    ensure_user_in_budget(db_thread_pool.clone(), api_key_user.0, budget_id).await?;
+
+    let forecast = forecast_budget(db_thread_pool, budget_id, Vec::new()).await?;
+
+    Ok(HttpResponse::Ok().json(forecast))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::env;
+    use crate::handlers::request_io::{
+        InputCategory, InputEntry, OutputApiUsage, OutputBudget, OutputEntryPage,
+    };
+    use crate::handlers::testing::{self, UserWithBudgetAndKey};
+    use crate::middleware::api_key::API_KEY_HEADER;
+    use crate::services;
+    use crate::utils::auth_token;
+    use crate::utils::forecasting::BudgetForecast;
+
+    fn create_user_with_budget_and_key() -> UserWithBudgetAndKey {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+
+        let user_and_budget = testing::create_user_with_budget_and_key(vec![InputCategory {
+            id: 0,
+            name: String::from("Groceries"),
+            limit_cents: 10000,
+            color: String::from("#ff11ee"),
+        }]);
+
+        db::budget::create_entry(
+            &db_connection,
+            &web::Json(InputEntry {
+                id: None,
+                budget_id: user_and_budget.budget.id,
+                amount_cents: 2500,
+                date: chrono::Utc::now().naive_utc().date(),
+                name: Some(String::from("Market")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            }),
+            user_and_budget.user_id,
+        )
+        .unwrap();
+
+        user_and_budget
+    }
+
+    #[actix_rt::test]
+    async fn test_public_endpoints() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_and_budget = create_user_with_budget_and_key();
+        let other_user_and_budget = create_user_with_budget_and_key();
+        let budget_id = user_and_budget.budget.id;
+
+        let req = test::TestRequest::get()
+            .uri("/api/public/budgets")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/public/budgets")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let budget_page = test::read_body_json::<OutputBudgetPage, _>(resp).await;
+        assert_eq!(budget_page.budgets.len(), 1);
+        assert_eq!(budget_page.budgets[0].id, budget_id);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/public/budget?budget_id={budget_id}"))
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let budget = test::read_body_json::<OutputBudget, _>(resp).await;
+        assert_eq!(budget.id, budget_id);
+        assert_eq!(budget.categories.len(), 1);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/public/entries?budget_id={budget_id}"))
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let entry_page = test::read_body_json::<OutputEntryPage, _>(resp).await;
+        assert_eq!(entry_page.entries.len(), 1);
+        assert_eq!(entry_page.entries[0].amount_cents, 2500);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/public/summary?budget_id={budget_id}"))
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let summary = test::read_body_json::<BudgetForecast, _>(resp).await;
+        assert_eq!(summary.limit_cents, Some(10000));
+        assert_eq!(summary.spent_cents, 2500);
+
+        // Another user's key cannot read the budget
+        for path in ["budget", "entries", "summary"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/public/{path}?budget_id={budget_id}"))
+                .insert_header((API_KEY_HEADER, other_user_and_budget.key.as_str()))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(!resp.status().is_success());
+        }
+
+        // The public API is read-only
+        let req = test::TestRequest::post()
+            .uri("/api/public/budgets")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(!resp.status().is_success());
+    }
+
+    #[actix_rt::test]
+    async fn test_daily_request_limit() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_and_budget = create_user_with_budget_and_key();
+        let daily_request_limit = env::CONF.security.api_key_daily_request_limit;
+
+        for _ in 0..daily_request_limit {
+            let req = test::TestRequest::get()
+                .uri("/api/public/budgets")
+                .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::OK);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/public/budgets")
+            .insert_header((API_KEY_HEADER, user_and_budget.key.as_str()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::TOO_MANY_REQUESTS);
+
+        let access_token = auth_token::generate_access_token(auth_token::TokenParams {
+            user_id: &user_and_budget.user_id,
+        })
+        .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get_api_usage")
+            .insert_header((
+                "authorization",
+                format!("Bearer {}", &access_token.to_string()),
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let api_usage = test::read_body_json::<OutputApiUsage, _>(resp).await;
+        assert_eq!(api_usage.daily_request_limit, daily_request_limit);
+        assert_eq!(api_usage.usage.len(), 1);
+        assert_eq!(
+            api_usage.usage[0].usage_date,
+            chrono::Utc::now().naive_utc().date()
+        );
+        // Rejected requests still count against the quota
+        assert_eq!(api_usage.usage[0].request_count, daily_request_limit + 1);
+    }
+}
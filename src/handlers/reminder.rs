@@ -0,0 +1,494 @@
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, NaiveDate};
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::budget::{ensure_user_in_budget, page_bounds};
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::{
+    InputEditReminder, InputPagination, InputReminder, InputReminderId, InputSnoozeReminder,
+};
+use crate::middleware;
+use crate::utils::db;
+
+pub const MAX_REMINDER_NOTE_LENGTH: usize = 500;
+pub const MAX_SNOOZE_DAYS: i64 = 365;
+
+pub async fn list(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    pagination: web::Query<InputPagination>,
+) -> Result<HttpResponse, ServerError> {
+    let (limit, offset) = page_bounds(&pagination)?;
+
+    let reminders = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reminder::get_reminders_for_user(&db_connection, auth_user_claims.0.uid, limit, offset)
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get reminders",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(reminders))
+}
+
+pub async fn create(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    reminder_data: web::Json<InputReminder>,
+) -> Result<HttpResponse, ServerError> {
+    validate_reminder(&reminder_data.note, reminder_data.remind_date)?;
+
+    let user_id = auth_user_claims.0.uid;
+
+    match (reminder_data.budget_id, reminder_data.entry_id) {
+        (Some(budget_id), _) => {
+            ensure_user_in_budget(db_thread_pool.clone(), user_id, budget_id).await?
+        }
+        (None, Some(_)) => {
+            return Err(ServerError::InvalidFormat(Some(
+                "An entry can only be linked along with its budget",
+            )))
+        }
+        (None, None) => (),
+    }
+
+    let reminder = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reminder::create_reminder(&db_connection, user_id, &reminder_data)
+    })
+    .await?
+    {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return Err(ServerError::NotFound(Some(
+                "Budget has no entry with provided ID",
+            )))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to create reminder",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Created().json(reminder))
+}
+
+pub async fn edit(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    reminder_data: web::Json<InputEditReminder>,
+) -> Result<HttpResponse, ServerError> {
+    validate_reminder(&reminder_data.note, reminder_data.remind_date)?;
+
+    let updated_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reminder::edit_reminder(&db_connection, auth_user_claims.0.uid, &reminder_data)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to edit reminder",
+            ))
+        }
+    };
+
+    if updated_count == 0 {
+        return Err(ServerError::NotFound(Some("No reminder with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Pushes the reminder back by the given number of days from today. A reminder that has already
+// been delivered is delivered again then.
+pub async fn snooze(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    snooze_data: web::Json<InputSnoozeReminder>,
+) -> Result<HttpResponse, ServerError> {
+    if snooze_data.days < 1 || snooze_data.days > MAX_SNOOZE_DAYS {
+        return Err(ServerError::InputRejected(Some(
+            "Reminders can be snoozed for between 1 and 365 days",
+        )));
+    }
+
+    let remind_date = chrono::Utc::now().naive_utc().date() + Duration::days(snooze_data.days);
+
+    let updated_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reminder::snooze_reminder(
+            &db_connection,
+            auth_user_claims.0.uid,
+            snooze_data.reminder_id,
+            remind_date,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to snooze reminder",
+            ))
+        }
+    };
+
+    if updated_count == 0 {
+        return Err(ServerError::NotFound(Some("No reminder with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn delete(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    reminder_id: web::Json<InputReminderId>,
+) -> Result<HttpResponse, ServerError> {
+    let deleted_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::reminder::delete_reminder(
+            &db_connection,
+            auth_user_claims.0.uid,
+            reminder_id.reminder_id,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to delete reminder",
+            ))
+        }
+    };
+
+    if deleted_count == 0 {
+        return Err(ServerError::NotFound(Some("No reminder with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Reminders are delivered once a day, so one set for today is still delivered
+fn validate_reminder(note: &str, remind_date: NaiveDate) -> Result<(), ServerError> {
+    let note_length = note.trim().chars().count();
+    if note_length == 0 || note_length > MAX_REMINDER_NOTE_LENGTH {
+        return Err(ServerError::InvalidFormat(Some(
+            "Note must be between 1 and 500 characters",
+        )));
+    }
+
+    if remind_date < chrono::Utc::now().naive_utc().date() {
+        return Err(ServerError::InputRejected(Some(
+            "Reminder date cannot be in the past",
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::env;
+    use crate::handlers::request_io::{InputEntry, OutputNotificationPage, OutputReminderPage};
+    use crate::handlers::testing::create_user_and_budget_with_access_token;
+    use crate::models::reminder::Reminder;
+    use crate::services;
+    use crate::utils::notification::{NotificationData, ReminderData};
+
+    #[actix_rt::test]
+    async fn test_reminders() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let (user_id, budget_id, access_token) = create_user_and_budget_with_access_token();
+        let (_, other_budget_id, other_access_token) = create_user_and_budget_with_access_token();
+
+        let entry = db::budget::create_entry(
+            &db_thread_pool.get().unwrap(),
+            &web::Json(InputEntry {
+                id: None,
+                budget_id,
+                amount_cents: 1299,
+                date: NaiveDate::from_ymd(2022, 6, 1),
+                name: Some(String::from("Streaming trial")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            }),
+            user_id,
+        )
+        .unwrap();
+
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let create = |token: &str, reminder: InputReminder| {
+            test::TestRequest::post()
+                .uri("/api/reminder/create")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&reminder)
+                .to_request()
+        };
+
+        let req = create(
+            &access_token,
+            InputReminder {
+                note: String::from("Cancel the streaming trial"),
+                remind_date: today - Duration::days(1),
+                budget_id: None,
+                entry_id: None,
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = create(
+            &access_token,
+            InputReminder {
+                note: String::from("  "),
+                remind_date: today,
+                budget_id: None,
+                entry_id: None,
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let req = create(
+            &access_token,
+            InputReminder {
+                note: String::from("Cancel the streaming trial"),
+                remind_date: today,
+                budget_id: None,
+                entry_id: Some(entry.id),
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        // Only entries in the linked budget, and only budgets the user belongs to, can be linked
+        let req = create(
+            &other_access_token,
+            InputReminder {
+                note: String::from("Cancel the streaming trial"),
+                remind_date: today,
+                budget_id: Some(budget_id),
+                entry_id: Some(entry.id),
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = create(
+            &other_access_token,
+            InputReminder {
+                note: String::from("Cancel the streaming trial"),
+                remind_date: today,
+                budget_id: Some(other_budget_id),
+                entry_id: Some(entry.id),
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = create(
+            &access_token,
+            InputReminder {
+                note: String::from(" Cancel the streaming trial "),
+                remind_date: today,
+                budget_id: Some(budget_id),
+                entry_id: Some(entry.id),
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let trial_reminder = test::read_body_json::<Reminder, _>(resp).await;
+        assert_eq!(trial_reminder.user_id, user_id);
+        assert_eq!(trial_reminder.note, "Cancel the streaming trial");
+        assert_eq!(trial_reminder.entry_id, Some(entry.id));
+        assert!(!trial_reminder.is_delivered);
+
+        let req = create(
+            &access_token,
+            InputReminder {
+                note: String::from("Check the electric bill"),
+                remind_date: today + Duration::days(10),
+                budget_id: None,
+                entry_id: None,
+            },
+        );
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let bill_reminder = test::read_body_json::<Reminder, _>(resp).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/reminder/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {other_access_token}")))
+            .set_json(&InputEditReminder {
+                reminder_id: bill_reminder.id,
+                note: String::from("Pay the electric bill"),
+                remind_date: today + Duration::days(5),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::post()
+            .uri("/api/reminder/edit")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputEditReminder {
+                reminder_id: bill_reminder.id,
+                note: String::from("Pay the electric bill"),
+                remind_date: today + Duration::days(5),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        db::reminder::deliver_due_reminders(&db_thread_pool.get().unwrap(), today).unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/list")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let page = test::read_body_json::<OutputNotificationPage, _>(resp).await;
+        assert_eq!(page.notifications.len(), 1);
+        assert_eq!(
+            page.notifications[0].alt_message,
+            "Cancel the streaming trial"
+        );
+        assert_eq!(
+            page.notifications[0].data,
+            Some(NotificationData::Reminder(ReminderData {
+                reminder_id: trial_reminder.id,
+                budget_id: Some(budget_id),
+                entry_id: Some(entry.id),
+            }))
+        );
+
+        // Delivered reminders are listed after the ones still to come
+        let req = test::TestRequest::get()
+            .uri("/api/reminder/list")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let page = test::read_body_json::<OutputReminderPage, _>(resp).await;
+        assert_eq!(page.reminders.len(), 2);
+        assert_eq!(page.reminders[0].id, bill_reminder.id);
+        assert_eq!(page.reminders[0].note, "Pay the electric bill");
+        assert_eq!(page.reminders[0].remind_date, today + Duration::days(5));
+        assert_eq!(page.reminders[1].id, trial_reminder.id);
+        assert!(page.reminders[1].is_delivered);
+
+        let snooze = |days: i64| {
+            test::TestRequest::post()
+                .uri("/api/reminder/snooze")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputSnoozeReminder {
+                    reminder_id: trial_reminder.id,
+                    days,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, snooze(0)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, snooze(MAX_SNOOZE_DAYS + 1)).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let resp = test::call_service(&app, snooze(2)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        // A snoozed reminder isn't due again until its new date
+        db::reminder::deliver_due_reminders(&db_thread_pool.get().unwrap(), today).unwrap();
+        db::reminder::deliver_due_reminders(
+            &db_thread_pool.get().unwrap(),
+            today + Duration::days(2),
+        )
+        .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/notification/list")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let page = test::read_body_json::<OutputNotificationPage, _>(resp).await;
+        assert_eq!(page.notifications.len(), 2);
+
+        let req = test::TestRequest::get()
+            .uri("/api/reminder/list")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let page = test::read_body_json::<OutputReminderPage, _>(resp).await;
+        assert!(!page.reminders[0].is_delivered);
+        assert_eq!(page.reminders[1].id, trial_reminder.id);
+        assert!(page.reminders[1].is_delivered);
+        assert_eq!(page.reminders[1].remind_date, today + Duration::days(2));
+
+        let delete = |token: &str| {
+            test::TestRequest::post()
+                .uri("/api/reminder/delete")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {token}")))
+                .set_json(&InputReminderId {
+                    reminder_id: trial_reminder.id,
+                })
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, delete(&other_access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, delete(&access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let resp = test::call_service(&app, delete(&access_token)).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+}
@@ -1,5 +1,6 @@
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 use crate::utils::validators;
@@ -21,11 +22,23 @@ pub struct InputUserId {
     pub user_id: Uuid,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputToken {
+    pub token: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InputBudgetId {
     pub budget_id: Uuid,
 }
 
+// `month` may be any day of the month
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputBudgetMonth {
+    pub budget_id: Uuid,
+    pub month: NaiveDate,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InputBudgetShareEventId {
     pub share_event_id: Uuid,
@@ -37,6 +50,37 @@ pub struct InputDateRange {
     pub end_date: NaiveDate,
 }
 
+// A fiscal year runs for twelve months from the first of `start_month` in `year`. If no month is
+// given, it starts in the month set in the user's preferences. The report covers one quarter of the
+// fiscal year, from 1 to 4, if `quarter` is given.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputTaxYear {
+    pub year: i32,
+    pub start_month: Option<u32>,
+    #[serde(default)]
+    pub quarter: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct InputPagination {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Amounts are returned in the currency of whoever recorded them unless conversion is asked for
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct InputCurrencyConversion {
+    #[serde(default)]
+    pub convert_currency: bool,
+}
+
+// Archived budgets are left out of budget lists unless they are asked for
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct InputArchiveFilter {
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct InputUser {
     pub email: String,
@@ -69,6 +113,9 @@ pub struct InputEditUser {
     pub last_name: String,
     pub date_of_birth: NaiveDate,
     pub currency: String,
+    // Left as it is if not given
+    #[serde(default)]
+    pub fiscal_year_start_month: Option<i16>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -82,12 +129,34 @@ pub struct SigninTokenOtpPair {
     pub otp: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InputEmail {
+    pub email: String,
+}
+
+impl InputEmail {
+    pub fn validate_email_address(&self) -> validators::Validity {
+        validators::validate_email_address(&self.email)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InputPasswordReset {
+    pub reset_token: String,
+    pub new_password: String,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CurrentAndNewPasswordPair {
     pub current_password: String,
     pub new_password: String,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InputPassword {
+    pub password: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InputCategory {
     pub id: i16,
@@ -103,6 +172,10 @@ pub struct InputBudget {
     pub categories: Vec<InputCategory>,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
+    #[serde(default)]
+    pub is_tracking_only: bool,
+    #[serde(default)]
+    pub is_envelope: bool,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -120,12 +193,447 @@ pub struct UserInvitationToBudget {
     pub budget_id: Uuid,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetMember {
+    pub budget_id: Uuid,
+    pub user_id: Uuid,
+}
+
+// `role` is 0 for an owner, 1 for an editor, or 2 for a viewer
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetMemberRole {
+    pub budget_id: Uuid,
+    pub user_id: Uuid,
+    pub role: i16,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct InputEntry {
+    // Set by clients that create the entry offline. Must be a UUIDv7.
+    #[serde(default)]
+    pub id: Option<Uuid>,
     pub budget_id: Uuid,
     pub amount_cents: i64,
     pub date: NaiveDate,
     pub name: Option<String>,
     pub category: Option<i16>,
     pub note: Option<String>,
+    pub tax_cents: Option<i64>,
+    pub tip_cents: Option<i64>,
+    #[serde(default)]
+    pub is_deductible: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputInboxEntry {
+    pub amount_cents: i64,
+    pub date: NaiveDate,
+    pub name: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputInboxEntries {
+    pub entries: Vec<InputInboxEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputInboxEntryIds {
+    pub inbox_entry_ids: Vec<Uuid>,
+}
+
+// Every entry is given the same category
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputInboxAssignment {
+    pub inbox_entry_ids: Vec<Uuid>,
+    pub budget_id: Uuid,
+    pub category: Option<i16>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputEntryFilter {
+    pub budget_id: Uuid,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub category: Option<i16>,
+    pub import_batch_id: Option<Uuid>,
+}
+
+// Leaving out the confirmation makes the request a dry run. The expected count and token both
+// come from a prior dry run with the same filter.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct InputBulkEntryDeletion {
+    #[serde(flatten)]
+    pub filter: InputEntryFilter,
+    pub expected_count: Option<i64>,
+    pub confirmation_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputImportedEntry {
+    pub amount_cents: i64,
+    pub date: NaiveDate,
+    pub name: Option<String>,
+    pub category: Option<i16>,
+    pub note: Option<String>,
+}
+
+// Clients parse the source file and map its columns to entries, so the server only ever sees
+// entries. The source name is for the user to tell batches apart (e.g. the file name).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEntryImport {
+    pub budget_id: Uuid,
+    pub source_name: String,
+    pub entries: Vec<InputImportedEntry>,
+}
+
+// Says which columns of an uploaded CSV statement hold what, by header name. The date format uses
+// strftime syntax (e.g. "%m/%d/%Y").
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputColumnMapping {
+    pub date_column: String,
+    pub amount_column: String,
+    #[serde(default)]
+    pub name_column: Option<String>,
+    #[serde(default)]
+    pub note_column: Option<String>,
+    #[serde(default = "default_statement_date_format")]
+    pub date_format: String,
+    // For banks that write money leaving the account as a negative amount
+    #[serde(default)]
+    pub negate_amounts: bool,
+}
+
+fn default_statement_date_format() -> String {
+    String::from("%Y-%m-%d")
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputImportBatchId {
+    pub import_batch_id: Uuid,
+}
+
+// `kind` is 0 for a link, whose location is its URL, or 1 for a file, whose location is the key
+// storage gave it
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetResource {
+    pub budget_id: Uuid,
+    pub kind: i16,
+    pub title: String,
+    pub location: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetResourceId {
+    pub budget_id: Uuid,
+    pub resource_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputHardCapOverride {
+    #[serde(default)]
+    pub override_hard_cap: bool,
+}
+
+// Sent once the user has confirmed that an amount flagged as unusual is right
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputUnusualAmountConfirmation {
+    #[serde(default)]
+    pub confirm_unusual_amount: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputNewCategory {
+    pub budget_id: Uuid,
+    pub name: String,
+    pub limit_cents: i64,
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputCategoryImport {
+    pub budget_id: Uuid,
+    pub categories: Vec<InputCategory>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEditCategory {
+    pub budget_id: Uuid,
+    pub category_id: i16,
+    pub name: String,
+    pub limit_cents: i64,
+    pub color: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputCategoryId {
+    pub budget_id: Uuid,
+    pub category_id: i16,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputCategoryHardCap {
+    pub budget_id: Uuid,
+    pub category_id: i16,
+    pub is_hard_capped: bool,
+}
+
+// A negative amount moves money out of the category and back to be budgeted
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputFundAllocation {
+    pub budget_id: Uuid,
+    pub category: i16,
+    pub amount_cents: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEditEntry {
+    pub entry_id: Uuid,
+    pub amount_cents: i64,
+    pub date: NaiveDate,
+    pub name: Option<String>,
+    pub category: Option<i16>,
+    pub note: Option<String>,
+    pub tax_cents: Option<i64>,
+    pub tip_cents: Option<i64>,
+    #[serde(default)]
+    pub is_deductible: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEntryId {
+    pub entry_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputReimbursement {
+    pub budget_id: Uuid,
+    pub entry_id: Uuid,
+}
+
+// The payment is an entry in the same budget with a negative amount
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputReimbursementPayment {
+    pub budget_id: Uuid,
+    pub entry_id: Uuid,
+    pub payment_entry_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEntryAttachments {
+    pub budget_id: Uuid,
+    pub entry_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEntryAttachmentId {
+    pub budget_id: Uuid,
+    pub attachment_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputRecurringEntry {
+    pub budget_id: Uuid,
+    pub amount_cents: i64,
+    pub name: Option<String>,
+    pub category: Option<i16>,
+    pub note: Option<String>,
+    pub frequency: i16,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+// The schedule (frequency and start date) can't be edited. Changing it requires deleting the
+// recurring entry and creating a new one.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEditRecurringEntry {
+    pub recurring_entry_id: Uuid,
+    pub amount_cents: i64,
+    pub name: Option<String>,
+    pub category: Option<i16>,
+    pub note: Option<String>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputRecurringEntryId {
+    pub recurring_entry_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InputSimulatedChange {
+    ScaleCategory {
+        category: i16,
+        percent_change: i32,
+    },
+    AddRecurringExpense {
+        amount_cents: i64,
+        category: Option<i16>,
+        frequency: i16,
+        start_date: NaiveDate,
+        end_date: Option<NaiveDate>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetSimulation {
+    pub budget_id: Uuid,
+    pub changes: Vec<InputSimulatedChange>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetComment {
+    // Set by clients that create the comment offline. Must be a UUIDv7.
+    #[serde(default)]
+    pub id: Option<Uuid>,
+    pub budget_id: Uuid,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEditBudgetComment {
+    pub comment_id: Uuid,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBudgetCommentId {
+    pub comment_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputShoppingList {
+    pub budget_id: Uuid,
+    pub category: i16,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputShoppingListId {
+    pub shopping_list_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputShoppingListItem {
+    pub shopping_list_id: Uuid,
+    pub name: String,
+    pub estimated_cost_cents: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEditShoppingListItem {
+    pub item_id: Uuid,
+    pub name: String,
+    pub estimated_cost_cents: i64,
+    pub is_checked: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputShoppingListItemId {
+    pub item_id: Uuid,
+}
+
+// If `amount_cents` is omitted, the entry's amount is the sum of the estimated costs of the
+// checked items
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputCompleteShoppingList {
+    pub shopping_list_id: Uuid,
+    pub date: NaiveDate,
+    pub amount_cents: Option<i64>,
+}
+
+// Clients can attach the IDs of their most recent requests to help with tracing a problem
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputSupportTicket {
+    pub message: String,
+    #[serde(default)]
+    pub recent_request_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputNotificationId {
+    pub notification_id: Uuid,
+}
+
+// An entry can only be linked along with the budget it belongs to
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputReminder {
+    pub note: String,
+    pub remind_date: NaiveDate,
+    pub budget_id: Option<Uuid>,
+    pub entry_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputEditReminder {
+    pub reminder_id: Uuid,
+    pub note: String,
+    pub remind_date: NaiveDate,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputReminderId {
+    pub reminder_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputSnoozeReminder {
+    pub reminder_id: Uuid,
+    pub days: i64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputApiKeyName {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputApiKeyId {
+    pub key_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputSessionId {
+    pub session_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputDeviceToken {
+    pub platform: i16,
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputThresholdPercent {
+    pub threshold_percent: i16,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputSpendingChallenge {
+    pub budget_id: Uuid,
+    pub category_id: i16,
+    pub limit_cents: i64,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub notify_on_completion: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputSpendingChallengeId {
+    pub challenge_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputBenchmarkingProfile {
+    pub country_code: String,
+    pub household_size: i16,
+}
+
+// Modules left out keep their current level
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InputLogLevels {
+    pub default_level: Option<String>,
+    #[serde(default)]
+    pub module_levels: BTreeMap<String, String>,
 }
@@ -1,8 +1,19 @@
 use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
+use crate::env::{ClientPlatform, OtpAlphabet};
+use crate::models::api_key_usage::ApiKeyUsage;
+use crate::models::budget_comment::BudgetComment;
 use crate::models::category::Category;
 use crate::models::entry::Entry;
+use crate::models::import_batch::ImportBatch;
+use crate::models::inbox_entry::InboxEntry;
+use crate::models::reminder::Reminder;
+use crate::models::shopping_list_item::ShoppingListItem;
+use crate::utils::engagement::Badge;
+use crate::utils::notification::NotificationData;
+use crate::utils::unusual_amount::EntryWarning;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OutputUserPrivate {
@@ -17,6 +28,7 @@ pub struct OutputUserPrivate {
     pub last_name: String,
     pub date_of_birth: NaiveDate,
     pub currency: String,
+    pub fiscal_year_start_month: i16,
 
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
@@ -32,11 +44,27 @@ pub struct OutputUserPublic {
     pub currency: String,
 }
 
+// Follows RFC 7662. Only `active` is set for a token that isn't active.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputTokenIntrospection {
+    pub active: bool,
+    pub user_id: Option<uuid::Uuid>,
+    pub token_type: Option<String>,
+    pub exp: Option<u64>,
+    pub key_id: Option<String>,
+    pub scope: Option<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SigninToken {
     pub signin_token: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UploadToken {
+    pub upload_token: String,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TokenPair {
     pub access_token: String,
@@ -49,6 +77,9 @@ pub struct OutputBudget {
     pub is_shared: bool,
     pub is_private: bool,
     pub is_deleted: bool,
+    pub is_tracking_only: bool,
+    pub is_envelope: bool,
+    pub is_archived: bool,
 
     pub name: String,
     pub description: Option<String>,
@@ -62,3 +93,436 @@ pub struct OutputBudget {
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
 }
+
+// An invitation as shown to the users it was sent from and to. The recipient only learns who sent it
+// until they accept, so `budget` is left out for them before then.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputInvitation {
+    pub id: uuid::Uuid,
+
+    pub recipient_user_id: uuid::Uuid,
+    pub sharer_user_id: uuid::Uuid,
+    pub sharer_first_name: String,
+    pub sharer_last_name: String,
+
+    pub accepted: bool,
+    pub budget: Option<OutputInvitationBudget>,
+
+    pub share_timestamp: NaiveDateTime,
+    pub accepted_declined_timestamp: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputInvitationBudget {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputNotification {
+    pub id: uuid::Uuid,
+
+    pub is_unread: bool,
+    pub is_pristine: bool,
+
+    pub notification_type: i16,
+    pub alt_title: String,
+    pub alt_message: String,
+
+    // None if the notification has no associated data or the data couldn't be read
+    pub data: Option<NotificationData>,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputNotificationPage {
+    pub notifications: Vec<OutputNotification>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputReminderPage {
+    pub reminders: Vec<Reminder>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputUnreadCount {
+    pub unread_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputInboxCount {
+    pub entry_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputInboxPage {
+    pub entries: Vec<InboxEntry>,
+    pub has_more: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputShoppingList {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub category: i16,
+
+    pub name: String,
+    pub is_completed: bool,
+    pub items: Vec<ShoppingListItem>,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputEnvelope {
+    pub category: i16,
+    pub allocated_cents: i64,
+    pub spent_cents: i64,
+    pub available_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputEnvelopeSummary {
+    pub budget_id: uuid::Uuid,
+    pub income_cents: i64,
+    pub allocated_cents: i64,
+    pub to_be_budgeted_cents: i64,
+    pub envelopes: Vec<OutputEnvelope>,
+}
+
+// Limits and remaining amounts are omitted for tracking-only budgets and for entries that aren't in
+// a category
+// In the form the category import takes, so an export can be imported into another budget as is
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCategoryExport {
+    pub budget_id: uuid::Uuid,
+    pub categories: Vec<OutputExportedCategory>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputExportedCategory {
+    pub id: i16,
+    pub name: String,
+    pub limit_cents: i64,
+    pub color: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCategorySummary {
+    pub category_id: Option<i16>,
+    pub name: Option<String>,
+    pub limit_cents: Option<i64>,
+    pub spent_cents: i64,
+    pub remaining_cents: Option<i64>,
+    // The part of `spent_cents` that went to tax
+    pub tax_cents: i64,
+    pub entry_count: i64,
+    pub daily_spend_rate_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputBudgetSummary {
+    pub budget_id: uuid::Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub days_elapsed: i64,
+    pub limit_cents: Option<i64>,
+    pub spent_cents: i64,
+    pub remaining_cents: Option<i64>,
+    pub tax_cents: i64,
+    pub daily_spend_rate_cents: i64,
+    pub categories: Vec<OutputCategorySummary>,
+}
+
+// `status` is 0 while the reimbursement is outstanding and 1 once it has been submitted
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputReimbursement {
+    pub entry: Entry,
+    pub status: i16,
+    pub submitted_timestamp: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputReimbursementSummary {
+    pub budget_id: uuid::Uuid,
+    pub outstanding_cents: i64,
+    pub submitted_cents: i64,
+    pub paid_cents: i64,
+    pub unpaid: Vec<OutputReimbursement>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputTaxReportCategory {
+    pub budget_id: uuid::Uuid,
+    pub budget_name: String,
+    pub category_id: Option<i16>,
+    pub category_name: Option<String>,
+    pub deductible_cents: i64,
+    pub tax_cents: i64,
+    pub entry_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputTaxReport {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub deductible_cents: i64,
+    pub tax_cents: i64,
+    pub categories: Vec<OutputTaxReportCategory>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputBudgetPage {
+    pub budgets: Vec<OutputBudget>,
+    pub has_more: bool,
+}
+
+// The entry is saved either way. `warning` is left out when there is nothing to warn about.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCreatedEntry {
+    #[serde(flatten)]
+    pub entry: Entry,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<EntryWarning>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputEntryPage {
+    pub entries: Vec<Entry>,
+    pub has_more: bool,
+}
+
+// `action` is 0 for an added entry, 1 for an edited entry, 2 for a changed category limit and 3 for
+// a new member. `user_id` is null once the member who made the change has deleted their account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputAuditLogEvent {
+    pub id: uuid::Uuid,
+    pub user_id: Option<uuid::Uuid>,
+    pub action: i16,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputAuditLogPage {
+    pub events: Vec<OutputAuditLogEvent>,
+    pub has_more: bool,
+}
+
+// `category` is null for the uncategorized entries. `closed_by_user_id` is null once the member who
+// closed the month has deleted their account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputClosedMonthTotal {
+    pub category: Option<i16>,
+    pub spent_cents: i64,
+    pub entry_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputClosedMonth {
+    pub month: NaiveDate,
+    pub closed_by_user_id: Option<uuid::Uuid>,
+    pub closed_timestamp: NaiveDateTime,
+    pub totals: Vec<OutputClosedMonthTotal>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputBulkDeletionPreview {
+    pub matched_count: i64,
+    pub sample: Vec<Entry>,
+    // None when nothing matches
+    pub confirmation_token: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputBulkDeletion {
+    pub deleted_count: usize,
+}
+
+// Items are newest first. Each item's `modified_timestamp` is when it was deleted, and it's purged
+// `retention_days` after that.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputTrash {
+    pub budget_id: uuid::Uuid,
+    pub retention_days: u64,
+    pub entries: Vec<Entry>,
+    pub comments: Vec<BudgetComment>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputSkippedRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+// No batch is created if every row was skipped
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputStatementImport {
+    pub import_batch: Option<ImportBatch>,
+    pub imported_count: usize,
+    pub skipped_rows: Vec<OutputSkippedRow>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputApiKey {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub last_used_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputSession {
+    pub id: uuid::Uuid,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_used_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityEventType {
+    SignedIn,
+    PasswordReset,
+    ApiKeyCreated,
+    InvitationSent,
+    InvitationAccepted,
+    InvitationDeclined,
+    EntryCreated,
+    StatementImported,
+    BadgeEarned,
+    SupportTicketOpened,
+}
+
+// `id` is the ID of the record the event created or changed. `detail` holds something to identify
+// it by, such as a session's device name or a badge's name, when there is one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputActivityEvent {
+    pub event: ActivityEventType,
+    pub id: uuid::Uuid,
+    pub budget_id: Option<uuid::Uuid>,
+    pub detail: Option<String>,
+    pub timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputNewApiKey {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub key: String,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputApiUsage {
+    pub daily_request_limit: i32,
+    pub usage: Vec<ApiKeyUsage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCategoryThresholdCrossing {
+    // Stable across polls so automation platforms can deduplicate
+    pub id: String,
+    pub budget_id: uuid::Uuid,
+    pub budget_name: String,
+    pub category_id: i16,
+    pub category_name: String,
+    pub limit_cents: i64,
+    pub spent_cents: i64,
+    pub threshold_percent: i16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputSpendingChallenge {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub category_id: i16,
+
+    pub limit_cents: i64,
+    pub spent_cents: i64,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+
+    pub is_succeeded: Option<bool>,
+    pub notify_on_completion: bool,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputBadge {
+    pub badge: Badge,
+    pub earned_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputStreak {
+    pub no_spend_streak_days: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCategoryBenchmark {
+    pub category_name: String,
+    pub user_spent_cents: i64,
+    pub cohort_user_count: i32,
+    pub cohort_p25_cents: i64,
+    pub cohort_median_cents: i64,
+    pub cohort_p75_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputBenchmarkComparison {
+    pub country_code: String,
+    pub household_size: i16,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub categories: Vec<OutputCategoryBenchmark>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputCompatibility {
+    pub android: ClientPlatform,
+    pub ios: ClientPlatform,
+    // Only present when the request identifies the client's platform and version
+    pub upgrade_required: Option<bool>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputRemoteConfig {
+    pub is_premium: bool,
+    pub feature_flags: BTreeMap<String, bool>,
+    pub limits: BTreeMap<String, i64>,
+    pub otp_length: usize,
+    pub otp_alphabet: OtpAlphabet,
+    pub otp_lifetime_mins: u64,
+    pub display_hints: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputLogLevels {
+    pub default_level: String,
+    pub module_levels: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputConnectionPoolStats {
+    pub max_connections: u32,
+    pub connections: u32,
+    pub idle_connections: u32,
+    pub connections_in_use: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputReadiness {
+    pub is_database_available: bool,
+    pub connection_pool: OutputConnectionPoolStats,
+}
@@ -0,0 +1,134 @@
+use actix_web::{web, HttpResponse};
+use chrono::Duration;
+
+use crate::definitions::DbThreadPool;
+use crate::handlers::error::ServerError;
+use crate::middleware;
+use crate::utils::db;
+use crate::utils::subscription_detection;
+
+// Long enough to see a few occurrences of a yearly charge
+const DETECTION_WINDOW_DAYS: i64 = 3 * 366;
+
+pub async fn get_detected(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let since = chrono::Utc::now().naive_utc().date() - Duration::days(DETECTION_WINDOW_DAYS);
+
+    let user_entries = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::budget::get_all_entries_for_user_since(&db_connection, auth_user_claims.0.uid, since)
+    })
+    .await?
+    {
+        Ok(e) => e,
+        Err(e) => return Err(ServerError::from_database_error(e, "Failed to get entries")),
+    };
+
+    let subscriptions =
+        web::block(move || subscription_detection::detect_subscriptions(&user_entries)).await?;
+
+    Ok(HttpResponse::Ok().json(subscriptions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+    use chrono::NaiveDate;
+    use rand::prelude::*;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputEntry, InputUser};
+    use crate::services;
+    use crate::utils::auth_token;
+    use crate::utils::subscription_detection::{Cadence, DetectedSubscription};
+
+    #[actix_rt::test]
+    async fn test_get_detected() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("zQ4&nW7!pLs9#KdT2vXe"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let user = db::user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        let today = chrono::Utc::now().naive_utc().date();
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: String::from("Streaming"),
+                limit_cents: 5000,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: today - Duration::days(120),
+            end_date: today,
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let budget =
+            db::budget::create_budget(&db_connection, &web::Json(new_budget), user.id).unwrap();
+
+        for months_ago in 1..=3 {
+            let entry = InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents: 1099,
+                date: today - Duration::days(30 * months_ago),
+                name: Some(String::from("Music Streaming")),
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            db::budget::create_entry(&db_connection, &web::Json(entry), user.id).unwrap();
+        }
+
+        let access_token =
+            auth_token::generate_access_token(auth_token::TokenParams { user_id: &user.id })
+                .unwrap();
+
+        let req = test::TestRequest::get()
+            .uri("/api/subscriptions/detected")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let subscriptions = test::read_body_json::<Vec<DetectedSubscription>, _>(resp).await;
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].payee, "music streaming");
+        assert_eq!(subscriptions[0].cadence, Cadence::Monthly);
+        assert_eq!(subscriptions[0].typical_amount_cents, 1099);
+        assert_eq!(subscriptions[0].next_expected_date, today);
+    }
+}
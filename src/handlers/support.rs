@@ -0,0 +1,174 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::definitions::DbThreadPool;
+use crate::env;
+use crate::handlers::error::ServerError;
+use crate::handlers::request_io::InputSupportTicket;
+use crate::middleware;
+use crate::middleware::client_version::{APP_PLATFORM_HEADER, APP_VERSION_HEADER};
+use crate::utils::db;
+
+pub const MAX_MESSAGE_LENGTH: usize = 5000;
+pub const MAX_RECENT_REQUEST_IDS: usize = 20;
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+// Attached to every ticket so support doesn't have to ask for it. Nothing here should identify
+// the user beyond the user ID the ticket is already stored under.
+#[derive(Serialize)]
+struct SupportTicketContext {
+    app_platform: Option<String>,
+    app_version: Option<String>,
+    recent_request_ids: Vec<String>,
+    environment: String,
+    is_premium: bool,
+    premium_expiration: Option<NaiveDate>,
+    currency: String,
+}
+
+pub async fn create_ticket(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    ticket_data: web::Json<InputSupportTicket>,
+    req: HttpRequest,
+) -> Result<HttpResponse, ServerError> {
+    if ticket_data.message.trim().is_empty() {
+        return Err(ServerError::InputRejected(Some("Message cannot be empty")));
+    }
+
+    if ticket_data.message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(ServerError::InputRejected(Some("Message is too long")));
+    }
+
+    let app_platform = header_value(&req, APP_PLATFORM_HEADER);
+    let app_version = header_value(&req, APP_VERSION_HEADER);
+
+    let recent_request_ids = ticket_data
+        .recent_request_ids
+        .iter()
+        .filter(|id| id.len() <= MAX_REQUEST_ID_LENGTH)
+        .take(MAX_RECENT_REQUEST_IDS)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let user_id = auth_user_claims.0.uid;
+
+    let ticket = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        let user = db::user::get_user_by_id(&db_connection, user_id)?;
+
+        let context = SupportTicketContext {
+            app_platform,
+            app_version,
+            recent_request_ids,
+            environment: env::CONF.error_reporting.environment.clone(),
+            is_premium: user.is_premium,
+            premium_expiration: user.premium_expiration,
+            currency: user.currency,
+        };
+        let context = serde_json::to_string(&context)
+            .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+        db::support::create_ticket(&db_connection, user_id, &ticket_data.message, &context)
+    })
+    .await?
+    {
+        Ok(t) => t,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None));
+            }
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::AccessForbidden(Some("No user with ID")));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create support ticket",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Created().json(ticket))
+}
+
+fn header_value(req: &HttpRequest, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|h| h.to_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::handlers::testing::create_user_with_access_token;
+    use crate::models::support_ticket::SupportTicket;
+    use crate::services;
+
+    #[actix_rt::test]
+    async fn test_create_ticket() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let access_token = create_user_with_access_token("EUR");
+
+        let req = test::TestRequest::post()
+            .uri("/api/support/ticket")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputSupportTicket {
+                message: String::from(" "),
+                recent_request_ids: Vec::new(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let mut recent_request_ids = vec![String::from("x").repeat(MAX_REQUEST_ID_LENGTH + 1)];
+        recent_request_ids.extend((0..30).map(|i| format!("req-{i}")));
+
+        let req = test::TestRequest::post()
+            .uri("/api/support/ticket")
+            .insert_header(("content-type", "application/json"))
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .insert_header((APP_PLATFORM_HEADER, "iOS"))
+            .insert_header((APP_VERSION_HEADER, "1.4.0"))
+            .set_json(&InputSupportTicket {
+                message: String::from("My entries aren't syncing"),
+                recent_request_ids,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let ticket = test::read_body_json::<SupportTicket, _>(resp).await;
+        assert_eq!(ticket.message, "My entries aren't syncing");
+        assert!(!ticket.is_resolved);
+
+        let context = serde_json::from_str::<serde_json::Value>(&ticket.context).unwrap();
+        assert_eq!(context["app_platform"], "iOS");
+        assert_eq!(context["app_version"], "1.4.0");
+        assert_eq!(context["environment"], "testing");
+        assert_eq!(context["currency"], "EUR");
+
+        let context_request_ids = context["recent_request_ids"].as_array().unwrap();
+        assert_eq!(context_request_ids.len(), MAX_RECENT_REQUEST_IDS);
+        assert_eq!(context_request_ids[0], "req-0");
+    }
+}
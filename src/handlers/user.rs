@@ -1,17 +1,32 @@
 use actix_web::{web, HttpResponse};
+use chrono::{Duration, NaiveDate};
+use futures::stream;
 use log::error;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::definitions::DbThreadPool;
 use crate::env;
-use crate::handlers::error::ServerError;
+use crate::handlers::error::{FieldError, ServerError};
 use crate::handlers::request_io::{
-    CurrentAndNewPasswordPair, InputEditUser, InputUser, OutputUserPrivate, SigninToken,
+    CredentialPair, CurrentAndNewPasswordPair, InputApiKeyId, InputApiKeyName, InputDateRange,
+    InputDeviceToken, InputEditUser, InputPassword, InputSessionId, InputTaxYear, InputUser,
+    OutputApiKey, OutputApiUsage, OutputNewApiKey, OutputSession, OutputTaxReport,
+    OutputUserPrivate, SigninToken,
 };
 use crate::middleware;
+use crate::utils::attempt_counter::{AttemptKind, ATTEMPT_COUNTER};
 use crate::utils::db;
+use crate::utils::fiscal_calendar::FiscalCalendar;
+use crate::utils::push::DevicePlatform;
 use crate::utils::{auth_token, otp, password_hasher, validators};
 
+const API_USAGE_HISTORY_DAYS: i64 = 30;
+
+pub const MAX_DEVICE_TOKEN_LENGTH: usize = 512;
+
+pub const MAX_ACTIVITY_EXPORT_DAYS: i64 = 366;
+const ACTIVITY_EXPORT_WINDOW_DAYS: i64 = 7;
+
 pub async fn get(
     db_thread_pool: web::Data<DbThreadPool>,
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
@@ -34,10 +49,10 @@ pub async fn get(
                 return Err(ServerError::AccessForbidden(Some("No user with ID")))
             }
             _ => {
-                error!("{}", e);
-                return Err(ServerError::DatabaseTransactionError(Some(
+                return Err(ServerError::from_database_error(
+                    e,
                     "Failed to get user data",
-                )));
+                ))
             }
         },
     };
@@ -52,6 +67,7 @@ pub async fn get(
         last_name: user.last_name,
         date_of_birth: user.date_of_birth,
         currency: user.currency,
+        fiscal_year_start_month: user.fiscal_year_start_month,
         modified_timestamp: user.modified_timestamp,
         created_timestamp: user.created_timestamp,
     };
@@ -63,12 +79,31 @@ pub async fn create(
     db_thread_pool: web::Data<DbThreadPool>,
     user_data: web::Json<InputUser>,
 ) -> Result<HttpResponse, ServerError> {
+    let mut field_errors = Vec::new();
+
     if !user_data.0.validate_email_address().is_valid() {
-        return Err(ServerError::InvalidFormat(Some("Invalid email address")));
+        field_errors.push(FieldError {
+            field: "email",
+            message: "Invalid email address",
+        });
+    } else if let validators::Validity::Invalid(msg) =
+        validators::validate_email_domain(&user_data.email)
+    {
+        field_errors.push(FieldError {
+            field: "email",
+            message: msg,
+        });
     }
 
     if let validators::Validity::Invalid(msg) = user_data.0.validate_strong_password() {
-        return Err(ServerError::InputRejected(Some(msg)));
+        field_errors.push(FieldError {
+            field: "password",
+            message: msg,
+        });
+    }
+
+    if !field_errors.is_empty() {
+        return Err(ServerError::InvalidFields(field_errors));
     }
 
     let user = match web::block(move || {
@@ -107,11 +142,8 @@ pub async fn create(
         },
     };
 
-    let signin_token = auth_token::generate_signin_token(auth_token::TokenParams {
-        user_id: &user.id,
-        user_email: &user.email,
-        user_currency: &user.currency,
-    });
+    let signin_token =
+        auth_token::generate_signin_token(auth_token::TokenParams { user_id: &user.id });
 
     let signin_token = match signin_token {
         Ok(signin_token) => signin_token,
@@ -134,7 +166,7 @@ pub async fn create(
 
     let otp = match otp::generate_otp(
         user.id,
-        current_time + env::CONF.lifetimes.otp_lifetime_mins * 60,
+        current_time + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60,
     ) {
         Ok(p) => p,
         Err(e) => {
@@ -154,6 +186,15 @@ pub async fn edit(
     auth_user_claims: middleware::auth::AuthorizedUserClaims,
     user_data: web::Json<InputEditUser>,
 ) -> Result<HttpResponse, ServerError> {
+    if user_data
+        .fiscal_year_start_month
+        .is_some_and(|m| !(1..=12).contains(&m))
+    {
+        return Err(ServerError::InvalidFormat(Some(
+            "Fiscal year start month must be from 1 to 12",
+        )));
+    }
+
     web::block(move || {
         let db_connection = db_thread_pool
             .get()
@@ -235,194 +276,978 @@ pub async fn change_password(
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    use actix_web::web::Data;
-    use actix_web::{http, test, App};
-    use chrono::NaiveDate;
-    use diesel::prelude::*;
-    use rand::prelude::*;
-
-    use crate::env;
-    use crate::handlers::request_io::{SigninTokenOtpPair, TokenPair};
-    use crate::models::user::User;
-    use crate::schema::users as user_fields;
-    use crate::schema::users::dsl::users;
-    use crate::services;
-    use crate::utils::auth_token::TokenClaims;
-
-    #[actix_rt::test]
-    async fn test_create() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(db_thread_pool.clone()))
-                .configure(services::api::configure),
-        )
-        .await;
-
-        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+// Merges a second account the user owns into the signed-in account. The user proves they own the
+// second account by providing its credentials.
+pub async fn merge_account(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    secondary_credentials: web::Json<CredentialPair>,
+) -> Result<HttpResponse, ServerError> {
+    const INVALID_CREDENTIALS_MSG: &str = "Incorrect email or password for account to merge";
 
-        let new_user = InputUser {
-            email: format!("test_user{}@test.com", &user_number),
-            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
-            first_name: format!("Test-{}", &user_number),
-            last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            currency: String::from("USD"),
-        };
+    if !secondary_credentials.validate_email_address().is_valid() {
+        return Err(ServerError::InvalidFormat(Some("Invalid email address")));
+    }
 
-        let req = test::TestRequest::post()
-            .uri("/api/user/create")
-            .insert_header(("content-type", "application/json"))
-            .set_json(&new_user)
-            .to_request();
+    let primary_user_id = auth_user_claims.0.uid;
+    let password = secondary_credentials.password.clone();
 
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    let db_thread_pool_copy = db_thread_pool.clone();
 
-        let db_connection = db_thread_pool.get().unwrap();
+    let secondary_user = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
 
-        let created_user = users
-            .filter(user_fields::email.eq(&new_user.email.to_lowercase()))
-            .first::<User>(&db_connection)
-            .unwrap();
+        db::user::get_user_by_email(&db_connection, &secondary_credentials.email)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(_) => return Err(ServerError::UserUnauthorized(Some(INVALID_CREDENTIALS_MSG))),
+    };
 
-        assert_eq!(&new_user.email, &created_user.email);
-        assert_ne!(&new_user.password, &created_user.password_hash);
-        assert_eq!(&new_user.first_name, &created_user.first_name);
-        assert_eq!(&new_user.last_name, &created_user.last_name);
-        assert_eq!(&new_user.date_of_birth, &created_user.date_of_birth);
-        assert_eq!(&new_user.currency, &created_user.currency);
+    if secondary_user.id == primary_user_id {
+        return Err(ServerError::InputRejected(Some(
+            "Cannot merge an account into itself",
+        )));
     }
 
-    #[actix_rt::test]
-    async fn test_edit() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(db_thread_pool.clone()))
-                .configure(services::api::configure),
-        )
-        .await;
+    let secondary_user_id = secondary_user.id;
+    let db_thread_pool_copy = db_thread_pool.clone();
 
-        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
-        let new_user = InputUser {
-            email: format!("test_user{}@test.com", &user_number),
-            password: String::from("1dIbCx^n@VF9f&0*c*39"),
-            first_name: format!("Test-{}", &user_number),
-            last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            currency: String::from("USD"),
-        };
+    let attempts = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
+        ATTEMPT_COUNTER.get_and_increment(&db_connection, AttemptKind::Password, secondary_user_id)
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to check password attempt count",
+            ))
+        }
+    };
 
-        let create_user_res = test::call_service(
-            &app,
-            test::TestRequest::post()
-                .uri("/api/user/create")
-                .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
-                .to_request(),
-        )
-        .await;
+    if attempts > env::CONF.security.password_max_attempts {
+        return Err(ServerError::AccessForbidden(Some(
+            "Too many login attempts. Try again in a few minutes.",
+        )));
+    }
 
-        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
-        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
-            .unwrap()
-            .uid;
+    let does_password_match_hash =
+        web::block(move || password_hasher::verify_hash(&password, &secondary_user.password_hash))
+            .await?;
 
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
+    if !does_password_match_hash {
+        return Err(ServerError::UserUnauthorized(Some(INVALID_CREDENTIALS_MSG)));
+    }
 
-        let token_and_otp = SigninTokenOtpPair {
-            signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
-        };
+    match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::merge_accounts(&db_connection, primary_user_id, secondary_user_id)
+    })
+    .await?
+    {
+        Ok(_) => (),
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to merge accounts",
+            ))
+        }
+    };
 
-        let req = test::TestRequest::post()
-            .uri("/api/auth/verify_otp_for_signin")
-            .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
-            .to_request();
+    Ok(HttpResponse::Ok().finish())
+}
 
-        let res = test::call_service(&app, req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
-        let access_token = token_pair.access_token.to_string();
+// Schedules the account for deletion once the grace period has passed. Every refresh token issued
+// up to this point is revoked; the user can sign in again to cancel the deletion.
+pub async fn delete_account(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    password: web::Json<InputPassword>,
+) -> Result<HttpResponse, ServerError> {
+    let user_id = auth_user_claims.0.uid;
+    let db_thread_pool_copy = db_thread_pool.clone();
 
-        let edited_user = InputEditUser {
-            first_name: format!("Test-{}-edited", &user_number),
-            last_name: new_user.last_name.clone(),
-            date_of_birth: new_user.date_of_birth.clone(),
-            currency: String::from("DOP"),
-        };
+    let user = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
 
-        let req = test::TestRequest::post()
-            .uri("/api/user/edit")
-            .insert_header((
-                "authorization",
-                format!("bearer {}", &access_token).as_str(),
-            ))
-            .set_json(&edited_user)
-            .to_request();
+        db::user::get_user_by_id(&db_connection, user_id)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(e) => {
+            error!("{}", e);
+            return Err(ServerError::InputRejected(Some("User not found")));
+        }
+    };
 
-        let res = test::call_service(&app, req).await;
-        assert_eq!(res.status(), http::StatusCode::OK);
+    let db_thread_pool_copy = db_thread_pool.clone();
 
-        let after_edit_get_req = test::TestRequest::get()
-            .uri("/api/user/get")
-            .insert_header((
-                "authorization",
-                format!("bearer {}", &access_token).as_str(),
+    let attempts = match web::block(move || {
+        let db_connection = db_thread_pool_copy
+            .get()
+            .expect("Failed to access database thread pool");
+        ATTEMPT_COUNTER.get_and_increment(&db_connection, AttemptKind::Password, user_id)
+    })
+    .await?
+    {
+        Ok(a) => a,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to check password attempt count",
             ))
-            .to_request();
-
-        let after_edit_get_res = test::call_service(&app, after_edit_get_req).await;
+        }
+    };
 
-        let res_body = String::from_utf8(
-            actix_web::test::read_body(after_edit_get_res)
-                .await
-                .to_vec(),
-        )
-        .unwrap();
-        let user_after_edit = serde_json::from_str::<OutputUserPrivate>(res_body.as_str()).unwrap();
+    if attempts > env::CONF.security.password_max_attempts {
+        return Err(ServerError::AccessForbidden(Some(
+            "Too many login attempts. Try again in a few minutes.",
+        )));
+    }
 
-        assert_eq!(&new_user.email, &user_after_edit.email);
-        assert_eq!(&new_user.last_name, &user_after_edit.last_name);
-        assert_eq!(&new_user.date_of_birth, &user_after_edit.date_of_birth);
+    let does_password_match_hash =
+        web::block(move || password_hasher::verify_hash(&password.password, &user.password_hash))
+            .await?;
 
-        assert_eq!(&edited_user.first_name, &user_after_edit.first_name);
-        assert_eq!(&edited_user.currency, &user_after_edit.currency);
+    if !does_password_match_hash {
+        return Err(ServerError::UserUnauthorized(Some("Incorrect password")));
     }
 
-    #[actix_rt::test]
-    async fn test_create_fails_with_invalid_email() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(db_thread_pool.clone()))
-                .configure(services::api::configure),
+    let grace_period = chrono::Duration::days(
+        i64::try_from(
+            env::reloadable_conf()
+                .lifetimes
+                .account_deletion_grace_period_days,
         )
-        .await;
+        .expect("Invalid account_deletion_grace_period_days config"),
+    );
 
-        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+    let pending_deletion = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::schedule_deletion(&db_connection, user_id, grace_period)
+    })
+    .await?
+    {
+        Ok(p) => p,
+        Err(e) => match e {
+            diesel::result::Error::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) => {
+                return Err(ServerError::AlreadyExists(Some(
+                    "Account is already scheduled for deletion",
+                )));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to schedule account deletion",
+                ))
+            }
+        },
+    };
 
-        let new_user = InputUser {
+    Ok(HttpResponse::Ok().json(pending_deletion))
+}
+
+pub async fn get_pending_deletion(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let pending_deletion = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::get_pending_deletion(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(p) => p,
+        Err(e) => match e {
+            diesel::result::Error::NotFound => {
+                return Err(ServerError::NotFound(Some(
+                    "Account is not scheduled for deletion",
+                )));
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to get pending account deletion",
+                ))
+            }
+        },
+    };
+
+    Ok(HttpResponse::Ok().json(pending_deletion))
+}
+
+pub async fn cancel_deletion(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let cancelled_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::cancel_deletion(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to cancel account deletion",
+            ))
+        }
+    };
+
+    if cancelled_count == 0 {
+        return Err(ServerError::NotFound(Some(
+            "Account is not scheduled for deletion",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn create_api_key(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    key_data: web::Json<InputApiKeyName>,
+) -> Result<HttpResponse, ServerError> {
+    if key_data.name.is_empty() || key_data.name.len() > 120 {
+        return Err(ServerError::InvalidFormat(Some(
+            "API key name must be between 1 and 120 characters",
+        )));
+    }
+
+    let (api_key, key) = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::api_key::create_api_key(&db_connection, auth_user_claims.0.uid, &key_data.name)
+    })
+    .await?
+    {
+        Ok(k) => k,
+        Err(e) => match e {
+            diesel::result::Error::InvalidCString(_)
+            | diesel::result::Error::DeserializationError(_) => {
+                return Err(ServerError::InvalidFormat(None))
+            }
+            _ => {
+                return Err(ServerError::from_database_error(
+                    e,
+                    "Failed to create API key",
+                ))
+            }
+        },
+    };
+
+    let output_key = OutputNewApiKey {
+        id: api_key.id,
+        name: api_key.name,
+        key,
+        created_timestamp: api_key.created_timestamp,
+    };
+
+    Ok(HttpResponse::Created().json(output_key))
+}
+
+pub async fn get_api_keys(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let api_keys = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::api_key::get_all_api_keys_for_user(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(k) => k,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get API keys",
+            ))
+        }
+    };
+
+    let output_keys = api_keys
+        .into_iter()
+        .map(|k| OutputApiKey {
+            id: k.id,
+            name: k.name,
+            last_used_timestamp: k.last_used_timestamp,
+            created_timestamp: k.created_timestamp,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(output_keys))
+}
+
+pub async fn revoke_api_key(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    key_data: web::Json<InputApiKeyId>,
+) -> Result<HttpResponse, ServerError> {
+    let revoked_count = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::api_key::revoke_api_key(&db_connection, auth_user_claims.0.uid, key_data.key_id)
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to revoke API key",
+            ))
+        }
+    };
+
+    if revoked_count == 0 {
+        return Err(ServerError::NotFound(Some("No API key with provided ID")));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn get_sessions(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let sessions = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::session::get_active_sessions_for_user(&db_connection, auth_user_claims.0.uid)
+    })
+    .await?
+    {
+        Ok(s) => s,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get sessions",
+            ))
+        }
+    };
+
+    let output_sessions = sessions
+        .into_iter()
+        .map(|s| OutputSession {
+            id: s.id,
+            device_name: s.device_name,
+            user_agent: s.user_agent,
+            ip_address: s.ip_address,
+            last_used_timestamp: s.last_used_timestamp,
+            created_timestamp: s.created_timestamp,
+        })
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(output_sessions))
+}
+
+// The session's refresh token stops working right away. Access tokens already issued for it keep
+// working until they expire.
+pub async fn revoke_session(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    session_data: web::Json<InputSessionId>,
+) -> Result<HttpResponse, ServerError> {
+    let was_revoked = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::session::revoke_session_for_user(
+            &db_connection,
+            auth_user_claims.0.uid,
+            session_data.session_id,
+        )
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to revoke session",
+            ))
+        }
+    };
+
+    if !was_revoked {
+        return Err(ServerError::NotFound(Some(
+            "No active session with provided ID",
+        )));
+    }
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// The apps register the token APNs or FCM gave the device whenever it changes. New notifications
+// are then pushed to the device.
+pub async fn register_device(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    device_token: web::Json<InputDeviceToken>,
+) -> Result<HttpResponse, ServerError> {
+    let platform = match DevicePlatform::try_from(device_token.platform) {
+        Ok(p) => p,
+        Err(_) => return Err(ServerError::InvalidFormat(Some("Invalid device platform"))),
+    };
+
+    let token_length = device_token.token.trim().len();
+    if token_length == 0 || token_length > MAX_DEVICE_TOKEN_LENGTH {
+        return Err(ServerError::InputRejected(Some(
+            "Device token must be between 1 and 512 characters",
+        )));
+    }
+
+    match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::push::register_device_token(
+            &db_connection,
+            auth_user_claims.0.uid,
+            device_token.token.trim(),
+            platform,
+        )
+    })
+    .await?
+    {
+        Ok(_) => (),
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to register device",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+// Streams the user's activity between the two dates, inclusive, as JSON lines. The range is read
+// one week at a time, so only a week of events is ever held in memory.
+pub async fn export_activity(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    date_range: web::Json<InputDateRange>,
+) -> Result<HttpResponse, ServerError> {
+    if date_range.start_date > date_range.end_date {
+        return Err(ServerError::InputRejected(Some(
+            "End date cannot come before start date",
+        )));
+    }
+
+    if (date_range.end_date - date_range.start_date).num_days() >= MAX_ACTIVITY_EXPORT_DAYS {
+        return Err(ServerError::InputRejected(Some(
+            "Activity can be exported for at most 366 days at a time",
+        )));
+    }
+
+    let user_id = auth_user_claims.0.uid;
+    let end_date = date_range.end_date + Duration::days(1);
+
+    let chunks = stream::try_unfold(
+        Some(date_range.start_date),
+        move |window_start: Option<NaiveDate>| {
+            let db_thread_pool = db_thread_pool.clone();
+
+            async move {
+                let window_start = match window_start {
+                    Some(d) => d,
+                    None => return Ok(None),
+                };
+
+                let window_end = std::cmp::min(
+                    window_start + Duration::days(ACTIVITY_EXPORT_WINDOW_DAYS),
+                    end_date,
+                );
+
+                let events = match web::block(move || {
+                    let db_connection = db_thread_pool
+                        .get()
+                        .expect("Failed to access database thread pool");
+                    db::activity::get_activity_for_user(
+                        &db_connection,
+                        user_id,
+                        window_start.and_hms(0, 0, 0),
+                        window_end.and_hms(0, 0, 0),
+                    )
+                })
+                .await?
+                {
+                    Ok(e) => e,
+                    Err(e) => {
+                        return Err(ServerError::from_database_error(
+                            e,
+                            "Failed to export activity",
+                        ))
+                    }
+                };
+
+                let mut lines = Vec::new();
+                for event in events.iter() {
+                    if serde_json::to_writer(&mut lines, event).is_err() {
+                        return Err(ServerError::InternalError(Some(
+                            "Failed to serialize activity event",
+                        )));
+                    }
+
+                    lines.push(b'\n');
+                }
+
+                let next_window_start = if window_end < end_date {
+                    Some(window_end)
+                } else {
+                    None
+                };
+
+                Ok(Some((web::Bytes::from(lines), next_window_start)))
+            }
+        },
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(chunks))
+}
+
+pub async fn get_tax_report(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    tax_year: web::Json<InputTaxYear>,
+) -> Result<HttpResponse, ServerError> {
+    let report = load_tax_report(db_thread_pool, auth_user_claims.0.uid, &tax_year).await?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// One row per budget category, amounts in decimal rather than cents, followed by a row of totals
+pub async fn export_tax_report(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+    tax_year: web::Json<InputTaxYear>,
+) -> Result<HttpResponse, ServerError> {
+    let report = load_tax_report(db_thread_pool, auth_user_claims.0.uid, &tax_year).await?;
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut rows = vec![[
+        String::from("Budget"),
+        String::from("Category"),
+        String::from("Deductible"),
+        String::from("Tax"),
+        String::from("Entries"),
+    ]];
+
+    for category in report.categories.iter() {
+        rows.push([
+            category.budget_name.clone(),
+            category
+                .category_name
+                .clone()
+                .unwrap_or_else(|| String::from("Uncategorized")),
+            format_cents(category.deductible_cents),
+            format_cents(category.tax_cents),
+            category.entry_count.to_string(),
+        ]);
+    }
+
+    let entry_count = report.categories.iter().map(|c| c.entry_count).sum::<i64>();
+    rows.push([
+        String::from("Total"),
+        String::new(),
+        format_cents(report.deductible_cents),
+        format_cents(report.tax_cents),
+        entry_count.to_string(),
+    ]);
+
+    for row in rows.iter() {
+        if writer.write_record(row).is_err() {
+            return Err(ServerError::InternalError(Some(
+                "Failed to write tax report",
+            )));
+        }
+    }
+
+    let csv = match writer.into_inner() {
+        Ok(c) => c,
+        Err(_) => {
+            return Err(ServerError::InternalError(Some(
+                "Failed to write tax report",
+            )))
+        }
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .insert_header((
+            "content-disposition",
+            match tax_year.quarter {
+                Some(quarter) => format!(
+                    "attachment; filename=\"tax-report-{}-q{}.csv\"",
+                    tax_year.year, quarter
+                ),
+                None => format!("attachment; filename=\"tax-report-{}.csv\"", tax_year.year),
+            },
+        ))
+        .body(csv))
+}
+
+async fn load_tax_report(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: uuid::Uuid,
+    tax_year: &InputTaxYear,
+) -> Result<OutputTaxReport, ServerError> {
+    let default_start_month = match tax_year.start_month {
+        Some(_) => 1,
+        None => get_fiscal_year_start_month(db_thread_pool.clone(), user_id).await?,
+    };
+
+    let (start_date, end_date) = match tax_report_bounds(tax_year, default_start_month) {
+        Some(b) => b,
+        None => return Err(ServerError::InputRejected(Some("Invalid tax year"))),
+    };
+
+    let categories = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::tax_report::get_deductible_totals_for_user(
+            &db_connection,
+            user_id,
+            start_date,
+            end_date,
+        )
+    })
+    .await?
+    {
+        Ok(c) => c,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get tax report",
+            ))
+        }
+    };
+
+    Ok(OutputTaxReport {
+        start_date,
+        end_date,
+        deductible_cents: categories.iter().map(|c| c.deductible_cents).sum(),
+        tax_cents: categories.iter().map(|c| c.tax_cents).sum(),
+        categories,
+    })
+}
+
+async fn get_fiscal_year_start_month(
+    db_thread_pool: web::Data<DbThreadPool>,
+    user_id: uuid::Uuid,
+) -> Result<u32, ServerError> {
+    let user = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        db::user::get_user_by_id(&db_connection, user_id)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(diesel::result::Error::NotFound) => {
+            return Err(ServerError::AccessForbidden(Some("No user with ID")))
+        }
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get user data",
+            ))
+        }
+    };
+
+    Ok(user.fiscal_year_start_month as u32)
+}
+
+// `default_start_month` is used if the request doesn't give a start month
+fn tax_report_bounds(
+    tax_year: &InputTaxYear,
+    default_start_month: u32,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let calendar = FiscalCalendar::new(tax_year.start_month.unwrap_or(default_start_month))?;
+
+    match tax_year.quarter {
+        Some(quarter) => calendar.quarter_bounds(tax_year.year, quarter),
+        None => calendar.year_bounds(tax_year.year),
+    }
+}
+
+fn format_cents(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+}
+
+pub async fn get_api_usage(
+    db_thread_pool: web::Data<DbThreadPool>,
+    auth_user_claims: middleware::auth::AuthorizedUserClaims,
+) -> Result<HttpResponse, ServerError> {
+    let since =
+        chrono::Utc::now().naive_utc().date() - chrono::Duration::days(API_USAGE_HISTORY_DAYS);
+
+    let usage = match web::block(move || {
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+
+        db::api_key::get_api_usage_for_user(&db_connection, auth_user_claims.0.uid, since)
+    })
+    .await?
+    {
+        Ok(u) => u,
+        Err(e) => {
+            return Err(ServerError::from_database_error(
+                e,
+                "Failed to get API usage",
+            ))
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(OutputApiUsage {
+        daily_request_limit: env::CONF.security.api_key_daily_request_limit,
+        usage,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+    use chrono::NaiveDate;
+    use diesel::prelude::*;
+    use futures::future::BoxFuture;
+    use rand::prelude::*;
+    use std::sync::{Arc, Mutex};
+
+    use crate::env;
+    use crate::handlers::request_io::{
+        ActivityEventType, InputBudget, InputCategory, InputEntry, OutputActivityEvent,
+        RefreshToken, SigninTokenOtpPair, TokenPair,
+    };
+    use crate::models::pending_deletion::PendingDeletion;
+    use crate::models::user::User;
+    use crate::models::user_notification::NewUserNotification;
+    use crate::schema::user_device_tokens as device_token_fields;
+    use crate::schema::user_device_tokens::dsl::user_device_tokens;
+    use crate::schema::user_notifications::dsl::user_notifications;
+    use crate::schema::users as user_fields;
+    use crate::schema::users::dsl::users;
+    use crate::services;
+    use crate::utils::auth_token::TokenClaims;
+    use crate::utils::logging::{self, RequestScope};
+    use crate::utils::push::{self, PushError, PushMessage, PushProvider};
+    use crate::utils::trace_context::TraceContext;
+
+    #[actix_rt::test]
+    async fn test_create() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/create")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&new_user)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user = users
+            .filter(user_fields::email.eq(&new_user.email.to_lowercase()))
+            .first::<User>(&db_connection)
+            .unwrap();
+
+        assert_eq!(&new_user.email, &created_user.email);
+        assert_ne!(&new_user.password, &created_user.password_hash);
+        assert_eq!(&new_user.first_name, &created_user.first_name);
+        assert_eq!(&new_user.last_name, &created_user.last_name);
+        assert_eq!(&new_user.date_of_birth, &created_user.date_of_birth);
+        assert_eq!(&new_user.currency, &created_user.currency);
+    }
+
+    #[actix_rt::test]
+    async fn test_edit() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("1dIbCx^n@VF9f&0*c*39"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let edited_user = InputEditUser {
+            first_name: format!("Test-{}-edited", &user_number),
+            last_name: new_user.last_name.clone(),
+            date_of_birth: new_user.date_of_birth.clone(),
+            currency: String::from("DOP"),
+            fiscal_year_start_month: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/edit")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &access_token).as_str(),
+            ))
+            .set_json(&edited_user)
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let after_edit_get_req = test::TestRequest::get()
+            .uri("/api/user/get")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &access_token).as_str(),
+            ))
+            .to_request();
+
+        let after_edit_get_res = test::call_service(&app, after_edit_get_req).await;
+
+        let res_body = String::from_utf8(
+            actix_web::test::read_body(after_edit_get_res)
+                .await
+                .to_vec(),
+        )
+        .unwrap();
+        let user_after_edit = serde_json::from_str::<OutputUserPrivate>(res_body.as_str()).unwrap();
+
+        assert_eq!(&new_user.email, &user_after_edit.email);
+        assert_eq!(&new_user.last_name, &user_after_edit.last_name);
+        assert_eq!(&new_user.date_of_birth, &user_after_edit.date_of_birth);
+
+        assert_eq!(&edited_user.first_name, &user_after_edit.first_name);
+        assert_eq!(&edited_user.currency, &user_after_edit.currency);
+    }
+
+    #[actix_rt::test]
+    async fn test_create_fails_with_invalid_email() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+
+        let new_user = InputUser {
             email: format!("test_user{}test.com", &user_number),
             password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
             first_name: format!("Test-{}", &user_number),
@@ -436,17 +1261,426 @@ mod tests {
         };
 
         let req = test::TestRequest::post()
-            .uri("/api/user/create")
+            .uri("/api/user/create")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&new_user)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body_json::<serde_json::Value, _>(resp).await;
+        assert_eq!(body["error_code"], "invalid_fields");
+        assert_eq!(body["field_errors"].as_array().unwrap().len(), 1);
+        assert_eq!(body["field_errors"][0]["field"], "email");
+    }
+
+    #[actix_rt::test]
+    async fn test_create_fails_with_disposable_email() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+
+        let mut new_user = InputUser {
+            email: format!("test_user{}@mailinator.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/create")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&new_user)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("disposable"));
+
+        // Allowed by the testing config despite being on the disposable domain list
+        new_user.email = format!("test_user{}@trashmail.com", &user_number);
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/create")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&new_user)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_create_fails_with_invalid_password() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("Password1234"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/create")
+            .insert_header(("content-type", "application/json"))
+            .set_json(&new_user)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body_json::<serde_json::Value, _>(resp).await;
+        assert_eq!(body["error_code"], "invalid_fields");
+        assert_eq!(body["field_errors"].as_array().unwrap().len(), 1);
+        assert_eq!(body["field_errors"][0]["field"], "password");
+    }
+
+    #[actix_rt::test]
+    async fn test_error_envelope() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/user/get").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let body = test::read_body_json::<serde_json::Value, _>(resp).await;
+        assert_eq!(body["error_code"], "unauthorized");
+        assert_eq!(body["message"], "User unauthorized: No token provided");
+        assert!(body.get("field_errors").is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/create")
+            .insert_header(("content-type", "application/json"))
+            .set_payload("{\"email\": ")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        let body = test::read_body_json::<serde_json::Value, _>(resp).await;
+        assert_eq!(body["error_code"], "invalid_format");
+        assert_eq!(
+            body["message"],
+            "Invalid request format: Invalid request body"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_get() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("1dIbCx^n@VF9f&0*c*39"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &access_token).as_str(),
+            ))
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let res_body = String::from_utf8(actix_web::test::read_body(res).await.to_vec()).unwrap();
+        let user_from_res = serde_json::from_str::<OutputUserPrivate>(res_body.as_str()).unwrap();
+
+        assert_eq!(&new_user.email, &user_from_res.email);
+        assert_eq!(&new_user.first_name, &user_from_res.first_name);
+        assert_eq!(&new_user.last_name, &user_from_res.last_name);
+        assert_eq!(&new_user.date_of_birth, &user_from_res.date_of_birth);
+        assert_eq!(&new_user.currency, &user_from_res.currency);
+    }
+
+    #[actix_rt::test]
+    async fn test_change_password() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
+            .insert_header(("content-type", "application/json"))
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let password_pair = CurrentAndNewPasswordPair {
+            current_password: new_user.password.clone(),
+            new_password: String::from("s$B5Pl@KC7t92&a!jZ3Gx"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/change_password")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &access_token).as_str(),
+            ))
+            .set_payload(serde_json::ser::to_vec(&password_pair).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        let db_password_hash = db::user::get_user_by_id(&db_connection, user_id)
+            .unwrap()
+            .password_hash;
+
+        assert!(!password_hasher::verify_hash(
+            &new_user.password,
+            &db_password_hash
+        ));
+        assert!(password_hasher::verify_hash(
+            &password_pair.new_password,
+            &db_password_hash
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_change_password_current_password_wrong() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
             .insert_header(("content-type", "application/json"))
-            .set_json(&new_user)
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
             .to_request();
 
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        let res = test::call_service(&app, req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let password_pair = CurrentAndNewPasswordPair {
+            current_password: new_user.password.clone() + " ",
+            new_password: String::from("s$B5Pl@KC7t92&a!jZ3Gx"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/change_password")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &access_token).as_str(),
+            ))
+            .set_payload(serde_json::ser::to_vec(&password_pair).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        let db_password_hash = db::user::get_user_by_id(&db_connection, user_id)
+            .unwrap()
+            .password_hash;
+
+        assert!(password_hasher::verify_hash(
+            &new_user.password,
+            &db_password_hash
+        ));
+        assert!(!password_hasher::verify_hash(
+            &password_pair.new_password,
+            &db_password_hash
+        ));
     }
 
     #[actix_rt::test]
-    async fn test_create_fails_with_invalid_password() {
+    async fn test_change_password_new_password_invalid() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -457,10 +1691,9 @@ mod tests {
         .await;
 
         let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
-
         let new_user = InputUser {
             email: format!("test_user{}@test.com", &user_number),
-            password: String::from("Password1234"),
+            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
             first_name: format!("Test-{}", &user_number),
             last_name: format!("User-{}", &user_number),
             date_of_birth: NaiveDate::from_ymd(
@@ -471,18 +1704,257 @@ mod tests {
             currency: String::from("USD"),
         };
 
+        let create_user_res = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/api/user/create")
+                .insert_header(("content-type", "application/json"))
+                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .to_request(),
+        )
+        .await;
+
+        let signin_token = test::read_body_json::<SigninToken, _>(create_user_res).await;
+        let user_id = TokenClaims::from_token_without_validation(&signin_token.signin_token)
+            .unwrap()
+            .uid;
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let otp = otp::generate_otp(user_id, current_time).unwrap();
+
+        let token_and_otp = SigninTokenOtpPair {
+            signin_token: signin_token.signin_token,
+            otp: otp.to_string(),
+        };
+
         let req = test::TestRequest::post()
-            .uri("/api/user/create")
+            .uri("/api/auth/verify_otp_for_signin")
             .insert_header(("content-type", "application/json"))
-            .set_json(&new_user)
+            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
             .to_request();
 
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        let res = test::call_service(&app, req).await;
+        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
+        let access_token = token_pair.access_token.to_string();
+
+        let password_pair = CurrentAndNewPasswordPair {
+            current_password: new_user.password.clone(),
+            new_password: String::from("Password1234"),
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/api/user/change_password")
+            .insert_header(("content-type", "application/json"))
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &access_token).as_str(),
+            ))
+            .set_payload(serde_json::ser::to_vec(&password_pair).unwrap())
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+
+        let db_connection = db_thread_pool
+            .get()
+            .expect("Failed to access database thread pool");
+        let db_password_hash = db::user::get_user_by_id(&db_connection, user_id)
+            .unwrap()
+            .password_hash;
+
+        assert!(password_hasher::verify_hash(
+            &new_user.password,
+            &db_password_hash
+        ));
+        assert!(!password_hasher::verify_hash(
+            &password_pair.new_password,
+            &db_password_hash
+        ));
     }
 
     #[actix_rt::test]
-    async fn test_get() {
+    async fn test_merge_account() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let mut created_users = Vec::new();
+        let mut passwords = Vec::new();
+
+        for password in ["hR4#mK9!xQ2&vLp7zWc0", "Tb8$eN3^qJ6*sYd1uFa5"] {
+            let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+            let new_user = InputUser {
+                email: format!("test_user{}@test.com", &user_number),
+                password: String::from(password),
+                first_name: format!("Test-{}", &user_number),
+                last_name: format!("User-{}", &user_number),
+                date_of_birth: NaiveDate::from_ymd(
+                    rand::thread_rng().gen_range(1950..=2020),
+                    rand::thread_rng().gen_range(1..=12),
+                    rand::thread_rng().gen_range(1..=28),
+                ),
+                currency: String::from("USD"),
+            };
+
+            created_users
+                .push(db::user::create_user(&db_connection, &web::Json(new_user)).unwrap());
+            passwords.push(password);
+        }
+
+        let primary_user = &created_users[0];
+        let secondary_user = &created_users[1];
+
+        let access_token = auth_token::generate_access_token(auth_token::TokenParams {
+            user_id: &primary_user.id,
+        })
+        .unwrap()
+        .to_string();
+
+        let attempts = [
+            (
+                secondary_user.email.clone(),
+                passwords[0],
+                http::StatusCode::UNAUTHORIZED,
+            ),
+            (
+                primary_user.email.clone(),
+                passwords[0],
+                http::StatusCode::BAD_REQUEST,
+            ),
+            (
+                secondary_user.email.clone(),
+                passwords[1],
+                http::StatusCode::OK,
+            ),
+        ];
+
+        for (email, password, expected_status) in attempts {
+            let req = test::TestRequest::post()
+                .uri("/api/user/merge_account")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&CredentialPair {
+                    email,
+                    password: String::from(password),
+                })
+                .to_request();
+
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), expected_status);
+        }
+
+        assert!(db::user::get_user_by_id(&db_connection, secondary_user.id).is_err());
+        assert!(db::user::get_user_by_id(&db_connection, primary_user.id).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_account_and_cancel_deletion() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        let db_connection = db_thread_pool.get().unwrap();
+
+        const PASSWORD: &str = "pQ7&wE2!rT9#yU4^iO6k";
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from(PASSWORD),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(2000, 1, 1),
+            currency: String::from("USD"),
+        };
+
+        let user = db::user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        let access_token =
+            auth_token::generate_access_token(auth_token::TokenParams { user_id: &user.id })
+                .unwrap()
+                .to_string();
+
+        let attempts = [
+            ("Not the password", http::StatusCode::UNAUTHORIZED),
+            (PASSWORD, http::StatusCode::OK),
+            (PASSWORD, http::StatusCode::BAD_REQUEST),
+        ];
+
+        for (password, expected_status) in attempts {
+            let req = test::TestRequest::post()
+                .uri("/api/user/delete_account")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputPassword {
+                    password: String::from(password),
+                })
+                .to_request();
+
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), expected_status);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get_pending_deletion")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let pending_deletion: PendingDeletion = test::read_body_json(res).await;
+        assert_eq!(pending_deletion.user_id, user.id);
+        assert_eq!(
+            pending_deletion.delete_after - pending_deletion.created_timestamp,
+            chrono::Duration::days(
+                i64::try_from(
+                    env::reloadable_conf()
+                        .lifetimes
+                        .account_deletion_grace_period_days
+                )
+                .unwrap()
+            )
+        );
+
+        for expected_status in [http::StatusCode::OK, http::StatusCode::NOT_FOUND] {
+            let req = test::TestRequest::post()
+                .uri("/api/user/cancel_deletion")
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .to_request();
+
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), expected_status);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get_pending_deletion")
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .to_request();
+
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+
+        assert!(db::user::get_user_by_id(&db_connection, user.id).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_sessions() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -498,11 +1970,7 @@ mod tests {
             password: String::from("1dIbCx^n@VF9f&0*c*39"),
             first_name: format!("Test-{}", &user_number),
             last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
+            date_of_birth: NaiveDate::from_ymd(1990, 4, 12),
             currency: String::from("USD"),
         };
 
@@ -511,7 +1979,7 @@ mod tests {
             test::TestRequest::post()
                 .uri("/api/user/create")
                 .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .set_json(&new_user)
                 .to_request(),
         )
         .await;
@@ -525,46 +1993,113 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
-
         let token_and_otp = SigninTokenOtpPair {
             signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
+            otp: otp::generate_otp(user_id, current_time)
+                .unwrap()
+                .to_string(),
         };
 
         let req = test::TestRequest::post()
             .uri("/api/auth/verify_otp_for_signin")
-            .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .insert_header(("Device-Name", "Work Laptop"))
+            .insert_header(("User-Agent", "BudgetApp/1.4 (macOS)"))
+            .insert_header(("X-Forwarded-For", "203.0.113.7"))
+            .set_json(&token_and_otp)
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let laptop_tokens = test::read_body_json::<TokenPair, _>(res).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/auth/verify_otp_for_signin")
+            .insert_header(("Device-Name", "Phone"))
+            .set_json(&token_and_otp)
             .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let phone_tokens = test::read_body_json::<TokenPair, _>(res).await;
 
+        // Using the laptop's refresh token makes it the most recently used session
+        let req = test::TestRequest::post()
+            .uri("/api/auth/refresh_tokens")
+            .set_json(&RefreshToken {
+                token: laptop_tokens.refresh_token.clone(),
+            })
+            .to_request();
         let res = test::call_service(&app, req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
-        let access_token = token_pair.access_token.to_string();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let laptop_tokens = test::read_body_json::<TokenPair, _>(res).await;
 
         let req = test::TestRequest::get()
-            .uri("/api/user/get")
+            .uri("/api/user/sessions")
             .insert_header((
                 "authorization",
-                format!("bearer {}", &access_token).as_str(),
+                format!("bearer {}", &phone_tokens.access_token),
             ))
             .to_request();
-
         let res = test::call_service(&app, req).await;
         assert_eq!(res.status(), http::StatusCode::OK);
 
-        let res_body = String::from_utf8(actix_web::test::read_body(res).await.to_vec()).unwrap();
-        let user_from_res = serde_json::from_str::<OutputUserPrivate>(res_body.as_str()).unwrap();
+        let sessions = test::read_body_json::<Vec<OutputSession>, _>(res).await;
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].device_name.as_deref(), Some("Work Laptop"));
+        assert_eq!(
+            sessions[0].user_agent.as_deref(),
+            Some("BudgetApp/1.4 (macOS)")
+        );
+        assert_eq!(sessions[0].ip_address.as_deref(), Some("203.0.113.7"));
+        assert!(sessions[0].last_used_timestamp > sessions[0].created_timestamp);
+        assert_eq!(sessions[1].device_name.as_deref(), Some("Phone"));
+        assert!(sessions[1].user_agent.is_none());
+
+        let laptop_session_id = sessions[0].id;
+
+        let revoke = |session_id| {
+            test::TestRequest::post()
+                .uri("/api/user/sessions/revoke")
+                .insert_header((
+                    "authorization",
+                    format!("bearer {}", &phone_tokens.access_token),
+                ))
+                .set_json(&InputSessionId { session_id })
+                .to_request()
+        };
 
-        assert_eq!(&new_user.email, &user_from_res.email);
-        assert_eq!(&new_user.first_name, &user_from_res.first_name);
-        assert_eq!(&new_user.last_name, &user_from_res.last_name);
-        assert_eq!(&new_user.date_of_birth, &user_from_res.date_of_birth);
-        assert_eq!(&new_user.currency, &user_from_res.currency);
+        let res = test::call_service(&app, revoke(laptop_session_id)).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let res = test::call_service(&app, revoke(laptop_session_id)).await;
+        assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+
+        let res = test::call_service(&app, revoke(uuid::Uuid::new_v4())).await;
+        assert_eq!(res.status(), http::StatusCode::NOT_FOUND);
+
+        // The other device is signed out
+        let req = test::TestRequest::post()
+            .uri("/api/auth/refresh_tokens")
+            .set_json(&RefreshToken {
+                token: laptop_tokens.refresh_token,
+            })
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/sessions")
+            .insert_header((
+                "authorization",
+                format!("bearer {}", &phone_tokens.access_token),
+            ))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        let sessions = test::read_body_json::<Vec<OutputSession>, _>(res).await;
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].device_name.as_deref(), Some("Phone"));
     }
 
     #[actix_rt::test]
-    async fn test_change_password() {
+    async fn test_export_activity() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -577,14 +2112,10 @@ mod tests {
         let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
         let new_user = InputUser {
             email: format!("test_user{}@test.com", &user_number),
-            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            password: String::from("1dIbCx^n@VF9f&0*c*39"),
             first_name: format!("Test-{}", &user_number),
             last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
+            date_of_birth: NaiveDate::from_ymd(1990, 4, 12),
             currency: String::from("USD"),
         };
 
@@ -593,7 +2124,7 @@ mod tests {
             test::TestRequest::post()
                 .uri("/api/user/create")
                 .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .set_json(&new_user)
                 .to_request(),
         )
         .await;
@@ -607,61 +2138,96 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
-
         let token_and_otp = SigninTokenOtpPair {
             signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
+            otp: otp::generate_otp(user_id, current_time)
+                .unwrap()
+                .to_string(),
         };
 
         let req = test::TestRequest::post()
             .uri("/api/auth/verify_otp_for_signin")
-            .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .insert_header(("Device-Name", "Desktop"))
+            .set_json(&token_and_otp)
             .to_request();
-
         let res = test::call_service(&app, req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
-        let access_token = token_pair.access_token.to_string();
-
-        let password_pair = CurrentAndNewPasswordPair {
-            current_password: new_user.password.clone(),
-            new_password: String::from("s$B5Pl@KC7t92&a!jZ3Gx"),
-        };
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let access_token = test::read_body_json::<TokenPair, _>(res).await.access_token;
 
         let req = test::TestRequest::post()
-            .uri("/api/user/change_password")
+            .uri("/api/user/create_api_key")
             .insert_header(("content-type", "application/json"))
-            .insert_header((
-                "authorization",
-                format!("bearer {}", &access_token).as_str(),
-            ))
-            .set_payload(serde_json::ser::to_vec(&password_pair).unwrap())
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputApiKeyName {
+                name: String::from("Spreadsheet"),
+            })
             .to_request();
-
         let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::CREATED);
+
+        let export = |start_date: NaiveDate, end_date: NaiveDate| {
+            test::TestRequest::post()
+                .uri("/api/user/export/activity")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputDateRange {
+                    start_date,
+                    end_date,
+                })
+                .to_request()
+        };
+
+        let today = chrono::Utc::now().naive_utc().date();
+
+        // Responses hold their export permits until they are dropped
+        let res = test::call_service(&app, export(today, today - Duration::days(1))).await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+        drop(res);
+
+        let res = test::call_service(
+            &app,
+            export(today - Duration::days(MAX_ACTIVITY_EXPORT_DAYS), today),
+        )
+        .await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+        drop(res);
 
+        let res = test::call_service(
+            &app,
+            export(today - Duration::days(MAX_ACTIVITY_EXPORT_DAYS - 1), today),
+        )
+        .await;
         assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            "application/x-ndjson"
+        );
 
-        let db_connection = db_thread_pool
-            .get()
-            .expect("Failed to access database thread pool");
-        let db_password_hash = db::user::get_user_by_id(&db_connection, user_id)
+        let body = test::read_body(res).await;
+        let events = std::str::from_utf8(&body)
             .unwrap()
-            .password_hash;
-
-        assert!(!password_hasher::verify_hash(
-            &new_user.password,
-            &db_password_hash
-        ));
-        assert!(password_hasher::verify_hash(
-            &password_pair.new_password,
-            &db_password_hash
-        ));
+            .lines()
+            .map(|l| serde_json::from_str::<OutputActivityEvent>(l).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, ActivityEventType::SignedIn);
+        assert_eq!(events[0].detail.as_deref(), Some("Desktop"));
+        assert_eq!(events[1].event, ActivityEventType::ApiKeyCreated);
+        assert_eq!(events[1].detail.as_deref(), Some("Spreadsheet"));
+        assert!(events[0].timestamp <= events[1].timestamp);
+
+        let res = test::call_service(
+            &app,
+            export(today - Duration::days(30), today - Duration::days(1)),
+        )
+        .await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert!(test::read_body(res).await.is_empty());
     }
 
     #[actix_rt::test]
-    async fn test_change_password_current_password_wrong() {
+    async fn test_tax_report() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -674,14 +2240,10 @@ mod tests {
         let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
         let new_user = InputUser {
             email: format!("test_user{}@test.com", &user_number),
-            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            password: String::from("1dIbCx^n@VF9f&0*c*39"),
             first_name: format!("Test-{}", &user_number),
             last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
+            date_of_birth: NaiveDate::from_ymd(1990, 4, 12),
             currency: String::from("USD"),
         };
 
@@ -690,7 +2252,7 @@ mod tests {
             test::TestRequest::post()
                 .uri("/api/user/create")
                 .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .set_json(&new_user)
                 .to_request(),
         )
         .await;
@@ -704,61 +2266,278 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
-
         let token_and_otp = SigninTokenOtpPair {
             signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
+            otp: otp::generate_otp(user_id, current_time)
+                .unwrap()
+                .to_string(),
         };
 
         let req = test::TestRequest::post()
             .uri("/api/auth/verify_otp_for_signin")
-            .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .set_json(&token_and_otp)
             .to_request();
-
         let res = test::call_service(&app, req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
-        let access_token = token_pair.access_token.to_string();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let access_token = test::read_body_json::<TokenPair, _>(res).await.access_token;
 
-        let password_pair = CurrentAndNewPasswordPair {
-            current_password: new_user.password.clone() + " ",
-            new_password: String::from("s$B5Pl@KC7t92&a!jZ3Gx"),
+        let db_connection = db_thread_pool.get().unwrap();
+        let new_budget = InputBudget {
+            name: String::from("Freelance, 2021-2022"),
+            description: None,
+            categories: vec![
+                InputCategory {
+                    id: 0,
+                    name: String::from("Office"),
+                    limit_cents: 100000,
+                    color: String::from("#ff11ee"),
+                },
+                InputCategory {
+                    id: 1,
+                    name: String::from("Travel"),
+                    limit_cents: 100000,
+                    color: String::from("#11eeff"),
+                },
+            ],
+            start_date: NaiveDate::from_ymd(2021, 1, 1),
+            end_date: NaiveDate::from_ymd(2022, 12, 31),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+        let budget =
+            db::budget::create_budget(&db_connection, &web::Json(new_budget), user_id).unwrap();
+
+        let entries = [
+            (
+                NaiveDate::from_ymd(2021, 8, 1),
+                Some(0),
+                5000,
+                Some(400),
+                true,
+            ),
+            (NaiveDate::from_ymd(2022, 6, 30), Some(0), 1500, None, true),
+            (NaiveDate::from_ymd(2021, 10, 10), None, 700, None, true),
+            // Outside the fiscal year
+            (NaiveDate::from_ymd(2021, 6, 30), Some(1), 9000, None, true),
+            (NaiveDate::from_ymd(2022, 7, 1), Some(1), 9000, None, true),
+            // Not deductible
+            (NaiveDate::from_ymd(2021, 9, 1), Some(1), 3000, None, false),
+        ];
+
+        for (date, category, amount_cents, tax_cents, is_deductible) in entries {
+            let entry = InputEntry {
+                id: None,
+                budget_id: budget.id,
+                amount_cents,
+                date,
+                name: None,
+                category,
+                note: None,
+                tax_cents,
+                tip_cents: None,
+                is_deductible,
+            };
+
+            db::budget::create_entry(&db_connection, &web::Json(entry), user_id).unwrap();
+        }
+
+        let tax_report = |uri: &str, start_month: Option<u32>| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputTaxYear {
+                    year: 2021,
+                    start_month,
+                    quarter: None,
+                })
+                .to_request()
+        };
+
+        let res = test::call_service(&app, tax_report("/api/user/tax_report", Some(13))).await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+        drop(res);
+
+        let res = test::call_service(&app, tax_report("/api/user/tax_report", Some(7))).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let report = test::read_body_json::<OutputTaxReport, _>(res).await;
+
+        assert_eq!(report.start_date, NaiveDate::from_ymd(2021, 7, 1));
+        assert_eq!(report.end_date, NaiveDate::from_ymd(2022, 6, 30));
+        assert_eq!(report.deductible_cents, 7200);
+        assert_eq!(report.tax_cents, 400);
+        assert_eq!(report.categories.len(), 2);
+        assert_eq!(report.categories[0].category_id, Some(0));
+        assert_eq!(report.categories[0].deductible_cents, 6500);
+        assert_eq!(report.categories[0].entry_count, 2);
+        assert_eq!(report.categories[1].category_id, None);
+        assert_eq!(report.categories[1].deductible_cents, 700);
+
+        // A calendar year is the default
+        let res = test::call_service(&app, tax_report("/api/user/tax_report", None)).await;
+        let report = test::read_body_json::<OutputTaxReport, _>(res).await;
+        assert_eq!(report.end_date, NaiveDate::from_ymd(2021, 12, 31));
+        assert_eq!(report.deductible_cents, 14700);
+
+        let edit_user = |fiscal_year_start_month: Option<i16>| {
+            test::TestRequest::post()
+                .uri("/api/user/edit")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputEditUser {
+                    first_name: new_user.first_name.clone(),
+                    last_name: new_user.last_name.clone(),
+                    date_of_birth: new_user.date_of_birth,
+                    currency: new_user.currency.clone(),
+                    fiscal_year_start_month,
+                })
+                .to_request()
         };
 
+        let res = test::call_service(&app, edit_user(Some(13))).await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
+
+        let res = test::call_service(&app, edit_user(Some(7))).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        // Leaving the start month out of an edit keeps the preference
+        let res = test::call_service(&app, edit_user(None)).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        // Once the user prefers a fiscal year starting in July, that's the default
+        let res = test::call_service(&app, tax_report("/api/user/tax_report", None)).await;
+        let report = test::read_body_json::<OutputTaxReport, _>(res).await;
+        assert_eq!(report.start_date, NaiveDate::from_ymd(2021, 7, 1));
+        assert_eq!(report.end_date, NaiveDate::from_ymd(2022, 6, 30));
+        assert_eq!(report.deductible_cents, 7200);
+
         let req = test::TestRequest::post()
-            .uri("/api/user/change_password")
+            .uri("/api/user/tax_report")
             .insert_header(("content-type", "application/json"))
-            .insert_header((
-                "authorization",
-                format!("bearer {}", &access_token).as_str(),
-            ))
-            .set_payload(serde_json::ser::to_vec(&password_pair).unwrap())
+            .insert_header(("authorization", format!("bearer {access_token}")))
+            .set_json(&InputTaxYear {
+                year: 2021,
+                start_month: None,
+                quarter: Some(2),
+            })
             .to_request();
-
         let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let report = test::read_body_json::<OutputTaxReport, _>(res).await;
+        assert_eq!(report.start_date, NaiveDate::from_ymd(2021, 10, 1));
+        assert_eq!(report.end_date, NaiveDate::from_ymd(2021, 12, 31));
+        assert_eq!(report.deductible_cents, 700);
 
-        assert_eq!(res.status(), http::StatusCode::UNAUTHORIZED);
+        let res =
+            test::call_service(&app, tax_report("/api/user/export/tax_report", Some(7))).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "text/csv");
+
+        let body = test::read_body(res).await;
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            "Budget,Category,Deductible,Tax,Entries\n\
+             \"Freelance, 2021-2022\",Office,65.00,4.00,2\n\
+             \"Freelance, 2021-2022\",Uncategorized,7.00,0.00,1\n\
+             Total,,72.00,4.00,3\n"
+        );
+    }
 
-        let db_connection = db_thread_pool
-            .get()
-            .expect("Failed to access database thread pool");
-        let db_password_hash = db::user::get_user_by_id(&db_connection, user_id)
-            .unwrap()
-            .password_hash;
+    #[actix_rt::test]
+    async fn test_tax_report_bounds() {
+        let bounds = |year: i32, start_month: Option<u32>| {
+            tax_report_bounds(
+                &InputTaxYear {
+                    year,
+                    start_month,
+                    quarter: None,
+                },
+                1,
+            )
+        };
 
-        assert!(password_hasher::verify_hash(
-            &new_user.password,
-            &db_password_hash
-        ));
-        assert!(!password_hasher::verify_hash(
-            &password_pair.new_password,
-            &db_password_hash
-        ));
+        assert_eq!(
+            bounds(2024, None),
+            Some((
+                NaiveDate::from_ymd(2024, 1, 1),
+                NaiveDate::from_ymd(2024, 12, 31)
+            ))
+        );
+        assert_eq!(
+            bounds(2023, Some(3)),
+            Some((
+                NaiveDate::from_ymd(2023, 3, 1),
+                NaiveDate::from_ymd(2024, 2, 29)
+            ))
+        );
+        assert_eq!(bounds(2023, Some(0)), None);
+        assert_eq!(bounds(2023, Some(13)), None);
+        assert_eq!(bounds(i32::MAX, None), None);
+
+        // The user's preferred start month only applies when the request doesn't give one
+        let quarter_bounds = |start_month: Option<u32>, quarter: u32| {
+            tax_report_bounds(
+                &InputTaxYear {
+                    year: 2023,
+                    start_month,
+                    quarter: Some(quarter),
+                },
+                10,
+            )
+        };
+        assert_eq!(
+            quarter_bounds(None, 2),
+            Some((
+                NaiveDate::from_ymd(2024, 1, 1),
+                NaiveDate::from_ymd(2024, 3, 31)
+            ))
+        );
+        assert_eq!(
+            quarter_bounds(Some(1), 2),
+            Some((
+                NaiveDate::from_ymd(2023, 4, 1),
+                NaiveDate::from_ymd(2023, 6, 30)
+            ))
+        );
+        assert_eq!(quarter_bounds(None, 5), None);
+
+        assert_eq!(format_cents(123456), "1234.56");
+        assert_eq!(format_cents(-5), "-0.05");
+        assert_eq!(format_cents(0), "0.00");
+    }
+
+    struct RecordingProvider {
+        platform: DevicePlatform,
+        dead_token: String,
+        sent: Arc<Mutex<Vec<(String, PushMessage)>>>,
+    }
+
+    impl PushProvider for RecordingProvider {
+        fn platform(&self) -> DevicePlatform {
+            self.platform
+        }
+
+        fn send(
+            &self,
+            device_token: &str,
+            message: &PushMessage,
+        ) -> BoxFuture<'static, Result<(), PushError>> {
+            let result = if device_token == self.dead_token {
+                Err(PushError::InvalidToken)
+            } else {
+                self.sent
+                    .lock()
+                    .unwrap()
+                    .push((String::from(device_token), message.clone()));
+                Ok(())
+            };
+
+            Box::pin(async move { result })
+        }
     }
 
     #[actix_rt::test]
-    async fn test_change_password_new_password_invalid() {
+    async fn test_push_delivery() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
 
         let app = test::init_service(
@@ -771,14 +2550,10 @@ mod tests {
         let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
         let new_user = InputUser {
             email: format!("test_user{}@test.com", &user_number),
-            password: String::from("tNmUV%9$khHK2TqOLw*%W"),
+            password: String::from("p7#Lw2@qZs9!mR4v^Xe1"),
             first_name: format!("Test-{}", &user_number),
             last_name: format!("User-{}", &user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
+            date_of_birth: NaiveDate::from_ymd(1988, 9, 3),
             currency: String::from("USD"),
         };
 
@@ -787,7 +2562,7 @@ mod tests {
             test::TestRequest::post()
                 .uri("/api/user/create")
                 .insert_header(("content-type", "application/json"))
-                .set_payload(serde_json::ser::to_vec(&new_user).unwrap())
+                .set_json(&new_user)
                 .to_request(),
         )
         .await;
@@ -801,56 +2576,169 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        let otp = otp::generate_otp(user_id, current_time).unwrap();
-
         let token_and_otp = SigninTokenOtpPair {
             signin_token: signin_token.signin_token,
-            otp: otp.to_string(),
+            otp: otp::generate_otp(user_id, current_time)
+                .unwrap()
+                .to_string(),
         };
 
         let req = test::TestRequest::post()
             .uri("/api/auth/verify_otp_for_signin")
-            .insert_header(("content-type", "application/json"))
-            .set_payload(serde_json::ser::to_vec(&token_and_otp).unwrap())
+            .set_json(&token_and_otp)
             .to_request();
-
         let res = test::call_service(&app, req).await;
-        let token_pair = actix_web::test::read_body_json::<TokenPair, _>(res).await;
-        let access_token = token_pair.access_token.to_string();
+        assert_eq!(res.status(), http::StatusCode::OK);
+        let access_token = test::read_body_json::<TokenPair, _>(res).await.access_token;
 
-        let password_pair = CurrentAndNewPasswordPair {
-            current_password: new_user.password.clone(),
-            new_password: String::from("Password1234"),
+        let register_device = |platform: i16, token: String| {
+            test::TestRequest::post()
+                .uri("/api/user/register_device")
+                .insert_header(("content-type", "application/json"))
+                .insert_header(("authorization", format!("bearer {access_token}")))
+                .set_json(&InputDeviceToken { platform, token })
+                .to_request()
         };
 
-        let req = test::TestRequest::post()
-            .uri("/api/user/change_password")
-            .insert_header(("content-type", "application/json"))
-            .insert_header((
-                "authorization",
-                format!("bearer {}", &access_token).as_str(),
-            ))
-            .set_payload(serde_json::ser::to_vec(&password_pair).unwrap())
-            .to_request();
+        let phone_token = format!("phone-{}", user_number);
+        let tablet_token = format!("tablet-{}", user_number);
+        let uninstalled_token = format!("uninstalled-{}", user_number);
 
-        let res = test::call_service(&app, req).await;
+        let res = test::call_service(&app, register_device(7, phone_token.clone())).await;
+        assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
 
+        let res = test::call_service(&app, register_device(0, String::from("  "))).await;
         assert_eq!(res.status(), http::StatusCode::BAD_REQUEST);
 
-        let db_connection = db_thread_pool
-            .get()
-            .expect("Failed to access database thread pool");
-        let db_password_hash = db::user::get_user_by_id(&db_connection, user_id)
-            .unwrap()
-            .password_hash;
+        for (platform, token) in [
+            (0, &phone_token),
+            (1, &tablet_token),
+            (1, &uninstalled_token),
+        ] {
+            let res = test::call_service(&app, register_device(platform, token.clone())).await;
+            assert_eq!(res.status(), http::StatusCode::OK);
+        }
 
-        assert!(password_hasher::verify_hash(
-            &new_user.password,
-            &db_password_hash
-        ));
-        assert!(!password_hasher::verify_hash(
-            &password_pair.new_password,
-            &db_password_hash
-        ));
+        // Registering again is harmless
+        let res = test::call_service(&app, register_device(0, phone_token.clone())).await;
+        assert_eq!(res.status(), http::StatusCode::OK);
+
+        let db_connection = db_thread_pool.get().unwrap();
+        let notification_time = chrono::Utc::now().naive_utc();
+        let notification_id = uuid::Uuid::new_v4();
+        let untraced_notification_id = uuid::Uuid::new_v4();
+
+        let new_notification = |id, alt_message| NewUserNotification {
+            id,
+            user_id,
+            is_unread: true,
+            is_pristine: true,
+            is_deleted: false,
+            notification_type: 4,
+            alt_title: "Reminder",
+            alt_message,
+            associated_data: None,
+            modified_timestamp: notification_time,
+            created_timestamp: notification_time,
+        };
+
+        // Created while a request is handled, so delivering it joins the request's trace
+        let request_trace_context =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        let request_scope = RequestScope {
+            request_id: uuid::Uuid::new_v4(),
+            trace_context: request_trace_context,
+        };
+
+        logging::with_request_scope(request_scope, async {
+            db_connection
+                .transaction::<_, diesel::result::Error, _>(|| {
+                    db::push::trace_queued_pushes(&db_connection)?;
+                    diesel::insert_into(user_notifications)
+                        .values(&new_notification(notification_id, "Pay the water bill"))
+                        .execute(&db_connection)
+                })
+                .unwrap();
+        })
+        .await;
+
+        // The trace only applies to the transaction it was set in
+        diesel::insert_into(user_notifications)
+            .values(&new_notification(
+                untraced_notification_id,
+                "Pay the electric bill",
+            ))
+            .execute(&db_connection)
+            .unwrap();
+
+        let ios_sent = Arc::new(Mutex::new(Vec::new()));
+        let android_sent = Arc::new(Mutex::new(Vec::new()));
+        let providers: Vec<Box<dyn PushProvider>> = vec![
+            Box::new(RecordingProvider {
+                platform: DevicePlatform::Ios,
+                dead_token: uninstalled_token.clone(),
+                sent: ios_sent.clone(),
+            }),
+            Box::new(RecordingProvider {
+                platform: DevicePlatform::Android,
+                dead_token: uninstalled_token.clone(),
+                sent: android_sent.clone(),
+            }),
+        ];
+
+        push::deliver_pending_pushes(&db_connection, &providers)
+            .await
+            .unwrap();
+
+        let pushes_for_notification = |sent: &Arc<Mutex<Vec<(String, PushMessage)>>>| {
+            sent.lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, message)| message.notification_id == notification_id)
+                .map(|(token, message)| (token.clone(), message.body.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            pushes_for_notification(&ios_sent),
+            vec![(phone_token.clone(), String::from("Pay the water bill"))]
+        );
+        assert_eq!(
+            pushes_for_notification(&android_sent),
+            vec![(tablet_token.clone(), String::from("Pay the water bill"))]
+        );
+
+        let push_trace_context = |notification_id| {
+            ios_sent
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(_, message)| message.notification_id == notification_id)
+                .map(|(_, message)| message.trace_context)
+                .unwrap()
+        };
+
+        let traced = push_trace_context(notification_id);
+        assert_eq!(traced.trace_id, request_trace_context.trace_id);
+        assert_ne!(traced.span_id, request_trace_context.span_id);
+        assert!(traced.is_sampled());
+
+        let untraced = push_trace_context(untraced_notification_id);
+        assert_ne!(untraced.trace_id, request_trace_context.trace_id);
+
+        let remaining_tokens = user_device_tokens
+            .filter(device_token_fields::user_id.eq(user_id))
+            .select(device_token_fields::token)
+            .order(device_token_fields::token.asc())
+            .load::<String>(&db_connection)
+            .unwrap();
+        assert_eq!(remaining_tokens, vec![phone_token, tablet_token]);
+
+        // A notification is only pushed once
+        push::deliver_pending_pushes(&db_connection, &providers)
+            .await
+            .unwrap();
+        assert_eq!(pushes_for_notification(&ios_sent).len(), 1);
+        assert_eq!(pushes_for_notification(&android_sent).len(), 1);
     }
 }
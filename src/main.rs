@@ -5,15 +5,17 @@ extern crate diesel_migrations;
 #[macro_use]
 extern crate lazy_static;
 
-use actix_web::middleware::Logger;
+use actix_web::middleware::Condition;
 use actix_web::web::Data;
 use actix_web::{App, HttpServer};
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
-use env_logger::Env;
 use std::net::{IpAddr, Ipv4Addr};
 use std::time::Duration;
 
+use definitions::{DbConnection, DbThreadPool};
+use utils::currency::ExchangeRateProvider;
+
 mod cron;
 mod definitions;
 mod env;
@@ -26,21 +28,51 @@ mod utils;
 
 diesel_migrations::embed_migrations!();
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Serve,
+    Migrate,
+    Check,
+    Seed,
+    Worker,
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let mut ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
     let mut port = 9000u16;
     let mut run_migrations = false;
     let mut schedule_cron_jobs = false;
+    let mut selected_jobs = None;
 
-    let mut args = std::env::args();
+    let mut args = std::env::args().peekable();
 
     // Eat the first argument, which is the relative path to the executable
     args.next();
 
+    // Running without a command (or with only options) serves the API, as it did before there were commands
+    let command = match args.peek().map(|a| a.to_lowercase()) {
+        Some(a) if !a.starts_with("--") => {
+            args.next();
+
+            match a.as_str() {
+                "serve" => Command::Serve,
+                "migrate" => Command::Migrate,
+                "check" => Command::Check,
+                "seed" => Command::Seed,
+                "worker" => Command::Worker,
+                c => {
+                    eprintln!("Invalid command: {}", c);
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => Command::Serve,
+    };
+
     while let Some(arg) = args.next() {
         match arg.to_lowercase().as_str() {
-            "--port" => {
+            "--port" if command == Command::Serve => {
                 let port_str = {
                     let next_arg = args.next();
 
@@ -67,7 +99,7 @@ async fn main() -> std::io::Result<()> {
 
                 continue;
             }
-            "--ip" => {
+            "--ip" if command == Command::Serve => {
                 ip = {
                     let next_arg = args.next();
 
@@ -88,16 +120,41 @@ async fn main() -> std::io::Result<()> {
 
                 continue;
             }
-            "--run-migrations" => {
+            "--run-migrations" if matches!(command, Command::Serve | Command::Worker) => {
                 run_migrations = true;
 
                 continue;
             }
-            "--schedule-cron-jobs" => {
+            "--schedule-cron-jobs" if command == Command::Serve => {
                 schedule_cron_jobs = true;
 
                 continue;
             }
+            "--jobs" if matches!(command, Command::Serve | Command::Worker) => {
+                let jobs = match args.next() {
+                    Some(s) => s
+                        .split(',')
+                        .map(|j| j.trim().to_lowercase())
+                        .collect::<Vec<_>>(),
+                    None => {
+                        eprintln!("--jobs option specified but no jobs were given");
+                        std::process::exit(1);
+                    }
+                };
+
+                if let Some(j) = jobs.iter().find(|j| !CRON_JOB_NAMES.contains(&j.as_str())) {
+                    eprintln!(
+                        "Unknown job: {}. Valid jobs are: {}",
+                        j,
+                        CRON_JOB_NAMES.join(", ")
+                    );
+                    std::process::exit(1);
+                }
+
+                selected_jobs = Some(jobs);
+
+                continue;
+            }
             a => {
                 eprintln!("Invalid argument: {}", &a);
                 std::process::exit(1);
@@ -105,11 +162,28 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
+    if command == Command::Serve && selected_jobs.is_some() && !schedule_cron_jobs {
+        eprintln!("--jobs option specified without --schedule-cron-jobs");
+        std::process::exit(1);
+    }
+
     let base_addr = format!("{}:{}", &ip, &port);
 
     env::initialize();
 
-    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    utils::logging::initialize();
+
+    // Held until the server shuts down so queued error reports get flushed
+    let _error_reporting_guard = utils::error_reporting::initialize();
+
+    if command == Command::Serve && middleware::fault_injection::is_enabled() {
+        log::warn!("Fault injection is enabled. Requests will randomly be delayed or fail");
+    } else if command == Command::Serve
+        && env::CONF.fault_injection.enabled
+        && !cfg!(feature = "fault-injection")
+    {
+        log::warn!("Fault injection is configured but isn't available in this build. Ignoring it");
+    }
 
     log::info!("Connecting to database...");
 
@@ -125,122 +199,603 @@ async fn main() -> std::io::Result<()> {
 
     log::info!("Successfully connected to database");
 
+    let db_connection = db_thread_pool
+        .get()
+        .expect("Failed to get thread for connecting to db");
+
+    match command {
+        Command::Migrate => {
+            if !apply_migrations(&db_connection) {
+                std::process::exit(1);
+            }
+
+            return Ok(());
+        }
+        Command::Check => {
+            // Building the storage backend and push providers validates their config
+            let _ = *utils::storage::BLOB_STORE;
+
+            if let Err(e) = utils::push::configured_providers() {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            if let Err(e) = diesel::sql_query("SELECT 1").execute(&db_connection) {
+                eprintln!("Database check failed: {}", e);
+                std::process::exit(1);
+            }
+
+            println!("Configuration and database are OK");
+            return Ok(());
+        }
+        Command::Seed => {
+            if let Err(e) = refresh_exchange_rates(&db_connection) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+
+            log::info!("Exchange rates cached");
+            return Ok(());
+        }
+        Command::Serve | Command::Worker => (),
+    }
+
     if run_migrations {
-        log::info!("Running migrations...");
+        apply_migrations(&db_connection);
+    }
+
+    drop(db_connection);
+
+    #[cfg(unix)]
+    actix_web::rt::spawn(reload_conf_on_hangup());
+
+    if command == Command::Worker {
+        let _runners = start_cron_jobs(&db_thread_pool, selected_jobs.as_deref());
+
+        log::info!("Cron jobs scheduled. Waiting for a shutdown signal...");
+        actix_web::rt::signal::ctrl_c().await?;
+        log::info!("Shutting down cron job runners...");
+
+        return Ok(());
+    }
+
+    // Declaring a vec of job runners here to give it the same lifetime as the HTTP server
+    let runners = if schedule_cron_jobs {
+        start_cron_jobs(&db_thread_pool, selected_jobs.as_deref())
+    } else {
+        Vec::new()
+    };
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(Data::new(db_thread_pool.clone()))
+            .configure(services::api::configure)
+            .configure(services::health::configure)
+            .configure(services::live_updates::configure)
+            .configure(services::web::configure)
+            .wrap(middleware::error_reporting::ErrorReporting)
+            // Wrapped outside of error reporting so injected faults aren't reported
+            .wrap(Condition::new(
+                middleware::fault_injection::is_enabled(),
+                middleware::fault_injection::FaultInjection::from_conf(),
+            ))
+            // Outermost so the correlation id covers everything else, including injected faults
+            .wrap(middleware::request_logging::RequestLogging)
+    })
+    .workers(env::CONF.workers.actix_workers)
+    .bind(base_addr)?
+    .run()
+    .await;
+
+    // Log something so th runners vec doesn't get optimized away
+    for _ in runners {
+        log::info!("Shutting down cron job runner...");
+    }
+
+    return server;
+}
+
+// Reads the reloadable sections of the configuration again each time the process gets a SIGHUP.
+// The rest of the configuration only changes on a restart.
+#[cfg(unix)]
+async fn reload_conf_on_hangup() {
+    use actix_web::rt::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Failed to listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+
+    while hangups.recv().await.is_some() {
+        match env::reload_conf() {
+            Ok(()) => log::info!("Configuration reloaded"),
+            Err(e) => log::error!("Failed to reload configuration: {}", e),
+        }
+    }
+}
+
+fn apply_migrations(db_connection: &PgConnection) -> bool {
+    log::info!("Running migrations...");
 
-        let db_connection = &db_thread_pool
+    match embedded_migrations::run_with_output(db_connection, &mut std::io::stdout()) {
+        Ok(_) => {
+            log::info!("Migrations run successfully");
+            true
+        }
+        Err(e) => {
+            log::error!("Error running migrations: {}", e.to_string());
+            false
+        }
+    }
+}
+
+// The names that can be given to --jobs
+const CRON_JOB_NAMES: &[&str] = &[
+    "clear-otp-attempts",
+    "clear-password-attempts",
+    "compute-cohort-stats",
+    "compute-ops-stats",
+    "deliver-pushes",
+    "deliver-reminders",
+    "evaluate-challenges",
+    "materialize-recurring-entries",
+    "purge-blacklisted-tokens",
+    "purge-daily-actions",
+    "purge-deleted-accounts",
+    "purge-sessions",
+    "purge-trash",
+    "refresh-exchange-rates",
+];
+
+// Runners keep scheduling their jobs for as long as the returned vec is held. When only some jobs
+// are selected, the rest are left to other processes.
+fn start_cron_jobs(
+    db_thread_pool: &DbThreadPool,
+    selected_jobs: Option<&[String]>,
+) -> Vec<cron::Runner> {
+    let is_selected = |name: &str| selected_jobs.is_none_or(|jobs| jobs.iter().any(|j| j == name));
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let clear_otp_verification_count_job = move || {
+        let db_connection = db_thread_pool_ref
             .get()
             .expect("Failed to get thread for connecting to db");
-        match embedded_migrations::run_with_output(db_connection, &mut std::io::stdout()) {
-            Ok(_) => log::info!("Migrations run successfully"),
-            Err(e) => log::error!("Error running migrations: {}", e.to_string()),
+
+        if utils::db::auth::clear_otp_verification_count(&db_connection).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to clear recent OTP verfications",
+            )));
         }
-    }
 
-    // Declaring a vec of job runners here to give it the same lifetime as the HTTP server
-    let mut runners = Vec::new();
+        Ok(())
+    };
 
-    if schedule_cron_jobs {
-        let db_thread_pool_ref = db_thread_pool.clone();
+    let db_thread_pool_ref = db_thread_pool.clone();
 
-        let clear_otp_verification_count_job = move || {
-            let db_connection = db_thread_pool_ref
-                .get()
-                .expect("Failed to get thread for connecting to db");
+    let clear_password_attempt_count_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
 
-            if utils::db::auth::clear_otp_verification_count(&db_connection).is_err() {
-                return Err(cron::CronJobError::JobFailure(Some(
-                    "Failed to clear recent OTP verfications",
-                )));
-            }
+        if utils::db::auth::clear_password_attempt_count(&db_connection).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to clear recent password attempts",
+            )));
+        }
 
-            Ok(())
-        };
+        Ok(())
+    };
 
-        let db_thread_pool_ref = db_thread_pool.clone();
+    let db_thread_pool_ref = db_thread_pool.clone();
 
-        let clear_password_attempt_count_job = move || {
-            let db_connection = db_thread_pool_ref
-                .get()
-                .expect("Failed to get thread for connecting to db");
+    let purge_expired_blacklisted_tokens_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
 
-            if utils::db::auth::clear_password_attempt_count(&db_connection).is_err() {
-                return Err(cron::CronJobError::JobFailure(Some(
-                    "Failed to clear recent password attempts",
-                )));
-            }
+        if utils::db::auth::purge_expired_blacklisted_tokens(&db_connection).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to purge expired blacklisted tokens",
+            )));
+        }
 
-            Ok(())
-        };
+        Ok(())
+    };
 
-        let db_thread_pool_ref = db_thread_pool.clone();
+    let db_thread_pool_ref = db_thread_pool.clone();
 
-        let clear_expired_blacklisted_tokens_job = move || {
-            let db_connection = db_thread_pool_ref
-                .get()
-                .expect("Failed to get thread for connecting to db");
+    let purge_expired_sessions_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
 
-            if utils::db::auth::clear_all_expired_refresh_tokens(&db_connection).is_err() {
-                return Err(cron::CronJobError::JobFailure(Some(
-                    "Failed to clear expired refresh tokens",
-                )));
+        if utils::db::session::purge_expired_sessions(&db_connection).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to purge expired sessions",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let evaluate_ended_challenges_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+        let today = chrono::Utc::now().naive_utc().date();
+
+        if utils::db::engagement::evaluate_ended_challenges(&db_connection, today).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to evaluate ended spending challenges",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let compute_cohort_stats_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+        let (period_start, period_end) =
+            utils::benchmarking::previous_month_range(chrono::Utc::now().naive_utc().date());
+
+        if utils::db::benchmarking::compute_cohort_stats(
+            &db_connection,
+            period_start,
+            period_end,
+            env::CONF.privacy.benchmarking_min_cohort_size,
+        )
+        .is_err()
+        {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to compute cohort benchmarking stats",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let compute_ops_stats_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+        let yesterday = chrono::Utc::now().naive_utc().date() - chrono::Duration::days(1);
+
+        if utils::db::ops_stats::compute_ops_stats(&db_connection, yesterday).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to compute ops stats",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let materialize_recurring_entries_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+        let today = chrono::Utc::now().naive_utc().date();
+
+        if utils::db::recurring_entry::materialize_due_recurring_entries(&db_connection, today)
+            .is_err()
+        {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to materialize recurring entries",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let deliver_due_reminders_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+        let today = chrono::Utc::now().naive_utc().date();
+
+        if utils::db::reminder::deliver_due_reminders(&db_connection, today).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to deliver due reminders",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let purge_deleted_accounts_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+
+        if utils::db::user::purge_expired_deletions(&db_connection, chrono::Utc::now().naive_utc())
+            .is_err()
+        {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to purge accounts scheduled for deletion",
+            )));
+        }
+
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    // Deleting attachment files is async, so this job gets its own runtime like push delivery
+    let purge_trash_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create runtime for purging the trash");
+
+    let purge_trash_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+        let deleted_before = chrono::Utc::now().naive_utc()
+            - chrono::Duration::days(env::reloadable_conf().lifetimes.trash_retention_days as i64);
+
+        let attachment_storage_keys =
+            match utils::db::trash::purge_trash(&db_connection, deleted_before) {
+                Ok(k) => k,
+                Err(_) => {
+                    return Err(cron::CronJobError::JobFailure(Some(
+                        "Failed to purge the trash",
+                    )))
+                }
+            };
+
+        // The rows are already gone, so a file that can't be deleted is only logged
+        for storage_key in attachment_storage_keys {
+            if let Err(e) =
+                purge_trash_runtime.block_on(utils::storage::BLOB_STORE.delete(&storage_key))
+            {
+                log::error!("Failed to delete attachment {}: {}", storage_key, e);
             }
+        }
 
-            Ok(())
-        };
+        Ok(())
+    };
+
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let purge_old_daily_actions_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
 
-        const SECONDS_IN_DAY: u64 = 86_400;
-        let long_lifetime_runner =
-            cron::Runner::with_granularity(Duration::from_secs(SECONDS_IN_DAY));
+        // Only today's counts are checked against the daily limits
+        let today = chrono::Utc::now().naive_utc().date();
 
-        let otp_attempts_reset_runner = cron::Runner::with_granularity(Duration::from_secs(
-            TryInto::<u64>::try_into(env::CONF.security.otp_attempts_reset_mins)
-                .expect("Invalid otp_attempts_reset_mins config")
-                * 60,
-        ));
+        if utils::db::daily_action::purge_daily_actions_before(&db_connection, today).is_err() {
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to purge old daily action counts",
+            )));
+        }
 
-        let password_attempts_reset_runner = cron::Runner::with_granularity(Duration::from_secs(
-            TryInto::<u64>::try_into(env::CONF.security.password_attempts_reset_mins)
-                .expect("Invalid password_attempts_reset_mins config")
-                * 60,
-        ));
+        Ok(())
+    };
 
+    let db_thread_pool_ref = db_thread_pool.clone();
+
+    let refresh_exchange_rates_job = move || {
+        let db_connection = db_thread_pool_ref
+            .get()
+            .expect("Failed to get thread for connecting to db");
+
+        refresh_exchange_rates(&db_connection)
+    };
+
+    // Runners wait a full interval before their first run, so the rates are cached up front
+    if is_selected("refresh-exchange-rates") {
+        if let Err(e) = refresh_exchange_rates_job() {
+            log::error!("{}", e);
+        }
+    }
+
+    const SECONDS_IN_DAY: u64 = 86_400;
+    let long_lifetime_runner = cron::Runner::with_granularity(Duration::from_secs(SECONDS_IN_DAY));
+
+    let otp_attempts_reset_runner = cron::Runner::with_granularity(Duration::from_secs(
+        TryInto::<u64>::try_into(env::CONF.security.otp_attempts_reset_mins)
+            .expect("Invalid otp_attempts_reset_mins config")
+            * 60,
+    ));
+
+    let password_attempts_reset_runner = cron::Runner::with_granularity(Duration::from_secs(
+        TryInto::<u64>::try_into(env::CONF.security.password_attempts_reset_mins)
+            .expect("Invalid password_attempts_reset_mins config")
+            * 60,
+    ));
+
+    let blacklisted_token_purge_runner = cron::Runner::with_granularity(Duration::from_secs(
+        TryInto::<u64>::try_into(env::CONF.security.blacklisted_token_purge_interval_mins)
+            .expect("Invalid blacklisted_token_purge_interval_mins config")
+            * 60,
+    ));
+
+    let exchange_rate_refresh_runner = cron::Runner::with_granularity(Duration::from_secs(
+        TryInto::<u64>::try_into(env::CONF.currency.rate_refresh_interval_mins)
+            .expect("Invalid rate_refresh_interval_mins config")
+            * 60,
+    ));
+
+    let push_delivery_runner =
+        cron::Runner::with_granularity(Duration::from_secs(env::CONF.push.delivery_interval_secs));
+
+    if is_selected("evaluate-challenges") {
+        long_lifetime_runner.add_job(
+            evaluate_ended_challenges_job,
+            String::from("Evaluate ended spending challenges"),
+        );
+    }
+
+    if is_selected("compute-cohort-stats") {
         long_lifetime_runner.add_job(
-            clear_expired_blacklisted_tokens_job,
-            String::from("Clear expired blacklisted refresh tokens"),
+            compute_cohort_stats_job,
+            String::from("Compute cohort benchmarking stats"),
         );
+    }
+
+    if is_selected("compute-ops-stats") {
+        long_lifetime_runner.add_job(compute_ops_stats_job, String::from("Compute ops stats"));
+    }
 
+    if is_selected("materialize-recurring-entries") {
+        long_lifetime_runner.add_job(
+            materialize_recurring_entries_job,
+            String::from("Materialize recurring entries"),
+        );
+    }
+
+    if is_selected("deliver-reminders") {
+        long_lifetime_runner.add_job(
+            deliver_due_reminders_job,
+            String::from("Deliver due reminders"),
+        );
+    }
+
+    if is_selected("purge-deleted-accounts") {
+        long_lifetime_runner.add_job(
+            purge_deleted_accounts_job,
+            String::from("Purge accounts scheduled for deletion"),
+        );
+    }
+
+    if is_selected("purge-trash") {
+        long_lifetime_runner.add_job(purge_trash_job, String::from("Purge the trash"));
+    }
+
+    if is_selected("purge-daily-actions") {
+        long_lifetime_runner.add_job(
+            purge_old_daily_actions_job,
+            String::from("Purge old daily action counts"),
+        );
+    }
+
+    if is_selected("clear-otp-attempts") {
         otp_attempts_reset_runner.add_job(
             clear_otp_verification_count_job,
             String::from("Clear OTP Verificaiton"),
         );
+    }
 
+    if is_selected("clear-password-attempts") {
         password_attempts_reset_runner.add_job(
             clear_password_attempt_count_job,
             String::from("Clear Password Attemps"),
         );
+    }
 
-        runners.push(long_lifetime_runner);
-        runners.push(otp_attempts_reset_runner);
-        runners.push(password_attempts_reset_runner);
+    if is_selected("purge-blacklisted-tokens") {
+        blacklisted_token_purge_runner.add_job(
+            purge_expired_blacklisted_tokens_job,
+            String::from("Purge expired blacklisted tokens"),
+        );
+    }
+    if is_selected("purge-sessions") {
+        blacklisted_token_purge_runner.add_job(
+            purge_expired_sessions_job,
+            String::from("Purge expired sessions"),
+        );
     }
 
-    let server = HttpServer::new(move || {
-        App::new()
-            .app_data(Data::new(db_thread_pool.clone()))
-            .configure(services::api::configure)
-            .configure(services::web::configure)
-            .wrap(Logger::default())
-    })
-    .workers(env::CONF.workers.actix_workers)
-    .bind(base_addr)?
-    .run()
-    .await;
+    if is_selected("refresh-exchange-rates") {
+        exchange_rate_refresh_runner.add_job(
+            refresh_exchange_rates_job,
+            String::from("Refresh exchange rates"),
+        );
+    }
 
-    // Log something so th runners vec doesn't get optimized away
-    for _ in runners {
-        log::info!("Shutting down cron job runner...");
+    if is_selected("deliver-pushes") {
+        let db_thread_pool_ref = db_thread_pool.clone();
+
+        let push_providers = match utils::push::configured_providers() {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+
+        // Cron jobs run on plain threads, so the push providers' HTTP clients get a runtime here
+        let push_runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create runtime for push delivery");
+
+        let deliver_pushes_job = move || {
+            let db_connection = db_thread_pool_ref
+                .get()
+                .expect("Failed to get thread for connecting to db");
+
+            if push_runtime
+                .block_on(utils::push::deliver_pending_pushes(
+                    &db_connection,
+                    &push_providers,
+                ))
+                .is_err()
+            {
+                return Err(cron::CronJobError::JobFailure(Some(
+                    "Failed to deliver pushes",
+                )));
+            }
+
+            Ok(())
+        };
+
+        push_delivery_runner.add_job(deliver_pushes_job, String::from("Deliver pushes"));
     }
 
-    return server;
+    vec![
+        long_lifetime_runner,
+        otp_attempts_reset_runner,
+        password_attempts_reset_runner,
+        blacklisted_token_purge_runner,
+        exchange_rate_refresh_runner,
+        push_delivery_runner,
+    ]
+}
+
+fn refresh_exchange_rates(db_connection: &DbConnection) -> Result<(), cron::CronJobError> {
+    let provider = utils::currency::ConfiguredRates;
+
+    let rates = match provider.fetch_rates() {
+        Ok(r) => r,
+        Err(e) => {
+            log::error!("{}", e);
+            return Err(cron::CronJobError::JobFailure(Some(
+                "Failed to fetch exchange rates",
+            )));
+        }
+    };
+
+    if utils::db::exchange_rate::replace_exchange_rates(
+        db_connection,
+        provider.base_currency(),
+        &rates,
+    )
+    .is_err()
+    {
+        return Err(cron::CronJobError::JobFailure(Some(
+            "Failed to cache exchange rates",
+        )));
+    }
+
+    Ok(())
 }
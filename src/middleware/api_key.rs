@@ -0,0 +1,93 @@
+use actix_web::dev::Payload;
+use actix_web::{error, web, FromRequest, HttpRequest};
+use futures::future::{self, LocalBoxFuture};
+use futures::FutureExt;
+use log::error;
+use uuid::Uuid;
+
+use crate::definitions::DbThreadPool;
+use crate::env;
+use crate::handlers::error::ServerError;
+use crate::utils::db;
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+// Authenticates requests from automation platforms (Zapier, IFTTT, etc.) and third-party apps that
+// hold a long-lived API key rather than a short-lived access token. Every request counts against
+// the key's daily quota. The wrapped value is the key owner's user ID.
+#[derive(Debug)]
+pub struct ApiKeyUser(pub Uuid);
+
+impl FromRequest for ApiKeyUser {
+    type Error = error::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        const INVALID_KEY_MSG: &str = "API key is invalid";
+
+        let key = match req.headers().get(API_KEY_HEADER) {
+            Some(header) => match header.to_str() {
+                Ok(k) => String::from(k.trim()),
+                Err(_) => {
+                    return future::err(ServerError::UserUnauthorized(Some(INVALID_KEY_MSG)).into())
+                        .boxed_local()
+                }
+            },
+            None => {
+                return future::err(
+                    ServerError::UserUnauthorized(Some("No API key provided")).into(),
+                )
+                .boxed_local()
+            }
+        };
+
+        let db_thread_pool = match req.app_data::<web::Data<DbThreadPool>>() {
+            Some(p) => p.clone(),
+            None => {
+                error!("Database thread pool is missing from app data");
+                return future::err(
+                    ServerError::InternalError(Some("Internal server error")).into(),
+                )
+                .boxed_local();
+            }
+        };
+
+        async move {
+            match web::block(move || {
+                let db_connection = db_thread_pool
+                    .get()
+                    .expect("Failed to access database thread pool");
+
+                let api_key = db::api_key::use_api_key(&db_connection, &key)?;
+                let request_count = db::api_key::record_api_key_request(
+                    &db_connection,
+                    api_key.id,
+                    chrono::Utc::now().naive_utc().date(),
+                )?;
+
+                Ok((api_key.user_id, request_count))
+            })
+            .await
+            {
+                Ok(Ok((_, request_count)))
+                    if request_count > env::CONF.security.api_key_daily_request_limit =>
+                {
+                    Err(ServerError::TooManyRequests(Some(
+                        "API key has reached its daily request limit",
+                    ))
+                    .into())
+                }
+                Ok(Ok((user_id, _))) => Ok(ApiKeyUser(user_id)),
+                Ok(Err(diesel::result::Error::NotFound)) => {
+                    Err(ServerError::UserUnauthorized(Some(INVALID_KEY_MSG)).into())
+                }
+                Ok(Err(e)) => {
+                    error!("{}", e);
+                    Err(ServerError::InternalError(Some("Failed to validate API key")).into())
+                }
+                Err(_) => Err(ServerError::InternalError(Some("Actix thread pool failure")).into()),
+            }
+        }
+        .boxed_local()
+    }
+}
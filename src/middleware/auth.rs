@@ -2,6 +2,7 @@ use actix_web::dev::Payload;
 use actix_web::{error, FromRequest, HttpRequest};
 use futures::future;
 
+use crate::handlers::error::ServerError;
 use crate::utils::auth_token;
 
 #[derive(Debug)]
@@ -16,32 +17,42 @@ impl FromRequest for AuthorizedUserClaims {
 
         let auth_header = match req.headers().get("Authorization") {
             Some(header) => header,
-            None => return future::err(error::ErrorUnauthorized("No token provided")),
+            None => {
+                return future::err(ServerError::UserUnauthorized(Some("No token provided")).into())
+            }
         };
 
         let mut header_parts_iter = match auth_header.to_str() {
             Ok(h) => h,
-            Err(_) => return future::err(error::ErrorUnauthorized(INVALID_TOKEN_MSG)),
+            Err(_) => {
+                return future::err(ServerError::UserUnauthorized(Some(INVALID_TOKEN_MSG)).into())
+            }
         }
         .split_ascii_whitespace();
 
         match header_parts_iter.next() {
             Some(str) => {
                 if str.to_ascii_lowercase() != "bearer" {
-                    return future::err(error::ErrorUnauthorized(INVALID_TOKEN_MSG));
+                    return future::err(
+                        ServerError::UserUnauthorized(Some(INVALID_TOKEN_MSG)).into(),
+                    );
                 }
             }
-            None => return future::err(error::ErrorUnauthorized(INVALID_TOKEN_MSG)),
+            None => {
+                return future::err(ServerError::UserUnauthorized(Some(INVALID_TOKEN_MSG)).into())
+            }
         };
 
         let token = match header_parts_iter.next() {
             Some(str) => str,
-            None => return future::err(error::ErrorUnauthorized(INVALID_TOKEN_MSG)),
+            None => {
+                return future::err(ServerError::UserUnauthorized(Some(INVALID_TOKEN_MSG)).into())
+            }
         };
 
         let claims = match auth_token::validate_access_token(token) {
             Ok(c) => c,
-            Err(_) => return future::err(error::ErrorUnauthorized(INVALID_TOKEN_MSG)),
+            Err(e) => return future::err(ServerError::from(e).into()),
         };
 
         future::ok(AuthorizedUserClaims(claims))
@@ -57,6 +68,7 @@ mod tests {
     use rand::prelude::*;
     use uuid::Uuid;
 
+    use crate::models::session::Session;
     use crate::models::user::NewUser;
 
     #[actix_rt::test]
@@ -85,8 +97,6 @@ mod tests {
 
         let token = auth_token::generate_access_token(auth_token::TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -139,8 +149,6 @@ mod tests {
 
         let _token = auth_token::generate_access_token(auth_token::TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -177,8 +185,6 @@ mod tests {
 
         let token = auth_token::generate_access_token(auth_token::TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -217,8 +223,6 @@ mod tests {
 
         let _ = auth_token::generate_access_token(auth_token::TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -256,8 +260,6 @@ mod tests {
 
         let token = auth_token::generate_access_token(auth_token::TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap()
         .to_string();
@@ -298,11 +300,26 @@ mod tests {
             created_timestamp: timestamp,
         };
 
-        let token = auth_token::generate_refresh_token(auth_token::TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let session = Session {
+            id: Uuid::new_v4(),
+            user_id,
+            version: 0,
+            expiration_timestamp: timestamp,
+            revoked_timestamp: None,
+            created_timestamp: timestamp,
+            is_password_reset: false,
+            device_name: None,
+            user_agent: None,
+            ip_address: None,
+            last_used_timestamp: timestamp,
+        };
+
+        let token = auth_token::generate_refresh_token(
+            auth_token::TokenParams {
+                user_id: &new_user.id,
+            },
+            &session,
+        )
         .unwrap();
 
         let req = test::TestRequest::get()
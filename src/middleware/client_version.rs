@@ -0,0 +1,218 @@
+use actix_web::body::{BoxBody, EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::HeaderMap;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use futures::future::{self, LocalBoxFuture};
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::env;
+use crate::handlers::error::ServerError;
+use crate::utils::app_version::{is_version_at_least, parse_version};
+
+pub const APP_PLATFORM_HEADER: &str = "App-Platform";
+pub const APP_VERSION_HEADER: &str = "App-Version";
+
+// Requests to these paths are never rejected so outdated clients can still find out what to do
+const EXEMPT_PATH_PREFIX: &str = "/api/meta";
+
+pub fn platform_conf(platform: &str) -> Option<&'static env::ClientPlatform> {
+    match platform.trim().to_ascii_lowercase().as_str() {
+        "android" => Some(&env::CONF.client_compatibility.android),
+        "ios" => Some(&env::CONF.client_compatibility.ios),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpgradeRequired {
+    pub error_code: String,
+    pub message: String,
+    pub platform: String,
+    pub client_version: String,
+    pub min_version: String,
+    pub store_url: String,
+}
+
+impl fmt::Display for UpgradeRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Upgrade required: {} client {} is older than {}",
+            self.platform, self.client_version, self.min_version
+        )
+    }
+}
+
+impl ResponseError for UpgradeRequired {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UPGRADE_REQUIRED
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        HttpResponse::build(self.status_code()).json(self)
+    }
+}
+
+// Clients that don't identify themselves (browsers, scripts, API-key integrations) and
+// platforms without a configured minimum are let through
+fn check_client_version(headers: &HeaderMap) -> Result<(), actix_web::Error> {
+    let platform = match headers.get(APP_PLATFORM_HEADER) {
+        Some(p) => p
+            .to_str()
+            .map_err(|_| ServerError::InvalidFormat(Some("Invalid app platform")))?,
+        None => return Ok(()),
+    };
+
+    let platform_conf = match platform_conf(platform) {
+        Some(c) => c,
+        None => return Ok(()),
+    };
+
+    let client_version = match headers.get(APP_VERSION_HEADER) {
+        Some(v) => v
+            .to_str()
+            .map_err(|_| ServerError::InvalidFormat(Some("Invalid app version")))?,
+        None => return Err(ServerError::InvalidFormat(Some("No app version provided")).into()),
+    };
+
+    let parsed_client_version = parse_version(client_version)
+        .ok_or(ServerError::InvalidFormat(Some("Invalid app version")))?;
+    let parsed_min_version = parse_version(&platform_conf.min_version)
+        .expect("Invalid client_compatibility min_version config");
+
+    if !is_version_at_least(&parsed_client_version, &parsed_min_version) {
+        return Err(UpgradeRequired {
+            error_code: String::from("upgrade_required"),
+            message: String::from("Upgrade required"),
+            platform: platform.trim().to_ascii_lowercase(),
+            client_version: String::from(client_version.trim()),
+            min_version: platform_conf.min_version.clone(),
+            store_url: platform_conf.store_url.clone(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+pub struct ClientVersionCheck;
+
+impl<S, B> Transform<S, ServiceRequest> for ClientVersionCheck
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = ClientVersionCheckMiddleware<S>;
+    type InitError = ();
+    type Future = future::Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ok(ClientVersionCheckMiddleware { service })
+    }
+}
+
+pub struct ClientVersionCheckMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for ClientVersionCheckMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !req.path().starts_with(EXEMPT_PATH_PREFIX) {
+            if let Err(e) = check_client_version(req.headers()) {
+                let resp = req.into_response(e.error_response()).map_into_right_body();
+                return future::ok(resp).boxed_local();
+            }
+        }
+
+        let fut = self.service.call(req);
+        async move { Ok(fut.await?.map_into_left_body()) }.boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web::Data;
+    use actix_web::{http, test, App};
+
+    use crate::services;
+
+    #[actix_rt::test]
+    async fn test_client_version_check() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(db_thread_pool.clone()))
+                .configure(services::api::configure),
+        )
+        .await;
+
+        // Unauthenticated, so a request that gets past the version check is rejected with a 401
+        let req = test::TestRequest::get().uri("/api/user/get").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get")
+            .insert_header((APP_PLATFORM_HEADER, "iOS"))
+            .insert_header((APP_VERSION_HEADER, "1.2.0"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get")
+            .insert_header((APP_PLATFORM_HEADER, "android"))
+            .insert_header((APP_VERSION_HEADER, "1.1.9"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UPGRADE_REQUIRED);
+
+        let upgrade_required = test::read_body_json::<UpgradeRequired, _>(resp).await;
+        assert_eq!(upgrade_required.platform, "android");
+        assert_eq!(upgrade_required.client_version, "1.1.9");
+        assert_eq!(
+            upgrade_required.min_version,
+            env::CONF.client_compatibility.android.min_version
+        );
+        assert_eq!(
+            upgrade_required.store_url,
+            env::CONF.client_compatibility.android.store_url
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/api/user/get")
+            .insert_header((APP_PLATFORM_HEADER, "ios"))
+            .insert_header((APP_VERSION_HEADER, "not-a-version"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+
+        // Outdated clients can still reach the meta endpoints
+        let req = test::TestRequest::get()
+            .uri("/api/meta/compatibility")
+            .insert_header((APP_PLATFORM_HEADER, "android"))
+            .insert_header((APP_VERSION_HEADER, "0.1"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+}
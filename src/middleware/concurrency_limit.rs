@@ -0,0 +1,400 @@
+use actix_web::body::{BodySize, BoxBody, EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::web::Bytes;
+use actix_web::ResponseError;
+use futures::future::{self, LocalBoxFuture};
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::env;
+use crate::handlers::error::ServerError;
+use crate::middleware::api_key::API_KEY_HEADER;
+use crate::utils::auth_token;
+
+lazy_static! {
+    static ref LIMITERS: Mutex<HashMap<&'static str, Arc<Limiter>>> = Mutex::new(HashMap::new());
+}
+
+// Caps how many requests to a group of expensive endpoints (imports, reports) run at once, both
+// overall and for each user. Requests over a cap wait in line for up to the queue timeout and are
+// then turned away with a 503 so a stampede can't tie up every database connection.
+//
+// Every `ConcurrencyLimit` with the same name shares its caps across all workers.
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    limiter: Arc<Limiter>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(name: &'static str) -> Self {
+        let conf = &env::CONF.concurrency_limits;
+
+        Self::with_limits(
+            name,
+            conf.global_limit,
+            conf.per_user_limit,
+            Duration::from_millis(conf.queue_timeout_ms),
+            conf.retry_after_secs,
+        )
+    }
+
+    fn with_limits(
+        name: &'static str,
+        global_limit: usize,
+        per_user_limit: usize,
+        queue_timeout: Duration,
+        retry_after_secs: u64,
+    ) -> Self {
+        let mut limiters = LIMITERS
+            .lock()
+            .expect("Concurrency limiters lock was poisoned");
+
+        let limiter = limiters.entry(name).or_insert_with(|| {
+            Arc::new(Limiter {
+                global: Arc::new(Semaphore::new(global_limit)),
+                per_user: Mutex::new(HashMap::new()),
+                per_user_limit,
+                queue_timeout,
+                retry_after_secs,
+            })
+        });
+
+        Self {
+            limiter: Arc::clone(limiter),
+        }
+    }
+}
+
+struct Limiter {
+    global: Arc<Semaphore>,
+    per_user: Mutex<HashMap<String, Arc<Semaphore>>>,
+    per_user_limit: usize,
+    queue_timeout: Duration,
+    retry_after_secs: u64,
+}
+
+// Held for as long as the request runs
+struct Permits {
+    limiter: Arc<Limiter>,
+    user_key: Option<String>,
+    user_permit: Option<OwnedSemaphorePermit>,
+    _global_permit: OwnedSemaphorePermit,
+}
+
+impl Drop for Permits {
+    fn drop(&mut self) {
+        drop(self.user_permit.take());
+
+        if let Some(user_key) = &self.user_key {
+            self.limiter.forget_user_if_idle(user_key);
+        }
+    }
+}
+
+// A streamed response body is still being read from the database after the handler returns, so the
+// permits are held until the body is done with
+pub struct PermitBody {
+    body: BoxBody,
+    _permits: Permits,
+}
+
+impl MessageBody for PermitBody {
+    type Error = <BoxBody as MessageBody>::Error;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Pin::new(&mut self.body).poll_next(cx)
+    }
+}
+
+impl Limiter {
+    async fn acquire(self: Arc<Self>, user_key: Option<String>) -> Option<Permits> {
+        let user_semaphore = user_key.as_ref().map(|k| {
+            let mut per_user = self
+                .per_user
+                .lock()
+                .expect("Concurrency limiter lock was poisoned");
+
+            Arc::clone(
+                per_user
+                    .entry(k.clone())
+                    .or_insert_with(|| Arc::new(Semaphore::new(self.per_user_limit))),
+            )
+        });
+
+        let limiter = Arc::clone(&self);
+        let permit_user_key = user_key.clone();
+        let permits = async move {
+            // The user's own cap is taken first so one user's queued requests don't hold global
+            // permits that other users could be using
+            let user_permit = match user_semaphore {
+                Some(s) => Some(s.acquire_owned().await.ok()?),
+                None => None,
+            };
+
+            let global_permit = Arc::clone(&limiter.global).acquire_owned().await.ok()?;
+
+            Some(Permits {
+                limiter,
+                user_key: permit_user_key,
+                user_permit,
+                _global_permit: global_permit,
+            })
+        };
+
+        let permits = actix_web::rt::time::timeout(self.queue_timeout, permits)
+            .await
+            .ok()
+            .flatten();
+
+        if let (None, Some(user_key)) = (&permits, &user_key) {
+            self.forget_user_if_idle(user_key);
+        }
+
+        permits
+    }
+
+    // Users with nothing in flight are dropped from the map so it doesn't grow without bound
+    fn forget_user_if_idle(&self, user_key: &str) {
+        let mut per_user = self
+            .per_user
+            .lock()
+            .expect("Concurrency limiter lock was poisoned");
+
+        let is_idle = per_user.get(user_key).is_some_and(|s| {
+            Arc::strong_count(s) == 1 && s.available_permits() == self.per_user_limit
+        });
+
+        if is_idle {
+            per_user.remove(user_key);
+        }
+    }
+}
+
+// Requests are attributed to the user behind a valid access token or to the API key they carry.
+// Anything else only counts toward the global cap and is left for the handler to reject.
+fn user_key(req: &ServiceRequest) -> Option<String> {
+    if let Some(token) = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| {
+            let mut parts = h.split_ascii_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("bearer") => Some(token),
+                _ => None,
+            }
+        })
+    {
+        return auth_token::validate_access_token(token)
+            .ok()
+            .map(|claims| format!("user:{}", claims.uid));
+    }
+
+    req.headers()
+        .get(API_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|k| format!("api_key:{}", k.trim()))
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ConcurrencyLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<PermitBody>>;
+    type Error = actix_web::Error;
+    type Transform = ConcurrencyLimitMiddleware<S>;
+    type InitError = ();
+    type Future = future::Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ok(ConcurrencyLimitMiddleware {
+            service: Rc::new(service),
+            limiter: Arc::clone(&self.limiter),
+        })
+    }
+}
+
+pub struct ConcurrencyLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: Arc<Limiter>,
+}
+
+impl<S, B> Service<ServiceRequest> for ConcurrencyLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<PermitBody>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let limiter = Arc::clone(&self.limiter);
+        let user_key = user_key(&req);
+
+        async move {
+            let retry_after_secs = limiter.retry_after_secs;
+
+            let permits = match limiter.acquire(user_key).await {
+                Some(p) => p,
+                None => {
+                    let mut response = ServerError::ServiceUnavailable(Some(
+                        "Too many requests in progress. Try again shortly.",
+                    ))
+                    .error_response();
+
+                    response
+                        .headers_mut()
+                        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+            };
+
+            let res = service.call(req).await?;
+
+            Ok(res
+                .map_body(|_, body| PermitBody {
+                    body: body.boxed(),
+                    _permits: permits,
+                })
+                .map_into_left_body())
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{http, test, web, App, HttpResponse};
+    use uuid::Uuid;
+
+    async fn slow_handler() -> HttpResponse {
+        actix_web::rt::time::sleep(Duration::from_millis(300)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    fn bearer(user_id: &Uuid) -> String {
+        let token = auth_token::generate_access_token(auth_token::TokenParams { user_id }).unwrap();
+        format!("bearer {}", token)
+    }
+
+    #[actix_rt::test]
+    async fn test_concurrency_limit() {
+        let limit = ConcurrencyLimit::with_limits(
+            "test_concurrency_limit",
+            2,
+            1,
+            Duration::from_millis(50),
+            7,
+        );
+
+        let app = test::init_service(
+            App::new().service(
+                web::resource("/slow")
+                    .wrap(limit)
+                    .route(web::get().to(slow_handler)),
+            ),
+        )
+        .await;
+
+        let user1_auth = bearer(&Uuid::new_v4());
+        let user2_auth = bearer(&Uuid::new_v4());
+        let user3_auth = bearer(&Uuid::new_v4());
+
+        let request = |auth: &str| {
+            test::TestRequest::get()
+                .uri("/slow")
+                .insert_header((header::AUTHORIZATION, auth))
+                .to_request()
+        };
+
+        // The second request from the same user is over the per-user cap. The third user is over
+        // the global cap.
+        let (first, same_user, second_user, third_user) = futures::join!(
+            test::call_service(&app, request(&user1_auth)),
+            test::call_service(&app, request(&user1_auth)),
+            test::call_service(&app, request(&user2_auth)),
+            test::call_service(&app, request(&user3_auth)),
+        );
+
+        assert_eq!(first.status(), http::StatusCode::OK);
+        assert_eq!(second_user.status(), http::StatusCode::OK);
+
+        let limiter = Arc::clone(&ConcurrencyLimit::new("test_concurrency_limit").limiter);
+
+        // Permits are held until the response body is done with
+        assert_eq!(limiter.global.available_permits(), 0);
+        drop((first, second_user));
+        assert_eq!(limiter.global.available_permits(), 2);
+
+        for resp in [same_user, third_user] {
+            assert_eq!(resp.status(), http::StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(
+                resp.headers().get(header::RETRY_AFTER).unwrap(),
+                HeaderValue::from(7u64)
+            );
+        }
+
+        let resp = test::call_service(&app, request(&user1_auth)).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        drop(resp);
+
+        assert_eq!(limiter.global.available_permits(), 2);
+        assert!(limiter.per_user.lock().unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_queued_request_runs_when_permit_frees_up() {
+        let limit = ConcurrencyLimit::with_limits(
+            "test_queued_request_runs_when_permit_frees_up",
+            1,
+            1,
+            Duration::from_secs(5),
+            1,
+        );
+
+        let app = test::init_service(
+            App::new().service(
+                web::resource("/slow")
+                    .wrap(limit)
+                    .route(web::get().to(slow_handler)),
+            ),
+        )
+        .await;
+
+        // Each response is dropped as soon as it arrives, giving its permit back
+        let call = || async {
+            test::call_service(&app, test::TestRequest::get().uri("/slow").to_request())
+                .await
+                .status()
+        };
+
+        let (first, second) = futures::join!(call(), call());
+
+        assert_eq!(first, http::StatusCode::OK);
+        assert_eq!(second, http::StatusCode::OK);
+    }
+}
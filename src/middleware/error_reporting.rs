@@ -0,0 +1,170 @@
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use futures::future::{self, LocalBoxFuture};
+use futures::FutureExt;
+
+use crate::middleware::client_version::{APP_PLATFORM_HEADER, APP_VERSION_HEADER};
+use crate::utils::error_reporting::{self, RequestContext};
+
+const UNMATCHED_ROUTE: &str = "<unmatched>";
+
+// Reports 5xx responses to the configured error-reporting service. Only the method, matched
+// route pattern, status, and client platform/version are attached to each report.
+pub struct ErrorReporting;
+
+impl<S, B> Transform<S, ServiceRequest> for ErrorReporting
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = ErrorReportingMiddleware<S>;
+    type InitError = ();
+    type Future = future::Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ok(ErrorReportingMiddleware { service })
+    }
+}
+
+pub struct ErrorReportingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for ErrorReportingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let method = String::from(req.method().as_str());
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| String::from(UNMATCHED_ROUTE));
+        let app_platform = header_value(&req, APP_PLATFORM_HEADER);
+        let app_version = header_value(&req, APP_VERSION_HEADER);
+
+        let fut = self.service.call(req);
+
+        async move {
+            let result = fut.await;
+
+            let (status, message) = match &result {
+                Ok(res) => (
+                    res.status(),
+                    res.response()
+                        .error()
+                        .map(|e| e.to_string())
+                        .unwrap_or_else(|| String::from("Server error")),
+                ),
+                Err(e) => (e.as_response_error().status_code(), e.to_string()),
+            };
+
+            if status.is_server_error() {
+                error_reporting::report_server_error(
+                    &message,
+                    &RequestContext {
+                        method: &method,
+                        route: &route,
+                        status: status.as_u16(),
+                        app_platform: app_platform.as_deref(),
+                        app_version: app_version.as_deref(),
+                    },
+                );
+            }
+
+            result
+        }
+        .boxed_local()
+    }
+}
+
+fn header_value(req: &ServiceRequest, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|h| h.to_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{http, test, web, App, HttpResponse};
+    use sentry::test::TestTransport;
+    use std::sync::Arc;
+
+    use crate::handlers::error::ServerError;
+
+    async fn failing_handler() -> Result<HttpResponse, ServerError> {
+        Err(ServerError::DatabaseTransactionError(Some(
+            "Failed to get budget",
+        )))
+    }
+
+    async fn missing_handler() -> Result<HttpResponse, ServerError> {
+        Err(ServerError::NotFound(Some("Budget not found")))
+    }
+
+    #[actix_rt::test]
+    async fn test_error_reporting() {
+        let transport = TestTransport::new();
+        let options = sentry::ClientOptions {
+            dsn: Some("https://public@sentry.invalid/1".parse().unwrap()),
+            transport: Some(Arc::new(transport.clone())),
+            ..Default::default()
+        };
+
+        // Each test thread gets its own hub, so binding here doesn't affect other tests
+        sentry::Hub::current().bind_client(Some(Arc::new(options.into())));
+
+        let app = test::init_service(
+            App::new()
+                .wrap(ErrorReporting)
+                .route("/budget/{id}", web::get().to(failing_handler))
+                .route("/missing", web::get().to(missing_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/missing?email=test_user@test.com")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        assert!(transport.fetch_and_clear_events().is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/budget/42?email=test_user@test.com")
+            .insert_header((APP_PLATFORM_HEADER, "ios"))
+            .insert_header((APP_VERSION_HEADER, "1.2.0"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+
+        let events = transport.fetch_and_clear_events();
+        assert_eq!(events.len(), 1);
+
+        let event = &events[0];
+        assert_eq!(
+            event.message.as_deref(),
+            Some("Database transaction failed: Failed to get budget")
+        );
+        assert_eq!(event.tags["http.method"], "GET");
+        assert_eq!(event.tags["http.route"], "/budget/{id}");
+        assert_eq!(event.tags["http.status_code"], "500");
+        assert_eq!(event.tags["app.platform"], "ios");
+        assert_eq!(event.tags["app.version"], "1.2.0");
+
+        let serialized_event = serde_json::to_string(event).unwrap();
+        assert!(!serialized_event.contains("test_user@test.com"));
+        assert!(!serialized_event.contains("/budget/42"));
+    }
+}
@@ -0,0 +1,226 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::ResponseError;
+use futures::future::{self, LocalBoxFuture};
+use futures::FutureExt;
+use rand::prelude::*;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::env;
+use crate::handlers::error::ServerError;
+
+const PRODUCTION_ENVIRONMENT: &str = "production";
+
+// Health checks are left alone so orchestrators don't restart or drain instances over faults
+// that were only injected
+const EXEMPT_PATH_PREFIX: &str = "/health/";
+
+// Fails closed: the config can only turn on faults in a build made with the `fault-injection`
+// feature, and never when error reporting is set up for production
+pub fn is_enabled() -> bool {
+    cfg!(feature = "fault-injection")
+        && env::CONF.fault_injection.enabled
+        && !env::CONF
+            .error_reporting
+            .environment
+            .eq_ignore_ascii_case(PRODUCTION_ENVIRONMENT)
+}
+
+// Randomly delays requests or answers them with the error responses the handlers give when a
+// database query or the server fails. The database is never touched, so this shows how clients
+// cope with the errors, not how the server copes with losing its database. Should only be wrapped
+// around the app when `is_enabled()` returns true.
+#[derive(Clone, Copy, Debug)]
+pub struct FaultInjection {
+    pub latency_rate: f64,
+    pub min_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub db_error_rate: f64,
+    pub server_error_rate: f64,
+}
+
+impl FaultInjection {
+    pub fn from_conf() -> Self {
+        let conf = &env::CONF.fault_injection;
+
+        Self {
+            latency_rate: conf.latency_rate,
+            min_latency_ms: conf.min_latency_ms,
+            max_latency_ms: conf.max_latency_ms,
+            db_error_rate: conf.db_error_rate,
+            server_error_rate: conf.server_error_rate,
+        }
+    }
+
+    fn roll(rate: f64) -> bool {
+        rate > 0.0 && rand::thread_rng().gen_bool(rate.min(1.0))
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        if !Self::roll(self.latency_rate) {
+            return None;
+        }
+
+        let max_latency_ms = self.max_latency_ms.max(self.min_latency_ms);
+        let latency_ms = rand::thread_rng().gen_range(self.min_latency_ms..=max_latency_ms);
+
+        Some(Duration::from_millis(latency_ms))
+    }
+
+    fn fault(&self) -> Option<ServerError> {
+        if Self::roll(self.db_error_rate) {
+            Some(ServerError::DatabaseTransactionError(Some(
+                "Injected fault: database error",
+            )))
+        } else if Self::roll(self.server_error_rate) {
+            Some(ServerError::InternalError(Some("Injected fault")))
+        } else {
+            None
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for FaultInjection
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = FaultInjectionMiddleware<S>;
+    type InitError = ();
+    type Future = future::Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ok(FaultInjectionMiddleware {
+            service: Rc::new(service),
+            faults: *self,
+        })
+    }
+}
+
+pub struct FaultInjectionMiddleware<S> {
+    service: Rc<S>,
+    faults: FaultInjection,
+}
+
+impl<S, B> Service<ServiceRequest> for FaultInjectionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        if req.path().starts_with(EXEMPT_PATH_PREFIX) {
+            return async move { Ok(service.call(req).await?.map_into_left_body()) }.boxed_local();
+        }
+
+        let latency = self.faults.latency();
+        let fault = self.faults.fault();
+
+        async move {
+            if let Some(latency) = latency {
+                actix_web::rt::time::sleep(latency).await;
+            }
+
+            if let Some(e) = fault {
+                return Ok(req.into_response(e.error_response()).map_into_right_body());
+            }
+
+            Ok(service.call(req).await?.map_into_left_body())
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{http, test, web, App, HttpResponse};
+    use std::time::Instant;
+
+    const NO_FAULTS: FaultInjection = FaultInjection {
+        latency_rate: 0.0,
+        min_latency_ms: 0,
+        max_latency_ms: 0,
+        db_error_rate: 0.0,
+        server_error_rate: 0.0,
+    };
+
+    async fn ok_handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_rt::test]
+    async fn test_fault_injection() {
+        let app = test::init_service(
+            App::new()
+                .wrap(NO_FAULTS)
+                .route("/", web::get().to(ok_handler)),
+        )
+        .await;
+        let resp = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(FaultInjection {
+                    db_error_rate: 1.0,
+                    ..NO_FAULTS
+                })
+                .route("/", web::get().to(ok_handler)),
+        )
+        .await;
+        let resp = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("database error"));
+
+        let app = test::init_service(
+            App::new()
+                .wrap(FaultInjection {
+                    server_error_rate: 1.0,
+                    ..NO_FAULTS
+                })
+                .route("/", web::get().to(ok_handler))
+                .route("/health/live", web::get().to(ok_handler)),
+        )
+        .await;
+        let resp = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(FaultInjection {
+                    latency_rate: 1.0,
+                    min_latency_ms: 50,
+                    max_latency_ms: 60,
+                    ..NO_FAULTS
+                })
+                .route("/", web::get().to(ok_handler)),
+        )
+        .await;
+        let start = Instant::now();
+        let resp = test::call_service(&app, test::TestRequest::get().to_request()).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // The testing config leaves fault injection disabled
+        assert!(!is_enabled());
+    }
+}
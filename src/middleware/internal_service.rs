@@ -0,0 +1,88 @@
+use actix_web::dev::Payload;
+use actix_web::{error, FromRequest, HttpRequest};
+use futures::future;
+
+use crate::env;
+use crate::handlers::error::ServerError;
+
+pub const INTERNAL_SERVICE_KEY_HEADER: &str = "X-Internal-Service-Key";
+
+// Authenticates requests from the other backend services. They share one of the configured
+// internal service keys rather than holding a user's token. With no keys configured, every
+// request is rejected.
+#[derive(Debug)]
+pub struct InternalService;
+
+impl FromRequest for InternalService {
+    type Error = error::Error;
+    type Future = future::Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let key = match req.headers().get(INTERNAL_SERVICE_KEY_HEADER) {
+            Some(header) => match header.to_str() {
+                Ok(k) => k.trim(),
+                Err(_) => {
+                    return future::err(
+                        ServerError::UserUnauthorized(Some("Invalid service key")).into(),
+                    )
+                }
+            },
+            None => {
+                return future::err(
+                    ServerError::UserUnauthorized(Some("No service key provided")).into(),
+                )
+            }
+        };
+
+        if env::CONF
+            .keys
+            .internal_service_keys
+            .iter()
+            .any(|k| constant_time_eq(k.as_bytes(), key.as_bytes()))
+        {
+            future::ok(InternalService)
+        } else {
+            future::err(ServerError::UserUnauthorized(Some("Invalid service key")).into())
+        }
+    }
+}
+
+// Compares every byte so the time taken doesn't reveal how much of a guessed key was right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::test;
+
+    #[actix_rt::test]
+    async fn test_internal_service_key() {
+        let key = env::CONF.keys.internal_service_keys[0].as_str();
+
+        let req = test::TestRequest::get().to_http_request();
+        assert!(InternalService::from_request(&req, &mut Payload::None)
+            .await
+            .is_err());
+
+        let req = test::TestRequest::get()
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, &key[1..]))
+            .to_http_request();
+        assert!(InternalService::from_request(&req, &mut Payload::None)
+            .await
+            .is_err());
+
+        let req = test::TestRequest::get()
+            .insert_header((INTERNAL_SERVICE_KEY_HEADER, key))
+            .to_http_request();
+        assert!(InternalService::from_request(&req, &mut Payload::None)
+            .await
+            .is_ok());
+    }
+}
@@ -1 +1,8 @@
+pub mod api_key;
 pub mod auth;
+pub mod client_version;
+pub mod concurrency_limit;
+pub mod error_reporting;
+pub mod fault_injection;
+pub mod internal_service;
+pub mod request_logging;
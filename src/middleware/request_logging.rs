@@ -0,0 +1,286 @@
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use futures::future::{self, LocalBoxFuture};
+use futures::FutureExt;
+use log::info;
+use std::time::{Instant, SystemTime};
+use uuid::Uuid;
+
+use crate::utils::auth_token;
+use crate::utils::logging::{self, RequestScope, REQUEST_LOG_TARGET};
+use crate::utils::span_export::{self, AttributeValue, Span, SpanKind};
+use crate::utils::trace_context::{TraceContext, TRACEPARENT_HEADER};
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+const UNMATCHED_ROUTE: &str = "<unmatched>";
+
+// Gives each request a correlation id and logs one record for it once it has been handled. The id
+// is taken from the X-Request-Id header when a proxy has already assigned one, is attached to
+// everything logged while the request is handled, and is returned in the response's X-Request-Id
+// header. Only the route pattern is logged, never the path, so IDs in the URL stay out of the logs.
+// A request with a W3C traceparent header is handled as a child span of the caller's trace, and the
+// request's span is exported when tracing is configured.
+pub struct RequestLogging;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestLogging
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RequestLoggingMiddleware<S>;
+    type InitError = ();
+    type Future = future::Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ok(RequestLoggingMiddleware { service })
+    }
+}
+
+pub struct RequestLoggingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestLoggingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let start = Instant::now();
+        let start_time = SystemTime::now();
+
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| Uuid::parse_str(h.trim()).ok())
+            .unwrap_or_else(Uuid::new_v4);
+
+        let parent_trace_context = req
+            .headers()
+            .get(TRACEPARENT_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .and_then(TraceContext::parse);
+
+        let trace_context = parent_trace_context
+            .map(|parent| parent.child())
+            .unwrap_or_else(TraceContext::new_root);
+
+        let scope = RequestScope {
+            request_id,
+            trace_context,
+        };
+
+        let method = String::from(req.method().as_str());
+        let route = req
+            .match_pattern()
+            .unwrap_or_else(|| String::from(UNMATCHED_ROUTE));
+        let user_id = access_token_user_id(&req);
+
+        let fut = logging::with_request_scope(scope, self.service.call(req));
+
+        async move {
+            let mut result = fut.await;
+
+            let status = match &result {
+                Ok(res) => res.status(),
+                Err(e) => e.as_response_error().status_code(),
+            };
+
+            let user_id = user_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| String::from("-"));
+
+            logging::with_request_scope(scope, async {
+                info!(
+                    target: REQUEST_LOG_TARGET,
+                    "method={} route={} status={} duration_ms={} user_id={}",
+                    method,
+                    route,
+                    status.as_u16(),
+                    start.elapsed().as_millis(),
+                    user_id,
+                );
+            })
+            .await;
+
+            span_export::export(Span {
+                name: format!("{} {}", method, route),
+                kind: SpanKind::Server,
+                context: trace_context,
+                parent_span_id: parent_trace_context.map(|parent| parent.span_id),
+                start_time,
+                end_time: SystemTime::now(),
+                attributes: vec![
+                    ("http.request.method", AttributeValue::String(method)),
+                    ("http.route", AttributeValue::String(route)),
+                    (
+                        "http.response.status_code",
+                        AttributeValue::Int(i64::from(status.as_u16())),
+                    ),
+                ],
+                is_error: status.is_server_error(),
+            });
+
+            if let Ok(res) = &mut result {
+                res.headers_mut().insert(
+                    HeaderName::from_static("x-request-id"),
+                    HeaderValue::from_str(&request_id.to_string())
+                        .expect("UUIDs are valid header values"),
+                );
+            }
+
+            result
+        }
+        .boxed_local()
+    }
+}
+
+// The user is only identified from a valid access token. Requests made with other kinds of tokens,
+// or with none, are logged without a user ID.
+fn access_token_user_id(req: &ServiceRequest) -> Option<Uuid> {
+    let header = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let mut header_parts = header.split_ascii_whitespace();
+
+    if !header_parts.next()?.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    auth_token::validate_access_token(header_parts.next()?)
+        .ok()
+        .map(|claims| claims.uid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::{http, test, web, App, HttpResponse};
+
+    async fn request_id_handler() -> HttpResponse {
+        HttpResponse::Ok().body(
+            logging::current_request_scope()
+                .map(|scope| scope.request_id.to_string())
+                .unwrap_or_default(),
+        )
+    }
+
+    async fn trace_context_handler() -> HttpResponse {
+        HttpResponse::Ok().body(
+            logging::current_request_scope()
+                .map(|scope| scope.trace_context.traceparent())
+                .unwrap_or_default(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_request_logging() {
+        let app = test::init_service(
+            App::new()
+                .route("/request_id", web::get().to(request_id_handler))
+                .wrap(RequestLogging),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/request_id").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+
+        let header_id = Uuid::parse_str(
+            resp.headers()
+                .get(REQUEST_ID_HEADER)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+        )
+        .unwrap();
+        let body = test::read_body(resp).await;
+        assert_eq!(body, header_id.to_string());
+
+        // An ID assigned upstream is kept
+        let upstream_id = Uuid::new_v4();
+        let req = test::TestRequest::get()
+            .uri("/request_id")
+            .insert_header((REQUEST_ID_HEADER, upstream_id.to_string()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(REQUEST_ID_HEADER).unwrap(),
+            upstream_id.to_string().as_str()
+        );
+        let body = test::read_body(resp).await;
+        assert_eq!(body, upstream_id.to_string());
+
+        // Anything that isn't a UUID is replaced
+        let req = test::TestRequest::get()
+            .uri("/request_id")
+            .insert_header((REQUEST_ID_HEADER, "\"; DROP TABLE users"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(Uuid::parse_str(
+            resp.headers()
+                .get(REQUEST_ID_HEADER)
+                .unwrap()
+                .to_str()
+                .unwrap()
+        )
+        .is_ok());
+
+        let req = test::TestRequest::get().uri("/missing").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        assert!(resp.headers().contains_key(REQUEST_ID_HEADER));
+
+        assert!(logging::current_request_scope().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_trace_context() {
+        let app = test::init_service(
+            App::new()
+                .route("/trace_context", web::get().to(trace_context_handler))
+                .wrap(RequestLogging),
+        )
+        .await;
+
+        // The request joins the caller's trace in a span of its own
+        let upstream = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let req = test::TestRequest::get()
+            .uri("/trace_context")
+            .insert_header((TRACEPARENT_HEADER, upstream))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        let context = TraceContext::parse(&body).unwrap();
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_ne!(hex::encode(context.span_id), "00f067aa0ba902b7");
+        assert!(body.ends_with("-01"));
+
+        // Without a valid traceparent, a new trace is started
+        for traceparent in [None, Some("not a traceparent")] {
+            let mut req = test::TestRequest::get().uri("/trace_context");
+            if let Some(traceparent) = traceparent {
+                req = req.insert_header((TRACEPARENT_HEADER, traceparent));
+            }
+
+            let resp = test::call_service(&app, req.to_request()).await;
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+            let context = TraceContext::parse(&body).unwrap();
+            assert_ne!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+            assert!(body.ends_with("-00"));
+        }
+
+        assert!(logging::current_request_scope().is_none());
+    }
+}
@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::api_keys;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[table_name = "api_keys"]
+pub struct ApiKey {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub key_hash: String,
+    pub name: String,
+    pub is_revoked: bool,
+
+    pub last_used_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "api_keys"]
+pub struct NewApiKey<'a> {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub key_hash: &'a str,
+    pub name: &'a str,
+    pub is_revoked: bool,
+
+    pub last_used_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::api_key::ApiKey;
+use crate::schema::api_key_usage;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(ApiKey, foreign_key = "api_key_id")]
+#[primary_key(api_key_id, usage_date)]
+#[table_name = "api_key_usage"]
+pub struct ApiKeyUsage {
+    pub api_key_id: uuid::Uuid,
+    pub usage_date: NaiveDate,
+    pub request_count: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "api_key_usage"]
+pub struct NewApiKeyUsage {
+    pub api_key_id: uuid::Uuid,
+    pub usage_date: NaiveDate,
+    pub request_count: i32,
+}
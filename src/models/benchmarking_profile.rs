@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::benchmarking_profiles;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[primary_key(user_id)]
+#[table_name = "benchmarking_profiles"]
+pub struct BenchmarkingProfile {
+    pub user_id: uuid::Uuid,
+    pub country_code: String,
+    pub household_size: i16,
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "benchmarking_profiles"]
+pub struct NewBenchmarkingProfile<'a> {
+    pub user_id: uuid::Uuid,
+    pub country_code: &'a str,
+    pub household_size: i16,
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
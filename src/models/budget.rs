@@ -21,6 +21,13 @@ pub struct Budget {
 
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
+
+    // Tracking-only budgets have no limits; their categories only record what was spent
+    pub is_tracking_only: bool,
+    // Envelope budgets assign income to categories before it can be spent
+    pub is_envelope: bool,
+    // Archived budgets are left out of budget lists unless they are asked for
+    pub is_archived: bool,
 }
 
 #[derive(Debug, Insertable)]
@@ -40,4 +47,11 @@ pub struct NewBudget<'a> {
 
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
+
+    // Tracking-only budgets have no limits; their categories only record what was spent
+    pub is_tracking_only: bool,
+    // Envelope budgets assign income to categories before it can be spent
+    pub is_envelope: bool,
+    // Archived budgets are left out of budget lists unless they are asked for
+    pub is_archived: bool,
 }
@@ -0,0 +1,32 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::budget_audit_log;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct BudgetAuditLogEntry {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: Option<uuid::Uuid>,
+
+    pub action: i16,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "budget_audit_log"]
+pub struct NewBudgetAuditLogEntry {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: Option<uuid::Uuid>,
+
+    pub action: i16,
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+
+    pub created_timestamp: NaiveDateTime,
+}
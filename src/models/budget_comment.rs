@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::models::user::User;
+use crate::schema::budget_comments;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "budget_comments"]
+pub struct BudgetComment {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub is_deleted: bool,
+    pub is_current: bool,
+
+    pub text: String,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "budget_comments"]
+pub struct NewBudgetComment<'a> {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub is_deleted: bool,
+    pub is_current: bool,
+
+    pub text: &'a str,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
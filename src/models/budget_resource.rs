@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::schema::budget_resources;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "budget_resources"]
+pub struct BudgetResource {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub kind: i16,
+    pub title: String,
+    pub location: String,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "budget_resources"]
+pub struct NewBudgetResource<'a> {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub kind: i16,
+    pub title: &'a str,
+    pub location: &'a str,
+
+    pub created_timestamp: NaiveDateTime,
+}
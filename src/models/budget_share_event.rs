@@ -19,6 +19,14 @@ pub struct BudgetShareEvent {
     pub accepted_declined_timestamp: Option<NaiveDateTime>,
 }
 
+// An invitation along with the name of the user who sent it
+#[derive(Debug, Queryable)]
+pub struct BudgetShareEventWithSharer {
+    pub share_event: BudgetShareEvent,
+    pub sharer_first_name: String,
+    pub sharer_last_name: String,
+}
+
 #[derive(Debug, Insertable)]
 #[table_name = "budget_share_events"]
 pub struct NewBudgetShareEvent {
@@ -18,6 +18,9 @@ pub struct Category {
     pub color: String,
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
+    // Entries that would take spending over the limit are rejected unless the budget's owner
+    // overrides the cap
+    pub is_hard_capped: bool,
 }
 
 #[derive(Clone, Debug, Insertable)]
@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::schema::category_allocations;
+
+// Allocations are kept as a ledger. A category's envelope holds the sum of its allocations, and
+// a negative allocation moves money back to be budgeted.
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "category_allocations"]
+pub struct CategoryAllocation {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub category: i16,
+
+    pub amount_cents: i64,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "category_allocations"]
+pub struct NewCategoryAllocation {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub category: i16,
+
+    pub amount_cents: i64,
+
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,22 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::closed_months;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct ClosedMonth {
+    pub budget_id: uuid::Uuid,
+    pub month: NaiveDate,
+    pub closed_by_user_id: Option<uuid::Uuid>,
+    pub closed_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "closed_months"]
+pub struct NewClosedMonth {
+    pub budget_id: uuid::Uuid,
+    pub month: NaiveDate,
+    pub closed_by_user_id: Option<uuid::Uuid>,
+    pub closed_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,12 @@
+use chrono::NaiveDate;
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct ClosedMonthTotal {
+    pub budget_id: uuid::Uuid,
+    pub month: NaiveDate,
+    pub category: i16,
+    pub spent_cents: i64,
+    pub entry_count: i64,
+}
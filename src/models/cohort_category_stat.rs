@@ -0,0 +1,21 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::Queryable;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::cohort_category_stats;
+
+// Rows are written by the aggregation job with raw SQL, so there is no insertable counterpart
+#[derive(Clone, Debug, Serialize, Deserialize, Identifiable, Queryable)]
+#[table_name = "cohort_category_stats"]
+pub struct CohortCategoryStat {
+    pub id: i32,
+    pub country_code: String,
+    pub household_size: i16,
+    pub category_name: String,
+    pub period_start: NaiveDate,
+    pub user_count: i32,
+    pub p25_cents: i64,
+    pub median_cents: i64,
+    pub p75_cents: i64,
+    pub computed_timestamp: NaiveDateTime,
+}
@@ -25,6 +25,15 @@ pub struct Entry {
 
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
+
+    pub recurring_entry_id: Option<uuid::Uuid>,
+    pub import_batch_id: Option<uuid::Uuid>,
+
+    // The parts of the amount that went to tax and tip
+    pub tax_cents: Option<i64>,
+    pub tip_cents: Option<i64>,
+
+    pub is_deductible: bool,
 }
 
 #[derive(Clone, Debug, Insertable)]
@@ -43,4 +52,13 @@ pub struct NewEntry<'a> {
 
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
+
+    pub recurring_entry_id: Option<uuid::Uuid>,
+    pub import_batch_id: Option<uuid::Uuid>,
+
+    // The parts of the amount that went to tax and tip
+    pub tax_cents: Option<i64>,
+    pub tip_cents: Option<i64>,
+
+    pub is_deductible: bool,
 }
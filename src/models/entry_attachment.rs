@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::entry_attachments;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct EntryAttachment {
+    pub id: uuid::Uuid,
+    pub entry_id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "entry_attachments"]
+pub struct NewEntryAttachment<'a> {
+    pub id: uuid::Uuid,
+    pub entry_id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub file_name: &'a str,
+    pub content_type: &'a str,
+    pub size_bytes: i64,
+    pub storage_key: &'a str,
+
+    pub created_timestamp: NaiveDateTime,
+}
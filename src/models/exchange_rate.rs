@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::exchange_rates;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Identifiable, Queryable)]
+#[primary_key(currency)]
+#[table_name = "exchange_rates"]
+pub struct ExchangeRate {
+    pub currency: String,
+    pub base_currency: String,
+    pub rate: f64,
+    pub fetched_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "exchange_rates"]
+pub struct NewExchangeRate<'a> {
+    pub currency: &'a str,
+    pub base_currency: &'a str,
+    pub rate: f64,
+    pub fetched_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::schema::import_batches;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "import_batches"]
+pub struct ImportBatch {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub source_name: String,
+    pub entry_count: i32,
+    pub is_rolled_back: bool,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "import_batches"]
+pub struct NewImportBatch<'a> {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub source_name: &'a str,
+    pub entry_count: i32,
+    pub is_rolled_back: bool,
+
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,32 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::inbox_entries;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct InboxEntry {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub amount_cents: i64,
+    pub date: NaiveDate,
+    pub name: Option<String>,
+    pub note: Option<String>,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "inbox_entries"]
+pub struct NewInboxEntry<'a> {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub amount_cents: i64,
+    pub date: NaiveDate,
+    pub name: Option<&'a str>,
+    pub note: Option<&'a str>,
+
+    pub created_timestamp: NaiveDateTime,
+}
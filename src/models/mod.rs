@@ -1,7 +1,35 @@
+pub mod api_key;
+pub mod api_key_usage;
+pub mod benchmarking_profile;
 pub mod blacklisted_token;
 pub mod budget;
+pub mod budget_audit_log_entry;
+pub mod budget_comment;
+pub mod budget_resource;
 pub mod budget_share_event;
 pub mod category;
+pub mod category_allocation;
+pub mod closed_month;
+pub mod closed_month_total;
+pub mod cohort_category_stat;
 pub mod entry;
+pub mod entry_attachment;
+pub mod exchange_rate;
+pub mod import_batch;
+pub mod inbox_entry;
+pub mod pending_deletion;
+pub mod recurring_entry;
+pub mod reimbursement;
+pub mod reminder;
+pub mod session;
+pub mod shopping_list;
+pub mod shopping_list_item;
+pub mod spending_challenge;
+pub mod support_ticket;
+pub mod token_revocation;
 pub mod user;
+pub mod user_badge;
 pub mod user_budget;
+pub mod user_daily_action;
+pub mod user_device_token;
+pub mod user_notification;
@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::pending_deletions;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[primary_key(user_id)]
+#[table_name = "pending_deletions"]
+pub struct PendingDeletion {
+    pub user_id: uuid::Uuid,
+    pub delete_after: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "pending_deletions"]
+pub struct NewPendingDeletion {
+    pub user_id: uuid::Uuid,
+    pub delete_after: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,51 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::models::user::User;
+use crate::schema::recurring_entries;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "recurring_entries"]
+pub struct RecurringEntry {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub amount_cents: i64,
+    pub name: Option<String>,
+    pub category: Option<i16>,
+    pub note: Option<String>,
+
+    pub frequency: i16,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub next_occurrence_date: NaiveDate,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[table_name = "recurring_entries"]
+pub struct NewRecurringEntry<'a> {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub amount_cents: i64,
+    pub name: Option<&'a str>,
+    pub category: Option<i16>,
+    pub note: Option<&'a str>,
+
+    pub frequency: i16,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub next_occurrence_date: NaiveDate,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,32 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::reimbursements;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct Reimbursement {
+    pub entry_id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+
+    pub status: i16,
+    pub payment_entry_id: Option<uuid::Uuid>,
+
+    pub submitted_timestamp: Option<NaiveDateTime>,
+    pub paid_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "reimbursements"]
+pub struct NewReimbursement {
+    pub entry_id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+
+    pub status: i16,
+    pub payment_entry_id: Option<uuid::Uuid>,
+
+    pub submitted_timestamp: Option<NaiveDateTime>,
+    pub paid_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,39 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::reminders;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[table_name = "reminders"]
+pub struct Reminder {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub budget_id: Option<uuid::Uuid>,
+    pub entry_id: Option<uuid::Uuid>,
+
+    pub note: String,
+    pub remind_date: NaiveDate,
+    pub is_delivered: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Insertable)]
+#[table_name = "reminders"]
+pub struct NewReminder<'a> {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub budget_id: Option<uuid::Uuid>,
+    pub entry_id: Option<uuid::Uuid>,
+
+    pub note: &'a str,
+    pub remind_date: NaiveDate,
+    pub is_delivered: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::sessions;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[table_name = "sessions"]
+pub struct Session {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub version: i32,
+    pub expiration_timestamp: NaiveDateTime,
+    pub revoked_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+    pub is_password_reset: bool,
+    // As reported by the client when the user signed in
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    // When the session's refresh token was last exchanged
+    pub last_used_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "sessions"]
+pub struct NewSession<'a> {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub version: i32,
+    pub expiration_timestamp: NaiveDateTime,
+    pub revoked_timestamp: Option<NaiveDateTime>,
+    pub created_timestamp: NaiveDateTime,
+    pub is_password_reset: bool,
+    pub device_name: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+    pub ip_address: Option<&'a str>,
+    pub last_used_timestamp: NaiveDateTime,
+}
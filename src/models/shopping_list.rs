@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::schema::shopping_lists;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "shopping_lists"]
+pub struct ShoppingList {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub category: i16,
+
+    pub name: String,
+    pub is_completed: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "shopping_lists"]
+pub struct NewShoppingList<'a> {
+    pub id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub category: i16,
+
+    pub name: &'a str,
+    pub is_completed: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
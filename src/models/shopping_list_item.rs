@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::shopping_list::ShoppingList;
+use crate::schema::shopping_list_items;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(ShoppingList, foreign_key = "shopping_list_id")]
+#[table_name = "shopping_list_items"]
+pub struct ShoppingListItem {
+    pub id: uuid::Uuid,
+    pub shopping_list_id: uuid::Uuid,
+
+    pub name: String,
+    pub estimated_cost_cents: i64,
+    pub is_checked: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "shopping_list_items"]
+pub struct NewShoppingListItem<'a> {
+    pub id: uuid::Uuid,
+    pub shopping_list_id: uuid::Uuid,
+
+    pub name: &'a str,
+    pub estimated_cost_cents: i64,
+    pub is_checked: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,45 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::budget::Budget;
+use crate::models::user::User;
+use crate::schema::spending_challenges;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[belongs_to(Budget, foreign_key = "budget_id")]
+#[table_name = "spending_challenges"]
+pub struct SpendingChallenge {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub category_id: i16,
+
+    pub limit_cents: i64,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+
+    pub is_succeeded: Option<bool>,
+    pub notify_on_completion: bool,
+
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "spending_challenges"]
+pub struct NewSpendingChallenge {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub budget_id: uuid::Uuid,
+    pub category_id: i16,
+
+    pub limit_cents: i64,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+
+    pub is_succeeded: Option<bool>,
+    pub notify_on_completion: bool,
+
+    pub created_timestamp: NaiveDateTime,
+}
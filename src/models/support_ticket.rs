@@ -0,0 +1,35 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::support_tickets;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[table_name = "support_tickets"]
+pub struct SupportTicket {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub message: String,
+    pub context: String,
+    pub is_resolved: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "support_tickets"]
+pub struct NewSupportTicket<'a> {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub message: &'a str,
+    pub context: &'a str,
+    pub is_resolved: bool,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
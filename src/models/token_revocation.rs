@@ -0,0 +1,22 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::token_revocations;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[primary_key(user_id)]
+#[table_name = "token_revocations"]
+pub struct TokenRevocation {
+    pub user_id: uuid::Uuid,
+    pub revoked_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "token_revocations"]
+pub struct NewTokenRevocation {
+    pub user_id: uuid::Uuid,
+    pub revoked_timestamp: NaiveDateTime,
+}
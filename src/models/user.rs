@@ -19,11 +19,23 @@ pub struct User {
     pub last_name: String,
     pub date_of_birth: NaiveDate,
     pub currency: String,
+    pub fiscal_year_start_month: i16,
 
     pub modified_timestamp: NaiveDateTime,
     pub created_timestamp: NaiveDateTime,
 }
 
+impl User {
+    // Premium without an expiration date never lapses
+    pub fn has_premium(&self, today: NaiveDate) -> bool {
+        self.is_premium
+            && match self.premium_expiration {
+                Some(expiration) => expiration >= today,
+                None => true,
+            }
+    }
+}
+
 #[derive(Debug, Insertable)]
 #[table_name = "users"]
 pub struct NewUser<'a> {
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::user_badges;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[table_name = "user_badges"]
+pub struct UserBadge {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub badge: i16,
+    pub earned_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "user_badges"]
+pub struct NewUserBadge {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+    pub badge: i16,
+    pub earned_timestamp: NaiveDateTime,
+}
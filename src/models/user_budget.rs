@@ -16,6 +16,7 @@ pub struct UserBudget {
     pub created_timestamp: NaiveDateTime,
     pub user_id: uuid::Uuid,
     pub budget_id: uuid::Uuid,
+    pub role: i16,
 }
 
 #[derive(Debug, Insertable)]
@@ -24,4 +25,5 @@ pub struct NewUserBudget {
     pub created_timestamp: NaiveDateTime,
     pub user_id: uuid::Uuid,
     pub budget_id: uuid::Uuid,
+    pub role: i16,
 }
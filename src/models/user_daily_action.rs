@@ -0,0 +1,26 @@
+use chrono::NaiveDate;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::user_daily_actions;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[primary_key(user_id, action, action_date)]
+#[table_name = "user_daily_actions"]
+pub struct UserDailyAction {
+    pub user_id: uuid::Uuid,
+    pub action: i16,
+    pub action_date: NaiveDate,
+    pub action_count: i32,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "user_daily_actions"]
+pub struct NewUserDailyAction {
+    pub user_id: uuid::Uuid,
+    pub action: i16,
+    pub action_date: NaiveDate,
+    pub action_count: i32,
+}
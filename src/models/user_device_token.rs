@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::user_device_tokens;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Queryable)]
+pub struct UserDeviceToken {
+    pub token: String,
+    pub user_id: uuid::Uuid,
+    pub platform: i16,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "user_device_tokens"]
+pub struct NewUserDeviceToken<'a> {
+    pub token: &'a str,
+    pub user_id: uuid::Uuid,
+    pub platform: i16,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
@@ -0,0 +1,47 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+use crate::schema::user_notifications;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Associations, Identifiable, Queryable)]
+#[belongs_to(User, foreign_key = "user_id")]
+#[table_name = "user_notifications"]
+pub struct UserNotification {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub is_unread: bool,
+    pub is_pristine: bool,
+    pub is_deleted: bool,
+
+    pub notification_type: i16,
+    pub alt_title: String,
+    pub alt_message: String,
+
+    pub associated_data: Option<String>,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "user_notifications"]
+pub struct NewUserNotification<'a> {
+    pub id: uuid::Uuid,
+    pub user_id: uuid::Uuid,
+
+    pub is_unread: bool,
+    pub is_pristine: bool,
+    pub is_deleted: bool,
+
+    pub notification_type: i16,
+    pub alt_title: &'a str,
+    pub alt_message: &'a str,
+
+    pub associated_data: Option<&'a str>,
+
+    pub modified_timestamp: NaiveDateTime,
+    pub created_timestamp: NaiveDateTime,
+}
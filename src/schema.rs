@@ -1,3 +1,33 @@
+table! {
+    api_key_usage (api_key_id, usage_date) {
+        api_key_id -> Uuid,
+        usage_date -> Date,
+        request_count -> Int4,
+    }
+}
+
+table! {
+    api_keys (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        key_hash -> Varchar,
+        name -> Varchar,
+        is_revoked -> Bool,
+        last_used_timestamp -> Nullable<Timestamp>,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    benchmarking_profiles (user_id) {
+        user_id -> Uuid,
+        country_code -> Varchar,
+        household_size -> Int2,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
 table! {
     blacklisted_tokens (id) {
         id -> Int4,
@@ -7,6 +37,28 @@ table! {
     }
 }
 
+table! {
+    budget_audit_log (id) {
+        id -> Uuid,
+        budget_id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        action -> Int2,
+        before_value -> Nullable<Text>,
+        after_value -> Nullable<Text>,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    budget_category_totals (budget_id, category) {
+        budget_id -> Uuid,
+        category -> Int2,
+        spent_cents -> Int8,
+        entry_count -> Int8,
+        tax_cents -> Int8,
+    }
+}
+
 table! {
     budget_comment_reactions (id) {
         id -> Uuid,
@@ -31,6 +83,18 @@ table! {
     }
 }
 
+table! {
+    budget_resources (id) {
+        id -> Uuid,
+        budget_id -> Uuid,
+        user_id -> Uuid,
+        kind -> Int2,
+        title -> Varchar,
+        location -> Varchar,
+        created_timestamp -> Timestamp,
+    }
+}
+
 table! {
     budget_share_events (id) {
         id -> Uuid,
@@ -56,6 +120,9 @@ table! {
         latest_entry_time -> Timestamp,
         modified_timestamp -> Timestamp,
         created_timestamp -> Timestamp,
+        is_tracking_only -> Bool,
+        is_envelope -> Bool,
+        is_archived -> Bool,
     }
 }
 
@@ -70,6 +137,52 @@ table! {
         color -> Varchar,
         modified_timestamp -> Timestamp,
         created_timestamp -> Timestamp,
+        is_hard_capped -> Bool,
+    }
+}
+
+table! {
+    category_allocations (id) {
+        id -> Uuid,
+        budget_id -> Uuid,
+        user_id -> Uuid,
+        category -> Int2,
+        amount_cents -> Int8,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    closed_month_totals (budget_id, month, category) {
+        budget_id -> Uuid,
+        month -> Date,
+        category -> Int2,
+        spent_cents -> Int8,
+        entry_count -> Int8,
+    }
+}
+
+table! {
+    closed_months (budget_id, month) {
+        budget_id -> Uuid,
+        month -> Date,
+        closed_by_user_id -> Nullable<Uuid>,
+        closed_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    cohort_category_stats (id) {
+        id -> Int4,
+        country_code -> Varchar,
+        household_size -> Int2,
+        category_name -> Varchar,
+        period_start -> Date,
+        user_count -> Int4,
+        p25_cents -> Int8,
+        median_cents -> Int8,
+        p75_cents -> Int8,
+        computed_timestamp -> Timestamp,
     }
 }
 
@@ -86,6 +199,25 @@ table! {
         note -> Nullable<Text>,
         modified_timestamp -> Timestamp,
         created_timestamp -> Timestamp,
+        recurring_entry_id -> Nullable<Uuid>,
+        import_batch_id -> Nullable<Uuid>,
+        tax_cents -> Nullable<Int8>,
+        tip_cents -> Nullable<Int8>,
+        is_deductible -> Bool,
+    }
+}
+
+table! {
+    entry_attachments (id) {
+        id -> Uuid,
+        entry_id -> Uuid,
+        budget_id -> Uuid,
+        user_id -> Uuid,
+        file_name -> Varchar,
+        content_type -> Varchar,
+        size_bytes -> Int8,
+        storage_key -> Varchar,
+        created_timestamp -> Timestamp,
     }
 }
 
@@ -113,6 +245,39 @@ table! {
     }
 }
 
+table! {
+    exchange_rates (currency) {
+        currency -> Varchar,
+        base_currency -> Varchar,
+        rate -> Float8,
+        fetched_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    import_batches (id) {
+        id -> Uuid,
+        budget_id -> Uuid,
+        user_id -> Uuid,
+        source_name -> Varchar,
+        entry_count -> Int4,
+        is_rolled_back -> Bool,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    inbox_entries (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        amount_cents -> Int8,
+        date -> Date,
+        name -> Nullable<Varchar>,
+        note -> Nullable<Text>,
+        created_timestamp -> Timestamp,
+    }
+}
+
 table! {
     otp_attempts (user_id) {
         user_id -> Uuid,
@@ -127,12 +292,182 @@ table! {
     }
 }
 
+table! {
+    password_reset_attempts (user_id) {
+        user_id -> Uuid,
+        attempt_count -> Int2,
+    }
+}
+
+table! {
+    pending_deletions (user_id) {
+        user_id -> Uuid,
+        delete_after -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    pending_pushes (notification_id) {
+        notification_id -> Uuid,
+        created_timestamp -> Timestamp,
+        traceparent -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    recurring_entries (id) {
+        id -> Uuid,
+        budget_id -> Uuid,
+        user_id -> Uuid,
+        amount_cents -> Int8,
+        name -> Nullable<Varchar>,
+        category -> Nullable<Int2>,
+        note -> Nullable<Text>,
+        frequency -> Int2,
+        start_date -> Date,
+        end_date -> Nullable<Date>,
+        next_occurrence_date -> Date,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    reimbursements (entry_id) {
+        entry_id -> Uuid,
+        budget_id -> Uuid,
+        status -> Int2,
+        payment_entry_id -> Nullable<Uuid>,
+        submitted_timestamp -> Nullable<Timestamp>,
+        paid_timestamp -> Nullable<Timestamp>,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    reminders (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        budget_id -> Nullable<Uuid>,
+        entry_id -> Nullable<Uuid>,
+        note -> Varchar,
+        remind_date -> Date,
+        is_delivered -> Bool,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    sessions (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        version -> Int4,
+        expiration_timestamp -> Timestamp,
+        revoked_timestamp -> Nullable<Timestamp>,
+        created_timestamp -> Timestamp,
+        is_password_reset -> Bool,
+        device_name -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        ip_address -> Nullable<Varchar>,
+        last_used_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    shopping_list_items (id) {
+        id -> Uuid,
+        shopping_list_id -> Uuid,
+        name -> Varchar,
+        estimated_cost_cents -> Int8,
+        is_checked -> Bool,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    shopping_lists (id) {
+        id -> Uuid,
+        budget_id -> Uuid,
+        category -> Int2,
+        name -> Varchar,
+        is_completed -> Bool,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    spending_challenges (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        budget_id -> Uuid,
+        category_id -> Int2,
+        limit_cents -> Int8,
+        start_date -> Date,
+        end_date -> Date,
+        is_succeeded -> Nullable<Bool>,
+        notify_on_completion -> Bool,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    support_tickets (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        message -> Text,
+        context -> Text,
+        is_resolved -> Bool,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    token_revocations (user_id) {
+        user_id -> Uuid,
+        revoked_timestamp -> Timestamp,
+    }
+}
+
+table! {
+    user_badges (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        badge -> Int2,
+        earned_timestamp -> Timestamp,
+    }
+}
+
 table! {
     user_budgets (id) {
         id -> Int4,
         created_timestamp -> Timestamp,
         user_id -> Uuid,
         budget_id -> Uuid,
+        role -> Int2,
+    }
+}
+
+table! {
+    user_daily_actions (user_id, action, action_date) {
+        user_id -> Uuid,
+        action -> Int2,
+        action_date -> Date,
+        action_count -> Int4,
+    }
+}
+
+table! {
+    user_device_tokens (token) {
+        token -> Varchar,
+        user_id -> Uuid,
+        platform -> Int2,
+        modified_timestamp -> Timestamp,
+        created_timestamp -> Timestamp,
     }
 }
 
@@ -164,26 +499,58 @@ table! {
         last_name -> Varchar,
         date_of_birth -> Date,
         currency -> Varchar,
+        fiscal_year_start_month -> Int2,
         modified_timestamp -> Timestamp,
         created_timestamp -> Timestamp,
     }
 }
 
+joinable!(entry_attachments -> entries (entry_id));
 joinable!(entry_comments -> entries (entry_id));
+joinable!(reimbursements -> entries (entry_id));
 
 allow_tables_to_appear_in_same_query!(
+    api_key_usage,
+    api_keys,
+    benchmarking_profiles,
     blacklisted_tokens,
+    budget_audit_log,
+    budget_category_totals,
     budget_comment_reactions,
     budget_comments,
+    budget_resources,
     budget_share_events,
     budgets,
     categories,
+    category_allocations,
+    closed_month_totals,
+    closed_months,
+    cohort_category_stats,
     entries,
+    entry_attachments,
     entry_comment_reactions,
     entry_comments,
+    exchange_rates,
+    import_batches,
+    inbox_entries,
     otp_attempts,
     password_attempts,
+    password_reset_attempts,
+    pending_deletions,
+    pending_pushes,
+    recurring_entries,
+    reimbursements,
+    reminders,
+    sessions,
+    shopping_list_items,
+    shopping_lists,
+    spending_challenges,
+    support_tickets,
+    token_revocations,
+    user_badges,
     user_budgets,
+    user_daily_actions,
+    user_device_tokens,
     user_notifications,
     users,
 );
@@ -0,0 +1,21 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/admin")
+            .route(
+                "/log_levels",
+                web::get().to(handlers::admin::get_log_levels),
+            )
+            .route(
+                "/set_log_levels",
+                web::post().to(handlers::admin::set_log_levels),
+            )
+            .route(
+                "/reset_log_levels",
+                web::post().to(handlers::admin::reset_log_levels),
+            ),
+    );
+}
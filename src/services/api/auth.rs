@@ -14,6 +14,15 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 "/refresh_tokens",
                 web::post().to(handlers::auth::refresh_tokens),
             )
-            .route("/logout", web::post().to(handlers::auth::logout)),
+            .route("/logout", web::post().to(handlers::auth::logout))
+            .route(
+                "/forgot_password",
+                web::post().to(handlers::auth::forgot_password),
+            )
+            .route(
+                "/reset_password",
+                web::post().to(handlers::auth::reset_password),
+            )
+            .route("/introspect", web::post().to(handlers::auth::introspect)),
     );
 }
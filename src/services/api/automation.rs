@@ -0,0 +1,26 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/automation")
+            .route("/me", web::get().to(handlers::automation::me))
+            .route(
+                "/triggers/new_entry",
+                web::get().to(handlers::automation::new_entry_trigger),
+            )
+            .route(
+                "/triggers/budget_threshold",
+                web::get().to(handlers::automation::budget_threshold_trigger),
+            )
+            .route(
+                "/actions/add_entry",
+                web::post().to(handlers::automation::add_entry_action),
+            )
+            .route(
+                "/actions/add_inbox_entry",
+                web::post().to(handlers::automation::add_inbox_entry_action),
+            ),
+    );
+}
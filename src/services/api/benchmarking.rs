@@ -0,0 +1,17 @@
+use actix_web::web;
+
+use crate::handlers;
+use crate::middleware::concurrency_limit::ConcurrencyLimit;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/benchmarking")
+            .route("/opt_in", web::post().to(handlers::benchmarking::opt_in))
+            .route("/opt_out", web::post().to(handlers::benchmarking::opt_out))
+            .service(
+                web::resource("/compare")
+                    .wrap(ConcurrencyLimit::new("reports"))
+                    .route(web::get().to(handlers::benchmarking::compare)),
+            ),
+    );
+}
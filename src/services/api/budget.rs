@@ -1,6 +1,7 @@
 use actix_web::web;
 
 use crate::handlers;
+use crate::middleware::concurrency_limit::ConcurrencyLimit;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -11,8 +12,259 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 "/get_all_between_dates",
                 web::post().to(handlers::budget::get_all_between_dates),
             )
+            .route(
+                "/get_entries",
+                web::post().to(handlers::budget::get_entries),
+            )
+            .service(
+                web::resource("/export/entries")
+                    .wrap(ConcurrencyLimit::new("exports"))
+                    .route(web::post().to(handlers::budget::export_entries)),
+            )
+            .route(
+                "/export/categories",
+                web::post().to(handlers::budget::export_categories),
+            )
+            .route(
+                "/import/categories",
+                web::post().to(handlers::budget::import_categories),
+            )
             .route("/create", web::post().to(handlers::budget::create))
             .route("/edit", web::post().to(handlers::budget::edit))
-            .route("/add_entry", web::post().to(handlers::budget::add_entry)),
+            .route("/archive", web::post().to(handlers::budget::archive))
+            .route("/unarchive", web::post().to(handlers::budget::unarchive))
+            .route("/add_entry", web::post().to(handlers::budget::add_entry))
+            .route(
+                "/add_category",
+                web::post().to(handlers::budget::add_category),
+            )
+            .route(
+                "/edit_category",
+                web::post().to(handlers::budget::edit_category),
+            )
+            .route(
+                "/remove_category",
+                web::post().to(handlers::budget::remove_category),
+            )
+            .route(
+                "/set_category_hard_cap",
+                web::post().to(handlers::budget::set_category_hard_cap),
+            )
+            .route("/edit_entry", web::post().to(handlers::budget::edit_entry))
+            .route(
+                "/delete_entry",
+                web::post().to(handlers::budget::delete_entry),
+            )
+            .route("/trash", web::post().to(handlers::budget::get_trash))
+            .route(
+                "/restore_entry",
+                web::post().to(handlers::budget::restore_entry),
+            )
+            .route(
+                "/audit_log",
+                web::post().to(handlers::budget::get_audit_log),
+            )
+            .route(
+                "/close_month",
+                web::post().to(handlers::budget::close_month),
+            )
+            .route(
+                "/reopen_month",
+                web::post().to(handlers::budget::reopen_month),
+            )
+            .route(
+                "/closed_months",
+                web::post().to(handlers::budget::get_closed_months),
+            )
+            .route(
+                "/bulk_delete_entries",
+                web::post().to(handlers::budget::bulk_delete_entries),
+            )
+            .route(
+                "/add_recurring_entry",
+                web::post().to(handlers::budget::add_recurring_entry),
+            )
+            .route(
+                "/get_all_recurring_entries",
+                web::get().to(handlers::budget::get_all_recurring_entries),
+            )
+            .route(
+                "/edit_recurring_entry",
+                web::post().to(handlers::budget::edit_recurring_entry),
+            )
+            .route(
+                "/delete_recurring_entry",
+                web::post().to(handlers::budget::delete_recurring_entry),
+            )
+            .service(
+                web::resource("/simulate")
+                    .wrap(ConcurrencyLimit::new("reports"))
+                    .route(web::post().to(handlers::budget::simulate)),
+            )
+            .service(
+                web::resource("/summary")
+                    .wrap(ConcurrencyLimit::new("reports"))
+                    .route(web::post().to(handlers::budget::get_summary)),
+            )
+            .route(
+                "/envelope/allocate",
+                web::post().to(handlers::budget::allocate_funds),
+            )
+            .route(
+                "/envelope/summary",
+                web::post().to(handlers::budget::get_envelope_summary),
+            )
+            .service(
+                web::resource("/import")
+                    .wrap(ConcurrencyLimit::new("imports"))
+                    .route(web::post().to(handlers::budget::import_statement)),
+            )
+            .service(
+                web::resource("/import/entries")
+                    .wrap(ConcurrencyLimit::new("imports"))
+                    .route(web::post().to(handlers::budget::import_entries)),
+            )
+            .route(
+                "/import/batches",
+                web::post().to(handlers::budget::get_import_batches),
+            )
+            .route(
+                "/import/upload_token",
+                web::post().to(handlers::budget::get_import_upload_token),
+            )
+            .route(
+                "/import/rollback",
+                web::post().to(handlers::budget::rollback_import),
+            )
+            .route(
+                "/attachments",
+                web::post().to(handlers::budget::get_entry_attachments),
+            )
+            .route(
+                "/attachments/upload",
+                web::post().to(handlers::budget::upload_entry_attachment),
+            )
+            .route(
+                "/attachments/download",
+                web::post().to(handlers::budget::download_entry_attachment),
+            )
+            .route(
+                "/attachments/delete",
+                web::post().to(handlers::budget::delete_entry_attachment),
+            )
+            .route(
+                "/resources",
+                web::post().to(handlers::budget::get_resources),
+            )
+            .route(
+                "/resources/add",
+                web::post().to(handlers::budget::add_resource),
+            )
+            .route(
+                "/resources/remove",
+                web::post().to(handlers::budget::remove_resource),
+            )
+            .route(
+                "/resources/upload_token",
+                web::post().to(handlers::budget::get_resource_upload_token),
+            )
+            .route(
+                "/reimbursements",
+                web::post().to(handlers::budget::get_reimbursements),
+            )
+            .route(
+                "/reimbursements/mark",
+                web::post().to(handlers::budget::mark_reimbursable),
+            )
+            .route(
+                "/reimbursements/unmark",
+                web::post().to(handlers::budget::unmark_reimbursable),
+            )
+            .route(
+                "/reimbursements/submit",
+                web::post().to(handlers::budget::submit_reimbursement),
+            )
+            .route(
+                "/reimbursements/record_payment",
+                web::post().to(handlers::budget::record_reimbursement_payment),
+            )
+            .route(
+                "/comment/create",
+                web::post().to(handlers::budget::create_comment),
+            )
+            .route(
+                "/comment/edit",
+                web::post().to(handlers::budget::edit_comment),
+            )
+            .route(
+                "/comment/delete",
+                web::post().to(handlers::budget::delete_comment),
+            )
+            .route(
+                "/comment/list",
+                web::post().to(handlers::budget::get_comments),
+            )
+            .route(
+                "/shopping_list/create",
+                web::post().to(handlers::budget::create_shopping_list),
+            )
+            .route(
+                "/shopping_list/list",
+                web::post().to(handlers::budget::get_shopping_lists),
+            )
+            .route(
+                "/shopping_list/delete",
+                web::post().to(handlers::budget::delete_shopping_list),
+            )
+            .route(
+                "/shopping_list/add_item",
+                web::post().to(handlers::budget::add_shopping_list_item),
+            )
+            .route(
+                "/shopping_list/edit_item",
+                web::post().to(handlers::budget::edit_shopping_list_item),
+            )
+            .route(
+                "/shopping_list/delete_item",
+                web::post().to(handlers::budget::delete_shopping_list_item),
+            )
+            .route(
+                "/shopping_list/complete",
+                web::post().to(handlers::budget::complete_shopping_list),
+            )
+            .route("/invite", web::post().to(handlers::budget::invite_user))
+            .route(
+                "/change_member_role",
+                web::post().to(handlers::budget::change_member_role),
+            )
+            .route(
+                "/remove_member",
+                web::post().to(handlers::budget::remove_member),
+            )
+            .route("/leave", web::post().to(handlers::budget::leave))
+            .route(
+                "/retract_invitation",
+                web::post().to(handlers::budget::retract_invitation),
+            )
+            .route(
+                "/accept_invitation",
+                web::post().to(handlers::budget::accept_invitation),
+            )
+            .route(
+                "/decline_invitation",
+                web::post().to(handlers::budget::decline_invitation),
+            )
+            .route(
+                "/get_all_pending_invitations_for_user",
+                web::get().to(handlers::budget::get_all_pending_invitations_for_user),
+            )
+            .route(
+                "/get_all_pending_invitations_made_by_user",
+                web::get().to(handlers::budget::get_all_pending_invitations_made_by_user),
+            )
+            .route(
+                "/get_invitation",
+                web::post().to(handlers::budget::get_invitation),
+            ),
     );
 }
@@ -0,0 +1,23 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/engagement")
+            .route("/streak", web::get().to(handlers::engagement::get_streak))
+            .route("/badges", web::get().to(handlers::engagement::get_badges))
+            .route(
+                "/create_challenge",
+                web::post().to(handlers::engagement::create_challenge),
+            )
+            .route(
+                "/get_all_challenges",
+                web::get().to(handlers::engagement::get_all_challenges),
+            )
+            .route(
+                "/delete_challenge",
+                web::post().to(handlers::engagement::delete_challenge),
+            ),
+    );
+}
@@ -0,0 +1,14 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/inbox")
+            .route("/entries", web::get().to(handlers::inbox::get_entries))
+            .route("/count", web::get().to(handlers::inbox::count))
+            .route("/add_entries", web::post().to(handlers::inbox::add_entries))
+            .route("/assign", web::post().to(handlers::inbox::assign))
+            .route("/dismiss", web::post().to(handlers::inbox::dismiss)),
+    );
+}
@@ -0,0 +1,17 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/meta")
+            .route(
+                "/compatibility",
+                web::get().to(handlers::meta::compatibility),
+            )
+            .route(
+                "/remote_config",
+                web::get().to(handlers::meta::remote_config),
+            ),
+    );
+}
@@ -1,14 +1,43 @@
 use actix_web::web;
 
+use crate::handlers::error;
+use crate::middleware::client_version::ClientVersionCheck;
+
+mod admin;
 mod auth;
+mod automation;
+mod benchmarking;
 mod budget;
+mod engagement;
+mod inbox;
+mod meta;
+mod notification;
+mod public;
+mod reminder;
+mod subscription;
+mod support;
 mod user;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
+            .app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(error::path_error_handler))
+            .configure(admin::configure)
             .configure(auth::configure)
+            .configure(automation::configure)
+            .configure(benchmarking::configure)
             .configure(budget::configure)
-            .configure(user::configure),
+            .configure(engagement::configure)
+            .configure(inbox::configure)
+            .configure(meta::configure)
+            .configure(notification::configure)
+            .configure(public::configure)
+            .configure(reminder::configure)
+            .configure(subscription::configure)
+            .configure(support::configure)
+            .configure(user::configure)
+            .wrap(ClientVersionCheck),
     );
 }
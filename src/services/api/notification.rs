@@ -0,0 +1,23 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/notification")
+            .route("/list", web::get().to(handlers::notification::list))
+            .route(
+                "/unread_count",
+                web::get().to(handlers::notification::unread_count),
+            )
+            .route(
+                "/mark_read",
+                web::post().to(handlers::notification::mark_read),
+            )
+            .route(
+                "/mark_all_read",
+                web::post().to(handlers::notification::mark_all_read),
+            )
+            .route("/dismiss", web::post().to(handlers::notification::dismiss)),
+    );
+}
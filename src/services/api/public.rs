@@ -0,0 +1,18 @@
+use actix_web::web;
+
+use crate::handlers;
+use crate::middleware::concurrency_limit::ConcurrencyLimit;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/public")
+            .route("/budgets", web::get().to(handlers::public::budgets))
+            .route("/budget", web::get().to(handlers::public::budget))
+            .route("/entries", web::get().to(handlers::public::entries))
+            .service(
+                web::resource("/summary")
+                    .wrap(ConcurrencyLimit::new("reports"))
+                    .route(web::get().to(handlers::public::summary)),
+            ),
+    );
+}
@@ -0,0 +1,14 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/reminder")
+            .route("/list", web::get().to(handlers::reminder::list))
+            .route("/create", web::post().to(handlers::reminder::create))
+            .route("/edit", web::post().to(handlers::reminder::edit))
+            .route("/snooze", web::post().to(handlers::reminder::snooze))
+            .route("/delete", web::post().to(handlers::reminder::delete)),
+    );
+}
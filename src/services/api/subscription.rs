@@ -0,0 +1,10 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/subscriptions").route(
+        "/detected",
+        web::get().to(handlers::subscription::get_detected),
+    ));
+}
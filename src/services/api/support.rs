@@ -0,0 +1,9 @@
+use actix_web::web;
+
+use crate::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/support").route("/ticket", web::post().to(handlers::support::create_ticket)),
+    );
+}
@@ -1,6 +1,7 @@
 use actix_web::web;
 
 use crate::handlers;
+use crate::middleware::concurrency_limit::ConcurrencyLimit;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -11,6 +12,59 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route(
                 "/change_password",
                 web::post().to(handlers::user::change_password),
+            )
+            .route(
+                "/merge_account",
+                web::post().to(handlers::user::merge_account),
+            )
+            .route(
+                "/delete_account",
+                web::post().to(handlers::user::delete_account),
+            )
+            .route(
+                "/get_pending_deletion",
+                web::get().to(handlers::user::get_pending_deletion),
+            )
+            .route(
+                "/cancel_deletion",
+                web::post().to(handlers::user::cancel_deletion),
+            )
+            .route(
+                "/create_api_key",
+                web::post().to(handlers::user::create_api_key),
+            )
+            .route("/get_api_keys", web::get().to(handlers::user::get_api_keys))
+            .route(
+                "/revoke_api_key",
+                web::post().to(handlers::user::revoke_api_key),
+            )
+            .route(
+                "/get_api_usage",
+                web::get().to(handlers::user::get_api_usage),
+            )
+            .route("/sessions", web::get().to(handlers::user::get_sessions))
+            .service(
+                web::resource("/export/activity")
+                    .wrap(ConcurrencyLimit::new("exports"))
+                    .route(web::post().to(handlers::user::export_activity)),
+            )
+            .service(
+                web::resource("/tax_report")
+                    .wrap(ConcurrencyLimit::new("reports"))
+                    .route(web::post().to(handlers::user::get_tax_report)),
+            )
+            .service(
+                web::resource("/export/tax_report")
+                    .wrap(ConcurrencyLimit::new("exports"))
+                    .route(web::post().to(handlers::user::export_tax_report)),
+            )
+            .route(
+                "/sessions/revoke",
+                web::post().to(handlers::user::revoke_session),
+            )
+            .route(
+                "/register_device",
+                web::post().to(handlers::user::register_device),
             ),
     );
 }
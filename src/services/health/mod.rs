@@ -0,0 +1,11 @@
+use actix_web::web;
+
+use crate::handlers::health;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/health")
+            .route("/live", web::get().to(health::live))
+            .route("/ready", web::get().to(health::ready)),
+    );
+}
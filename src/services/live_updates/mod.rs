@@ -0,0 +1,7 @@
+use actix_web::web;
+
+use crate::handlers::live_updates;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/ws", web::get().to(live_updates::subscribe));
+}
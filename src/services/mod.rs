@@ -1,2 +1,4 @@
 pub mod api;
+pub mod health;
+pub mod live_updates;
 pub mod web;
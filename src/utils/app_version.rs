@@ -0,0 +1,50 @@
+// Helpers for the dotted version numbers mobile clients report in the App-Version header
+
+pub fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let parts = version
+        .trim()
+        .split('.')
+        .map(|p| p.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    if parts.is_empty() || parts.len() > 4 {
+        return None;
+    }
+
+    Some(parts)
+}
+
+// Compares dotted versions numerically, treating missing trailing parts as zero (1.2 == 1.2.0)
+pub fn is_version_at_least(version: &[u64], minimum: &[u64]) -> bool {
+    let len = std::cmp::max(version.len(), minimum.len());
+
+    for i in 0..len {
+        let v = version.get(i).copied().unwrap_or(0);
+        let m = minimum.get(i).copied().unwrap_or(0);
+
+        if v != m {
+            return v > m;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_compare_versions() {
+        assert_eq!(parse_version("1.2.3"), Some(vec![1, 2, 3]));
+        assert_eq!(parse_version(" 10.0 "), Some(vec![10, 0]));
+        assert_eq!(parse_version("1.2.beta"), None);
+        assert_eq!(parse_version(""), None);
+
+        assert!(is_version_at_least(&[1, 2, 0], &[1, 2]));
+        assert!(is_version_at_least(&[1, 10], &[1, 9, 9]));
+        assert!(is_version_at_least(&[2], &[1, 9, 9]));
+        assert!(!is_version_at_least(&[1, 1, 9], &[1, 2]));
+        assert!(!is_version_at_least(&[1, 2], &[1, 2, 1]));
+    }
+}
@@ -0,0 +1,186 @@
+use diesel::r2d2::Pool;
+use log::error;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::definitions::DbConnection;
+use crate::env;
+use crate::utils::db;
+
+lazy_static! {
+    pub static ref ATTEMPT_COUNTER: AttemptCounter =
+        AttemptCounter::new(env::CONF.connections.redis_uri.as_deref());
+}
+
+// A request shouldn't wait long on Redis when the database can count the attempt instead
+const REDIS_CONNECTION_TIMEOUT: Duration = Duration::from_millis(500);
+
+// The first attempt in a window starts the window's timer. Incrementing and setting the expiry
+// happen in one script so a key can't be left without an expiry (and lock the user out for good).
+const INCREMENT_SCRIPT: &str = r"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then
+    redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return count
+";
+
+const REFUND_SCRIPT: &str = r"
+local count = tonumber(redis.call('GET', KEYS[1]))
+if count and count > 0 then
+    redis.call('DECR', KEYS[1])
+end
+return 0
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttemptKind {
+    Otp,
+    Password,
+    PasswordReset,
+}
+
+impl AttemptKind {
+    fn redis_key(self, user_id: Uuid) -> String {
+        match self {
+            AttemptKind::Otp => format!("budgetapp:otp_attempts:{}", user_id),
+            AttemptKind::Password => format!("budgetapp:password_attempts:{}", user_id),
+            AttemptKind::PasswordReset => {
+                format!("budgetapp:password_reset_attempts:{}", user_id)
+            }
+        }
+    }
+
+    fn window_secs(self) -> u64 {
+        let window_mins = match self {
+            AttemptKind::Otp => env::CONF.security.otp_attempts_reset_mins,
+            AttemptKind::Password | AttemptKind::PasswordReset => {
+                env::CONF.security.password_attempts_reset_mins
+            }
+        };
+
+        u64::try_from(window_mins).unwrap_or_default() * 60
+    }
+}
+
+// Counts sign-in, OTP and password reset attempts in Redis when it's configured, so the counts are
+// shared by every replica and expire on their own. The database tables (reset by the scheduled
+// jobs) are used when Redis isn't configured or can't be reached.
+pub struct AttemptCounter {
+    redis: Option<Pool<redis::Client>>,
+}
+
+impl AttemptCounter {
+    pub fn new(redis_uri: Option<&str>) -> Self {
+        let redis = redis_uri.map(|uri| {
+            let client = redis::Client::open(uri).expect("Invalid redis_uri");
+
+            // Unchecked so the server can start (and fall back to the database) while Redis is down
+            Pool::builder()
+                .connection_timeout(REDIS_CONNECTION_TIMEOUT)
+                .build_unchecked(client)
+        });
+
+        Self { redis }
+    }
+
+    pub fn get_and_increment(
+        &self,
+        db_connection: &DbConnection,
+        kind: AttemptKind,
+        user_id: Uuid,
+    ) -> Result<i16, diesel::result::Error> {
+        if let Some(pool) = &self.redis {
+            let result = pool.get().map_err(|e| e.to_string()).and_then(|mut conn| {
+                redis::Script::new(INCREMENT_SCRIPT)
+                    .key(kind.redis_key(user_id))
+                    .arg(kind.window_secs())
+                    .invoke::<i64>(&mut *conn)
+                    .map_err(|e| e.to_string())
+            });
+
+            match result {
+                Ok(count) => return Ok(i16::try_from(count).unwrap_or(i16::MAX)),
+                Err(e) => error!("Failed to count attempt in Redis, using database: {}", e),
+            }
+        }
+
+        match kind {
+            AttemptKind::Otp => {
+                db::auth::get_and_increment_otp_verification_count(db_connection, user_id)
+            }
+            AttemptKind::Password => {
+                db::auth::get_and_increment_password_attempt_count(db_connection, user_id)
+            }
+            AttemptKind::PasswordReset => {
+                db::auth::get_and_increment_password_reset_attempt_count(db_connection, user_id)
+            }
+        }
+    }
+
+    // Only password attempts are refunded. See db::auth::refund_password_attempt.
+    pub fn refund_password_attempt(
+        &self,
+        db_connection: &DbConnection,
+        user_id: Uuid,
+    ) -> Result<(), diesel::result::Error> {
+        if let Some(pool) = &self.redis {
+            let result = pool.get().map_err(|e| e.to_string()).and_then(|mut conn| {
+                redis::Script::new(REFUND_SCRIPT)
+                    .key(AttemptKind::Password.redis_key(user_id))
+                    .invoke::<i64>(&mut *conn)
+                    .map_err(|e| e.to_string())
+            });
+
+            match result {
+                Ok(_) => return Ok(()),
+                Err(e) => error!("Failed to refund attempt in Redis, using database: {}", e),
+            }
+        }
+
+        db::auth::refund_password_attempt(db_connection, user_id).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::env;
+    use crate::utils::db::testing::create_test_user;
+
+    #[test]
+    fn test_falls_back_to_database() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user = create_test_user(&db_connection);
+
+        // Nothing listens on port 1, so every attempt goes to the database
+        let counter = AttemptCounter::new(Some("redis://127.0.0.1:1"));
+
+        for expected in 1..=3 {
+            let count = counter
+                .get_and_increment(&db_connection, AttemptKind::Password, user.id)
+                .unwrap();
+            assert_eq!(count, expected);
+        }
+
+        counter
+            .refund_password_attempt(&db_connection, user.id)
+            .unwrap();
+
+        let count = counter
+            .get_and_increment(&db_connection, AttemptKind::Password, user.id)
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let count = counter
+            .get_and_increment(&db_connection, AttemptKind::Otp, user.id)
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let count = counter
+            .get_and_increment(&db_connection, AttemptKind::PasswordReset, user.id)
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+}
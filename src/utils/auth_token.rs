@@ -10,8 +10,14 @@ use uuid::Uuid;
 use crate::definitions::*;
 use crate::env;
 use crate::models::blacklisted_token::{BlacklistedToken, NewBlacklistedToken};
+use crate::models::pending_deletion::PendingDeletion;
+use crate::models::session::Session;
+use crate::models::token_revocation::TokenRevocation;
 use crate::schema::blacklisted_tokens as blacklisted_token_fields;
 use crate::schema::blacklisted_tokens::dsl::blacklisted_tokens;
+use crate::schema::pending_deletions::dsl::pending_deletions;
+use crate::schema::sessions::dsl::sessions;
+use crate::schema::token_revocations::dsl::token_revocations;
 
 // TODO: This module needs to be refactored for clarity and performace
 
@@ -24,6 +30,7 @@ pub enum TokenError {
     TokenExpired,
     SystemResourceAccessFailure,
     WrongTokenType,
+    WrongScope,
 }
 
 impl std::error::Error for TokenError {}
@@ -33,16 +40,35 @@ impl fmt::Display for TokenError {
         match self {
             TokenError::DatabaseError(e) => write!(f, "DatabaseError: {}", e),
             TokenError::InvalidTokenType(e) => write!(f, "InvalidTokenType: {}", e),
-            _ => write!(f, "Error: {}", self),
+            TokenError::TokenInvalid => write!(f, "TokenInvalid"),
+            TokenError::TokenBlacklisted => write!(f, "TokenBlacklisted"),
+            TokenError::TokenExpired => write!(f, "TokenExpired"),
+            TokenError::SystemResourceAccessFailure => write!(f, "SystemResourceAccessFailure"),
+            TokenError::WrongTokenType => write!(f, "WrongTokenType"),
+            TokenError::WrongScope => write!(f, "WrongScope"),
         }
     }
 }
 
+impl From<diesel::result::Error> for TokenError {
+    fn from(e: diesel::result::Error) -> Self {
+        TokenError::DatabaseError(e)
+    }
+}
+
+impl From<TokenTypeError> for TokenError {
+    fn from(e: TokenTypeError) -> Self {
+        TokenError::InvalidTokenType(e)
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum TokenType {
     Access,
     Refresh,
     SignIn,
+    Upload,
+    PasswordReset,
 }
 
 #[derive(Debug)]
@@ -68,6 +94,8 @@ impl std::convert::TryFrom<u8> for TokenType {
             0 => Ok(TokenType::Access),
             1 => Ok(TokenType::Refresh),
             2 => Ok(TokenType::SignIn),
+            3 => Ok(TokenType::Upload),
+            4 => Ok(TokenType::PasswordReset),
             v => Err(TokenTypeError::NoMatchForValue(v)),
         }
     }
@@ -79,6 +107,8 @@ impl std::convert::From<TokenType> for u8 {
             TokenType::Access => 0,
             TokenType::Refresh => 1,
             TokenType::SignIn => 2,
+            TokenType::Upload => 3,
+            TokenType::PasswordReset => 4,
         }
     }
 }
@@ -86,19 +116,34 @@ impl std::convert::From<TokenType> for u8 {
 #[derive(Debug, Clone)]
 pub struct TokenParams<'a> {
     pub user_id: &'a Uuid,
-    pub user_email: &'a str,
-    pub user_currency: &'a str,
 }
 
+// Profile data such as email and currency is deliberately left out so tokens don't go stale
+// when the user changes it
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct TokenClaims {
-    pub exp: u64,    // Expiration in time since UNIX epoch
-    pub uid: Uuid,   // User ID
-    pub eml: String, // User email address
-    pub cur: String, // User currency
-    pub typ: u8,     // Token type (Access=0, Refresh=1, SignIn=2)
-    pub slt: u32,    // Random salt (makes it so two tokens generated in the same
-                     //              second are different--useful for testing)
+    pub exp: u64,  // Expiration in time since UNIX epoch
+    pub uid: Uuid, // User ID
+    pub typ: u8,   // Token type (Access=0, Refresh=1, SignIn=2, Upload=3, PasswordReset=4)
+    // ID of the key the token was signed with. Empty for tokens issued before keys had IDs; those
+    // tokens also embed the user's email and currency, which are ignored.
+    #[serde(default)]
+    pub kid: String,
+    // What the token may be used for. Only upload tokens carry a scope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scp: Option<String>,
+    // The session and session version the token was issued for. Only refresh and password reset
+    // tokens belong to a session; those issued before sessions existed have neither.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ver: Option<i32>,
+    // When the token was issued, in time since UNIX epoch. Only tokens that belong to a session
+    // carry it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    pub slt: u32, // Random salt (makes it so two tokens generated in the same
+                  //              second are different--useful for testing)
 }
 
 impl TokenClaims {
@@ -205,6 +250,16 @@ impl Token {
     fn is_signin_token(&self) -> bool {
         matches!(self.token_type, TokenType::SignIn)
     }
+
+    #[allow(dead_code)]
+    fn is_upload_token(&self) -> bool {
+        matches!(self.token_type, TokenType::Upload)
+    }
+
+    #[allow(dead_code)]
+    fn is_password_reset_token(&self) -> bool {
+        matches!(self.token_type, TokenType::PasswordReset)
+    }
 }
 
 impl fmt::Display for Token {
@@ -213,6 +268,23 @@ impl fmt::Display for Token {
     }
 }
 
+// Upload tokens are handed to clients for uploading straight to storage so an access token never
+// ends up in an upload URL. Each one is only good for a single kind of upload to a single place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UploadScope {
+    Import { budget_id: Uuid },
+    Resource { budget_id: Uuid },
+}
+
+impl fmt::Display for UploadScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadScope::Import { budget_id } => write!(f, "import:{}", budget_id),
+            UploadScope::Resource { budget_id } => write!(f, "resource:{}", budget_id),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TokenPair {
     pub access_token: Token,
@@ -225,8 +297,8 @@ pub fn generate_access_token(params: TokenParams) -> Result<Token, TokenError> {
 }
 
 #[inline]
-pub fn generate_refresh_token(params: TokenParams) -> Result<Token, TokenError> {
-    generate_token(params, TokenType::Refresh)
+pub fn generate_refresh_token(params: TokenParams, session: &Session) -> Result<Token, TokenError> {
+    generate_scoped_token(params, TokenType::Refresh, None, Some(session))
 }
 
 #[inline]
@@ -235,9 +307,25 @@ pub fn generate_signin_token(params: TokenParams) -> Result<Token, TokenError> {
 }
 
 #[inline]
-pub fn generate_token_pair(params: TokenParams) -> Result<TokenPair, TokenError> {
+pub fn generate_upload_token(params: TokenParams, scope: UploadScope) -> Result<Token, TokenError> {
+    generate_scoped_token(params, TokenType::Upload, Some(scope.to_string()), None)
+}
+
+#[inline]
+pub fn generate_password_reset_token(
+    params: TokenParams,
+    session: &Session,
+) -> Result<Token, TokenError> {
+    generate_scoped_token(params, TokenType::PasswordReset, None, Some(session))
+}
+
+#[inline]
+pub fn generate_token_pair(
+    params: TokenParams,
+    session: &Session,
+) -> Result<TokenPair, TokenError> {
     let access_token = generate_access_token(params.clone())?;
-    let refresh_token = generate_refresh_token(params)?;
+    let refresh_token = generate_refresh_token(params, session)?;
 
     Ok(TokenPair {
         access_token,
@@ -245,29 +333,56 @@ pub fn generate_token_pair(params: TokenParams) -> Result<TokenPair, TokenError>
     })
 }
 
+#[inline]
 fn generate_token(params: TokenParams, token_type: TokenType) -> Result<Token, TokenError> {
-    let lifetime_sec = match token_type {
-        TokenType::Access => env::CONF.lifetimes.access_token_lifetime_mins * 60,
-        TokenType::Refresh => env::CONF.lifetimes.refresh_token_lifetime_days * 24 * 60 * 60,
+    generate_scoped_token(params, token_type, None, None)
+}
+
+pub fn token_lifetime_secs(token_type: TokenType) -> u64 {
+    match token_type {
+        TokenType::Access => env::reloadable_conf().lifetimes.access_token_lifetime_mins * 60,
+        TokenType::Refresh => {
+            env::reloadable_conf().lifetimes.refresh_token_lifetime_days * 24 * 60 * 60
+        }
         // Because of how the one-time passcodes expire, a future passcode is sent to the user.
         // The verification endpoint checks the current code and the next (future) code, meaning
         // a user's code will be valid for a maximum of OTP_LIFETIME_SECS * 2.
-        TokenType::SignIn => env::CONF.lifetimes.otp_lifetime_mins * 60 * 2,
-    };
+        TokenType::SignIn => env::reloadable_conf().lifetimes.otp_lifetime_mins * 60 * 2,
+        TokenType::Upload => env::reloadable_conf().lifetimes.upload_token_lifetime_mins * 60,
+        TokenType::PasswordReset => {
+            env::reloadable_conf()
+                .lifetimes
+                .password_reset_token_lifetime_mins
+                * 60
+        }
+    }
+}
+
+fn generate_scoped_token(
+    params: TokenParams,
+    token_type: TokenType,
+    scope: Option<String>,
+    session: Option<&Session>,
+) -> Result<Token, TokenError> {
+    let lifetime_sec = token_lifetime_secs(token_type);
 
     let time_since_epoch = match SystemTime::now().duration_since(UNIX_EPOCH) {
         Ok(t) => t,
         Err(_) => return Err(TokenError::SystemResourceAccessFailure),
     };
 
-    let expiration = time_since_epoch.as_secs() + lifetime_sec;
+    let issued_at = time_since_epoch.as_secs();
+    let expiration = issued_at + lifetime_sec;
     let salt = rand::thread_rng().gen_range(1..u32::MAX);
 
     let claims = TokenClaims {
         exp: expiration,
         uid: *params.user_id,
-        eml: params.user_email.to_string(),
-        cur: params.user_currency.to_string(),
+        kid: env::CONF.keys.token_signing_key_id.clone(),
+        scp: scope,
+        sid: session.map(|s| s.id),
+        ver: session.map(|s| s.version),
+        iat: session.map(|_| issued_at),
         typ: token_type.into(),
         slt: salt,
     };
@@ -287,11 +402,31 @@ pub fn validate_refresh_token(
     token: &str,
     db_connection: &DbConnection,
 ) -> Result<TokenClaims, TokenError> {
-    if is_on_blacklist(token, db_connection)? {
+    let claims = validate_token(token, TokenType::Refresh)?;
+
+    if is_revoked(token, &claims, db_connection)?
+        || is_revoked_by_pending_deletion(&claims, db_connection)?
+        || is_revoked_by_password_reset(&claims, db_connection)?
+    {
+        return Err(TokenError::TokenBlacklisted);
+    }
+
+    Ok(claims)
+}
+
+// Reset tokens are single-use, so using one revokes its session
+#[inline]
+pub fn validate_password_reset_token(
+    token: &str,
+    db_connection: &DbConnection,
+) -> Result<TokenClaims, TokenError> {
+    let claims = validate_token(token, TokenType::PasswordReset)?;
+
+    if is_revoked(token, &claims, db_connection)? {
         return Err(TokenError::TokenBlacklisted);
     }
 
-    validate_token(token, TokenType::Refresh)
+    Ok(claims)
 }
 
 #[inline]
@@ -299,17 +434,63 @@ pub fn validate_signin_token(token: &str) -> Result<TokenClaims, TokenError> {
     validate_token(token, TokenType::SignIn)
 }
 
+// For services that receive uploads in-process. Remote storage checks tokens by introspection.
+#[allow(dead_code)]
+pub fn validate_upload_token(token: &str, scope: UploadScope) -> Result<TokenClaims, TokenError> {
+    let claims = validate_token(token, TokenType::Upload)?;
+
+    if claims.scp.as_deref() != Some(scope.to_string().as_str()) {
+        return Err(TokenError::WrongScope);
+    }
+
+    Ok(claims)
+}
+
+// Checks a token of any type the same way the endpoint it was issued for would
+pub fn validate_token_of_any_type(
+    token: &str,
+    db_connection: &DbConnection,
+) -> Result<(TokenClaims, TokenType), TokenError> {
+    let token_type = TokenType::try_from(TokenClaims::from_token_without_validation(token)?.typ)?;
+
+    let claims = match token_type {
+        TokenType::Access => validate_access_token(token)?,
+        TokenType::Refresh => validate_refresh_token(token, db_connection)?,
+        TokenType::SignIn => validate_signin_token(token)?,
+        // The service receiving the upload checks that the scope matches what is being uploaded
+        TokenType::Upload => validate_token(token, TokenType::Upload)?,
+        TokenType::PasswordReset => validate_password_reset_token(token, db_connection)?,
+    };
+
+    Ok((claims, token_type))
+}
+
+// Tokens without a key ID predate key rotation and were signed with the current key
+fn signing_key_for_id(key_id: &str) -> Option<&'static str> {
+    let keys = &env::CONF.keys;
+
+    if key_id == keys.token_signing_key_id || (key_id.is_empty() && keys.accept_legacy_tokens) {
+        return Some(&keys.token_signing_key);
+    }
+
+    keys.previous_token_signing_keys
+        .iter()
+        .find(|k| !key_id.is_empty() && k.id == key_id)
+        .map(|k| k.key.as_str())
+}
+
 fn validate_token(token: &str, token_type: TokenType) -> Result<TokenClaims, TokenError> {
-    let decoded_token = TokenClaims::from_token_with_validation(
-        token,
-        env::CONF.keys.token_signing_key.as_bytes(),
-    )?;
+    let key_id = TokenClaims::from_token_without_validation(token)?.kid;
 
-    let token_type_claim = match TokenType::try_from(decoded_token.typ) {
-        Ok(t) => t,
-        Err(e) => return Err(TokenError::InvalidTokenType(e)),
+    let key = match signing_key_for_id(&key_id) {
+        Some(k) => k,
+        None => return Err(TokenError::TokenInvalid),
     };
 
+    let decoded_token = TokenClaims::from_token_with_validation(token, key.as_bytes())?;
+
+    let token_type_claim = TokenType::try_from(decoded_token.typ)?;
+
     if std::mem::discriminant(&token_type_claim) != std::mem::discriminant(&token_type) {
         Err(TokenError::WrongTokenType)
     } else {
@@ -317,6 +498,8 @@ fn validate_token(token: &str, token_type: TokenType) -> Result<TokenClaims, Tok
     }
 }
 
+// Tokens issued before sessions existed are revoked by blacklisting the whole token. Nothing new
+// gets blacklisted once those tokens have expired.
 pub fn blacklist_token(
     token: &str,
     db_connection: &DbConnection,
@@ -335,13 +518,9 @@ pub fn blacklist_token(
         },
     };
 
-    match dsl::insert_into(blacklisted_tokens)
+    Ok(dsl::insert_into(blacklisted_tokens)
         .values(&blacklisted_token)
-        .get_result::<BlacklistedToken>(db_connection)
-    {
-        Ok(t) => Ok(t),
-        Err(e) => Err(TokenError::DatabaseError(e)),
-    }
+        .get_result::<BlacklistedToken>(db_connection)?)
 }
 
 pub fn is_on_blacklist(token: &str, db_connection: &DbConnection) -> Result<bool, TokenError> {
@@ -355,22 +534,113 @@ pub fn is_on_blacklist(token: &str, db_connection: &DbConnection) -> Result<bool
     }
 }
 
+fn is_revoked(
+    token: &str,
+    claims: &TokenClaims,
+    db_connection: &DbConnection,
+) -> Result<bool, TokenError> {
+    let session_id = match claims.sid {
+        Some(id) => id,
+        None => return is_on_blacklist(token, db_connection),
+    };
+
+    let session = match sessions.find(session_id).first::<Session>(db_connection) {
+        Ok(s) => s,
+        Err(diesel::result::Error::NotFound) => return Ok(true),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(session.user_id != claims.uid
+        || session.revoked_timestamp.is_some()
+        || Some(session.version) != claims.ver)
+}
+
+// Scheduling an account for deletion revokes every refresh token that was issued before the
+// deletion was requested
+fn is_revoked_by_pending_deletion(
+    claims: &TokenClaims,
+    db_connection: &DbConnection,
+) -> Result<bool, TokenError> {
+    let pending_deletion = match pending_deletions
+        .find(claims.uid)
+        .first::<PendingDeletion>(db_connection)
+    {
+        Ok(p) => p,
+        Err(diesel::result::Error::NotFound) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    let deletion_requested_at =
+        u64::try_from(pending_deletion.created_timestamp.timestamp()).unwrap_or(0);
+
+    Ok(refresh_token_issued_at(claims) <= deletion_requested_at)
+}
+
+// Resetting a password revokes every refresh token that was issued before the reset
+fn is_revoked_by_password_reset(
+    claims: &TokenClaims,
+    db_connection: &DbConnection,
+) -> Result<bool, TokenError> {
+    let token_revocation = match token_revocations
+        .find(claims.uid)
+        .first::<TokenRevocation>(db_connection)
+    {
+        Ok(r) => r,
+        Err(diesel::result::Error::NotFound) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    let revoked_at = u64::try_from(token_revocation.revoked_timestamp.timestamp()).unwrap_or(0);
+
+    Ok(refresh_token_issued_at(claims) <= revoked_at)
+}
+
+// Tokens issued before refresh tokens carried an issue time are treated as the oldest possible, so
+// any revocation covers them
+fn refresh_token_issued_at(claims: &TokenClaims) -> u64 {
+    claims.iat.unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     use chrono::NaiveDate;
 
+    use crate::models::pending_deletion::NewPendingDeletion;
     use crate::models::user::NewUser;
+    use crate::schema::pending_deletions as pending_deletion_fields;
     use crate::schema::users::dsl::users;
+    use crate::utils::db;
+
+    fn session_for(user_id: &Uuid) -> Session {
+        let current_time = chrono::Utc::now().naive_utc();
+
+        Session {
+            id: Uuid::new_v4(),
+            user_id: *user_id,
+            version: 0,
+            expiration_timestamp: current_time,
+            revoked_timestamp: None,
+            created_timestamp: current_time,
+            is_password_reset: false,
+            device_name: None,
+            user_agent: None,
+            ip_address: None,
+            last_used_timestamp: current_time,
+        }
+    }
 
     #[actix_rt::test]
     async fn test_create_token() {
         let claims = TokenClaims {
             exp: 123456789,
             uid: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
-            eml: format!("Testing_tokens@example.com"),
-            cur: String::from("USD"),
+            kid: env::CONF.keys.token_signing_key_id.clone(),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
             typ: u8::from(TokenType::Access),
             slt: 10000,
         };
@@ -378,8 +648,11 @@ mod tests {
         let claims_different = TokenClaims {
             exp: 123456788,
             uid: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
-            eml: format!("Testing_tokens@example.com"),
-            cur: String::from("USD"),
+            kid: env::CONF.keys.token_signing_key_id.clone(),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
             typ: u8::from(TokenType::Access),
             slt: 10000,
         };
@@ -387,7 +660,7 @@ mod tests {
         let token = claims.create_token(env::CONF.keys.token_signing_key.as_bytes());
         let token_different =
             claims_different.create_token(env::CONF.keys.token_signing_key.as_bytes());
-        let expected_token = String::from("eyJleHAiOjEyMzQ1Njc4OSwidWlkIjoiNjdlNTUwNDQtMTBiMS00MjZmLTkyNDctYmI2ODBlNWZlMGM4IiwiZW1sIjoiVGVzdGluZ190b2tlbnNAZXhhbXBsZS5jb20iLCJjdXIiOiJVU0QiLCJ0eXAiOjAsInNsdCI6MTAwMDB9fDY0OWYyNDBkNzZiYzRhOThhMTYzMzc5Y2VhZTdhZDBkNzAwOTgwNWMzYzVlMDlmMzkyMjRjNmM5NGEzZGVlN2Q");
+        let expected_token = String::from("eyJleHAiOjEyMzQ1Njc4OSwidWlkIjoiNjdlNTUwNDQtMTBiMS00MjZmLTkyNDctYmI2ODBlNWZlMGM4IiwidHlwIjowLCJraWQiOiIyIiwic2x0IjoxMDAwMH18OTQ1ZWQ2Nzk2YjNlMTUzMDBmZjkwOGU5OGFiYTQ5ZjkyNjBhYzI1OGRlNDBkN2U0Y2E0NzU0MTQ2ODkxYTVjZA");
 
         assert_eq!(token, expected_token);
         assert_ne!(token, token_different);
@@ -406,8 +679,7 @@ mod tests {
 
         assert_eq!(decoded_claims.exp, claims.exp);
         assert_eq!(decoded_claims.uid, claims.uid);
-        assert_eq!(decoded_claims.eml, claims.eml);
-        assert_eq!(decoded_claims.cur, claims.cur);
+        assert_eq!(decoded_claims.kid, claims.kid);
         assert_eq!(decoded_claims.typ, claims.typ);
         assert_eq!(decoded_claims.slt, claims.slt);
     }
@@ -417,8 +689,11 @@ mod tests {
         let claims = TokenClaims {
             exp: u64::MAX,
             uid: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
-            eml: format!("Testing_tokens@example.com"),
-            cur: String::from("USD"),
+            kid: env::CONF.keys.token_signing_key_id.clone(),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
             typ: u8::from(TokenType::Access),
             slt: 10000,
         };
@@ -435,8 +710,7 @@ mod tests {
 
         assert_eq!(decoded_claims.exp, claims.exp);
         assert_eq!(decoded_claims.uid, claims.uid);
-        assert_eq!(decoded_claims.eml, claims.eml);
-        assert_eq!(decoded_claims.cur, claims.cur);
+        assert_eq!(decoded_claims.kid, claims.kid);
         assert_eq!(decoded_claims.typ, claims.typ);
         assert_eq!(decoded_claims.slt, claims.slt);
     }
@@ -446,8 +720,11 @@ mod tests {
         let claims = TokenClaims {
             exp: u64::MAX,
             uid: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
-            eml: format!("Testing_tokens@example.com"),
-            cur: String::from("USD"),
+            kid: env::CONF.keys.token_signing_key_id.clone(),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
             typ: u8::from(TokenType::Access),
             slt: 10000,
         };
@@ -466,13 +743,39 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_token_error_display() {
+        let errors = [
+            TokenError::DatabaseError(diesel::result::Error::NotFound),
+            TokenError::from(TokenTypeError::NoMatchForValue(9)),
+            TokenError::TokenInvalid,
+            TokenError::TokenBlacklisted,
+            TokenError::TokenExpired,
+            TokenError::SystemResourceAccessFailure,
+            TokenError::WrongTokenType,
+            TokenError::WrongScope,
+        ];
+
+        let messages = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(messages[1], "InvalidTokenType: NoMatchForValue: 9");
+        assert_eq!(messages[4], "TokenExpired");
+
+        for (i, message) in messages.iter().enumerate() {
+            assert!(!messages[..i].contains(message));
+        }
+    }
+
     #[actix_rt::test]
     async fn test_token_validation_fails_when_expired() {
         let claims = TokenClaims {
             exp: 1657076995,
             uid: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
-            eml: format!("Testing_tokens@example.com"),
-            cur: String::from("USD"),
+            kid: env::CONF.keys.token_signing_key_id.clone(),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
             typ: u8::from(TokenType::Access),
             slt: 10000,
         };
@@ -496,8 +799,11 @@ mod tests {
         let claims = TokenClaims {
             exp: 1657076995,
             uid: uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
-            eml: format!("Testing_tokens@example.com"),
-            cur: String::from("USD"),
+            kid: env::CONF.keys.token_signing_key_id.clone(),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
             typ: u8::from(TokenType::Access),
             slt: 10000,
         };
@@ -507,8 +813,7 @@ mod tests {
 
         assert_eq!(decoded_claims.exp, claims.exp);
         assert_eq!(decoded_claims.uid, claims.uid);
-        assert_eq!(decoded_claims.eml, claims.eml);
-        assert_eq!(decoded_claims.cur, claims.cur);
+        assert_eq!(decoded_claims.kid, claims.kid);
         assert_eq!(decoded_claims.typ, claims.typ);
         assert_eq!(decoded_claims.slt, claims.slt);
     }
@@ -539,8 +844,6 @@ mod tests {
 
         let token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -554,8 +857,7 @@ mod tests {
 
         assert_eq!(decoded_token.typ, u8::from(TokenType::Access));
         assert_eq!(decoded_token.uid, user_id);
-        assert_eq!(decoded_token.eml, new_user.email);
-        assert_eq!(decoded_token.cur, new_user.currency);
+        assert_eq!(decoded_token.kid, env::CONF.keys.token_signing_key_id);
         assert!(
             decoded_token.exp
                 > SystemTime::now()
@@ -589,11 +891,12 @@ mod tests {
             created_timestamp: timestamp,
         };
 
-        let token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
 
         assert!(!token.token.contains(&user_id.to_string()));
@@ -606,8 +909,7 @@ mod tests {
 
         assert_eq!(decoded_token.typ, u8::from(TokenType::Refresh));
         assert_eq!(decoded_token.uid, user_id);
-        assert_eq!(decoded_token.eml, new_user.email);
-        assert_eq!(decoded_token.cur, new_user.currency);
+        assert_eq!(decoded_token.kid, env::CONF.keys.token_signing_key_id);
         assert!(
             decoded_token.exp
                 > SystemTime::now()
@@ -643,8 +945,6 @@ mod tests {
 
         let token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -658,8 +958,7 @@ mod tests {
 
         assert_eq!(decoded_token.typ, u8::from(TokenType::SignIn));
         assert_eq!(decoded_token.uid, user_id);
-        assert_eq!(decoded_token.eml, new_user.email);
-        assert_eq!(decoded_token.cur, new_user.currency);
+        assert_eq!(decoded_token.kid, env::CONF.keys.token_signing_key_id);
         assert!(
             decoded_token.exp
                 > SystemTime::now()
@@ -693,11 +992,12 @@ mod tests {
             created_timestamp: timestamp,
         };
 
-        let token = generate_token_pair(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let token = generate_token_pair(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
 
         assert!(!token.access_token.token.contains(&user_id.to_string()));
@@ -711,8 +1011,10 @@ mod tests {
 
         assert_eq!(decoded_access_token.typ, u8::from(TokenType::Access));
         assert_eq!(decoded_access_token.uid, user_id);
-        assert_eq!(decoded_access_token.eml, new_user.email);
-        assert_eq!(decoded_access_token.cur, new_user.currency);
+        assert_eq!(
+            decoded_access_token.kid,
+            env::CONF.keys.token_signing_key_id
+        );
         assert!(
             decoded_access_token.exp
                 > SystemTime::now()
@@ -729,8 +1031,10 @@ mod tests {
 
         assert_eq!(decoded_refresh_token.typ, u8::from(TokenType::Refresh));
         assert_eq!(decoded_refresh_token.uid, user_id);
-        assert_eq!(decoded_refresh_token.eml, new_user.email);
-        assert_eq!(decoded_refresh_token.cur, new_user.currency);
+        assert_eq!(
+            decoded_refresh_token.kid,
+            env::CONF.keys.token_signing_key_id
+        );
         assert!(
             decoded_refresh_token.exp
                 > SystemTime::now()
@@ -767,8 +1071,6 @@ mod tests {
         let access_token = generate_token(
             TokenParams {
                 user_id: &new_user.id,
-                user_email: new_user.email,
-                user_currency: new_user.currency,
             },
             TokenType::Access,
         )
@@ -776,8 +1078,6 @@ mod tests {
         let refresh_token = generate_token(
             TokenParams {
                 user_id: &new_user.id,
-                user_email: new_user.email,
-                user_currency: new_user.currency,
             },
             TokenType::Refresh,
         )
@@ -785,8 +1085,6 @@ mod tests {
         let signin_token = generate_token(
             TokenParams {
                 user_id: &new_user.id,
-                user_email: new_user.email,
-                user_currency: new_user.currency,
             },
             TokenType::SignIn,
         )
@@ -812,8 +1110,10 @@ mod tests {
 
         assert_eq!(decoded_access_token.typ, u8::from(TokenType::Access));
         assert_eq!(decoded_access_token.uid, user_id);
-        assert_eq!(decoded_access_token.eml, new_user.email);
-        assert_eq!(decoded_access_token.cur, new_user.currency);
+        assert_eq!(
+            decoded_access_token.kid,
+            env::CONF.keys.token_signing_key_id
+        );
         assert!(
             decoded_access_token.exp
                 > SystemTime::now()
@@ -824,8 +1124,10 @@ mod tests {
 
         assert_eq!(decoded_refresh_token.typ, u8::from(TokenType::Refresh));
         assert_eq!(decoded_refresh_token.uid, user_id);
-        assert_eq!(decoded_refresh_token.eml, new_user.email);
-        assert_eq!(decoded_refresh_token.cur, new_user.currency);
+        assert_eq!(
+            decoded_refresh_token.kid,
+            env::CONF.keys.token_signing_key_id
+        );
         assert!(
             decoded_refresh_token.exp
                 > SystemTime::now()
@@ -836,8 +1138,10 @@ mod tests {
 
         assert_eq!(decoded_signin_token.typ, u8::from(TokenType::SignIn));
         assert_eq!(decoded_signin_token.uid, user_id);
-        assert_eq!(decoded_signin_token.eml, new_user.email);
-        assert_eq!(decoded_signin_token.cur, new_user.currency);
+        assert_eq!(
+            decoded_signin_token.kid,
+            env::CONF.keys.token_signing_key_id
+        );
         assert!(
             decoded_signin_token.exp
                 > SystemTime::now()
@@ -847,6 +1151,75 @@ mod tests {
         );
     }
 
+    #[actix_rt::test]
+    async fn test_validate_token_key_ids() {
+        let keys = &env::CONF.keys;
+        let exp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60;
+        let user_id = Uuid::new_v4();
+
+        // Signed the same way `create_token` signs, but with the claims tokens used to carry
+        let legacy_claims_json = serde_json::json!({
+            "exp": exp,
+            "uid": user_id,
+            "eml": "legacy_token@example.com",
+            "cur": "USD",
+            "typ": u8::from(TokenType::Access),
+            "slt": 10000,
+        })
+        .to_string();
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(keys.token_signing_key.as_bytes()).unwrap();
+        mac.update(legacy_claims_json.as_bytes());
+        let hash = hex::encode(mac.finalize().into_bytes());
+
+        let legacy_token = base64::encode_config(
+            format!("{legacy_claims_json}|{hash}"),
+            base64::URL_SAFE_NO_PAD,
+        );
+
+        let decoded_legacy_token = validate_access_token(&legacy_token).unwrap();
+        assert_eq!(decoded_legacy_token.uid, user_id);
+        assert!(decoded_legacy_token.kid.is_empty());
+
+        let previous_key = &keys.previous_token_signing_keys[0];
+        let claims_with_kid = |kid: &str| TokenClaims {
+            exp,
+            uid: user_id,
+            typ: u8::from(TokenType::Access),
+            kid: String::from(kid),
+            scp: None,
+            sid: None,
+            ver: None,
+            iat: None,
+            slt: 10000,
+        };
+
+        let previous_key_token =
+            claims_with_kid(&previous_key.id).create_token(previous_key.key.as_bytes());
+        assert_eq!(
+            validate_access_token(&previous_key_token).unwrap().kid,
+            previous_key.id
+        );
+
+        let mismatched_key_token =
+            claims_with_kid(&previous_key.id).create_token(keys.token_signing_key.as_bytes());
+        assert!(matches!(
+            validate_access_token(&mismatched_key_token),
+            Err(TokenError::TokenInvalid)
+        ));
+
+        let unknown_key_token =
+            claims_with_kid("unknown").create_token(keys.token_signing_key.as_bytes());
+        assert!(matches!(
+            validate_access_token(&unknown_key_token),
+            Err(TokenError::TokenInvalid)
+        ));
+    }
+
     #[actix_rt::test]
     async fn test_validate_access_token() {
         let user_id = Uuid::new_v4();
@@ -873,20 +1246,17 @@ mod tests {
 
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -925,22 +1295,32 @@ mod tests {
             created_timestamp: timestamp,
         };
 
+        dsl::insert_into(users)
+            .values(&new_user)
+            .execute(&db_connection)
+            .unwrap();
+
+        let session = db::session::create_session(
+            &db_connection,
+            user_id,
+            3600,
+            &db::session::SessionDevice::default(),
+        )
+        .unwrap();
+
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session,
+        )
         .unwrap();
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -952,6 +1332,90 @@ mod tests {
         );
         assert!(validate_refresh_token(&access_token.token, &db_connection).is_err());
         assert!(validate_refresh_token(&signin_token.token, &db_connection).is_err());
+
+        // A token for a session that was never started
+        let unknown_session_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
+        .unwrap();
+        assert!(matches!(
+            validate_refresh_token(&unknown_session_token.token, &db_connection),
+            Err(TokenError::TokenBlacklisted)
+        ));
+
+        // Rotating the session leaves only the newest token valid
+        let rotated_session = db::session::rotate_session(&db_connection, session.id, 0, 3600)
+            .unwrap()
+            .unwrap();
+        let rotated_refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &rotated_session,
+        )
+        .unwrap();
+        assert!(matches!(
+            validate_refresh_token(&refresh_token.token, &db_connection),
+            Err(TokenError::TokenBlacklisted)
+        ));
+        assert!(validate_refresh_token(&rotated_refresh_token.token, &db_connection).is_ok());
+
+        db::session::revoke_session(&db_connection, session.id).unwrap();
+        assert!(matches!(
+            validate_refresh_token(&rotated_refresh_token.token, &db_connection),
+            Err(TokenError::TokenBlacklisted)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_refresh_tokens_without_session_use_blacklist() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let user_id = Uuid::new_v4();
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let timestamp = chrono::Utc::now().naive_utc();
+        let new_user = NewUser {
+            id: user_id,
+            is_active: true,
+            is_premium: false,
+            premium_expiration: Option::None,
+            email: &format!("test_user{}@test.com", &user_number),
+            password_hash: "test_hash",
+            first_name: &format!("Test-{}", &user_number),
+            last_name: &format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(2000, 1, 1),
+            currency: "USD",
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+        };
+
+        dsl::insert_into(users)
+            .values(&new_user)
+            .execute(&db_connection)
+            .unwrap();
+
+        // Issued the way refresh tokens were before sessions existed
+        let legacy_refresh_token =
+            generate_token(TokenParams { user_id: &user_id }, TokenType::Refresh).unwrap();
+        assert!(
+            TokenClaims::from_token_without_validation(&legacy_refresh_token.token)
+                .unwrap()
+                .sid
+                .is_none()
+        );
+
+        assert!(validate_refresh_token(&legacy_refresh_token.token, &db_connection).is_ok());
+
+        blacklist_token(&legacy_refresh_token.token, &db_connection).unwrap();
+
+        assert!(matches!(
+            validate_refresh_token(&legacy_refresh_token.token, &db_connection),
+            Err(TokenError::TokenBlacklisted)
+        ));
     }
 
     #[actix_rt::test]
@@ -980,20 +1444,17 @@ mod tests {
 
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -1031,20 +1492,17 @@ mod tests {
 
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -1094,26 +1552,92 @@ mod tests {
 
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
+        let upload_token = generate_upload_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            UploadScope::Import {
+                budget_id: Uuid::new_v4(),
+            },
+        )
+        .unwrap();
+
         assert!(validate_token(&access_token.token, TokenType::SignIn).is_err());
         assert!(validate_token(&refresh_token.token, TokenType::Access).is_err());
         assert!(validate_token(&signin_token.token, TokenType::Refresh).is_err());
+        assert!(validate_token(&access_token.token, TokenType::Upload).is_err());
+        assert!(validate_token(&signin_token.token, TokenType::PasswordReset).is_err());
+
+        let password_reset_token = generate_password_reset_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
+        .unwrap();
+
+        for token_type in [TokenType::Access, TokenType::Refresh, TokenType::SignIn] {
+            assert!(validate_token(&upload_token.token, token_type).is_err());
+            assert!(validate_token(&password_reset_token.token, token_type).is_err());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_validate_upload_token() {
+        let user_id = Uuid::new_v4();
+        let scope = UploadScope::Import {
+            budget_id: Uuid::new_v4(),
+        };
+
+        let upload_token = generate_upload_token(TokenParams { user_id: &user_id }, scope).unwrap();
+        let access_token = generate_access_token(TokenParams { user_id: &user_id }).unwrap();
+
+        let claims = validate_upload_token(&upload_token.token, scope).unwrap();
+        assert_eq!(claims.uid, user_id);
+        assert_eq!(claims.scp, Some(scope.to_string()));
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(
+            claims.exp
+                <= current_time + env::reloadable_conf().lifetimes.upload_token_lifetime_mins * 60
+        );
+
+        assert!(matches!(
+            validate_upload_token(
+                &upload_token.token,
+                UploadScope::Import {
+                    budget_id: Uuid::new_v4()
+                }
+            ),
+            Err(TokenError::WrongScope)
+        ));
+        assert!(matches!(
+            validate_upload_token(&access_token.token, scope),
+            Err(TokenError::WrongTokenType)
+        ));
+
+        // Other tokens don't carry a scope at all
+        assert_eq!(
+            validate_access_token(&access_token.token).unwrap().scp,
+            None
+        );
     }
 
     #[actix_rt::test]
@@ -1142,20 +1666,17 @@ mod tests {
 
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -1216,11 +1737,12 @@ mod tests {
             .execute(&db_connection)
             .unwrap();
 
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
 
         let blacklist_token = blacklist_token(&refresh_token.token, &db_connection).unwrap();
@@ -1272,11 +1794,12 @@ mod tests {
             .execute(&db_connection)
             .unwrap();
 
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
 
         assert!(!is_on_blacklist(&refresh_token.token, &db_connection).unwrap());
@@ -1286,6 +1809,94 @@ mod tests {
         assert!(is_on_blacklist(&refresh_token.token, &db_connection).unwrap());
     }
 
+    #[actix_rt::test]
+    async fn test_pending_deletion_revokes_earlier_refresh_tokens() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let user_id = Uuid::new_v4();
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let timestamp = chrono::Utc::now().naive_utc();
+        let new_user = NewUser {
+            id: user_id,
+            is_active: true,
+            is_premium: false,
+            premium_expiration: Option::None,
+            email: &format!("test_user{}@test.com", &user_number),
+            password_hash: "test_hash",
+            first_name: &format!("Test-{}", &user_number),
+            last_name: &format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(2000, 1, 1),
+            currency: "USD",
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+        };
+
+        dsl::insert_into(users)
+            .values(&new_user)
+            .execute(&db_connection)
+            .unwrap();
+
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &db::session::create_session(
+                &db_connection,
+                user_id,
+                3600,
+                &db::session::SessionDevice::default(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert!(validate_refresh_token(&refresh_token.token, &db_connection).is_ok());
+
+        let pending_deletion = NewPendingDeletion {
+            user_id,
+            delete_after: timestamp + chrono::Duration::days(14),
+            created_timestamp: chrono::Utc::now().naive_utc(),
+        };
+
+        dsl::insert_into(pending_deletions)
+            .values(&pending_deletion)
+            .execute(&db_connection)
+            .unwrap();
+
+        assert!(matches!(
+            validate_refresh_token(&refresh_token.token, &db_connection),
+            Err(TokenError::TokenBlacklisted)
+        ));
+
+        // Tokens issued after the deletion was requested (e.g. to cancel it) remain valid
+        dsl::update(pending_deletions.find(user_id))
+            .set(
+                pending_deletion_fields::created_timestamp
+                    .eq(timestamp - chrono::Duration::hours(1)),
+            )
+            .execute(&db_connection)
+            .unwrap();
+
+        assert!(validate_refresh_token(&refresh_token.token, &db_connection).is_ok());
+
+        // The issue time is read from the token rather than worked out from the configured
+        // lifetime, which may have changed since the token was issued
+        let claims = TokenClaims::from_token_without_validation(&refresh_token.token).unwrap();
+        assert!(claims.iat.unwrap() >= u64::try_from(timestamp.timestamp()).unwrap());
+
+        let token_without_issue_time = TokenClaims {
+            iat: None,
+            ..claims
+        }
+        .create_token(env::CONF.keys.token_signing_key.as_bytes());
+
+        assert!(matches!(
+            validate_refresh_token(&token_without_issue_time, &db_connection),
+            Err(TokenError::TokenBlacklisted)
+        ));
+    }
+
     #[actix_rt::test]
     async fn test_is_access_token() {
         let user_id = Uuid::new_v4();
@@ -1312,8 +1923,6 @@ mod tests {
 
         let access_token = generate_access_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
@@ -1346,11 +1955,12 @@ mod tests {
             created_timestamp: timestamp,
         };
 
-        let refresh_token = generate_refresh_token(TokenParams {
-            user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
-        })
+        let refresh_token = generate_refresh_token(
+            TokenParams {
+                user_id: &new_user.id,
+            },
+            &session_for(&new_user.id),
+        )
         .unwrap();
 
         assert!(refresh_token.is_refresh_token());
@@ -1384,8 +1994,6 @@ mod tests {
 
         let signin_token = generate_signin_token(TokenParams {
             user_id: &new_user.id,
-            user_email: new_user.email,
-            user_currency: new_user.currency,
         })
         .unwrap();
 
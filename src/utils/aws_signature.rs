@@ -0,0 +1,75 @@
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// Signs requests to AWS and services compatible with it using Signature Version 4. The host, the
+// payload hash and the date are always signed, along with any other headers the caller passes.
+// Every signed header must be sent with the request.
+pub struct AwsSigner {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub region: String,
+    pub service: &'static str,
+}
+
+impl AwsSigner {
+    // Header names must be lowercase
+    pub fn authorization(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        payload_hash: &str,
+        timestamp: NaiveDateTime,
+    ) -> String {
+        let amz_date = timestamp.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = timestamp.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+
+        let mut signed_headers = headers.iter().copied().collect::<BTreeMap<_, _>>();
+        signed_headers.insert("host", host);
+        signed_headers.insert("x-amz-content-sha256", payload_hash);
+        signed_headers.insert("x-amz-date", &amz_date);
+
+        let canonical_headers = signed_headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect::<String>();
+        let signed_header_names = signed_headers.keys().copied().collect::<Vec<_>>().join(";");
+
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, path, canonical_headers, signed_header_names, payload_hash,
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let signing_key = [self.region.as_str(), self.service, "aws4_request"]
+            .iter()
+            .fold(
+                hmac_sha256(
+                    format!("AWS4{}", self.secret_access_key).as_bytes(),
+                    date.as_bytes(),
+                ),
+                |key, part| hmac_sha256(&key, part.as_bytes()),
+            );
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_header_names, signature,
+        )
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("Failed to generate hash from key");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,74 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::utils::validators::Validity;
+
+pub const MAX_HOUSEHOLD_SIZE: i16 = 20;
+
+pub fn validate_country_code(country_code: &str) -> Validity {
+    if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_uppercase()) {
+        return Validity::Invalid("Country code must be a two-letter ISO 3166-1 code.");
+    }
+
+    Validity::Valid
+}
+
+pub fn validate_household_size(household_size: i16) -> Validity {
+    if !(1..=MAX_HOUSEHOLD_SIZE).contains(&household_size) {
+        return Validity::Invalid("Household size must be between 1 and 20.");
+    }
+
+    Validity::Valid
+}
+
+// Cohorts are compared over the most recent complete calendar month. Returns the first day of
+// that month and the first day of the month after it.
+pub fn previous_month_range(today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let current_month_start = NaiveDate::from_ymd(today.year(), today.month(), 1);
+    let previous_month_end = current_month_start - Duration::days(1);
+    let previous_month_start =
+        NaiveDate::from_ymd(previous_month_end.year(), previous_month_end.month(), 1);
+
+    (previous_month_start, current_month_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_country_code() {
+        assert!(validate_country_code("US").is_valid());
+        assert!(validate_country_code("DE").is_valid());
+        assert!(!validate_country_code("us").is_valid());
+        assert!(!validate_country_code("USA").is_valid());
+        assert!(!validate_country_code("").is_valid());
+        assert!(!validate_country_code("Ü1").is_valid());
+    }
+
+    #[test]
+    fn test_validate_household_size() {
+        assert!(validate_household_size(1).is_valid());
+        assert!(validate_household_size(20).is_valid());
+        assert!(!validate_household_size(0).is_valid());
+        assert!(!validate_household_size(21).is_valid());
+    }
+
+    #[test]
+    fn test_previous_month_range() {
+        assert_eq!(
+            previous_month_range(NaiveDate::from_ymd(2022, 3, 15)),
+            (
+                NaiveDate::from_ymd(2022, 2, 1),
+                NaiveDate::from_ymd(2022, 3, 1)
+            )
+        );
+
+        assert_eq!(
+            previous_month_range(NaiveDate::from_ymd(2022, 1, 1)),
+            (
+                NaiveDate::from_ymd(2021, 12, 1),
+                NaiveDate::from_ymd(2022, 1, 1)
+            )
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::env;
+
+// Confirmation tokens let a client carry out a destructive operation it has already previewed
+// without the server keeping state between the two requests. A token is bound to the user and to
+// a description of exactly what was previewed, so it can't authorize anything else.
+
+pub fn generate_confirmation_token(user_id: Uuid, previewed: &str, lifetime_secs: u64) -> String {
+    let expiration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to fetch system time")
+        .as_secs()
+        + lifetime_secs;
+
+    format!(
+        "{}.{}",
+        expiration,
+        hex::encode(
+            confirmation_mac(user_id, previewed, expiration)
+                .finalize()
+                .into_bytes()
+        )
+    )
+}
+
+pub fn verify_confirmation_token(token: &str, user_id: Uuid, previewed: &str) -> bool {
+    let (expiration, hash) = match token.split_once('.') {
+        Some((e, h)) => (e, h),
+        None => return false,
+    };
+
+    let expiration = match expiration.parse::<u64>() {
+        Ok(e) => e,
+        Err(_) => return false,
+    };
+
+    let hash = match hex::decode(hash) {
+        Ok(h) => h,
+        Err(_) => return false,
+    };
+
+    let current_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to fetch system time")
+        .as_secs();
+
+    if current_time >= expiration {
+        return false;
+    }
+
+    confirmation_mac(user_id, previewed, expiration)
+        .verify_slice(&hash)
+        .is_ok()
+}
+
+fn confirmation_mac(user_id: Uuid, previewed: &str, expiration: u64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(env::CONF.keys.token_signing_key.as_bytes())
+        .expect("Failed to generate hash from key");
+
+    // The prefix keeps these MACs distinct from those of any other token signed with the same key
+    mac.update(format!("confirmation|{}|{}|{}", user_id, expiration, previewed).as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_confirmation_token() {
+        let user_id = Uuid::new_v4();
+        let token = generate_confirmation_token(user_id, "delete 12 entries", 60);
+
+        assert!(verify_confirmation_token(
+            &token,
+            user_id,
+            "delete 12 entries"
+        ));
+
+        assert!(!verify_confirmation_token(
+            &token,
+            user_id,
+            "delete 13 entries"
+        ));
+        assert!(!verify_confirmation_token(
+            &token,
+            Uuid::new_v4(),
+            "delete 12 entries"
+        ));
+        assert!(!verify_confirmation_token("", user_id, "delete 12 entries"));
+        assert!(!verify_confirmation_token(
+            &token.replace('.', ""),
+            user_id,
+            "delete 12 entries"
+        ));
+
+        // Pushing out the expiration invalidates the hash
+        let (expiration, hash) = token.split_once('.').unwrap();
+        let extended_token = format!("{}.{}", expiration.parse::<u64>().unwrap() + 3600, hash);
+        assert!(!verify_confirmation_token(
+            &extended_token,
+            user_id,
+            "delete 12 entries"
+        ));
+
+        let expired_token = generate_confirmation_token(user_id, "delete 12 entries", 0);
+        assert!(!verify_confirmation_token(
+            &expired_token,
+            user_id,
+            "delete 12 entries"
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::env;
+
+// Amounts are stored in the minor unit of the currency of whoever recorded them (cents for USD,
+// yen for JPY). Rates are kept relative to a single base currency and any pair is converted
+// through it.
+
+#[derive(Debug)]
+pub enum CurrencyError {
+    ProviderFailure(Option<&'static str>),
+}
+
+impl std::error::Error for CurrencyError {}
+
+impl fmt::Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CurrencyError::ProviderFailure(msg) => {
+                write!(f, "Exchange rate provider failure")?;
+
+                match msg {
+                    Some(msg) => write!(f, ": {}", msg),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+pub trait ExchangeRateProvider {
+    fn base_currency(&self) -> &str;
+
+    // Units of each currency per unit of the base currency
+    fn fetch_rates(&self) -> Result<BTreeMap<String, f64>, CurrencyError>;
+}
+
+// Serves the rates from the server's configuration. A provider backed by a market data feed can
+// take its place without changing anything that reads the cached rates.
+pub struct ConfiguredRates;
+
+impl ExchangeRateProvider for ConfiguredRates {
+    fn base_currency(&self) -> &str {
+        &env::CONF.currency.base_currency
+    }
+
+    fn fetch_rates(&self) -> Result<BTreeMap<String, f64>, CurrencyError> {
+        let rates = env::CONF
+            .currency
+            .rates
+            .iter()
+            .filter(|(_, rate)| rate.is_finite() && **rate > 0.0)
+            .map(|(currency, rate)| (currency.to_ascii_uppercase(), *rate))
+            .collect::<BTreeMap<_, _>>();
+
+        if rates.len() != env::CONF.currency.rates.len() {
+            return Err(CurrencyError::ProviderFailure(Some(
+                "Configured exchange rates must be positive",
+            )));
+        }
+
+        Ok(rates)
+    }
+}
+
+pub struct ExchangeRates {
+    base_currency: String,
+    rates: HashMap<String, f64>,
+}
+
+impl ExchangeRates {
+    pub fn new(base_currency: &str, rates: HashMap<String, f64>) -> Self {
+        Self {
+            base_currency: base_currency.to_ascii_uppercase(),
+            rates: rates
+                .into_iter()
+                .map(|(currency, rate)| (currency.to_ascii_uppercase(), rate))
+                .collect(),
+        }
+    }
+
+    fn rate(&self, currency: &str) -> Option<f64> {
+        if currency == self.base_currency {
+            Some(1.0)
+        } else {
+            self.rates.get(currency).copied()
+        }
+    }
+
+    // Returns None if either currency has no known rate
+    pub fn convert(&self, amount: i64, from_currency: &str, to_currency: &str) -> Option<i64> {
+        let from_currency = from_currency.to_ascii_uppercase();
+        let to_currency = to_currency.to_ascii_uppercase();
+
+        if from_currency == to_currency {
+            return Some(amount);
+        }
+
+        let from_rate = self.rate(&from_currency)?;
+        let to_rate = self.rate(&to_currency)?;
+
+        let major_units = amount as f64 / 10f64.powi(minor_unit_exponent(&from_currency));
+        let converted = major_units / from_rate * to_rate;
+
+        Some((converted * 10f64.powi(minor_unit_exponent(&to_currency))).round() as i64)
+    }
+}
+
+// The number of digits after the decimal point in the currency's minor unit, per ISO 4217
+pub fn minor_unit_exponent(currency: &str) -> i32 {
+    match currency {
+        "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX"
+        | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+        "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let rates = ExchangeRates::new(
+            "usd",
+            HashMap::from([
+                (String::from("EUR"), 0.8),
+                (String::from("jpy"), 150.0),
+                (String::from("KWD"), 0.3),
+            ]),
+        );
+
+        assert_eq!(rates.convert(1000, "USD", "USD"), Some(1000));
+        assert_eq!(rates.convert(1000, "USD", "EUR"), Some(800));
+        assert_eq!(rates.convert(800, "EUR", "usd"), Some(1000));
+        assert_eq!(rates.convert(-1000, "USD", "EUR"), Some(-800));
+
+        // Yen have no minor unit and dinar have three digits of one
+        assert_eq!(rates.convert(1000, "USD", "JPY"), Some(1500));
+        assert_eq!(rates.convert(1500, "JPY", "EUR"), Some(800));
+        assert_eq!(rates.convert(1000, "USD", "KWD"), Some(3000));
+
+        // Rounded to the nearest minor unit
+        assert_eq!(rates.convert(1, "EUR", "USD"), Some(1));
+        assert_eq!(rates.convert(3, "EUR", "JPY"), Some(6));
+
+        assert_eq!(rates.convert(1000, "USD", "GBP"), None);
+        assert_eq!(rates.convert(1000, "GBP", "USD"), None);
+    }
+}
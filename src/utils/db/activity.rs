@@ -0,0 +1,233 @@
+use chrono::NaiveDateTime;
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{ActivityEventType, OutputActivityEvent};
+use crate::schema::api_keys as api_key_fields;
+use crate::schema::api_keys::dsl::api_keys;
+use crate::schema::budget_share_events as budget_share_event_fields;
+use crate::schema::budget_share_events::dsl::budget_share_events;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::import_batches as import_batch_fields;
+use crate::schema::import_batches::dsl::import_batches;
+use crate::schema::sessions as session_fields;
+use crate::schema::sessions::dsl::sessions;
+use crate::schema::support_tickets as support_ticket_fields;
+use crate::schema::support_tickets::dsl::support_tickets;
+use crate::schema::user_badges as badge_fields;
+use crate::schema::user_badges::dsl::user_badges;
+use crate::utils::engagement::Badge;
+
+// A user's activity is put together from the timestamps on the records their actions created, so
+// it covers everything those records still hold. The budget audit log only records changes to
+// budgets, so it isn't used here.
+//
+// Returns the events that happened at or after `start` and before `end`, oldest first
+pub fn get_activity_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<Vec<OutputActivityEvent>, diesel::result::Error> {
+    let mut events = Vec::new();
+
+    let user_sessions = sessions
+        .select((
+            session_fields::id,
+            session_fields::is_password_reset,
+            session_fields::device_name,
+            session_fields::created_timestamp,
+        ))
+        .filter(session_fields::user_id.eq(user_id))
+        .filter(session_fields::created_timestamp.ge(start))
+        .filter(session_fields::created_timestamp.lt(end))
+        .load::<(Uuid, bool, Option<String>, NaiveDateTime)>(db_connection)?;
+
+    events.extend(user_sessions.into_iter().map(
+        |(id, is_password_reset, device_name, timestamp)| OutputActivityEvent {
+            event: if is_password_reset {
+                ActivityEventType::PasswordReset
+            } else {
+                ActivityEventType::SignedIn
+            },
+            id,
+            budget_id: None,
+            detail: device_name,
+            timestamp,
+        },
+    ));
+
+    let user_api_keys = api_keys
+        .select((
+            api_key_fields::id,
+            api_key_fields::name,
+            api_key_fields::created_timestamp,
+        ))
+        .filter(api_key_fields::user_id.eq(user_id))
+        .filter(api_key_fields::created_timestamp.ge(start))
+        .filter(api_key_fields::created_timestamp.lt(end))
+        .load::<(Uuid, String, NaiveDateTime)>(db_connection)?;
+
+    events.extend(
+        user_api_keys
+            .into_iter()
+            .map(|(id, name, timestamp)| OutputActivityEvent {
+                event: ActivityEventType::ApiKeyCreated,
+                id,
+                budget_id: None,
+                detail: Some(name),
+                timestamp,
+            }),
+    );
+
+    let sent_invitations = budget_share_events
+        .select((
+            budget_share_event_fields::id,
+            budget_share_event_fields::budget_id,
+            budget_share_event_fields::share_timestamp,
+        ))
+        .filter(budget_share_event_fields::sharer_user_id.eq(user_id))
+        .filter(budget_share_event_fields::share_timestamp.ge(start))
+        .filter(budget_share_event_fields::share_timestamp.lt(end))
+        .load::<(Uuid, Uuid, NaiveDateTime)>(db_connection)?;
+
+    events.extend(
+        sent_invitations
+            .into_iter()
+            .map(|(id, budget_id, timestamp)| OutputActivityEvent {
+                event: ActivityEventType::InvitationSent,
+                id,
+                budget_id: Some(budget_id),
+                detail: None,
+                timestamp,
+            }),
+    );
+
+    let answered_invitations = budget_share_events
+        .select((
+            budget_share_event_fields::id,
+            budget_share_event_fields::budget_id,
+            budget_share_event_fields::accepted,
+            budget_share_event_fields::accepted_declined_timestamp,
+        ))
+        .filter(budget_share_event_fields::recipient_user_id.eq(user_id))
+        .filter(
+            budget_share_event_fields::accepted_declined_timestamp
+                .ge(start)
+                .and(budget_share_event_fields::accepted_declined_timestamp.lt(end)),
+        )
+        .load::<(Uuid, Uuid, bool, Option<NaiveDateTime>)>(db_connection)?;
+
+    events.extend(answered_invitations.into_iter().filter_map(
+        |(id, budget_id, accepted, timestamp)| {
+            Some(OutputActivityEvent {
+                event: if accepted {
+                    ActivityEventType::InvitationAccepted
+                } else {
+                    ActivityEventType::InvitationDeclined
+                },
+                id,
+                budget_id: Some(budget_id),
+                detail: None,
+                timestamp: timestamp?,
+            })
+        },
+    ));
+
+    let created_entries = entries
+        .select((
+            entry_fields::id,
+            entry_fields::budget_id,
+            entry_fields::created_timestamp,
+        ))
+        .filter(entry_fields::user_id.eq(user_id))
+        .filter(entry_fields::created_timestamp.ge(start))
+        .filter(entry_fields::created_timestamp.lt(end))
+        .load::<(Uuid, Uuid, NaiveDateTime)>(db_connection)?;
+
+    events.extend(
+        created_entries
+            .into_iter()
+            .map(|(id, budget_id, timestamp)| OutputActivityEvent {
+                event: ActivityEventType::EntryCreated,
+                id,
+                budget_id: Some(budget_id),
+                detail: None,
+                timestamp,
+            }),
+    );
+
+    let user_import_batches = import_batches
+        .select((
+            import_batch_fields::id,
+            import_batch_fields::budget_id,
+            import_batch_fields::source_name,
+            import_batch_fields::created_timestamp,
+        ))
+        .filter(import_batch_fields::user_id.eq(user_id))
+        .filter(import_batch_fields::created_timestamp.ge(start))
+        .filter(import_batch_fields::created_timestamp.lt(end))
+        .load::<(Uuid, Uuid, String, NaiveDateTime)>(db_connection)?;
+
+    events.extend(user_import_batches.into_iter().map(
+        |(id, budget_id, source_name, timestamp)| OutputActivityEvent {
+            event: ActivityEventType::StatementImported,
+            id,
+            budget_id: Some(budget_id),
+            detail: Some(source_name),
+            timestamp,
+        },
+    ));
+
+    let earned_badges = user_badges
+        .select((
+            badge_fields::id,
+            badge_fields::badge,
+            badge_fields::earned_timestamp,
+        ))
+        .filter(badge_fields::user_id.eq(user_id))
+        .filter(badge_fields::earned_timestamp.ge(start))
+        .filter(badge_fields::earned_timestamp.lt(end))
+        .load::<(Uuid, i16, NaiveDateTime)>(db_connection)?;
+
+    events.extend(
+        earned_badges
+            .into_iter()
+            .map(|(id, badge, timestamp)| OutputActivityEvent {
+                event: ActivityEventType::BadgeEarned,
+                id,
+                budget_id: None,
+                detail: Badge::try_from(badge).ok().map(|b| format!("{:?}", b)),
+                timestamp,
+            }),
+    );
+
+    let user_support_tickets = support_tickets
+        .select((
+            support_ticket_fields::id,
+            support_ticket_fields::created_timestamp,
+        ))
+        .filter(support_ticket_fields::user_id.eq(user_id))
+        .filter(support_ticket_fields::created_timestamp.ge(start))
+        .filter(support_ticket_fields::created_timestamp.lt(end))
+        .load::<(Uuid, NaiveDateTime)>(db_connection)?;
+
+    events.extend(
+        user_support_tickets
+            .into_iter()
+            .map(|(id, timestamp)| OutputActivityEvent {
+                event: ActivityEventType::SupportTicketOpened,
+                id,
+                budget_id: None,
+                detail: None,
+                timestamp,
+            }),
+    );
+
+    events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.id.cmp(&b.id)));
+
+    Ok(events)
+}
@@ -0,0 +1,249 @@
+use chrono::NaiveDate;
+use diesel::{dsl, BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
+use rand::prelude::*;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::api_key::{ApiKey, NewApiKey};
+use crate::models::api_key_usage::{ApiKeyUsage, NewApiKeyUsage};
+use crate::schema::api_key_usage as api_key_usage_fields;
+use crate::schema::api_key_usage::dsl::api_key_usage;
+use crate::schema::api_keys as api_key_fields;
+use crate::schema::api_keys::dsl::api_keys;
+use crate::utils::record_id;
+
+const API_KEY_BYTES: usize = 32;
+
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+// Returns the new key record along with the plaintext key. The plaintext key is not stored.
+pub fn create_api_key(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    name: &str,
+) -> Result<(ApiKey, String), diesel::result::Error> {
+    let mut key_bytes = [0u8; API_KEY_BYTES];
+    rand::thread_rng().fill_bytes(&mut key_bytes);
+    let key = base64::encode_config(key_bytes, base64::URL_SAFE_NO_PAD);
+    let key_hash = hash_api_key(&key);
+
+    let new_api_key = NewApiKey {
+        id: record_id::generate(),
+        user_id,
+        key_hash: &key_hash,
+        name,
+        is_revoked: false,
+        last_used_timestamp: None,
+        created_timestamp: chrono::Utc::now().naive_utc(),
+    };
+
+    let api_key = dsl::insert_into(api_keys)
+        .values(&new_api_key)
+        .get_result::<ApiKey>(db_connection)?;
+
+    Ok((api_key, key))
+}
+
+pub fn get_all_api_keys_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<ApiKey>, diesel::result::Error> {
+    api_keys
+        .filter(api_key_fields::user_id.eq(user_id))
+        .filter(api_key_fields::is_revoked.eq(false))
+        .order(api_key_fields::created_timestamp.asc())
+        .load::<ApiKey>(db_connection)
+}
+
+pub fn revoke_api_key(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    key_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        api_keys.filter(
+            api_key_fields::id
+                .eq(key_id)
+                .and(api_key_fields::user_id.eq(user_id)),
+        ),
+    )
+    .set(api_key_fields::is_revoked.eq(true))
+    .execute(db_connection)
+}
+
+// Looks up an active key and records that it was used
+pub fn use_api_key(
+    db_connection: &DbConnection,
+    key: &str,
+) -> Result<ApiKey, diesel::result::Error> {
+    diesel::update(
+        api_keys.filter(
+            api_key_fields::key_hash
+                .eq(hash_api_key(key))
+                .and(api_key_fields::is_revoked.eq(false)),
+        ),
+    )
+    .set(api_key_fields::last_used_timestamp.eq(chrono::Utc::now().naive_utc()))
+    .get_result::<ApiKey>(db_connection)
+}
+
+// Counts a request against the key's usage for the day. Returns the number of requests made with
+// the key that day, including this one.
+pub fn record_api_key_request(
+    db_connection: &DbConnection,
+    api_key_id: Uuid,
+    date: NaiveDate,
+) -> Result<i32, diesel::result::Error> {
+    let new_usage = NewApiKeyUsage {
+        api_key_id,
+        usage_date: date,
+        request_count: 1,
+    };
+
+    dsl::insert_into(api_key_usage)
+        .values(&new_usage)
+        .on_conflict((
+            api_key_usage_fields::api_key_id,
+            api_key_usage_fields::usage_date,
+        ))
+        .do_update()
+        .set(api_key_usage_fields::request_count.eq(api_key_usage_fields::request_count + 1))
+        .returning(api_key_usage_fields::request_count)
+        .get_result::<i32>(db_connection)
+}
+
+// Includes usage of keys that have since been revoked
+pub fn get_api_usage_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    since: NaiveDate,
+) -> Result<Vec<ApiKeyUsage>, diesel::result::Error> {
+    let user_key_ids = api_keys
+        .select(api_key_fields::id)
+        .filter(api_key_fields::user_id.eq(user_id));
+
+    api_key_usage
+        .filter(api_key_usage_fields::api_key_id.eq_any(user_key_ids))
+        .filter(api_key_usage_fields::usage_date.ge(since))
+        .order((
+            api_key_usage_fields::usage_date.desc(),
+            api_key_usage_fields::api_key_id.asc(),
+        ))
+        .load::<ApiKeyUsage>(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::env;
+    use crate::utils::db::testing::create_test_user;
+
+    #[test]
+    fn test_create_and_use_api_key() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user = create_test_user(&db_connection);
+
+        let (api_key, key) = create_api_key(&db_connection, user.id, "Zapier").unwrap();
+
+        assert_eq!(api_key.user_id, user.id);
+        assert_eq!(api_key.name, "Zapier");
+        assert_ne!(api_key.key_hash, key);
+        assert_eq!(api_key.key_hash, hash_api_key(&key));
+        assert!(api_key.last_used_timestamp.is_none());
+
+        let user_id = use_api_key(&db_connection, &key).unwrap().user_id;
+        assert_eq!(user_id, user.id);
+
+        let api_key = api_keys
+            .find(api_key.id)
+            .get_result::<ApiKey>(&db_connection)
+            .unwrap();
+        assert!(api_key.last_used_timestamp.is_some());
+
+        assert!(use_api_key(&db_connection, "not a key").is_err());
+    }
+
+    #[test]
+    fn test_revoke_api_key() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user = create_test_user(&db_connection);
+        let other_user = create_test_user(&db_connection);
+
+        let (api_key, key) = create_api_key(&db_connection, user.id, "IFTTT").unwrap();
+        create_api_key(&db_connection, user.id, "Zapier").unwrap();
+
+        assert_eq!(
+            get_all_api_keys_for_user(&db_connection, user.id)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Another user cannot revoke the key
+        assert_eq!(
+            revoke_api_key(&db_connection, other_user.id, api_key.id).unwrap(),
+            0
+        );
+        assert!(use_api_key(&db_connection, &key).is_ok());
+
+        assert_eq!(
+            revoke_api_key(&db_connection, user.id, api_key.id).unwrap(),
+            1
+        );
+        assert!(use_api_key(&db_connection, &key).is_err());
+
+        let remaining_keys = get_all_api_keys_for_user(&db_connection, user.id).unwrap();
+        assert_eq!(remaining_keys.len(), 1);
+        assert_eq!(remaining_keys[0].name, "Zapier");
+    }
+
+    #[test]
+    fn test_record_api_key_request() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user = create_test_user(&db_connection);
+        let other_user = create_test_user(&db_connection);
+
+        let (api_key, _) = create_api_key(&db_connection, user.id, "Zapier").unwrap();
+        let (other_api_key, _) = create_api_key(&db_connection, user.id, "IFTTT").unwrap();
+        create_api_key(&db_connection, other_user.id, "Zapier").unwrap();
+
+        let today = chrono::Utc::now().naive_utc().date();
+        let yesterday = today - chrono::Duration::days(1);
+
+        assert_eq!(
+            record_api_key_request(&db_connection, api_key.id, today).unwrap(),
+            1
+        );
+        assert_eq!(
+            record_api_key_request(&db_connection, api_key.id, today).unwrap(),
+            2
+        );
+        assert_eq!(
+            record_api_key_request(&db_connection, api_key.id, yesterday).unwrap(),
+            1
+        );
+        assert_eq!(
+            record_api_key_request(&db_connection, other_api_key.id, today).unwrap(),
+            1
+        );
+
+        let usage = get_api_usage_for_user(&db_connection, user.id, yesterday).unwrap();
+        assert_eq!(usage.len(), 3);
+        assert!(usage
+            .iter()
+            .any(|u| u.api_key_id == api_key.id && u.usage_date == today && u.request_count == 2));
+
+        let usage = get_api_usage_for_user(&db_connection, user.id, today).unwrap();
+        assert_eq!(usage.len(), 2);
+
+        assert!(
+            get_api_usage_for_user(&db_connection, other_user.id, yesterday)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
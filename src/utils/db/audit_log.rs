@@ -0,0 +1,111 @@
+use diesel::{dsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use serde::Serialize;
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{OutputAuditLogEvent, OutputAuditLogPage};
+use crate::models::budget_audit_log_entry::{BudgetAuditLogEntry, NewBudgetAuditLogEntry};
+use crate::schema::budget_audit_log as audit_log_fields;
+use crate::schema::budget_audit_log::dsl::budget_audit_log;
+use crate::utils::record_id;
+
+// The values are stored in the database, so existing variants must keep their numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    EntryAdded = 0,
+    EntryEdited = 1,
+    CategoryLimitChanged = 2,
+    MemberAdded = 3,
+    EntryDeleted = 4,
+    EntryRestored = 5,
+}
+
+impl TryFrom<i16> for AuditAction {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(AuditAction::EntryAdded),
+            1 => Ok(AuditAction::EntryEdited),
+            2 => Ok(AuditAction::CategoryLimitChanged),
+            3 => Ok(AuditAction::MemberAdded),
+            4 => Ok(AuditAction::EntryDeleted),
+            5 => Ok(AuditAction::EntryRestored),
+            _ => Err(()),
+        }
+    }
+}
+
+// Call this inside the transaction that makes the change so the change and its record are
+// committed or rolled back together
+pub fn record<B: Serialize, A: Serialize>(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    user_id: Uuid,
+    action: AuditAction,
+    before: Option<&B>,
+    after: Option<&A>,
+) -> Result<(), diesel::result::Error> {
+    let new_entry = NewBudgetAuditLogEntry {
+        id: record_id::generate(),
+        budget_id,
+        user_id: Some(user_id),
+        action: action as i16,
+        before_value: before.map(to_json).transpose()?,
+        after_value: after.map(to_json).transpose()?,
+        created_timestamp: chrono::Utc::now().naive_utc(),
+    };
+
+    dsl::insert_into(budget_audit_log)
+        .values(&new_entry)
+        .execute(db_connection)?;
+
+    Ok(())
+}
+
+// Newest first
+pub fn get_audit_log(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputAuditLogPage, diesel::result::Error> {
+    let mut loaded_events = budget_audit_log
+        .filter(audit_log_fields::budget_id.eq(budget_id))
+        .order((
+            audit_log_fields::created_timestamp.desc(),
+            audit_log_fields::id.desc(),
+        ))
+        .limit(limit + 1)
+        .offset(offset)
+        .load::<BudgetAuditLogEntry>(db_connection)?;
+
+    let has_more = loaded_events.len() as i64 > limit;
+    loaded_events.truncate(limit as usize);
+
+    let events = loaded_events
+        .into_iter()
+        .map(|event| {
+            Ok(OutputAuditLogEvent {
+                id: event.id,
+                user_id: event.user_id,
+                action: event.action,
+                before: event.before_value.as_deref().map(from_json).transpose()?,
+                after: event.after_value.as_deref().map(from_json).transpose()?,
+                created_timestamp: event.created_timestamp,
+            })
+        })
+        .collect::<Result<Vec<_>, diesel::result::Error>>()?;
+
+    Ok(OutputAuditLogPage { events, has_more })
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, diesel::result::Error> {
+    serde_json::to_string(value).map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))
+}
+
+fn from_json(value: &str) -> Result<serde_json::Value, diesel::result::Error> {
+    serde_json::from_str(value)
+        .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))
+}
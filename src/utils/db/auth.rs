@@ -6,8 +6,10 @@ use uuid::Uuid;
 use crate::definitions::*;
 use crate::schema::blacklisted_tokens as token_fields;
 use crate::schema::blacklisted_tokens::dsl::blacklisted_tokens;
+use crate::schema::password_attempts as password_attempt_fields;
+use crate::schema::password_attempts::dsl::password_attempts;
 
-pub fn clear_all_expired_refresh_tokens(
+pub fn purge_expired_blacklisted_tokens(
     db_connection: &DbConnection,
 ) -> Result<usize, diesel::result::Error> {
     // Add two minutes to current time to prevent slight clock differences/inaccuracies from
@@ -37,7 +39,8 @@ pub fn clear_password_attempt_count(
     db_connection: &DbConnection,
 ) -> Result<usize, diesel::result::Error> {
     // The use of this raw(ish) query is safe because it takes no input from the client.
-    diesel::sql_query("TRUNCATE password_attempts").execute(db_connection)
+    // Password reset requests share the window for password attempts
+    diesel::sql_query("TRUNCATE password_attempts, password_reset_attempts").execute(db_connection)
 }
 
 #[derive(QueryableByName)]
@@ -90,6 +93,43 @@ pub fn get_and_increment_password_attempt_count(
     Ok(db_resp[0].attempt_count)
 }
 
+pub fn get_and_increment_password_reset_attempt_count(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<i16, diesel::result::Error> {
+    // The use of this raw(ish) query is safe because the input (user_id) comes from the database.
+    //
+    // BEWARE of using this function when the user_id comes as input directly from the client.
+    let query = format!(
+        "INSERT INTO password_reset_attempts \
+         (user_id, attempt_count) \
+         VALUES ('{user_id}', 1) \
+         ON CONFLICT (user_id) DO UPDATE \
+         SET attempt_count = password_reset_attempts.attempt_count + 1 \
+         WHERE password_reset_attempts.user_id = '{user_id}' \
+         RETURNING password_reset_attempts.attempt_count"
+    );
+
+    let db_resp = diesel::sql_query(&query).load::<AttemptCount>(db_connection)?;
+
+    Ok(db_resp[0].attempt_count)
+}
+
+// Attempts are counted before the password is checked so that concurrent guesses can't slip past
+// the limit. A correct password gives its attempt back, leaving only failures counted.
+pub fn refund_password_attempt(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        password_attempts
+            .filter(password_attempt_fields::user_id.eq(user_id))
+            .filter(password_attempt_fields::attempt_count.gt(0)),
+    )
+    .set(password_attempt_fields::attempt_count.eq(password_attempt_fields::attempt_count - 1))
+    .execute(db_connection)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,10 +147,10 @@ mod tests {
     use crate::schema::otp_attempts::dsl::otp_attempts;
     use crate::schema::password_attempts::dsl::password_attempts;
     use crate::utils::auth_token;
-    use crate::utils::db::user;
+    use crate::utils::db::{session, user};
 
     #[actix_rt::test]
-    async fn test_clear_all_expired_refresh_tokens() {
+    async fn test_purge_expired_blacklisted_tokens() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
         let db_connection = db_thread_pool.get().unwrap();
 
@@ -134,15 +174,20 @@ mod tests {
             .unwrap()
             .id;
 
-        let token_params = auth_token::TokenParams {
-            user_id: &user_id,
-            user_email: &new_user.email,
-            user_currency: &new_user.currency,
-        };
+        let token_params = auth_token::TokenParams { user_id: &user_id };
+        let session = session::create_session(
+            &db_connection,
+            user_id,
+            3600,
+            &session::SessionDevice::default(),
+        )
+        .unwrap();
 
         let pretend_expired_token =
-            auth_token::generate_refresh_token(token_params.clone()).unwrap();
-        let unexpired_token = auth_token::generate_refresh_token(token_params).unwrap();
+            auth_token::generate_refresh_token(token_params.clone(), &session).unwrap();
+        let nearly_expired_token =
+            auth_token::generate_refresh_token(token_params.clone(), &session).unwrap();
+        let unexpired_token = auth_token::generate_refresh_token(token_params, &session).unwrap();
 
         let current_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -155,6 +200,13 @@ mod tests {
             token_expiration_time: (current_time - 3600).try_into().unwrap(),
         };
 
+        // Falls within the two-minute allowance for clock differences
+        let nearly_expired_blacklisted = NewBlacklistedToken {
+            token: &nearly_expired_token.to_string(),
+            user_id,
+            token_expiration_time: (current_time + 60).try_into().unwrap(),
+        };
+
         let unexpired_blacklisted = NewBlacklistedToken {
             token: &unexpired_token.to_string(),
             user_id,
@@ -165,17 +217,25 @@ mod tests {
             .values(&expired_blacklisted)
             .execute(&db_connection)
             .unwrap();
+        dsl::insert_into(blacklisted_tokens)
+            .values(&nearly_expired_blacklisted)
+            .execute(&db_connection)
+            .unwrap();
         dsl::insert_into(blacklisted_tokens)
             .values(&unexpired_blacklisted)
             .execute(&db_connection)
             .unwrap();
 
-        assert!(clear_all_expired_refresh_tokens(&db_connection).unwrap() >= 1);
+        assert!(purge_expired_blacklisted_tokens(&db_connection).unwrap() >= 2);
 
         assert!(
             !auth_token::is_on_blacklist(&pretend_expired_token.to_string(), &db_connection)
                 .unwrap()
         );
+        assert!(
+            !auth_token::is_on_blacklist(&nearly_expired_token.to_string(), &db_connection)
+                .unwrap()
+        );
         assert!(auth_token::is_on_blacklist(&unexpired_token.to_string(), &db_connection).unwrap());
     }
 
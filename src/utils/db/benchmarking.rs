@@ -0,0 +1,327 @@
+use chrono::NaiveDate;
+use diesel::sql_types::{BigInt, Varchar};
+use diesel::{dsl, sql_query, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::benchmarking_profile::{BenchmarkingProfile, NewBenchmarkingProfile};
+use crate::models::cohort_category_stat::CohortCategoryStat;
+use crate::schema::benchmarking_profiles as profile_fields;
+use crate::schema::benchmarking_profiles::dsl::benchmarking_profiles;
+use crate::schema::cohort_category_stats as stat_fields;
+use crate::schema::cohort_category_stats::dsl::cohort_category_stats;
+
+// Spending is grouped by normalized category name because categories belong to individual
+// budgets and have no identity shared between users
+const PER_USER_CATEGORY_SPENDING: &str = "SELECT entries.user_id AS user_id, \
+     LOWER(TRIM(categories.name)) AS category_name, \
+     SUM(entries.amount_cents)::BIGINT AS spent_cents \
+     FROM entries \
+     JOIN categories ON categories.budget_id = entries.budget_id \
+     AND categories.id = entries.category \
+     WHERE entries.is_deleted = FALSE \
+     AND entries.amount_cents > 0";
+
+pub fn set_profile(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    country_code: &str,
+    household_size: i16,
+) -> Result<BenchmarkingProfile, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_profile = NewBenchmarkingProfile {
+        user_id,
+        country_code,
+        household_size,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(benchmarking_profiles)
+        .values(&new_profile)
+        .on_conflict(profile_fields::user_id)
+        .do_update()
+        .set((
+            profile_fields::country_code.eq(country_code),
+            profile_fields::household_size.eq(household_size),
+            profile_fields::modified_timestamp.eq(current_time),
+        ))
+        .get_result::<BenchmarkingProfile>(db_connection)
+}
+
+pub fn get_profile(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<BenchmarkingProfile, diesel::result::Error> {
+    benchmarking_profiles
+        .find(user_id)
+        .first::<BenchmarkingProfile>(db_connection)
+}
+
+pub fn delete_profile(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(benchmarking_profiles.find(user_id)).execute(db_connection)
+}
+
+#[derive(Debug, QueryableByName)]
+pub struct CategorySpending {
+    #[sql_type = "Varchar"]
+    pub category_name: String,
+    #[sql_type = "BigInt"]
+    pub spent_cents: i64,
+}
+
+pub fn get_user_spending_by_category(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    period_start: NaiveDate,
+    period_end: NaiveDate,
+) -> Result<Vec<CategorySpending>, diesel::result::Error> {
+    // The use of this raw(ish) query is safe because the input (user_id) comes from a signed token
+    // and the dates are typed.
+    //
+    // BEWARE of using this function when the user_id comes as input directly from the client.
+    let query = format!(
+        "SELECT category_name, spent_cents FROM ({PER_USER_CATEGORY_SPENDING} \
+         AND entries.user_id = '{user_id}' \
+         AND entries.date >= '{period_start}' \
+         AND entries.date < '{period_end}' \
+         GROUP BY entries.user_id, LOWER(TRIM(categories.name))) AS spending \
+         ORDER BY category_name"
+    );
+
+    sql_query(&query).load::<CategorySpending>(db_connection)
+}
+
+// Recomputes the aggregates for one period. Only cohorts (country, household size, category)
+// with at least min_cohort_size contributing users are stored. Returns the number of rows
+// written.
+pub fn compute_cohort_stats(
+    db_connection: &DbConnection,
+    period_start: NaiveDate,
+    period_end: NaiveDate,
+    min_cohort_size: i64,
+) -> Result<usize, diesel::result::Error> {
+    // The use of this raw(ish) query is safe because it takes no input from the client.
+    let query = format!(
+        "INSERT INTO cohort_category_stats \
+         (country_code, household_size, category_name, period_start, user_count, \
+         p25_cents, median_cents, p75_cents, computed_timestamp) \
+         SELECT benchmarking_profiles.country_code, benchmarking_profiles.household_size, \
+         spending.category_name, '{period_start}', COUNT(*), \
+         PERCENTILE_CONT(0.25) WITHIN GROUP (ORDER BY spending.spent_cents)::BIGINT, \
+         PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY spending.spent_cents)::BIGINT, \
+         PERCENTILE_CONT(0.75) WITHIN GROUP (ORDER BY spending.spent_cents)::BIGINT, \
+         NOW() AT TIME ZONE 'UTC' \
+         FROM ({PER_USER_CATEGORY_SPENDING} \
+         AND entries.date >= '{period_start}' \
+         AND entries.date < '{period_end}' \
+         GROUP BY entries.user_id, LOWER(TRIM(categories.name))) AS spending \
+         JOIN benchmarking_profiles ON benchmarking_profiles.user_id = spending.user_id \
+         GROUP BY benchmarking_profiles.country_code, benchmarking_profiles.household_size, \
+         spending.category_name \
+         HAVING COUNT(*) >= {min_cohort_size}"
+    );
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // Cohorts that shrank below the minimum size since the last run must not keep stale rows
+        diesel::delete(cohort_category_stats.filter(stat_fields::period_start.eq(period_start)))
+            .execute(db_connection)?;
+
+        sql_query(&query).execute(db_connection)
+    })
+}
+
+pub fn get_cohort_stats(
+    db_connection: &DbConnection,
+    country_code: &str,
+    household_size: i16,
+    period_start: NaiveDate,
+) -> Result<Vec<CohortCategoryStat>, diesel::result::Error> {
+    cohort_category_stats
+        .filter(stat_fields::country_code.eq(country_code))
+        .filter(stat_fields::household_size.eq(household_size))
+        .filter(stat_fields::period_start.eq(period_start))
+        .order(stat_fields::category_name.asc())
+        .load::<CohortCategoryStat>(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use rand::prelude::*;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputEntry, InputUser};
+    use crate::utils::db::{budget, user};
+
+    fn random_country_code() -> String {
+        (0..2)
+            .map(|_| rand::thread_rng().gen_range(b'A'..=b'Z') as char)
+            .collect()
+    }
+
+    // Creates an opted-in user who spent the given amount on "Dining" on the given date
+    fn create_opted_in_user_with_spending(
+        db_connection: &DbConnection,
+        country_code: &str,
+        household_size: i16,
+        date: NaiveDate,
+        dining_cents: i64,
+    ) -> Uuid {
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("hT4$wq9!Lmz2#XeR7vKc"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let created_user = user::create_user(db_connection, &web::Json(new_user)).unwrap();
+
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                // Category names are compared without regard to case or surrounding whitespace
+                name: if user_number % 2 == 0 {
+                    String::from("Dining")
+                } else {
+                    String::from(" dining ")
+                },
+                limit_cents: 50000,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: date,
+            end_date: date,
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let created_budget =
+            budget::create_budget(db_connection, &web::Json(new_budget), created_user.id).unwrap();
+
+        let entry = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: dining_cents,
+            date,
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        budget::create_entry(db_connection, &web::Json(entry), created_user.id).unwrap();
+        set_profile(db_connection, created_user.id, country_code, household_size).unwrap();
+
+        created_user.id
+    }
+
+    #[test]
+    fn test_compute_cohort_stats() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+
+        let period_start = NaiveDate::from_ymd(1999, 4, 1);
+        let period_end = NaiveDate::from_ymd(1999, 5, 1);
+        let date_in_period = NaiveDate::from_ymd(1999, 4, 12);
+
+        let country_code = random_country_code();
+        let household_size = rand::thread_rng().gen_range(1..=20);
+
+        let mut user_ids = Vec::new();
+
+        for dining_cents in [1000, 2000, 3000, 4000, 5000] {
+            user_ids.push(create_opted_in_user_with_spending(
+                &db_connection,
+                &country_code,
+                household_size,
+                date_in_period,
+                dining_cents,
+            ));
+        }
+
+        // A cohort too small to be reported
+        let small_cohort_household_size = household_size % 20 + 1;
+        create_opted_in_user_with_spending(
+            &db_connection,
+            &country_code,
+            small_cohort_household_size,
+            date_in_period,
+            1000,
+        );
+
+        compute_cohort_stats(&db_connection, period_start, period_end, 5).unwrap();
+
+        let stats =
+            get_cohort_stats(&db_connection, &country_code, household_size, period_start).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].category_name, "dining");
+        assert_eq!(stats[0].user_count, 5);
+        assert_eq!(stats[0].p25_cents, 2000);
+        assert_eq!(stats[0].median_cents, 3000);
+        assert_eq!(stats[0].p75_cents, 4000);
+
+        let stats = get_cohort_stats(
+            &db_connection,
+            &country_code,
+            small_cohort_household_size,
+            period_start,
+        )
+        .unwrap();
+        assert!(stats.is_empty());
+
+        let user_spending =
+            get_user_spending_by_category(&db_connection, user_ids[1], period_start, period_end)
+                .unwrap();
+        assert_eq!(user_spending.len(), 1);
+        assert_eq!(user_spending[0].category_name, "dining");
+        assert_eq!(user_spending[0].spent_cents, 2000);
+
+        // Once a user opts out the cohort falls below the minimum size and its stats go away
+        delete_profile(&db_connection, user_ids[0]).unwrap();
+        compute_cohort_stats(&db_connection, period_start, period_end, 5).unwrap();
+
+        let stats =
+            get_cohort_stats(&db_connection, &country_code, household_size, period_start).unwrap();
+        assert!(stats.is_empty());
+    }
+
+    #[test]
+    fn test_set_profile_updates_existing() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user_id = create_opted_in_user_with_spending(
+            &db_connection,
+            "US",
+            2,
+            NaiveDate::from_ymd(1999, 6, 1),
+            1000,
+        );
+
+        set_profile(&db_connection, user_id, "DE", 3).unwrap();
+
+        let profile = get_profile(&db_connection, user_id).unwrap();
+        assert_eq!(profile.country_code, "DE");
+        assert_eq!(profile.household_size, 3);
+
+        assert_eq!(delete_profile(&db_connection, user_id).unwrap(), 1);
+        assert_eq!(
+            get_profile(&db_connection, user_id).unwrap_err(),
+            diesel::result::Error::NotFound
+        );
+    }
+}
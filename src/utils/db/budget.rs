@@ -1,20 +1,27 @@
 use actix_web::web;
 use chrono::NaiveDate;
 use diesel::associations::GroupedBy;
+use diesel::sql_types::{BigInt, Nullable, SmallInt, Uuid as SqlUuid, Varchar};
 use diesel::{
-    dsl, sql_query, BelongingToDsl, BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl,
+    dsl, sql_query, BelongingToDsl, BoolExpressionMethods, Connection, ExpressionMethods,
+    OptionalExtension, QueryDsl, RunQueryDsl,
 };
+use std::convert::TryFrom;
 use uuid::Uuid;
 
 use crate::definitions::*;
-use crate::handlers::request_io::{InputBudget, InputEditBudget, InputEntry, OutputBudget};
+use crate::handlers::request_io::{
+    InputBudget, InputCategory, InputEditBudget, InputEditCategory, InputEditEntry, InputEntry,
+    InputEntryFilter, InputNewCategory, OutputBudget, OutputBudgetPage, OutputBudgetSummary,
+    OutputCategorySummary, OutputEntryPage,
+};
 use crate::models::budget::{Budget, NewBudget};
-use crate::models::budget_share_event::{BudgetShareEvent, NewBudgetShareEvent};
 use crate::models::category::{Category, NewCategory};
 use crate::models::entry::{Entry, NewEntry};
 use crate::models::user_budget::NewUserBudget;
-use crate::schema::budget_share_events as budget_share_event_fields;
-use crate::schema::budget_share_events::dsl::budget_share_events;
+use crate::models::user_notification::NewUserNotification;
+use crate::schema::budget_category_totals as category_total_fields;
+use crate::schema::budget_category_totals::dsl::budget_category_totals;
 use crate::schema::budgets as budget_fields;
 use crate::schema::budgets::dsl::budgets;
 use crate::schema::categories as category_fields;
@@ -23,6 +30,39 @@ use crate::schema::entries as entry_fields;
 use crate::schema::entries::dsl::entries;
 use crate::schema::user_budgets as user_budget_fields;
 use crate::schema::user_budgets::dsl::user_budgets;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::utils::db::audit_log::{self, AuditAction};
+use crate::utils::db::{category_total, push};
+use crate::utils::notification::{MemberLeftBudgetData, NotificationType, RemovedFromBudgetData};
+use crate::utils::record_id;
+
+// The values are stored in the database, so existing variants must keep their numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetRole {
+    Owner = 0,
+    Editor = 1,
+    Viewer = 2,
+}
+
+impl BudgetRole {
+    // Owners can do anything an editor can, and editors anything a viewer can
+    pub fn allows(self, required_role: BudgetRole) -> bool {
+        (self as i16) <= (required_role as i16)
+    }
+}
+
+impl TryFrom<i16> for BudgetRole {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BudgetRole::Owner),
+            1 => Ok(BudgetRole::Editor),
+            2 => Ok(BudgetRole::Viewer),
+            _ => Err(()),
+        }
+    }
+}
 
 pub fn get_budget_by_id(
     db_connection: &DbConnection,
@@ -42,6 +82,9 @@ pub fn get_budget_by_id(
         is_shared: budget.is_shared,
         is_private: budget.is_private,
         is_deleted: budget.is_deleted,
+        is_tracking_only: budget.is_tracking_only,
+        is_envelope: budget.is_envelope,
+        is_archived: budget.is_archived,
         name: budget.name,
         description: budget.description,
         categories: loaded_categories,
@@ -59,56 +102,26 @@ pub fn get_budget_by_id(
 pub fn get_all_budgets_for_user(
     db_connection: &DbConnection,
     user_id: Uuid,
-) -> Result<Vec<OutputBudget>, diesel::result::Error> {
-    // The use of this raw(ish) query is safe because the input (user_id) comes from a signed token.
+    include_archived: bool,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputBudgetPage, diesel::result::Error> {
+    // The use of this raw(ish) query is safe because the input (user_id) comes from a signed token
+    // and the limit and offset are integers.
     //
     // BEWARE of using this function when the user_id comes as input directly from the client.
     let query = format!(
         "SELECT budgets.* FROM user_budgets, budgets \
          WHERE user_budgets.user_id = '{user_id}' \
          AND user_budgets.budget_id = budgets.id \
-	 ORDER BY budgets.start_date"
+         {} \
+         ORDER BY budgets.start_date, budgets.id \
+         LIMIT {} OFFSET {offset}",
+        archived_budget_condition(include_archived),
+        limit + 1,
     );
 
-    let loaded_budgets = sql_query(&query).load::<Budget>(db_connection)?;
-    let mut loaded_categories = Category::belonging_to(&loaded_budgets)
-        .order(category_fields::id.asc())
-        .load::<Category>(db_connection)?
-        .grouped_by(&loaded_budgets)
-        .into_iter();
-    let mut loaded_entries = Entry::belonging_to(&loaded_budgets)
-        .order(entry_fields::date.asc())
-        .load::<Entry>(db_connection)?
-        .grouped_by(&loaded_budgets)
-        .into_iter();
-
-    let mut output_budgets = Vec::new();
-
-    for budget in loaded_budgets.into_iter() {
-        let output_budget = OutputBudget {
-            id: budget.id,
-            is_shared: budget.is_shared,
-            is_private: budget.is_private,
-            is_deleted: budget.is_deleted,
-            name: budget.name,
-            description: budget.description,
-            categories: loaded_categories
-                .next()
-                .expect("Failed to fetch all categories for budget"),
-            entries: loaded_entries
-                .next()
-                .expect("Failed to fetch all entries for budget"),
-            start_date: budget.start_date,
-            end_date: budget.end_date,
-            latest_entry_time: budget.latest_entry_time,
-            modified_timestamp: budget.modified_timestamp,
-            created_timestamp: budget.created_timestamp,
-        };
-
-        output_budgets.push(output_budget);
-    }
-
-    Ok(output_budgets)
+    load_budget_page(db_connection, &query, limit)
 }
 
 pub fn get_all_budgets_for_user_between_dates(
@@ -116,9 +129,12 @@ pub fn get_all_budgets_for_user_between_dates(
     user_id: Uuid,
     start_date: NaiveDate,
     end_date: NaiveDate,
-) -> Result<Vec<OutputBudget>, diesel::result::Error> {
-    // The use of this raw(ish) query is safe because the user_id comes from a signed token and the
-    // dates are type-checked when they are deserialized.
+    include_archived: bool,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputBudgetPage, diesel::result::Error> {
+    // The use of this raw(ish) query is safe because the user_id comes from a signed token, the
+    // dates are type-checked when they are deserialized, and the limit and offset are integers.
     //
     // BEWARE of using this function when either the user_id or the dates come as input directly
     // from the client.
@@ -129,10 +145,36 @@ pub fn get_all_budgets_for_user_between_dates(
          AND user_budgets.budget_id = budgets.id \
          AND budgets.end_date >= '{start_date}' \
          AND budgets.start_date <= '{end_date}' \
-         ORDER BY budgets.start_date"
+         {} \
+         ORDER BY budgets.start_date, budgets.id \
+         LIMIT {} OFFSET {offset}",
+        archived_budget_condition(include_archived),
+        limit + 1,
     );
 
-    let loaded_budgets = sql_query(&query).load::<Budget>(db_connection)?;
+    load_budget_page(db_connection, &query, limit)
+}
+
+fn archived_budget_condition(include_archived: bool) -> &'static str {
+    if include_archived {
+        ""
+    } else {
+        "AND NOT budgets.is_archived"
+    }
+}
+
+// Expects a query that selects one more budget than the page size so it can tell whether another
+// page follows
+fn load_budget_page(
+    db_connection: &DbConnection,
+    query: &str,
+    limit: i64,
+) -> Result<OutputBudgetPage, diesel::result::Error> {
+    let mut loaded_budgets = sql_query(query).load::<Budget>(db_connection)?;
+
+    let has_more = loaded_budgets.len() as i64 > limit;
+    loaded_budgets.truncate(limit as usize);
+
     let mut loaded_categories = Category::belonging_to(&loaded_budgets)
         .order(category_fields::id.asc())
         .load::<Category>(db_connection)?
@@ -152,6 +194,9 @@ pub fn get_all_budgets_for_user_between_dates(
             is_shared: budget.is_shared,
             is_private: budget.is_private,
             is_deleted: budget.is_deleted,
+            is_tracking_only: budget.is_tracking_only,
+            is_envelope: budget.is_envelope,
+            is_archived: budget.is_archived,
             name: budget.name,
             description: budget.description,
             categories: loaded_categories
@@ -170,7 +215,66 @@ pub fn get_all_budgets_for_user_between_dates(
         output_budgets.push(output_budget);
     }
 
-    Ok(output_budgets)
+    Ok(OutputBudgetPage {
+        budgets: output_budgets,
+        has_more,
+    })
+}
+
+pub fn get_entry(
+    db_connection: &DbConnection,
+    entry_id: Uuid,
+) -> Result<Entry, diesel::result::Error> {
+    entries.find(entry_id).first::<Entry>(db_connection)
+}
+
+pub fn get_entries_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputEntryPage, diesel::result::Error> {
+    let mut loaded_entries = entries
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .order((entry_fields::date.asc(), entry_fields::id.asc()))
+        .limit(limit + 1)
+        .offset(offset)
+        .load::<Entry>(db_connection)?;
+
+    let has_more = loaded_entries.len() as i64 > limit;
+    loaded_entries.truncate(limit as usize);
+
+    Ok(OutputEntryPage {
+        entries: loaded_entries,
+        has_more,
+    })
+}
+
+// Pages by the last entry returned rather than by offset so each chunk of a large export costs the
+// same as the first. Deleted entries are left out.
+pub fn get_live_entries_for_budget_after(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    after: Option<(NaiveDate, Uuid)>,
+    limit: i64,
+) -> Result<Vec<Entry>, diesel::result::Error> {
+    let mut query = entries
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .into_boxed();
+
+    if let Some((date, id)) = after {
+        query = query.filter(
+            entry_fields::date
+                .gt(date)
+                .or(entry_fields::date.eq(date).and(entry_fields::id.gt(id))),
+        );
+    }
+
+    query
+        .order((entry_fields::date.asc(), entry_fields::id.asc()))
+        .limit(limit)
+        .load::<Entry>(db_connection)
 }
 
 pub fn check_user_in_budget(
@@ -196,13 +300,30 @@ pub fn check_user_in_budget(
     Ok(association_exists)
 }
 
+// Returns None if the user isn't in the budget. A role this server doesn't know is treated as
+// viewing only.
+pub fn get_user_budget_role(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    budget_id: Uuid,
+) -> Result<Option<BudgetRole>, diesel::result::Error> {
+    let role = user_budgets
+        .select(user_budget_fields::role)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::budget_id.eq(budget_id))
+        .first::<i16>(db_connection)
+        .optional()?;
+
+    Ok(role.map(|r| BudgetRole::try_from(r).unwrap_or(BudgetRole::Viewer)))
+}
+
 pub fn create_budget(
     db_connection: &DbConnection,
     budget_data: &web::Json<InputBudget>,
     user_id: Uuid,
 ) -> Result<OutputBudget, diesel::result::Error> {
     let current_time = chrono::Utc::now().naive_utc();
-    let budget_id = Uuid::new_v4();
+    let budget_id = record_id::generate();
 
     let description = budget_data.description.as_deref();
 
@@ -218,48 +339,67 @@ pub fn create_budget(
         latest_entry_time: current_time,
         modified_timestamp: current_time,
         created_timestamp: current_time,
+        is_tracking_only: budget_data.is_tracking_only,
+        is_envelope: budget_data.is_envelope,
+        is_archived: false,
     };
 
-    let budget = dsl::insert_into(budgets)
-        .values(&new_budget)
-        .get_result::<Budget>(db_connection)?;
-
-    let new_user_budget_association = NewUserBudget {
-        created_timestamp: current_time,
-        user_id: user_id,
-        budget_id,
-    };
-
-    dsl::insert_into(user_budgets)
-        .values(&new_user_budget_association)
-        .execute(db_connection)?;
-
-    let mut budget_categories = Vec::new();
-
-    for category in &budget_data.categories {
-        let new_category = NewCategory {
-            budget_id,
-            is_deleted: false,
-            id: category.id,
-            name: &category.name,
-            limit_cents: category.limit_cents,
-            color: &category.color,
-            modified_timestamp: budget.modified_timestamp,
-            created_timestamp: budget.created_timestamp,
-        };
+    // A category with a duplicate ID fails the insert, which takes the budget down with it
+    let (budget, inserted_categories) =
+        db_connection.transaction::<_, diesel::result::Error, _>(|| {
+            let budget = dsl::insert_into(budgets)
+                .values(&new_budget)
+                .get_result::<Budget>(db_connection)?;
+
+            let new_user_budget_association = NewUserBudget {
+                created_timestamp: current_time,
+                user_id: user_id,
+                budget_id,
+                role: BudgetRole::Owner as i16,
+            };
+
+            dsl::insert_into(user_budgets)
+                .values(&new_user_budget_association)
+                .execute(db_connection)?;
+
+            let mut budget_categories = Vec::new();
+
+            for category in &budget_data.categories {
+                let new_category = NewCategory {
+                    budget_id,
+                    is_deleted: false,
+                    id: category.id,
+                    name: &category.name,
+                    // Limits sent for a tracking-only budget are discarded rather than left to go
+                    // stale
+                    limit_cents: if budget_data.is_tracking_only {
+                        0
+                    } else {
+                        category.limit_cents
+                    },
+                    color: &category.color,
+                    modified_timestamp: budget.modified_timestamp,
+                    created_timestamp: budget.created_timestamp,
+                };
+
+                budget_categories.push(new_category);
+            }
 
-        budget_categories.push(new_category);
-    }
+            let inserted_categories = dsl::insert_into(categories)
+                .values(budget_categories)
+                .get_results::<Category>(db_connection)?;
 
-    let inserted_categories = dsl::insert_into(categories)
-        .values(budget_categories)
-        .get_results::<Category>(db_connection)?;
+            Ok((budget, inserted_categories))
+        })?;
 
     let output_budget = OutputBudget {
         id: budget.id,
         is_shared: budget.is_shared,
         is_private: budget.is_private,
         is_deleted: budget.is_deleted,
+        is_tracking_only: budget.is_tracking_only,
+        is_envelope: budget.is_envelope,
+        is_archived: budget.is_archived,
         name: budget.name,
         description: budget.description,
         categories: inserted_categories,
@@ -278,163 +418,181 @@ pub fn edit_budget(
     db_connection: &DbConnection,
     edited_budget_data: &web::Json<InputEditBudget>,
 ) -> Result<(), diesel::result::Error> {
-    match dsl::update(budgets.filter(budget_fields::id.eq(edited_budget_data.id)))
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        dsl::update(budgets.filter(budget_fields::id.eq(edited_budget_data.id)))
+            .set((
+                budget_fields::name.eq(&edited_budget_data.name),
+                budget_fields::description.eq(&edited_budget_data.description),
+                budget_fields::start_date.eq(&edited_budget_data.start_date),
+                budget_fields::end_date.eq(&edited_budget_data.end_date),
+            ))
+            .execute(db_connection)?;
+
+        // The new date range may take in different entries
+        category_total::recompute_for_budget(db_connection, edited_budget_data.id)
+    })
+}
+
+pub fn set_budget_archived(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    is_archived: bool,
+) -> Result<usize, diesel::result::Error> {
+    dsl::update(budgets.find(budget_id))
         .set((
-            budget_fields::name.eq(&edited_budget_data.name),
-            budget_fields::description.eq(&edited_budget_data.description),
-            budget_fields::start_date.eq(&edited_budget_data.start_date),
-            budget_fields::end_date.eq(&edited_budget_data.end_date),
+            budget_fields::is_archived.eq(is_archived),
+            budget_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
         ))
         .execute(db_connection)
-    {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
-    }
 }
 
-pub fn invite_user(
+pub fn add_user(
     db_connection: &DbConnection,
     budget_id: Uuid,
-    invitee_user_id: Uuid,
-    sharer_user_id: Uuid,
+    user_id: Uuid,
+    role: BudgetRole,
 ) -> Result<usize, diesel::result::Error> {
-    let budget_share_event = NewBudgetShareEvent {
-        id: Uuid::new_v4(),
-        recipient_user_id: invitee_user_id,
-        sharer_user_id,
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_user_budget_association = NewUserBudget {
+        created_timestamp: current_time,
+        user_id,
         budget_id,
-        accepted: false,
-        share_timestamp: chrono::Utc::now().naive_utc(),
-        accepted_declined_timestamp: None,
+        role: role as i16,
     };
 
-    dsl::insert_into(budget_share_events)
-        .values(&budget_share_event)
+    dsl::insert_into(user_budgets)
+        .values(&new_user_budget_association)
         .execute(db_connection)
 }
 
-pub fn delete_invitation(
+// If the last owner leaves, the longest-standing remaining member becomes the owner
+pub fn remove_user(
     db_connection: &DbConnection,
-    invitation_id: Uuid,
-    sharer_user_id: Uuid,
+    budget_id: Uuid,
+    user_id: Uuid,
 ) -> Result<usize, diesel::result::Error> {
-    diesel::delete(
-        budget_share_events
-            .find(invitation_id)
-            .filter(budget_share_event_fields::sharer_user_id.eq(sharer_user_id)),
-    )
-    .execute(db_connection)
-}
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        lock_budget_members(db_connection, budget_id)?;
 
-pub fn mark_invitation_accepted(
-    db_connection: &DbConnection,
-    invitation_id: Uuid,
-    recipient_user_id: Uuid,
-) -> Result<usize, diesel::result::Error> {
-    diesel::update(
-        budget_share_events
-            .find(invitation_id)
-            .filter(budget_share_event_fields::recipient_user_id.eq(recipient_user_id)),
-    )
-    .set((
-        budget_share_event_fields::accepted.eq(true),
-        budget_share_event_fields::accepted_declined_timestamp.eq(chrono::Utc::now().naive_utc()),
-    ))
-    .execute(db_connection)
-}
+        let removed_count = diesel::delete(
+            user_budgets
+                .filter(user_budget_fields::user_id.eq(user_id))
+                .filter(user_budget_fields::budget_id.eq(budget_id)),
+        )
+        .execute(db_connection)?;
 
-pub fn mark_invitation_declined(
-    db_connection: &DbConnection,
-    invitation_id: Uuid,
-    recipient_user_id: Uuid,
-) -> Result<usize, diesel::result::Error> {
-    diesel::update(
-        budget_share_events
-            .find(invitation_id)
-            .filter(budget_share_event_fields::recipient_user_id.eq(recipient_user_id)),
-    )
-    .set((
-        budget_share_event_fields::accepted.eq(false),
-        budget_share_event_fields::accepted_declined_timestamp.eq(chrono::Utc::now().naive_utc()),
-    ))
-    .execute(db_connection)
-}
+        let owner_count = user_budgets
+            .filter(user_budget_fields::budget_id.eq(budget_id))
+            .filter(user_budget_fields::role.eq(BudgetRole::Owner as i16))
+            .count()
+            .get_result::<i64>(db_connection)?;
+
+        if owner_count == 0 {
+            let next_owner_id = user_budgets
+                .select(user_budget_fields::id)
+                .filter(user_budget_fields::budget_id.eq(budget_id))
+                .order((
+                    user_budget_fields::created_timestamp.asc(),
+                    user_budget_fields::id.asc(),
+                ))
+                .first::<i32>(db_connection)
+                .optional()?;
+
+            if let Some(next_owner_id) = next_owner_id {
+                diesel::update(user_budgets.find(next_owner_id))
+                    .set(user_budget_fields::role.eq(BudgetRole::Owner as i16))
+                    .execute(db_connection)?;
+            }
+        }
 
-pub fn get_all_pending_invitations_for_user(
-    db_connection: &DbConnection,
-    user_id: Uuid,
-) -> Result<Vec<BudgetShareEvent>, diesel::result::Error> {
-    budget_share_events
-        .filter(budget_share_event_fields::recipient_user_id.eq(user_id))
-        .filter(budget_share_event_fields::accepted_declined_timestamp.is_null())
-        .order(budget_share_event_fields::share_timestamp.asc())
-        .load::<BudgetShareEvent>(db_connection)
+        Ok(removed_count)
+    })
 }
 
-pub fn get_all_pending_invitations_made_by_user(
+// Returns None without changing anything if the change would leave the budget without an owner.
+// Returns Some(0) if the user isn't in the budget.
+pub fn set_user_role(
     db_connection: &DbConnection,
+    budget_id: Uuid,
     user_id: Uuid,
-) -> Result<Vec<BudgetShareEvent>, diesel::result::Error> {
-    budget_share_events
-        .filter(budget_share_event_fields::sharer_user_id.eq(user_id))
-        .filter(budget_share_event_fields::accepted_declined_timestamp.is_null())
-        .order(budget_share_event_fields::share_timestamp.asc())
-        .load::<BudgetShareEvent>(db_connection)
-}
+    role: BudgetRole,
+) -> Result<Option<usize>, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        lock_budget_members(db_connection, budget_id)?;
+
+        if role != BudgetRole::Owner {
+            let other_owner_count = user_budgets
+                .filter(user_budget_fields::budget_id.eq(budget_id))
+                .filter(user_budget_fields::user_id.ne(user_id))
+                .filter(user_budget_fields::role.eq(BudgetRole::Owner as i16))
+                .count()
+                .get_result::<i64>(db_connection)?;
+
+            if other_owner_count == 0 {
+                return Ok(None);
+            }
+        }
 
-pub fn get_invitation(
-    db_connection: &DbConnection,
-    invitation_id: Uuid,
-    user_id: Uuid,
-) -> Result<BudgetShareEvent, diesel::result::Error> {
-    budget_share_events
-        .find(invitation_id)
-        .filter(
-            budget_share_event_fields::sharer_user_id
-                .eq(user_id)
-                .or(budget_share_event_fields::recipient_user_id.eq(user_id)),
+        diesel::update(
+            user_budgets
+                .filter(user_budget_fields::user_id.eq(user_id))
+                .filter(user_budget_fields::budget_id.eq(budget_id)),
         )
-        .first::<BudgetShareEvent>(db_connection)
+        .set(user_budget_fields::role.eq(role as i16))
+        .execute(db_connection)
+        .map(Some)
+    })
 }
 
-pub fn add_user(
+// Keeps concurrent role changes and departures from leaving a budget without an owner
+fn lock_budget_members(
     db_connection: &DbConnection,
     budget_id: Uuid,
-    user_id: Uuid,
-) -> Result<usize, diesel::result::Error> {
-    let current_time = chrono::Utc::now().naive_utc();
+) -> Result<(), diesel::result::Error> {
+    user_budgets
+        .filter(user_budget_fields::budget_id.eq(budget_id))
+        .for_update()
+        .execute(db_connection)?;
 
-    let new_user_budget_association = NewUserBudget {
-        created_timestamp: current_time,
-        user_id,
-        budget_id,
-    };
+    Ok(())
+}
 
-    dsl::insert_into(user_budgets)
-        .values(&new_user_budget_association)
+pub fn count_users_remaining_in_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    user_budgets
+        .filter(user_budget_fields::budget_id.eq(budget_id))
         .execute(db_connection)
 }
 
-pub fn remove_user(
+pub fn get_budget_member_ids(
     db_connection: &DbConnection,
     budget_id: Uuid,
-    user_id: Uuid,
-) -> Result<usize, diesel::result::Error> {
-    diesel::delete(
-        user_budgets
-            .filter(user_budget_fields::user_id.eq(user_id))
-            .filter(user_budget_fields::budget_id.eq(budget_id)),
-    )
-    .execute(db_connection)
+) -> Result<Vec<Uuid>, diesel::result::Error> {
+    user_budgets
+        .select(user_budget_fields::user_id)
+        .filter(user_budget_fields::budget_id.eq(budget_id))
+        .load::<Uuid>(db_connection)
 }
 
-pub fn count_users_remaining_in_budget(
+// A budget can have several owners. The primary one, whose currency the budget's limits are in, is
+// the owner who has belonged to it the longest. That is the user who created it unless they have
+// since left the budget.
+pub fn get_budget_owner_id(
     db_connection: &DbConnection,
     budget_id: Uuid,
-) -> Result<usize, diesel::result::Error> {
+) -> Result<Uuid, diesel::result::Error> {
     user_budgets
+        .select(user_budget_fields::user_id)
         .filter(user_budget_fields::budget_id.eq(budget_id))
-        .execute(db_connection)
+        .filter(user_budget_fields::role.eq(BudgetRole::Owner as i16))
+        .order((
+            user_budget_fields::created_timestamp.asc(),
+            user_budget_fields::id.asc(),
+        ))
+        .first::<Uuid>(db_connection)
 }
 
 pub fn delete_budget(
@@ -444,13 +602,129 @@ pub fn delete_budget(
     diesel::delete(budgets.find(budget_id)).execute(db_connection)
 }
 
+// Takes the user out of the budget and lets those affected know. `remover_user_id` is None when the
+// user is leaving on their own, in which case the owners are told. A budget with no members left is
+// deleted. Returns the number of memberships removed.
+pub fn remove_member(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    user_id: Uuid,
+    remover_user_id: Option<Uuid>,
+) -> Result<usize, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let removed_count = remove_user(db_connection, budget_id, user_id)?;
+
+        if removed_count == 0 {
+            return Ok(0);
+        }
+
+        if count_users_remaining_in_budget(db_connection, budget_id)? == 0 {
+            delete_budget(db_connection, budget_id)?;
+            return Ok(removed_count);
+        }
+
+        let current_time = chrono::Utc::now().naive_utc();
+        let mut notifications = Vec::new();
+
+        match remover_user_id {
+            Some(remover_user_id) => {
+                let associated_data = serde_json::to_string(&RemovedFromBudgetData {
+                    budget_id,
+                    remover_user_id,
+                })
+                .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+                notifications.push((
+                    user_id,
+                    NotificationType::RemovedFromBudget,
+                    "Removed from budget",
+                    "You have been removed from a shared budget.",
+                    associated_data,
+                ));
+            }
+            None => {
+                let associated_data = serde_json::to_string(&MemberLeftBudgetData {
+                    budget_id,
+                    member_user_id: user_id,
+                })
+                .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+                // Includes anyone who just became an owner because the last one left
+                let owner_ids = user_budgets
+                    .select(user_budget_fields::user_id)
+                    .filter(user_budget_fields::budget_id.eq(budget_id))
+                    .filter(user_budget_fields::role.eq(BudgetRole::Owner as i16))
+                    .load::<Uuid>(db_connection)?;
+
+                for owner_id in owner_ids {
+                    notifications.push((
+                        owner_id,
+                        NotificationType::MemberLeftBudget,
+                        "Member left budget",
+                        "A member has left one of your shared budgets.",
+                        associated_data.clone(),
+                    ));
+                }
+            }
+        }
+
+        let new_notifications = notifications
+            .iter()
+            .map(
+                |(recipient_id, notification_type, alt_title, alt_message, associated_data)| {
+                    NewUserNotification {
+                        id: record_id::generate(),
+                        user_id: *recipient_id,
+                        is_unread: true,
+                        is_pristine: true,
+                        is_deleted: false,
+                        notification_type: i16::from(*notification_type),
+                        alt_title,
+                        alt_message,
+                        associated_data: Some(associated_data),
+                        modified_timestamp: current_time,
+                        created_timestamp: current_time,
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        push::trace_queued_pushes(db_connection)?;
+        dsl::insert_into(user_notifications)
+            .values(&new_notifications)
+            .execute(db_connection)?;
+
+        Ok(removed_count)
+    })
+}
+
+// Fails with an error `category_total::is_hard_cap_violation` recognizes if the entry would take a
+// hard-capped category over its limit
 pub fn create_entry(
     db_connection: &DbConnection,
     entry_data: &web::Json<InputEntry>,
     user_id: Uuid,
+) -> Result<Entry, diesel::result::Error> {
+    insert_entry(db_connection, entry_data, user_id, true)
+}
+
+// Only for a budget's owners, who may go over a category's hard cap
+pub fn create_entry_over_hard_cap(
+    db_connection: &DbConnection,
+    entry_data: &web::Json<InputEntry>,
+    user_id: Uuid,
+) -> Result<Entry, diesel::result::Error> {
+    insert_entry(db_connection, entry_data, user_id, false)
+}
+
+fn insert_entry(
+    db_connection: &DbConnection,
+    entry_data: &web::Json<InputEntry>,
+    user_id: Uuid,
+    enforce_hard_cap: bool,
 ) -> Result<Entry, diesel::result::Error> {
     let current_time = chrono::Utc::now().naive_utc();
-    let entry_id = Uuid::new_v4();
+    let entry_id = entry_data.id.unwrap_or_else(record_id::generate);
 
     let name = entry_data.name.as_deref();
     let note = entry_data.note.as_deref();
@@ -467,592 +741,931 @@ pub fn create_entry(
         note,
         modified_timestamp: current_time,
         created_timestamp: current_time,
+        recurring_entry_id: None,
+        import_batch_id: None,
+        tax_cents: entry_data.tax_cents,
+        tip_cents: entry_data.tip_cents,
+        is_deductible: entry_data.is_deductible,
     };
 
-    let entry = dsl::insert_into(entries)
-        .values(&new_entry)
-        .get_result::<Entry>(db_connection)?;
-    diesel::update(budgets.find(new_entry.budget_id))
-        .set(budget_fields::latest_entry_time.eq(current_time))
-        .execute(db_connection)?;
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let entry = dsl::insert_into(entries)
+            .values(&new_entry)
+            .get_result::<Entry>(db_connection)?;
+        diesel::update(budgets.find(new_entry.budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
+
+        if enforce_hard_cap {
+            category_total::add_entries_within_hard_caps(db_connection, &[entry.id])?;
+        } else {
+            category_total::add_entries(db_connection, &[entry.id])?;
+        }
 
-    Ok(entry)
+        audit_log::record(
+            db_connection,
+            entry.budget_id,
+            user_id,
+            AuditAction::EntryAdded,
+            None::<&Entry>,
+            Some(&entry),
+        )?;
+
+        Ok(entry)
+    })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// Category IDs are only unique within a budget. Removed categories keep their IDs so the entries
+// that point at them stay valid, so a new category gets the ID after every one the budget has ever
+// had. Returns None if the budget has run out of IDs.
+pub fn add_category(
+    db_connection: &DbConnection,
+    category_data: &InputNewCategory,
+) -> Result<Option<Category>, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
 
-    use actix_web::web;
-    use chrono::NaiveDate;
-    use diesel::ExpressionMethods;
-    use rand::prelude::*;
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // Locking the budget keeps concurrent requests from being given the same ID
+        let is_tracking_only = budgets
+            .find(category_data.budget_id)
+            .select(budget_fields::is_tracking_only)
+            .for_update()
+            .first::<bool>(db_connection)?;
+
+        let max_id = categories
+            .select(dsl::max(category_fields::id))
+            .filter(category_fields::budget_id.eq(category_data.budget_id))
+            .first::<Option<i16>>(db_connection)?;
+
+        let next_id = match max_id {
+            Some(id) => match id.checked_add(1) {
+                Some(next_id) => next_id,
+                None => return Ok(None),
+            },
+            None => 0,
+        };
 
-    use crate::env;
-    use crate::handlers::request_io::{InputBudget, InputCategory, InputUser, OutputBudget};
-    use crate::models::budget::Budget;
-    use crate::models::budget_share_event::BudgetShareEvent;
-    use crate::models::category::Category;
-    use crate::models::user::User;
-    use crate::models::user_budget::UserBudget;
-    use crate::schema::budget_share_events as budget_share_event_fields;
-    use crate::schema::budget_share_events::dsl::budget_share_events;
-    use crate::schema::budgets::dsl::budgets;
-    use crate::schema::categories as category_fields;
-    use crate::schema::categories::dsl::categories;
-    use crate::schema::entries as entry_fields;
-    use crate::schema::user_budgets as user_budget_fields;
-    use crate::schema::user_budgets::dsl::user_budgets;
-    use crate::utils::db::user;
+        let new_category = NewCategory {
+            budget_id: category_data.budget_id,
+            is_deleted: false,
+            id: next_id,
+            name: &category_data.name,
+            limit_cents: if is_tracking_only {
+                0
+            } else {
+                category_data.limit_cents
+            },
+            color: &category_data.color,
+            modified_timestamp: current_time,
+            created_timestamp: current_time,
+        };
 
-    pub struct UserAndBudget {
-        user: User,
-        budget: OutputBudget,
-    }
+        dsl::insert_into(categories)
+            .values(&new_category)
+            .get_result::<Category>(db_connection)
+            .map(Some)
+    })
+}
 
-    pub fn generate_user_and_budget(
-        db_connection: &DbConnection,
-    ) -> Result<UserAndBudget, diesel::result::Error> {
-        let user_number = rand::thread_rng().gen_range::<u32, _>(10_000_000..100_000_000);
-        let new_user = InputUser {
-            email: format!("test_user{}@test.com", user_number),
-            password: String::from("g&eWi3#oIKDW%cTu*5*2"),
-            first_name: format!("Test-{}", user_number),
-            last_name: format!("User-{}", user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            currency: String::from("USD"),
-        };
-
-        let new_user_json = web::Json(new_user);
-        let created_user = user::create_user(db_connection, &new_user_json)?;
-
-        let category0 = InputCategory {
-            id: 0,
-            name: format!("First Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#ff11ee"),
-        };
-
-        let category1 = InputCategory {
-            id: 1,
-            name: format!("Second Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#112233"),
-        };
-
-        let budget_categories = vec![category0, category1];
-
-        let new_budget = InputBudget {
-            name: format!("Test Budget {user_number}"),
-            description: Some(format!(
-                "This is a description of Test Budget {user_number}.",
-            )),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(
-                2021,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            end_date: NaiveDate::from_ymd(
-                2023,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-        };
-
-        let new_budget_json = web::Json(new_budget.clone());
-        let created_budget = create_budget(db_connection, &new_budget_json, created_user.id)?;
-
-        Ok(UserAndBudget {
-            user: created_user,
-            budget: created_budget,
-        })
-    }
-
-    #[actix_rt::test]
-    async fn test_create_budget() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
-
-        let user_number = rand::thread_rng().gen_range::<u32, _>(10_000_000..100_000_000);
-        let new_user = InputUser {
-            email: format!("test_user{}@test.com", user_number),
-            password: String::from("g&eWi3#oIKDW%cTu*5*2"),
-            first_name: format!("Test-{}", user_number),
-            last_name: format!("User-{}", user_number),
-            date_of_birth: NaiveDate::from_ymd(
-                rand::thread_rng().gen_range(1950..=2020),
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            currency: String::from("USD"),
-        };
-
-        let new_user_json = web::Json(new_user);
-        let created_user = user::create_user(&db_connection, &new_user_json).unwrap();
-
-        let category0 = InputCategory {
-            id: 0,
-            name: format!("First Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#ff11ee"),
-        };
-
-        let category1 = InputCategory {
-            id: 1,
-            name: format!("Second Random Category {user_number}"),
-            limit_cents: rand::thread_rng().gen_range(100..500),
-            color: String::from("#112233"),
+// Only a change to the limit goes in the budget's audit log
+pub fn edit_category(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    category_data: &InputEditCategory,
+) -> Result<usize, diesel::result::Error> {
+    let category_to_edit = categories
+        .filter(category_fields::budget_id.eq(category_data.budget_id))
+        .filter(category_fields::id.eq(category_data.category_id))
+        .filter(category_fields::is_deleted.eq(false));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let is_tracking_only = budgets
+            .find(category_data.budget_id)
+            .select(budget_fields::is_tracking_only)
+            .first::<bool>(db_connection)?;
+
+        let previous_category = match category_to_edit
+            .for_update()
+            .first::<Category>(db_connection)
+            .optional()?
+        {
+            Some(c) => c,
+            None => return Ok(0),
         };
 
-        let budget_categories = vec![category0, category1];
+        let edited_category = diesel::update(category_to_edit)
+            .set((
+                category_fields::name.eq(&category_data.name),
+                category_fields::limit_cents.eq(if is_tracking_only {
+                    0
+                } else {
+                    category_data.limit_cents
+                }),
+                category_fields::color.eq(&category_data.color),
+                category_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result::<Category>(db_connection)?;
+
+        if edited_category.limit_cents != previous_category.limit_cents {
+            audit_log::record(
+                db_connection,
+                edited_category.budget_id,
+                user_id,
+                AuditAction::CategoryLimitChanged,
+                Some(&previous_category),
+                Some(&edited_category),
+            )?;
+        }
 
-        let new_budget = InputBudget {
-            name: format!("Test Budget {user_number}"),
-            description: Some(format!(
-                "This is a description of Test Budget {user_number}.",
-            )),
-            categories: budget_categories.clone(),
-            start_date: NaiveDate::from_ymd(
-                2021,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-            end_date: NaiveDate::from_ymd(
-                2023,
-                rand::thread_rng().gen_range(1..=12),
-                rand::thread_rng().gen_range(1..=28),
-            ),
-        };
+        Ok(1)
+    })
+}
 
-        let new_budget_json = web::Json(new_budget.clone());
-        create_budget(&db_connection, &new_budget_json, created_user.id).unwrap();
+// Entries in the category keep pointing at it, so they still show the category's name
+pub fn remove_category(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    category_id: i16,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        categories
+            .filter(category_fields::budget_id.eq(budget_id))
+            .filter(category_fields::id.eq(category_id))
+            .filter(category_fields::is_deleted.eq(false)),
+    )
+    .set((
+        category_fields::is_deleted.eq(true),
+        category_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
 
-        let created_user_budget_associations = user_budgets
-            .filter(user_budget_fields::user_id.eq(created_user.id))
-            .load::<UserBudget>(&db_connection)
-            .unwrap();
+// Deleted categories are left out of an export
+pub fn get_live_categories_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<Category>, diesel::result::Error> {
+    categories
+        .filter(category_fields::budget_id.eq(budget_id))
+        .filter(category_fields::is_deleted.eq(false))
+        .order(category_fields::id.asc())
+        .load::<Category>(db_connection)
+}
 
-        assert_eq!(created_user_budget_associations.len(), 1);
-        assert!(
-            created_user_budget_associations[0].created_timestamp < chrono::Utc::now().naive_utc()
-        );
-        assert_eq!(created_user_budget_associations[0].user_id, created_user.id);
+// Imported categories keep the IDs they were exported with. An ID already used in the budget, even
+// by a deleted category, fails the whole import.
+pub fn import_categories(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    imported_categories: &[InputCategory],
+) -> Result<Vec<Category>, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
 
-        let budget_id = created_user_budget_associations[0].budget_id;
-        let budget = budgets
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let is_tracking_only = budgets
             .find(budget_id)
-            .first::<Budget>(&db_connection)
-            .unwrap();
-
-        assert_eq!(budget.name, new_budget.name);
-        assert_eq!(budget.description, new_budget.description);
-        assert_eq!(budget.start_date, new_budget.start_date);
-        assert_eq!(budget.end_date, new_budget.end_date);
+            .select(budget_fields::is_tracking_only)
+            .for_update()
+            .first::<bool>(db_connection)?;
+
+        let new_categories = imported_categories
+            .iter()
+            .map(|category| NewCategory {
+                budget_id,
+                is_deleted: false,
+                id: category.id,
+                name: &category.name,
+                limit_cents: if is_tracking_only {
+                    0
+                } else {
+                    category.limit_cents
+                },
+                color: &category.color,
+                modified_timestamp: current_time,
+                created_timestamp: current_time,
+            })
+            .collect::<Vec<_>>();
+
+        dsl::insert_into(categories)
+            .values(new_categories)
+            .get_results::<Category>(db_connection)
+    })
+}
 
-        let saved_categories = categories
+pub fn set_category_hard_cap(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    category_id: i16,
+    is_hard_capped: bool,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        categories
             .filter(category_fields::budget_id.eq(budget_id))
-            .load::<Category>(&db_connection)
-            .unwrap();
+            .filter(category_fields::id.eq(category_id))
+            .filter(category_fields::is_deleted.eq(false)),
+    )
+    .set((
+        category_fields::is_hard_capped.eq(is_hard_capped),
+        category_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
 
-        assert_eq!(saved_categories[0].id, budget_categories[0].id);
-        assert_eq!(saved_categories[0].name, budget_categories[0].name);
-        assert_eq!(
-            saved_categories[0].limit_cents,
-            budget_categories[0].limit_cents
-        );
-        assert_eq!(saved_categories[0].color, budget_categories[0].color);
+// Returns the category's limit and the amount spent in it so far within the budget's date range, or
+// None if the category isn't hard-capped. Caps have no effect in tracking-only budgets.
+pub fn get_hard_cap_usage(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    category_id: i16,
+) -> Result<Option<(i64, i64)>, diesel::result::Error> {
+    let is_tracking_only = budgets
+        .find(budget_id)
+        .select(budget_fields::is_tracking_only)
+        .first::<bool>(db_connection)?;
+
+    if is_tracking_only {
+        return Ok(None);
     }
 
-    #[actix_rt::test]
-    async fn test_invite_user() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
+    let limit_cents = categories
+        .select(category_fields::limit_cents)
+        .filter(category_fields::budget_id.eq(budget_id))
+        .filter(category_fields::id.eq(category_id))
+        .filter(category_fields::is_deleted.eq(false))
+        .filter(category_fields::is_hard_capped.eq(true))
+        .first::<i64>(db_connection)
+        .optional()?;
+
+    let limit_cents = match limit_cents {
+        Some(l) => l,
+        None => return Ok(None),
+    };
 
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+    let spent_cents = budget_category_totals
+        .select(category_total_fields::spent_cents)
+        .filter(category_total_fields::budget_id.eq(budget_id))
+        .filter(category_total_fields::category.eq(category_id))
+        .first::<i64>(db_connection)
+        .optional()?;
 
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
+    Ok(Some((limit_cents, spent_cents.unwrap_or(0))))
+}
 
-        let budget = created_user_and_budget1.budget.clone();
+// Only entries in budgets the user owns or edits can be edited. Returns the number of entries
+// edited, which is zero if the entry doesn't exist or the user can't access it.
+pub fn edit_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_entry_data: &InputEditEntry,
+) -> Result<usize, diesel::result::Error> {
+    update_entry(db_connection, user_id, edited_entry_data, true)
+}
 
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
+// Only for a budget's owners, who may go over a category's hard cap
+pub fn edit_entry_over_hard_cap(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_entry_data: &InputEditEntry,
+) -> Result<usize, diesel::result::Error> {
+    update_entry(db_connection, user_id, edited_entry_data, false)
+}
 
-        assert_eq!(created_budget_share_events.len(), 0);
+fn update_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_entry_data: &InputEditEntry,
+    enforce_hard_cap: bool,
+) -> Result<usize, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
 
-        invite_user(
-            &db_connection,
-            budget.id,
-            created_user2.id,
-            created_user1.id,
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let previous_entry = entries
+            .find(edited_entry_data.entry_id)
+            .for_update()
+            .first::<Entry>(db_connection)
+            .optional()?;
+
+        let entry_ids = [edited_entry_data.entry_id];
+        category_total::subtract_entries(db_connection, &entry_ids)?;
+
+        let edited_entries = diesel::update(
+            entries
+                .filter(entry_fields::id.eq(edited_entry_data.entry_id))
+                .filter(entry_fields::is_deleted.eq(false))
+                .filter(entry_fields::budget_id.eq_any(user_budget_ids)),
         )
-        .unwrap();
-
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
-
-        assert_eq!(created_budget_share_events.len(), 1);
-
-        assert_eq!(
-            created_budget_share_events[0].recipient_user_id,
-            created_user2.id
-        );
-        assert_eq!(
-            created_budget_share_events[0].sharer_user_id,
-            created_user1.id
-        );
-        assert_eq!(created_budget_share_events[0].budget_id, budget.id);
-        assert_eq!(created_budget_share_events[0].accepted, false);
-
-        assert!(created_budget_share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert_eq!(
-            created_budget_share_events[0].accepted_declined_timestamp,
-            None
-        );
-    }
+        .set((
+            entry_fields::amount_cents.eq(edited_entry_data.amount_cents),
+            entry_fields::date.eq(edited_entry_data.date),
+            entry_fields::name.eq(edited_entry_data.name.as_deref()),
+            entry_fields::category.eq(edited_entry_data.category),
+            entry_fields::note.eq(edited_entry_data.note.as_deref()),
+            entry_fields::tax_cents.eq(edited_entry_data.tax_cents),
+            entry_fields::tip_cents.eq(edited_entry_data.tip_cents),
+            entry_fields::is_deductible.eq(edited_entry_data.is_deductible),
+            entry_fields::modified_timestamp.eq(current_time),
+        ))
+        .get_results::<Entry>(db_connection)?;
+
+        // An edit that adds no spending to a category isn't checked, so an entry in a category
+        // that's already over its cap can still be lowered
+        let adds_spending = !edited_entries.is_empty()
+            && previous_entry.as_ref().is_none_or(|p| {
+                p.category != edited_entry_data.category
+                    || p.date != edited_entry_data.date
+                    || p.amount_cents < edited_entry_data.amount_cents
+            });
+
+        if enforce_hard_cap && adds_spending {
+            category_total::add_entries_within_hard_caps(db_connection, &entry_ids)?;
+        } else {
+            category_total::add_entries(db_connection, &entry_ids)?;
+        }
 
-    #[actix_rt::test]
-    async fn test_delete_invitation() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
+        for entry in edited_entries.iter() {
+            diesel::update(budgets.find(entry.budget_id))
+                .set(budget_fields::latest_entry_time.eq(current_time))
+                .execute(db_connection)?;
+
+            audit_log::record(
+                db_connection,
+                entry.budget_id,
+                user_id,
+                AuditAction::EntryEdited,
+                previous_entry.as_ref(),
+                Some(entry),
+            )?;
+        }
 
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+        Ok(edited_entries.len())
+    })
+}
 
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
+// Soft-deletes the entry by setting `is_deleted` so clients can sync the deletion. Returns the
+// number of entries deleted, which is zero if the entry doesn't exist or the user can't access it.
+pub fn delete_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    entry_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
 
-        let budget = created_user_and_budget1.budget.clone();
-        invite_user(
-            &db_connection,
-            budget.id,
-            created_user2.id,
-            created_user1.id,
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // The entry is added back if the user couldn't delete it
+        category_total::subtract_entries(db_connection, &[entry_id])?;
+
+        let previous_entry = entries
+            .find(entry_id)
+            .first::<Entry>(db_connection)
+            .optional()?;
+
+        let deleted_entries = diesel::update(
+            entries
+                .filter(entry_fields::id.eq(entry_id))
+                .filter(entry_fields::is_deleted.eq(false))
+                .filter(entry_fields::budget_id.eq_any(user_budget_ids)),
         )
-        .unwrap();
-
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
-
-        assert_eq!(created_budget_share_events.len(), 1);
+        .set((
+            entry_fields::is_deleted.eq(true),
+            entry_fields::modified_timestamp.eq(current_time),
+        ))
+        .get_results::<Entry>(db_connection)?;
+
+        category_total::add_entries(db_connection, &[entry_id])?;
+
+        for entry in deleted_entries.iter() {
+            diesel::update(budgets.find(entry.budget_id))
+                .set(budget_fields::latest_entry_time.eq(current_time))
+                .execute(db_connection)?;
+
+            audit_log::record(
+                db_connection,
+                entry.budget_id,
+                user_id,
+                AuditAction::EntryDeleted,
+                previous_entry.as_ref(),
+                None::<&Entry>,
+            )?;
+        }
 
-        delete_invitation(
-            &db_connection,
-            created_budget_share_events[0].id,
-            created_user1.id,
-        )
-        .unwrap();
+        Ok(deleted_entries.len())
+    })
+}
 
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
+fn entries_matching_filter(
+    filter: &InputEntryFilter,
+) -> entry_fields::BoxedQuery<'static, diesel::pg::Pg> {
+    let mut query = entries
+        .filter(entry_fields::budget_id.eq(filter.budget_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .into_boxed();
 
-        assert_eq!(created_budget_share_events.len(), 0);
+    if let Some(start_date) = filter.start_date {
+        query = query.filter(entry_fields::date.ge(start_date));
     }
 
-    #[actix_rt::test]
-    async fn test_mark_invitation_accepted() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
-
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
-
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
-
-        let budget = created_user_and_budget1.budget.clone();
-
-        invite_user(
-            &db_connection,
-            budget.id,
-            created_user2.id,
-            created_user1.id,
-        )
-        .unwrap();
-
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
-
-        assert_eq!(created_budget_share_events.len(), 1);
-
-        mark_invitation_accepted(
-            &db_connection,
-            created_budget_share_events[0].id,
-            created_user2.id,
-        )
-        .unwrap();
+    if let Some(end_date) = filter.end_date {
+        query = query.filter(entry_fields::date.le(end_date));
+    }
 
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
+    if let Some(category) = filter.category {
+        query = query.filter(entry_fields::category.eq(category));
+    }
 
-        assert_eq!(created_budget_share_events.len(), 1);
+    if let Some(import_batch_id) = filter.import_batch_id {
+        query = query.filter(entry_fields::import_batch_id.eq(import_batch_id));
+    }
 
-        assert_eq!(
-            created_budget_share_events[0].recipient_user_id,
-            created_user2.id
-        );
-        assert_eq!(
-            created_budget_share_events[0].sharer_user_id,
-            created_user1.id
-        );
-        assert_eq!(created_budget_share_events[0].budget_id, budget.id);
-        assert_eq!(created_budget_share_events[0].accepted, true);
+    query
+}
 
-        assert!(created_budget_share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(
-            created_budget_share_events[0]
-                .accepted_declined_timestamp
-                .unwrap()
-                < chrono::Utc::now().naive_utc()
-        );
-        assert!(
-            created_budget_share_events[0]
-                .accepted_declined_timestamp
-                .unwrap()
-                > created_budget_share_events[0].share_timestamp
-        );
-    }
+// Returns the number of entries matching the filter along with up to `sample_size` of them
+pub fn preview_entry_deletion(
+    db_connection: &DbConnection,
+    filter: &InputEntryFilter,
+    sample_size: i64,
+) -> Result<(i64, Vec<Entry>), diesel::result::Error> {
+    let matched_count = entries_matching_filter(filter)
+        .count()
+        .get_result::<i64>(db_connection)?;
+
+    let sample = entries_matching_filter(filter)
+        .order((entry_fields::date.asc(), entry_fields::id.asc()))
+        .limit(sample_size)
+        .load::<Entry>(db_connection)?;
 
-    #[actix_rt::test]
-    async fn test_mark_invitation_declined() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
+    Ok((matched_count, sample))
+}
 
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+// Soft-deletes every entry matching the filter, but only if exactly `expected_count` entries
+// match. Returns None without deleting anything if the matching entries have changed.
+pub fn delete_entries_matching_filter(
+    db_connection: &DbConnection,
+    filter: &InputEntryFilter,
+    expected_count: i64,
+) -> Result<Option<usize>, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // Adding an entry updates the budget row, so locking it keeps the matching entries from
+        // changing between the count and the deletion
+        budgets
+            .find(filter.budget_id)
+            .for_update()
+            .execute(db_connection)?;
+
+        let matching_entry_ids = entries_matching_filter(filter)
+            .select(entry_fields::id)
+            .load::<Uuid>(db_connection)?;
+
+        if matching_entry_ids.len() as i64 != expected_count {
+            return Ok(None);
+        }
 
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
+        let current_time = chrono::Utc::now().naive_utc();
 
-        let budget = created_user_and_budget1.budget.clone();
+        category_total::subtract_entries(db_connection, &matching_entry_ids)?;
 
-        invite_user(
-            &db_connection,
-            budget.id,
-            created_user2.id,
-            created_user1.id,
-        )
-        .unwrap();
+        let deleted_count =
+            diesel::update(entries.filter(entry_fields::id.eq_any(&matching_entry_ids)))
+                .set((
+                    entry_fields::is_deleted.eq(true),
+                    entry_fields::modified_timestamp.eq(current_time),
+                ))
+                .execute(db_connection)?;
 
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
+        diesel::update(budgets.find(filter.budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
 
-        assert_eq!(created_budget_share_events.len(), 1);
+        Ok(Some(deleted_count))
+    })
+}
 
-        mark_invitation_declined(
-            &db_connection,
-            created_budget_share_events[0].id,
-            created_user2.id,
-        )
-        .unwrap();
+pub fn get_recent_entries_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    limit: i64,
+) -> Result<Vec<Entry>, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id));
+
+    entries
+        .filter(entry_fields::budget_id.eq_any(user_budget_ids))
+        .filter(entry_fields::is_deleted.eq(false))
+        .order(entry_fields::created_timestamp.desc())
+        .limit(limit)
+        .load::<Entry>(db_connection)
+}
 
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
+// The amounts of the user's latest expenses in the category of the budget, newest first
+pub fn get_recent_expense_amounts(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    budget_id: Uuid,
+    category: i16,
+    limit: i64,
+) -> Result<Vec<i64>, diesel::result::Error> {
+    entries
+        .select(entry_fields::amount_cents)
+        .filter(entry_fields::user_id.eq(user_id))
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::category.eq(category))
+        .filter(entry_fields::is_deleted.eq(false))
+        .filter(entry_fields::amount_cents.gt(0))
+        .order((
+            entry_fields::date.desc(),
+            entry_fields::created_timestamp.desc(),
+        ))
+        .limit(limit)
+        .load::<i64>(db_connection)
+}
 
-        assert_eq!(created_budget_share_events.len(), 1);
+pub fn get_all_entries_for_user_since(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    since: NaiveDate,
+) -> Result<Vec<Entry>, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id));
+
+    entries
+        .filter(entry_fields::budget_id.eq_any(user_budget_ids))
+        .filter(entry_fields::is_deleted.eq(false))
+        .filter(entry_fields::date.ge(since))
+        .order(entry_fields::date.asc())
+        .load::<Entry>(db_connection)
+}
 
-        assert_eq!(
-            created_budget_share_events[0].recipient_user_id,
-            created_user2.id
-        );
-        assert_eq!(
-            created_budget_share_events[0].sharer_user_id,
-            created_user1.id
-        );
-        assert_eq!(created_budget_share_events[0].budget_id, budget.id);
-        assert_eq!(created_budget_share_events[0].accepted, false);
+#[derive(Debug, QueryableByName)]
+pub struct CategorySpending {
+    #[sql_type = "SqlUuid"]
+    pub budget_id: Uuid,
+    #[sql_type = "Varchar"]
+    pub budget_name: String,
+    #[sql_type = "SmallInt"]
+    pub category_id: i16,
+    #[sql_type = "Varchar"]
+    pub category_name: String,
+    #[sql_type = "BigInt"]
+    pub limit_cents: i64,
+    #[sql_type = "BigInt"]
+    pub spent_cents: i64,
+}
 
-        assert!(created_budget_share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(
-            created_budget_share_events[0]
-                .accepted_declined_timestamp
-                .unwrap()
-                < chrono::Utc::now().naive_utc()
-        );
-        assert!(
-            created_budget_share_events[0]
-                .accepted_declined_timestamp
-                .unwrap()
-                > created_budget_share_events[0].share_timestamp
-        );
-    }
+pub fn get_categories_over_threshold_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    threshold_percent: i16,
+) -> Result<Vec<CategorySpending>, diesel::result::Error> {
+    // The use of this raw(ish) query is safe because the user_id comes from the database (via an
+    // API key lookup) and the threshold_percent is an integer.
+    //
+    // BEWARE of using this function when the user_id comes as input directly from the client.
+    let query = format!(
+        "SELECT budgets.id AS budget_id, budgets.name AS budget_name, \
+         categories.id AS category_id, categories.name AS category_name, \
+         categories.limit_cents AS limit_cents, \
+         COALESCE(SUM(entries.amount_cents), 0)::BIGINT AS spent_cents \
+         FROM user_budgets \
+         JOIN budgets ON budgets.id = user_budgets.budget_id \
+         JOIN categories ON categories.budget_id = budgets.id \
+         LEFT JOIN entries ON entries.budget_id = budgets.id \
+         AND entries.category = categories.id \
+         AND entries.is_deleted = FALSE \
+         WHERE user_budgets.user_id = '{user_id}' \
+         AND budgets.is_deleted = FALSE \
+         AND budgets.end_date >= CURRENT_DATE \
+         AND budgets.is_tracking_only = FALSE \
+         AND categories.is_deleted = FALSE \
+         AND categories.limit_cents > 0 \
+         GROUP BY budgets.id, categories.pk \
+         HAVING COALESCE(SUM(entries.amount_cents), 0) * 100 \
+         >= categories.limit_cents * {threshold_percent} \
+         ORDER BY budgets.id, categories.id"
+    );
 
-    #[actix_rt::test]
-    async fn test_get_all_pending_invitations_for_user() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
+    sql_query(&query).load::<CategorySpending>(db_connection)
+}
 
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+#[derive(Debug, QueryableByName)]
+struct CategoryTotal {
+    #[sql_type = "Nullable<SmallInt>"]
+    category_id: Option<i16>,
+    #[sql_type = "Nullable<Varchar>"]
+    category_name: Option<String>,
+    #[sql_type = "Nullable<BigInt>"]
+    limit_cents: Option<i64>,
+    #[sql_type = "BigInt"]
+    spent_cents: i64,
+    #[sql_type = "BigInt"]
+    entry_count: i64,
+    #[sql_type = "BigInt"]
+    tax_cents: i64,
+}
 
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
+// Totals cover entries dated within the budget's date range and are read from the running totals
+// kept in `budget_category_totals`. Entries with no category or whose category has been removed are
+// totaled together in a row with no category ID. Spend rates are
+// averaged over the days of the budget that have passed as of `today`.
+pub fn get_budget_summary(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    today: NaiveDate,
+) -> Result<OutputBudgetSummary, diesel::result::Error> {
+    let (start_date, end_date, is_tracking_only) = budgets
+        .find(budget_id)
+        .filter(budget_fields::is_deleted.eq(false))
+        .select((
+            budget_fields::start_date,
+            budget_fields::end_date,
+            budget_fields::is_tracking_only,
+        ))
+        .first::<(NaiveDate, NaiveDate, bool)>(db_connection)?;
+
+    let category_totals = sql_query(
+        "SELECT budget_categories.id AS category_id, \
+         budget_categories.name AS category_name, \
+         budget_categories.limit_cents AS limit_cents, \
+         COALESCE(SUM(totals.spent_cents), 0)::BIGINT AS spent_cents, \
+         COALESCE(SUM(totals.entry_count), 0)::BIGINT AS entry_count, \
+         COALESCE(SUM(totals.tax_cents), 0)::BIGINT AS tax_cents \
+         FROM (SELECT id, name, limit_cents FROM categories \
+         WHERE budget_id = $1 AND is_deleted = FALSE) AS budget_categories \
+         FULL JOIN (SELECT category, spent_cents, entry_count, tax_cents \
+         FROM budget_category_totals \
+         WHERE budget_id = $1 AND entry_count > 0) AS totals \
+         ON totals.category = budget_categories.id \
+         GROUP BY budget_categories.id, budget_categories.name, budget_categories.limit_cents \
+         ORDER BY budget_categories.id NULLS LAST",
+    )
+    .bind::<SqlUuid, _>(budget_id)
+    .load::<CategoryTotal>(db_connection)?;
 
-        let budget1 = created_user_and_budget1.budget.clone();
-        let budget2 = created_user_and_budget2.budget.clone();
+    let days_elapsed = if today < start_date {
+        0
+    } else {
+        (today.min(end_date) - start_date).num_days() + 1
+    };
 
-        invite_user(
-            &db_connection,
-            budget1.id,
-            created_user2.id,
-            created_user1.id,
-        )
-        .unwrap();
+    let daily_rate = |spent_cents: i64| {
+        if days_elapsed == 0 {
+            0
+        } else {
+            spent_cents / days_elapsed
+        }
+    };
 
-        invite_user(
-            &db_connection,
-            budget2.id,
-            created_user2.id,
-            created_user1.id,
+    let category_summaries = category_totals
+        .into_iter()
+        .map(|t| {
+            let limit_cents = t.limit_cents.filter(|_| !is_tracking_only);
+
+            OutputCategorySummary {
+                category_id: t.category_id,
+                name: t.category_name,
+                limit_cents,
+                spent_cents: t.spent_cents,
+                remaining_cents: limit_cents.map(|l| l - t.spent_cents),
+                tax_cents: t.tax_cents,
+                entry_count: t.entry_count,
+                daily_spend_rate_cents: daily_rate(t.spent_cents),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let spent_cents = category_summaries
+        .iter()
+        .map(|c| c.spent_cents)
+        .sum::<i64>();
+    let tax_cents = category_summaries.iter().map(|c| c.tax_cents).sum::<i64>();
+    let limit_cents = if is_tracking_only {
+        None
+    } else {
+        Some(
+            category_summaries
+                .iter()
+                .filter_map(|c| c.limit_cents)
+                .sum::<i64>(),
         )
-        .unwrap();
+    };
 
-        let share_events =
-            get_all_pending_invitations_for_user(&db_connection, created_user1.id).unwrap();
+    Ok(OutputBudgetSummary {
+        budget_id,
+        start_date,
+        end_date,
+        days_elapsed,
+        limit_cents,
+        spent_cents,
+        remaining_cents: limit_cents.map(|l| l - spent_cents),
+        tax_cents,
+        daily_spend_rate_cents: daily_rate(spent_cents),
+        categories: category_summaries,
+    })
+}
 
-        assert_eq!(share_events.len(), 0);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let share_events =
-            get_all_pending_invitations_for_user(&db_connection, created_user2.id).unwrap();
+    use actix_web::web;
+    use chrono::NaiveDate;
+    use diesel::ExpressionMethods;
+    use rand::prelude::*;
 
-        assert_eq!(share_events.len(), 2);
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputUser, OutputBudget};
+    use crate::models::budget::Budget;
+    use crate::models::category::Category;
+    use crate::models::user::User;
+    use crate::models::user_budget::UserBudget;
+    use crate::schema::budgets::dsl::budgets;
+    use crate::schema::categories as category_fields;
+    use crate::schema::categories::dsl::categories;
+    use crate::schema::entries as entry_fields;
+    use crate::schema::user_budgets as user_budget_fields;
+    use crate::schema::user_budgets::dsl::user_budgets;
+    use crate::utils::db::user;
 
-        assert_eq!(share_events[0].recipient_user_id, created_user2.id);
-        assert_eq!(share_events[0].sharer_user_id, created_user1.id);
-        assert_eq!(share_events[0].budget_id, budget1.id);
-        assert_eq!(share_events[0].accepted, false);
+    pub struct UserAndBudget {
+        user: User,
+        budget: OutputBudget,
+    }
 
-        assert!(share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_events[0].accepted_declined_timestamp.is_none());
+    pub fn generate_user_and_budget(
+        db_connection: &DbConnection,
+    ) -> Result<UserAndBudget, diesel::result::Error> {
+        let user_number = rand::thread_rng().gen_range::<u32, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", user_number),
+            password: String::from("g&eWi3#oIKDW%cTu*5*2"),
+            first_name: format!("Test-{}", user_number),
+            last_name: format!("User-{}", user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
 
-        assert_eq!(share_events[1].recipient_user_id, created_user2.id);
-        assert_eq!(share_events[1].sharer_user_id, created_user1.id);
-        assert_eq!(share_events[1].budget_id, budget2.id);
-        assert_eq!(share_events[1].accepted, false);
+        let new_user_json = web::Json(new_user);
+        let created_user = user::create_user(db_connection, &new_user_json)?;
 
-        assert!(share_events[1].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_events[1].accepted_declined_timestamp.is_none());
+        let category0 = InputCategory {
+            id: 0,
+            name: format!("First Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#ff11ee"),
+        };
 
-        mark_invitation_accepted(&db_connection, share_events[0].id, created_user2.id).unwrap();
+        let category1 = InputCategory {
+            id: 1,
+            name: format!("Second Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#112233"),
+        };
 
-        let share_events =
-            get_all_pending_invitations_for_user(&db_connection, created_user2.id).unwrap();
+        let budget_categories = vec![category0, category1];
 
-        assert_eq!(share_events.len(), 1);
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: Some(format!(
+                "This is a description of Test Budget {user_number}.",
+            )),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(
+                2021,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            end_date: NaiveDate::from_ymd(
+                2023,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
 
-        assert_eq!(share_events[0].recipient_user_id, created_user2.id);
-        assert_eq!(share_events[0].sharer_user_id, created_user1.id);
-        assert_eq!(share_events[0].budget_id, budget2.id);
-        assert_eq!(share_events[0].accepted, false);
+        let new_budget_json = web::Json(new_budget.clone());
+        let created_budget = create_budget(db_connection, &new_budget_json, created_user.id)?;
 
-        assert!(share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_events[0].accepted_declined_timestamp.is_none());
+        Ok(UserAndBudget {
+            user: created_user,
+            budget: created_budget,
+        })
     }
 
     #[actix_rt::test]
-    async fn test_get_all_pending_invitations_made_by_user() {
+    async fn test_create_budget() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
         let db_connection = db_thread_pool.get().unwrap();
 
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
-
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
-
-        let budget1 = created_user_and_budget1.budget.clone();
-        let budget2 = created_user_and_budget2.budget.clone();
-
-        invite_user(
-            &db_connection,
-            budget1.id,
-            created_user2.id,
-            created_user1.id,
-        )
-        .unwrap();
-
-        invite_user(
-            &db_connection,
-            budget2.id,
-            created_user2.id,
-            created_user1.id,
-        )
-        .unwrap();
-
-        let share_events =
-            get_all_pending_invitations_made_by_user(&db_connection, created_user2.id).unwrap();
+        let user_number = rand::thread_rng().gen_range::<u32, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", user_number),
+            password: String::from("g&eWi3#oIKDW%cTu*5*2"),
+            first_name: format!("Test-{}", user_number),
+            last_name: format!("User-{}", user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
 
-        assert_eq!(share_events.len(), 0);
+        let new_user_json = web::Json(new_user);
+        let created_user = user::create_user(&db_connection, &new_user_json).unwrap();
 
-        let share_events =
-            get_all_pending_invitations_made_by_user(&db_connection, created_user1.id).unwrap();
+        let category0 = InputCategory {
+            id: 0,
+            name: format!("First Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#ff11ee"),
+        };
 
-        assert_eq!(share_events.len(), 2);
+        let category1 = InputCategory {
+            id: 1,
+            name: format!("Second Random Category {user_number}"),
+            limit_cents: rand::thread_rng().gen_range(100..500),
+            color: String::from("#112233"),
+        };
 
-        assert_eq!(share_events[0].recipient_user_id, created_user2.id);
-        assert_eq!(share_events[0].sharer_user_id, created_user1.id);
-        assert_eq!(share_events[0].budget_id, budget1.id);
-        assert_eq!(share_events[0].accepted, false);
+        let budget_categories = vec![category0, category1];
 
-        assert!(share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_events[0].accepted_declined_timestamp.is_none());
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: Some(format!(
+                "This is a description of Test Budget {user_number}.",
+            )),
+            categories: budget_categories.clone(),
+            start_date: NaiveDate::from_ymd(
+                2021,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            end_date: NaiveDate::from_ymd(
+                2023,
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
 
-        assert_eq!(share_events[1].recipient_user_id, created_user2.id);
-        assert_eq!(share_events[1].sharer_user_id, created_user1.id);
-        assert_eq!(share_events[1].budget_id, budget2.id);
-        assert_eq!(share_events[1].accepted, false);
+        let new_budget_json = web::Json(new_budget.clone());
+        create_budget(&db_connection, &new_budget_json, created_user.id).unwrap();
 
-        assert!(share_events[1].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_events[1].accepted_declined_timestamp.is_none());
+        let created_user_budget_associations = user_budgets
+            .filter(user_budget_fields::user_id.eq(created_user.id))
+            .load::<UserBudget>(&db_connection)
+            .unwrap();
 
-        mark_invitation_declined(&db_connection, share_events[0].id, created_user2.id).unwrap();
+        assert_eq!(created_user_budget_associations.len(), 1);
+        assert!(
+            created_user_budget_associations[0].created_timestamp < chrono::Utc::now().naive_utc()
+        );
+        assert_eq!(created_user_budget_associations[0].user_id, created_user.id);
 
-        let share_events =
-            get_all_pending_invitations_made_by_user(&db_connection, created_user1.id).unwrap();
+        let budget_id = created_user_budget_associations[0].budget_id;
+        let budget = budgets
+            .find(budget_id)
+            .first::<Budget>(&db_connection)
+            .unwrap();
 
-        assert_eq!(share_events.len(), 1);
+        assert_eq!(budget.name, new_budget.name);
+        assert_eq!(budget.description, new_budget.description);
+        assert_eq!(budget.start_date, new_budget.start_date);
+        assert_eq!(budget.end_date, new_budget.end_date);
 
-        assert_eq!(share_events[0].recipient_user_id, created_user2.id);
-        assert_eq!(share_events[0].sharer_user_id, created_user1.id);
-        assert_eq!(share_events[0].budget_id, budget2.id);
-        assert_eq!(share_events[0].accepted, false);
+        let saved_categories = categories
+            .filter(category_fields::budget_id.eq(budget_id))
+            .load::<Category>(&db_connection)
+            .unwrap();
 
-        assert!(share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_events[0].accepted_declined_timestamp.is_none());
+        assert_eq!(saved_categories[0].id, budget_categories[0].id);
+        assert_eq!(saved_categories[0].name, budget_categories[0].name);
+        assert_eq!(
+            saved_categories[0].limit_cents,
+            budget_categories[0].limit_cents
+        );
+        assert_eq!(saved_categories[0].color, budget_categories[0].color);
     }
 
     #[actix_rt::test]
-    async fn test_get_invitation() {
+    async fn test_add_user() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
         let db_connection = db_thread_pool.get().unwrap();
 
@@ -1064,77 +1677,14 @@ mod tests {
 
         let budget = created_user_and_budget1.budget.clone();
 
-        invite_user(
+        add_user(
             &db_connection,
             budget.id,
             created_user2.id,
-            created_user1.id,
-        )
-        .unwrap();
-
-        let created_budget_share_events = budget_share_events
-            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
-            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
-            .load::<BudgetShareEvent>(&db_connection)
-            .unwrap();
-
-        assert_eq!(created_budget_share_events.len(), 1);
-
-        mark_invitation_accepted(
-            &db_connection,
-            created_budget_share_events[0].id,
-            created_user2.id,
-        )
-        .unwrap();
-
-        let share_event = get_invitation(
-            &db_connection,
-            created_budget_share_events[0].id,
-            created_user1.id,
-        )
-        .unwrap();
-
-        assert_eq!(share_event.recipient_user_id, created_user2.id);
-        assert_eq!(share_event.sharer_user_id, created_user1.id);
-        assert_eq!(share_event.budget_id, budget.id);
-        assert_eq!(share_event.accepted, true);
-
-        assert!(share_event.share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_event.accepted_declined_timestamp.unwrap() < chrono::Utc::now().naive_utc());
-        assert!(share_event.accepted_declined_timestamp.unwrap() > share_event.share_timestamp);
-
-        let share_event = get_invitation(
-            &db_connection,
-            created_budget_share_events[0].id,
-            created_user2.id,
+            BudgetRole::Editor,
         )
         .unwrap();
 
-        assert_eq!(share_event.recipient_user_id, created_user2.id);
-        assert_eq!(share_event.sharer_user_id, created_user1.id);
-        assert_eq!(share_event.budget_id, budget.id);
-        assert_eq!(share_event.accepted, true);
-
-        assert!(share_event.share_timestamp < chrono::Utc::now().naive_utc());
-        assert!(share_event.accepted_declined_timestamp.unwrap() < chrono::Utc::now().naive_utc());
-        assert!(share_event.accepted_declined_timestamp.unwrap() > share_event.share_timestamp);
-    }
-
-    #[actix_rt::test]
-    async fn test_add_user() {
-        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
-        let db_connection = db_thread_pool.get().unwrap();
-
-        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
-        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
-
-        let created_user1 = created_user_and_budget1.user.clone();
-        let created_user2 = created_user_and_budget2.user.clone();
-
-        let budget = created_user_and_budget1.budget.clone();
-
-        add_user(&db_connection, budget.id, created_user2.id).unwrap();
-
         let created_user1_budget_associations = user_budgets
             .filter(user_budget_fields::user_id.eq(created_user1.id))
             .load::<UserBudget>(&db_connection)
@@ -1229,8 +1779,20 @@ mod tests {
         let budget1 = created_user_and_budget1.budget.clone();
         let budget2 = created_user_and_budget2.budget.clone();
 
-        add_user(&db_connection, budget1.id, created_user2.id).unwrap();
-        add_user(&db_connection, budget2.id, created_user1.id).unwrap();
+        add_user(
+            &db_connection,
+            budget1.id,
+            created_user2.id,
+            BudgetRole::Editor,
+        )
+        .unwrap();
+        add_user(
+            &db_connection,
+            budget2.id,
+            created_user1.id,
+            BudgetRole::Editor,
+        )
+        .unwrap();
 
         let created_user1_budget_associations = user_budgets
             .filter(user_budget_fields::user_id.eq(created_user1.id))
@@ -1313,7 +1875,13 @@ mod tests {
         let budget_user_count = count_users_remaining_in_budget(&db_connection, budget.id).unwrap();
         assert_eq!(budget_user_count, 1);
 
-        add_user(&db_connection, budget.id, created_user2.id).unwrap();
+        add_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            BudgetRole::Editor,
+        )
+        .unwrap();
 
         let budget_user_count = count_users_remaining_in_budget(&db_connection, budget.id).unwrap();
         assert_eq!(budget_user_count, 2);
@@ -1453,6 +2021,7 @@ mod tests {
         let created_budget = created_user_and_budget.budget.clone();
 
         let new_entry = InputEntry {
+            id: None,
             budget_id: created_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1463,6 +2032,9 @@ mod tests {
             name: Some(format!("Test Entry 0 for user")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let new_entry_json = web::Json(new_entry.clone());
@@ -1492,6 +2064,313 @@ mod tests {
         assert_eq!(fetched_budget_entry.note, new_entry.note);
     }
 
+    #[actix_rt::test]
+    async fn test_hard_cap_usage_and_owner() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget = generate_user_and_budget(&db_connection).unwrap();
+        let created_user = created_user_and_budget.user.clone();
+        let created_budget = created_user_and_budget.budget.clone();
+
+        let other_user = generate_user_and_budget(&db_connection).unwrap().user;
+        add_user(
+            &db_connection,
+            created_budget.id,
+            other_user.id,
+            BudgetRole::Editor,
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_budget_owner_id(&db_connection, created_budget.id).unwrap(),
+            created_user.id
+        );
+
+        assert!(get_hard_cap_usage(&db_connection, created_budget.id, 0)
+            .unwrap()
+            .is_none());
+
+        assert_eq!(
+            set_category_hard_cap(&db_connection, created_budget.id, 0, true).unwrap(),
+            1
+        );
+        assert_eq!(
+            set_category_hard_cap(&db_connection, created_budget.id, 7, true).unwrap(),
+            0
+        );
+
+        for amount_cents in [40, 25] {
+            let new_entry = InputEntry {
+                id: None,
+                budget_id: created_budget.id,
+                amount_cents,
+                date: NaiveDate::from_ymd(2022, 3, 14),
+                name: None,
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            create_entry(&db_connection, &web::Json(new_entry), other_user.id).unwrap();
+        }
+
+        let (limit_cents, spent_cents) = get_hard_cap_usage(&db_connection, created_budget.id, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(limit_cents, created_budget.categories[0].limit_cents);
+        assert_eq!(spent_cents, 65);
+
+        let over_cap_entry = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: limit_cents - spent_cents + 1,
+            date: NaiveDate::from_ymd(2022, 3, 14),
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let error = create_entry(
+            &db_connection,
+            &web::Json(over_cap_entry.clone()),
+            other_user.id,
+        )
+        .unwrap_err();
+        assert!(category_total::is_hard_cap_violation(&error));
+
+        let refund = InputEntry {
+            amount_cents: -10,
+            ..over_cap_entry.clone()
+        };
+        create_entry(&db_connection, &web::Json(refund), other_user.id).unwrap();
+
+        create_entry_over_hard_cap(&db_connection, &web::Json(over_cap_entry), created_user.id)
+            .unwrap();
+
+        let (_, spent_cents) = get_hard_cap_usage(&db_connection, created_budget.id, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(spent_cents, limit_cents + 1 - 10);
+
+        assert!(get_hard_cap_usage(&db_connection, created_budget.id, 1)
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_tracking_only_budget() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user = generate_user_and_budget(&db_connection).unwrap().user;
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let new_budget = InputBudget {
+            name: String::from("Variable Income Tracking"),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: String::from("Groceries"),
+                limit_cents: 500,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: today - chrono::Duration::days(10),
+            end_date: today + chrono::Duration::days(10),
+            is_tracking_only: true,
+            is_envelope: false,
+        };
+
+        let created_budget =
+            create_budget(&db_connection, &web::Json(new_budget), created_user.id).unwrap();
+        assert!(created_budget.is_tracking_only);
+        assert_eq!(created_budget.categories[0].limit_cents, 0);
+
+        let fetched_budget = get_budget_by_id(&db_connection, created_budget.id).unwrap();
+        assert!(fetched_budget.is_tracking_only);
+
+        set_category_hard_cap(&db_connection, created_budget.id, 0, true).unwrap();
+
+        let new_entry = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: 1000,
+            date: today,
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        create_entry(&db_connection, &web::Json(new_entry), created_user.id).unwrap();
+
+        assert!(get_hard_cap_usage(&db_connection, created_budget.id, 0)
+            .unwrap()
+            .is_none());
+
+        let crossings =
+            get_categories_over_threshold_for_user(&db_connection, created_user.id, 1).unwrap();
+        assert!(crossings.iter().all(|c| c.budget_id != created_budget.id));
+    }
+
+    #[actix_rt::test]
+    async fn test_edit_and_delete_entry() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget = generate_user_and_budget(&db_connection).unwrap();
+        let created_user = created_user_and_budget.user.clone();
+        let created_budget = created_user_and_budget.budget.clone();
+
+        let other_user = generate_user_and_budget(&db_connection).unwrap().user;
+
+        let new_entry = InputEntry {
+            id: None,
+            budget_id: created_budget.id,
+            amount_cents: 1500,
+            date: NaiveDate::from_ymd(2022, 5, 3),
+            name: Some(String::from("Groceries")),
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        let created_entry =
+            create_entry(&db_connection, &web::Json(new_entry), created_user.id).unwrap();
+
+        let edited_entry = InputEditEntry {
+            entry_id: created_entry.id,
+            amount_cents: 2500,
+            date: NaiveDate::from_ymd(2022, 5, 4),
+            name: Some(String::from("Farmers market")),
+            category: Some(1),
+            note: Some(String::from("Split with roommate")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        assert_eq!(
+            edit_entry(&db_connection, other_user.id, &edited_entry).unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_entry(&db_connection, other_user.id, created_entry.id).unwrap(),
+            0
+        );
+
+        assert_eq!(
+            edit_entry(&db_connection, created_user.id, &edited_entry).unwrap(),
+            1
+        );
+
+        let entry = entries
+            .find(created_entry.id)
+            .first::<Entry>(&db_connection)
+            .unwrap();
+
+        assert_eq!(entry.amount_cents, edited_entry.amount_cents);
+        assert_eq!(entry.date, edited_entry.date);
+        assert_eq!(entry.name, edited_entry.name);
+        assert_eq!(entry.category, edited_entry.category);
+        assert_eq!(entry.note, edited_entry.note);
+        assert!(entry.modified_timestamp > created_entry.modified_timestamp);
+
+        assert_eq!(
+            delete_entry(&db_connection, created_user.id, created_entry.id).unwrap(),
+            1
+        );
+
+        let entry = entries
+            .find(created_entry.id)
+            .first::<Entry>(&db_connection)
+            .unwrap();
+        assert!(entry.is_deleted);
+
+        // Deleted entries can't be edited or deleted again
+        assert_eq!(
+            edit_entry(&db_connection, created_user.id, &edited_entry).unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_entry(&db_connection, created_user.id, created_entry.id).unwrap(),
+            0
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_entries_matching_filter() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget = generate_user_and_budget(&db_connection).unwrap();
+        let created_user = created_user_and_budget.user.clone();
+        let created_budget = created_user_and_budget.budget.clone();
+
+        for day in 1..=3 {
+            let new_entry = InputEntry {
+                id: None,
+                budget_id: created_budget.id,
+                amount_cents: 1500,
+                date: NaiveDate::from_ymd(2022, 5, day),
+                name: None,
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            create_entry(&db_connection, &web::Json(new_entry), created_user.id).unwrap();
+        }
+
+        let filter = InputEntryFilter {
+            budget_id: created_budget.id,
+            start_date: Some(NaiveDate::from_ymd(2022, 5, 2)),
+            end_date: None,
+            category: None,
+            import_batch_id: None,
+        };
+
+        let (matched_count, sample) = preview_entry_deletion(&db_connection, &filter, 1).unwrap();
+        assert_eq!(matched_count, 2);
+        assert_eq!(sample.len(), 1);
+        assert_eq!(sample[0].date, NaiveDate::from_ymd(2022, 5, 2));
+
+        // Nothing is deleted if the count no longer matches
+        assert!(delete_entries_matching_filter(&db_connection, &filter, 3)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            preview_entry_deletion(&db_connection, &filter, 1)
+                .unwrap()
+                .0,
+            2
+        );
+
+        assert_eq!(
+            delete_entries_matching_filter(&db_connection, &filter, 2).unwrap(),
+            Some(2)
+        );
+
+        let remaining_entries = entries
+            .filter(entry_fields::budget_id.eq(created_budget.id))
+            .filter(entry_fields::is_deleted.eq(false))
+            .load::<Entry>(&db_connection)
+            .unwrap();
+        assert_eq!(remaining_entries.len(), 1);
+        assert_eq!(remaining_entries[0].date, NaiveDate::from_ymd(2022, 5, 1));
+    }
+
     #[actix_rt::test]
     async fn test_get_budget_by_id() {
         let db_thread_pool = &*env::testing::DB_THREAD_POOL;
@@ -1502,6 +2381,7 @@ mod tests {
         let created_budget = created_user_and_budget.budget.clone();
 
         let entry0 = InputEntry {
+            id: None,
             budget_id: created_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1512,9 +2392,13 @@ mod tests {
             name: Some(format!("Test Entry 0 for user")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let entry1 = InputEntry {
+            id: None,
             budget_id: created_budget.id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1525,6 +2409,9 @@ mod tests {
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let created_entries = vec![entry0.clone(), entry1.clone()];
@@ -1627,6 +2514,8 @@ mod tests {
                 rand::thread_rng().gen_range(1..=12),
                 rand::thread_rng().gen_range(1..=28),
             ),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
         let mut created_budgets = Vec::new();
@@ -1638,6 +2527,7 @@ mod tests {
         created_budgets.push(budget0);
 
         let entry0 = InputEntry {
+            id: None,
             budget_id: created_budgets[0].id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1648,9 +2538,13 @@ mod tests {
             name: Some(format!("Test Entry 0 for user")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let entry1 = InputEntry {
+            id: None,
             budget_id: created_budgets[0].id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1661,9 +2555,13 @@ mod tests {
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let entry2 = InputEntry {
+            id: None,
             budget_id: created_budgets[1].id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1674,9 +2572,13 @@ mod tests {
             name: Some(format!("Test Entry 2 for user")),
             category: Some(0),
             note: Some(String::from("This is 2 little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let entry3 = InputEntry {
+            id: None,
             budget_id: created_budgets[1].id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(
@@ -1687,6 +2589,9 @@ mod tests {
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let created_entries = vec![
@@ -1704,7 +2609,10 @@ mod tests {
         create_entry(&db_connection, &entry2_json, created_user.id).unwrap();
         create_entry(&db_connection, &entry3_json, created_user.id).unwrap();
 
-        let fetched_budgets = get_all_budgets_for_user(&db_connection, created_user.id).unwrap();
+        let fetched_budgets =
+            get_all_budgets_for_user(&db_connection, created_user.id, false, 100, 0)
+                .unwrap()
+                .budgets;
         assert_eq!(fetched_budgets.len(), created_budgets.len());
 
         for i in 0..fetched_budgets.len() {
@@ -1808,6 +2716,8 @@ mod tests {
             categories: budget_categories.clone(),
             start_date: NaiveDate::from_ymd(2022, 3, 14),
             end_date: NaiveDate::from_ymd(2022, 3, 30),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
         let in_range_budget0 = InputBudget {
@@ -1818,6 +2728,8 @@ mod tests {
             categories: budget_categories.clone(),
             start_date: NaiveDate::from_ymd(2022, 3, 12),
             end_date: NaiveDate::from_ymd(2022, 4, 18),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
         let in_range_budget1 = InputBudget {
@@ -1828,6 +2740,8 @@ mod tests {
             categories: budget_categories.clone(),
             start_date: NaiveDate::from_ymd(2022, 4, 8),
             end_date: NaiveDate::from_ymd(2022, 4, 10),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
         let in_range_budget2 = InputBudget {
@@ -1838,6 +2752,8 @@ mod tests {
             categories: budget_categories.clone(),
             start_date: NaiveDate::from_ymd(2022, 4, 9),
             end_date: NaiveDate::from_ymd(2022, 5, 6),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
         let too_late_budget = InputBudget {
@@ -1848,6 +2764,8 @@ mod tests {
             categories: budget_categories,
             start_date: NaiveDate::from_ymd(2022, 4, 22),
             end_date: NaiveDate::from_ymd(2022, 4, 30),
+            is_tracking_only: false,
+            is_envelope: false,
         };
 
         let mut in_range_budgets = Vec::new();
@@ -1871,21 +2789,29 @@ mod tests {
         create_budget(&db_connection, &too_late_budget_json, created_user.id).unwrap();
 
         let entry0 = InputEntry {
+            id: None,
             budget_id: in_range_budgets[0].id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(2022, 4, 8),
             name: Some(format!("Test Entry 0 for {user_number}")),
             category: Some(0),
             note: Some(String::from("This is a little note")),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let entry1 = InputEntry {
+            id: None,
             budget_id: in_range_budgets[0].id,
             amount_cents: rand::thread_rng().gen_range(90..=120000),
             date: NaiveDate::from_ymd(2022, 4, 9),
             name: None,
             category: None,
             note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
         };
 
         let mut entry2 = entry0.clone();
@@ -1930,8 +2856,12 @@ mod tests {
             created_user.id,
             NaiveDate::from_ymd(2022, 4, 6),
             NaiveDate::from_ymd(2022, 4, 12),
+            false,
+            100,
+            0,
         )
-        .unwrap();
+        .unwrap()
+        .budgets;
         assert_eq!(fetched_budgets.len(), in_range_budgets.len());
 
         for i in 0..fetched_budgets.len() {
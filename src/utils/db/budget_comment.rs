@@ -0,0 +1,256 @@
+use diesel::{dsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{InputBudgetComment, InputEditBudgetComment};
+use crate::models::budget_comment::{BudgetComment, NewBudgetComment};
+use crate::schema::budget_comments as budget_comment_fields;
+use crate::schema::budget_comments::dsl::budget_comments;
+use crate::schema::user_budgets as user_budget_fields;
+use crate::schema::user_budgets::dsl::user_budgets;
+use crate::utils::record_id;
+
+pub fn create_comment(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    comment_data: &InputBudgetComment,
+) -> Result<BudgetComment, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_comment = NewBudgetComment {
+        id: comment_data.id.unwrap_or_else(record_id::generate),
+        budget_id: comment_data.budget_id,
+        user_id,
+        is_deleted: false,
+        is_current: true,
+        text: &comment_data.text,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(budget_comments)
+        .values(&new_comment)
+        .get_result::<BudgetComment>(db_connection)
+}
+
+pub fn get_comment(
+    db_connection: &DbConnection,
+    comment_id: Uuid,
+) -> Result<BudgetComment, diesel::result::Error> {
+    budget_comments
+        .find(comment_id)
+        .first::<BudgetComment>(db_connection)
+}
+
+// Returns the current, non-deleted comments on the budget, oldest first
+pub fn get_comments_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<BudgetComment>, diesel::result::Error> {
+    budget_comments
+        .filter(budget_comment_fields::budget_id.eq(budget_id))
+        .filter(budget_comment_fields::is_current.eq(true))
+        .filter(budget_comment_fields::is_deleted.eq(false))
+        .order(budget_comment_fields::created_timestamp.asc())
+        .load::<BudgetComment>(db_connection)
+}
+
+// Edits don't modify the comment in place. The previous version is kept with `is_current` set to
+// false and a new row is inserted with the edited text, so the returned comment has a new ID. The
+// new row keeps the original `created_timestamp` so the comment's position in the list is stable.
+//
+// Returns diesel::result::Error::NotFound if the comment doesn't exist, isn't the current version,
+// was deleted, or wasn't written by the user in a budget the user still belongs to.
+pub fn edit_comment(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_comment_data: &InputEditBudgetComment,
+) -> Result<BudgetComment, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let previous_comment = diesel::update(
+            budget_comments
+                .filter(budget_comment_fields::id.eq(edited_comment_data.comment_id))
+                .filter(budget_comment_fields::user_id.eq(user_id))
+                .filter(budget_comment_fields::is_current.eq(true))
+                .filter(budget_comment_fields::is_deleted.eq(false))
+                .filter(budget_comment_fields::budget_id.eq_any(user_budget_ids)),
+        )
+        .set((
+            budget_comment_fields::is_current.eq(false),
+            budget_comment_fields::modified_timestamp.eq(current_time),
+        ))
+        .get_result::<BudgetComment>(db_connection)?;
+
+        let new_comment = NewBudgetComment {
+            id: record_id::generate(),
+            budget_id: previous_comment.budget_id,
+            user_id,
+            is_deleted: false,
+            is_current: true,
+            text: &edited_comment_data.text,
+            modified_timestamp: current_time,
+            created_timestamp: previous_comment.created_timestamp,
+        };
+
+        dsl::insert_into(budget_comments)
+            .values(&new_comment)
+            .get_result::<BudgetComment>(db_connection)
+    })
+}
+
+// Soft-deletes the current version of the comment. Returns the number of comments deleted, which
+// is zero if the comment doesn't exist or the user can't delete it.
+pub fn delete_comment(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    comment_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id));
+
+    diesel::update(
+        budget_comments
+            .filter(budget_comment_fields::id.eq(comment_id))
+            .filter(budget_comment_fields::user_id.eq(user_id))
+            .filter(budget_comment_fields::is_current.eq(true))
+            .filter(budget_comment_fields::is_deleted.eq(false))
+            .filter(budget_comment_fields::budget_id.eq_any(user_budget_ids)),
+    )
+    .set((
+        budget_comment_fields::is_deleted.eq(true),
+        budget_comment_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    use crate::env;
+    use crate::utils::db::budget;
+    use crate::utils::db::budget::BudgetRole;
+    use crate::utils::db::testing::create_user_and_budget;
+
+    #[test]
+    fn test_edit_comment_preserves_history() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        let original = create_comment(
+            &db_connection,
+            user_id,
+            &InputBudgetComment {
+                id: None,
+                budget_id,
+                text: String::from("Let's cut back on takeout"),
+            },
+        )
+        .unwrap();
+
+        let edited = edit_comment(
+            &db_connection,
+            user_id,
+            &InputEditBudgetComment {
+                comment_id: original.id,
+                text: String::from("Let's cut back on takeout this month"),
+            },
+        )
+        .unwrap();
+
+        assert_ne!(edited.id, original.id);
+        assert!(edited.is_current);
+        assert_eq!(edited.text, "Let's cut back on takeout this month");
+        assert_eq!(edited.created_timestamp, original.created_timestamp);
+
+        let previous = budget_comments
+            .find(original.id)
+            .first::<BudgetComment>(&db_connection)
+            .unwrap();
+        assert!(!previous.is_current);
+        assert_eq!(previous.text, "Let's cut back on takeout");
+
+        let comments = get_comments_for_budget(&db_connection, budget_id).unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, edited.id);
+
+        // Only the current version can be edited
+        let result = edit_comment(
+            &db_connection,
+            user_id,
+            &InputEditBudgetComment {
+                comment_id: original.id,
+                text: String::from("Stale edit"),
+            },
+        );
+        assert_eq!(result.unwrap_err(), diesel::result::Error::NotFound);
+    }
+
+    #[test]
+    fn test_comments_restricted_to_author() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+        let (other_user_id, _) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        budget::add_user(&db_connection, budget_id, other_user_id, BudgetRole::Editor).unwrap();
+
+        let comment = create_comment(
+            &db_connection,
+            user_id,
+            &InputBudgetComment {
+                id: None,
+                budget_id,
+                text: String::from("Rent went up"),
+            },
+        )
+        .unwrap();
+
+        let result = edit_comment(
+            &db_connection,
+            other_user_id,
+            &InputEditBudgetComment {
+                comment_id: comment.id,
+                text: String::from("Not my comment"),
+            },
+        );
+        assert_eq!(result.unwrap_err(), diesel::result::Error::NotFound);
+        assert_eq!(
+            delete_comment(&db_connection, other_user_id, comment.id).unwrap(),
+            0
+        );
+
+        assert_eq!(
+            delete_comment(&db_connection, user_id, comment.id).unwrap(),
+            1
+        );
+        assert_eq!(
+            delete_comment(&db_connection, user_id, comment.id).unwrap(),
+            0
+        );
+
+        assert!(get_comments_for_budget(&db_connection, budget_id)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+use diesel::{dsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::InputBudgetResource;
+use crate::models::budget_resource::{BudgetResource, NewBudgetResource};
+use crate::schema::budget_resources as budget_resource_fields;
+use crate::schema::budget_resources::dsl::budget_resources;
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::utils::record_id;
+
+// The values are stored in the database, so existing variants must keep their numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BudgetResourceKind {
+    Link = 0,
+    File = 1,
+}
+
+impl TryFrom<i16> for BudgetResourceKind {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BudgetResourceKind::Link),
+            1 => Ok(BudgetResourceKind::File),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn get_resources_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<BudgetResource>, diesel::result::Error> {
+    budget_resources
+        .filter(budget_resource_fields::budget_id.eq(budget_id))
+        .order(budget_resource_fields::created_timestamp.asc())
+        .load::<BudgetResource>(db_connection)
+}
+
+// Returns None if the budget already has `max_resources` resources
+pub fn add_resource(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    resource_data: &InputBudgetResource,
+    max_resources: i64,
+) -> Result<Option<BudgetResource>, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // Locking the budget keeps concurrent requests from going over the limit together
+        budgets
+            .find(resource_data.budget_id)
+            .select(budget_fields::id)
+            .for_update()
+            .first::<Uuid>(db_connection)?;
+
+        let resource_count = budget_resources
+            .filter(budget_resource_fields::budget_id.eq(resource_data.budget_id))
+            .count()
+            .get_result::<i64>(db_connection)?;
+
+        if resource_count >= max_resources {
+            return Ok(None);
+        }
+
+        let new_resource = NewBudgetResource {
+            id: record_id::generate(),
+            budget_id: resource_data.budget_id,
+            user_id,
+            kind: resource_data.kind,
+            title: resource_data.title.trim(),
+            location: resource_data.location.trim(),
+            created_timestamp: chrono::Utc::now().naive_utc(),
+        };
+
+        dsl::insert_into(budget_resources)
+            .values(&new_resource)
+            .get_result::<BudgetResource>(db_connection)
+            .map(Some)
+    })
+}
+
+pub fn remove_resource(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    resource_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        budget_resources
+            .filter(budget_resource_fields::id.eq(resource_id))
+            .filter(budget_resource_fields::budget_id.eq(budget_id)),
+    )
+    .execute(db_connection)
+}
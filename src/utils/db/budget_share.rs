@@ -0,0 +1,845 @@
+use diesel::{
+    dsl, BoolExpressionMethods, Connection, ExpressionMethods, JoinOnDsl, QueryDsl, RunQueryDsl,
+};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::budget_share_event::{
+    BudgetShareEvent, BudgetShareEventWithSharer, NewBudgetShareEvent,
+};
+use crate::models::user_notification::NewUserNotification;
+use crate::schema::budget_share_events as budget_share_event_fields;
+use crate::schema::budget_share_events::dsl::budget_share_events;
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::schema::users as user_fields;
+use crate::schema::users::dsl::users;
+use crate::utils::db::audit_log::{self, AuditAction};
+use crate::utils::db::budget;
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::db::push;
+use crate::utils::notification::{BudgetInvitationData, NotificationType};
+use crate::utils::record_id;
+
+pub fn invite_user(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    invitee_user_id: Uuid,
+    sharer_user_id: Uuid,
+) -> Result<BudgetShareEvent, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let budget_share_event = NewBudgetShareEvent {
+        id: record_id::generate(),
+        recipient_user_id: invitee_user_id,
+        sharer_user_id,
+        budget_id,
+        accepted: false,
+        share_timestamp: current_time,
+        accepted_declined_timestamp: None,
+    };
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let share_event = dsl::insert_into(budget_share_events)
+            .values(&budget_share_event)
+            .get_result::<BudgetShareEvent>(db_connection)?;
+
+        let associated_data = serde_json::to_string(&BudgetInvitationData {
+            share_event_id: share_event.id,
+            budget_id,
+            sharer_user_id,
+        })
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+        let notification = NewUserNotification {
+            id: record_id::generate(),
+            user_id: invitee_user_id,
+            is_unread: true,
+            is_pristine: true,
+            is_deleted: false,
+            notification_type: i16::from(NotificationType::BudgetInvitation),
+            alt_title: "Budget invitation",
+            alt_message: "You have been invited to join a shared budget.",
+            associated_data: Some(&associated_data),
+            modified_timestamp: current_time,
+            created_timestamp: current_time,
+        };
+
+        push::trace_queued_pushes(db_connection)?;
+        dsl::insert_into(user_notifications)
+            .values(&notification)
+            .execute(db_connection)?;
+
+        Ok(share_event)
+    })
+}
+
+pub fn delete_invitation(
+    db_connection: &DbConnection,
+    invitation_id: Uuid,
+    sharer_user_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        budget_share_events
+            .find(invitation_id)
+            .filter(budget_share_event_fields::sharer_user_id.eq(sharer_user_id))
+            .filter(budget_share_event_fields::accepted_declined_timestamp.is_null()),
+    )
+    .execute(db_connection)
+}
+
+// Returns diesel::result::Error::NotFound if the invitation doesn't exist, belongs to another
+// user, or has already been accepted or declined
+pub fn accept_invitation(
+    db_connection: &DbConnection,
+    invitation_id: Uuid,
+    recipient_user_id: Uuid,
+) -> Result<BudgetShareEvent, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let share_event = diesel::update(
+            budget_share_events
+                .find(invitation_id)
+                .filter(budget_share_event_fields::recipient_user_id.eq(recipient_user_id))
+                .filter(budget_share_event_fields::accepted_declined_timestamp.is_null()),
+        )
+        .set((
+            budget_share_event_fields::accepted.eq(true),
+            budget_share_event_fields::accepted_declined_timestamp
+                .eq(chrono::Utc::now().naive_utc()),
+        ))
+        .get_result::<BudgetShareEvent>(db_connection)?;
+
+        if !budget::check_user_in_budget(db_connection, recipient_user_id, share_event.budget_id)? {
+            budget::add_user(
+                db_connection,
+                share_event.budget_id,
+                recipient_user_id,
+                BudgetRole::Editor,
+            )?;
+
+            audit_log::record(
+                db_connection,
+                share_event.budget_id,
+                recipient_user_id,
+                AuditAction::MemberAdded,
+                None::<&serde_json::Value>,
+                Some(&serde_json::json!({
+                    "user_id": recipient_user_id,
+                    "role": BudgetRole::Editor as i16,
+                    "invited_by": share_event.sharer_user_id,
+                })),
+            )?;
+        }
+
+        Ok(share_event)
+    })
+}
+
+pub fn decline_invitation(
+    db_connection: &DbConnection,
+    invitation_id: Uuid,
+    recipient_user_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        budget_share_events
+            .find(invitation_id)
+            .filter(budget_share_event_fields::recipient_user_id.eq(recipient_user_id))
+            .filter(budget_share_event_fields::accepted_declined_timestamp.is_null()),
+    )
+    .set((
+        budget_share_event_fields::accepted.eq(false),
+        budget_share_event_fields::accepted_declined_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+pub fn get_all_pending_invitations_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<BudgetShareEventWithSharer>, diesel::result::Error> {
+    budget_share_events
+        .inner_join(users.on(user_fields::id.eq(budget_share_event_fields::sharer_user_id)))
+        .select((
+            budget_share_event_fields::all_columns,
+            user_fields::first_name,
+            user_fields::last_name,
+        ))
+        .filter(budget_share_event_fields::recipient_user_id.eq(user_id))
+        .filter(budget_share_event_fields::accepted_declined_timestamp.is_null())
+        .order(budget_share_event_fields::share_timestamp.asc())
+        .load::<BudgetShareEventWithSharer>(db_connection)
+}
+
+pub fn get_all_pending_invitations_made_by_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<BudgetShareEvent>, diesel::result::Error> {
+    budget_share_events
+        .filter(budget_share_event_fields::sharer_user_id.eq(user_id))
+        .filter(budget_share_event_fields::accepted_declined_timestamp.is_null())
+        .order(budget_share_event_fields::share_timestamp.asc())
+        .load::<BudgetShareEvent>(db_connection)
+}
+
+pub fn get_invitation(
+    db_connection: &DbConnection,
+    invitation_id: Uuid,
+    user_id: Uuid,
+) -> Result<BudgetShareEventWithSharer, diesel::result::Error> {
+    budget_share_events
+        .inner_join(users.on(user_fields::id.eq(budget_share_event_fields::sharer_user_id)))
+        .select((
+            budget_share_event_fields::all_columns,
+            user_fields::first_name,
+            user_fields::last_name,
+        ))
+        .filter(budget_share_event_fields::id.eq(invitation_id))
+        .filter(
+            budget_share_event_fields::sharer_user_id
+                .eq(user_id)
+                .or(budget_share_event_fields::recipient_user_id.eq(user_id)),
+        )
+        .first::<BudgetShareEventWithSharer>(db_connection)
+}
+
+// The name and description of an invitation's budget. These are only shown to the recipient once
+// they have accepted the invitation.
+pub fn get_invitation_budget_details(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<(String, Option<String>), diesel::result::Error> {
+    budgets
+        .find(budget_id)
+        .select((budget_fields::name, budget_fields::description))
+        .first::<(String, Option<String>)>(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use chrono::NaiveDate;
+    use rand::prelude::*;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputUser, OutputBudget};
+    use crate::models::user::User;
+    use crate::models::user_notification::UserNotification;
+    use crate::schema::user_notifications as user_notification_fields;
+    use crate::utils::db::user;
+
+    pub struct UserAndBudget {
+        user: User,
+        budget: OutputBudget,
+    }
+
+    pub fn generate_user_and_budget(
+        db_connection: &DbConnection,
+    ) -> Result<UserAndBudget, diesel::result::Error> {
+        let user_number = rand::thread_rng().gen_range::<u32, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", user_number),
+            password: String::from("g&eWi3#oIKDW%cTu*5*2"),
+            first_name: format!("Test-{}", user_number),
+            last_name: format!("User-{}", user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let created_user = user::create_user(db_connection, &web::Json(new_user))?;
+
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: format!("Random Category {user_number}"),
+                limit_cents: rand::thread_rng().gen_range(100..500),
+                color: String::from("#ff11ee"),
+            }],
+            start_date: NaiveDate::from_ymd(2021, 1, 1),
+            end_date: NaiveDate::from_ymd(2023, 12, 31),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let created_budget =
+            budget::create_budget(db_connection, &web::Json(new_budget), created_user.id)?;
+
+        Ok(UserAndBudget {
+            user: created_user,
+            budget: created_budget,
+        })
+    }
+
+    #[actix_rt::test]
+    async fn test_invite_user() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget = created_user_and_budget1.budget.clone();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 0);
+
+        invite_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        assert_eq!(
+            created_budget_share_events[0].recipient_user_id,
+            created_user2.id
+        );
+        assert_eq!(
+            created_budget_share_events[0].sharer_user_id,
+            created_user1.id
+        );
+        assert_eq!(created_budget_share_events[0].budget_id, budget.id);
+        assert_eq!(created_budget_share_events[0].accepted, false);
+
+        assert!(created_budget_share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
+        assert_eq!(
+            created_budget_share_events[0].accepted_declined_timestamp,
+            None
+        );
+
+        let notifications = user_notifications
+            .filter(user_notification_fields::user_id.eq(created_user2.id))
+            .filter(
+                user_notification_fields::notification_type
+                    .eq(i16::from(NotificationType::BudgetInvitation)),
+            )
+            .load::<UserNotification>(&db_connection)
+            .unwrap();
+
+        assert_eq!(notifications.len(), 1);
+        assert!(notifications[0].is_unread);
+        assert!(notifications[0]
+            .associated_data
+            .as_ref()
+            .unwrap()
+            .contains(&created_budget_share_events[0].id.to_string()));
+
+        assert!(invite_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_delete_invitation() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget = created_user_and_budget1.budget.clone();
+        invite_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        delete_invitation(
+            &db_connection,
+            created_budget_share_events[0].id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_accept_invitation() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget = created_user_and_budget1.budget.clone();
+
+        invite_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        accept_invitation(
+            &db_connection,
+            created_budget_share_events[0].id,
+            created_user2.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        assert_eq!(
+            created_budget_share_events[0].recipient_user_id,
+            created_user2.id
+        );
+        assert_eq!(
+            created_budget_share_events[0].sharer_user_id,
+            created_user1.id
+        );
+        assert_eq!(created_budget_share_events[0].budget_id, budget.id);
+        assert_eq!(created_budget_share_events[0].accepted, true);
+
+        assert!(created_budget_share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(
+            created_budget_share_events[0]
+                .accepted_declined_timestamp
+                .unwrap()
+                < chrono::Utc::now().naive_utc()
+        );
+        assert!(
+            created_budget_share_events[0]
+                .accepted_declined_timestamp
+                .unwrap()
+                > created_budget_share_events[0].share_timestamp
+        );
+
+        assert!(budget::check_user_in_budget(&db_connection, created_user2.id, budget.id).unwrap());
+
+        assert_eq!(
+            accept_invitation(
+                &db_connection,
+                created_budget_share_events[0].id,
+                created_user2.id,
+            )
+            .unwrap_err(),
+            diesel::result::Error::NotFound
+        );
+        assert_eq!(
+            decline_invitation(
+                &db_connection,
+                created_budget_share_events[0].id,
+                created_user2.id,
+            )
+            .unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_invitation(
+                &db_connection,
+                created_budget_share_events[0].id,
+                created_user1.id,
+            )
+            .unwrap(),
+            0
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_decline_invitation() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget = created_user_and_budget1.budget.clone();
+
+        invite_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        decline_invitation(
+            &db_connection,
+            created_budget_share_events[0].id,
+            created_user2.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        assert_eq!(
+            created_budget_share_events[0].recipient_user_id,
+            created_user2.id
+        );
+        assert_eq!(
+            created_budget_share_events[0].sharer_user_id,
+            created_user1.id
+        );
+        assert_eq!(created_budget_share_events[0].budget_id, budget.id);
+        assert_eq!(created_budget_share_events[0].accepted, false);
+
+        assert!(created_budget_share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(
+            created_budget_share_events[0]
+                .accepted_declined_timestamp
+                .unwrap()
+                < chrono::Utc::now().naive_utc()
+        );
+        assert!(
+            created_budget_share_events[0]
+                .accepted_declined_timestamp
+                .unwrap()
+                > created_budget_share_events[0].share_timestamp
+        );
+
+        assert!(
+            !budget::check_user_in_budget(&db_connection, created_user2.id, budget.id).unwrap()
+        );
+
+        assert_eq!(
+            accept_invitation(
+                &db_connection,
+                created_budget_share_events[0].id,
+                created_user2.id,
+            )
+            .unwrap_err(),
+            diesel::result::Error::NotFound
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_get_all_pending_invitations_for_user() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget1 = created_user_and_budget1.budget.clone();
+        let budget2 = created_user_and_budget2.budget.clone();
+
+        invite_user(
+            &db_connection,
+            budget1.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        invite_user(
+            &db_connection,
+            budget2.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let share_events =
+            get_all_pending_invitations_for_user(&db_connection, created_user1.id).unwrap();
+
+        assert_eq!(share_events.len(), 0);
+
+        let share_events =
+            get_all_pending_invitations_for_user(&db_connection, created_user2.id).unwrap();
+
+        assert_eq!(share_events.len(), 2);
+
+        assert_eq!(
+            share_events[0].share_event.recipient_user_id,
+            created_user2.id
+        );
+        assert_eq!(share_events[0].share_event.sharer_user_id, created_user1.id);
+        assert_eq!(share_events[0].share_event.budget_id, budget1.id);
+        assert_eq!(share_events[0].share_event.accepted, false);
+        assert_eq!(share_events[0].sharer_first_name, created_user1.first_name);
+        assert_eq!(share_events[0].sharer_last_name, created_user1.last_name);
+
+        assert!(share_events[0].share_event.share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(share_events[0]
+            .share_event
+            .accepted_declined_timestamp
+            .is_none());
+
+        assert_eq!(
+            share_events[1].share_event.recipient_user_id,
+            created_user2.id
+        );
+        assert_eq!(share_events[1].share_event.sharer_user_id, created_user1.id);
+        assert_eq!(share_events[1].share_event.budget_id, budget2.id);
+        assert_eq!(share_events[1].share_event.accepted, false);
+
+        assert!(share_events[1].share_event.share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(share_events[1]
+            .share_event
+            .accepted_declined_timestamp
+            .is_none());
+
+        accept_invitation(
+            &db_connection,
+            share_events[0].share_event.id,
+            created_user2.id,
+        )
+        .unwrap();
+
+        let share_events =
+            get_all_pending_invitations_for_user(&db_connection, created_user2.id).unwrap();
+
+        assert_eq!(share_events.len(), 1);
+
+        assert_eq!(
+            share_events[0].share_event.recipient_user_id,
+            created_user2.id
+        );
+        assert_eq!(share_events[0].share_event.sharer_user_id, created_user1.id);
+        assert_eq!(share_events[0].share_event.budget_id, budget2.id);
+        assert_eq!(share_events[0].share_event.accepted, false);
+
+        assert!(share_events[0].share_event.share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(share_events[0]
+            .share_event
+            .accepted_declined_timestamp
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_get_all_pending_invitations_made_by_user() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget1 = created_user_and_budget1.budget.clone();
+        let budget2 = created_user_and_budget2.budget.clone();
+
+        invite_user(
+            &db_connection,
+            budget1.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        invite_user(
+            &db_connection,
+            budget2.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let share_events =
+            get_all_pending_invitations_made_by_user(&db_connection, created_user2.id).unwrap();
+
+        assert_eq!(share_events.len(), 0);
+
+        let share_events =
+            get_all_pending_invitations_made_by_user(&db_connection, created_user1.id).unwrap();
+
+        assert_eq!(share_events.len(), 2);
+
+        assert_eq!(share_events[0].recipient_user_id, created_user2.id);
+        assert_eq!(share_events[0].sharer_user_id, created_user1.id);
+        assert_eq!(share_events[0].budget_id, budget1.id);
+        assert_eq!(share_events[0].accepted, false);
+
+        assert!(share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(share_events[0].accepted_declined_timestamp.is_none());
+
+        assert_eq!(share_events[1].recipient_user_id, created_user2.id);
+        assert_eq!(share_events[1].sharer_user_id, created_user1.id);
+        assert_eq!(share_events[1].budget_id, budget2.id);
+        assert_eq!(share_events[1].accepted, false);
+
+        assert!(share_events[1].share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(share_events[1].accepted_declined_timestamp.is_none());
+
+        decline_invitation(&db_connection, share_events[0].id, created_user2.id).unwrap();
+
+        let share_events =
+            get_all_pending_invitations_made_by_user(&db_connection, created_user1.id).unwrap();
+
+        assert_eq!(share_events.len(), 1);
+
+        assert_eq!(share_events[0].recipient_user_id, created_user2.id);
+        assert_eq!(share_events[0].sharer_user_id, created_user1.id);
+        assert_eq!(share_events[0].budget_id, budget2.id);
+        assert_eq!(share_events[0].accepted, false);
+
+        assert!(share_events[0].share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(share_events[0].accepted_declined_timestamp.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_get_invitation() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let created_user_and_budget1 = generate_user_and_budget(&db_connection).unwrap();
+        let created_user_and_budget2 = generate_user_and_budget(&db_connection).unwrap();
+
+        let created_user1 = created_user_and_budget1.user.clone();
+        let created_user2 = created_user_and_budget2.user.clone();
+
+        let budget = created_user_and_budget1.budget.clone();
+
+        invite_user(
+            &db_connection,
+            budget.id,
+            created_user2.id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        let created_budget_share_events = budget_share_events
+            .filter(budget_share_event_fields::recipient_user_id.eq(created_user2.id))
+            .filter(budget_share_event_fields::sharer_user_id.eq(created_user1.id))
+            .load::<BudgetShareEvent>(&db_connection)
+            .unwrap();
+
+        assert_eq!(created_budget_share_events.len(), 1);
+
+        accept_invitation(
+            &db_connection,
+            created_budget_share_events[0].id,
+            created_user2.id,
+        )
+        .unwrap();
+
+        let invite = get_invitation(
+            &db_connection,
+            created_budget_share_events[0].id,
+            created_user1.id,
+        )
+        .unwrap();
+
+        assert_eq!(invite.share_event.recipient_user_id, created_user2.id);
+        assert_eq!(invite.share_event.sharer_user_id, created_user1.id);
+        assert_eq!(invite.share_event.budget_id, budget.id);
+        assert_eq!(invite.share_event.accepted, true);
+
+        assert!(invite.share_event.share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(
+            invite.share_event.accepted_declined_timestamp.unwrap()
+                < chrono::Utc::now().naive_utc()
+        );
+        assert!(
+            invite.share_event.accepted_declined_timestamp.unwrap()
+                > invite.share_event.share_timestamp
+        );
+
+        let invite = get_invitation(
+            &db_connection,
+            created_budget_share_events[0].id,
+            created_user2.id,
+        )
+        .unwrap();
+
+        assert_eq!(invite.share_event.recipient_user_id, created_user2.id);
+        assert_eq!(invite.share_event.sharer_user_id, created_user1.id);
+        assert_eq!(invite.share_event.budget_id, budget.id);
+        assert_eq!(invite.share_event.accepted, true);
+
+        assert!(invite.share_event.share_timestamp < chrono::Utc::now().naive_utc());
+        assert!(
+            invite.share_event.accepted_declined_timestamp.unwrap()
+                < chrono::Utc::now().naive_utc()
+        );
+        assert!(
+            invite.share_event.accepted_declined_timestamp.unwrap()
+                > invite.share_event.share_timestamp
+        );
+        assert_eq!(invite.sharer_first_name, created_user1.first_name);
+        assert_eq!(invite.sharer_last_name, created_user1.last_name);
+
+        let (name, description) =
+            get_invitation_budget_details(&db_connection, invite.share_event.budget_id).unwrap();
+        assert_eq!(name, budget.name);
+        assert_eq!(description, budget.description);
+    }
+}
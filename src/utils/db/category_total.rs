@@ -0,0 +1,350 @@
+use diesel::sql_types::{Array, Integer, Uuid as SqlUuid};
+use diesel::{sql_query, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::schema::budget_category_totals as category_total_fields;
+use crate::schema::budget_category_totals::dsl::budget_category_totals;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+
+// The `budget_category_totals` table keeps running totals of the live entries dated within each
+// budget's date range so summaries don't have to aggregate entries on every request.
+//
+// Every write to entries must keep the totals in step within the same transaction. Before entries
+// are updated, `subtract_entries` takes out whatever they currently contribute. After, `add_entries`
+// puts back whatever they contribute now, which is nothing for an entry that was deleted. Inserts
+// only need the latter. Changing a budget's date range changes which entries count, so the
+// budget's totals are rebuilt with `recompute_for_budget`.
+//
+// New, edited and restored entries are added with `add_entries_within_hard_caps`, which also
+// rejects them if they take a hard-capped category over its limit. Only a budget's owners may skip
+// the check.
+
+const UNCATEGORIZED: i16 = -1;
+
+// The constraint name Postgres reports when new entries would go over a category's hard cap
+const HARD_CAP_CONSTRAINT: &str = "category_hard_cap";
+
+pub fn add_entries(
+    db_connection: &DbConnection,
+    entry_ids: &[Uuid],
+) -> Result<(), diesel::result::Error> {
+    apply_entries(db_connection, entry_ids, 1)
+}
+
+// The check reads the totals the entries were just added to, whose rows stay locked until the
+// caller's transaction ends. Must be called inside that transaction.
+pub fn add_entries_within_hard_caps(
+    db_connection: &DbConnection,
+    entry_ids: &[Uuid],
+) -> Result<(), diesel::result::Error> {
+    if entry_ids.is_empty() {
+        return Ok(());
+    }
+
+    apply_entries(db_connection, entry_ids, 1)?;
+
+    sql_query("SELECT check_category_hard_caps($1)")
+        .bind::<Array<SqlUuid>, _>(entry_ids)
+        .execute(db_connection)?;
+
+    Ok(())
+}
+
+pub fn is_hard_cap_violation(e: &diesel::result::Error) -> bool {
+    matches!(
+        e,
+        diesel::result::Error::DatabaseError(_, info)
+            if info.constraint_name() == Some(HARD_CAP_CONSTRAINT)
+    )
+}
+
+pub fn subtract_entries(
+    db_connection: &DbConnection,
+    entry_ids: &[Uuid],
+) -> Result<(), diesel::result::Error> {
+    // Locking the entries keeps a concurrent write from changing them between the subtraction and
+    // the caller's update
+    entries
+        .select(entry_fields::id)
+        .filter(entry_fields::id.eq_any(entry_ids))
+        .for_update()
+        .execute(db_connection)?;
+
+    apply_entries(db_connection, entry_ids, -1)
+}
+
+fn apply_entries(
+    db_connection: &DbConnection,
+    entry_ids: &[Uuid],
+    sign: i32,
+) -> Result<(), diesel::result::Error> {
+    if entry_ids.is_empty() {
+        return Ok(());
+    }
+
+    sql_query(format!(
+        "INSERT INTO budget_category_totals \
+         (budget_id, category, spent_cents, entry_count, tax_cents) \
+         SELECT entries.budget_id, COALESCE(entries.category, {UNCATEGORIZED}), \
+         SUM(entries.amount_cents) * $2, COUNT(*) * $2, \
+         COALESCE(SUM(entries.tax_cents), 0) * $2 \
+         FROM entries \
+         JOIN budgets ON budgets.id = entries.budget_id \
+         WHERE entries.id = ANY($1) \
+         AND entries.is_deleted = FALSE \
+         AND entries.date BETWEEN budgets.start_date AND budgets.end_date \
+         GROUP BY entries.budget_id, COALESCE(entries.category, {UNCATEGORIZED}) \
+         ON CONFLICT (budget_id, category) DO UPDATE \
+         SET spent_cents = budget_category_totals.spent_cents + EXCLUDED.spent_cents, \
+         entry_count = budget_category_totals.entry_count + EXCLUDED.entry_count, \
+         tax_cents = budget_category_totals.tax_cents + EXCLUDED.tax_cents"
+    ))
+    .bind::<Array<SqlUuid>, _>(entry_ids)
+    .bind::<Integer, _>(sign)
+    .execute(db_connection)?;
+
+    Ok(())
+}
+
+pub fn recompute_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<(), diesel::result::Error> {
+    diesel::delete(budget_category_totals.filter(category_total_fields::budget_id.eq(budget_id)))
+        .execute(db_connection)?;
+
+    sql_query(format!(
+        "INSERT INTO budget_category_totals \
+         (budget_id, category, spent_cents, entry_count, tax_cents) \
+         SELECT entries.budget_id, COALESCE(entries.category, {UNCATEGORIZED}), \
+         SUM(entries.amount_cents), COUNT(*), COALESCE(SUM(entries.tax_cents), 0) \
+         FROM entries \
+         JOIN budgets ON budgets.id = entries.budget_id \
+         WHERE entries.budget_id = $1 \
+         AND entries.is_deleted = FALSE \
+         AND entries.date BETWEEN budgets.start_date AND budgets.end_date \
+         GROUP BY entries.budget_id, COALESCE(entries.category, {UNCATEGORIZED})"
+    ))
+    .bind::<SqlUuid, _>(budget_id)
+    .execute(db_connection)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use chrono::NaiveDate;
+    use std::collections::BTreeMap;
+
+    use crate::env;
+    use crate::handlers::request_io::{
+        InputEditBudget, InputEditEntry, InputEntry, InputEntryFilter, InputEntryImport,
+        InputImportedEntry,
+    };
+    use crate::models::entry::Entry;
+    use crate::utils::db::testing::create_user_and_budget;
+    use crate::utils::db::{budget, import};
+
+    // The running totals must always match a fresh aggregate of the budget's entries
+    fn assert_totals_match_entries(db_connection: &DbConnection, budget_id: Uuid) {
+        let (start_date, end_date) = (
+            NaiveDate::from_ymd(2022, 6, 1),
+            NaiveDate::from_ymd(2022, 6, 30),
+        );
+
+        let mut expected = BTreeMap::<i16, (i64, i64)>::new();
+
+        for entry in entries
+            .filter(entry_fields::budget_id.eq(budget_id))
+            .filter(entry_fields::is_deleted.eq(false))
+            .load::<Entry>(db_connection)
+            .unwrap()
+        {
+            if entry.date < start_date || entry.date > end_date {
+                continue;
+            }
+
+            let total = expected
+                .entry(entry.category.unwrap_or(UNCATEGORIZED))
+                .or_default();
+            total.0 += entry.amount_cents;
+            total.1 += 1;
+        }
+
+        let actual = budget_category_totals
+            .select((
+                category_total_fields::category,
+                category_total_fields::spent_cents,
+                category_total_fields::entry_count,
+            ))
+            .filter(category_total_fields::budget_id.eq(budget_id))
+            .filter(category_total_fields::entry_count.gt(0))
+            .load::<(i16, i64, i64)>(db_connection)
+            .unwrap()
+            .into_iter()
+            .map(|(c, s, n)| (c, (s, n)))
+            .collect::<BTreeMap<_, _>>();
+
+        assert_eq!(actual, expected);
+    }
+
+    fn entry(budget_id: Uuid, category: Option<i16>, amount_cents: i64, day: u32) -> InputEntry {
+        InputEntry {
+            id: None,
+            budget_id,
+            amount_cents,
+            date: NaiveDate::from_ymd(2022, 6, day),
+            name: None,
+            category,
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        }
+    }
+
+    #[test]
+    fn test_totals_follow_entry_writes() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 6, 1),
+            NaiveDate::from_ymd(2022, 6, 30),
+        );
+
+        let groceries = budget::create_entry(
+            &db_connection,
+            &web::Json(entry(budget_id, Some(0), 1200, 3)),
+            user_id,
+        )
+        .unwrap();
+        let rent = budget::create_entry(
+            &db_connection,
+            &web::Json(entry(budget_id, Some(1), 90000, 1)),
+            user_id,
+        )
+        .unwrap();
+        budget::create_entry(
+            &db_connection,
+            &web::Json(entry(budget_id, None, 450, 12)),
+            user_id,
+        )
+        .unwrap();
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        // Moved to another category
+        budget::edit_entry(
+            &db_connection,
+            user_id,
+            &InputEditEntry {
+                entry_id: groceries.id,
+                amount_cents: 1500,
+                date: groceries.date,
+                name: None,
+                category: Some(1),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            },
+        )
+        .unwrap();
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        // Moved out of the budget's date range
+        budget::edit_entry(
+            &db_connection,
+            user_id,
+            &InputEditEntry {
+                entry_id: rent.id,
+                amount_cents: rent.amount_cents,
+                date: NaiveDate::from_ymd(2022, 7, 1),
+                name: None,
+                category: rent.category,
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            },
+        )
+        .unwrap();
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        // Another user's failed edit and deletion leave the totals alone
+        let (other_user_id, _) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 6, 1),
+            NaiveDate::from_ymd(2022, 6, 30),
+        );
+        assert_eq!(
+            budget::delete_entry(&db_connection, other_user_id, groceries.id).unwrap(),
+            0
+        );
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        assert_eq!(
+            budget::delete_entry(&db_connection, user_id, groceries.id).unwrap(),
+            1
+        );
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        let import_batch = import::import_entries(
+            &db_connection,
+            user_id,
+            &InputEntryImport {
+                budget_id,
+                source_name: String::from("june.csv"),
+                entries: (1..=4)
+                    .map(|day| InputImportedEntry {
+                        amount_cents: 100 * day as i64,
+                        date: NaiveDate::from_ymd(2022, 6, day),
+                        name: None,
+                        category: Some(0),
+                        note: None,
+                    })
+                    .collect(),
+            },
+        )
+        .unwrap();
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        let filter = InputEntryFilter {
+            budget_id,
+            start_date: Some(NaiveDate::from_ymd(2022, 6, 3)),
+            end_date: None,
+            category: Some(0),
+            import_batch_id: Some(import_batch.id),
+        };
+        budget::delete_entries_matching_filter(&db_connection, &filter, 2).unwrap();
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        import::rollback_import_batch(&db_connection, user_id, import_batch.id).unwrap();
+        assert_totals_match_entries(&db_connection, budget_id);
+
+        // Widening the date range takes in the entry that was moved out of it
+        budget::edit_budget(
+            &db_connection,
+            &web::Json(InputEditBudget {
+                id: budget_id,
+                name: String::from("Summer"),
+                description: None,
+                start_date: NaiveDate::from_ymd(2022, 6, 1),
+                end_date: NaiveDate::from_ymd(2022, 7, 31),
+            }),
+        )
+        .unwrap();
+
+        let rent_total = budget_category_totals
+            .select(category_total_fields::spent_cents)
+            .filter(category_total_fields::budget_id.eq(budget_id))
+            .filter(category_total_fields::category.eq(1))
+            .first::<i64>(&db_connection)
+            .unwrap();
+        assert_eq!(rent_total, 90000);
+    }
+}
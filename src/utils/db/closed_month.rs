@@ -0,0 +1,154 @@
+use chrono::NaiveDate;
+use diesel::sql_types::{Date, Uuid as SqlUuid};
+use diesel::{dsl, sql_query, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{OutputClosedMonth, OutputClosedMonthTotal};
+use crate::models::closed_month::{ClosedMonth, NewClosedMonth};
+use crate::models::closed_month_total::ClosedMonthTotal;
+use crate::models::user_notification::NewUserNotification;
+use crate::schema::closed_month_totals as closed_month_total_fields;
+use crate::schema::closed_month_totals::dsl::closed_month_totals;
+use crate::schema::closed_months as closed_month_fields;
+use crate::schema::closed_months::dsl::closed_months;
+use crate::schema::user_budgets as user_budget_fields;
+use crate::schema::user_budgets::dsl::user_budgets;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::utils::db::push;
+use crate::utils::notification::{MonthClosedData, NotificationType};
+use crate::utils::record_id;
+
+// Once a month is closed, a trigger on the entries table rejects any change to the budget's entries
+// dated in that month until an owner reopens it. `month` is always the first day of the month.
+
+const UNCATEGORIZED: i16 = -1;
+
+// Closes the month, records what was spent in each category and lets the budget's other members
+// know. Fails with a unique violation if the month is already closed.
+pub fn close_month(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    month: NaiveDate,
+    closer_user_id: Uuid,
+) -> Result<(), diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let current_time = chrono::Utc::now().naive_utc();
+
+        dsl::insert_into(closed_months)
+            .values(&NewClosedMonth {
+                budget_id,
+                month,
+                closed_by_user_id: Some(closer_user_id),
+                closed_timestamp: current_time,
+            })
+            .execute(db_connection)?;
+
+        sql_query(format!(
+            "INSERT INTO closed_month_totals \
+             (budget_id, month, category, spent_cents, entry_count) \
+             SELECT $1, $2, COALESCE(category, {UNCATEGORIZED}), SUM(amount_cents), COUNT(*) \
+             FROM entries \
+             WHERE budget_id = $1 \
+             AND is_deleted = FALSE \
+             AND date >= $2 \
+             AND date < ($2 + INTERVAL '1 month')::DATE \
+             GROUP BY COALESCE(category, {UNCATEGORIZED})"
+        ))
+        .bind::<SqlUuid, _>(budget_id)
+        .bind::<Date, _>(month)
+        .execute(db_connection)?;
+
+        let associated_data = serde_json::to_string(&MonthClosedData {
+            budget_id,
+            month,
+            closer_user_id,
+        })
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+        let member_ids = user_budgets
+            .select(user_budget_fields::user_id)
+            .filter(user_budget_fields::budget_id.eq(budget_id))
+            .filter(user_budget_fields::user_id.ne(closer_user_id))
+            .load::<Uuid>(db_connection)?;
+
+        let new_notifications = member_ids
+            .iter()
+            .map(|member_id| NewUserNotification {
+                id: record_id::generate(),
+                user_id: *member_id,
+                is_unread: true,
+                is_pristine: true,
+                is_deleted: false,
+                notification_type: i16::from(NotificationType::MonthClosed),
+                alt_title: "Month closed",
+                alt_message: "A month in one of your shared budgets has been closed.",
+                associated_data: Some(&associated_data),
+                modified_timestamp: current_time,
+                created_timestamp: current_time,
+            })
+            .collect::<Vec<_>>();
+
+        push::trace_queued_pushes(db_connection)?;
+        dsl::insert_into(user_notifications)
+            .values(&new_notifications)
+            .execute(db_connection)?;
+
+        Ok(())
+    })
+}
+
+// Returns the number of months reopened, which is 0 if the month wasn't closed. The month's totals
+// are deleted along with it.
+pub fn reopen_month(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    month: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        closed_months
+            .filter(closed_month_fields::budget_id.eq(budget_id))
+            .filter(closed_month_fields::month.eq(month)),
+    )
+    .execute(db_connection)
+}
+
+// Newest first
+pub fn get_closed_months(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<OutputClosedMonth>, diesel::result::Error> {
+    let months = closed_months
+        .filter(closed_month_fields::budget_id.eq(budget_id))
+        .order(closed_month_fields::month.desc())
+        .load::<ClosedMonth>(db_connection)?;
+
+    let totals = closed_month_totals
+        .filter(closed_month_total_fields::budget_id.eq(budget_id))
+        .order(closed_month_total_fields::category.asc())
+        .load::<ClosedMonthTotal>(db_connection)?;
+
+    let output = months
+        .into_iter()
+        .map(|m| OutputClosedMonth {
+            month: m.month,
+            closed_by_user_id: m.closed_by_user_id,
+            closed_timestamp: m.closed_timestamp,
+            totals: totals
+                .iter()
+                .filter(|t| t.month == m.month)
+                .map(|t| OutputClosedMonthTotal {
+                    category: if t.category == UNCATEGORIZED {
+                        None
+                    } else {
+                        Some(t.category)
+                    },
+                    spent_cents: t.spent_cents,
+                    entry_count: t.entry_count,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(output)
+}
@@ -0,0 +1,106 @@
+use chrono::NaiveDate;
+use diesel::{dsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::user_daily_action::NewUserDailyAction;
+use crate::schema::user_daily_actions as daily_action_fields;
+use crate::schema::user_daily_actions::dsl::user_daily_actions;
+
+// Actions a user can only take so many times a day. The values are stored in the database, so
+// existing variants must keep their numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DailyAction {
+    BudgetInvitation = 0,
+    BudgetComment = 1,
+}
+
+// Counts an action against the user's actions of that kind for the day. Returns the number taken
+// that day, including this one.
+pub fn record_daily_action(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    action: DailyAction,
+    date: NaiveDate,
+) -> Result<i32, diesel::result::Error> {
+    let new_action = NewUserDailyAction {
+        user_id,
+        action: action as i16,
+        action_date: date,
+        action_count: 1,
+    };
+
+    dsl::insert_into(user_daily_actions)
+        .values(&new_action)
+        .on_conflict((
+            daily_action_fields::user_id,
+            daily_action_fields::action,
+            daily_action_fields::action_date,
+        ))
+        .do_update()
+        .set(daily_action_fields::action_count.eq(daily_action_fields::action_count + 1))
+        .returning(daily_action_fields::action_count)
+        .get_result::<i32>(db_connection)
+}
+
+pub fn purge_daily_actions_before(
+    db_connection: &DbConnection,
+    date: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(user_daily_actions.filter(daily_action_fields::action_date.lt(date)))
+        .execute(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use rand::prelude::*;
+
+    use crate::env;
+    use crate::handlers::request_io::InputUser;
+    use crate::utils::db::user;
+
+    #[test]
+    fn test_record_daily_action() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+
+        let user_id = user::create_user(
+            &db_connection,
+            &web::Json(InputUser {
+                email: format!("test_user{}@test.com", &user_number),
+                password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+                first_name: format!("Test-{}", &user_number),
+                last_name: format!("User-{}", &user_number),
+                date_of_birth: NaiveDate::from_ymd(1990, 4, 12),
+                currency: String::from("USD"),
+            }),
+        )
+        .unwrap()
+        .id;
+
+        let yesterday = NaiveDate::from_ymd(2022, 6, 14);
+        let today = NaiveDate::from_ymd(2022, 6, 15);
+
+        let record =
+            |action, date| record_daily_action(&db_connection, user_id, action, date).unwrap();
+
+        assert_eq!(record(DailyAction::BudgetInvitation, yesterday), 1);
+        assert_eq!(record(DailyAction::BudgetInvitation, today), 1);
+        assert_eq!(record(DailyAction::BudgetInvitation, today), 2);
+        assert_eq!(record(DailyAction::BudgetComment, today), 1);
+
+        purge_daily_actions_before(&db_connection, today).unwrap();
+
+        let remaining_dates = user_daily_actions
+            .select(daily_action_fields::action_date)
+            .filter(daily_action_fields::user_id.eq(user_id))
+            .load::<NaiveDate>(&db_connection)
+            .unwrap();
+        assert_eq!(remaining_dates, vec![today, today]);
+
+        assert_eq!(record(DailyAction::BudgetInvitation, today), 3);
+    }
+}
@@ -0,0 +1,418 @@
+use chrono::NaiveDate;
+use diesel::{dsl, BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::InputSpendingChallenge;
+use crate::models::spending_challenge::{NewSpendingChallenge, SpendingChallenge};
+use crate::models::user_badge::{NewUserBadge, UserBadge};
+use crate::models::user_notification::NewUserNotification;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::spending_challenges as challenge_fields;
+use crate::schema::spending_challenges::dsl::spending_challenges;
+use crate::schema::user_badges as badge_fields;
+use crate::schema::user_badges::dsl::user_badges;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::utils::engagement::Badge;
+use crate::utils::notification::{ChallengeEndedData, NotificationType};
+use crate::utils::record_id;
+
+pub fn get_spending_dates_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    since: NaiveDate,
+) -> Result<Vec<NaiveDate>, diesel::result::Error> {
+    entries
+        .select(entry_fields::date)
+        .filter(entry_fields::user_id.eq(user_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .filter(entry_fields::amount_cents.gt(0))
+        .filter(entry_fields::date.ge(since))
+        .distinct()
+        .load::<NaiveDate>(db_connection)
+}
+
+pub fn get_spent_cents_in_category(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    category_id: i16,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<i64, diesel::result::Error> {
+    let amounts = entries
+        .select(entry_fields::amount_cents)
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::category.eq(category_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .filter(entry_fields::date.between(start_date, end_date))
+        .load::<i64>(db_connection)?;
+
+    Ok(amounts.iter().sum())
+}
+
+pub fn create_challenge(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    challenge_data: &InputSpendingChallenge,
+) -> Result<SpendingChallenge, diesel::result::Error> {
+    let new_challenge = NewSpendingChallenge {
+        id: record_id::generate(),
+        user_id,
+        budget_id: challenge_data.budget_id,
+        category_id: challenge_data.category_id,
+        limit_cents: challenge_data.limit_cents,
+        start_date: challenge_data.start_date,
+        end_date: challenge_data.end_date,
+        is_succeeded: None,
+        notify_on_completion: challenge_data.notify_on_completion,
+        created_timestamp: chrono::Utc::now().naive_utc(),
+    };
+
+    dsl::insert_into(spending_challenges)
+        .values(&new_challenge)
+        .get_result::<SpendingChallenge>(db_connection)
+}
+
+pub fn get_all_challenges_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<SpendingChallenge>, diesel::result::Error> {
+    spending_challenges
+        .filter(challenge_fields::user_id.eq(user_id))
+        .order(challenge_fields::start_date.desc())
+        .load::<SpendingChallenge>(db_connection)
+}
+
+pub fn delete_challenge(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    challenge_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        spending_challenges.filter(
+            challenge_fields::id
+                .eq(challenge_id)
+                .and(challenge_fields::user_id.eq(user_id)),
+        ),
+    )
+    .execute(db_connection)
+}
+
+pub fn award_badges(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    badges: &[Badge],
+) -> Result<usize, diesel::result::Error> {
+    if badges.is_empty() {
+        return Ok(0);
+    }
+
+    let current_time = chrono::Utc::now().naive_utc();
+    let new_badges = badges
+        .iter()
+        .map(|b| NewUserBadge {
+            id: record_id::generate(),
+            user_id,
+            badge: i16::from(*b),
+            earned_timestamp: current_time,
+        })
+        .collect::<Vec<_>>();
+
+    dsl::insert_into(user_badges)
+        .values(&new_badges)
+        .on_conflict((badge_fields::user_id, badge_fields::badge))
+        .do_nothing()
+        .execute(db_connection)
+}
+
+pub fn get_badges_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<UserBadge>, diesel::result::Error> {
+    user_badges
+        .filter(badge_fields::user_id.eq(user_id))
+        .order(badge_fields::earned_timestamp.asc())
+        .load::<UserBadge>(db_connection)
+}
+
+// Evaluates every challenge that ended before today and hasn't been evaluated yet, awarding badges
+// and sending notifications to users who opted in. Returns the number of challenges evaluated.
+pub fn evaluate_ended_challenges(
+    db_connection: &DbConnection,
+    today: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    let ended_challenges = spending_challenges
+        .filter(challenge_fields::end_date.lt(today))
+        .filter(challenge_fields::is_succeeded.is_null())
+        .load::<SpendingChallenge>(db_connection)?;
+
+    for challenge in ended_challenges.iter() {
+        db_connection.transaction::<_, diesel::result::Error, _>(|| {
+            evaluate_challenge(db_connection, challenge)
+        })?;
+    }
+
+    Ok(ended_challenges.len())
+}
+
+fn evaluate_challenge(
+    db_connection: &DbConnection,
+    challenge: &SpendingChallenge,
+) -> Result<(), diesel::result::Error> {
+    let spent_cents = get_spent_cents_in_category(
+        db_connection,
+        challenge.budget_id,
+        challenge.category_id,
+        challenge.start_date,
+        challenge.end_date,
+    )?;
+    let is_succeeded = spent_cents <= challenge.limit_cents;
+
+    diesel::update(spending_challenges.find(challenge.id))
+        .set(challenge_fields::is_succeeded.eq(is_succeeded))
+        .execute(db_connection)?;
+
+    if is_succeeded {
+        let succeeded_count = spending_challenges
+            .filter(challenge_fields::user_id.eq(challenge.user_id))
+            .filter(challenge_fields::is_succeeded.eq(true))
+            .count()
+            .get_result::<i64>(db_connection)?;
+
+        award_badges(
+            db_connection,
+            challenge.user_id,
+            &Badge::for_completed_challenge_count(succeeded_count),
+        )?;
+    }
+
+    if challenge.notify_on_completion {
+        let (title, message) = if is_succeeded {
+            (
+                "Challenge complete",
+                "Nice work! You stayed under your spending limit.",
+            )
+        } else {
+            (
+                "Challenge ended",
+                "You went over your spending limit this time. Try again?",
+            )
+        };
+
+        let associated_data = serde_json::to_string(&ChallengeEndedData {
+            challenge_id: challenge.id,
+        })
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+        let current_time = chrono::Utc::now().naive_utc();
+
+        let notification = NewUserNotification {
+            id: record_id::generate(),
+            user_id: challenge.user_id,
+            is_unread: true,
+            is_pristine: true,
+            is_deleted: false,
+            notification_type: i16::from(NotificationType::ChallengeEnded),
+            alt_title: title,
+            alt_message: message,
+            associated_data: Some(&associated_data),
+            modified_timestamp: current_time,
+            created_timestamp: current_time,
+        };
+
+        dsl::insert_into(user_notifications)
+            .values(&notification)
+            .execute(db_connection)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use chrono::Duration;
+
+    use crate::env;
+    use crate::handlers::request_io::InputEntry;
+    use crate::models::user_notification::UserNotification;
+    use crate::schema::user_notifications as notification_fields;
+    use crate::utils::db::budget;
+    use crate::utils::db::testing::create_user_and_budget;
+
+    fn add_entry(
+        db_connection: &DbConnection,
+        user_id: Uuid,
+        budget_id: Uuid,
+        date: NaiveDate,
+        amount_cents: i64,
+    ) {
+        let entry = InputEntry {
+            id: None,
+            budget_id,
+            amount_cents,
+            date,
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+
+        budget::create_entry(db_connection, &web::Json(entry), user_id).unwrap();
+    }
+
+    #[test]
+    fn test_get_spending_dates_for_user() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let start_date = NaiveDate::from_ymd(2022, 3, 1);
+        let (user_id, budget_id) =
+            create_user_and_budget(&db_connection, start_date, start_date + Duration::days(30));
+
+        add_entry(&db_connection, user_id, budget_id, start_date, 500);
+        add_entry(&db_connection, user_id, budget_id, start_date, 700);
+        add_entry(
+            &db_connection,
+            user_id,
+            budget_id,
+            start_date + Duration::days(4),
+            100,
+        );
+        add_entry(
+            &db_connection,
+            user_id,
+            budget_id,
+            start_date + Duration::days(5),
+            -100,
+        );
+
+        let mut dates = get_spending_dates_for_user(&db_connection, user_id, start_date).unwrap();
+        dates.sort();
+        assert_eq!(dates, vec![start_date, start_date + Duration::days(4)]);
+
+        let dates =
+            get_spending_dates_for_user(&db_connection, user_id, start_date + Duration::days(1))
+                .unwrap();
+        assert_eq!(dates, vec![start_date + Duration::days(4)]);
+    }
+
+    #[test]
+    fn test_evaluate_ended_challenges() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let start_date = NaiveDate::from_ymd(2022, 5, 2);
+        let (user_id, budget_id) =
+            create_user_and_budget(&db_connection, start_date, start_date + Duration::days(30));
+
+        add_entry(&db_connection, user_id, budget_id, start_date, 3000);
+        add_entry(
+            &db_connection,
+            user_id,
+            budget_id,
+            start_date + Duration::days(3),
+            2000,
+        );
+
+        // Outside of the challenge's dates
+        add_entry(
+            &db_connection,
+            user_id,
+            budget_id,
+            start_date + Duration::days(9),
+            9000,
+        );
+
+        let under_limit = create_challenge(
+            &db_connection,
+            user_id,
+            &InputSpendingChallenge {
+                budget_id,
+                category_id: 0,
+                limit_cents: 6000,
+                start_date,
+                end_date: start_date + Duration::days(6),
+                notify_on_completion: true,
+            },
+        )
+        .unwrap();
+
+        let over_limit = create_challenge(
+            &db_connection,
+            user_id,
+            &InputSpendingChallenge {
+                budget_id,
+                category_id: 0,
+                limit_cents: 4000,
+                start_date,
+                end_date: start_date + Duration::days(6),
+                notify_on_completion: false,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_spent_cents_in_category(
+                &db_connection,
+                budget_id,
+                0,
+                start_date,
+                start_date + Duration::days(6)
+            )
+            .unwrap(),
+            5000
+        );
+
+        // Challenges that haven't ended yet are left alone
+        evaluate_ended_challenges(&db_connection, start_date + Duration::days(6)).unwrap();
+        let challenges = get_all_challenges_for_user(&db_connection, user_id).unwrap();
+        assert!(challenges.iter().all(|c| c.is_succeeded.is_none()));
+
+        evaluate_ended_challenges(&db_connection, start_date + Duration::days(7)).unwrap();
+        let challenges = get_all_challenges_for_user(&db_connection, user_id).unwrap();
+
+        let under_limit = challenges.iter().find(|c| c.id == under_limit.id).unwrap();
+        let over_limit = challenges.iter().find(|c| c.id == over_limit.id).unwrap();
+        assert_eq!(under_limit.is_succeeded, Some(true));
+        assert_eq!(over_limit.is_succeeded, Some(false));
+
+        let badges = get_badges_for_user(&db_connection, user_id).unwrap();
+        assert_eq!(badges.len(), 1);
+        assert_eq!(badges[0].badge, i16::from(Badge::FirstChallengeCompleted));
+
+        // Only the challenge that opted in produces a notification
+        let notifications = user_notifications
+            .filter(notification_fields::user_id.eq(user_id))
+            .load::<UserNotification>(&db_connection)
+            .unwrap();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(
+            notifications[0].notification_type,
+            i16::from(NotificationType::ChallengeEnded)
+        );
+        assert!(notifications[0]
+            .associated_data
+            .as_ref()
+            .unwrap()
+            .contains(&under_limit.id.to_string()));
+    }
+
+    #[test]
+    fn test_award_badges_is_idempotent() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, _) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 1, 31),
+        );
+
+        award_badges(&db_connection, user_id, &Badge::for_streak(8)).unwrap();
+        award_badges(&db_connection, user_id, &Badge::for_streak(31)).unwrap();
+
+        let badges = get_badges_for_user(&db_connection, user_id).unwrap();
+        assert_eq!(badges.len(), 2);
+        assert_eq!(badges[0].badge, i16::from(Badge::SevenDayNoSpendStreak));
+        assert_eq!(badges[1].badge, i16::from(Badge::ThirtyDayNoSpendStreak));
+    }
+}
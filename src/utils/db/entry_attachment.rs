@@ -0,0 +1,79 @@
+use diesel::{dsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::entry_attachment::{EntryAttachment, NewEntryAttachment};
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::entry_attachments as entry_attachment_fields;
+use crate::schema::entry_attachments::dsl::entry_attachments;
+
+pub fn get_attachments_for_entry(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    entry_id: Uuid,
+) -> Result<Vec<EntryAttachment>, diesel::result::Error> {
+    entry_attachments
+        .filter(entry_attachment_fields::entry_id.eq(entry_id))
+        .filter(entry_attachment_fields::budget_id.eq(budget_id))
+        .order(entry_attachment_fields::created_timestamp.asc())
+        .load::<EntryAttachment>(db_connection)
+}
+
+pub fn get_attachment(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<EntryAttachment, diesel::result::Error> {
+    entry_attachments
+        .filter(entry_attachment_fields::id.eq(attachment_id))
+        .filter(entry_attachment_fields::budget_id.eq(budget_id))
+        .first::<EntryAttachment>(db_connection)
+}
+
+// Records the attachment before its file is stored so the limit is enforced up front. Returns None
+// if the entry already has `max_attachments` attachments. Fails with `NotFound` if the entry isn't
+// a live entry in the budget.
+pub fn add_attachment(
+    db_connection: &DbConnection,
+    attachment: &NewEntryAttachment,
+    max_attachments: i64,
+) -> Result<Option<EntryAttachment>, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // Locking the entry keeps concurrent uploads from going over the limit together
+        entries
+            .filter(entry_fields::id.eq(attachment.entry_id))
+            .filter(entry_fields::budget_id.eq(attachment.budget_id))
+            .filter(entry_fields::is_deleted.eq(false))
+            .select(entry_fields::id)
+            .for_update()
+            .first::<Uuid>(db_connection)?;
+
+        let attachment_count = entry_attachments
+            .filter(entry_attachment_fields::entry_id.eq(attachment.entry_id))
+            .count()
+            .get_result::<i64>(db_connection)?;
+
+        if attachment_count >= max_attachments {
+            return Ok(None);
+        }
+
+        dsl::insert_into(entry_attachments)
+            .values(attachment)
+            .get_result::<EntryAttachment>(db_connection)
+            .map(Some)
+    })
+}
+
+pub fn delete_attachment(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    attachment_id: Uuid,
+) -> Result<EntryAttachment, diesel::result::Error> {
+    diesel::delete(
+        entry_attachments
+            .filter(entry_attachment_fields::id.eq(attachment_id))
+            .filter(entry_attachment_fields::budget_id.eq(budget_id)),
+    )
+    .get_result::<EntryAttachment>(db_connection)
+}
@@ -0,0 +1,115 @@
+use diesel::{dsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{InputFundAllocation, OutputEnvelope, OutputEnvelopeSummary};
+use crate::models::category_allocation::{CategoryAllocation, NewCategoryAllocation};
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::schema::categories as category_fields;
+use crate::schema::categories::dsl::categories;
+use crate::schema::category_allocations as allocation_fields;
+use crate::schema::category_allocations::dsl::category_allocations;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::utils::record_id;
+
+// In an envelope budget, income is any uncategorized entry with a negative amount (an inflow).
+// Income becomes money to be budgeted until it is allocated to a category.
+
+pub fn is_envelope_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<bool, diesel::result::Error> {
+    budgets
+        .find(budget_id)
+        .select(budget_fields::is_envelope)
+        .first::<bool>(db_connection)
+}
+
+pub fn get_envelope_summary(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<OutputEnvelopeSummary, diesel::result::Error> {
+    let category_ids = categories
+        .select(category_fields::id)
+        .filter(category_fields::budget_id.eq(budget_id))
+        .filter(category_fields::is_deleted.eq(false))
+        .order(category_fields::id.asc())
+        .load::<i16>(db_connection)?;
+
+    let budget_entries = entries
+        .select((entry_fields::category, entry_fields::amount_cents))
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .load::<(Option<i16>, i64)>(db_connection)?;
+
+    let allocations = category_allocations
+        .select((allocation_fields::category, allocation_fields::amount_cents))
+        .filter(allocation_fields::budget_id.eq(budget_id))
+        .load::<(i16, i64)>(db_connection)?;
+
+    let mut income_cents = 0;
+    let mut spent_by_category = BTreeMap::<i16, i64>::new();
+
+    for (category, amount_cents) in budget_entries.into_iter() {
+        match category {
+            Some(c) => *spent_by_category.entry(c).or_default() += amount_cents,
+            None if amount_cents < 0 => income_cents -= amount_cents,
+            None => (),
+        }
+    }
+
+    let mut allocated_by_category = BTreeMap::<i16, i64>::new();
+
+    for (category, amount_cents) in allocations.iter() {
+        *allocated_by_category.entry(*category).or_default() += amount_cents;
+    }
+
+    // Money allocated to a category that has since been deleted stays allocated so that the total
+    // to be budgeted doesn't jump when a category is removed
+    let allocated_cents = allocated_by_category.values().sum::<i64>();
+
+    let envelopes = category_ids
+        .into_iter()
+        .map(|category| {
+            let allocated_cents = allocated_by_category.get(&category).copied().unwrap_or(0);
+            let spent_cents = spent_by_category.get(&category).copied().unwrap_or(0);
+
+            OutputEnvelope {
+                category,
+                allocated_cents,
+                spent_cents,
+                available_cents: allocated_cents - spent_cents,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(OutputEnvelopeSummary {
+        budget_id,
+        income_cents,
+        allocated_cents,
+        to_be_budgeted_cents: income_cents - allocated_cents,
+        envelopes,
+    })
+}
+
+pub fn allocate_funds(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    allocation_data: &InputFundAllocation,
+) -> Result<CategoryAllocation, diesel::result::Error> {
+    let new_allocation = NewCategoryAllocation {
+        id: record_id::generate(),
+        budget_id: allocation_data.budget_id,
+        user_id,
+        category: allocation_data.category,
+        amount_cents: allocation_data.amount_cents,
+        created_timestamp: chrono::Utc::now().naive_utc(),
+    };
+
+    dsl::insert_into(category_allocations)
+        .values(&new_allocation)
+        .get_result::<CategoryAllocation>(db_connection)
+}
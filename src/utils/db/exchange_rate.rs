@@ -0,0 +1,50 @@
+use diesel::{dsl, Connection, RunQueryDsl};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::definitions::*;
+use crate::models::exchange_rate::{ExchangeRate, NewExchangeRate};
+use crate::schema::exchange_rates::dsl::exchange_rates;
+use crate::utils::currency::ExchangeRates;
+
+// Swaps in a fresh set of rates, all relative to the same base currency
+pub fn replace_exchange_rates(
+    db_connection: &DbConnection,
+    base_currency: &str,
+    rates: &BTreeMap<String, f64>,
+) -> Result<usize, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_rates = rates
+        .iter()
+        .map(|(currency, rate)| NewExchangeRate {
+            currency,
+            base_currency,
+            rate: *rate,
+            fetched_timestamp: current_time,
+        })
+        .collect::<Vec<_>>();
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::delete(exchange_rates).execute(db_connection)?;
+
+        dsl::insert_into(exchange_rates)
+            .values(&new_rates)
+            .execute(db_connection)
+    })
+}
+
+// Rates cached under a base currency other than the configured one (left over from before the
+// base was changed) are ignored
+pub fn get_exchange_rates(
+    db_connection: &DbConnection,
+    base_currency: &str,
+) -> Result<ExchangeRates, diesel::result::Error> {
+    let rates = exchange_rates
+        .load::<ExchangeRate>(db_connection)?
+        .into_iter()
+        .filter(|r| r.base_currency.eq_ignore_ascii_case(base_currency))
+        .map(|r| (r.currency, r.rate))
+        .collect::<HashMap<_, _>>();
+
+    Ok(ExchangeRates::new(base_currency, rates))
+}
@@ -0,0 +1,353 @@
+use chrono::NaiveDate;
+use diesel::{dsl, Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::InputEntryImport;
+use crate::models::entry::NewEntry;
+use crate::models::import_batch::{ImportBatch, NewImportBatch};
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::import_batches as import_batch_fields;
+use crate::schema::import_batches::dsl::import_batches;
+use crate::schema::user_budgets as user_budget_fields;
+use crate::schema::user_budgets::dsl::user_budgets;
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::db::category_total;
+use crate::utils::import::{SkippedRow, StatementRow};
+use crate::utils::record_id;
+
+// Every imported entry is tagged with the batch it came in with so the whole import can be undone
+// if the source columns were mapped wrong
+
+pub fn import_entries(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    import_data: &InputEntryImport,
+) -> Result<ImportBatch, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_import_batch = NewImportBatch {
+        id: record_id::generate(),
+        budget_id: import_data.budget_id,
+        user_id,
+        source_name: &import_data.source_name,
+        entry_count: import_data.entries.len() as i32,
+        is_rolled_back: false,
+        created_timestamp: current_time,
+    };
+
+    let new_entries = import_data
+        .entries
+        .iter()
+        .map(|e| NewEntry {
+            id: record_id::generate(),
+            budget_id: import_data.budget_id,
+            user_id,
+            is_deleted: false,
+            amount_cents: e.amount_cents,
+            date: e.date,
+            name: e.name.as_deref(),
+            category: e.category,
+            note: e.note.as_deref(),
+            modified_timestamp: current_time,
+            created_timestamp: current_time,
+            recurring_entry_id: None,
+            import_batch_id: Some(new_import_batch.id),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        })
+        .collect::<Vec<_>>();
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let import_batch = dsl::insert_into(import_batches)
+            .values(&new_import_batch)
+            .get_result::<ImportBatch>(db_connection)?;
+
+        dsl::insert_into(entries)
+            .values(&new_entries)
+            .execute(db_connection)?;
+
+        let new_entry_ids = new_entries.iter().map(|e| e.id).collect::<Vec<_>>();
+        category_total::add_entries_within_hard_caps(db_connection, &new_entry_ids)?;
+
+        diesel::update(budgets.find(import_data.budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
+
+        Ok(import_batch)
+    })
+}
+
+// Rows matching a live entry already in the budget, or an earlier row of the same statement, on
+// date, amount and name are skipped so importing overlapping statements doesn't count anything
+// twice. Returns None for the batch if every row was skipped.
+pub fn import_statement(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    budget_id: Uuid,
+    source_name: &str,
+    rows: &[StatementRow],
+) -> Result<(Option<ImportBatch>, Vec<SkippedRow>), diesel::result::Error> {
+    let (first_date, last_date) = match (
+        rows.iter().map(|r| r.date).min(),
+        rows.iter().map(|r| r.date).max(),
+    ) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok((None, Vec::new())),
+    };
+
+    let current_time = chrono::Utc::now().naive_utc();
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let existing_entries = entries
+            .select((
+                entry_fields::date,
+                entry_fields::amount_cents,
+                entry_fields::name,
+            ))
+            .filter(entry_fields::budget_id.eq(budget_id))
+            .filter(entry_fields::is_deleted.eq(false))
+            .filter(entry_fields::date.between(first_date, last_date))
+            .load::<(NaiveDate, i64, Option<String>)>(db_connection)?;
+
+        let existing_keys = existing_entries
+            .iter()
+            .map(|(date, amount_cents, name)| (*date, *amount_cents, name.as_deref()))
+            .collect::<HashSet<_>>();
+
+        let mut seen_keys = HashSet::new();
+        let mut rows_to_import = Vec::new();
+        let mut skipped_rows = Vec::new();
+
+        for row in rows {
+            let key = row.dedup_key();
+
+            if existing_keys.contains(&key) {
+                skipped_rows.push(SkippedRow {
+                    row: row.row,
+                    reason: "Matches an existing entry",
+                });
+            } else if !seen_keys.insert(key) {
+                skipped_rows.push(SkippedRow {
+                    row: row.row,
+                    reason: "Matches an earlier row",
+                });
+            } else {
+                rows_to_import.push(row);
+            }
+        }
+
+        if rows_to_import.is_empty() {
+            return Ok((None, skipped_rows));
+        }
+
+        let import_batch = dsl::insert_into(import_batches)
+            .values(&NewImportBatch {
+                id: record_id::generate(),
+                budget_id,
+                user_id,
+                source_name,
+                entry_count: rows_to_import.len() as i32,
+                is_rolled_back: false,
+                created_timestamp: current_time,
+            })
+            .get_result::<ImportBatch>(db_connection)?;
+
+        let new_entries = rows_to_import
+            .iter()
+            .map(|r| r.to_new_entry(budget_id, user_id, import_batch.id, current_time))
+            .collect::<Vec<_>>();
+
+        dsl::insert_into(entries)
+            .values(&new_entries)
+            .execute(db_connection)?;
+
+        let new_entry_ids = new_entries.iter().map(|e| e.id).collect::<Vec<_>>();
+        category_total::add_entries_within_hard_caps(db_connection, &new_entry_ids)?;
+
+        diesel::update(budgets.find(budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
+
+        Ok((Some(import_batch), skipped_rows))
+    })
+}
+
+pub fn get_import_batches_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<ImportBatch>, diesel::result::Error> {
+    import_batches
+        .filter(import_batch_fields::budget_id.eq(budget_id))
+        .order(import_batch_fields::created_timestamp.desc())
+        .load::<ImportBatch>(db_connection)
+}
+
+// Soft-deletes every entry that came in with the batch, including any that were edited after the
+// import. Returns None if the batch doesn't exist, the user can't access it, or it has already
+// been rolled back.
+pub fn rollback_import_batch(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    import_batch_id: Uuid,
+) -> Result<Option<usize>, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let import_batch = diesel::update(
+            import_batches
+                .filter(import_batch_fields::id.eq(import_batch_id))
+                .filter(import_batch_fields::is_rolled_back.eq(false))
+                .filter(import_batch_fields::budget_id.eq_any(user_budget_ids)),
+        )
+        .set(import_batch_fields::is_rolled_back.eq(true))
+        .get_result::<ImportBatch>(db_connection)
+        .optional()?;
+
+        let import_batch = match import_batch {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+
+        let current_time = chrono::Utc::now().naive_utc();
+
+        let batch_entry_ids = entries
+            .select(entry_fields::id)
+            .filter(entry_fields::import_batch_id.eq(import_batch.id))
+            .filter(entry_fields::is_deleted.eq(false))
+            .load::<Uuid>(db_connection)?;
+        category_total::subtract_entries(db_connection, &batch_entry_ids)?;
+
+        let deleted_count = diesel::update(
+            entries
+                .filter(entry_fields::import_batch_id.eq(import_batch.id))
+                .filter(entry_fields::is_deleted.eq(false)),
+        )
+        .set((
+            entry_fields::is_deleted.eq(true),
+            entry_fields::modified_timestamp.eq(current_time),
+        ))
+        .execute(db_connection)?;
+
+        diesel::update(budgets.find(import_batch.budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
+
+        Ok(Some(deleted_count))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use chrono::NaiveDate;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputEntry, InputImportedEntry};
+    use crate::models::entry::Entry;
+    use crate::utils::db::budget;
+    use crate::utils::db::testing::create_user_and_budget;
+
+    fn imported_entry(day: u32, amount_cents: i64) -> InputImportedEntry {
+        InputImportedEntry {
+            amount_cents,
+            date: NaiveDate::from_ymd(2022, 6, day),
+            name: Some(format!("Statement line {day}")),
+            category: Some(0),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_import_and_rollback() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 6, 1),
+            NaiveDate::from_ymd(2022, 6, 30),
+        );
+        let (other_user_id, _) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 6, 1),
+            NaiveDate::from_ymd(2022, 6, 30),
+        );
+
+        let manual_entry = budget::create_entry(
+            &db_connection,
+            &web::Json(InputEntry {
+                id: None,
+                budget_id,
+                amount_cents: 700,
+                date: NaiveDate::from_ymd(2022, 6, 2),
+                name: None,
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            }),
+            user_id,
+        )
+        .unwrap();
+
+        let import_data = InputEntryImport {
+            budget_id,
+            source_name: String::from("june.csv"),
+            entries: vec![imported_entry(3, 1200), imported_entry(4, 3400)],
+        };
+
+        let import_batch = import_entries(&db_connection, user_id, &import_data).unwrap();
+        assert_eq!(import_batch.entry_count, 2);
+        assert!(!import_batch.is_rolled_back);
+
+        let imported_entries = entries
+            .filter(entry_fields::import_batch_id.eq(import_batch.id))
+            .load::<Entry>(&db_connection)
+            .unwrap();
+        assert_eq!(imported_entries.len(), 2);
+
+        let batches = get_import_batches_for_budget(&db_connection, budget_id).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].source_name, "june.csv");
+
+        // Another user can't roll back the import
+        assert!(
+            rollback_import_batch(&db_connection, other_user_id, import_batch.id)
+                .unwrap()
+                .is_none()
+        );
+
+        assert_eq!(
+            rollback_import_batch(&db_connection, user_id, import_batch.id).unwrap(),
+            Some(2)
+        );
+        assert!(
+            rollback_import_batch(&db_connection, user_id, import_batch.id)
+                .unwrap()
+                .is_none()
+        );
+
+        let budget_entries = entries
+            .filter(entry_fields::budget_id.eq(budget_id))
+            .load::<Entry>(&db_connection)
+            .unwrap();
+        assert_eq!(budget_entries.len(), 3);
+
+        for entry in budget_entries {
+            assert_eq!(entry.is_deleted, entry.id != manual_entry.id);
+        }
+
+        let batches = get_import_batches_for_budget(&db_connection, budget_id).unwrap();
+        assert!(batches[0].is_rolled_back);
+    }
+}
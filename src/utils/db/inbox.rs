@@ -0,0 +1,164 @@
+use diesel::{dsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{InputInboxAssignment, InputInboxEntry, OutputInboxPage};
+use crate::models::entry::{Entry, NewEntry};
+use crate::models::inbox_entry::{InboxEntry, NewInboxEntry};
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::schema::entries::dsl::entries;
+use crate::schema::inbox_entries as inbox_entry_fields;
+use crate::schema::inbox_entries::dsl::inbox_entries;
+use crate::utils::db::audit_log::{self, AuditAction};
+use crate::utils::db::category_total;
+use crate::utils::record_id;
+
+pub fn add_entries(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    entries_data: &[InputInboxEntry],
+) -> Result<Vec<InboxEntry>, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_entries = entries_data
+        .iter()
+        .map(|e| NewInboxEntry {
+            id: record_id::generate(),
+            user_id,
+            amount_cents: e.amount_cents,
+            date: e.date,
+            name: e.name.as_deref(),
+            note: e.note.as_deref(),
+            created_timestamp: current_time,
+        })
+        .collect::<Vec<_>>();
+
+    dsl::insert_into(inbox_entries)
+        .values(&new_entries)
+        .get_results::<InboxEntry>(db_connection)
+}
+
+// Newest first
+pub fn get_entries(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputInboxPage, diesel::result::Error> {
+    let mut loaded_entries = inbox_entries
+        .filter(inbox_entry_fields::user_id.eq(user_id))
+        .order((
+            inbox_entry_fields::created_timestamp.desc(),
+            inbox_entry_fields::id.desc(),
+        ))
+        .limit(limit + 1)
+        .offset(offset)
+        .load::<InboxEntry>(db_connection)?;
+
+    let has_more = loaded_entries.len() as i64 > limit;
+    loaded_entries.truncate(limit as usize);
+
+    Ok(OutputInboxPage {
+        entries: loaded_entries,
+        has_more,
+    })
+}
+
+pub fn count_entries(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<i64, diesel::result::Error> {
+    inbox_entries
+        .filter(inbox_entry_fields::user_id.eq(user_id))
+        .count()
+        .get_result(db_connection)
+}
+
+// Moves the entries out of the user's inbox and into the budget. The new entries keep the inbox
+// entries' IDs. Fails with NotFound, leaving the inbox as it was, if any of the entries isn't in the
+// user's inbox, which includes an entry that a concurrent request has already assigned.
+pub fn assign_entries(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    assignment: &InputInboxAssignment,
+) -> Result<Vec<Entry>, diesel::result::Error> {
+    let mut inbox_entry_ids = assignment.inbox_entry_ids.clone();
+    inbox_entry_ids.sort_unstable();
+    inbox_entry_ids.dedup();
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let current_time = chrono::Utc::now().naive_utc();
+
+        let assigned_entries = diesel::delete(
+            inbox_entries
+                .filter(inbox_entry_fields::user_id.eq(user_id))
+                .filter(inbox_entry_fields::id.eq_any(&inbox_entry_ids)),
+        )
+        .get_results::<InboxEntry>(db_connection)?;
+
+        if assigned_entries.len() != inbox_entry_ids.len() {
+            return Err(diesel::result::Error::NotFound);
+        }
+
+        let new_entries = assigned_entries
+            .iter()
+            .map(|e| NewEntry {
+                id: e.id,
+                budget_id: assignment.budget_id,
+                user_id,
+                is_deleted: false,
+                amount_cents: e.amount_cents,
+                date: e.date,
+                name: e.name.as_deref(),
+                category: assignment.category,
+                note: e.note.as_deref(),
+                modified_timestamp: current_time,
+                created_timestamp: current_time,
+                recurring_entry_id: None,
+                import_batch_id: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            })
+            .collect::<Vec<_>>();
+
+        let new_entries = dsl::insert_into(entries)
+            .values(&new_entries)
+            .get_results::<Entry>(db_connection)?;
+
+        diesel::update(budgets.find(assignment.budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
+
+        let new_entry_ids = new_entries.iter().map(|e| e.id).collect::<Vec<_>>();
+        category_total::add_entries_within_hard_caps(db_connection, &new_entry_ids)?;
+
+        for entry in new_entries.iter() {
+            audit_log::record(
+                db_connection,
+                entry.budget_id,
+                user_id,
+                AuditAction::EntryAdded,
+                None::<&Entry>,
+                Some(entry),
+            )?;
+        }
+
+        Ok(new_entries)
+    })
+}
+
+// Returns the number of entries removed from the inbox
+pub fn dismiss_entries(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    inbox_entry_ids: &[Uuid],
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        inbox_entries
+            .filter(inbox_entry_fields::user_id.eq(user_id))
+            .filter(inbox_entry_fields::id.eq_any(inbox_entry_ids)),
+    )
+    .execute(db_connection)
+}
@@ -1,3 +1,99 @@
+pub mod activity;
+pub mod api_key;
+pub mod audit_log;
 pub mod auth;
+pub mod benchmarking;
 pub mod budget;
+pub mod budget_comment;
+pub mod budget_resource;
+pub mod budget_share;
+pub mod category_total;
+pub mod closed_month;
+pub mod daily_action;
+pub mod engagement;
+pub mod entry_attachment;
+pub mod envelope;
+pub mod exchange_rate;
+pub mod import;
+pub mod inbox;
+pub mod notification;
+pub mod ops_stats;
+pub mod push;
+pub mod recurring_entry;
+pub mod reimbursement;
+pub mod reminder;
+pub mod session;
+pub mod shopping_list;
+pub mod support;
+pub mod tax_report;
+pub mod trash;
 pub mod user;
+
+#[cfg(test)]
+pub mod testing {
+    use actix_web::web;
+    use chrono::NaiveDate;
+    use rand::prelude::*;
+    use uuid::Uuid;
+
+    use crate::definitions::*;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputUser};
+    use crate::models::user::User;
+    use crate::utils::db::{budget, user};
+
+    pub fn create_test_user(db_connection: &DbConnection) -> User {
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("Ry8^cQ2!mVw5#HbN0tLz"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        user::create_user(db_connection, &web::Json(new_user)).unwrap()
+    }
+
+    // The budget is owned by a new user and has two categories, with IDs 0 and 1. Returns the
+    // user's ID and the budget's ID.
+    pub fn create_user_and_budget(
+        db_connection: &DbConnection,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> (Uuid, Uuid) {
+        let created_user = create_test_user(db_connection);
+
+        let new_budget = InputBudget {
+            name: format!("Test Budget {}", created_user.first_name),
+            description: None,
+            categories: vec![
+                InputCategory {
+                    id: 0,
+                    name: String::from("Groceries"),
+                    limit_cents: 50000,
+                    color: String::from("#ff11ee"),
+                },
+                InputCategory {
+                    id: 1,
+                    name: String::from("Rent"),
+                    limit_cents: 150000,
+                    color: String::from("#112233"),
+                },
+            ],
+            start_date,
+            end_date,
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let created_budget =
+            budget::create_budget(db_connection, &web::Json(new_budget), created_user.id).unwrap();
+
+        (created_user.id, created_budget.id)
+    }
+}
@@ -0,0 +1,139 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+use log::warn;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{OutputNotification, OutputNotificationPage};
+use crate::models::user_notification::UserNotification;
+use crate::schema::user_notifications as user_notification_fields;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::utils::notification::NotificationData;
+
+// Dismissed notifications are excluded. Newest notifications come first.
+pub fn get_notifications_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputNotificationPage, diesel::result::Error> {
+    let mut loaded_notifications = user_notifications
+        .filter(user_notification_fields::user_id.eq(user_id))
+        .filter(user_notification_fields::is_deleted.eq(false))
+        .order((
+            user_notification_fields::created_timestamp.desc(),
+            user_notification_fields::id.asc(),
+        ))
+        .limit(limit + 1)
+        .offset(offset)
+        .load::<UserNotification>(db_connection)?;
+
+    let has_more = loaded_notifications.len() as i64 > limit;
+    loaded_notifications.truncate(limit as usize);
+
+    let notifications = loaded_notifications
+        .into_iter()
+        .map(|notification| {
+            let data = notification
+                .associated_data
+                .as_deref()
+                .and_then(|associated_data| {
+                    match NotificationData::parse(notification.notification_type, associated_data) {
+                        Ok(data) => Some(data),
+                        Err(e) => {
+                            warn!(
+                                "Failed to read data for notification with ID '{}': {}",
+                                notification.id, e
+                            );
+                            None
+                        }
+                    }
+                });
+
+            OutputNotification {
+                id: notification.id,
+                is_unread: notification.is_unread,
+                is_pristine: notification.is_pristine,
+                notification_type: notification.notification_type,
+                alt_title: notification.alt_title,
+                alt_message: notification.alt_message,
+                data,
+                modified_timestamp: notification.modified_timestamp,
+                created_timestamp: notification.created_timestamp,
+            }
+        })
+        .collect();
+
+    Ok(OutputNotificationPage {
+        notifications,
+        has_more,
+    })
+}
+
+pub fn count_unread_notifications(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<i64, diesel::result::Error> {
+    user_notifications
+        .filter(user_notification_fields::user_id.eq(user_id))
+        .filter(user_notification_fields::is_deleted.eq(false))
+        .filter(user_notification_fields::is_unread.eq(true))
+        .count()
+        .get_result::<i64>(db_connection)
+}
+
+pub fn mark_notification_read(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    notification_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        user_notifications
+            .filter(user_notification_fields::id.eq(notification_id))
+            .filter(user_notification_fields::user_id.eq(user_id))
+            .filter(user_notification_fields::is_deleted.eq(false)),
+    )
+    .set((
+        user_notification_fields::is_unread.eq(false),
+        user_notification_fields::is_pristine.eq(false),
+        user_notification_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+pub fn mark_all_notifications_read(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        user_notifications
+            .filter(user_notification_fields::user_id.eq(user_id))
+            .filter(user_notification_fields::is_deleted.eq(false))
+            .filter(user_notification_fields::is_unread.eq(true)),
+    )
+    .set((
+        user_notification_fields::is_unread.eq(false),
+        user_notification_fields::is_pristine.eq(false),
+        user_notification_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+// Soft-deletes the notification so clients can sync the dismissal
+pub fn dismiss_notification(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    notification_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        user_notifications
+            .filter(user_notification_fields::id.eq(notification_id))
+            .filter(user_notification_fields::user_id.eq(user_id))
+            .filter(user_notification_fields::is_deleted.eq(false)),
+    )
+    .set((
+        user_notification_fields::is_deleted.eq(true),
+        user_notification_fields::is_unread.eq(false),
+        user_notification_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
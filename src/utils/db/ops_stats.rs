@@ -0,0 +1,296 @@
+use chrono::{Duration, NaiveDate};
+use diesel::sql_types::{BigInt, Date, Integer, Timestamp};
+use diesel::{sql_query, Connection, RunQueryDsl};
+use std::collections::BTreeMap;
+
+use crate::definitions::*;
+
+const SIGNIFICANT_FIGURES: u32 = 2;
+
+#[derive(Debug, QueryableByName)]
+struct Count {
+    #[sql_type = "BigInt"]
+    count: i64,
+}
+
+#[derive(Debug, QueryableByName)]
+struct ImportSizeCount {
+    #[sql_type = "Integer"]
+    entry_count: i32,
+    #[sql_type = "BigInt"]
+    import_count: i64,
+}
+
+#[derive(Debug, QueryableByName)]
+struct BudgetEntryStats {
+    #[sql_type = "BigInt"]
+    budget_count: i64,
+    #[sql_type = "BigInt"]
+    median_entries: i64,
+    #[sql_type = "BigInt"]
+    p95_entries: i64,
+}
+
+// Recomputes the ops stats for one day. Everything is aggregated across all users before it is
+// written and nothing keyed by a user or budget leaves this function. Returns the number of rows
+// written.
+pub fn compute_ops_stats(
+    db_connection: &DbConnection,
+    day: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    let day_start = day.and_hms(0, 0, 0);
+    let day_end = day_start + Duration::days(1);
+    let current_time = chrono::Utc::now().naive_utc();
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        for table in [
+            "ops.daily_entry_counts",
+            "ops.import_size_buckets",
+            "ops.budget_entry_stats",
+        ] {
+            sql_query(format!("DELETE FROM {table} WHERE day = $1"))
+                .bind::<Date, _>(day)
+                .execute(db_connection)?;
+        }
+
+        let entry_count = sql_query(
+            "SELECT COUNT(*) AS count FROM entries \
+             WHERE created_timestamp >= $1 AND created_timestamp < $2",
+        )
+        .bind::<Timestamp, _>(day_start)
+        .bind::<Timestamp, _>(day_end)
+        .get_result::<Count>(db_connection)?
+        .count;
+
+        let mut rows_written = sql_query(
+            "INSERT INTO ops.daily_entry_counts (day, entry_count, computed_timestamp) \
+             VALUES ($1, $2, $3)",
+        )
+        .bind::<Date, _>(day)
+        .bind::<BigInt, _>(round_down(entry_count))
+        .bind::<Timestamp, _>(current_time)
+        .execute(db_connection)?;
+
+        let import_sizes = sql_query(
+            "SELECT entry_count, COUNT(*) AS import_count FROM import_batches \
+             WHERE created_timestamp >= $1 AND created_timestamp < $2 \
+             GROUP BY entry_count",
+        )
+        .bind::<Timestamp, _>(day_start)
+        .bind::<Timestamp, _>(day_end)
+        .load::<ImportSizeCount>(db_connection)?;
+
+        let mut import_size_buckets = BTreeMap::new();
+        for size in import_sizes {
+            *import_size_buckets
+                .entry(size_bucket(size.entry_count))
+                .or_insert(0) += size.import_count;
+        }
+
+        for (min_entries, import_count) in import_size_buckets {
+            rows_written += sql_query(
+                "INSERT INTO ops.import_size_buckets \
+                 (day, min_entries, import_count, computed_timestamp) \
+                 VALUES ($1, $2, $3, $4)",
+            )
+            .bind::<Date, _>(day)
+            .bind::<Integer, _>(min_entries)
+            .bind::<BigInt, _>(round_down(import_count))
+            .bind::<Timestamp, _>(current_time)
+            .execute(db_connection)?;
+        }
+
+        let budget_stats = sql_query(
+            "SELECT COUNT(*) AS budget_count, \
+             COALESCE(PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY entry_count), 0)::BIGINT \
+             AS median_entries, \
+             COALESCE(PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY entry_count), 0)::BIGINT \
+             AS p95_entries \
+             FROM (SELECT COUNT(entries.id) AS entry_count FROM budgets \
+             LEFT JOIN entries ON entries.budget_id = budgets.id \
+             AND entries.is_deleted = FALSE \
+             WHERE budgets.is_deleted = FALSE \
+             GROUP BY budgets.id) AS per_budget",
+        )
+        .get_result::<BudgetEntryStats>(db_connection)?;
+
+        rows_written += sql_query(
+            "INSERT INTO ops.budget_entry_stats \
+             (day, budget_count, median_entries, p95_entries, computed_timestamp) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind::<Date, _>(day)
+        .bind::<BigInt, _>(round_down(budget_stats.budget_count))
+        .bind::<BigInt, _>(round_down(budget_stats.median_entries))
+        .bind::<BigInt, _>(round_down(budget_stats.p95_entries))
+        .bind::<Timestamp, _>(current_time)
+        .execute(db_connection)?;
+
+        Ok(rows_written)
+    })
+}
+
+// Capacity planning only needs the rough size of a count. Exact totals, compared from one day to
+// the next, would say more about individual users than ops needs to know.
+fn round_down(count: i64) -> i64 {
+    let digits = count.checked_ilog10().map_or(1, |d| d + 1);
+
+    if digits <= SIGNIFICANT_FIGURES {
+        return count;
+    }
+
+    let unit = 10i64.pow(digits - SIGNIFICANT_FIGURES);
+    count / unit * unit
+}
+
+// Imports are bucketed by powers of two: 1, 2-3, 4-7, 8-15 and so on
+fn size_bucket(entry_count: i32) -> i32 {
+    if entry_count < 1 {
+        return 0;
+    }
+
+    1 << entry_count.ilog2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use rand::prelude::*;
+
+    use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputEntry, InputUser};
+    use crate::utils::db::{budget, user};
+
+    #[derive(Debug, QueryableByName)]
+    struct StoredEntryCount {
+        #[sql_type = "BigInt"]
+        entry_count: i64,
+    }
+
+    #[derive(Debug, QueryableByName)]
+    struct StoredBudgetEntryStats {
+        #[sql_type = "BigInt"]
+        budget_count: i64,
+        #[sql_type = "BigInt"]
+        p95_entries: i64,
+    }
+
+    #[test]
+    fn test_round_down() {
+        assert_eq!(round_down(0), 0);
+        assert_eq!(round_down(7), 7);
+        assert_eq!(round_down(99), 99);
+        assert_eq!(round_down(100), 100);
+        assert_eq!(round_down(129), 120);
+        assert_eq!(round_down(12_345), 12_000);
+        assert_eq!(round_down(999_999), 990_000);
+    }
+
+    #[test]
+    fn test_size_bucket() {
+        assert_eq!(size_bucket(0), 0);
+        assert_eq!(size_bucket(1), 1);
+        assert_eq!(size_bucket(2), 2);
+        assert_eq!(size_bucket(3), 2);
+        assert_eq!(size_bucket(4), 4);
+        assert_eq!(size_bucket(1000), 512);
+        assert_eq!(size_bucket(1024), 1024);
+    }
+
+    #[test]
+    fn test_compute_ops_stats() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("hT4$wq9!Lmz2#XeR7vKc"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(
+                rand::thread_rng().gen_range(1950..=2020),
+                rand::thread_rng().gen_range(1..=12),
+                rand::thread_rng().gen_range(1..=28),
+            ),
+            currency: String::from("USD"),
+        };
+
+        let created_user = user::create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        let date = NaiveDate::from_ymd(2021, 3, 14);
+        let new_budget = InputBudget {
+            name: format!("Test Budget {user_number}"),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: String::from("Groceries"),
+                limit_cents: 50000,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: date,
+            end_date: date,
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let created_budget =
+            budget::create_budget(&db_connection, &web::Json(new_budget), created_user.id).unwrap();
+
+        for _ in 0..3 {
+            let entry = InputEntry {
+                id: None,
+                budget_id: created_budget.id,
+                amount_cents: 1000,
+                date,
+                name: None,
+                category: Some(0),
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            budget::create_entry(&db_connection, &web::Json(entry), created_user.id).unwrap();
+        }
+
+        let today = chrono::Utc::now().naive_utc().date();
+
+        // Running again for the same day replaces the day's rows
+        compute_ops_stats(&db_connection, today).unwrap();
+        let rows_written = compute_ops_stats(&db_connection, today).unwrap();
+        assert!(rows_written >= 2);
+
+        // Other tests add entries concurrently, so only a lower bound can be checked
+        let entry_counts =
+            sql_query("SELECT entry_count FROM ops.daily_entry_counts WHERE day = $1")
+                .bind::<Date, _>(today)
+                .load::<StoredEntryCount>(&db_connection)
+                .unwrap();
+        assert_eq!(entry_counts.len(), 1);
+        assert!(entry_counts[0].entry_count >= 3);
+
+        let budget_stats = sql_query(
+            "SELECT budget_count, p95_entries FROM ops.budget_entry_stats WHERE day = $1",
+        )
+        .bind::<Date, _>(today)
+        .load::<StoredBudgetEntryStats>(&db_connection)
+        .unwrap();
+        assert_eq!(budget_stats.len(), 1);
+        assert!(budget_stats[0].budget_count >= 1);
+        assert!(budget_stats[0].p95_entries >= 0);
+
+        // A day with nothing in it still gets a row so gaps in the series are visible
+        let empty_day = NaiveDate::from_ymd(1970, 1, 1);
+        compute_ops_stats(&db_connection, empty_day).unwrap();
+
+        let entry_counts =
+            sql_query("SELECT entry_count FROM ops.daily_entry_counts WHERE day = $1")
+                .bind::<Date, _>(empty_day)
+                .load::<StoredEntryCount>(&db_connection)
+                .unwrap();
+        assert_eq!(entry_counts.len(), 1);
+        assert_eq!(entry_counts[0].entry_count, 0);
+    }
+}
@@ -0,0 +1,130 @@
+use diesel::sql_types::Text;
+use diesel::{dsl, sql_query, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::user_device_token::{NewUserDeviceToken, UserDeviceToken};
+use crate::models::user_notification::UserNotification;
+use crate::schema::pending_pushes as pending_push_fields;
+use crate::schema::pending_pushes::dsl::pending_pushes;
+use crate::schema::user_device_tokens as device_token_fields;
+use crate::schema::user_device_tokens::dsl::user_device_tokens;
+use crate::schema::user_notifications as user_notification_fields;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::utils::logging;
+use crate::utils::push::{DevicePlatform, PendingPush};
+use crate::utils::trace_context::TraceContext;
+
+// Registering a token another user registered moves it to the new user, since it means the device
+// has changed hands or accounts
+pub fn register_device_token(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    token: &str,
+    platform: DevicePlatform,
+) -> Result<usize, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    dsl::insert_into(user_device_tokens)
+        .values(&NewUserDeviceToken {
+            token,
+            user_id,
+            platform: platform as i16,
+            modified_timestamp: current_time,
+            created_timestamp: current_time,
+        })
+        .on_conflict(device_token_fields::token)
+        .do_update()
+        .set((
+            device_token_fields::user_id.eq(user_id),
+            device_token_fields::platform.eq(platform as i16),
+            device_token_fields::modified_timestamp.eq(current_time),
+        ))
+        .execute(db_connection)
+}
+
+pub fn remove_device_tokens(
+    db_connection: &DbConnection,
+    tokens: &[String],
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(user_device_tokens.filter(device_token_fields::token.eq_any(tokens)))
+        .execute(db_connection)
+}
+
+// Pushes queued for notifications created later in the current transaction are tagged with the
+// trace of the request being handled, if there is one. Must be called inside a transaction.
+pub fn trace_queued_pushes(db_connection: &DbConnection) -> Result<(), diesel::result::Error> {
+    let scope = match logging::current_request_scope() {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+
+    sql_query("SELECT set_config('budgetapp.traceparent', $1, TRUE)")
+        .bind::<Text, _>(scope.trace_context.traceparent())
+        .execute(db_connection)?;
+
+    Ok(())
+}
+
+// Takes up to `limit` of the oldest queued pushes off the queue, along with the devices each
+// should go to. Claimed pushes are removed right away so concurrent workers never send the same
+// push twice.
+pub fn claim_pending_pushes(
+    db_connection: &DbConnection,
+    limit: i64,
+) -> Result<Vec<PendingPush>, diesel::result::Error> {
+    let claimed = db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let claimed = pending_pushes
+            .select((
+                pending_push_fields::notification_id,
+                pending_push_fields::traceparent,
+            ))
+            .order(pending_push_fields::created_timestamp.asc())
+            .limit(limit)
+            .for_update()
+            .skip_locked()
+            .load::<(Uuid, Option<String>)>(db_connection)?;
+
+        let notification_ids = claimed.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+        diesel::delete(
+            pending_pushes.filter(pending_push_fields::notification_id.eq_any(&notification_ids)),
+        )
+        .execute(db_connection)?;
+
+        Ok(claimed)
+    })?;
+
+    let notification_ids = claimed.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+    let notifications = user_notifications
+        .filter(user_notification_fields::id.eq_any(&notification_ids))
+        .order(user_notification_fields::created_timestamp.asc())
+        .load::<UserNotification>(db_connection)?;
+
+    let user_ids = notifications
+        .iter()
+        .map(|notification| notification.user_id)
+        .collect::<Vec<_>>();
+
+    let devices = user_device_tokens
+        .filter(device_token_fields::user_id.eq_any(&user_ids))
+        .load::<UserDeviceToken>(db_connection)?;
+
+    Ok(notifications
+        .into_iter()
+        .map(|notification| PendingPush {
+            trace_context: claimed
+                .iter()
+                .find(|(id, _)| *id == notification.id)
+                .and_then(|(_, traceparent)| traceparent.as_deref())
+                .and_then(TraceContext::parse),
+            devices: devices
+                .iter()
+                .filter(|device| device.user_id == notification.user_id)
+                .cloned()
+                .collect(),
+            notification,
+        })
+        .collect())
+}
@@ -0,0 +1,421 @@
+use chrono::NaiveDate;
+use diesel::{
+    dsl, BoolExpressionMethods, Connection, ExpressionMethods, NullableExpressionMethods, QueryDsl,
+    RunQueryDsl,
+};
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{InputEditRecurringEntry, InputRecurringEntry};
+use crate::models::entry::NewEntry;
+use crate::models::recurring_entry::{NewRecurringEntry, RecurringEntry};
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::schema::entries::dsl::entries;
+use crate::schema::recurring_entries as recurring_entry_fields;
+use crate::schema::recurring_entries::dsl::recurring_entries;
+use crate::utils::db;
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::record_id;
+use crate::utils::recurrence::RecurrenceFrequency;
+
+pub fn create_recurring_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    recurring_entry_data: &InputRecurringEntry,
+) -> Result<RecurringEntry, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_recurring_entry = NewRecurringEntry {
+        id: record_id::generate(),
+        budget_id: recurring_entry_data.budget_id,
+        user_id,
+        amount_cents: recurring_entry_data.amount_cents,
+        name: recurring_entry_data.name.as_deref(),
+        category: recurring_entry_data.category,
+        note: recurring_entry_data.note.as_deref(),
+        frequency: recurring_entry_data.frequency,
+        start_date: recurring_entry_data.start_date,
+        end_date: recurring_entry_data.end_date,
+        next_occurrence_date: recurring_entry_data.start_date,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(recurring_entries)
+        .values(&new_recurring_entry)
+        .get_result::<RecurringEntry>(db_connection)
+}
+
+pub fn get_all_recurring_entries_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<RecurringEntry>, diesel::result::Error> {
+    recurring_entries
+        .filter(recurring_entry_fields::user_id.eq(user_id))
+        .order(recurring_entry_fields::next_occurrence_date.asc())
+        .load::<RecurringEntry>(db_connection)
+}
+
+pub fn get_recurring_entries_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<RecurringEntry>, diesel::result::Error> {
+    recurring_entries
+        .filter(recurring_entry_fields::budget_id.eq(budget_id))
+        .order(recurring_entry_fields::next_occurrence_date.asc())
+        .load::<RecurringEntry>(db_connection)
+}
+
+pub fn edit_recurring_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_data: &InputEditRecurringEntry,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        recurring_entries.filter(
+            recurring_entry_fields::id
+                .eq(edited_data.recurring_entry_id)
+                .and(recurring_entry_fields::user_id.eq(user_id)),
+        ),
+    )
+    .set((
+        recurring_entry_fields::amount_cents.eq(edited_data.amount_cents),
+        recurring_entry_fields::name.eq(edited_data.name.as_deref()),
+        recurring_entry_fields::category.eq(edited_data.category),
+        recurring_entry_fields::note.eq(edited_data.note.as_deref()),
+        recurring_entry_fields::end_date.eq(edited_data.end_date),
+        recurring_entry_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+// Entries that were already materialized are kept and lose their link to the recurring entry
+pub fn delete_recurring_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    recurring_entry_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        recurring_entries.filter(
+            recurring_entry_fields::id
+                .eq(recurring_entry_id)
+                .and(recurring_entry_fields::user_id.eq(user_id)),
+        ),
+    )
+    .execute(db_connection)
+}
+
+// Inserts an entry for every occurrence due on or before today, catching up on any occurrences
+// that were missed. Recurring entries whose creator has left the budget or can no longer add
+// entries to it are skipped. So are those whose occurrences would take a hard-capped category over
+// its limit; they stay due until there is room in the category. Returns the number of entries
+// created.
+pub fn materialize_due_recurring_entries(
+    db_connection: &DbConnection,
+    today: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    let due_recurring_entries = recurring_entries
+        .filter(recurring_entry_fields::next_occurrence_date.le(today))
+        .filter(
+            recurring_entry_fields::end_date
+                .is_null()
+                .or(recurring_entry_fields::end_date
+                    .ge(recurring_entry_fields::next_occurrence_date.nullable())),
+        )
+        .load::<RecurringEntry>(db_connection)?;
+
+    let mut created_count = 0;
+
+    for recurring_entry in due_recurring_entries.iter() {
+        match db::budget::get_user_budget_role(
+            db_connection,
+            recurring_entry.user_id,
+            recurring_entry.budget_id,
+        )? {
+            Some(role) if role.allows(BudgetRole::Editor) => (),
+            _ => continue,
+        }
+
+        match db_connection.transaction::<_, diesel::result::Error, _>(|| {
+            materialize_recurring_entry(db_connection, recurring_entry, today)
+        }) {
+            Ok(count) => created_count += count,
+            Err(e) if db::category_total::is_hard_cap_violation(&e) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(created_count)
+}
+
+fn materialize_recurring_entry(
+    db_connection: &DbConnection,
+    recurring_entry: &RecurringEntry,
+    today: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    let frequency = RecurrenceFrequency::try_from(recurring_entry.frequency)
+        .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
+    let last_date = match recurring_entry.end_date {
+        Some(end_date) if end_date < today => end_date,
+        _ => today,
+    };
+
+    let current_time = chrono::Utc::now().naive_utc();
+    let mut new_entries = Vec::new();
+    let mut occurrence_date = recurring_entry.next_occurrence_date;
+
+    while occurrence_date <= last_date {
+        new_entries.push(NewEntry {
+            id: record_id::generate(),
+            budget_id: recurring_entry.budget_id,
+            user_id: recurring_entry.user_id,
+            is_deleted: false,
+            amount_cents: recurring_entry.amount_cents,
+            date: occurrence_date,
+            name: recurring_entry.name.as_deref(),
+            category: recurring_entry.category,
+            note: recurring_entry.note.as_deref(),
+            modified_timestamp: current_time,
+            created_timestamp: current_time,
+            recurring_entry_id: Some(recurring_entry.id),
+            import_batch_id: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        });
+
+        occurrence_date = frequency.next_occurrence(recurring_entry.start_date, occurrence_date);
+    }
+
+    dsl::insert_into(entries)
+        .values(&new_entries)
+        .execute(db_connection)?;
+
+    let new_entry_ids = new_entries.iter().map(|e| e.id).collect::<Vec<_>>();
+    db::category_total::add_entries_within_hard_caps(db_connection, &new_entry_ids)?;
+
+    diesel::update(recurring_entries.find(recurring_entry.id))
+        .set(recurring_entry_fields::next_occurrence_date.eq(occurrence_date))
+        .execute(db_connection)?;
+
+    diesel::update(budgets.find(recurring_entry.budget_id))
+        .set(budget_fields::latest_entry_time.eq(current_time))
+        .execute(db_connection)?;
+
+    Ok(new_entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::env;
+    use crate::models::entry::Entry;
+    use crate::schema::entries as entry_fields;
+    use crate::utils::db::budget;
+    use crate::utils::db::testing::create_user_and_budget;
+
+    fn get_materialized_entries(
+        db_connection: &DbConnection,
+        recurring_entry_id: Uuid,
+    ) -> Vec<Entry> {
+        entries
+            .filter(entry_fields::recurring_entry_id.eq(recurring_entry_id))
+            .order(entry_fields::date.asc())
+            .load::<Entry>(db_connection)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_materialize_due_recurring_entries() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        let recurring_entry = create_recurring_entry(
+            &db_connection,
+            user_id,
+            &InputRecurringEntry {
+                budget_id,
+                amount_cents: 150000,
+                name: Some(String::from("Rent")),
+                category: Some(0),
+                note: None,
+                frequency: i16::from(RecurrenceFrequency::Monthly),
+                start_date: NaiveDate::from_ymd(2022, 1, 31),
+                end_date: Some(NaiveDate::from_ymd(2022, 4, 30)),
+            },
+        )
+        .unwrap();
+
+        materialize_due_recurring_entries(&db_connection, NaiveDate::from_ymd(2022, 3, 15))
+            .unwrap();
+
+        let materialized = get_materialized_entries(&db_connection, recurring_entry.id);
+        assert_eq!(
+            materialized.iter().map(|e| e.date).collect::<Vec<_>>(),
+            vec![
+                NaiveDate::from_ymd(2022, 1, 31),
+                NaiveDate::from_ymd(2022, 2, 28)
+            ]
+        );
+        assert!(materialized.iter().all(|e| e.amount_cents == 150000
+            && e.name.as_deref() == Some("Rent")
+            && e.budget_id == budget_id
+            && e.user_id == user_id));
+
+        // Running again on the same day doesn't duplicate entries
+        materialize_due_recurring_entries(&db_connection, NaiveDate::from_ymd(2022, 3, 15))
+            .unwrap();
+        assert_eq!(
+            get_materialized_entries(&db_connection, recurring_entry.id).len(),
+            2
+        );
+
+        // Occurrences stop at the end date
+        materialize_due_recurring_entries(&db_connection, NaiveDate::from_ymd(2022, 12, 1))
+            .unwrap();
+        let materialized = get_materialized_entries(&db_connection, recurring_entry.id);
+        assert_eq!(
+            materialized.iter().map(|e| e.date).collect::<Vec<_>>(),
+            vec![
+                NaiveDate::from_ymd(2022, 1, 31),
+                NaiveDate::from_ymd(2022, 2, 28),
+                NaiveDate::from_ymd(2022, 3, 31),
+                NaiveDate::from_ymd(2022, 4, 30),
+            ]
+        );
+
+        // Deleting the recurring entry keeps the entries it created
+        assert_eq!(
+            delete_recurring_entry(&db_connection, user_id, recurring_entry.id).unwrap(),
+            1
+        );
+        let remaining_entries = entries
+            .filter(entry_fields::budget_id.eq(budget_id))
+            .load::<Entry>(&db_connection)
+            .unwrap();
+        assert_eq!(remaining_entries.len(), 4);
+        assert!(remaining_entries
+            .iter()
+            .all(|e| e.recurring_entry_id.is_none()));
+    }
+
+    #[test]
+    fn test_materialize_within_hard_cap() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        budget::set_category_hard_cap(&db_connection, budget_id, 0, true).unwrap();
+
+        let recurring_entry = create_recurring_entry(
+            &db_connection,
+            user_id,
+            &InputRecurringEntry {
+                budget_id,
+                amount_cents: 150000,
+                name: Some(String::from("Rent")),
+                category: Some(0),
+                note: None,
+                frequency: i16::from(RecurrenceFrequency::Monthly),
+                start_date: NaiveDate::from_ymd(2022, 1, 31),
+                end_date: None,
+            },
+        )
+        .unwrap();
+
+        // Two months of rent would go over the cap, so neither is created
+        materialize_due_recurring_entries(&db_connection, NaiveDate::from_ymd(2022, 3, 15))
+            .unwrap();
+        assert!(get_materialized_entries(&db_connection, recurring_entry.id).is_empty());
+
+        budget::set_category_hard_cap(&db_connection, budget_id, 0, false).unwrap();
+
+        materialize_due_recurring_entries(&db_connection, NaiveDate::from_ymd(2022, 3, 15))
+            .unwrap();
+        assert_eq!(
+            get_materialized_entries(&db_connection, recurring_entry.id).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_edit_and_delete_recurring_entry() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+        let (other_user_id, _) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        let recurring_entry = create_recurring_entry(
+            &db_connection,
+            user_id,
+            &InputRecurringEntry {
+                budget_id,
+                amount_cents: 420000,
+                name: Some(String::from("Salary")),
+                category: None,
+                note: None,
+                frequency: i16::from(RecurrenceFrequency::Biweekly),
+                start_date: NaiveDate::from_ymd(2022, 1, 7),
+                end_date: None,
+            },
+        )
+        .unwrap();
+
+        let edit = InputEditRecurringEntry {
+            recurring_entry_id: recurring_entry.id,
+            amount_cents: -430000,
+            name: Some(String::from("Salary")),
+            category: None,
+            note: Some(String::from("Raise")),
+            end_date: Some(NaiveDate::from_ymd(2022, 12, 31)),
+        };
+
+        assert_eq!(
+            edit_recurring_entry(&db_connection, other_user_id, &edit).unwrap(),
+            0
+        );
+        assert_eq!(
+            edit_recurring_entry(&db_connection, user_id, &edit).unwrap(),
+            1
+        );
+
+        let recurring_entries_for_user =
+            get_all_recurring_entries_for_user(&db_connection, user_id).unwrap();
+        assert_eq!(recurring_entries_for_user.len(), 1);
+        assert_eq!(recurring_entries_for_user[0].amount_cents, -430000);
+        assert_eq!(recurring_entries_for_user[0].note.as_deref(), Some("Raise"));
+        assert_eq!(
+            recurring_entries_for_user[0].end_date,
+            Some(NaiveDate::from_ymd(2022, 12, 31))
+        );
+
+        assert_eq!(
+            delete_recurring_entry(&db_connection, other_user_id, recurring_entry.id).unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_recurring_entry(&db_connection, user_id, recurring_entry.id).unwrap(),
+            1
+        );
+        assert!(get_all_recurring_entries_for_user(&db_connection, user_id)
+            .unwrap()
+            .is_empty());
+    }
+}
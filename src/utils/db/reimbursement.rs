@@ -0,0 +1,180 @@
+use diesel::{dsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use std::convert::TryFrom;
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{OutputReimbursement, OutputReimbursementSummary};
+use crate::models::entry::Entry;
+use crate::models::reimbursement::{NewReimbursement, Reimbursement};
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::reimbursements as reimbursement_fields;
+use crate::schema::reimbursements::dsl::reimbursements;
+
+// The values are stored in the database, so existing variants must keep their numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReimbursementStatus {
+    Outstanding = 0,
+    Submitted = 1,
+    Paid = 2,
+}
+
+impl TryFrom<i16> for ReimbursementStatus {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ReimbursementStatus::Outstanding),
+            1 => Ok(ReimbursementStatus::Submitted),
+            2 => Ok(ReimbursementStatus::Paid),
+            _ => Err(()),
+        }
+    }
+}
+
+// Only money spent can be reimbursed. Returns None if the entry isn't a live expense in the budget.
+pub fn mark_reimbursable(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    entry_id: Uuid,
+) -> Result<Option<Reimbursement>, diesel::result::Error> {
+    let expense_count = entries
+        .filter(entry_fields::id.eq(entry_id))
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .filter(entry_fields::amount_cents.gt(0))
+        .count()
+        .get_result::<i64>(db_connection)?;
+
+    if expense_count == 0 {
+        return Ok(None);
+    }
+
+    let new_reimbursement = NewReimbursement {
+        entry_id,
+        budget_id,
+        status: ReimbursementStatus::Outstanding as i16,
+        payment_entry_id: None,
+        submitted_timestamp: None,
+        paid_timestamp: None,
+        created_timestamp: chrono::Utc::now().naive_utc(),
+    };
+
+    dsl::insert_into(reimbursements)
+        .values(&new_reimbursement)
+        .get_result::<Reimbursement>(db_connection)
+        .map(Some)
+}
+
+pub fn unmark_reimbursable(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    entry_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        reimbursements
+            .filter(reimbursement_fields::entry_id.eq(entry_id))
+            .filter(reimbursement_fields::budget_id.eq(budget_id)),
+    )
+    .execute(db_connection)
+}
+
+// Returns the number of reimbursements submitted, which is zero if the entry has no outstanding
+// reimbursement
+pub fn submit_reimbursement(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    entry_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        reimbursements
+            .filter(reimbursement_fields::entry_id.eq(entry_id))
+            .filter(reimbursement_fields::budget_id.eq(budget_id))
+            .filter(reimbursement_fields::status.eq(ReimbursementStatus::Outstanding as i16)),
+    )
+    .set((
+        reimbursement_fields::status.eq(ReimbursementStatus::Submitted as i16),
+        reimbursement_fields::submitted_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+// Payments come in as entries with negative amounts. A reimbursement can be paid without first being
+// submitted. Returns None if the payment entry isn't live incoming money in the budget. Otherwise
+// returns the number of reimbursements paid, which is zero if the entry has no unpaid reimbursement.
+pub fn record_payment(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    entry_id: Uuid,
+    payment_entry_id: Uuid,
+) -> Result<Option<usize>, diesel::result::Error> {
+    let payment_count = entries
+        .filter(entry_fields::id.eq(payment_entry_id))
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .filter(entry_fields::amount_cents.lt(0))
+        .count()
+        .get_result::<i64>(db_connection)?;
+
+    if payment_count == 0 {
+        return Ok(None);
+    }
+
+    diesel::update(
+        reimbursements
+            .filter(reimbursement_fields::entry_id.eq(entry_id))
+            .filter(reimbursement_fields::budget_id.eq(budget_id))
+            .filter(reimbursement_fields::status.ne(ReimbursementStatus::Paid as i16)),
+    )
+    .set((
+        reimbursement_fields::status.eq(ReimbursementStatus::Paid as i16),
+        reimbursement_fields::payment_entry_id.eq(payment_entry_id),
+        reimbursement_fields::paid_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+    .map(Some)
+}
+
+// Reimbursements of deleted entries are left out. Unpaid reimbursements are listed oldest expense
+// first.
+pub fn get_reimbursement_summary(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<OutputReimbursementSummary, diesel::result::Error> {
+    let loaded = reimbursements
+        .inner_join(entries)
+        .filter(reimbursement_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::is_deleted.eq(false))
+        .order((entry_fields::date.asc(), entry_fields::id.asc()))
+        .load::<(Reimbursement, Entry)>(db_connection)?;
+
+    let mut summary = OutputReimbursementSummary {
+        budget_id,
+        outstanding_cents: 0,
+        submitted_cents: 0,
+        paid_cents: 0,
+        unpaid: Vec::new(),
+    };
+
+    for (reimbursement, entry) in loaded.into_iter() {
+        let status = ReimbursementStatus::try_from(reimbursement.status)
+            .map_err(|_| diesel::result::Error::DeserializationError("Invalid status".into()))?;
+
+        match status {
+            ReimbursementStatus::Outstanding => summary.outstanding_cents += entry.amount_cents,
+            ReimbursementStatus::Submitted => summary.submitted_cents += entry.amount_cents,
+            ReimbursementStatus::Paid => {
+                summary.paid_cents += entry.amount_cents;
+                continue;
+            }
+        }
+
+        summary.unpaid.push(OutputReimbursement {
+            entry,
+            status: reimbursement.status,
+            submitted_timestamp: reimbursement.submitted_timestamp,
+        });
+    }
+
+    Ok(summary)
+}
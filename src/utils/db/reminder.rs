@@ -0,0 +1,201 @@
+use chrono::NaiveDate;
+use diesel::{dsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{InputEditReminder, InputReminder, OutputReminderPage};
+use crate::models::reminder::{NewReminder, Reminder};
+use crate::models::user_notification::NewUserNotification;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::reminders as reminder_fields;
+use crate::schema::reminders::dsl::reminders;
+use crate::schema::user_notifications::dsl::user_notifications;
+use crate::utils::notification::{NotificationType, ReminderData};
+use crate::utils::record_id;
+
+// Reminders that are still to come are listed first, soonest first
+pub fn get_reminders_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    limit: i64,
+    offset: i64,
+) -> Result<OutputReminderPage, diesel::result::Error> {
+    let mut loaded_reminders = reminders
+        .filter(reminder_fields::user_id.eq(user_id))
+        .order((
+            reminder_fields::is_delivered.asc(),
+            reminder_fields::remind_date.asc(),
+            reminder_fields::id.asc(),
+        ))
+        .limit(limit + 1)
+        .offset(offset)
+        .load::<Reminder>(db_connection)?;
+
+    let has_more = loaded_reminders.len() as i64 > limit;
+    loaded_reminders.truncate(limit as usize);
+
+    Ok(OutputReminderPage {
+        reminders: loaded_reminders,
+        has_more,
+    })
+}
+
+// The caller checks that the user belongs to the linked budget. Returns None if the linked entry
+// isn't a live entry in that budget.
+pub fn create_reminder(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    reminder_data: &InputReminder,
+) -> Result<Option<Reminder>, diesel::result::Error> {
+    if let (Some(entry_id), Some(budget_id)) = (reminder_data.entry_id, reminder_data.budget_id) {
+        let entry_count = entries
+            .filter(entry_fields::id.eq(entry_id))
+            .filter(entry_fields::budget_id.eq(budget_id))
+            .filter(entry_fields::is_deleted.eq(false))
+            .count()
+            .get_result::<i64>(db_connection)?;
+
+        if entry_count == 0 {
+            return Ok(None);
+        }
+    }
+
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_reminder = NewReminder {
+        id: record_id::generate(),
+        user_id,
+        budget_id: reminder_data.budget_id,
+        entry_id: reminder_data.entry_id,
+        note: reminder_data.note.trim(),
+        remind_date: reminder_data.remind_date,
+        is_delivered: false,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(reminders)
+        .values(&new_reminder)
+        .get_result::<Reminder>(db_connection)
+        .map(Some)
+}
+
+// A reminder that has already been delivered is delivered again on its new date
+pub fn edit_reminder(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_reminder_data: &InputEditReminder,
+) -> Result<usize, diesel::result::Error> {
+    dsl::update(
+        reminders
+            .filter(reminder_fields::id.eq(edited_reminder_data.reminder_id))
+            .filter(reminder_fields::user_id.eq(user_id)),
+    )
+    .set((
+        reminder_fields::note.eq(edited_reminder_data.note.trim()),
+        reminder_fields::remind_date.eq(edited_reminder_data.remind_date),
+        reminder_fields::is_delivered.eq(false),
+        reminder_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+pub fn snooze_reminder(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    reminder_id: Uuid,
+    remind_date: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    dsl::update(
+        reminders
+            .filter(reminder_fields::id.eq(reminder_id))
+            .filter(reminder_fields::user_id.eq(user_id)),
+    )
+    .set((
+        reminder_fields::remind_date.eq(remind_date),
+        reminder_fields::is_delivered.eq(false),
+        reminder_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(db_connection)
+}
+
+pub fn delete_reminder(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    reminder_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        reminders
+            .filter(reminder_fields::id.eq(reminder_id))
+            .filter(reminder_fields::user_id.eq(user_id)),
+    )
+    .execute(db_connection)
+}
+
+// Sends a notification for each reminder that has come due and hasn't been delivered. Returns the
+// number of reminders delivered.
+pub fn deliver_due_reminders(
+    db_connection: &DbConnection,
+    today: NaiveDate,
+) -> Result<usize, diesel::result::Error> {
+    let due_reminders = reminders
+        .filter(reminder_fields::is_delivered.eq(false))
+        .filter(reminder_fields::remind_date.le(today))
+        .load::<Reminder>(db_connection)?;
+
+    for reminder in due_reminders.iter() {
+        db_connection.transaction::<_, diesel::result::Error, _>(|| {
+            deliver_reminder(db_connection, reminder)
+        })?;
+    }
+
+    Ok(due_reminders.len())
+}
+
+fn deliver_reminder(
+    db_connection: &DbConnection,
+    reminder: &Reminder,
+) -> Result<(), diesel::result::Error> {
+    // The reminder may have been snoozed or deleted since it was loaded
+    let updated_count = diesel::update(
+        reminders
+            .find(reminder.id)
+            .filter(reminder_fields::is_delivered.eq(false))
+            .filter(reminder_fields::remind_date.eq(reminder.remind_date)),
+    )
+    .set(reminder_fields::is_delivered.eq(true))
+    .execute(db_connection)?;
+
+    if updated_count == 0 {
+        return Ok(());
+    }
+
+    let associated_data = serde_json::to_string(&ReminderData {
+        reminder_id: reminder.id,
+        budget_id: reminder.budget_id,
+        entry_id: reminder.entry_id,
+    })
+    .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let notification = NewUserNotification {
+        id: record_id::generate(),
+        user_id: reminder.user_id,
+        is_unread: true,
+        is_pristine: true,
+        is_deleted: false,
+        notification_type: i16::from(NotificationType::Reminder),
+        alt_title: "Reminder",
+        alt_message: &reminder.note,
+        associated_data: Some(&associated_data),
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(user_notifications)
+        .values(&notification)
+        .execute(db_connection)?;
+
+    Ok(())
+}
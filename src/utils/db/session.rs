@@ -0,0 +1,252 @@
+use chrono::NaiveDateTime;
+use diesel::{dsl, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::session::{NewSession, Session};
+use crate::schema::sessions as session_fields;
+use crate::schema::sessions::dsl::sessions;
+use crate::utils::record_id;
+
+// A session is started when a user signs in (or asks for a password reset) and the tokens issued
+// for it carry its ID and version. Revoking the session revokes every token issued for it.
+
+// Where a sign-in session was started from. Every field is optional because clients and proxies
+// don't always provide them.
+#[derive(Clone, Debug, Default)]
+pub struct SessionDevice {
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+fn expiration_from_now(lifetime_secs: u64) -> NaiveDateTime {
+    chrono::Utc::now().naive_utc() + chrono::Duration::seconds(lifetime_secs as i64)
+}
+
+fn insert_session(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    lifetime_secs: u64,
+    is_password_reset: bool,
+    device: &SessionDevice,
+) -> Result<Session, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_session = NewSession {
+        id: record_id::generate(),
+        user_id,
+        version: 0,
+        expiration_timestamp: expiration_from_now(lifetime_secs),
+        revoked_timestamp: None,
+        created_timestamp: current_time,
+        is_password_reset,
+        device_name: device.device_name.as_deref(),
+        user_agent: device.user_agent.as_deref(),
+        ip_address: device.ip_address.as_deref(),
+        last_used_timestamp: current_time,
+    };
+
+    dsl::insert_into(sessions)
+        .values(&new_session)
+        .get_result::<Session>(db_connection)
+}
+
+pub fn create_session(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    lifetime_secs: u64,
+    device: &SessionDevice,
+) -> Result<Session, diesel::result::Error> {
+    insert_session(db_connection, user_id, lifetime_secs, false, device)
+}
+
+pub fn create_password_reset_session(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    lifetime_secs: u64,
+) -> Result<Session, diesel::result::Error> {
+    insert_session(
+        db_connection,
+        user_id,
+        lifetime_secs,
+        true,
+        &SessionDevice::default(),
+    )
+}
+
+// The user's sign-in sessions that can still be refreshed, most recently used first
+pub fn get_active_sessions_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<Vec<Session>, diesel::result::Error> {
+    sessions
+        .filter(session_fields::user_id.eq(user_id))
+        .filter(session_fields::is_password_reset.eq(false))
+        .filter(session_fields::revoked_timestamp.is_null())
+        .filter(session_fields::expiration_timestamp.gt(chrono::Utc::now().naive_utc()))
+        .order(session_fields::last_used_timestamp.desc())
+        .load::<Session>(db_connection)
+}
+
+// Moves the session on to its next version so the refresh token being exchanged can't be used
+// again. Returns None if the session was revoked or already moved past `version`, which happens
+// when the same refresh token is used twice.
+pub fn rotate_session(
+    db_connection: &DbConnection,
+    session_id: Uuid,
+    version: i32,
+    lifetime_secs: u64,
+) -> Result<Option<Session>, diesel::result::Error> {
+    diesel::update(
+        sessions
+            .filter(session_fields::id.eq(session_id))
+            .filter(session_fields::version.eq(version))
+            .filter(session_fields::revoked_timestamp.is_null()),
+    )
+    .set((
+        session_fields::version.eq(session_fields::version + 1),
+        session_fields::expiration_timestamp.eq(expiration_from_now(lifetime_secs)),
+        session_fields::last_used_timestamp.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .get_result::<Session>(db_connection)
+    .optional()
+}
+
+// Returns false if the session had already been revoked
+pub fn revoke_session(
+    db_connection: &DbConnection,
+    session_id: Uuid,
+) -> Result<bool, diesel::result::Error> {
+    let revoked_count = diesel::update(
+        sessions
+            .filter(session_fields::id.eq(session_id))
+            .filter(session_fields::revoked_timestamp.is_null()),
+    )
+    .set(session_fields::revoked_timestamp.eq(chrono::Utc::now().naive_utc()))
+    .execute(db_connection)?;
+
+    Ok(revoked_count > 0)
+}
+
+// Only revokes the session if it belongs to the user. Returns false if there was no such session
+// or it had already been revoked.
+pub fn revoke_session_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    session_id: Uuid,
+) -> Result<bool, diesel::result::Error> {
+    let revoked_count = diesel::update(
+        sessions
+            .filter(session_fields::id.eq(session_id))
+            .filter(session_fields::user_id.eq(user_id))
+            .filter(session_fields::revoked_timestamp.is_null()),
+    )
+    .set(session_fields::revoked_timestamp.eq(chrono::Utc::now().naive_utc()))
+    .execute(db_connection)?;
+
+    Ok(revoked_count > 0)
+}
+
+pub fn purge_expired_sessions(
+    db_connection: &DbConnection,
+) -> Result<usize, diesel::result::Error> {
+    // Tokens for an expired session are rejected anyway once they expire. The two minutes leave
+    // room for slight clock differences between servers.
+    let cutoff = chrono::Utc::now().naive_utc() - chrono::Duration::minutes(2);
+
+    diesel::delete(sessions.filter(session_fields::expiration_timestamp.lt(cutoff)))
+        .execute(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use actix_web::web;
+    use chrono::NaiveDate;
+    use rand::prelude::*;
+
+    use crate::env;
+    use crate::handlers::request_io::InputUser;
+    use crate::utils::db::user;
+
+    fn create_user(db_connection: &DbConnection) -> Uuid {
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("OAgZbc6d&ARg*Wq#NPe3"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(1990, 4, 12),
+            currency: String::from("USD"),
+        };
+
+        user::create_user(db_connection, &web::Json(new_user))
+            .unwrap()
+            .id
+    }
+
+    #[test]
+    fn test_rotate_and_revoke_session() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user_id = create_user(&db_connection);
+
+        let session =
+            create_session(&db_connection, user_id, 3600, &SessionDevice::default()).unwrap();
+        assert_eq!(session.user_id, user_id);
+        assert_eq!(session.version, 0);
+        assert!(session.revoked_timestamp.is_none());
+
+        let rotated = rotate_session(&db_connection, session.id, 0, 3600)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rotated.version, 1);
+
+        // The old version has been used up
+        assert!(rotate_session(&db_connection, session.id, 0, 3600)
+            .unwrap()
+            .is_none());
+
+        assert!(revoke_session(&db_connection, session.id).unwrap());
+        assert!(!revoke_session(&db_connection, session.id).unwrap());
+        assert!(rotate_session(&db_connection, session.id, 1, 3600)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_purge_expired_sessions() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let user_id = create_user(&db_connection);
+
+        let expired_session =
+            create_session(&db_connection, user_id, 0, &SessionDevice::default()).unwrap();
+        let live_session =
+            create_session(&db_connection, user_id, 3600, &SessionDevice::default()).unwrap();
+
+        diesel::update(sessions.find(expired_session.id))
+            .set(
+                session_fields::expiration_timestamp
+                    .eq(chrono::Utc::now().naive_utc() - chrono::Duration::hours(1)),
+            )
+            .execute(&db_connection)
+            .unwrap();
+
+        assert!(purge_expired_sessions(&db_connection).unwrap() >= 1);
+
+        assert!(sessions
+            .find(expired_session.id)
+            .first::<Session>(&db_connection)
+            .optional()
+            .unwrap()
+            .is_none());
+        assert!(sessions
+            .find(live_session.id)
+            .first::<Session>(&db_connection)
+            .optional()
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,461 @@
+use actix_web::web;
+use diesel::associations::GroupedBy;
+use diesel::{dsl, BelongingToDsl, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::{
+    InputCompleteShoppingList, InputEditShoppingListItem, InputEntry, InputShoppingList,
+    InputShoppingListItem, OutputShoppingList,
+};
+use crate::models::entry::Entry;
+use crate::models::shopping_list::{NewShoppingList, ShoppingList};
+use crate::models::shopping_list_item::{NewShoppingListItem, ShoppingListItem};
+use crate::schema::categories as category_fields;
+use crate::schema::categories::dsl::categories;
+use crate::schema::shopping_list_items as shopping_list_item_fields;
+use crate::schema::shopping_list_items::dsl::shopping_list_items;
+use crate::schema::shopping_lists as shopping_list_fields;
+use crate::schema::shopping_lists::dsl::shopping_lists;
+use crate::schema::user_budgets as user_budget_fields;
+use crate::schema::user_budgets::dsl::user_budgets;
+use crate::utils::db;
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::record_id;
+
+// Any change to a list's items also bumps the list's `modified_timestamp` so clients that share
+// the list can tell when to refetch it.
+
+// Returns diesel::result::Error::NotFound if the budget has no category with the given ID
+pub fn create_shopping_list(
+    db_connection: &DbConnection,
+    shopping_list_data: &InputShoppingList,
+) -> Result<ShoppingList, diesel::result::Error> {
+    let category_count = categories
+        .filter(category_fields::budget_id.eq(shopping_list_data.budget_id))
+        .filter(category_fields::id.eq(shopping_list_data.category))
+        .filter(category_fields::is_deleted.eq(false))
+        .count()
+        .get_result::<i64>(db_connection)?;
+
+    if category_count == 0 {
+        return Err(diesel::result::Error::NotFound);
+    }
+
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_shopping_list = NewShoppingList {
+        id: record_id::generate(),
+        budget_id: shopping_list_data.budget_id,
+        category: shopping_list_data.category,
+        name: &shopping_list_data.name,
+        is_completed: false,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(shopping_lists)
+        .values(&new_shopping_list)
+        .get_result::<ShoppingList>(db_connection)
+}
+
+pub fn get_shopping_lists_for_budget(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+) -> Result<Vec<OutputShoppingList>, diesel::result::Error> {
+    let loaded_lists = shopping_lists
+        .filter(shopping_list_fields::budget_id.eq(budget_id))
+        .order(shopping_list_fields::created_timestamp.asc())
+        .load::<ShoppingList>(db_connection)?;
+
+    let loaded_items = ShoppingListItem::belonging_to(&loaded_lists)
+        .order(shopping_list_item_fields::created_timestamp.asc())
+        .load::<ShoppingListItem>(db_connection)?
+        .grouped_by(&loaded_lists);
+
+    let output_lists = loaded_lists
+        .into_iter()
+        .zip(loaded_items)
+        .map(|(list, items)| OutputShoppingList {
+            id: list.id,
+            budget_id: list.budget_id,
+            category: list.category,
+            name: list.name,
+            is_completed: list.is_completed,
+            items,
+            modified_timestamp: list.modified_timestamp,
+            created_timestamp: list.created_timestamp,
+        })
+        .collect();
+
+    Ok(output_lists)
+}
+
+pub fn delete_shopping_list(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    shopping_list_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    diesel::delete(
+        shopping_lists
+            .filter(shopping_list_fields::id.eq(shopping_list_id))
+            .filter(shopping_list_fields::budget_id.eq_any(user_budget_ids)),
+    )
+    .execute(db_connection)
+}
+
+// Returns diesel::result::Error::NotFound if the list doesn't exist, has been completed, or is in
+// a budget the user doesn't belong to
+pub fn add_item(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    item_data: &InputShoppingListItem,
+) -> Result<ShoppingListItem, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_item = NewShoppingListItem {
+        id: record_id::generate(),
+        shopping_list_id: item_data.shopping_list_id,
+        name: &item_data.name,
+        estimated_cost_cents: item_data.estimated_cost_cents,
+        is_checked: false,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        if touch_open_list(db_connection, user_id, item_data.shopping_list_id)? == 0 {
+            return Err(diesel::result::Error::NotFound);
+        }
+
+        dsl::insert_into(shopping_list_items)
+            .values(&new_item)
+            .get_result::<ShoppingListItem>(db_connection)
+    })
+}
+
+// Returns the number of items edited, which is zero if the item doesn't exist, its list has been
+// completed, or the user can't access it
+pub fn edit_item(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    edited_item_data: &InputEditShoppingListItem,
+) -> Result<usize, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let shopping_list_id = match get_item_list_id(db_connection, edited_item_data.item_id)? {
+            Some(id) => id,
+            None => return Ok(0),
+        };
+
+        if touch_open_list(db_connection, user_id, shopping_list_id)? == 0 {
+            return Ok(0);
+        }
+
+        diesel::update(shopping_list_items.find(edited_item_data.item_id))
+            .set((
+                shopping_list_item_fields::name.eq(&edited_item_data.name),
+                shopping_list_item_fields::estimated_cost_cents
+                    .eq(edited_item_data.estimated_cost_cents),
+                shopping_list_item_fields::is_checked.eq(edited_item_data.is_checked),
+                shopping_list_item_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(db_connection)
+    })
+}
+
+// Returns the number of items deleted, which is zero if the item doesn't exist, its list has
+// been completed, or the user can't access it
+pub fn delete_item(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    item_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let shopping_list_id = match get_item_list_id(db_connection, item_id)? {
+            Some(id) => id,
+            None => return Ok(0),
+        };
+
+        if touch_open_list(db_connection, user_id, shopping_list_id)? == 0 {
+            return Ok(0);
+        }
+
+        diesel::delete(shopping_list_items.find(item_id)).execute(db_connection)
+    })
+}
+
+// Marks the list completed and records the shopping trip as an entry in the list's category.
+// Returns diesel::result::Error::NotFound if the list doesn't exist, was already completed, or is
+// in a budget the user doesn't belong to.
+pub fn complete_shopping_list(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    completion_data: &InputCompleteShoppingList,
+) -> Result<Entry, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let shopping_list = diesel::update(
+            shopping_lists
+                .filter(shopping_list_fields::id.eq(completion_data.shopping_list_id))
+                .filter(shopping_list_fields::is_completed.eq(false))
+                .filter(shopping_list_fields::budget_id.eq_any(user_budget_ids)),
+        )
+        .set((
+            shopping_list_fields::is_completed.eq(true),
+            shopping_list_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()),
+        ))
+        .get_result::<ShoppingList>(db_connection)?;
+
+        let amount_cents = match completion_data.amount_cents {
+            Some(amount_cents) => amount_cents,
+            None => shopping_list_items
+                .select(shopping_list_item_fields::estimated_cost_cents)
+                .filter(shopping_list_item_fields::shopping_list_id.eq(shopping_list.id))
+                .filter(shopping_list_item_fields::is_checked.eq(true))
+                .load::<i64>(db_connection)?
+                .iter()
+                .sum(),
+        };
+
+        let entry_data = web::Json(InputEntry {
+            id: None,
+            budget_id: shopping_list.budget_id,
+            amount_cents,
+            date: completion_data.date,
+            name: Some(shopping_list.name),
+            category: Some(shopping_list.category),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        });
+
+        db::budget::create_entry(db_connection, &entry_data, user_id)
+    })
+}
+
+fn get_item_list_id(
+    db_connection: &DbConnection,
+    item_id: Uuid,
+) -> Result<Option<Uuid>, diesel::result::Error> {
+    match shopping_list_items
+        .select(shopping_list_item_fields::shopping_list_id)
+        .find(item_id)
+        .first::<Uuid>(db_connection)
+    {
+        Ok(id) => Ok(Some(id)),
+        Err(diesel::result::Error::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn touch_open_list(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    shopping_list_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    diesel::update(
+        shopping_lists
+            .filter(shopping_list_fields::id.eq(shopping_list_id))
+            .filter(shopping_list_fields::is_completed.eq(false))
+            .filter(shopping_list_fields::budget_id.eq_any(user_budget_ids)),
+    )
+    .set(shopping_list_fields::modified_timestamp.eq(chrono::Utc::now().naive_utc()))
+    .execute(db_connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    use crate::env;
+    use crate::utils::db::testing::create_user_and_budget;
+
+    fn add_test_item(
+        db_connection: &DbConnection,
+        user_id: Uuid,
+        shopping_list_id: Uuid,
+        name: &str,
+        estimated_cost_cents: i64,
+    ) -> ShoppingListItem {
+        add_item(
+            db_connection,
+            user_id,
+            &InputShoppingListItem {
+                shopping_list_id,
+                name: String::from(name),
+                estimated_cost_cents,
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_create_shopping_list_requires_category() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (_, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        let result = create_shopping_list(
+            &db_connection,
+            &InputShoppingList {
+                budget_id,
+                category: 7,
+                name: String::from("Costco run"),
+            },
+        );
+
+        assert_eq!(result.unwrap_err(), diesel::result::Error::NotFound);
+    }
+
+    #[test]
+    fn test_complete_shopping_list() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        let shopping_list = create_shopping_list(
+            &db_connection,
+            &InputShoppingList {
+                budget_id,
+                category: 0,
+                name: String::from("Weekly groceries"),
+            },
+        )
+        .unwrap();
+
+        let milk = add_test_item(&db_connection, user_id, shopping_list.id, "Milk", 450);
+        let eggs = add_test_item(&db_connection, user_id, shopping_list.id, "Eggs", 600);
+        add_test_item(&db_connection, user_id, shopping_list.id, "Saffron", 2000);
+
+        for item in [&milk, &eggs] {
+            let edited_count = edit_item(
+                &db_connection,
+                user_id,
+                &InputEditShoppingListItem {
+                    item_id: item.id,
+                    name: item.name.clone(),
+                    estimated_cost_cents: item.estimated_cost_cents,
+                    is_checked: true,
+                },
+            )
+            .unwrap();
+            assert_eq!(edited_count, 1);
+        }
+
+        let lists = get_shopping_lists_for_budget(&db_connection, budget_id).unwrap();
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].items.len(), 3);
+        assert!(lists[0].modified_timestamp > shopping_list.modified_timestamp);
+
+        let entry = complete_shopping_list(
+            &db_connection,
+            user_id,
+            &InputCompleteShoppingList {
+                shopping_list_id: shopping_list.id,
+                date: NaiveDate::from_ymd(2022, 3, 14),
+                amount_cents: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(entry.amount_cents, 1050);
+        assert_eq!(entry.budget_id, budget_id);
+        assert_eq!(entry.category, Some(0));
+        assert_eq!(entry.name.as_deref(), Some("Weekly groceries"));
+
+        let lists = get_shopping_lists_for_budget(&db_connection, budget_id).unwrap();
+        assert!(lists[0].is_completed);
+
+        // Completed lists can't be changed or completed again
+        assert_eq!(delete_item(&db_connection, user_id, milk.id).unwrap(), 0);
+
+        let result = complete_shopping_list(
+            &db_connection,
+            user_id,
+            &InputCompleteShoppingList {
+                shopping_list_id: shopping_list.id,
+                date: NaiveDate::from_ymd(2022, 3, 14),
+                amount_cents: Some(1000),
+            },
+        );
+        assert_eq!(result.unwrap_err(), diesel::result::Error::NotFound);
+    }
+
+    #[test]
+    fn test_shopping_list_restricted_to_budget_members() {
+        let db_connection = env::testing::DB_THREAD_POOL.get().unwrap();
+        let (user_id, budget_id) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+        let (other_user_id, _) = create_user_and_budget(
+            &db_connection,
+            NaiveDate::from_ymd(2022, 1, 1),
+            NaiveDate::from_ymd(2022, 12, 31),
+        );
+
+        let shopping_list = create_shopping_list(
+            &db_connection,
+            &InputShoppingList {
+                budget_id,
+                category: 0,
+                name: String::from("Party supplies"),
+            },
+        )
+        .unwrap();
+
+        let item = add_test_item(&db_connection, user_id, shopping_list.id, "Balloons", 800);
+
+        let result = add_item(
+            &db_connection,
+            other_user_id,
+            &InputShoppingListItem {
+                shopping_list_id: shopping_list.id,
+                name: String::from("Streamers"),
+                estimated_cost_cents: 300,
+            },
+        );
+        assert_eq!(result.unwrap_err(), diesel::result::Error::NotFound);
+
+        assert_eq!(
+            delete_item(&db_connection, other_user_id, item.id).unwrap(),
+            0
+        );
+        assert_eq!(
+            delete_shopping_list(&db_connection, other_user_id, shopping_list.id).unwrap(),
+            0
+        );
+
+        assert_eq!(
+            delete_shopping_list(&db_connection, user_id, shopping_list.id).unwrap(),
+            1
+        );
+        assert!(get_shopping_lists_for_budget(&db_connection, budget_id)
+            .unwrap()
+            .is_empty());
+    }
+}
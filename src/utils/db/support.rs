@@ -0,0 +1,30 @@
+use diesel::{dsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::support_ticket::{NewSupportTicket, SupportTicket};
+use crate::schema::support_tickets::dsl::support_tickets;
+use crate::utils::record_id;
+
+pub fn create_ticket(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    message: &str,
+    context: &str,
+) -> Result<SupportTicket, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_ticket = NewSupportTicket {
+        id: record_id::generate(),
+        user_id,
+        message,
+        context,
+        is_resolved: false,
+        modified_timestamp: current_time,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(support_tickets)
+        .values(&new_ticket)
+        .get_result::<SupportTicket>(db_connection)
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use diesel::sql_types::{BigInt, Date, Nullable, SmallInt, Uuid as SqlUuid, Varchar};
+use diesel::{sql_query, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::handlers::request_io::OutputTaxReportCategory;
+
+#[derive(Debug, QueryableByName)]
+struct DeductibleTotal {
+    #[sql_type = "SqlUuid"]
+    budget_id: Uuid,
+    #[sql_type = "Varchar"]
+    budget_name: String,
+    #[sql_type = "Nullable<SmallInt>"]
+    category_id: Option<i16>,
+    #[sql_type = "Nullable<Varchar>"]
+    category_name: Option<String>,
+    #[sql_type = "BigInt"]
+    deductible_cents: i64,
+    #[sql_type = "BigInt"]
+    tax_cents: i64,
+    #[sql_type = "BigInt"]
+    entry_count: i64,
+}
+
+// Totals the deductible entries the user recorded between the two dates, inclusive, in every budget
+// they still belong to, archived ones included. Entries with no category or whose category has been
+// removed are totaled together in a row with no category ID for their budget.
+pub fn get_deductible_totals_for_user(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+) -> Result<Vec<OutputTaxReportCategory>, diesel::result::Error> {
+    let totals = sql_query(
+        "SELECT budgets.id AS budget_id, budgets.name AS budget_name, \
+         categories.id AS category_id, categories.name AS category_name, \
+         SUM(entries.amount_cents)::BIGINT AS deductible_cents, \
+         COALESCE(SUM(entries.tax_cents), 0)::BIGINT AS tax_cents, \
+         COUNT(*) AS entry_count \
+         FROM entries \
+         JOIN user_budgets ON user_budgets.budget_id = entries.budget_id \
+         AND user_budgets.user_id = $1 \
+         JOIN budgets ON budgets.id = entries.budget_id \
+         LEFT JOIN categories ON categories.budget_id = entries.budget_id \
+         AND categories.id = entries.category \
+         AND categories.is_deleted = FALSE \
+         WHERE entries.user_id = $1 \
+         AND entries.is_deductible = TRUE \
+         AND entries.is_deleted = FALSE \
+         AND budgets.is_deleted = FALSE \
+         AND entries.date BETWEEN $2 AND $3 \
+         GROUP BY budgets.id, budgets.name, categories.id, categories.name \
+         ORDER BY budgets.name ASC, budgets.id ASC, categories.id ASC NULLS LAST",
+    )
+    .bind::<SqlUuid, _>(user_id)
+    .bind::<Date, _>(start_date)
+    .bind::<Date, _>(end_date)
+    .load::<DeductibleTotal>(db_connection)?;
+
+    Ok(totals
+        .into_iter()
+        .map(|t| OutputTaxReportCategory {
+            budget_id: t.budget_id,
+            budget_name: t.budget_name,
+            category_id: t.category_id,
+            category_name: t.category_name,
+            deductible_cents: t.deductible_cents,
+            tax_cents: t.tax_cents,
+            entry_count: t.entry_count,
+        })
+        .collect())
+}
@@ -0,0 +1,176 @@
+use chrono::NaiveDateTime;
+use diesel::{BoolExpressionMethods, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::definitions::*;
+use crate::models::budget_comment::BudgetComment;
+use crate::models::entry::Entry;
+use crate::schema::budget_comments as budget_comment_fields;
+use crate::schema::budget_comments::dsl::budget_comments;
+use crate::schema::budgets as budget_fields;
+use crate::schema::budgets::dsl::budgets;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::entry_attachments as entry_attachment_fields;
+use crate::schema::entry_attachments::dsl::entry_attachments;
+use crate::schema::user_budgets as user_budget_fields;
+use crate::schema::user_budgets::dsl::user_budgets;
+use crate::utils::db::audit_log::{self, AuditAction};
+use crate::utils::db::budget::BudgetRole;
+use crate::utils::db::category_total;
+
+// Deleted entries and comments aren't modified again, so their `modified_timestamp` is when they
+// were deleted. Only items deleted at or after `deleted_since` are in the trash; anything older is
+// waiting to be purged.
+pub fn get_trash(
+    db_connection: &DbConnection,
+    budget_id: Uuid,
+    deleted_since: NaiveDateTime,
+) -> Result<(Vec<Entry>, Vec<BudgetComment>), diesel::result::Error> {
+    let deleted_entries = entries
+        .filter(entry_fields::budget_id.eq(budget_id))
+        .filter(entry_fields::is_deleted.eq(true))
+        .filter(entry_fields::modified_timestamp.ge(deleted_since))
+        .order(entry_fields::modified_timestamp.desc())
+        .load::<Entry>(db_connection)?;
+
+    // Only the version of a comment that was showing when it was deleted is listed
+    let deleted_comments = budget_comments
+        .filter(budget_comment_fields::budget_id.eq(budget_id))
+        .filter(budget_comment_fields::is_current.eq(true))
+        .filter(budget_comment_fields::is_deleted.eq(true))
+        .filter(budget_comment_fields::modified_timestamp.ge(deleted_since))
+        .order(budget_comment_fields::modified_timestamp.desc())
+        .load::<BudgetComment>(db_connection)?;
+
+    Ok((deleted_entries, deleted_comments))
+}
+
+// Undoes `budget::delete_entry`. Fails with `NotFound` if the entry isn't in the trash of a budget
+// the user can edit.
+pub fn restore_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    entry_id: Uuid,
+    deleted_since: NaiveDateTime,
+) -> Result<Entry, diesel::result::Error> {
+    undelete_entry(db_connection, user_id, entry_id, deleted_since, true)
+}
+
+// Only for a budget's owners, who may go over a category's hard cap
+pub fn restore_entry_over_hard_cap(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    entry_id: Uuid,
+    deleted_since: NaiveDateTime,
+) -> Result<Entry, diesel::result::Error> {
+    undelete_entry(db_connection, user_id, entry_id, deleted_since, false)
+}
+
+fn undelete_entry(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    entry_id: Uuid,
+    deleted_since: NaiveDateTime,
+    enforce_hard_cap: bool,
+) -> Result<Entry, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let user_budget_ids = user_budgets
+        .select(user_budget_fields::budget_id)
+        .filter(user_budget_fields::user_id.eq(user_id))
+        .filter(user_budget_fields::role.ne(BudgetRole::Viewer as i16));
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        // As in `budget::delete_entry`, subtracting and adding back around the update leaves the
+        // totals counting the entry only if it was restored
+        category_total::subtract_entries(db_connection, &[entry_id])?;
+
+        let restored_entry = diesel::update(
+            entries
+                .filter(entry_fields::id.eq(entry_id))
+                .filter(entry_fields::is_deleted.eq(true))
+                .filter(entry_fields::modified_timestamp.ge(deleted_since))
+                .filter(entry_fields::budget_id.eq_any(user_budget_ids)),
+        )
+        .set((
+            entry_fields::is_deleted.eq(false),
+            entry_fields::modified_timestamp.eq(current_time),
+        ))
+        .get_result::<Entry>(db_connection)?;
+
+        if enforce_hard_cap {
+            category_total::add_entries_within_hard_caps(db_connection, &[entry_id])?;
+        } else {
+            category_total::add_entries(db_connection, &[entry_id])?;
+        }
+
+        diesel::update(budgets.find(restored_entry.budget_id))
+            .set(budget_fields::latest_entry_time.eq(current_time))
+            .execute(db_connection)?;
+
+        audit_log::record(
+            db_connection,
+            restored_entry.budget_id,
+            user_id,
+            AuditAction::EntryRestored,
+            None::<&Entry>,
+            Some(&restored_entry),
+        )?;
+
+        Ok(restored_entry)
+    })
+}
+
+// Hard-deletes entries and comments deleted before `deleted_before`. A comment's earlier versions
+// go with it. Returns the storage keys of the purged entries' attachments so their files can be
+// deleted too.
+pub fn purge_trash(
+    db_connection: &DbConnection,
+    deleted_before: NaiveDateTime,
+) -> Result<Vec<String>, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let purged_entry_ids = entries
+            .select(entry_fields::id)
+            .filter(entry_fields::is_deleted.eq(true))
+            .filter(entry_fields::modified_timestamp.lt(deleted_before));
+
+        let attachment_storage_keys = entry_attachments
+            .select(entry_attachment_fields::storage_key)
+            .filter(entry_attachment_fields::entry_id.eq_any(purged_entry_ids))
+            .load::<String>(db_connection)?;
+
+        // Attachments, comments, reminders and reimbursements on the entries are removed by the
+        // cascading foreign keys
+        diesel::delete(
+            entries
+                .filter(entry_fields::is_deleted.eq(true))
+                .filter(entry_fields::modified_timestamp.lt(deleted_before)),
+        )
+        .execute(db_connection)?;
+
+        let purged_comments = diesel::delete(
+            budget_comments
+                .filter(budget_comment_fields::is_current.eq(true))
+                .filter(budget_comment_fields::is_deleted.eq(true))
+                .filter(budget_comment_fields::modified_timestamp.lt(deleted_before)),
+        )
+        .get_results::<BudgetComment>(db_connection)?;
+
+        // Every version of a comment keeps the original `created_timestamp`
+        for comment in purged_comments {
+            diesel::delete(
+                budget_comments.filter(
+                    budget_comment_fields::budget_id
+                        .eq(comment.budget_id)
+                        .and(budget_comment_fields::user_id.eq(comment.user_id))
+                        .and(budget_comment_fields::created_timestamp.eq(comment.created_timestamp))
+                        .and(budget_comment_fields::is_current.eq(false)),
+                ),
+            )
+            .execute(db_connection)?;
+        }
+
+        Ok(attachment_storage_keys)
+    })
+}
@@ -1,13 +1,29 @@
 use actix_web::web;
-use diesel::{dsl, ExpressionMethods, QueryDsl, RunQueryDsl};
+use chrono::NaiveDateTime;
+use diesel::sql_types::{Timestamp, Uuid as SqlUuid};
+use diesel::{dsl, sql_query, Connection, ExpressionMethods, QueryDsl, RunQueryDsl};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::definitions::*;
 use crate::handlers::request_io::{InputEditUser, InputUser};
+use crate::models::blacklisted_token::NewBlacklistedToken;
+use crate::models::pending_deletion::{NewPendingDeletion, PendingDeletion};
+use crate::models::token_revocation::NewTokenRevocation;
 use crate::models::user::{NewUser, User};
+use crate::schema::blacklisted_tokens as blacklisted_token_fields;
+use crate::schema::blacklisted_tokens::dsl::blacklisted_tokens;
+use crate::schema::entries as entry_fields;
+use crate::schema::entries::dsl::entries;
+use crate::schema::pending_deletions as pending_deletion_fields;
+use crate::schema::pending_deletions::dsl::pending_deletions;
+use crate::schema::token_revocations as token_revocation_fields;
+use crate::schema::token_revocations::dsl::token_revocations;
 use crate::schema::users as user_fields;
 use crate::schema::users::dsl::users;
+use crate::utils::db::{category_total, session};
 use crate::utils::password_hasher;
+use crate::utils::record_id;
 
 pub fn get_user_by_id(
     db_connection: &DbConnection,
@@ -16,6 +32,18 @@ pub fn get_user_by_id(
     users.find(user_id).first::<User>(db_connection)
 }
 
+pub fn get_currencies_for_users(
+    db_connection: &DbConnection,
+    user_ids: &[Uuid],
+) -> Result<HashMap<Uuid, String>, diesel::result::Error> {
+    Ok(users
+        .select((user_fields::id, user_fields::currency))
+        .filter(user_fields::id.eq_any(user_ids))
+        .load::<(Uuid, String)>(db_connection)?
+        .into_iter()
+        .collect())
+}
+
 pub fn get_user_by_email(
     db_connection: &DbConnection,
     user_email: &str,
@@ -33,7 +61,7 @@ pub fn create_user(
     let current_time = chrono::Utc::now().naive_utc();
 
     let new_user = NewUser {
-        id: Uuid::new_v4(),
+        id: record_id::generate(),
         is_active: true,
         is_premium: false,
         premium_expiration: Option::None,
@@ -57,18 +85,24 @@ pub fn edit_user(
     user_id: Uuid,
     edited_user_data: &web::Json<InputEditUser>,
 ) -> Result<(), diesel::result::Error> {
-    match dsl::update(users.filter(user_fields::id.eq(user_id)))
-        .set((
-            user_fields::first_name.eq(&edited_user_data.first_name),
-            user_fields::last_name.eq(&edited_user_data.last_name),
-            user_fields::date_of_birth.eq(&edited_user_data.date_of_birth),
-            user_fields::currency.eq(&edited_user_data.currency),
-        ))
-        .execute(db_connection)
-    {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
-    }
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        dsl::update(users.filter(user_fields::id.eq(user_id)))
+            .set((
+                user_fields::first_name.eq(&edited_user_data.first_name),
+                user_fields::last_name.eq(&edited_user_data.last_name),
+                user_fields::date_of_birth.eq(&edited_user_data.date_of_birth),
+                user_fields::currency.eq(&edited_user_data.currency),
+            ))
+            .execute(db_connection)?;
+
+        if let Some(start_month) = edited_user_data.fiscal_year_start_month {
+            dsl::update(users.filter(user_fields::id.eq(user_id)))
+                .set(user_fields::fiscal_year_start_month.eq(start_month))
+                .execute(db_connection)?;
+        }
+
+        Ok(())
+    })
 }
 
 pub fn change_password(
@@ -87,6 +121,252 @@ pub fn change_password(
     }
 }
 
+// Uses up the reset token, sets the new password, and revokes every refresh token issued before
+// now, all in one transaction. Returns false without changing anything if the reset token has
+// already been used.
+//
+// The reset token is used up by revoking its session. Tokens issued before sessions existed have
+// no session and are blacklisted instead.
+pub fn reset_password(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    new_password: &str,
+    reset_session_id: Option<Uuid>,
+    reset_token: &str,
+    reset_token_expiration: i64,
+) -> Result<bool, diesel::result::Error> {
+    let hashed_password = password_hasher::hash_password(new_password);
+    let current_time = chrono::Utc::now().naive_utc();
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let was_token_unused = match reset_session_id {
+            Some(session_id) => session::revoke_session(db_connection, session_id)?,
+            None => {
+                dsl::insert_into(blacklisted_tokens)
+                    .values(&NewBlacklistedToken {
+                        token: reset_token,
+                        user_id,
+                        token_expiration_time: reset_token_expiration,
+                    })
+                    .on_conflict(blacklisted_token_fields::token)
+                    .do_nothing()
+                    .execute(db_connection)?
+                    > 0
+            }
+        };
+
+        if !was_token_unused {
+            return Ok(false);
+        }
+
+        dsl::update(users.find(user_id))
+            .set(user_fields::password_hash.eq(hashed_password))
+            .execute(db_connection)?;
+
+        dsl::insert_into(token_revocations)
+            .values(&NewTokenRevocation {
+                user_id,
+                revoked_timestamp: current_time,
+            })
+            .on_conflict(token_revocation_fields::user_id)
+            .do_update()
+            .set(token_revocation_fields::revoked_timestamp.eq(current_time))
+            .execute(db_connection)?;
+
+        Ok(true)
+    })
+}
+
+// Replaces a hash created under older, weaker hashing settings. Only called after the password
+// has been verified against `current_hash`. The update is skipped if the password was changed in
+// the meantime. Returns whether the password was rehashed.
+pub fn rehash_password_if_needed(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    password: &str,
+    current_hash: &str,
+) -> Result<bool, diesel::result::Error> {
+    if !password_hasher::needs_rehash(current_hash) {
+        return Ok(false);
+    }
+
+    let new_hash = password_hasher::hash_password(password);
+
+    let updated_count = dsl::update(
+        users
+            .filter(user_fields::id.eq(user_id))
+            .filter(user_fields::password_hash.eq(current_hash)),
+    )
+    .set(user_fields::password_hash.eq(new_hash))
+    .execute(db_connection)?;
+
+    Ok(updated_count > 0)
+}
+
+// Tables whose rows move to the primary account as-is when accounts are merged
+const MERGED_USER_TABLES: [&str; 17] = [
+    "api_keys",
+    "budget_audit_log",
+    "budget_comment_reactions",
+    "budget_comments",
+    "budget_resources",
+    "category_allocations",
+    "entries",
+    "entry_attachments",
+    "entry_comment_reactions",
+    "entry_comments",
+    "import_batches",
+    "inbox_entries",
+    "recurring_entries",
+    "reminders",
+    "spending_challenges",
+    "support_tickets",
+    "user_notifications",
+];
+
+// Moves everything the secondary account owns to the primary account, then deletes the secondary
+// account. Rows that would duplicate something the primary account already has (budget
+// memberships, invitations, badges, a benchmarking profile) are dropped instead of moved.
+pub fn merge_accounts(
+    db_connection: &DbConnection,
+    primary_user_id: Uuid,
+    secondary_user_id: Uuid,
+) -> Result<(), diesel::result::Error> {
+    // All of the queries below take the user IDs as bound parameters ($1 is the primary
+    // account, $2 is the secondary account). Only the table names are formatted in, and those are
+    // constants.
+    let statements = [
+        "DELETE FROM user_budgets WHERE user_id = $2 \
+         AND budget_id IN (SELECT budget_id FROM user_budgets WHERE user_id = $1)"
+            .to_string(),
+        "UPDATE user_budgets SET user_id = $1 WHERE user_id = $2".to_string(),
+        "DELETE FROM budget_share_events \
+         WHERE (sharer_user_id = $1 AND recipient_user_id = $2) \
+         OR (sharer_user_id = $2 AND recipient_user_id = $1)"
+            .to_string(),
+        "DELETE FROM budget_share_events s WHERE s.recipient_user_id = $2 \
+         AND EXISTS (SELECT 1 FROM budget_share_events p \
+         WHERE p.recipient_user_id = $1 \
+         AND p.sharer_user_id = s.sharer_user_id \
+         AND p.budget_id = s.budget_id)"
+            .to_string(),
+        "UPDATE budget_share_events SET recipient_user_id = $1 WHERE recipient_user_id = $2"
+            .to_string(),
+        "DELETE FROM budget_share_events s WHERE s.sharer_user_id = $2 \
+         AND EXISTS (SELECT 1 FROM budget_share_events p \
+         WHERE p.sharer_user_id = $1 \
+         AND p.recipient_user_id = s.recipient_user_id \
+         AND p.budget_id = s.budget_id)"
+            .to_string(),
+        "UPDATE budget_share_events SET sharer_user_id = $1 WHERE sharer_user_id = $2".to_string(),
+        "UPDATE user_badges SET user_id = $1 WHERE user_id = $2 \
+         AND badge NOT IN (SELECT badge FROM user_badges WHERE user_id = $1)"
+            .to_string(),
+        "UPDATE benchmarking_profiles SET user_id = $1 WHERE user_id = $2 \
+         AND NOT EXISTS (SELECT 1 FROM benchmarking_profiles WHERE user_id = $1)"
+            .to_string(),
+        "UPDATE closed_months SET closed_by_user_id = $1 WHERE closed_by_user_id = $2".to_string(),
+    ];
+
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        let table_statements = MERGED_USER_TABLES
+            .iter()
+            .map(|table| format!("UPDATE {table} SET user_id = $1 WHERE user_id = $2"));
+
+        for statement in statements.iter().cloned().chain(table_statements) {
+            sql_query(statement)
+                .bind::<SqlUuid, _>(primary_user_id)
+                .bind::<SqlUuid, _>(secondary_user_id)
+                .execute(db_connection)?;
+        }
+
+        // Anything left behind (attempt counters, blacklisted tokens, duplicate badges) is removed
+        // by the cascading foreign keys
+        diesel::delete(users.find(secondary_user_id)).execute(db_connection)?;
+
+        Ok(())
+    })
+}
+
+pub fn schedule_deletion(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+    grace_period: chrono::Duration,
+) -> Result<PendingDeletion, diesel::result::Error> {
+    let current_time = chrono::Utc::now().naive_utc();
+
+    let new_pending_deletion = NewPendingDeletion {
+        user_id,
+        delete_after: current_time + grace_period,
+        created_timestamp: current_time,
+    };
+
+    dsl::insert_into(pending_deletions)
+        .values(&new_pending_deletion)
+        .get_result::<PendingDeletion>(db_connection)
+}
+
+pub fn get_pending_deletion(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<PendingDeletion, diesel::result::Error> {
+    pending_deletions
+        .find(user_id)
+        .first::<PendingDeletion>(db_connection)
+}
+
+pub fn cancel_deletion(
+    db_connection: &DbConnection,
+    user_id: Uuid,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(pending_deletions.find(user_id)).execute(db_connection)
+}
+
+// Hard-deletes every user whose grace period ended before `now`, along with any budget that no
+// remaining user belongs to. Returns the number of users deleted.
+pub fn purge_expired_deletions(
+    db_connection: &DbConnection,
+    now: NaiveDateTime,
+) -> Result<usize, diesel::result::Error> {
+    db_connection.transaction::<_, diesel::result::Error, _>(|| {
+        sql_query(
+            "DELETE FROM budgets b \
+             WHERE b.id IN (SELECT ub.budget_id FROM user_budgets ub \
+             JOIN pending_deletions pd ON pd.user_id = ub.user_id \
+             WHERE pd.delete_after <= $1) \
+             AND NOT EXISTS (SELECT 1 FROM user_budgets ub \
+             WHERE ub.budget_id = b.id \
+             AND ub.user_id NOT IN (SELECT user_id FROM pending_deletions \
+             WHERE delete_after <= $1))",
+        )
+        .bind::<Timestamp, _>(now)
+        .execute(db_connection)?;
+
+        // Comments, entries, tokens, and everything else tied to the user are removed by the
+        // cascading foreign keys (including the pending deletion itself)
+        let expired_user_ids = pending_deletions
+            .select(pending_deletion_fields::user_id)
+            .filter(pending_deletion_fields::delete_after.le(now));
+
+        // The cascade doesn't keep category totals in step, so the totals of the budgets that other
+        // members keep are rebuilt without the user's entries
+        let kept_budget_ids = entries
+            .select(entry_fields::budget_id)
+            .filter(entry_fields::user_id.eq_any(expired_user_ids))
+            .distinct()
+            .load::<Uuid>(db_connection)?;
+
+        let deleted_count = diesel::delete(users.filter(user_fields::id.eq_any(expired_user_ids)))
+            .execute(db_connection)?;
+
+        for budget_id in kept_budget_ids {
+            category_total::recompute_for_budget(db_connection, budget_id)?;
+        }
+
+        Ok(deleted_count)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,6 +375,16 @@ mod tests {
     use rand::prelude::*;
 
     use crate::env;
+    use crate::handlers::request_io::{InputBudget, InputCategory, InputEntry};
+    use crate::models::closed_month::ClosedMonth;
+    use crate::models::entry_attachment::NewEntryAttachment;
+    use crate::models::user_badge::UserBadge;
+    use crate::schema::closed_months::dsl::closed_months;
+    use crate::schema::user_badges as badge_fields;
+    use crate::schema::user_badges::dsl::user_badges;
+    use crate::utils::db::budget::BudgetRole;
+    use crate::utils::db::{audit_log, budget, closed_month, engagement, entry_attachment};
+    use crate::utils::engagement::Badge;
 
     #[actix_rt::test]
     async fn test_create_user() {
@@ -231,6 +521,7 @@ mod tests {
             last_name: user_before.last_name.clone(),
             date_of_birth: user_before.date_of_birth.clone(),
             currency: user_before.currency.clone(),
+            fiscal_year_start_month: None,
         };
 
         let user_edits_json = web::Json(user_edits.clone());
@@ -281,6 +572,7 @@ mod tests {
                 rand::thread_rng().gen_range(1..=28),
             ),
             currency: String::from("DOP"),
+            fiscal_year_start_month: None,
         };
 
         let user_edits_json = web::Json(user_edits.clone());
@@ -347,4 +639,394 @@ mod tests {
             &updated_password_saved_hash
         ));
     }
+
+    #[actix_rt::test]
+    async fn test_rehash_password_if_needed() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        const PASSWORD: &str = "Lk3$vB8!nQ1^wE5&tZ7r";
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from(PASSWORD),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(2000, 1, 1),
+            currency: String::from("USD"),
+        };
+
+        let user = create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        assert!(
+            !rehash_password_if_needed(&db_connection, user.id, PASSWORD, &user.password_hash)
+                .unwrap()
+        );
+
+        let mut hashing_key_mut = env::CONF.keys.hashing_key.clone();
+        let mut salt = vec![3u8; env::CONF.hashing.salt_length_bytes];
+        let weak_hash = password_hasher::hash_argon2id(
+            PASSWORD,
+            unsafe { hashing_key_mut.as_bytes_mut() },
+            &mut salt[..],
+            u32::try_from(env::CONF.hashing.hash_length).unwrap(),
+            env::CONF.hashing.hash_iterations - 1,
+            env::CONF.hashing.hash_mem_size_kib,
+            env::CONF.hashing.hash_lanes,
+        )
+        .to_hash_string();
+
+        dsl::update(users.find(user.id))
+            .set(user_fields::password_hash.eq(&weak_hash))
+            .execute(&db_connection)
+            .unwrap();
+
+        assert!(rehash_password_if_needed(&db_connection, user.id, PASSWORD, &weak_hash).unwrap());
+
+        let rehashed = get_user_by_id(&db_connection, user.id)
+            .unwrap()
+            .password_hash;
+        assert_ne!(rehashed, weak_hash);
+        assert!(!password_hasher::needs_rehash(&rehashed));
+        assert!(password_hasher::verify_hash(PASSWORD, &rehashed));
+
+        // A stale hash doesn't overwrite the one that replaced it
+        assert!(!rehash_password_if_needed(&db_connection, user.id, PASSWORD, &weak_hash).unwrap());
+    }
+
+    #[actix_rt::test]
+    async fn test_merge_accounts() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let mut created_users = Vec::new();
+
+        for _ in 0..2 {
+            let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+            let new_user = InputUser {
+                email: format!("test_user{}@test.com", &user_number),
+                password: String::from("Zm6!pW2#kR8&cT4^yB0e"),
+                first_name: format!("Test-{}", &user_number),
+                last_name: format!("User-{}", &user_number),
+                date_of_birth: NaiveDate::from_ymd(
+                    rand::thread_rng().gen_range(1950..=2020),
+                    rand::thread_rng().gen_range(1..=12),
+                    rand::thread_rng().gen_range(1..=28),
+                ),
+                currency: String::from("USD"),
+            };
+
+            created_users.push(create_user(&db_connection, &web::Json(new_user)).unwrap());
+        }
+
+        let primary_user_id = created_users[0].id;
+        let secondary_user_id = created_users[1].id;
+
+        let new_budget = |name: &str| InputBudget {
+            name: String::from(name),
+            description: None,
+            categories: vec![InputCategory {
+                id: 0,
+                name: String::from("Groceries"),
+                limit_cents: 30000,
+                color: String::from("#ff11ee"),
+            }],
+            start_date: NaiveDate::from_ymd(2022, 1, 1),
+            end_date: NaiveDate::from_ymd(2022, 12, 31),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let shared_budget = budget::create_budget(
+            &db_connection,
+            &web::Json(new_budget("Shared")),
+            primary_user_id,
+        )
+        .unwrap();
+        budget::add_user(
+            &db_connection,
+            shared_budget.id,
+            secondary_user_id,
+            BudgetRole::Editor,
+        )
+        .unwrap();
+
+        let secondary_budget = budget::create_budget(
+            &db_connection,
+            &web::Json(new_budget("Secondary")),
+            secondary_user_id,
+        )
+        .unwrap();
+
+        let entry = InputEntry {
+            id: None,
+            budget_id: secondary_budget.id,
+            amount_cents: 1200,
+            date: NaiveDate::from_ymd(2022, 2, 1),
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+        let created_entry =
+            budget::create_entry(&db_connection, &web::Json(entry), secondary_user_id).unwrap();
+
+        // An attachment the secondary account uploaded in a budget shared with someone else
+        let shared_entry = InputEntry {
+            id: None,
+            budget_id: shared_budget.id,
+            amount_cents: 3400,
+            date: NaiveDate::from_ymd(2022, 3, 1),
+            name: None,
+            category: Some(0),
+            note: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        };
+        let created_shared_entry =
+            budget::create_entry(&db_connection, &web::Json(shared_entry), secondary_user_id)
+                .unwrap();
+
+        let attachment_id = Uuid::new_v4();
+        entry_attachment::add_attachment(
+            &db_connection,
+            &NewEntryAttachment {
+                id: attachment_id,
+                entry_id: created_shared_entry.id,
+                budget_id: shared_budget.id,
+                user_id: secondary_user_id,
+                file_name: "receipt.pdf",
+                content_type: "application/pdf",
+                size_bytes: 1024,
+                storage_key: &attachment_id.to_string(),
+                created_timestamp: chrono::Utc::now().naive_utc(),
+            },
+            10,
+        )
+        .unwrap()
+        .unwrap();
+
+        closed_month::close_month(
+            &db_connection,
+            secondary_budget.id,
+            NaiveDate::from_ymd(2022, 2, 1),
+            secondary_user_id,
+        )
+        .unwrap();
+
+        engagement::award_badges(
+            &db_connection,
+            primary_user_id,
+            &[Badge::FirstChallengeCompleted],
+        )
+        .unwrap();
+        engagement::award_badges(
+            &db_connection,
+            secondary_user_id,
+            &[Badge::FirstChallengeCompleted, Badge::SevenDayNoSpendStreak],
+        )
+        .unwrap();
+
+        merge_accounts(&db_connection, primary_user_id, secondary_user_id).unwrap();
+
+        assert!(get_user_by_id(&db_connection, secondary_user_id).is_err());
+
+        assert!(
+            budget::check_user_in_budget(&db_connection, primary_user_id, shared_budget.id)
+                .unwrap()
+        );
+        assert!(
+            budget::check_user_in_budget(&db_connection, primary_user_id, secondary_budget.id)
+                .unwrap()
+        );
+
+        let fetched_budget = budget::get_budget_by_id(&db_connection, secondary_budget.id).unwrap();
+        assert_eq!(fetched_budget.entries.len(), 1);
+        assert_eq!(fetched_budget.entries[0].id, created_entry.id);
+        assert_eq!(fetched_budget.entries[0].user_id, primary_user_id);
+
+        let attachment =
+            entry_attachment::get_attachment(&db_connection, shared_budget.id, attachment_id)
+                .unwrap();
+        assert_eq!(attachment.user_id, primary_user_id);
+        assert_eq!(attachment.entry_id, created_shared_entry.id);
+
+        let badges = user_badges
+            .filter(badge_fields::user_id.eq(primary_user_id))
+            .load::<UserBadge>(&db_connection)
+            .unwrap();
+        assert_eq!(badges.len(), 2);
+
+        // The audit log still says who made each change
+        for budget_id in [secondary_budget.id, shared_budget.id] {
+            let audit_log = audit_log::get_audit_log(&db_connection, budget_id, 100, 0).unwrap();
+            assert!(!audit_log.events.is_empty());
+            assert!(audit_log
+                .events
+                .iter()
+                .all(|e| e.user_id == Some(primary_user_id)));
+        }
+
+        let closed = closed_months
+            .find((secondary_budget.id, NaiveDate::from_ymd(2022, 2, 1)))
+            .first::<ClosedMonth>(&db_connection)
+            .unwrap();
+        assert_eq!(closed.closed_by_user_id, Some(primary_user_id));
+    }
+
+    #[actix_rt::test]
+    async fn test_schedule_and_cancel_deletion() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+        let new_user = InputUser {
+            email: format!("test_user{}@test.com", &user_number),
+            password: String::from("Zm6!pW2#kR8&cT4^yB0e"),
+            first_name: format!("Test-{}", &user_number),
+            last_name: format!("User-{}", &user_number),
+            date_of_birth: NaiveDate::from_ymd(2000, 1, 1),
+            currency: String::from("USD"),
+        };
+
+        let user = create_user(&db_connection, &web::Json(new_user)).unwrap();
+
+        assert!(get_pending_deletion(&db_connection, user.id).is_err());
+
+        let pending_deletion =
+            schedule_deletion(&db_connection, user.id, chrono::Duration::days(14)).unwrap();
+        assert_eq!(
+            pending_deletion.delete_after - pending_deletion.created_timestamp,
+            chrono::Duration::days(14)
+        );
+
+        assert!(schedule_deletion(&db_connection, user.id, chrono::Duration::days(14)).is_err());
+
+        let fetched_pending_deletion = get_pending_deletion(&db_connection, user.id).unwrap();
+        assert_eq!(
+            fetched_pending_deletion.delete_after,
+            pending_deletion.delete_after
+        );
+
+        assert_eq!(cancel_deletion(&db_connection, user.id).unwrap(), 1);
+        assert_eq!(cancel_deletion(&db_connection, user.id).unwrap(), 0);
+        assert!(get_pending_deletion(&db_connection, user.id).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_purge_expired_deletions() {
+        let db_thread_pool = &*env::testing::DB_THREAD_POOL;
+        let db_connection = db_thread_pool.get().unwrap();
+
+        let mut created_users = Vec::new();
+
+        for _ in 0..3 {
+            let user_number = rand::thread_rng().gen_range::<u128, _>(10_000_000..100_000_000);
+            let new_user = InputUser {
+                email: format!("test_user{}@test.com", &user_number),
+                password: String::from("Zm6!pW2#kR8&cT4^yB0e"),
+                first_name: format!("Test-{}", &user_number),
+                last_name: format!("User-{}", &user_number),
+                date_of_birth: NaiveDate::from_ymd(2000, 1, 1),
+                currency: String::from("USD"),
+            };
+
+            created_users.push(create_user(&db_connection, &web::Json(new_user)).unwrap());
+        }
+
+        let deleted_user_id = created_users[0].id;
+        let other_user_id = created_users[1].id;
+        let still_pending_user_id = created_users[2].id;
+
+        let new_budget = |name: &str| InputBudget {
+            name: String::from(name),
+            description: None,
+            categories: Vec::new(),
+            start_date: NaiveDate::from_ymd(2022, 1, 1),
+            end_date: NaiveDate::from_ymd(2022, 12, 31),
+            is_tracking_only: false,
+            is_envelope: false,
+        };
+
+        let solo_budget = budget::create_budget(
+            &db_connection,
+            &web::Json(new_budget("Solo")),
+            deleted_user_id,
+        )
+        .unwrap();
+
+        let shared_budget = budget::create_budget(
+            &db_connection,
+            &web::Json(new_budget("Shared")),
+            deleted_user_id,
+        )
+        .unwrap();
+        budget::add_user(
+            &db_connection,
+            shared_budget.id,
+            other_user_id,
+            BudgetRole::Editor,
+        )
+        .unwrap();
+
+        let pending_budget = budget::create_budget(
+            &db_connection,
+            &web::Json(new_budget("Pending")),
+            still_pending_user_id,
+        )
+        .unwrap();
+
+        for (user_id, amount_cents) in [(deleted_user_id, 4000), (other_user_id, 1500)] {
+            let entry = InputEntry {
+                id: None,
+                budget_id: shared_budget.id,
+                amount_cents,
+                date: NaiveDate::from_ymd(2022, 5, 5),
+                name: None,
+                category: None,
+                note: None,
+                tax_cents: None,
+                tip_cents: None,
+                is_deductible: false,
+            };
+
+            budget::create_entry(&db_connection, &web::Json(entry), user_id).unwrap();
+        }
+
+        schedule_deletion(&db_connection, deleted_user_id, chrono::Duration::days(-1)).unwrap();
+        schedule_deletion(
+            &db_connection,
+            still_pending_user_id,
+            chrono::Duration::days(14),
+        )
+        .unwrap();
+
+        let purged_count =
+            purge_expired_deletions(&db_connection, chrono::Utc::now().naive_utc()).unwrap();
+        assert!(purged_count >= 1);
+
+        assert!(get_user_by_id(&db_connection, deleted_user_id).is_err());
+        assert!(get_pending_deletion(&db_connection, deleted_user_id).is_err());
+        assert!(budget::get_budget_by_id(&db_connection, solo_budget.id).is_err());
+
+        assert!(
+            budget::check_user_in_budget(&db_connection, other_user_id, shared_budget.id).unwrap()
+        );
+
+        let summary = budget::get_budget_summary(
+            &db_connection,
+            shared_budget.id,
+            NaiveDate::from_ymd(2022, 6, 1),
+        )
+        .unwrap();
+        assert_eq!(summary.spent_cents, 1500);
+
+        assert!(get_user_by_id(&db_connection, still_pending_user_id).is_ok());
+        assert!(get_pending_deletion(&db_connection, still_pending_user_id).is_ok());
+        assert!(budget::get_budget_by_id(&db_connection, pending_budget.id).is_ok());
+    }
 }
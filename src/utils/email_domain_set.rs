@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead};
+
+use crate::env;
+
+// Domains are matched along with their subdomains, so listing `example.com` also covers
+// `mail.example.com`
+pub struct EmailDomainSet(HashSet<String>);
+
+impl EmailDomainSet {
+    pub fn generate() -> EmailDomainSet {
+        let path = std::path::Path::new(*env::email::DISPOSABLE_EMAIL_DOMAINS_FILE_PATH);
+
+        let file_error_msg = format!(
+            "Failed to open {}",
+            path.to_str()
+                .unwrap_or(*env::email::DISPOSABLE_EMAIL_DOMAINS_FILE_PATH)
+        );
+        let domains_file = File::open(path).expect(&file_error_msg);
+
+        let domains = io::BufReader::new(domains_file)
+            .lines()
+            .map_while(Result::ok);
+
+        EmailDomainSet::from_domains(domains)
+    }
+
+    // Blank lines and lines starting with `#` are skipped
+    pub fn from_domains<I, S>(domains: I) -> EmailDomainSet
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        EmailDomainSet(
+            domains
+                .into_iter()
+                .map(|d| d.as_ref().trim().trim_end_matches('.').to_ascii_lowercase())
+                .filter(|d| !d.is_empty() && !d.starts_with('#'))
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, domain: &str) -> bool {
+        let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+        let mut remaining = domain.as_str();
+
+        loop {
+            if self.0.contains(remaining) {
+                return true;
+            }
+
+            match remaining.split_once('.') {
+                Some((_, parent)) => remaining = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_domain_set() {
+        let set = EmailDomainSet::from_domains([
+            "# Comments are skipped",
+            "",
+            "Mailinator.com",
+            "  yopmail.fr  ",
+        ]);
+
+        assert!(set.contains("mailinator.com"));
+        assert!(set.contains("MAILINATOR.COM"));
+        assert!(set.contains("inbox.mailinator.com"));
+        assert!(set.contains("yopmail.fr."));
+
+        assert!(!set.contains("notmailinator.com"));
+        assert!(!set.contains("mailinator.com.au"));
+        assert!(!set.contains("com"));
+        assert!(!set.contains("# Comments are skipped"));
+
+        let set = EmailDomainSet::generate();
+        assert!(set.contains("mailinator.com"));
+        assert!(!set.contains("gmail.com"));
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+// Streaks are only computed this far back
+pub const MAX_STREAK_DAYS: i64 = 365;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Badge {
+    FirstChallengeCompleted,
+    FiveChallengesCompleted,
+    SevenDayNoSpendStreak,
+    ThirtyDayNoSpendStreak,
+}
+
+impl Badge {
+    pub fn for_streak(streak_days: u32) -> Vec<Badge> {
+        let mut badges = Vec::new();
+
+        if streak_days >= 7 {
+            badges.push(Badge::SevenDayNoSpendStreak);
+        }
+
+        if streak_days >= 30 {
+            badges.push(Badge::ThirtyDayNoSpendStreak);
+        }
+
+        badges
+    }
+
+    pub fn for_completed_challenge_count(count: i64) -> Vec<Badge> {
+        let mut badges = Vec::new();
+
+        if count >= 1 {
+            badges.push(Badge::FirstChallengeCompleted);
+        }
+
+        if count >= 5 {
+            badges.push(Badge::FiveChallengesCompleted);
+        }
+
+        badges
+    }
+}
+
+#[derive(Debug)]
+pub enum BadgeError {
+    NoMatchForValue(i16),
+}
+
+impl std::error::Error for BadgeError {}
+
+impl fmt::Display for BadgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BadgeError::NoMatchForValue(v) => write!(f, "NoMatchForValue: {}", v),
+        }
+    }
+}
+
+impl std::convert::TryFrom<i16> for Badge {
+    type Error = BadgeError;
+
+    fn try_from(value: i16) -> Result<Self, BadgeError> {
+        match value {
+            0 => Ok(Badge::FirstChallengeCompleted),
+            1 => Ok(Badge::FiveChallengesCompleted),
+            2 => Ok(Badge::SevenDayNoSpendStreak),
+            3 => Ok(Badge::ThirtyDayNoSpendStreak),
+            v => Err(BadgeError::NoMatchForValue(v)),
+        }
+    }
+}
+
+impl std::convert::From<Badge> for i16 {
+    fn from(badge: Badge) -> Self {
+        match badge {
+            Badge::FirstChallengeCompleted => 0,
+            Badge::FiveChallengesCompleted => 1,
+            Badge::SevenDayNoSpendStreak => 2,
+            Badge::ThirtyDayNoSpendStreak => 3,
+        }
+    }
+}
+
+// Counts the consecutive days, ending with today, on which the user recorded no spending. Days
+// before earliest_date (e.g. before the user signed up) don't count towards the streak.
+pub fn compute_no_spend_streak(
+    spending_dates: &[NaiveDate],
+    today: NaiveDate,
+    earliest_date: NaiveDate,
+) -> u32 {
+    let spending_dates = spending_dates.iter().collect::<HashSet<_>>();
+
+    let mut streak = 0;
+    let mut day = today;
+
+    while day >= earliest_date && !spending_dates.contains(&day) {
+        streak += 1;
+        day -= Duration::days(1);
+    }
+
+    streak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_no_spend_streak() {
+        let today = NaiveDate::from_ymd(2022, 6, 15);
+        let long_ago = NaiveDate::from_ymd(2020, 1, 1);
+
+        let spending_dates = vec![
+            NaiveDate::from_ymd(2022, 6, 10),
+            NaiveDate::from_ymd(2022, 6, 1),
+        ];
+        assert_eq!(compute_no_spend_streak(&spending_dates, today, long_ago), 5);
+
+        let spending_dates = vec![today, NaiveDate::from_ymd(2022, 6, 1)];
+        assert_eq!(compute_no_spend_streak(&spending_dates, today, long_ago), 0);
+
+        assert_eq!(
+            compute_no_spend_streak(&[], today, NaiveDate::from_ymd(2022, 6, 13)),
+            3
+        );
+    }
+
+    #[test]
+    fn test_badges_earned() {
+        assert!(Badge::for_streak(6).is_empty());
+        assert_eq!(Badge::for_streak(7), vec![Badge::SevenDayNoSpendStreak]);
+        assert_eq!(
+            Badge::for_streak(45),
+            vec![Badge::SevenDayNoSpendStreak, Badge::ThirtyDayNoSpendStreak]
+        );
+
+        assert!(Badge::for_completed_challenge_count(0).is_empty());
+        assert_eq!(
+            Badge::for_completed_challenge_count(5),
+            vec![
+                Badge::FirstChallengeCompleted,
+                Badge::FiveChallengesCompleted
+            ]
+        );
+
+        for badge in [
+            Badge::FirstChallengeCompleted,
+            Badge::FiveChallengesCompleted,
+            Badge::SevenDayNoSpendStreak,
+            Badge::ThirtyDayNoSpendStreak,
+        ] {
+            assert_eq!(Badge::try_from(i16::from(badge)).unwrap(), badge);
+        }
+
+        assert!(Badge::try_from(-1).is_err());
+    }
+}
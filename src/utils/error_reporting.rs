@@ -0,0 +1,74 @@
+use sentry::protocol::Level;
+use std::borrow::Cow;
+
+use crate::cron::CronJobError;
+use crate::env;
+
+// Returns a guard that flushes queued reports when dropped, so it must be held for the lifetime
+// of the server. No PII (IP addresses, cookies, request bodies) is attached to reports.
+pub fn initialize() -> Option<sentry::ClientInitGuard> {
+    let conf = &env::CONF.error_reporting;
+
+    let dsn = match &conf.dsn {
+        Some(dsn) if !dsn.trim().is_empty() => dsn.trim(),
+        _ => return None,
+    };
+
+    let dsn = match dsn.parse() {
+        Ok(d) => d,
+        Err(e) => {
+            log::error!("Invalid error_reporting dsn config: {}", e);
+            return None;
+        }
+    };
+
+    Some(sentry::init(sentry::ClientOptions {
+        dsn: Some(dsn),
+        release: sentry::release_name!(),
+        environment: Some(Cow::Owned(conf.environment.clone())),
+        sample_rate: conf.sample_rate,
+        send_default_pii: false,
+        ..Default::default()
+    }))
+}
+
+pub struct RequestContext<'a> {
+    pub method: &'a str,
+    // The matched route pattern rather than the raw path or query string, which may contain
+    // user data
+    pub route: &'a str,
+    pub status: u16,
+    pub app_platform: Option<&'a str>,
+    pub app_version: Option<&'a str>,
+}
+
+pub fn report_server_error(message: &str, context: &RequestContext) {
+    sentry::with_scope(
+        |scope| {
+            scope.set_tag("http.method", context.method);
+            scope.set_tag("http.route", context.route);
+            scope.set_tag("http.status_code", context.status);
+
+            if let Some(platform) = context.app_platform {
+                scope.set_tag("app.platform", platform);
+            }
+
+            if let Some(version) = context.app_version {
+                scope.set_tag("app.version", version);
+            }
+        },
+        || sentry::capture_message(message, Level::Error),
+    );
+}
+
+pub fn report_job_failure(job_name: &str, error: &CronJobError) {
+    sentry::with_scope(
+        |scope| scope.set_tag("cron.job", job_name),
+        || {
+            sentry::capture_message(
+                &format!("Cron job failed: '{job_name}': {error}"),
+                Level::Error,
+            )
+        },
+    );
+}
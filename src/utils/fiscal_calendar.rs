@@ -0,0 +1,152 @@
+use chrono::NaiveDate;
+
+// A fiscal year runs for twelve months from the first day of its start month. It is named for the
+// calendar year it starts in, so with a July start, fiscal year 2023 runs from July 1, 2023 to
+// June 30, 2024. Quarters are the three-month periods counted from the start of the fiscal year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FiscalCalendar {
+    start_month: u32,
+}
+
+impl FiscalCalendar {
+    // None if `start_month` isn't a month from 1 (January) to 12 (December)
+    pub fn new(start_month: u32) -> Option<Self> {
+        if (1..=12).contains(&start_month) {
+            Some(Self { start_month })
+        } else {
+            None
+        }
+    }
+
+    // The first and last days of the fiscal year. None if the year is outside the dates chrono
+    // can represent.
+    pub fn year_bounds(&self, fiscal_year: i32) -> Option<(NaiveDate, NaiveDate)> {
+        self.period_bounds(fiscal_year, 0, 12)
+    }
+
+    // The first and last days of the quarter, which is from 1 to 4
+    pub fn quarter_bounds(&self, fiscal_year: i32, quarter: u32) -> Option<(NaiveDate, NaiveDate)> {
+        if !(1..=4).contains(&quarter) {
+            return None;
+        }
+
+        self.period_bounds(fiscal_year, (quarter - 1) * 3, 3)
+    }
+
+    // The period starts `offset_months` after the start of the fiscal year and lasts `length_months`
+    fn period_bounds(
+        &self,
+        fiscal_year: i32,
+        offset_months: u32,
+        length_months: u32,
+    ) -> Option<(NaiveDate, NaiveDate)> {
+        let start = first_of_month_after(fiscal_year, self.start_month, offset_months)?;
+        let end =
+            first_of_month_after(fiscal_year, self.start_month, offset_months + length_months)?
+                .pred_opt()?;
+
+        Some((start, end))
+    }
+}
+
+impl Default for FiscalCalendar {
+    fn default() -> Self {
+        Self { start_month: 1 }
+    }
+}
+
+fn first_of_month_after(year: i32, month: u32, months_after: u32) -> Option<NaiveDate> {
+    let months_from_january = month - 1 + months_after;
+    let year = year.checked_add((months_from_january / 12) as i32)?;
+
+    NaiveDate::from_ymd_opt(year, months_from_january % 12 + 1, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Datelike;
+
+    #[test]
+    fn test_fiscal_calendar() {
+        let calendar_year = FiscalCalendar::default();
+        assert_eq!(
+            calendar_year.year_bounds(2024),
+            Some((
+                NaiveDate::from_ymd(2024, 1, 1),
+                NaiveDate::from_ymd(2024, 12, 31)
+            ))
+        );
+        assert_eq!(
+            calendar_year.quarter_bounds(2024, 4),
+            Some((
+                NaiveDate::from_ymd(2024, 10, 1),
+                NaiveDate::from_ymd(2024, 12, 31)
+            ))
+        );
+
+        // The year ends on the last day of February, whether or not it's a leap year
+        let march = FiscalCalendar::new(3).unwrap();
+        assert_eq!(
+            march.year_bounds(2023),
+            Some((
+                NaiveDate::from_ymd(2023, 3, 1),
+                NaiveDate::from_ymd(2024, 2, 29)
+            ))
+        );
+        assert_eq!(
+            march.year_bounds(2024),
+            Some((
+                NaiveDate::from_ymd(2024, 3, 1),
+                NaiveDate::from_ymd(2025, 2, 28)
+            ))
+        );
+        assert_eq!(
+            march.quarter_bounds(2023, 4),
+            Some((
+                NaiveDate::from_ymd(2023, 12, 1),
+                NaiveDate::from_ymd(2024, 2, 29)
+            ))
+        );
+
+        let october = FiscalCalendar::new(10).unwrap();
+        assert_eq!(
+            october.quarter_bounds(2023, 1),
+            Some((
+                NaiveDate::from_ymd(2023, 10, 1),
+                NaiveDate::from_ymd(2023, 12, 31)
+            ))
+        );
+        assert_eq!(
+            october.quarter_bounds(2023, 2),
+            Some((
+                NaiveDate::from_ymd(2024, 1, 1),
+                NaiveDate::from_ymd(2024, 3, 31)
+            ))
+        );
+
+        for start_month in 1..=12 {
+            let calendar = FiscalCalendar::new(start_month).unwrap();
+            let (start, end) = calendar.year_bounds(2023).unwrap();
+            assert_eq!(start.month(), start_month);
+            assert_eq!(end.succ_opt().unwrap().month(), start_month);
+
+            // Quarters follow on from each other with no gaps
+            let mut next_start = start;
+            for quarter in 1..=4 {
+                let (quarter_start, quarter_end) = calendar.quarter_bounds(2023, quarter).unwrap();
+                assert_eq!(quarter_start, next_start);
+                next_start = quarter_end.succ_opt().unwrap();
+            }
+            assert_eq!(next_start, end.succ_opt().unwrap());
+        }
+
+        assert_eq!(FiscalCalendar::new(0), None);
+        assert_eq!(FiscalCalendar::new(13), None);
+        assert_eq!(october.quarter_bounds(2023, 0), None);
+        assert_eq!(october.quarter_bounds(2023, 5), None);
+        assert_eq!(calendar_year.year_bounds(i32::MAX), None);
+        assert_eq!(october.year_bounds(262143), None);
+    }
+}
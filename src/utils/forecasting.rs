@@ -0,0 +1,447 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::handlers::request_io::OutputBudget;
+use crate::models::recurring_entry::RecurringEntry;
+use crate::utils::recurrence::{RecurrenceFrequency, RecurrenceFrequencyError};
+
+pub const OUTLOOK_MONTHS: u32 = 6;
+
+// An expense that repeats on a schedule, either an existing recurring entry or a hypothetical one
+// from a simulation
+#[derive(Clone, Debug)]
+pub struct ScheduledExpense {
+    pub category: Option<i16>,
+    pub amount_cents: i64,
+    pub frequency: RecurrenceFrequency,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub next_occurrence_date: NaiveDate,
+}
+
+impl ScheduledExpense {
+    // Sums the occurrences that fall after `after` and on or before `through`
+    fn total_between(&self, after: NaiveDate, through: NaiveDate) -> i64 {
+        let last_date = match self.end_date {
+            Some(end_date) if end_date < through => end_date,
+            _ => through,
+        };
+
+        let mut total = 0;
+        let mut occurrence = self.next_occurrence_date;
+
+        while occurrence <= last_date {
+            if occurrence > after {
+                total += self.amount_cents;
+            }
+
+            occurrence = self.frequency.next_occurrence(self.start_date, occurrence);
+        }
+
+        total
+    }
+}
+
+impl TryFrom<&RecurringEntry> for ScheduledExpense {
+    type Error = RecurrenceFrequencyError;
+
+    fn try_from(recurring_entry: &RecurringEntry) -> Result<Self, RecurrenceFrequencyError> {
+        Ok(ScheduledExpense {
+            category: recurring_entry.category,
+            amount_cents: recurring_entry.amount_cents,
+            frequency: RecurrenceFrequency::try_from(recurring_entry.frequency)?,
+            start_date: recurring_entry.start_date,
+            end_date: recurring_entry.end_date,
+            next_occurrence_date: recurring_entry.next_occurrence_date,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Adjustment {
+    // Changes future day-to-day spending in a category by a percentage (e.g. -20 to cut dining out
+    // by a fifth). Scheduled expenses in the category are unaffected.
+    ScaleCategory { category: i16, percent_change: i32 },
+    AddScheduledExpense(ScheduledExpense),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CategoryForecast {
+    pub category: Option<i16>,
+    pub limit_cents: Option<i64>,
+    pub spent_cents: i64,
+    pub projected_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MonthForecast {
+    pub month_start: NaiveDate,
+    pub projected_cents: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BudgetForecast {
+    pub as_of: NaiveDate,
+    pub period_end: NaiveDate,
+    // None for tracking-only budgets, which have no limits to measure against
+    pub limit_cents: Option<i64>,
+    pub spent_cents: i64,
+    pub projected_period_end_cents: i64,
+    pub categories: Vec<CategoryForecast>,
+    pub monthly_outlook: Vec<MonthForecast>,
+}
+
+#[derive(Default)]
+struct CategoryTotals {
+    limit_cents: Option<i64>,
+    spent_cents: i64,
+    discretionary_cents: i64,
+    percent_changes: Vec<i32>,
+}
+
+impl CategoryTotals {
+    // Projects day-to-day spending over `days` from the rate observed over `observed_days`
+    fn project_discretionary(&self, days: i64, observed_days: i64) -> i64 {
+        if observed_days <= 0 {
+            return 0;
+        }
+
+        let projected = self.discretionary_cents * days / observed_days;
+
+        self.percent_changes
+            .iter()
+            .fold(projected, |amount, percent| {
+                amount * (100 + i64::from(*percent)) / 100
+            })
+    }
+}
+
+fn first_of_next_month(date: NaiveDate) -> NaiveDate {
+    if date.month() == 12 {
+        NaiveDate::from_ymd(date.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd(date.year(), date.month() + 1, 1)
+    }
+}
+
+// Projects spending for the rest of the budget period and for the months that follow it.
+// Day-to-day spending is extrapolated from the rate observed so far in the period. Entries created
+// from recurring entries are left out of that rate because their future occurrences are counted
+// exactly from the schedule instead. A budget period that hasn't started yet has no observed rate,
+// so only scheduled expenses are projected for it.
+pub fn forecast_budget(
+    budget: &OutputBudget,
+    scheduled_expenses: &[ScheduledExpense],
+    adjustments: &[Adjustment],
+    today: NaiveDate,
+) -> BudgetForecast {
+    let observed_through = if today < budget.end_date {
+        today
+    } else {
+        budget.end_date
+    };
+    let observed_days = ((observed_through - budget.start_date).num_days() + 1).max(0);
+
+    let projected_from = if today < budget.start_date {
+        budget.start_date - Duration::days(1)
+    } else {
+        observed_through
+    };
+    let remaining_days = (budget.end_date - projected_from).num_days().max(0);
+
+    let mut totals = BTreeMap::<Option<i16>, CategoryTotals>::new();
+
+    for category in budget.categories.iter().filter(|c| !c.is_deleted) {
+        let category_totals = totals.entry(Some(category.id)).or_default();
+
+        if !budget.is_tracking_only {
+            category_totals.limit_cents = Some(category.limit_cents);
+        }
+    }
+
+    for entry in budget.entries.iter() {
+        if entry.is_deleted || entry.date < budget.start_date || entry.date > observed_through {
+            continue;
+        }
+
+        // Income in an envelope budget is money coming in, not negative spending
+        if budget.is_envelope && entry.category.is_none() && entry.amount_cents < 0 {
+            continue;
+        }
+
+        let category_totals = totals.entry(entry.category).or_default();
+        category_totals.spent_cents += entry.amount_cents;
+
+        if entry.recurring_entry_id.is_none() {
+            category_totals.discretionary_cents += entry.amount_cents;
+        }
+    }
+
+    let mut all_scheduled_expenses = scheduled_expenses.to_vec();
+
+    for adjustment in adjustments.iter() {
+        match adjustment {
+            Adjustment::ScaleCategory {
+                category,
+                percent_change,
+            } => totals
+                .entry(Some(*category))
+                .or_default()
+                .percent_changes
+                .push(*percent_change),
+            Adjustment::AddScheduledExpense(expense) => {
+                all_scheduled_expenses.push(expense.clone())
+            }
+        }
+    }
+
+    for expense in all_scheduled_expenses.iter() {
+        totals.entry(expense.category).or_default();
+    }
+
+    let scheduled_total = |category: Option<i16>, after: NaiveDate, through: NaiveDate| {
+        all_scheduled_expenses
+            .iter()
+            .filter(|e| e.category == category)
+            .map(|e| e.total_between(after, through))
+            .sum::<i64>()
+    };
+
+    let categories = totals
+        .iter()
+        .map(|(category, category_totals)| CategoryForecast {
+            category: *category,
+            limit_cents: category_totals.limit_cents,
+            spent_cents: category_totals.spent_cents,
+            projected_cents: category_totals.spent_cents
+                + category_totals.project_discretionary(remaining_days, observed_days)
+                + scheduled_total(*category, projected_from, budget.end_date),
+        })
+        .collect::<Vec<_>>();
+
+    let mut monthly_outlook = Vec::new();
+    let mut month_start = first_of_next_month(today);
+
+    for _ in 0..OUTLOOK_MONTHS {
+        let next_month_start = first_of_next_month(month_start);
+        let days_in_month = (next_month_start - month_start).num_days();
+
+        let projected_cents = totals
+            .iter()
+            .map(|(category, category_totals)| {
+                category_totals.project_discretionary(days_in_month, observed_days)
+                    + scheduled_total(
+                        *category,
+                        month_start - Duration::days(1),
+                        next_month_start - Duration::days(1),
+                    )
+            })
+            .sum();
+
+        monthly_outlook.push(MonthForecast {
+            month_start,
+            projected_cents,
+        });
+
+        month_start = next_month_start;
+    }
+
+    BudgetForecast {
+        as_of: today,
+        period_end: budget.end_date,
+        limit_cents: if budget.is_tracking_only {
+            None
+        } else {
+            Some(categories.iter().filter_map(|c| c.limit_cents).sum())
+        },
+        spent_cents: categories.iter().map(|c| c.spent_cents).sum(),
+        projected_period_end_cents: categories.iter().map(|c| c.projected_cents).sum(),
+        categories,
+        monthly_outlook,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    use crate::models::category::Category;
+    use crate::models::entry::Entry;
+
+    fn category(id: i16, limit_cents: i64) -> Category {
+        let timestamp = chrono::Utc::now().naive_utc();
+
+        Category {
+            pk: 0,
+            budget_id: Uuid::nil(),
+            is_deleted: false,
+            id,
+            name: format!("Category {}", id),
+            limit_cents,
+            color: String::from("#ffffff"),
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+            is_hard_capped: false,
+        }
+    }
+
+    fn entry(category: Option<i16>, amount_cents: i64, date: NaiveDate) -> Entry {
+        let timestamp = chrono::Utc::now().naive_utc();
+
+        Entry {
+            id: Uuid::new_v4(),
+            budget_id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            is_deleted: false,
+            amount_cents,
+            date,
+            name: None,
+            category,
+            note: None,
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+            recurring_entry_id: None,
+            import_batch_id: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        }
+    }
+
+    fn budget(categories: Vec<Category>, entries: Vec<Entry>) -> OutputBudget {
+        let timestamp = chrono::Utc::now().naive_utc();
+
+        OutputBudget {
+            id: Uuid::nil(),
+            is_shared: false,
+            is_private: true,
+            is_deleted: false,
+            is_tracking_only: false,
+            is_envelope: false,
+            is_archived: false,
+            name: String::from("Test"),
+            description: None,
+            categories,
+            entries,
+            start_date: NaiveDate::from_ymd(2022, 4, 1),
+            end_date: NaiveDate::from_ymd(2022, 4, 30),
+            latest_entry_time: timestamp,
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+        }
+    }
+
+    #[test]
+    fn test_forecast_extrapolates_observed_spending() {
+        let budget = budget(
+            vec![category(0, 60000), category(1, 20000)],
+            vec![
+                entry(Some(0), 10000, NaiveDate::from_ymd(2022, 4, 2)),
+                entry(Some(0), 5000, NaiveDate::from_ymd(2022, 4, 9)),
+                entry(Some(1), 3000, NaiveDate::from_ymd(2022, 4, 5)),
+                entry(None, 1500, NaiveDate::from_ymd(2022, 4, 7)),
+            ],
+        );
+
+        let forecast = forecast_budget(&budget, &[], &[], NaiveDate::from_ymd(2022, 4, 10));
+
+        assert_eq!(forecast.limit_cents, Some(80000));
+        assert_eq!(forecast.spent_cents, 19500);
+
+        // 10 days observed, 20 days remaining
+        assert_eq!(forecast.categories.len(), 3);
+        assert_eq!(forecast.categories[0].category, None);
+        assert_eq!(forecast.categories[0].projected_cents, 4500);
+        assert_eq!(forecast.categories[1].category, Some(0));
+        assert_eq!(forecast.categories[1].projected_cents, 45000);
+        assert_eq!(forecast.categories[2].projected_cents, 9000);
+        assert_eq!(forecast.projected_period_end_cents, 58500);
+
+        assert_eq!(forecast.monthly_outlook.len(), OUTLOOK_MONTHS as usize);
+        assert_eq!(
+            forecast.monthly_outlook[0].month_start,
+            NaiveDate::from_ymd(2022, 5, 1)
+        );
+        // 31 days in May at 1,950 cents per day
+        assert_eq!(forecast.monthly_outlook[0].projected_cents, 60450);
+    }
+
+    #[test]
+    fn test_forecast_applies_adjustments() {
+        let budget = budget(
+            vec![category(0, 60000)],
+            vec![entry(Some(0), 10000, NaiveDate::from_ymd(2022, 4, 10))],
+        );
+
+        let rent = ScheduledExpense {
+            category: None,
+            amount_cents: 40000,
+            frequency: RecurrenceFrequency::Monthly,
+            start_date: NaiveDate::from_ymd(2022, 4, 15),
+            end_date: None,
+            next_occurrence_date: NaiveDate::from_ymd(2022, 4, 15),
+        };
+
+        let adjustments = [
+            Adjustment::ScaleCategory {
+                category: 0,
+                percent_change: -20,
+            },
+            Adjustment::AddScheduledExpense(rent),
+        ];
+
+        let forecast =
+            forecast_budget(&budget, &[], &adjustments, NaiveDate::from_ymd(2022, 4, 10));
+
+        // 20,000 of projected future spending cut by 20%, plus one rent payment
+        assert_eq!(forecast.categories[0].category, None);
+        assert_eq!(forecast.categories[0].projected_cents, 40000);
+        assert_eq!(forecast.categories[1].projected_cents, 26000);
+        assert_eq!(forecast.projected_period_end_cents, 66000);
+
+        // 31 days at 800 cents per day plus rent
+        assert_eq!(forecast.monthly_outlook[0].projected_cents, 64800);
+    }
+
+    #[test]
+    fn test_forecast_tracking_only_budget_has_no_limits() {
+        let mut budget = budget(
+            vec![category(0, 60000)],
+            vec![entry(Some(0), 10000, NaiveDate::from_ymd(2022, 4, 2))],
+        );
+        budget.is_tracking_only = true;
+
+        let forecast = forecast_budget(&budget, &[], &[], NaiveDate::from_ymd(2022, 4, 10));
+
+        assert_eq!(forecast.limit_cents, None);
+        assert_eq!(forecast.spent_cents, 10000);
+        assert!(forecast.categories.iter().all(|c| c.limit_cents.is_none()));
+    }
+
+    #[test]
+    fn test_forecast_before_period_starts() {
+        let budget = budget(vec![category(0, 60000)], Vec::new());
+
+        let groceries = ScheduledExpense {
+            category: Some(0),
+            amount_cents: 5000,
+            frequency: RecurrenceFrequency::Weekly,
+            start_date: NaiveDate::from_ymd(2022, 3, 4),
+            end_date: Some(NaiveDate::from_ymd(2022, 4, 15)),
+            next_occurrence_date: NaiveDate::from_ymd(2022, 3, 25),
+        };
+
+        let forecast =
+            forecast_budget(&budget, &[groceries], &[], NaiveDate::from_ymd(2022, 3, 20));
+
+        // Occurs on Apr 1, 8, and 15 within the period
+        assert_eq!(forecast.spent_cents, 0);
+        assert_eq!(forecast.projected_period_end_cents, 15000);
+        assert_eq!(forecast.monthly_outlook[0].projected_cents, 15000);
+        assert_eq!(forecast.monthly_outlook[1].projected_cents, 0);
+    }
+}
@@ -0,0 +1,466 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::handlers::request_io::InputColumnMapping;
+use crate::models::entry::NewEntry;
+use crate::utils::record_id;
+
+// Turns bank statement files into entries. Every bank lays out its CSV exports differently, so
+// the client says which columns hold what. OFX files always use the same fields.
+//
+// Rows that can't be read are skipped rather than failing the whole file, and are reported back
+// with the 1-based number of the transaction in the file (not counting a CSV header row).
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatementFormat {
+    Csv,
+    Ofx,
+}
+
+impl StatementFormat {
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let extension = file_name.rsplit_once('.')?.1;
+
+        if extension.eq_ignore_ascii_case("csv") {
+            Some(StatementFormat::Csv)
+        } else if extension.eq_ignore_ascii_case("ofx") || extension.eq_ignore_ascii_case("qfx") {
+            Some(StatementFormat::Ofx)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Unreadable(&'static str),
+    MissingColumn(String),
+}
+
+impl std::error::Error for ImportError {}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Unreadable(msg) => write!(f, "Unreadable: {}", msg),
+            ImportError::MissingColumn(column) => write!(f, "MissingColumn: {}", column),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatementRow {
+    pub row: usize,
+    pub date: NaiveDate,
+    pub amount_cents: i64,
+    pub name: Option<String>,
+    pub note: Option<String>,
+}
+
+impl StatementRow {
+    // Two rows with the same key are taken to be the same transaction
+    pub fn dedup_key(&self) -> (NaiveDate, i64, Option<&str>) {
+        (self.date, self.amount_cents, self.name.as_deref())
+    }
+
+    pub fn to_new_entry(
+        &self,
+        budget_id: Uuid,
+        user_id: Uuid,
+        import_batch_id: Uuid,
+        timestamp: NaiveDateTime,
+    ) -> NewEntry<'_> {
+        NewEntry {
+            id: record_id::generate(),
+            budget_id,
+            user_id,
+            is_deleted: false,
+            amount_cents: self.amount_cents,
+            date: self.date,
+            name: self.name.as_deref(),
+            category: None,
+            note: self.note.as_deref(),
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+            recurring_entry_id: None,
+            import_batch_id: Some(import_batch_id),
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedStatement {
+    pub rows: Vec<StatementRow>,
+    pub skipped_rows: Vec<SkippedRow>,
+}
+
+pub fn parse_statement(
+    format: StatementFormat,
+    data: &[u8],
+    mapping: Option<&InputColumnMapping>,
+) -> Result<ParsedStatement, ImportError> {
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+
+    match format {
+        StatementFormat::Csv => match mapping {
+            Some(m) => parse_csv(data, m),
+            None => Err(ImportError::Unreadable(
+                "A column mapping is required for CSV files",
+            )),
+        },
+        StatementFormat::Ofx => parse_ofx(data),
+    }
+}
+
+fn parse_csv(data: &[u8], mapping: &InputColumnMapping) -> Result<ParsedStatement, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    let headers = match reader.headers() {
+        Ok(h) => h.clone(),
+        Err(_) => {
+            return Err(ImportError::Unreadable(
+                "The file's header row can't be read",
+            ))
+        }
+    };
+
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| ImportError::MissingColumn(String::from(name)))
+    };
+
+    let date_column = column(&mapping.date_column)?;
+    let amount_column = column(&mapping.amount_column)?;
+    let name_column = mapping.name_column.as_deref().map(column).transpose()?;
+    let note_column = mapping.note_column.as_deref().map(column).transpose()?;
+
+    let mut statement = ParsedStatement::default();
+
+    for (i, record) in reader.records().enumerate() {
+        let row = i + 1;
+
+        let skip = |reason| SkippedRow { row, reason };
+
+        let record = match record {
+            Ok(r) => r,
+            Err(_) => {
+                statement.skipped_rows.push(skip("Row can't be read"));
+                continue;
+            }
+        };
+
+        let date = match record
+            .get(date_column)
+            .and_then(|d| NaiveDate::parse_from_str(d, &mapping.date_format).ok())
+        {
+            Some(d) => d,
+            None => {
+                statement.skipped_rows.push(skip("Invalid date"));
+                continue;
+            }
+        };
+
+        let amount_cents = match record.get(amount_column).and_then(parse_amount_cents) {
+            Some(a) if mapping.negate_amounts => -a,
+            Some(a) => a,
+            None => {
+                statement.skipped_rows.push(skip("Invalid amount"));
+                continue;
+            }
+        };
+
+        let text_field =
+            |column: Option<usize>| non_empty(column.and_then(|c| record.get(c)).unwrap_or(""));
+
+        statement.rows.push(StatementRow {
+            row,
+            date,
+            amount_cents,
+            name: text_field(name_column),
+            note: text_field(note_column),
+        });
+    }
+
+    Ok(statement)
+}
+
+// Handles both SGML-style OFX 1.x files, where a field's closing tag is optional, and XML-style
+// OFX 2.x files. Money leaving the account is negative in OFX, so amounts are negated to record
+// it as spending.
+fn parse_ofx(data: &[u8]) -> Result<ParsedStatement, ImportError> {
+    let text = String::from_utf8_lossy(data);
+
+    let transactions = text.split("<STMTTRN>").skip(1).collect::<Vec<_>>();
+
+    if transactions.is_empty() {
+        return Err(ImportError::Unreadable(
+            "The file doesn't contain any transactions",
+        ));
+    }
+
+    let mut statement = ParsedStatement::default();
+
+    for (i, transaction) in transactions.into_iter().enumerate() {
+        let row = i + 1;
+
+        let transaction = transaction
+            .split_once("</STMTTRN>")
+            .map_or(transaction, |(t, _)| t);
+
+        let skip = |reason| SkippedRow { row, reason };
+
+        // Dates look like 20220601, optionally followed by a time and time zone
+        let date = match ofx_field(transaction, "DTPOSTED")
+            .and_then(|d| d.get(..8))
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+        {
+            Some(d) => d,
+            None => {
+                statement.skipped_rows.push(skip("Invalid date"));
+                continue;
+            }
+        };
+
+        let amount_cents = match ofx_field(transaction, "TRNAMT").and_then(parse_amount_cents) {
+            Some(a) => -a,
+            None => {
+                statement.skipped_rows.push(skip("Invalid amount"));
+                continue;
+            }
+        };
+
+        statement.rows.push(StatementRow {
+            row,
+            date,
+            amount_cents,
+            name: ofx_field(transaction, "NAME").and_then(non_empty),
+            note: ofx_field(transaction, "MEMO").and_then(non_empty),
+        });
+    }
+
+    Ok(statement)
+}
+
+// A field's value runs from its opening tag to the next tag
+fn ofx_field<'a>(transaction: &'a str, tag: &str) -> Option<&'a str> {
+    let start = transaction.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let value = &transaction[start..];
+    let end = value.find('<').unwrap_or(value.len());
+
+    Some(value[..end].trim())
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(String::from(text))
+    }
+}
+
+// Accepts amounts written the ways banks tend to write them, e.g. "1,234.56", "-12.00", "$12" or
+// "(12.00)"
+pub fn parse_amount_cents(amount: &str) -> Option<i64> {
+    let amount = amount
+        .chars()
+        .filter(|c| !matches!(c, ',' | '$' | '€' | '£' | '¥') && !c.is_whitespace())
+        .collect::<String>();
+
+    let (is_negative, amount) =
+        if let Some(a) = amount.strip_prefix('(').and_then(|a| a.strip_suffix(')')) {
+            (true, a)
+        } else if let Some(a) = amount.strip_prefix('-') {
+            (true, a)
+        } else {
+            (false, amount.strip_prefix('+').unwrap_or(&amount))
+        };
+
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > 2
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<i64>().ok()?
+    };
+    let fraction = format!("{:0<2}", fraction).parse::<i64>().ok()?;
+
+    let cents = whole.checked_mul(100)?.checked_add(fraction)?;
+
+    Some(if is_negative { -cents } else { cents })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping() -> InputColumnMapping {
+        InputColumnMapping {
+            date_column: String::from("Date"),
+            amount_column: String::from("Amount"),
+            name_column: Some(String::from("Description")),
+            note_column: None,
+            date_format: String::from("%m/%d/%Y"),
+            negate_amounts: true,
+        }
+    }
+
+    #[test]
+    fn test_parse_amount_cents() {
+        assert_eq!(parse_amount_cents("12.34"), Some(1234));
+        assert_eq!(parse_amount_cents("-12.3"), Some(-1230));
+        assert_eq!(parse_amount_cents("$1,234"), Some(123400));
+        assert_eq!(parse_amount_cents("(5.00)"), Some(-500));
+        assert_eq!(parse_amount_cents("+.5"), Some(50));
+        assert_eq!(parse_amount_cents(" - 7.01 "), Some(-701));
+
+        assert_eq!(parse_amount_cents(""), None);
+        assert_eq!(parse_amount_cents("."), None);
+        assert_eq!(parse_amount_cents("1.234"), None);
+        assert_eq!(parse_amount_cents("12a"), None);
+        assert_eq!(parse_amount_cents("1.2.3"), None);
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let data = "\u{feff}Date,Description,Amount,Balance\n\
+                    06/01/2022,Grocer,-45.10,100.00\n\
+                    06/02/2022, Paycheck ,\"1,200.00\",1300.00\n\
+                    not a date,Coffee,-3.50,1296.50\n\
+                    06/04/2022,,oops,1296.50\n\
+                    06/05/2022,Coffee,-3.50\n";
+
+        let statement =
+            parse_statement(StatementFormat::Csv, data.as_bytes(), Some(&mapping())).unwrap();
+
+        assert_eq!(
+            statement.rows,
+            vec![
+                StatementRow {
+                    row: 1,
+                    date: NaiveDate::from_ymd(2022, 6, 1),
+                    amount_cents: 4510,
+                    name: Some(String::from("Grocer")),
+                    note: None,
+                },
+                StatementRow {
+                    row: 2,
+                    date: NaiveDate::from_ymd(2022, 6, 2),
+                    amount_cents: -120000,
+                    name: Some(String::from("Paycheck")),
+                    note: None,
+                },
+                StatementRow {
+                    row: 5,
+                    date: NaiveDate::from_ymd(2022, 6, 5),
+                    amount_cents: 350,
+                    name: Some(String::from("Coffee")),
+                    note: None,
+                },
+            ]
+        );
+        assert_eq!(
+            statement.skipped_rows,
+            vec![
+                SkippedRow {
+                    row: 3,
+                    reason: "Invalid date",
+                },
+                SkippedRow {
+                    row: 4,
+                    reason: "Invalid amount",
+                },
+            ]
+        );
+
+        let mut missing_column = mapping();
+        missing_column.note_column = Some(String::from("Memo"));
+        assert!(matches!(
+            parse_statement(StatementFormat::Csv, data.as_bytes(), Some(&missing_column)),
+            Err(ImportError::MissingColumn(c)) if c == "Memo"
+        ));
+
+        assert!(parse_statement(StatementFormat::Csv, data.as_bytes(), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_ofx() {
+        let sgml = "OFXHEADER:100\nDATA:OFXSGML\n\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>\
+                    <BANKTRANLIST>\n\
+                    <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20220601120000[-5:EST]\n\
+                    <TRNAMT>-45.10\n<FITID>1\n<NAME>Grocer\n<MEMO>Weekly shop\n</STMTTRN>\n\
+                    <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>2022\n<TRNAMT>-1.00\n</STMTTRN>\n\
+                    </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+        let xml = "<?xml version=\"1.0\"?><OFX><BANKTRANLIST>\
+                   <STMTTRN><TRNTYPE>CREDIT</TRNTYPE><DTPOSTED>20220602</DTPOSTED>\
+                   <TRNAMT>1200.00</TRNAMT><NAME>Paycheck</NAME></STMTTRN>\
+                   </BANKTRANLIST></OFX>";
+
+        let statement = parse_statement(StatementFormat::Ofx, sgml.as_bytes(), None).unwrap();
+        assert_eq!(
+            statement.rows,
+            vec![StatementRow {
+                row: 1,
+                date: NaiveDate::from_ymd(2022, 6, 1),
+                amount_cents: 4510,
+                name: Some(String::from("Grocer")),
+                note: Some(String::from("Weekly shop")),
+            }]
+        );
+        assert_eq!(
+            statement.skipped_rows,
+            vec![SkippedRow {
+                row: 2,
+                reason: "Invalid date",
+            }]
+        );
+
+        let statement = parse_statement(StatementFormat::Ofx, xml.as_bytes(), None).unwrap();
+        assert_eq!(statement.rows.len(), 1);
+        assert_eq!(statement.rows[0].amount_cents, -120000);
+        assert_eq!(statement.rows[0].name.as_deref(), Some("Paycheck"));
+
+        assert!(parse_statement(StatementFormat::Ofx, b"<OFX></OFX>", None).is_err());
+    }
+
+    #[test]
+    fn test_statement_format_from_file_name() {
+        assert_eq!(
+            StatementFormat::from_file_name("June.CSV"),
+            Some(StatementFormat::Csv)
+        );
+        assert_eq!(
+            StatementFormat::from_file_name("june.qfx"),
+            Some(StatementFormat::Ofx)
+        );
+        assert_eq!(StatementFormat::from_file_name("june.pdf"), None);
+        assert_eq!(StatementFormat::from_file_name("csv"), None);
+    }
+}
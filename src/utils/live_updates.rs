@@ -0,0 +1,84 @@
+use actix_web::web;
+use log::error;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::definitions::DbThreadPool;
+use crate::models::budget_comment::BudgetComment;
+use crate::models::entry::Entry;
+use crate::utils::db;
+
+// A subscriber that falls this far behind misses the oldest updates it hasn't received
+const BUS_CAPACITY: usize = 1024;
+
+lazy_static! {
+    static ref BUS: broadcast::Sender<BudgetUpdate> = broadcast::channel(BUS_CAPACITY).0;
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BudgetEvent {
+    EntryAdded { entry: Entry },
+    CommentPosted { comment: BudgetComment },
+    ShareAccepted { user_id: Uuid },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BudgetUpdate {
+    pub budget_id: Uuid,
+    #[serde(flatten)]
+    pub event: BudgetEvent,
+
+    // The budget's members when the event happened, who are the only users it gets sent to
+    #[serde(skip)]
+    recipients: Arc<Vec<Uuid>>,
+}
+
+impl BudgetUpdate {
+    pub fn is_for(&self, user_id: Uuid) -> bool {
+        self.recipients.contains(&user_id)
+    }
+}
+
+pub fn subscribe() -> broadcast::Receiver<BudgetUpdate> {
+    BUS.subscribe()
+}
+
+// Sends an event to whichever of the budget's members are subscribed to this server. The members
+// are looked up in the background so the caller's response isn't held up, and aren't looked up at
+// all when nobody is subscribed.
+pub fn publish(db_thread_pool: web::Data<DbThreadPool>, budget_id: Uuid, event: BudgetEvent) {
+    if BUS.receiver_count() == 0 {
+        return;
+    }
+
+    actix_web::rt::spawn(async move {
+        let recipients = match web::block(move || {
+            let db_connection = db_thread_pool
+                .get()
+                .expect("Failed to access database thread pool");
+            db::budget::get_budget_member_ids(&db_connection, budget_id)
+        })
+        .await
+        {
+            Ok(Ok(r)) => r,
+            Ok(Err(e)) => {
+                error!("Failed to get recipients for budget update: {}", e);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to get recipients for budget update: {}", e);
+                return;
+            }
+        };
+
+        // Sending only fails if every subscriber has disconnected since the check above
+        let _ = BUS.send(BudgetUpdate {
+            budget_id,
+            event,
+            recipients: Arc::new(recipients),
+        });
+    });
+}
@@ -0,0 +1,435 @@
+use actix_web::error::BlockingError;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+use crate::env;
+use crate::utils::trace_context::TraceContext;
+
+// Log records are filtered by the module that wrote them. Each named module covers a group of log
+// targets and can be given its own level. Everything else, including dependencies, logs at the
+// default level. Levels can be changed while the server runs, but only on the instance that
+// receives the change.
+pub const LOG_MODULES: [(&str, &[&str]); 5] = [
+    (
+        "auth",
+        &[
+            "budgetapp_server::handlers::auth",
+            "budgetapp_server::middleware::api_key",
+            "budgetapp_server::middleware::auth",
+            "budgetapp_server::middleware::internal_service",
+            "budgetapp_server::utils::auth_token",
+            "budgetapp_server::utils::otp",
+            "budgetapp_server::utils::password_hasher",
+        ],
+    ),
+    ("db", &["budgetapp_server::utils::db"]),
+    ("jobs", &["budgetapp_server::cron"]),
+    ("mail", &[MAIL_LOG_TARGET]),
+    ("requests", &[REQUEST_LOG_TARGET]),
+];
+
+// Messages that will be emailed to users once the server sends email
+pub const MAIL_LOG_TARGET: &str = "budgetapp_server::mail";
+
+// One record per request handled, written by the request logging middleware
+pub const REQUEST_LOG_TARGET: &str = "budgetapp_server::requests";
+
+// What identifies the request being handled in its log records
+#[derive(Clone, Copy, Debug)]
+pub struct RequestScope {
+    pub request_id: Uuid,
+    pub trace_context: TraceContext,
+}
+
+tokio::task_local! {
+    static REQUEST_SCOPE: RequestScope;
+}
+
+thread_local! {
+    static BLOCKING_REQUEST_SCOPE: Cell<Option<RequestScope>> = const { Cell::new(None) };
+}
+
+lazy_static! {
+    static ref LEVELS: RwLock<LogLevels> = RwLock::new(configured_levels().unwrap_or_else(|e| {
+        eprintln!("Invalid logging config: {}", e);
+        std::process::exit(1);
+    }));
+}
+
+#[derive(Debug)]
+pub enum LoggingError {
+    InvalidLevel(String),
+    UnknownModule(String),
+}
+
+impl std::error::Error for LoggingError {}
+
+impl fmt::Display for LoggingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoggingError::InvalidLevel(level) => write!(f, "Invalid log level: {}", level),
+            LoggingError::UnknownModule(module) => write!(f, "Unknown log module: {}", module),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LogLevels {
+    pub default_level: LevelFilter,
+    // Modules without a level of their own log at the default level
+    pub module_levels: BTreeMap<&'static str, LevelFilter>,
+}
+
+impl LogLevels {
+    pub fn parse(
+        default_level: &str,
+        module_levels: &BTreeMap<String, String>,
+    ) -> Result<Self, LoggingError> {
+        let mut levels = LogLevels {
+            default_level: LevelFilter::Info,
+            module_levels: BTreeMap::new(),
+        };
+
+        levels.update(Some(default_level), module_levels)?;
+
+        Ok(levels)
+    }
+
+    // Nothing is changed unless every level is valid. Modules left out keep their current level.
+    pub fn update(
+        &mut self,
+        default_level: Option<&str>,
+        module_levels: &BTreeMap<String, String>,
+    ) -> Result<(), LoggingError> {
+        let default_level = default_level.map(parse_level).transpose()?;
+        let mut parsed = Vec::with_capacity(module_levels.len());
+
+        for (module, level) in module_levels {
+            let module = LOG_MODULES
+                .iter()
+                .map(|(name, _)| *name)
+                .find(|name| name.eq_ignore_ascii_case(module.trim()))
+                .ok_or_else(|| LoggingError::UnknownModule(module.clone()))?;
+
+            parsed.push((module, parse_level(level)?));
+        }
+
+        if let Some(default_level) = default_level {
+            self.default_level = default_level;
+        }
+
+        self.module_levels.extend(parsed);
+
+        Ok(())
+    }
+
+    pub fn level_for_module(&self, module: &str) -> LevelFilter {
+        self.module_levels
+            .get(module)
+            .copied()
+            .unwrap_or(self.default_level)
+    }
+
+    pub fn level_for_target(&self, target: &str) -> LevelFilter {
+        LOG_MODULES
+            .iter()
+            .find(|(_, targets)| {
+                targets.iter().any(|t| {
+                    target
+                        .strip_prefix(t)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+                })
+            })
+            .map(|(module, _)| self.level_for_module(module))
+            .unwrap_or(self.default_level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.module_levels
+            .values()
+            .copied()
+            .fold(self.default_level, Ord::max)
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, LoggingError> {
+    LevelFilter::from_str(level.trim()).map_err(|_| LoggingError::InvalidLevel(level.to_string()))
+}
+
+pub fn configured_levels() -> Result<LogLevels, LoggingError> {
+    LogLevels::parse(
+        &env::CONF.logging.default_level,
+        &env::CONF.logging.module_levels,
+    )
+}
+
+// Runs a request's future with its correlation id and trace attached to everything logged while it
+// is polled. Work moved off the task with `web::block` doesn't carry them, but work moved off with
+// `block` does.
+pub async fn with_request_scope<F: Future>(scope: RequestScope, f: F) -> F::Output {
+    REQUEST_SCOPE.scope(scope, f).await
+}
+
+// Like `web::block`, but the closure runs in the scope of the request being handled, so what it
+// logs and the pushes it queues are tied to the request
+pub async fn block<F, R>(f: F) -> Result<R, BlockingError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let scope = current_request_scope();
+
+    actix_web::web::block(move || {
+        let _scope_guard = BlockingScopeGuard::enter(scope);
+        f()
+    })
+    .await
+}
+
+pub fn current_request_scope() -> Option<RequestScope> {
+    REQUEST_SCOPE
+        .try_with(|scope| *scope)
+        .ok()
+        .or_else(|| BLOCKING_REQUEST_SCOPE.with(Cell::get))
+}
+
+// Blocking threads are reused, so the scope is cleared when the closure finishes, even if it panics
+struct BlockingScopeGuard;
+
+impl BlockingScopeGuard {
+    fn enter(scope: Option<RequestScope>) -> Self {
+        BLOCKING_REQUEST_SCOPE.with(|s| s.set(scope));
+        Self
+    }
+}
+
+impl Drop for BlockingScopeGuard {
+    fn drop(&mut self) {
+        BLOCKING_REQUEST_SCOPE.with(|s| s.set(None));
+    }
+}
+
+fn write_record(
+    out: &mut impl Write,
+    timestamp: &dyn fmt::Display,
+    record: &Record,
+    scope: Option<RequestScope>,
+    json_output: bool,
+) -> io::Result<()> {
+    if json_output {
+        let mut line = serde_json::json!({
+            "timestamp": timestamp.to_string(),
+            "level": record.level().as_str(),
+            "target": record.target(),
+            "message": record.args().to_string(),
+        });
+
+        if let Some(scope) = scope {
+            line["request_id"] = serde_json::Value::String(scope.request_id.to_string());
+            line["trace_id"] = serde_json::Value::String(scope.trace_context.trace_id_hex());
+        }
+
+        return writeln!(out, "{}", line);
+    }
+
+    match scope {
+        Some(scope) => writeln!(
+            out,
+            "[{} {} {}] [{}] {}",
+            timestamp,
+            record.level(),
+            record.target(),
+            scope.request_id,
+            record.args()
+        ),
+        None => writeln!(
+            out,
+            "[{} {} {}] {}",
+            timestamp,
+            record.level(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
+
+struct ModuleFilteredLogger {
+    inner: env_logger::Logger,
+}
+
+impl Log for ModuleFilteredLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let levels = LEVELS.read().expect("Log levels lock was poisoned");
+        metadata.level() <= levels.level_for_target(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+pub fn initialize() {
+    let json_output = env::CONF.logging.json_output;
+
+    // The inner logger only formats and writes. Filtering is left to the levels above.
+    let inner = env_logger::Builder::new()
+        .filter_level(LevelFilter::Trace)
+        .format(move |buf, record| {
+            let timestamp = buf.timestamp();
+            write_record(
+                buf,
+                &timestamp,
+                record,
+                current_request_scope(),
+                json_output,
+            )
+        })
+        .build();
+
+    log::set_max_level(current_levels().max_level());
+
+    if log::set_boxed_logger(Box::new(ModuleFilteredLogger { inner })).is_err() {
+        eprintln!("A logger was already initialized");
+        std::process::exit(1);
+    }
+}
+
+pub fn current_levels() -> LogLevels {
+    LEVELS.read().expect("Log levels lock was poisoned").clone()
+}
+
+pub fn set_levels(levels: LogLevels) {
+    let mut current = LEVELS.write().expect("Log levels lock was poisoned");
+
+    // Records above the max level are dropped by the log macros before reaching the logger
+    log::set_max_level(levels.max_level());
+    *current = levels;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_for_target() {
+        let levels = LogLevels::parse(
+            "warn",
+            &BTreeMap::from([
+                (String::from("Auth"), String::from("debug")),
+                (String::from("mail"), String::from("OFF")),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(levels.level_for_module("auth"), LevelFilter::Debug);
+        assert_eq!(levels.level_for_module("db"), LevelFilter::Warn);
+        assert_eq!(levels.max_level(), LevelFilter::Debug);
+
+        assert_eq!(
+            levels.level_for_target("budgetapp_server::handlers::auth"),
+            LevelFilter::Debug
+        );
+        assert_eq!(
+            levels.level_for_target("budgetapp_server::utils::db::session"),
+            LevelFilter::Warn
+        );
+        assert_eq!(levels.level_for_target(MAIL_LOG_TARGET), LevelFilter::Off);
+
+        // Only whole module paths match
+        assert_eq!(
+            levels.level_for_target("budgetapp_server::handlers::authorization"),
+            LevelFilter::Warn
+        );
+        assert_eq!(
+            levels.level_for_target("actix_web::middleware"),
+            LevelFilter::Warn
+        );
+
+        assert!(matches!(
+            LogLevels::parse("loud", &BTreeMap::new()),
+            Err(LoggingError::InvalidLevel(_))
+        ));
+        assert!(matches!(
+            LogLevels::parse(
+                "info",
+                &BTreeMap::from([(String::from("billing"), String::from("debug"))])
+            ),
+            Err(LoggingError::UnknownModule(_))
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_block_keeps_request_scope() {
+        let scope = RequestScope {
+            request_id: Uuid::new_v4(),
+            trace_context: TraceContext::new_root(),
+        };
+
+        let blocking_scope =
+            with_request_scope(scope, async { block(current_request_scope).await.unwrap() }).await;
+        assert_eq!(blocking_scope.map(|s| s.request_id), Some(scope.request_id));
+
+        // Nothing is left behind on the blocking thread
+        assert!(block(current_request_scope).await.unwrap().is_none());
+        assert!(actix_web::web::block(current_request_scope)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_write_record() {
+        let scope = RequestScope {
+            request_id: Uuid::new_v4(),
+            trace_context: TraceContext::new_root(),
+        };
+        let args = format_args!("Failed to get budget");
+        let record = Record::builder()
+            .args(args)
+            .level(log::Level::Error)
+            .target("budgetapp_server::handlers")
+            .build();
+
+        let mut out = Vec::new();
+        write_record(&mut out, &"2022-06-15T12:00:00Z", &record, None, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[2022-06-15T12:00:00Z ERROR budgetapp_server::handlers] Failed to get budget\n"
+        );
+
+        let mut out = Vec::new();
+        write_record(
+            &mut out,
+            &"2022-06-15T12:00:00Z",
+            &record,
+            Some(scope),
+            true,
+        )
+        .unwrap();
+        let line = serde_json::from_slice::<serde_json::Value>(&out).unwrap();
+        assert_eq!(line["level"], "ERROR");
+        assert_eq!(line["target"], "budgetapp_server::handlers");
+        assert_eq!(line["message"], "Failed to get budget");
+        assert_eq!(line["request_id"], scope.request_id.to_string());
+        assert_eq!(line["trace_id"], scope.trace_context.trace_id_hex());
+
+        let mut out = Vec::new();
+        write_record(&mut out, &"2022-06-15T12:00:00Z", &record, None, true).unwrap();
+        let line = serde_json::from_slice::<serde_json::Value>(&out).unwrap();
+        assert!(line.get("request_id").is_none());
+        assert!(line.get("trace_id").is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use futures::future::BoxFuture;
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::env::{self, HttpMail, MailerKind};
+use crate::utils::logging;
+use crate::utils::trace_context::TRACEPARENT_HEADER;
+
+lazy_static! {
+    pub static ref MAILER: Box<dyn Mailer> = configured_mailer();
+}
+
+#[derive(Debug)]
+pub enum MailError {
+    DeliveryFailure(String),
+}
+
+impl std::error::Error for MailError {}
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MailError::DeliveryFailure(msg) => write!(f, "Mail delivery failure: {}", msg),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+fn configured_mailer() -> Box<dyn Mailer> {
+    match env::CONF.mail.backend {
+        MailerKind::Directory => Box::new(DirectoryMailer::new(&env::CONF.mail.directory)),
+        MailerKind::Http => Box::new(HttpMailer::new(
+            env::CONF
+                .mail
+                .http
+                .as_ref()
+                .expect("The http mail backend requires a [mail.http] section"),
+        )),
+    }
+}
+
+// Sends plain-text email to users. Messages may carry secrets such as password reset tokens, so
+// they must never be logged.
+pub trait Mailer: Send + Sync {
+    fn send(&self, message: MailMessage) -> BoxFuture<'static, Result<(), MailError>>;
+}
+
+// Writes each message to a file under a directory named for the recipient instead of sending it.
+// Meant for development and tests, where the directory stands in for the user's inbox.
+pub struct DirectoryMailer {
+    directory: PathBuf,
+}
+
+impl DirectoryMailer {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+        }
+    }
+}
+
+impl Mailer for DirectoryMailer {
+    fn send(&self, message: MailMessage) -> BoxFuture<'static, Result<(), MailError>> {
+        let inbox = self.directory.join(&message.to);
+        let path = inbox.join(format!("{}.txt", uuid::Uuid::new_v4()));
+        let contents = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            env::CONF.mail.from_address,
+            message.to,
+            message.subject,
+            message.body,
+        );
+
+        Box::pin(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                std::fs::create_dir_all(&inbox)?;
+                std::fs::write(&path, contents)
+            })
+            .await;
+
+            match result {
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => Err(MailError::DeliveryFailure(e.to_string())),
+                Err(e) => Err(MailError::DeliveryFailure(e.to_string())),
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct HttpMailRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    subject: &'a str,
+    text: &'a str,
+}
+
+// Sends through a mail provider's HTTP API, which takes a JSON body with `from`, `to`, `subject`
+// and `text` fields and a bearer API key
+pub struct HttpMailer {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+}
+
+impl HttpMailer {
+    pub fn new(conf: &HttpMail) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: conf.endpoint.clone(),
+            api_key: conf.api_key.clone(),
+        }
+    }
+}
+
+impl Mailer for HttpMailer {
+    fn send(&self, message: MailMessage) -> BoxFuture<'static, Result<(), MailError>> {
+        let request = self
+            .client
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .json(&HttpMailRequest {
+                from: &env::CONF.mail.from_address,
+                to: &message.to,
+                subject: &message.subject,
+                text: &message.body,
+            });
+
+        let request = match logging::current_request_scope() {
+            Some(scope) => request.header(TRACEPARENT_HEADER, scope.trace_context.traceparent()),
+            None => request,
+        };
+
+        Box::pin(async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| MailError::DeliveryFailure(e.to_string()))?;
+
+            if response.status().is_success() {
+                Ok(())
+            } else {
+                Err(MailError::DeliveryFailure(format!(
+                    "Mail provider responded with {}",
+                    response.status()
+                )))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::path::Path;
+
+    use crate::env;
+
+    // The messages the directory mailer has written for a recipient, oldest first
+    pub fn read_inbox(to: &str) -> Vec<String> {
+        let inbox = Path::new(&env::CONF.mail.directory).join(to);
+
+        let mut messages = match std::fs::read_dir(inbox) {
+            Ok(entries) => entries
+                .map(|entry| {
+                    let path = entry.unwrap().path();
+                    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+                    (modified, std::fs::read_to_string(&path).unwrap())
+                })
+                .collect::<Vec<_>>(),
+            Err(_) => Vec::new(),
+        };
+
+        messages.sort_by_key(|(modified, _)| *modified);
+        messages.into_iter().map(|(_, m)| m).collect()
+    }
+}
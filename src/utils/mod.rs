@@ -1,7 +1,32 @@
+pub mod app_version;
 pub mod argon2;
+pub mod attempt_counter;
 pub mod auth_token;
+pub mod aws_signature;
+pub mod benchmarking;
 pub mod common_password_set;
+pub mod confirmation_token;
+pub mod currency;
 pub mod db;
+pub mod email_domain_set;
+pub mod engagement;
+pub mod error_reporting;
+pub mod fiscal_calendar;
+pub mod forecasting;
+pub mod import;
+pub mod live_updates;
+pub mod logging;
+pub mod mail;
+pub mod notification;
 pub mod otp;
 pub mod password_hasher;
+pub mod push;
+pub mod record_id;
+pub mod recurrence;
+pub mod secrets;
+pub mod span_export;
+pub mod storage;
+pub mod subscription_detection;
+pub mod trace_context;
+pub mod unusual_amount;
 pub mod validators;
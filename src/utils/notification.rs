@@ -0,0 +1,215 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+
+// Stored in user_notifications.notification_type
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NotificationType {
+    ChallengeEnded,
+    BudgetInvitation,
+    BudgetComment,
+    CommentReaction,
+    Reminder,
+    RemovedFromBudget,
+    MemberLeftBudget,
+    MonthClosed,
+}
+
+// The contents of user_notifications.associated_data for each notification type
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChallengeEndedData {
+    pub challenge_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BudgetInvitationData {
+    pub share_event_id: Uuid,
+    pub budget_id: Uuid,
+    pub sharer_user_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BudgetCommentData {
+    pub budget_id: Uuid,
+    pub comment_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CommentReactionData {
+    pub comment_id: Uuid,
+    pub reaction_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ReminderData {
+    pub reminder_id: Uuid,
+    pub budget_id: Option<Uuid>,
+    pub entry_id: Option<Uuid>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RemovedFromBudgetData {
+    pub budget_id: Uuid,
+    pub remover_user_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MemberLeftBudgetData {
+    pub budget_id: Uuid,
+    pub member_user_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MonthClosedData {
+    pub budget_id: Uuid,
+    pub month: NaiveDate,
+    pub closer_user_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationData {
+    ChallengeEnded(ChallengeEndedData),
+    BudgetInvitation(BudgetInvitationData),
+    BudgetComment(BudgetCommentData),
+    CommentReaction(CommentReactionData),
+    Reminder(ReminderData),
+    RemovedFromBudget(RemovedFromBudgetData),
+    MemberLeftBudget(MemberLeftBudgetData),
+    MonthClosed(MonthClosedData),
+}
+
+impl NotificationData {
+    // The associated data is stored untagged, so the notification type determines how it is read
+    pub fn parse(
+        notification_type: i16,
+        associated_data: &str,
+    ) -> Result<NotificationData, NotificationTypeError> {
+        let parse_error = |e: serde_json::Error| NotificationTypeError::InvalidData(e.to_string());
+
+        let data = match NotificationType::try_from(notification_type)? {
+            NotificationType::ChallengeEnded => NotificationData::ChallengeEnded(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::BudgetInvitation => NotificationData::BudgetInvitation(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::BudgetComment => NotificationData::BudgetComment(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::CommentReaction => NotificationData::CommentReaction(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::Reminder => NotificationData::Reminder(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::RemovedFromBudget => NotificationData::RemovedFromBudget(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::MemberLeftBudget => NotificationData::MemberLeftBudget(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+            NotificationType::MonthClosed => NotificationData::MonthClosed(
+                serde_json::from_str(associated_data).map_err(parse_error)?,
+            ),
+        };
+
+        Ok(data)
+    }
+}
+
+#[derive(Debug)]
+pub enum NotificationTypeError {
+    NoMatchForValue(i16),
+    InvalidData(String),
+}
+
+impl std::error::Error for NotificationTypeError {}
+
+impl fmt::Display for NotificationTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationTypeError::NoMatchForValue(v) => write!(f, "NoMatchForValue: {}", v),
+            NotificationTypeError::InvalidData(e) => write!(f, "InvalidData: {}", e),
+        }
+    }
+}
+
+impl std::convert::TryFrom<i16> for NotificationType {
+    type Error = NotificationTypeError;
+
+    fn try_from(value: i16) -> Result<Self, NotificationTypeError> {
+        match value {
+            0 => Ok(NotificationType::ChallengeEnded),
+            1 => Ok(NotificationType::BudgetInvitation),
+            2 => Ok(NotificationType::BudgetComment),
+            3 => Ok(NotificationType::CommentReaction),
+            4 => Ok(NotificationType::Reminder),
+            5 => Ok(NotificationType::RemovedFromBudget),
+            6 => Ok(NotificationType::MemberLeftBudget),
+            7 => Ok(NotificationType::MonthClosed),
+            v => Err(NotificationTypeError::NoMatchForValue(v)),
+        }
+    }
+}
+
+impl std::convert::From<NotificationType> for i16 {
+    fn from(notification_type: NotificationType) -> Self {
+        match notification_type {
+            NotificationType::ChallengeEnded => 0,
+            NotificationType::BudgetInvitation => 1,
+            NotificationType::BudgetComment => 2,
+            NotificationType::CommentReaction => 3,
+            NotificationType::Reminder => 4,
+            NotificationType::RemovedFromBudget => 5,
+            NotificationType::MemberLeftBudget => 6,
+            NotificationType::MonthClosed => 7,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_type_conversion() {
+        for value in 0..8 {
+            let notification_type = NotificationType::try_from(value).unwrap();
+            assert_eq!(i16::from(notification_type), value);
+        }
+
+        assert!(NotificationType::try_from(8).is_err());
+        assert!(NotificationType::try_from(-1).is_err());
+    }
+
+    #[test]
+    fn test_parse_notification_data() {
+        let challenge_id = Uuid::new_v4();
+        let associated_data = serde_json::json!({ "challenge_id": challenge_id }).to_string();
+
+        let data = NotificationData::parse(
+            i16::from(NotificationType::ChallengeEnded),
+            &associated_data,
+        )
+        .unwrap();
+        assert_eq!(
+            data,
+            NotificationData::ChallengeEnded(ChallengeEndedData { challenge_id })
+        );
+
+        let serialized = serde_json::to_value(&data).unwrap();
+        assert_eq!(serialized["type"], "challenge_ended");
+        assert_eq!(serialized["challenge_id"], challenge_id.to_string());
+
+        // Data that doesn't match the notification type is rejected
+        assert!(NotificationData::parse(
+            i16::from(NotificationType::BudgetInvitation),
+            &associated_data,
+        )
+        .is_err());
+        assert!(NotificationData::parse(99, &associated_data).is_err());
+    }
+}
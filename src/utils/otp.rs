@@ -5,7 +5,17 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use uuid::Uuid;
 
-use crate::env;
+use crate::env::{self, OtpAlphabet};
+
+// Alphanumeric codes leave out characters that are easily confused with one another (0 and O,
+// 1 and I and L) and are case-insensitive
+const ALPHANUMERIC_CHARS: &[u8] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+
+// A digit code comes from RFC4226's 31-bit truncated value, so it can't be longer than 9 digits.
+// Alphanumeric codes are taken from 127 bits of the hash, which is enough for 16 characters.
+pub const MIN_OTP_LENGTH: usize = 6;
+pub const MAX_DIGIT_OTP_LENGTH: usize = 9;
+pub const MAX_ALPHANUMERIC_OTP_LENGTH: usize = 16;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum OtpError {
@@ -32,18 +42,13 @@ impl fmt::Display for OtpError {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub struct OneTimePasscode(u32);
+#[derive(Clone, Debug)]
+pub struct OneTimePasscode(String);
 
 impl fmt::Display for OneTimePasscode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:0>4} {:0>4}", self.0 / 10000, self.0 % 10000)
-    }
-}
-
-impl From<u32> for OneTimePasscode {
-    fn from(value: u32) -> Self {
-        OneTimePasscode(value)
+        let (first_half, second_half) = self.0.split_at(self.0.len().div_ceil(2));
+        write!(f, "{} {}", first_half, second_half)
     }
 }
 
@@ -52,17 +57,22 @@ impl TryFrom<String> for OneTimePasscode {
 
     fn try_from(mut value: String) -> Result<Self, OtpError> {
         value.retain(|c| !c.is_whitespace());
+        value.make_ascii_uppercase();
 
-        if value.len() != 8 {
+        if value.len() != env::CONF.otp.code_length {
             return Err(OtpError::ImproperlyFormatted);
         }
 
-        let code = match value.parse() {
-            Ok(u) => u,
-            Err(_) => return Err(OtpError::ImproperlyFormatted),
+        let is_in_alphabet = match env::CONF.otp.alphabet {
+            OtpAlphabet::Digits => value.bytes().all(|c| c.is_ascii_digit()),
+            OtpAlphabet::Alphanumeric => value.bytes().all(|c| ALPHANUMERIC_CHARS.contains(&c)),
         };
 
-        Ok(OneTimePasscode(code))
+        if !is_in_alphabet {
+            return Err(OtpError::ImproperlyFormatted);
+        }
+
+        Ok(OneTimePasscode(value))
     }
 }
 
@@ -73,7 +83,22 @@ impl PartialEq for OneTimePasscode {
 }
 
 pub fn generate_otp(user_id: Uuid, unix_timestamp: u64) -> Result<OneTimePasscode, OtpError> {
-    let time_segment = unix_timestamp / (env::CONF.lifetimes.otp_lifetime_mins * 60);
+    let code_length = env::CONF.otp.code_length;
+    let alphabet = env::CONF.otp.alphabet;
+
+    let max_length = match alphabet {
+        OtpAlphabet::Digits => MAX_DIGIT_OTP_LENGTH,
+        OtpAlphabet::Alphanumeric => MAX_ALPHANUMERIC_OTP_LENGTH,
+    };
+
+    if !(MIN_OTP_LENGTH..=max_length).contains(&code_length) {
+        return Err(OtpError::Error(format!(
+            "Configured code length of {} is outside of the supported range {}-{}",
+            code_length, MIN_OTP_LENGTH, max_length
+        )));
+    }
+
+    let time_segment = unix_timestamp / (env::reloadable_conf().lifetimes.otp_lifetime_mins * 60);
 
     let contents = format!("{}:{}", user_id, time_segment);
 
@@ -90,23 +115,48 @@ pub fn generate_otp(user_id: Uuid, unix_timestamp: u64) -> Result<OneTimePasscod
     );
     let hash = hmac::sign(&key, contents.as_bytes());
 
-    let offset = hash.as_ref()[19] as usize & 0xf;
-    let bin_code = (hash.as_ref()[offset] as u32 & 0x7f) << 24
-        | (hash.as_ref()[offset + 1] as u32 & 0xff) << 16
-        | (hash.as_ref()[offset + 2] as u32 & 0xff) << 8
-        | (hash.as_ref()[offset + 3] as u32 & 0xff);
-
-    let otp = bin_code % (10u32.pow(8));
+    Ok(OneTimePasscode(encode_code(
+        hash.as_ref(),
+        code_length,
+        alphabet,
+    )))
+}
 
-    Ok(OneTimePasscode(otp))
+fn encode_code(hash: &[u8], code_length: usize, alphabet: OtpAlphabet) -> String {
+    match alphabet {
+        OtpAlphabet::Digits => {
+            let offset = hash[19] as usize & 0xf;
+            let bin_code = (hash[offset] as u32 & 0x7f) << 24
+                | (hash[offset + 1] as u32 & 0xff) << 16
+                | (hash[offset + 2] as u32 & 0xff) << 8
+                | (hash[offset + 3] as u32 & 0xff);
+
+            let code = bin_code % (10u32.pow(code_length as u32));
+            format!("{:0>width$}", code, width = code_length)
+        }
+        OtpAlphabet::Alphanumeric => {
+            let mut bytes = [0u8; 16];
+            bytes.copy_from_slice(&hash[..16]);
+            let mut bin_code = u128::from_be_bytes(bytes) & (u128::MAX >> 1);
+
+            let base = ALPHANUMERIC_CHARS.len() as u128;
+            (0..code_length)
+                .map(|_| {
+                    let c = ALPHANUMERIC_CHARS[(bin_code % base) as usize] as char;
+                    bin_code /= base;
+                    c
+                })
+                .collect()
+        }
+    }
 }
 
 pub fn verify_otp(
-    passcode: OneTimePasscode,
+    passcode: &OneTimePasscode,
     user_id: Uuid,
     unix_timestamp: u64,
 ) -> Result<bool, OtpError> {
-    Ok(generate_otp(user_id, unix_timestamp)? == passcode)
+    Ok(generate_otp(user_id, unix_timestamp)? == *passcode)
 }
 
 #[cfg(test)]
@@ -143,14 +193,15 @@ mod tests {
         let otp1 = generate_otp(user_id, current_time).unwrap();
         let otp2 = generate_otp(
             user_id,
-            current_time + env::CONF.lifetimes.otp_lifetime_mins * 60,
+            current_time + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60,
         )
         .unwrap();
 
         assert_ne!(otp1, otp2);
 
-        let time3 = current_time - (current_time % (env::CONF.lifetimes.otp_lifetime_mins * 60));
-        let time4 = time3 + env::CONF.lifetimes.otp_lifetime_mins * 60;
+        let time3 = current_time
+            - (current_time % (env::reloadable_conf().lifetimes.otp_lifetime_mins * 60));
+        let time4 = time3 + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60;
 
         let otp3 = generate_otp(user_id, time3).unwrap();
         let otp4 = generate_otp(user_id, time4).unwrap();
@@ -166,14 +217,47 @@ mod tests {
             .as_secs();
 
         let user_id = Uuid::new_v4();
-        let time1 = current_time - (current_time % (env::CONF.lifetimes.otp_lifetime_mins * 60));
-        let time2 = time1 + env::CONF.lifetimes.otp_lifetime_mins * 60 - 1;
+        let time1 = current_time
+            - (current_time % (env::reloadable_conf().lifetimes.otp_lifetime_mins * 60));
+        let time2 = time1 + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60 - 1;
         let otp1 = generate_otp(user_id, time1).unwrap();
         let otp2 = generate_otp(user_id, time2).unwrap();
 
         assert_eq!(otp1, otp2);
     }
 
+    #[test]
+    fn test_encode_code() {
+        let hash = [
+            0x1f, 0x86, 0x98, 0x69, 0x0e, 0x02, 0xca, 0x16, 0x61, 0x85, 0x50, 0xef, 0x7f, 0x19,
+            0xda, 0x8e, 0x94, 0x5b, 0x55, 0x5a,
+        ];
+
+        // The example from RFC4226 section 5.4
+        assert_eq!(encode_code(&hash, 6, OtpAlphabet::Digits), "872921");
+        assert_eq!(encode_code(&hash, 9, OtpAlphabet::Digits), "357872921");
+
+        let code = encode_code(&hash, 12, OtpAlphabet::Alphanumeric);
+        assert_eq!(code.len(), 12);
+        assert!(code.bytes().all(|c| ALPHANUMERIC_CHARS.contains(&c)));
+        assert_eq!(code, encode_code(&hash, 12, OtpAlphabet::Alphanumeric));
+    }
+
+    #[test]
+    fn test_parse_passcode() {
+        let code = "2".repeat(env::CONF.otp.code_length);
+        let otp = OneTimePasscode::try_from(code.clone()).unwrap();
+        assert_eq!(otp.0, code);
+
+        // Whitespace from the displayed form is ignored
+        let otp = OneTimePasscode::try_from(otp.to_string()).unwrap();
+        assert_eq!(otp.0, code);
+
+        assert!(OneTimePasscode::try_from(code[1..].to_owned()).is_err());
+        assert!(OneTimePasscode::try_from(format!("{}2", code)).is_err());
+        assert!(OneTimePasscode::try_from(format!("#{}", &code[1..])).is_err());
+    }
+
     #[actix_rt::test]
     async fn test_verify_otp() {
         let current_time = SystemTime::now()
@@ -182,12 +266,13 @@ mod tests {
             .as_secs();
 
         let user_id = Uuid::new_v4();
-        let generate_time =
-            current_time - (current_time % (env::CONF.lifetimes.otp_lifetime_mins * 60));
-        let verify_time = generate_time + env::CONF.lifetimes.otp_lifetime_mins * 60 - 1;
+        let generate_time = current_time
+            - (current_time % (env::reloadable_conf().lifetimes.otp_lifetime_mins * 60));
+        let verify_time =
+            generate_time + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60 - 1;
         let otp = generate_otp(user_id, generate_time).unwrap();
 
-        assert!(verify_otp(otp, user_id, verify_time).unwrap());
+        assert!(verify_otp(&otp, user_id, verify_time).unwrap());
     }
 
     #[actix_rt::test]
@@ -198,12 +283,12 @@ mod tests {
             .as_secs();
 
         let user_id = Uuid::new_v4();
-        let generate_time =
-            current_time - (current_time % (env::CONF.lifetimes.otp_lifetime_mins * 60));
-        let verify_time = generate_time + env::CONF.lifetimes.otp_lifetime_mins * 60;
+        let generate_time = current_time
+            - (current_time % (env::reloadable_conf().lifetimes.otp_lifetime_mins * 60));
+        let verify_time = generate_time + env::reloadable_conf().lifetimes.otp_lifetime_mins * 60;
         let otp = generate_otp(user_id, generate_time).unwrap();
 
-        assert!(!verify_otp(otp, user_id, verify_time).unwrap());
+        assert!(!verify_otp(&otp, user_id, verify_time).unwrap());
     }
 
     #[actix_rt::test]
@@ -219,7 +304,7 @@ mod tests {
         let otp1 = generate_otp(user1_id, current_time).unwrap();
         let otp2 = generate_otp(user2_id, current_time).unwrap();
 
-        assert!(!verify_otp(otp1, user2_id, current_time).unwrap());
-        assert!(!verify_otp(otp2, user1_id, current_time).unwrap());
+        assert!(!verify_otp(&otp1, user2_id, current_time).unwrap());
+        assert!(!verify_otp(&otp2, user1_id, current_time).unwrap());
     }
 }
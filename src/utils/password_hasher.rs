@@ -26,6 +26,16 @@ pub struct BinaryHash {
     pub hash: Vec<u8>,
 }
 
+// The parameters a stored hash was created with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub lanes: u32,
+    pub hash_length: usize,
+    pub salt_length_bytes: usize,
+}
+
 impl TokenizedHash {
     pub fn from_str(parameterized_hash: &str) -> Result<TokenizedHash, ()> {
         enum HashStates {
@@ -407,6 +417,35 @@ pub fn verify_hash(password: &str, hash: &str) -> bool {
     verify_argon2id(password, hash, unsafe { hashing_key_mut.as_bytes_mut() })
 }
 
+pub fn hash_params(hash: &str) -> Option<HashParams> {
+    let tokenized_hash = TokenizedHash::from_str(hash).ok()?;
+
+    let salt = base64::decode_config(tokenized_hash.b64_salt, base64::STANDARD_NO_PAD).ok()?;
+    let hash = base64::decode_config(tokenized_hash.b64_hash, base64::STANDARD_NO_PAD).ok()?;
+
+    Some(HashParams {
+        memory_kib: tokenized_hash.memory_kib,
+        iterations: tokenized_hash.iterations,
+        lanes: tokenized_hash.lanes,
+        hash_length: hash.len(),
+        salt_length_bytes: salt.len(),
+    })
+}
+
+// Whether a hash was created with weaker parameters than the current hashing configuration. Hashes
+// that can't be parsed are never rehashed because they can't be verified in the first place.
+pub fn needs_rehash(hash: &str) -> bool {
+    let params = match hash_params(hash) {
+        Some(p) => p,
+        None => return false,
+    };
+
+    params.memory_kib < env::CONF.hashing.hash_mem_size_kib
+        || params.iterations < env::CONF.hashing.hash_iterations
+        || params.hash_length < env::CONF.hashing.hash_length
+        || params.salt_length_bytes < env::CONF.hashing.salt_length_bytes
+}
+
 pub fn hash_argon2id(
     password: &str,
     key: &mut [u8],
@@ -693,4 +732,45 @@ mod tests {
 
         assert!(!verify_hash("@pa$$20rd-Test", &hash));
     }
+
+    #[actix_rt::test]
+    async fn test_hash_params() {
+        let hash = hash_password("@Pa$$20rd-Test");
+        let params = hash_params(&hash).unwrap();
+
+        assert_eq!(params.memory_kib, env::CONF.hashing.hash_mem_size_kib);
+        assert_eq!(params.iterations, env::CONF.hashing.hash_iterations);
+        assert_eq!(params.lanes, env::CONF.hashing.hash_lanes);
+        assert_eq!(params.hash_length, env::CONF.hashing.hash_length);
+        assert_eq!(
+            params.salt_length_bytes,
+            env::CONF.hashing.salt_length_bytes
+        );
+
+        assert!(hash_params("$argon2id$v=19$m=128,t=3,p=2$AQIDBAUGBwg$").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_needs_rehash() {
+        let password = "@Pa$$20rd-Test";
+        let mut hashing_key_mut = env::CONF.keys.hashing_key.clone();
+        let mut salt = vec![7u8; env::CONF.hashing.salt_length_bytes];
+
+        let weak_hash = hash_argon2id(
+            password,
+            unsafe { hashing_key_mut.as_bytes_mut() },
+            &mut salt[..],
+            u32::try_from(env::CONF.hashing.hash_length).unwrap(),
+            env::CONF.hashing.hash_iterations - 1,
+            env::CONF.hashing.hash_mem_size_kib,
+            env::CONF.hashing.hash_lanes,
+        )
+        .to_hash_string();
+
+        assert!(verify_hash(password, &weak_hash));
+        assert!(needs_rehash(&weak_hash));
+
+        assert!(!needs_rehash(&hash_password(password)));
+        assert!(!needs_rehash("not a hash"));
+    }
 }
@@ -0,0 +1,623 @@
+use futures::future::BoxFuture;
+use log::warn;
+use ring::signature::{self, EcdsaKeyPair, RsaKeyPair};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::definitions::*;
+use crate::env::{self, ApnsPush, FcmPush};
+use crate::models::user_device_token::UserDeviceToken;
+use crate::models::user_notification::UserNotification;
+use crate::utils::db;
+use crate::utils::span_export::{self, AttributeValue, Span, SpanKind};
+use crate::utils::trace_context::{TraceContext, TRACEPARENT_HEADER};
+
+pub const PUSH_BATCH_SIZE: i64 = 500;
+
+// The values are stored in the database, so existing variants must keep their numbers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DevicePlatform {
+    Ios = 0,
+    Android = 1,
+}
+
+impl TryFrom<i16> for DevicePlatform {
+    type Error = ();
+
+    fn try_from(value: i16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DevicePlatform::Ios),
+            1 => Ok(DevicePlatform::Android),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PushError {
+    // The provider says the token will never work again, such as after the app was uninstalled
+    InvalidToken,
+    ProviderFailure(String),
+}
+
+impl std::error::Error for PushError {}
+
+impl fmt::Display for PushError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushError::InvalidToken => write!(f, "Device token is no longer valid"),
+            PushError::ProviderFailure(msg) => write!(f, "Push provider failure: {}", msg),
+        }
+    }
+}
+
+pub struct PendingPush {
+    pub notification: UserNotification,
+    pub devices: Vec<UserDeviceToken>,
+    // The trace of the request that created the notification, if one did
+    pub trace_context: Option<TraceContext>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PushMessage {
+    pub notification_id: uuid::Uuid,
+    pub notification_type: i16,
+    pub title: String,
+    pub body: String,
+    // The span the push is delivered in, passed on to the provider
+    pub trace_context: TraceContext,
+}
+
+impl PushMessage {
+    pub fn new(notification: &UserNotification, trace_context: TraceContext) -> Self {
+        Self {
+            notification_id: notification.id,
+            notification_type: notification.notification_type,
+            title: notification.alt_title.clone(),
+            body: notification.alt_message.clone(),
+            trace_context,
+        }
+    }
+}
+
+pub trait PushProvider: Send + Sync {
+    fn platform(&self) -> DevicePlatform;
+    fn send(
+        &self,
+        device_token: &str,
+        message: &PushMessage,
+    ) -> BoxFuture<'static, Result<(), PushError>>;
+}
+
+// Pushes are best-effort. A push that fails for any reason other than a dead token is dropped
+// rather than retried; the notification is still in the user's list. Each notification's pushes
+// are delivered in a span of the trace that queued them, or of a new trace. Returns the number of
+// pushes sent.
+pub async fn deliver_pending_pushes(
+    db_connection: &DbConnection,
+    providers: &[Box<dyn PushProvider>],
+) -> Result<usize, diesel::result::Error> {
+    let pending = db::push::claim_pending_pushes(db_connection, PUSH_BATCH_SIZE)?;
+
+    let mut sent_count = 0;
+    let mut invalid_tokens = Vec::new();
+
+    for push in pending.iter() {
+        let start_time = SystemTime::now();
+        let trace_context = push
+            .trace_context
+            .map(|parent| parent.child())
+            .unwrap_or_else(TraceContext::new_root);
+
+        let message = PushMessage::new(&push.notification, trace_context);
+        let mut failed_count = 0;
+
+        for device in push.devices.iter() {
+            let provider = match providers
+                .iter()
+                .find(|p| p.platform() as i16 == device.platform)
+            {
+                Some(p) => p,
+                None => continue,
+            };
+
+            match provider.send(&device.token, &message).await {
+                Ok(()) => sent_count += 1,
+                Err(PushError::InvalidToken) => invalid_tokens.push(device.token.clone()),
+                Err(e) => {
+                    failed_count += 1;
+                    warn!(
+                        "Failed to push notification {}: {}",
+                        message.notification_id, e
+                    );
+                }
+            }
+        }
+
+        span_export::export(Span {
+            name: String::from("deliver push"),
+            kind: SpanKind::Consumer,
+            context: trace_context,
+            parent_span_id: push.trace_context.map(|parent| parent.span_id),
+            start_time,
+            end_time: SystemTime::now(),
+            attributes: vec![
+                (
+                    "budgetapp.notification_type",
+                    AttributeValue::Int(i64::from(message.notification_type)),
+                ),
+                (
+                    "budgetapp.device_count",
+                    AttributeValue::Int(push.devices.len() as i64),
+                ),
+            ],
+            is_error: failed_count > 0,
+        });
+    }
+
+    if !invalid_tokens.is_empty() {
+        db::push::remove_device_tokens(db_connection, &invalid_tokens)?;
+    }
+
+    Ok(sent_count)
+}
+
+// A platform whose provider isn't configured gets no pushes
+pub fn configured_providers() -> Result<Vec<Box<dyn PushProvider>>, String> {
+    let mut providers = Vec::<Box<dyn PushProvider>>::new();
+
+    if let Some(conf) = &env::CONF.push.apns {
+        providers.push(Box::new(Apns::new(conf)?));
+    }
+
+    if let Some(conf) = &env::CONF.push.fcm {
+        providers.push(Box::new(Fcm::new(conf)?));
+    }
+
+    Ok(providers)
+}
+
+fn read_pem_file(path: &str) -> Result<Vec<u8>, String> {
+    let pem = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read key file '{}': {}", path, e))?;
+    pem_to_der(&pem).ok_or_else(|| format!("Key file '{}' is not a valid PEM file", path))
+}
+
+fn pem_to_der(pem: &str) -> Option<Vec<u8>> {
+    let encoded = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<String>();
+
+    base64::decode(encoded).ok()
+}
+
+fn jwt_part<T: Serialize>(part: &T) -> String {
+    base64::encode_config(
+        serde_json::to_vec(part).expect("Failed to serialize JWT"),
+        base64::URL_SAFE_NO_PAD,
+    )
+}
+
+fn unix_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the Unix epoch")
+        .as_secs()
+}
+
+// A bearer token along with when it should be replaced
+struct CachedToken {
+    token: String,
+    refresh_at: Instant,
+}
+
+fn cached_token(
+    cache: &Mutex<Option<CachedToken>>,
+    refresh_after: Duration,
+    generate: impl FnOnce() -> Result<String, PushError>,
+) -> Result<String, PushError> {
+    let mut cache = cache.lock().expect("Tried to aquire poisoned mutex");
+
+    if let Some(cached) = cache.as_ref() {
+        if Instant::now() < cached.refresh_at {
+            return Ok(cached.token.clone());
+        }
+    }
+
+    let token = generate()?;
+    *cache = Some(CachedToken {
+        token: token.clone(),
+        refresh_at: Instant::now() + refresh_after,
+    });
+
+    Ok(token)
+}
+
+// Apple Push Notification service, authenticated with a token signing key (.p8) from the Apple
+// developer account
+pub struct Apns {
+    client: reqwest::Client,
+    endpoint: &'static str,
+    topic: String,
+    team_id: String,
+    key_id: String,
+    signing_key: EcdsaKeyPair,
+    provider_token: Mutex<Option<CachedToken>>,
+}
+
+#[derive(Serialize)]
+struct ApnsTokenHeader<'a> {
+    alg: &'static str,
+    kid: &'a str,
+}
+
+#[derive(Serialize)]
+struct ApnsTokenClaims<'a> {
+    iss: &'a str,
+    iat: u64,
+}
+
+#[derive(Deserialize)]
+struct ApnsErrorResponse {
+    reason: String,
+}
+
+impl Apns {
+    // Apple rejects provider tokens older than an hour and throttles replacing them more often
+    // than every 20 minutes
+    const PROVIDER_TOKEN_REFRESH_SECS: u64 = 40 * 60;
+
+    pub fn new(conf: &ApnsPush) -> Result<Self, String> {
+        let key = read_pem_file(&conf.private_key_path)?;
+        let signing_key =
+            EcdsaKeyPair::from_pkcs8(&signature::ECDSA_P256_SHA256_FIXED_SIGNING, &key)
+                .map_err(|e| format!("Invalid APNs signing key: {}", e))?;
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            endpoint: if conf.use_sandbox {
+                "https://api.sandbox.push.apple.com"
+            } else {
+                "https://api.push.apple.com"
+            },
+            topic: conf.topic.clone(),
+            team_id: conf.team_id.clone(),
+            key_id: conf.key_id.clone(),
+            signing_key,
+            provider_token: Mutex::new(None),
+        })
+    }
+
+    fn provider_token(&self) -> Result<String, PushError> {
+        cached_token(
+            &self.provider_token,
+            Duration::from_secs(Self::PROVIDER_TOKEN_REFRESH_SECS),
+            || {
+                let unsigned_token = format!(
+                    "{}.{}",
+                    jwt_part(&ApnsTokenHeader {
+                        alg: "ES256",
+                        kid: &self.key_id,
+                    }),
+                    jwt_part(&ApnsTokenClaims {
+                        iss: &self.team_id,
+                        iat: unix_time_secs(),
+                    }),
+                );
+
+                let signature = self
+                    .signing_key
+                    .sign(
+                        &*env::rand::SECURE_RANDOM_GENERATOR,
+                        unsigned_token.as_bytes(),
+                    )
+                    .map_err(|_| {
+                        PushError::ProviderFailure(String::from("Failed to sign APNs token"))
+                    })?;
+
+                Ok(format!(
+                    "{}.{}",
+                    unsigned_token,
+                    base64::encode_config(signature.as_ref(), base64::URL_SAFE_NO_PAD),
+                ))
+            },
+        )
+    }
+}
+
+impl PushProvider for Apns {
+    fn platform(&self) -> DevicePlatform {
+        DevicePlatform::Ios
+    }
+
+    fn send(
+        &self,
+        device_token: &str,
+        message: &PushMessage,
+    ) -> BoxFuture<'static, Result<(), PushError>> {
+        let provider_token = match self.provider_token() {
+            Ok(t) => t,
+            Err(e) => return Box::pin(async move { Err(e) }),
+        };
+
+        let payload = serde_json::json!({
+            "aps": {
+                "alert": {
+                    "title": message.title,
+                    "body": message.body,
+                },
+                "sound": "default",
+            },
+            "notification_id": message.notification_id,
+            "notification_type": message.notification_type,
+        });
+
+        let request = self
+            .client
+            .post(format!("{}/3/device/{}", self.endpoint, device_token))
+            .header("authorization", format!("bearer {}", provider_token))
+            .header("apns-topic", self.topic.as_str())
+            .header("apns-push-type", "alert")
+            .header(TRACEPARENT_HEADER, message.trace_context.traceparent())
+            .body(payload.to_string());
+
+        Box::pin(async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| PushError::ProviderFailure(e.to_string()))?;
+
+            let status = response.status();
+            if status.is_success() {
+                return Ok(());
+            }
+
+            let reason = response
+                .bytes()
+                .await
+                .ok()
+                .and_then(|body| serde_json::from_slice::<ApnsErrorResponse>(&body).ok())
+                .map(|error| error.reason)
+                .unwrap_or_default();
+
+            match (status, reason.as_str()) {
+                (reqwest::StatusCode::GONE, _)
+                | (reqwest::StatusCode::BAD_REQUEST, "BadDeviceToken")
+                | (reqwest::StatusCode::BAD_REQUEST, "DeviceTokenNotForTopic") => {
+                    Err(PushError::InvalidToken)
+                }
+                _ => Err(PushError::ProviderFailure(format!(
+                    "APNs responded with {} {}",
+                    status, reason
+                ))),
+            }
+        })
+    }
+}
+
+// Firebase Cloud Messaging's HTTP v1 API, authenticated as a service account
+pub struct Fcm {
+    client: reqwest::Client,
+    send_url: String,
+    client_email: String,
+    signing_key: RsaKeyPair,
+    access_token: Arc<Mutex<Option<CachedToken>>>,
+}
+
+enum FcmCredentials {
+    AccessToken(String),
+    // A signed request for a new access token
+    Assertion(String),
+}
+
+// The fields used from the JSON key file Google provides for a service account
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    project_id: String,
+    client_email: String,
+    private_key: String,
+}
+
+#[derive(Serialize)]
+struct GoogleTokenClaims<'a> {
+    iss: &'a str,
+    scope: &'static str,
+    aud: &'static str,
+    iat: u64,
+    exp: u64,
+}
+
+#[derive(Deserialize)]
+struct GoogleTokenResponse {
+    access_token: String,
+}
+
+impl Fcm {
+    const TOKEN_URL: &'static str = "https://oauth2.googleapis.com/token";
+    const MESSAGING_SCOPE: &'static str = "https://www.googleapis.com/auth/firebase.messaging";
+
+    // Access tokens last an hour
+    const ACCESS_TOKEN_LIFETIME_SECS: u64 = 60 * 60;
+    const ACCESS_TOKEN_REFRESH_SECS: u64 = 50 * 60;
+
+    pub fn new(conf: &FcmPush) -> Result<Self, String> {
+        let key_file = std::fs::read(&conf.service_account_path).map_err(|e| {
+            format!(
+                "Failed to read service account file '{}': {}",
+                conf.service_account_path, e
+            )
+        })?;
+        let key = serde_json::from_slice::<ServiceAccountKey>(&key_file)
+            .map_err(|e| format!("Invalid service account file: {}", e))?;
+
+        let der = pem_to_der(&key.private_key)
+            .ok_or_else(|| String::from("Invalid service account private key"))?;
+        let signing_key = RsaKeyPair::from_pkcs8(&der)
+            .map_err(|e| format!("Invalid service account private key: {}", e))?;
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            send_url: format!(
+                "https://fcm.googleapis.com/v1/projects/{}/messages:send",
+                key.project_id
+            ),
+            client_email: key.client_email,
+            signing_key,
+            access_token: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    fn signed_assertion(&self) -> Result<String, PushError> {
+        let now = unix_time_secs();
+        let unsigned_assertion = format!(
+            "{}.{}",
+            jwt_part(&serde_json::json!({ "alg": "RS256", "typ": "JWT" })),
+            jwt_part(&GoogleTokenClaims {
+                iss: &self.client_email,
+                scope: Self::MESSAGING_SCOPE,
+                aud: Self::TOKEN_URL,
+                iat: now,
+                exp: now + Self::ACCESS_TOKEN_LIFETIME_SECS,
+            }),
+        );
+
+        let mut signature = vec![0; self.signing_key.public_modulus_len()];
+        self.signing_key
+            .sign(
+                &signature::RSA_PKCS1_SHA256,
+                &*env::rand::SECURE_RANDOM_GENERATOR,
+                unsigned_assertion.as_bytes(),
+                &mut signature,
+            )
+            .map_err(|_| {
+                PushError::ProviderFailure(String::from("Failed to sign FCM token request"))
+            })?;
+
+        Ok(format!(
+            "{}.{}",
+            unsigned_assertion,
+            base64::encode_config(&signature, base64::URL_SAFE_NO_PAD),
+        ))
+    }
+
+    async fn fetch_access_token(
+        client: &reqwest::Client,
+        assertion: &str,
+    ) -> Result<String, PushError> {
+        let response = client
+            .post(Self::TOKEN_URL)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion),
+            ])
+            .send()
+            .await
+            .map_err(|e| PushError::ProviderFailure(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(PushError::ProviderFailure(format!(
+                "Google token endpoint responded with {}",
+                response.status()
+            )));
+        }
+
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| PushError::ProviderFailure(e.to_string()))?;
+
+        serde_json::from_slice::<GoogleTokenResponse>(&body)
+            .map(|r| r.access_token)
+            .map_err(|e| PushError::ProviderFailure(e.to_string()))
+    }
+}
+
+impl PushProvider for Fcm {
+    fn platform(&self) -> DevicePlatform {
+        DevicePlatform::Android
+    }
+
+    fn send(
+        &self,
+        device_token: &str,
+        message: &PushMessage,
+    ) -> BoxFuture<'static, Result<(), PushError>> {
+        let cached_access_token = self
+            .access_token
+            .lock()
+            .expect("Tried to aquire poisoned mutex")
+            .as_ref()
+            .filter(|cached| Instant::now() < cached.refresh_at)
+            .map(|cached| cached.token.clone());
+
+        let credentials = match cached_access_token {
+            Some(token) => FcmCredentials::AccessToken(token),
+            None => match self.signed_assertion() {
+                Ok(assertion) => FcmCredentials::Assertion(assertion),
+                Err(e) => return Box::pin(async move { Err(e) }),
+            },
+        };
+
+        let payload = serde_json::json!({
+            "message": {
+                "token": device_token,
+                "notification": {
+                    "title": message.title,
+                    "body": message.body,
+                },
+                "data": {
+                    "notification_id": message.notification_id.to_string(),
+                    "notification_type": message.notification_type.to_string(),
+                },
+            },
+        });
+
+        let client = self.client.clone();
+        let send_url = self.send_url.clone();
+        let traceparent = message.trace_context.traceparent();
+        let access_token_cache = Arc::clone(&self.access_token);
+
+        Box::pin(async move {
+            let access_token = match credentials {
+                FcmCredentials::AccessToken(token) => token,
+                FcmCredentials::Assertion(assertion) => {
+                    let token = Self::fetch_access_token(&client, &assertion).await?;
+
+                    *access_token_cache
+                        .lock()
+                        .expect("Tried to aquire poisoned mutex") = Some(CachedToken {
+                        token: token.clone(),
+                        refresh_at: Instant::now()
+                            + Duration::from_secs(Self::ACCESS_TOKEN_REFRESH_SECS),
+                    });
+
+                    token
+                }
+            };
+
+            let response = client
+                .post(send_url)
+                .header("authorization", format!("Bearer {}", access_token))
+                .header("content-type", "application/json")
+                .header(TRACEPARENT_HEADER, traceparent)
+                .body(payload.to_string())
+                .send()
+                .await
+                .map_err(|e| PushError::ProviderFailure(e.to_string()))?;
+
+            match response.status() {
+                s if s.is_success() => Ok(()),
+                reqwest::StatusCode::NOT_FOUND => Err(PushError::InvalidToken),
+                s => Err(PushError::ProviderFailure(format!(
+                    "FCM responded with {}",
+                    s
+                ))),
+            }
+        })
+    }
+}
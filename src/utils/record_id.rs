@@ -0,0 +1,70 @@
+use rand::RngCore;
+use uuid::Uuid;
+
+// Records get UUIDv7 IDs. The first 48 bits are the Unix time in milliseconds, so records created
+// around the same time sit next to each other in the primary key indexes instead of being scattered
+// across them. The remaining 74 bits are random.
+//
+// Clients may create entries and comments offline and give them IDs of their own so they can refer
+// to them before they are uploaded. Those IDs have to be UUIDv7s too.
+
+// A client's clock may be a little ahead of the server's
+const MAX_CLIENT_CLOCK_SKEW_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+pub fn generate() -> Uuid {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes[6..]);
+
+    let timestamp_millis = chrono::Utc::now().timestamp_millis() as u64;
+    bytes[..6].copy_from_slice(&timestamp_millis.to_be_bytes()[2..]);
+
+    bytes[6] = 0x70 | (bytes[6] & 0x0F);
+    bytes[8] = 0x80 | (bytes[8] & 0x3F);
+
+    Uuid::from_bytes(bytes)
+}
+
+pub fn is_acceptable_client_id(id: Uuid) -> bool {
+    if id.get_version_num() != 7 || id.get_variant() != Some(uuid::Variant::RFC4122) {
+        return false;
+    }
+
+    let mut timestamp_bytes = [0u8; 8];
+    timestamp_bytes[2..].copy_from_slice(&id.as_bytes()[..6]);
+    let timestamp_millis = u64::from_be_bytes(timestamp_bytes) as i64;
+
+    timestamp_millis <= chrono::Utc::now().timestamp_millis() + MAX_CLIENT_CLOCK_SKEW_MILLIS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let id = generate();
+        assert_eq!(id.get_version_num(), 7);
+        assert_eq!(id.get_variant(), Some(uuid::Variant::RFC4122));
+        assert_ne!(generate(), id);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert!(generate() > id);
+    }
+
+    #[test]
+    fn test_is_acceptable_client_id() {
+        assert!(is_acceptable_client_id(generate()));
+        assert!(!is_acceptable_client_id(Uuid::new_v4()));
+        assert!(!is_acceptable_client_id(Uuid::nil()));
+
+        let earliest = Uuid::parse_str("00000000-0000-7000-8000-000000000000").unwrap();
+        assert!(is_acceptable_client_id(earliest));
+
+        let far_future = Uuid::parse_str("ffffffff-ffff-7000-8000-000000000000").unwrap();
+        assert!(!is_acceptable_client_id(far_future));
+
+        // The variant bits have to be right as well as the version
+        let wrong_variant = Uuid::parse_str("00000000-0000-7000-c000-000000000000").unwrap();
+        assert!(!is_acceptable_client_id(wrong_variant));
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RecurrenceFrequency {
+    Weekly,
+    Biweekly,
+    Monthly,
+    Yearly,
+}
+
+impl RecurrenceFrequency {
+    // Monthly and yearly occurrences stay on the start date's day of the month, falling back to
+    // the last day of shorter months (e.g. Jan 31 -> Feb 28 -> Mar 31)
+    pub fn next_occurrence(self, start_date: NaiveDate, previous: NaiveDate) -> NaiveDate {
+        match self {
+            RecurrenceFrequency::Weekly => previous + chrono::Duration::weeks(1),
+            RecurrenceFrequency::Biweekly => previous + chrono::Duration::weeks(2),
+            RecurrenceFrequency::Monthly => {
+                let (year, month) = if previous.month() == 12 {
+                    (previous.year() + 1, 1)
+                } else {
+                    (previous.year(), previous.month() + 1)
+                };
+
+                clamped_date(year, month, start_date.day())
+            }
+            RecurrenceFrequency::Yearly => {
+                clamped_date(previous.year() + 1, start_date.month(), start_date.day())
+            }
+        }
+    }
+}
+
+fn clamped_date(year: i32, month: u32, day: u32) -> NaiveDate {
+    (1..=day)
+        .rev()
+        .find_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .expect("Every month has a first day")
+}
+
+#[derive(Debug)]
+pub enum RecurrenceFrequencyError {
+    NoMatchForValue(i16),
+}
+
+impl std::error::Error for RecurrenceFrequencyError {}
+
+impl fmt::Display for RecurrenceFrequencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecurrenceFrequencyError::NoMatchForValue(v) => write!(f, "NoMatchForValue: {}", v),
+        }
+    }
+}
+
+impl std::convert::TryFrom<i16> for RecurrenceFrequency {
+    type Error = RecurrenceFrequencyError;
+
+    fn try_from(value: i16) -> Result<Self, RecurrenceFrequencyError> {
+        match value {
+            0 => Ok(RecurrenceFrequency::Weekly),
+            1 => Ok(RecurrenceFrequency::Biweekly),
+            2 => Ok(RecurrenceFrequency::Monthly),
+            3 => Ok(RecurrenceFrequency::Yearly),
+            v => Err(RecurrenceFrequencyError::NoMatchForValue(v)),
+        }
+    }
+}
+
+impl std::convert::From<RecurrenceFrequency> for i16 {
+    fn from(frequency: RecurrenceFrequency) -> Self {
+        match frequency {
+            RecurrenceFrequency::Weekly => 0,
+            RecurrenceFrequency::Biweekly => 1,
+            RecurrenceFrequency::Monthly => 2,
+            RecurrenceFrequency::Yearly => 3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_occurrence() {
+        let start = NaiveDate::from_ymd(2022, 1, 31);
+
+        assert_eq!(
+            RecurrenceFrequency::Weekly.next_occurrence(start, start),
+            NaiveDate::from_ymd(2022, 2, 7)
+        );
+        assert_eq!(
+            RecurrenceFrequency::Biweekly.next_occurrence(start, start),
+            NaiveDate::from_ymd(2022, 2, 14)
+        );
+
+        let feb = RecurrenceFrequency::Monthly.next_occurrence(start, start);
+        assert_eq!(feb, NaiveDate::from_ymd(2022, 2, 28));
+        let mar = RecurrenceFrequency::Monthly.next_occurrence(start, feb);
+        assert_eq!(mar, NaiveDate::from_ymd(2022, 3, 31));
+        assert_eq!(
+            RecurrenceFrequency::Monthly.next_occurrence(start, NaiveDate::from_ymd(2022, 12, 31)),
+            NaiveDate::from_ymd(2023, 1, 31)
+        );
+
+        let leap_day = NaiveDate::from_ymd(2024, 2, 29);
+        let next_year = RecurrenceFrequency::Yearly.next_occurrence(leap_day, leap_day);
+        assert_eq!(next_year, NaiveDate::from_ymd(2025, 2, 28));
+        assert_eq!(
+            RecurrenceFrequency::Yearly.next_occurrence(leap_day, NaiveDate::from_ymd(2027, 2, 28)),
+            NaiveDate::from_ymd(2028, 2, 29)
+        );
+    }
+
+    #[test]
+    fn test_frequency_conversion() {
+        for value in 0..4 {
+            let frequency = RecurrenceFrequency::try_from(value).unwrap();
+            assert_eq!(i16::from(frequency), value);
+        }
+
+        assert!(RecurrenceFrequency::try_from(4).is_err());
+        assert!(RecurrenceFrequency::try_from(-1).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+use futures::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::env::{AwsSecretsManagerSecrets, SecretProviderKind, Secrets, VaultSecrets};
+use crate::utils::aws_signature::AwsSigner;
+
+// Somewhere other than the configuration file that secret keys can be kept. The provider is asked
+// once, when the configuration is first read, and gives back every secret it holds by name.
+pub trait SecretProvider: Send + Sync {
+    fn fetch_secrets(&self) -> BoxFuture<'static, Result<BTreeMap<String, String>, String>>;
+}
+
+pub fn configured_secret_provider(conf: &Secrets) -> Result<Box<dyn SecretProvider>, String> {
+    match conf.provider {
+        SecretProviderKind::Vault => match &conf.vault {
+            Some(vault) => Ok(Box::new(Vault::new(vault))),
+            None => Err(String::from(
+                "The vault secret provider requires a [secrets.vault] section",
+            )),
+        },
+        SecretProviderKind::AwsSecretsManager => match &conf.aws_secrets_manager {
+            Some(aws) => Ok(Box::new(AwsSecretsManager::new(aws))),
+            None => Err(String::from(
+                "The aws_secrets_manager secret provider requires a [secrets.aws_secrets_manager] section",
+            )),
+        },
+    }
+}
+
+// Secrets are read from a HashiCorp Vault KV version 2 secrets engine. Each field of the secret is
+// a separate secret.
+pub struct Vault {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl Vault {
+    pub fn new(conf: &VaultSecrets) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: format!(
+                "{}/v1/{}/data/{}",
+                conf.address.trim_end_matches('/'),
+                conf.mount,
+                conf.path,
+            ),
+            token: conf.token.clone(),
+        }
+    }
+}
+
+impl SecretProvider for Vault {
+    fn fetch_secrets(&self) -> BoxFuture<'static, Result<BTreeMap<String, String>, String>> {
+        let request = self
+            .client
+            .get(&self.url)
+            .header("x-vault-token", &self.token);
+
+        Box::pin(async move {
+            let body = send(request, "Vault").await?;
+            string_fields(&body["data"]["data"])
+        })
+    }
+}
+
+// The secret's value must be a JSON object, which is how the AWS console stores key/value secrets.
+// Each field of the object is a separate secret.
+pub struct AwsSecretsManager {
+    client: reqwest::Client,
+    host: String,
+    secret_id: String,
+    signer: AwsSigner,
+}
+
+impl AwsSecretsManager {
+    pub fn new(conf: &AwsSecretsManagerSecrets) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            host: format!("secretsmanager.{}.amazonaws.com", conf.region),
+            secret_id: conf.secret_id.clone(),
+            signer: AwsSigner {
+                access_key_id: conf.access_key_id.clone(),
+                secret_access_key: conf.secret_access_key.clone(),
+                region: conf.region.clone(),
+                service: "secretsmanager",
+            },
+        }
+    }
+}
+
+impl SecretProvider for AwsSecretsManager {
+    fn fetch_secrets(&self) -> BoxFuture<'static, Result<BTreeMap<String, String>, String>> {
+        const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+        const TARGET: &str = "secretsmanager.GetSecretValue";
+
+        let body = serde_json::json!({ "SecretId": self.secret_id }).to_string();
+        let payload_hash = hex::encode(Sha256::digest(body.as_bytes()));
+        let timestamp = chrono::Utc::now().naive_utc();
+        let authorization = self.signer.authorization(
+            "POST",
+            &self.host,
+            "/",
+            &[("content-type", CONTENT_TYPE), ("x-amz-target", TARGET)],
+            &payload_hash,
+            timestamp,
+        );
+
+        let request = self
+            .client
+            .post(format!("https://{}/", self.host))
+            .header("content-type", CONTENT_TYPE)
+            .header("x-amz-target", TARGET)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", timestamp.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("authorization", authorization)
+            .body(body);
+
+        Box::pin(async move {
+            let body = send(request, "AWS Secrets Manager").await?;
+
+            let secret_string = body["SecretString"]
+                .as_str()
+                .ok_or_else(|| String::from("AWS Secrets Manager secret has no SecretString"))?;
+            let secret = serde_json::from_str::<serde_json::Value>(secret_string)
+                .map_err(|e| format!("AWS Secrets Manager secret isn't JSON: {}", e))?;
+
+            string_fields(&secret)
+        })
+    }
+}
+
+async fn send(
+    request: reqwest::RequestBuilder,
+    provider_name: &str,
+) -> Result<serde_json::Value, String> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach {}: {}", provider_name, e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "{} responded with {}",
+            provider_name,
+            response.status()
+        ));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response from {}: {}", provider_name, e))?;
+
+    serde_json::from_str(&body)
+        .map_err(|e| format!("{} responded with invalid JSON: {}", provider_name, e))
+}
+
+// Fields that aren't strings can't be keys, so they are left out
+fn string_fields(value: &serde_json::Value) -> Result<BTreeMap<String, String>, String> {
+    let fields = value
+        .as_object()
+        .ok_or_else(|| String::from("Secret must be an object of named values"))?;
+
+    Ok(fields
+        .iter()
+        .filter_map(|(name, value)| Some((name.clone(), String::from(value.as_str()?))))
+        .collect())
+}
@@ -0,0 +1,247 @@
+use log::warn;
+use serde_json::json;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::env;
+use crate::utils::trace_context::TraceContext;
+
+const MAX_BATCH_SIZE: usize = 512;
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(5);
+
+lazy_static! {
+    static ref EXPORTER: Option<SpanExporter> = env::CONF
+        .tracing
+        .otlp_endpoint
+        .as_deref()
+        .map(SpanExporter::start);
+}
+
+// Finished spans are sent to the configured collector with OTLP's JSON encoding over HTTP. They are
+// batched on a thread of their own, so exporting never holds up a request or a job. Spans of
+// unsampled traces are never exported, and a batch the collector doesn't accept is dropped.
+
+// The values are OTLP's
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanKind {
+    Server = 2,
+    Consumer = 5,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+}
+
+#[derive(Clone, Debug)]
+pub struct Span {
+    pub name: String,
+    pub kind: SpanKind,
+    pub context: TraceContext,
+    pub parent_span_id: Option<[u8; 8]>,
+    pub start_time: SystemTime,
+    pub end_time: SystemTime,
+    pub attributes: Vec<(&'static str, AttributeValue)>,
+    pub is_error: bool,
+}
+
+struct SpanExporter {
+    sender: Mutex<Sender<Span>>,
+}
+
+impl SpanExporter {
+    fn start(endpoint: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let endpoint = endpoint.to_string();
+
+        std::thread::Builder::new()
+            .name(String::from("span-export"))
+            .spawn(move || export_batches(&endpoint, receiver))
+            .expect("Failed to start span export thread");
+
+        Self {
+            sender: Mutex::new(sender),
+        }
+    }
+}
+
+pub fn is_enabled() -> bool {
+    EXPORTER.is_some()
+}
+
+pub fn export(span: Span) {
+    if !span.context.is_sampled() {
+        return;
+    }
+
+    if let Some(exporter) = EXPORTER.as_ref() {
+        // Sending only fails if the export thread has panicked, in which case the span is dropped
+        let _ = exporter
+            .sender
+            .lock()
+            .expect("Tried to aquire poisoned mutex")
+            .send(span);
+    }
+}
+
+fn export_batches(endpoint: &str, receiver: Receiver<Span>) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create runtime for exporting spans");
+    let client = reqwest::Client::new();
+
+    // Waits for a span, then gathers whatever else finishes shortly after it
+    while let Ok(first_span) = receiver.recv() {
+        let mut batch = vec![first_span];
+        let deadline = Instant::now() + MAX_BATCH_DELAY;
+
+        while batch.len() < MAX_BATCH_SIZE {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(span) => batch.push(span),
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let request = client
+            .post(endpoint)
+            .json(&otlp_request(&env::CONF.tracing.service_name, &batch));
+
+        match runtime.block_on(request.send()) {
+            Ok(response) if response.status().is_success() => (),
+            Ok(response) => warn!(
+                "Span collector rejected {} spans with {}",
+                batch.len(),
+                response.status()
+            ),
+            Err(e) => warn!("Failed to export {} spans: {}", batch.len(), e),
+        }
+    }
+}
+
+fn otlp_request(service_name: &str, spans: &[Span]) -> serde_json::Value {
+    let service_name = AttributeValue::String(service_name.to_string());
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [otlp_attribute("service.name", &service_name)],
+            },
+            "scopeSpans": [{
+                "scope": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "spans": spans.iter().map(otlp_span).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn otlp_span(span: &Span) -> serde_json::Value {
+    let mut otlp_span = json!({
+        "traceId": span.context.trace_id_hex(),
+        "spanId": hex::encode(span.context.span_id),
+        "name": span.name,
+        "kind": span.kind as i32,
+        "startTimeUnixNano": unix_nanos(span.start_time),
+        "endTimeUnixNano": unix_nanos(span.end_time),
+        "attributes": span
+            .attributes
+            .iter()
+            .map(|(key, value)| otlp_attribute(key, value))
+            .collect::<Vec<_>>(),
+        // OTLP's status codes are 0 for unset and 2 for an error
+        "status": { "code": if span.is_error { 2 } else { 0 } },
+    });
+
+    if let Some(parent_span_id) = span.parent_span_id {
+        otlp_span["parentSpanId"] = serde_json::Value::String(hex::encode(parent_span_id));
+    }
+
+    otlp_span
+}
+
+fn otlp_attribute(key: &str, value: &AttributeValue) -> serde_json::Value {
+    // 64-bit integers are written as strings in OTLP's JSON encoding
+    let value = match value {
+        AttributeValue::String(s) => json!({ "stringValue": s }),
+        AttributeValue::Int(i) => json!({ "intValue": i.to_string() }),
+    };
+
+    json!({ "key": key, "value": value })
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_otlp_request() {
+        let parent =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        let context = parent.child();
+        let start_time = UNIX_EPOCH + Duration::from_millis(1_655_294_400_000);
+
+        let span = Span {
+            name: String::from("POST /api/budget/invite"),
+            kind: SpanKind::Server,
+            context,
+            parent_span_id: Some(parent.span_id),
+            start_time,
+            end_time: start_time + Duration::from_millis(25),
+            attributes: vec![
+                (
+                    "http.request.method",
+                    AttributeValue::String(String::from("POST")),
+                ),
+                ("http.response.status_code", AttributeValue::Int(500)),
+            ],
+            is_error: true,
+        };
+
+        let root_span = Span {
+            name: String::from("deliver push"),
+            kind: SpanKind::Consumer,
+            parent_span_id: None,
+            attributes: Vec::new(),
+            is_error: false,
+            ..span.clone()
+        };
+
+        let request = otlp_request("budgetapp-server", &[span, root_span]);
+        let resource_spans = &request["resourceSpans"][0];
+        assert_eq!(
+            resource_spans["resource"]["attributes"][0],
+            json!({ "key": "service.name", "value": { "stringValue": "budgetapp-server" } })
+        );
+
+        let spans = resource_spans["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+
+        assert_eq!(spans[0]["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(spans[0]["spanId"], hex::encode(context.span_id));
+        assert_eq!(spans[0]["parentSpanId"], "00f067aa0ba902b7");
+        assert_eq!(spans[0]["kind"], 2);
+        assert_eq!(spans[0]["startTimeUnixNano"], "1655294400000000000");
+        assert_eq!(spans[0]["endTimeUnixNano"], "1655294400025000000");
+        assert_eq!(
+            spans[0]["attributes"][1],
+            json!({ "key": "http.response.status_code", "value": { "intValue": "500" } })
+        );
+        assert_eq!(spans[0]["status"]["code"], 2);
+
+        assert!(spans[1].get("parentSpanId").is_none());
+        assert_eq!(spans[1]["kind"], 5);
+        assert_eq!(spans[1]["status"]["code"], 0);
+    }
+}
@@ -0,0 +1,333 @@
+use futures::future::BoxFuture;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::env::{self, BlobStoreKind, GcsStorage, S3Storage};
+use crate::utils::aws_signature::AwsSigner;
+use crate::utils::logging;
+use crate::utils::trace_context::TRACEPARENT_HEADER;
+
+lazy_static! {
+    pub static ref BLOB_STORE: Box<dyn BlobStore> = configured_blob_store();
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    NotFound,
+    BackendFailure(String),
+}
+
+impl std::error::Error for StorageError {}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "Object not found in storage"),
+            StorageError::BackendFailure(msg) => write!(f, "Storage backend failure: {}", msg),
+        }
+    }
+}
+
+fn configured_blob_store() -> Box<dyn BlobStore> {
+    match env::CONF.storage.backend {
+        BlobStoreKind::Local => Box::new(LocalStorage::new(&env::CONF.storage.local_directory)),
+        BlobStoreKind::S3 => {
+            Box::new(S3::new(env::CONF.storage.s3.as_ref().expect(
+                "The s3 storage backend requires a [storage.s3] section",
+            )))
+        }
+        BlobStoreKind::Gcs => {
+            Box::new(Gcs::new(env::CONF.storage.gcs.as_ref().expect(
+                "The gcs storage backend requires a [storage.gcs] section",
+            )))
+        }
+    }
+}
+
+// Object storage for files the server keeps. Keys are generated by the server and only contain
+// characters that are safe in both a file path and a URL path without escaping
+pub trait BlobStore: Send + Sync {
+    fn put(&self, key: &str, data: Vec<u8>) -> BoxFuture<'static, Result<(), StorageError>>;
+    fn get(&self, key: &str) -> BoxFuture<'static, Result<Vec<u8>, StorageError>>;
+
+    // Deleting an object that doesn't exist succeeds
+    fn delete(&self, key: &str) -> BoxFuture<'static, Result<(), StorageError>>;
+}
+
+// Recognizes the file types attachments may have from the first bytes of the file
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else if data.len() >= 12
+        && &data[4..8] == b"ftyp"
+        && matches!(&data[8..12], b"heic" | b"heix" | b"mif1" | b"msf1")
+    {
+        Some("image/heic")
+    } else {
+        None
+    }
+}
+
+pub struct LocalStorage {
+    directory: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+        }
+    }
+}
+
+impl BlobStore for LocalStorage {
+    fn put(&self, key: &str, data: Vec<u8>) -> BoxFuture<'static, Result<(), StorageError>> {
+        let path = self.directory.join(key);
+
+        Box::pin(run_blocking(move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(&path, data)
+        }))
+    }
+
+    fn get(&self, key: &str) -> BoxFuture<'static, Result<Vec<u8>, StorageError>> {
+        let path = self.directory.join(key);
+        Box::pin(run_blocking(move || std::fs::read(&path)))
+    }
+
+    fn delete(&self, key: &str) -> BoxFuture<'static, Result<(), StorageError>> {
+        let path = self.directory.join(key);
+
+        Box::pin(run_blocking(move || match std::fs::remove_file(&path) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            r => r,
+        }))
+    }
+}
+
+async fn run_blocking<T, F>(f: F) -> Result<T, StorageError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> std::io::Result<T> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(r)) => Ok(r),
+        Ok(Err(e)) if e.kind() == ErrorKind::NotFound => Err(StorageError::NotFound),
+        Ok(Err(e)) => Err(StorageError::BackendFailure(e.to_string())),
+        Err(e) => Err(StorageError::BackendFailure(e.to_string())),
+    }
+}
+
+// Requests are signed with AWS Signature Version 4
+pub struct S3 {
+    client: reqwest::Client,
+    endpoint: String,
+    host: String,
+    bucket: String,
+    signer: AwsSigner,
+}
+
+impl S3 {
+    pub fn new(conf: &S3Storage) -> Self {
+        let endpoint = conf.endpoint.trim_end_matches('/');
+        let host = endpoint
+            .split_once("://")
+            .map_or(endpoint, |(_, rest)| rest)
+            .split('/')
+            .next()
+            .unwrap_or_default();
+
+        Self {
+            client: reqwest::Client::new(),
+            endpoint: String::from(endpoint),
+            host: String::from(host),
+            bucket: conf.bucket.clone(),
+            signer: AwsSigner {
+                access_key_id: conf.access_key_id.clone(),
+                secret_access_key: conf.secret_access_key.clone(),
+                region: conf.region.clone(),
+                service: "s3",
+            },
+        }
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: Vec<u8>,
+    ) -> BoxFuture<'static, Result<reqwest::Response, StorageError>> {
+        let path = format!("/{}/{}", self.bucket, key);
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let timestamp = chrono::Utc::now().naive_utc();
+        let authorization = self.signer.authorization(
+            method.as_str(),
+            &self.host,
+            &path,
+            &[],
+            &payload_hash,
+            timestamp,
+        );
+
+        let request = self
+            .client
+            .request(method, format!("{}{}", self.endpoint, path))
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", timestamp.format("%Y%m%dT%H%M%SZ").to_string())
+            .header("authorization", authorization);
+
+        let request = match logging::current_request_scope() {
+            Some(scope) => request.header(TRACEPARENT_HEADER, scope.trace_context.traceparent()),
+            None => request,
+        }
+        .body(body);
+
+        Box::pin(async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| StorageError::BackendFailure(e.to_string()))?;
+
+            match response.status() {
+                s if s.is_success() => Ok(response),
+                reqwest::StatusCode::NOT_FOUND => Err(StorageError::NotFound),
+                s => Err(StorageError::BackendFailure(format!(
+                    "S3 responded with {}",
+                    s
+                ))),
+            }
+        })
+    }
+}
+
+impl BlobStore for S3 {
+    fn put(&self, key: &str, data: Vec<u8>) -> BoxFuture<'static, Result<(), StorageError>> {
+        let request = self.request(reqwest::Method::PUT, key, data);
+        Box::pin(async move { request.await.map(|_| ()) })
+    }
+
+    fn get(&self, key: &str) -> BoxFuture<'static, Result<Vec<u8>, StorageError>> {
+        let request = self.request(reqwest::Method::GET, key, Vec::new());
+
+        Box::pin(async move {
+            let body = request
+                .await?
+                .bytes()
+                .await
+                .map_err(|e| StorageError::BackendFailure(e.to_string()))?;
+
+            Ok(body.to_vec())
+        })
+    }
+
+    fn delete(&self, key: &str) -> BoxFuture<'static, Result<(), StorageError>> {
+        let request = self.request(reqwest::Method::DELETE, key, Vec::new());
+
+        Box::pin(async move {
+            match request.await {
+                Ok(_) | Err(StorageError::NotFound) => Ok(()),
+                Err(e) => Err(e),
+            }
+        })
+    }
+}
+
+// GCS's XML API accepts requests signed the same way as S3's when they're made with an HMAC key
+pub struct Gcs {
+    xml_api: S3,
+}
+
+impl Gcs {
+    const XML_API_ENDPOINT: &'static str = "https://storage.googleapis.com";
+
+    pub fn new(conf: &GcsStorage) -> Self {
+        Self {
+            xml_api: S3::new(&S3Storage {
+                endpoint: String::from(Self::XML_API_ENDPOINT),
+                region: String::from("auto"),
+                bucket: conf.bucket.clone(),
+                access_key_id: conf.hmac_access_id.clone(),
+                secret_access_key: conf.hmac_secret.clone(),
+            }),
+        }
+    }
+}
+
+impl BlobStore for Gcs {
+    fn put(&self, key: &str, data: Vec<u8>) -> BoxFuture<'static, Result<(), StorageError>> {
+        self.xml_api.put(key, data)
+    }
+
+    fn get(&self, key: &str) -> BoxFuture<'static, Result<Vec<u8>, StorageError>> {
+        self.xml_api.get(key)
+    }
+
+    fn delete(&self, key: &str) -> BoxFuture<'static, Result<(), StorageError>> {
+        self.xml_api.delete(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_content_type() {
+        assert_eq!(
+            sniff_content_type(b"%PDF-1.7\n%\xE2\xE3"),
+            Some("application/pdf")
+        );
+        assert_eq!(
+            sniff_content_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10]),
+            Some("image/jpeg")
+        );
+        assert_eq!(
+            sniff_content_type(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00]),
+            Some("image/png")
+        );
+        assert_eq!(
+            sniff_content_type(b"\x00\x00\x00\x18ftypheic\x00\x00\x00\x00"),
+            Some("image/heic")
+        );
+        assert_eq!(sniff_content_type(b"\x00\x00\x00\x18ftypmp42"), None);
+        assert_eq!(sniff_content_type(b"Date,Amount\n"), None);
+        assert_eq!(sniff_content_type(b""), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_local_storage() {
+        let directory = std::env::temp_dir().join(format!("budgetapp-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(directory.to_str().unwrap());
+
+        assert!(matches!(
+            storage.get("receipts/missing").await,
+            Err(StorageError::NotFound)
+        ));
+
+        storage
+            .put("receipts/first", b"receipt".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(storage.get("receipts/first").await.unwrap(), b"receipt");
+
+        storage.delete("receipts/first").await.unwrap();
+        assert!(matches!(
+            storage.get("receipts/first").await,
+            Err(StorageError::NotFound)
+        ));
+
+        // Deleting twice is harmless
+        storage.delete("receipts/first").await.unwrap();
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
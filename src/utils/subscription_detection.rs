@@ -0,0 +1,250 @@
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::entry::Entry;
+
+// A charge has to show up at least this many times before it is considered a subscription
+const MIN_OCCURRENCES: usize = 3;
+
+// Amounts may drift a little (taxes, price changes, currency conversion)
+const AMOUNT_TOLERANCE_PERCENT: i64 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Cadence {
+    Weekly,
+    Biweekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Cadence {
+    fn from_interval_days(days: i64) -> Option<Cadence> {
+        match days {
+            6..=8 => Some(Cadence::Weekly),
+            13..=16 => Some(Cadence::Biweekly),
+            27..=32 => Some(Cadence::Monthly),
+            88..=93 => Some(Cadence::Quarterly),
+            358..=372 => Some(Cadence::Yearly),
+            _ => None,
+        }
+    }
+
+    pub fn typical_interval_days(&self) -> i64 {
+        match self {
+            Cadence::Weekly => 7,
+            Cadence::Biweekly => 14,
+            Cadence::Monthly => 30,
+            Cadence::Quarterly => 91,
+            Cadence::Yearly => 365,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DetectedSubscription {
+    pub payee: String,
+    pub cadence: Cadence,
+    pub typical_amount_cents: i64,
+    pub occurrence_count: usize,
+    pub last_date: NaiveDate,
+    pub next_expected_date: NaiveDate,
+}
+
+fn normalize_payee(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn median(values: &mut [i64]) -> i64 {
+    values.sort_unstable();
+    values[values.len() / 2]
+}
+
+// Finds entries with the same payee, a similar amount, and a regular cadence. Entries without a
+// name can't be matched up and are ignored.
+pub fn detect_subscriptions(entries: &[Entry]) -> Vec<DetectedSubscription> {
+    let mut entries_by_payee = HashMap::<String, Vec<&Entry>>::new();
+
+    for entry in entries.iter() {
+        if entry.is_deleted || entry.amount_cents <= 0 {
+            continue;
+        }
+
+        let payee = match &entry.name {
+            Some(n) => normalize_payee(n),
+            None => continue,
+        };
+
+        if payee.is_empty() {
+            continue;
+        }
+
+        entries_by_payee.entry(payee).or_default().push(entry);
+    }
+
+    let mut subscriptions = Vec::new();
+
+    for (payee, mut payee_entries) in entries_by_payee.into_iter() {
+        if payee_entries.len() < MIN_OCCURRENCES {
+            continue;
+        }
+
+        payee_entries.sort_by_key(|e| e.date);
+
+        let mut amounts = payee_entries
+            .iter()
+            .map(|e| e.amount_cents)
+            .collect::<Vec<_>>();
+        let typical_amount_cents = median(&mut amounts);
+
+        let amounts_are_similar = payee_entries.iter().all(|e| {
+            (e.amount_cents - typical_amount_cents).abs() * 100
+                <= typical_amount_cents * AMOUNT_TOLERANCE_PERCENT
+        });
+
+        if !amounts_are_similar {
+            continue;
+        }
+
+        let mut intervals = payee_entries
+            .windows(2)
+            .map(|w| (w[1].date - w[0].date).num_days())
+            .collect::<Vec<_>>();
+
+        let cadence = match Cadence::from_interval_days(median(&mut intervals)) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        let cadence_is_regular = intervals
+            .iter()
+            .all(|days| Cadence::from_interval_days(*days) == Some(cadence));
+
+        if !cadence_is_regular {
+            continue;
+        }
+
+        let last_date = payee_entries[payee_entries.len() - 1].date;
+
+        subscriptions.push(DetectedSubscription {
+            payee,
+            cadence,
+            typical_amount_cents,
+            occurrence_count: payee_entries.len(),
+            last_date,
+            next_expected_date: last_date + Duration::days(cadence.typical_interval_days()),
+        });
+    }
+
+    subscriptions.sort_by_key(|s| s.next_expected_date);
+    subscriptions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use uuid::Uuid;
+
+    fn entry(name: Option<&str>, amount_cents: i64, date: NaiveDate) -> Entry {
+        let timestamp = chrono::Utc::now().naive_utc();
+
+        Entry {
+            id: Uuid::new_v4(),
+            budget_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            is_deleted: false,
+            amount_cents,
+            date,
+            name: name.map(String::from),
+            category: None,
+            note: None,
+            modified_timestamp: timestamp,
+            created_timestamp: timestamp,
+            recurring_entry_id: None,
+            import_batch_id: None,
+            tax_cents: None,
+            tip_cents: None,
+            is_deductible: false,
+        }
+    }
+
+    #[test]
+    fn test_detects_monthly_subscription() {
+        let entries = vec![
+            entry(Some("Netflix"), 1549, NaiveDate::from_ymd(2022, 1, 15)),
+            entry(Some("Groceries"), 8423, NaiveDate::from_ymd(2022, 1, 16)),
+            entry(Some("netflix "), 1549, NaiveDate::from_ymd(2022, 2, 15)),
+            entry(Some("Groceries"), 5012, NaiveDate::from_ymd(2022, 2, 20)),
+            entry(Some("NETFLIX"), 1599, NaiveDate::from_ymd(2022, 3, 16)),
+            entry(Some("Groceries"), 12001, NaiveDate::from_ymd(2022, 3, 28)),
+            entry(None, 1549, NaiveDate::from_ymd(2022, 4, 15)),
+        ];
+
+        let subscriptions = detect_subscriptions(&entries);
+
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].payee, "netflix");
+        assert_eq!(subscriptions[0].cadence, Cadence::Monthly);
+        assert_eq!(subscriptions[0].typical_amount_cents, 1549);
+        assert_eq!(subscriptions[0].occurrence_count, 3);
+        assert_eq!(subscriptions[0].last_date, NaiveDate::from_ymd(2022, 3, 16));
+        assert_eq!(
+            subscriptions[0].next_expected_date,
+            NaiveDate::from_ymd(2022, 4, 15)
+        );
+    }
+
+    #[test]
+    fn test_detects_weekly_and_yearly_subscriptions() {
+        let mut entries = Vec::new();
+
+        for week in 0..5 {
+            entries.push(entry(
+                Some("Meal Kit"),
+                6000,
+                NaiveDate::from_ymd(2022, 1, 3) + Duration::weeks(week),
+            ));
+        }
+
+        for year in 2019..2022 {
+            entries.push(entry(
+                Some("Domain Renewal"),
+                1200,
+                NaiveDate::from_ymd(year, 7, 1),
+            ));
+        }
+
+        let subscriptions = detect_subscriptions(&entries);
+
+        assert_eq!(subscriptions.len(), 2);
+        assert_eq!(subscriptions[0].payee, "meal kit");
+        assert_eq!(subscriptions[0].cadence, Cadence::Weekly);
+        assert_eq!(subscriptions[1].payee, "domain renewal");
+        assert_eq!(subscriptions[1].cadence, Cadence::Yearly);
+    }
+
+    #[test]
+    fn test_ignores_irregular_or_varying_charges() {
+        let entries = vec![
+            // Too few occurrences
+            entry(Some("Gym"), 3000, NaiveDate::from_ymd(2022, 1, 1)),
+            entry(Some("Gym"), 3000, NaiveDate::from_ymd(2022, 2, 1)),
+            // Irregular cadence
+            entry(Some("Coffee"), 450, NaiveDate::from_ymd(2022, 1, 1)),
+            entry(Some("Coffee"), 450, NaiveDate::from_ymd(2022, 1, 31)),
+            entry(Some("Coffee"), 450, NaiveDate::from_ymd(2022, 2, 3)),
+            entry(Some("Coffee"), 450, NaiveDate::from_ymd(2022, 3, 5)),
+            // Amount varies too much
+            entry(Some("Electric"), 4000, NaiveDate::from_ymd(2022, 1, 10)),
+            entry(Some("Electric"), 9000, NaiveDate::from_ymd(2022, 2, 10)),
+            entry(Some("Electric"), 5500, NaiveDate::from_ymd(2022, 3, 10)),
+        ];
+
+        assert!(detect_subscriptions(&entries).is_empty());
+    }
+}
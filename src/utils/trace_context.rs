@@ -0,0 +1,165 @@
+use rand::prelude::*;
+
+use crate::utils::span_export;
+
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+const SAMPLED_FLAG: u8 = 0x01;
+
+// A W3C Trace Context (https://www.w3.org/TR/trace-context/). Requests that arrive with a
+// traceparent header join the caller's trace; every other request starts a trace of its own. Traces
+// the server starts are only sampled when it exports spans.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub flags: u8,
+}
+
+impl TraceContext {
+    pub fn new_root() -> Self {
+        let mut rng = rand::thread_rng();
+
+        Self {
+            trace_id: nonzero_id(&mut rng),
+            span_id: nonzero_id(&mut rng),
+            flags: if span_export::is_enabled() {
+                SAMPLED_FLAG
+            } else {
+                0
+            },
+        }
+    }
+
+    // Versions above 00 may append fields, which are ignored. A header that doesn't parse is
+    // treated as absent, as the spec requires.
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let traceparent = traceparent.trim();
+        let mut parts = traceparent.splitn(5, '-');
+
+        let version = parse_hex::<1>(parts.next()?)?[0];
+        let trace_id = parse_hex::<16>(parts.next()?)?;
+        let span_id = parse_hex::<8>(parts.next()?)?;
+        let flags = parse_hex::<1>(parts.next()?)?[0];
+
+        let has_extra_fields = parts.next().is_some();
+        if version == 0xff || (version == 0 && has_extra_fields) {
+            return None;
+        }
+
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+
+        Some(Self {
+            trace_id,
+            span_id,
+            flags,
+        })
+    }
+
+    // The context to hand to the next hop: the same trace with a new span ID
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id,
+            span_id: nonzero_id(&mut rand::thread_rng()),
+            flags: self.flags & SAMPLED_FLAG,
+        }
+    }
+
+    pub fn is_sampled(&self) -> bool {
+        self.flags & SAMPLED_FLAG != 0
+    }
+
+    pub fn trace_id_hex(&self) -> String {
+        hex::encode(self.trace_id)
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            hex::encode(self.trace_id),
+            hex::encode(self.span_id),
+            self.flags & SAMPLED_FLAG
+        )
+    }
+}
+
+fn nonzero_id<const N: usize>(rng: &mut impl Rng) -> [u8; N] {
+    loop {
+        let mut id = [0; N];
+        rng.fill_bytes(&mut id);
+
+        if id != [0; N] {
+            return id;
+        }
+    }
+}
+
+// Only lowercase hex is valid in a traceparent
+fn parse_hex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != N * 2 || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+
+    let mut bytes = [0; N];
+    hex::decode_to_slice(s, &mut bytes).ok()?;
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_traceparent() {
+        let context =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(hex::encode(context.span_id), "00f067aa0ba902b7");
+        assert_eq!(context.flags, 0x01);
+        assert!(context.is_sampled());
+        assert_eq!(
+            context.traceparent(),
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+
+        // Later versions may add fields
+        let context =
+            TraceContext::parse("cc-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra")
+                .unwrap();
+        assert_eq!(context.flags, 0x00);
+        assert!(!context.is_sampled());
+
+        for invalid in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902bz-01",
+        ] {
+            assert_eq!(TraceContext::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_child_context() {
+        let parent =
+            TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-03").unwrap();
+        let child = parent.child();
+
+        assert_eq!(child.trace_id, parent.trace_id);
+        assert_ne!(child.span_id, parent.span_id);
+        assert!(child.traceparent().ends_with("-01"));
+
+        let root = TraceContext::new_root();
+        assert_ne!(root.trace_id, [0; 16]);
+        assert_eq!(root.flags, 0x00);
+        assert_eq!(TraceContext::parse(&root.traceparent()), Some(root));
+    }
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+// An entry is flagged when the amount is at least this many times the user's usual amount for the
+// category, or at most a this-many-th of it. A slipped decimal point is a factor of 100 and an
+// extra or missing digit a factor of 10.
+const USUAL_AMOUNT_MULTIPLE: i64 = 10;
+
+// With fewer past entries than this, there isn't enough to say what is usual
+const MIN_HISTORY_LEN: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EntryWarning {
+    UnusualAmount {
+        usual_amount_cents: i64,
+        smallest_amount_cents: i64,
+        largest_amount_cents: i64,
+    },
+}
+
+// `history` holds the amounts of the user's past expenses in the category. Refunds and other
+// negative amounts are never flagged. Amounts within the range the user has already recorded are
+// never flagged either, however far they are from the median.
+pub fn check_amount(history: &mut [i64], amount_cents: i64) -> Option<EntryWarning> {
+    if amount_cents <= 0 || history.len() < MIN_HISTORY_LEN {
+        return None;
+    }
+
+    history.sort_unstable();
+    let usual_amount_cents = history[history.len() / 2];
+    let smallest_amount_cents = history[0];
+    let largest_amount_cents = history[history.len() - 1];
+
+    let is_far_above = amount_cents > largest_amount_cents
+        && amount_cents >= usual_amount_cents.saturating_mul(USUAL_AMOUNT_MULTIPLE);
+    let is_far_below = amount_cents < smallest_amount_cents
+        && amount_cents.saturating_mul(USUAL_AMOUNT_MULTIPLE) <= usual_amount_cents;
+
+    if is_far_above || is_far_below {
+        Some(EntryWarning::UnusualAmount {
+            usual_amount_cents,
+            smallest_amount_cents,
+            largest_amount_cents,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_amount() {
+        let coffees = [450, 500, 475, 520, 390, 610];
+
+        assert_eq!(check_amount(&mut coffees.clone(), 550), None);
+        assert_eq!(check_amount(&mut coffees.clone(), 2400), None);
+        assert_eq!(check_amount(&mut coffees.clone(), -50000), None);
+
+        let warning = Some(EntryWarning::UnusualAmount {
+            usual_amount_cents: 500,
+            smallest_amount_cents: 390,
+            largest_amount_cents: 610,
+        });
+        assert_eq!(check_amount(&mut coffees.clone(), 500000), warning);
+        assert_eq!(check_amount(&mut coffees.clone(), 5000), warning);
+        assert_eq!(check_amount(&mut coffees.clone(), 50), warning);
+
+        // Not enough history to go on
+        assert_eq!(check_amount(&mut coffees[..4].to_vec(), 500000), None);
+
+        // An amount the user has recorded before isn't unusual
+        let mut history = [450, 500, 475, 520, 390, 90000];
+        assert_eq!(check_amount(&mut history, 85000), None);
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::Datelike;
 
 use crate::env;
+use crate::env::email::{ALLOWED_EMAIL_DOMAINS, BLOCKED_EMAIL_DOMAINS, DISPOSABLE_EMAIL_DOMAINS};
 use crate::env::password::COMMON_PASSWORDS_SET;
 
 #[derive(Debug)]
@@ -52,6 +53,31 @@ pub fn validate_email_address(email: &str) -> Validity {
     Validity::Valid
 }
 
+// Should be checked wherever a user gives a new email address for their account. Only the domain is
+// checked, so the address's format should be validated separately.
+pub fn validate_email_domain(email: &str) -> Validity {
+    if !env::CONF.email_screening.reject_disposable_domains {
+        return Validity::Valid;
+    }
+
+    let domain = match email.rsplit_once('@') {
+        Some((_, domain)) => domain,
+        None => return Validity::Valid,
+    };
+
+    if ALLOWED_EMAIL_DOMAINS.contains(domain) {
+        return Validity::Valid;
+    }
+
+    if BLOCKED_EMAIL_DOMAINS.contains(domain) || DISPOSABLE_EMAIL_DOMAINS.contains(domain) {
+        return Validity::Invalid(
+            "Email addresses from disposable email providers are not accepted.",
+        );
+    }
+
+    Validity::Valid
+}
+
 pub fn validate_strong_password(
     password: &str,
     email: &str,
@@ -208,6 +234,20 @@ mod tests {
         assert!(!validate_email_address(DOT_LAST_CHAR).is_valid());
     }
 
+    #[actix_rt::test]
+    async fn test_validate_email_domain() {
+        // The testing config rejects disposable domains, blocks spam.example.com and allows
+        // trashmail.com
+        assert!(validate_email_domain("test@example.com").is_valid());
+        assert!(validate_email_domain("test@gmail.com").is_valid());
+        assert!(validate_email_domain("test@trashmail.com").is_valid());
+
+        assert!(!validate_email_domain("test@mailinator.com").is_valid());
+        assert!(!validate_email_domain("test@YOPMAIL.com").is_valid());
+        assert!(!validate_email_domain("test@inbox.guerrillamail.com").is_valid());
+        assert!(!validate_email_domain("test@spam.example.com").is_valid());
+    }
+
     #[actix_rt::test]
     async fn test_validate_strong_password() {
         const EMAIL: &str = "test_user@test.com";